@@ -7,41 +7,102 @@ use alloc::boxed::Box;
 use crater_fsw::{
     device::{
         bsp::{self, CraterBsp},
+        power_adc::PowerAdc,
+        servo::PwmServos,
         spi::{SpiDevice, SpiDeviceConfig},
+        sx127x::{self, Sx127x},
+        w25q::W25q,
+        watchdog::{self, CriticalTask, Supervisor},
+    },
+    io::{
+        can::{CanLinkRx, CanLinkTx},
+        channel::EmbassyReceiver,
+        sd_log::SdLog,
     },
-    io::channel::EmbassyReceiver,
     sensors::{
         self,
         bmp390::{self, Bmp390, Bmp390Sample},
         icm42688::{AccelAAFConfig, GyroAAFConfig, Icm42688, Icm42688Sample},
+        ublox::{self, Ublox},
     },
 };
 use crater_gnc::{
-    MavHeader,
-    common::Ts,
-    datatypes::sensors::{ImuSensorSample, PressureSensorSample},
-    hal::channel::Receiver,
+    InstantU64, MavHeader,
+    common::{Ts, power_monitor::PowerMonitor},
+    datatypes::{
+        actuators::ServoCommand,
+        power::PowerStatus,
+        sensors::{
+            GnssSensorSample, ImuSensorSample, MagnetometerSensorSample, PressureSensorSample,
+        },
+    },
+    events::{Event, EventItem, EventLog, EventLogEntry},
+    gnc_main::CraterConfig,
+    hal::{channel::Receiver, servo::ServoOutput},
+    io::{
+        airtime::{AirtimeBudget, AirtimeScheduler},
+        can_protocol::{CanMessage, CanNodeId},
+        flash_log::{FlashLog, RecordFramer},
+    },
     mav_crater::{
-        self, ImuSensorId, MavMessage, PressureSensorId, SensImuSample_DATA,
-        SensPressureSample_DATA,
+        self, BootStatus_DATA, ComponentId, GnssSensorId, ImuSensorId, MavMessage,
+        PressureSensorId, SensImuSample_DATA, SensPressureSample_DATA,
     },
-    write_v2_msg_async,
+    peek_reader::PeekReader,
+    read_v2_msg, write_v2_msg_async,
 };
 use defmt::*;
 use embassy_executor::Spawner;
-use embassy_sync::pubsub::DynPublisher;
-use embassy_time::Timer;
+use embassy_stm32::usart::BufferedUart;
+use embassy_sync::pubsub::{DynPublisher, DynSubscriber};
+use embassy_time::{Instant, Timer};
 use uom::si::{
     angular_absement::degree_second, pressure::pascal, thermodynamic_temperature::degree_celsius,
 };
+#[cfg(feature = "hil")]
+use {
+    crater_gnc::io::{
+        MAVLINK_MSG_MAX_SIZE,
+        hil::{HilSample, HilSensorSource, MavlinkDeframer, time_sync_reply},
+    },
+    embassy_stm32::usart::RingBufferedUartRx,
+};
 use {defmt_rtt as _, panic_probe as _};
 extern crate alloc;
 
+/// Erased before flight, about 2 minutes of logs: no erase stalls the logger in flight
+const LOG_PREERASE_BYTES: u32 = 2 * 1024 * 1024;
+
+/// Flash programming interval, the most data lost on a power failure
+const LOG_FLUSH_PERIOD_MS: u64 = 500;
+
+/// Events kept by the black box, written to the flash again on landing
+const EVENT_LOG_LEN: usize = 64;
+
+/// The file size is only updated on the card when flushing
+const SD_FLUSH_PERIOD_MS: u64 = 5000;
+
+const CAN_HEARTBEAT_PERIOD_MS: u64 = 100;
+
+const POWER_SAMPLE_PERIOD_MS: u64 = 100;
+
+/// Share of the time the radio may transmit, leaving time to receive the ground commands
+const LORA_DUTY_CYCLE: f32 = 0.1;
+
+/// Longest sequence of packets sent back to back [µs]
+const LORA_MAX_BURST_US: f32 = 500_000.0;
+
 #[embassy_executor::main]
 async fn main(spawner: Spawner) {
     let bsp = CraterBsp::init().await;
     Timer::after_millis(100).await;
 
+    info!("Reset cause: {}", defmt::Debug2Format(&bsp.reset_cause));
+    let boot_status = MavMessage::BootStatus(BootStatus_DATA {
+        timestamp_us: Instant::now().as_micros() as i64,
+        reset_cause: bsp.reset_cause,
+    });
+
     // let dev_bmp390 = SpiDevice::new(
     //     &bsp::bus::SPI_1,
     //     bsp.sens_bmp390.cs,
@@ -63,28 +124,38 @@ async fn main(spawner: Spawner) {
 
     // info!("BMP390 Initialized!");
 
-    let dev_icm_42688 = SpiDevice::new(
-        &bsp::bus::SPI_1,
-        bsp.sens_icm42688.cs,
-        SpiDeviceConfig::default(),
-    );
+    // In HIL mode, the samples of the simulation replace the ones of the drivers
+    #[cfg(not(feature = "hil"))]
+    let (icm42688, ublox) = {
+        let dev_icm_42688 = SpiDevice::new(
+            &bsp::bus::SPI_1,
+            bsp.sens_icm42688.cs,
+            SpiDeviceConfig::default(),
+        );
 
-    let config_icm42688 = sensors::icm42688::Config {
-        accel_fs: sensors::icm42688::regs::AccelFullScale::Fs2g,
-        accel_odr: sensors::icm42688::regs::AccelDataRate::Odr200hz,
-        gyro_fs: sensors::icm42688::regs::GyroFullScale::Fs15_625dps,
-        gyro_odr: sensors::icm42688::regs::GyroDataRate::Odr200hz,
-        accel_aaf: AccelAAFConfig::default(),
-        gyro_aaf: GyroAAFConfig::default(),
-    };
+        let config_icm42688 = sensors::icm42688::Config {
+            accel_fs: sensors::icm42688::regs::AccelFullScale::Fs2g,
+            accel_odr: sensors::icm42688::regs::AccelDataRate::Odr200hz,
+            gyro_fs: sensors::icm42688::regs::GyroFullScale::Fs15_625dps,
+            gyro_odr: sensors::icm42688::regs::GyroDataRate::Odr200hz,
+            accel_aaf: AccelAAFConfig::default(),
+            gyro_aaf: GyroAAFConfig::default(),
+        };
+
+        let icm42688 = Icm42688::init(
+            dev_icm_42688,
+            config_icm42688,
+            &bsp::interrupts::SIGNAL_ICM_42688_DRDY,
+        )
+        .await
+        .expect("Could not init Icm42688!");
 
-    let icm42688 = Icm42688::init(
-        dev_icm_42688,
-        config_icm42688,
-        &bsp::interrupts::SIGNAL_ICM_42688_DRDY,
-    )
-    .await
-    .expect("Could not init Icm42688!");
+        let ublox = Ublox::init(bsp.sens_ublox.uart, ublox::Config::default())
+            .await
+            .expect("Could not init u-blox receiver!");
+
+        (icm42688, ublox)
+    };
 
     let tx_bmp390 = bsp::channels::SENS_BMP_390_SAMPLE.dyn_publisher().unwrap();
     let mut rx_bmp390 = bsp::channels::SENS_BMP_390_SAMPLE.dyn_subscriber().unwrap();
@@ -96,10 +167,121 @@ async fn main(spawner: Spawner) {
         .dyn_subscriber()
         .unwrap();
 
+    let tx_ublox = bsp::channels::SENS_UBLOX_SAMPLE.dyn_publisher().unwrap();
+    let mut rx_ublox = bsp::channels::SENS_UBLOX_SAMPLE.dyn_subscriber().unwrap();
+
+    let w25q = W25q::init(bsp.flash.spi, bsp.flash.cs)
+        .await
+        .expect("Could not init flash!");
+
+    // Erasing takes up to a minute on a full flash: done before any sample is produced
+    let mut flash_log = FlashLog::mount(w25q);
+    flash_log.begin(0, LOG_PREERASE_BYTES);
+    info!(
+        "LOGGER | Log started, {} sectors erased",
+        flash_log.num_erased()
+    );
+
+    // The flight goes on without the SD card
+    match SdLog::init(bsp.sd.spi, bsp.sd.cs) {
+        Ok(sd_log) => spawner
+            .spawn(sd_logger(
+                sd_log,
+                bsp::channels::EVENTS.dyn_subscriber().unwrap(),
+            ))
+            .unwrap(),
+        Err(err) => warn!("SD | {}", defmt::Display2Format(&err)),
+    }
+
+    spawner
+        .spawn(logger(
+            flash_log,
+            boot_status.clone(),
+            bsp::channels::SENS_ICM_42688_SAMPLE
+                .dyn_subscriber()
+                .unwrap(),
+            bsp::channels::SENS_BMP_390_SAMPLE.dyn_subscriber().unwrap(),
+            bsp::channels::SENS_UBLOX_SAMPLE.dyn_subscriber().unwrap(),
+            bsp::channels::SENS_MAG_SAMPLE.dyn_subscriber().unwrap(),
+            bsp::channels::POWER_STATUS.dyn_subscriber().unwrap(),
+            bsp::channels::EVENTS.dyn_subscriber().unwrap(),
+        ))
+        .unwrap();
+
+    spawner
+        .spawn(power_monitor(
+            bsp.power.adc,
+            PowerMonitor::new(CraterConfig::default().power),
+            bsp::channels::POWER_STATUS.dyn_publisher().unwrap(),
+            bsp::channels::EVENTS.dyn_publisher().unwrap(),
+        ))
+        .unwrap();
+
+    let servos = PwmServos::new(bsp.servos.pwm, CraterConfig::default().servo);
+    spawner
+        .spawn(act_servos(
+            servos,
+            bsp::channels::ACT_SERVO_COMMAND.dyn_subscriber().unwrap(),
+        ))
+        .unwrap();
+
+    spawner
+        .spawn(can_tx(
+            CanLinkTx::new(bsp.can.tx, CanNodeId::MainFc),
+            bsp::channels::EVENTS.dyn_subscriber().unwrap(),
+        ))
+        .unwrap();
+    spawner
+        .spawn(can_rx(
+            CanLinkRx::new(bsp.can.rx),
+            bsp::channels::CAN_RX.dyn_publisher().unwrap(),
+        ))
+        .unwrap();
+
+    let dev_lora = SpiDevice::new(&bsp::bus::SPI_1, bsp.lora.cs, SpiDeviceConfig::default());
+
+    // The flight goes on without the radio
+    match Sx127x::init(dev_lora, sx127x::Config::default()).await {
+        Ok(lora) => spawner
+            .spawn(radio(
+                lora,
+                bsp::channels::EVENTS.dyn_subscriber().unwrap(),
+                bsp::channels::SENS_UBLOX_SAMPLE.dyn_subscriber().unwrap(),
+                bsp::channels::POWER_STATUS.dyn_subscriber().unwrap(),
+                bsp::channels::DOWNLINK.dyn_subscriber().unwrap(),
+                bsp::channels::UPLINK.dyn_publisher().unwrap(),
+            ))
+            .unwrap(),
+        Err(err) => warn!("SX127X | {}", defmt::Display2Format(&err)),
+    }
+
+    bsp::channels::DOWNLINK
+        .immediate_publisher()
+        .publish_immediate(boot_status);
+
     // spawner.spawn(sens_press(bmp390, tx_bmp390)).unwrap();
-    spawner.spawn(sens_imu(icm42688, tx_icm42688)).unwrap();
+    #[cfg(not(feature = "hil"))]
+    {
+        spawner.spawn(sens_imu(icm42688, tx_icm42688)).unwrap();
+        spawner.spawn(sens_gnss(ublox, tx_ublox)).unwrap();
+    }
+    #[cfg(feature = "hil")]
+    spawner
+        .spawn(sens_hil(
+            bsp.hil.rx,
+            tx_icm42688,
+            tx_bmp390,
+            tx_ublox,
+            bsp::channels::SENS_MAG_SAMPLE.dyn_publisher().unwrap(),
+        ))
+        .unwrap();
     // spawner.spawn(interru()).unwrap();
 
+    // Last, once the critical tasks run
+    spawner
+        .spawn(supervisor(Supervisor::new(bsp.watchdog.iwdg)))
+        .unwrap();
+
     let mut seq_cnt: u8 = 0;
     let mut header = MavHeader {
         ..Default::default()
@@ -130,6 +312,19 @@ async fn main(spawner: Spawner) {
                 .unwrap();
         }
 
+        while let Some(sample) = rx_ublox.try_next_message_pure() {
+            let mav = sample.v.to_mavlink(GnssSensorId::UbloxM9, sample.t);
+
+            header.sequence = seq_cnt;
+            seq_cnt += 1;
+
+            write_v2_msg_async(uart_tx.as_mut().unwrap(), header, &mav)
+                .await
+                .unwrap();
+        }
+
+        // Shared with the HIL sensor source, which answers the time sync messages
+        drop(uart_tx);
         Timer::after_millis(2).await;
     }
 }
@@ -140,6 +335,361 @@ async fn sens_imu(mut icm: Icm42688, tx: DynPublisher<'static, Ts<Icm42688Sample
     loop {
         let sample = icm.sample().await;
         tx.publish_immediate(Ts::new(sample.t, sample.v));
+        watchdog::check_in(CriticalTask::Imu);
+    }
+}
+
+#[embassy_executor::task]
+async fn sens_gnss(
+    mut ublox: Ublox<BufferedUart<'static>>,
+    tx: DynPublisher<'static, Ts<GnssSensorSample>>,
+) {
+    info!("Running GNSS");
+    loop {
+        match ublox.sample().await {
+            Ok(sample) => tx.publish_immediate(sample),
+            Err(_) => warn!("UBLOX | UART error"),
+        }
+    }
+}
+
+/// Sensor source of the HIL mode, in place of the drivers: publishes the samples sent by the
+/// simulation over the debug serial port, on the local clock, and echoes its time sync messages
+#[cfg(feature = "hil")]
+#[embassy_executor::task]
+async fn sens_hil(
+    mut rx: RingBufferedUartRx<'static>,
+    tx_imu: DynPublisher<'static, Ts<Icm42688Sample>>,
+    tx_press: DynPublisher<'static, Ts<PressureSensorSample>>,
+    tx_gnss: DynPublisher<'static, Ts<GnssSensorSample>>,
+    tx_mag: DynPublisher<'static, Ts<MagnetometerSensorSample>>,
+) {
+    info!("Running HIL sensors");
+    let mut source = HilSensorSource::new();
+    let mut deframer = MavlinkDeframer::<{ 2 * MAVLINK_MSG_MAX_SIZE }>::new();
+    let mut chunk = [0u8; MAVLINK_MSG_MAX_SIZE];
+    let mut header = MavHeader::default();
+
+    loop {
+        let len = match rx.read(&mut chunk).await {
+            Ok(len) => len,
+            Err(_) => {
+                warn!("HIL | UART error");
+                continue;
+            }
+        };
+        let now = crater_gnc::Instant(InstantU64::from_ticks(Instant::now().as_micros()));
+
+        if !deframer.push(&chunk[..len]) {
+            warn!("HIL | Receive buffer overflow");
+        }
+
+        while let Some(msg) = deframer.next_message() {
+            if let MavMessage::HilTimeSync(sync) = &msg {
+                let mut uart_tx = bsp::bus::DEBUG_SERIAL_TX.lock().await;
+                let reply = time_sync_reply(sync, now);
+                if write_v2_msg_async(uart_tx.as_mut().unwrap(), header, &reply)
+                    .await
+                    .is_err()
+                {
+                    warn!("HIL | Could not answer the time sync");
+                }
+                header.sequence = header.sequence.wrapping_add(1);
+            }
+
+            let synced = source.is_synced();
+            match source.handle(&msg, now) {
+                Some(HilSample::Imu(sample)) => {
+                    tx_imu.publish_immediate(Ts::new(sample.t, Icm42688Sample { data: sample.v }));
+                    watchdog::check_in(CriticalTask::Imu);
+                }
+                Some(HilSample::Pressure(sample)) => tx_press.publish_immediate(sample),
+                Some(HilSample::Gnss(sample)) => tx_gnss.publish_immediate(sample),
+                Some(HilSample::Mag(sample)) => tx_mag.publish_immediate(sample),
+                None => {}
+            }
+
+            if !synced && source.is_synced() {
+                info!("HIL | Clock synced to the simulation");
+            }
+        }
+    }
+}
+
+/// Appends a record to the flash log, and forwards it to the SD logger if it keeps up
+fn log_record(log: &mut FlashLog<W25q>, framer: &mut RecordFramer, msg: &MavMessage) {
+    let Some(frame) = framer.frame(msg) else {
+        return;
+    };
+
+    log.append(frame);
+
+    // Whole records only
+    let sd_records = &bsp::channels::SD_LOG_RECORDS;
+    if sd_records.free_capacity() >= frame.len() {
+        let _ = sd_records.try_write(frame);
+    }
+}
+
+/// Black box: records the sensor samples & the GNC events to the flash, as MAVLink messages. The
+/// event log is written again on landing, before the final flush.
+#[embassy_executor::task]
+async fn logger(
+    mut log: FlashLog<W25q>,
+    boot_status: MavMessage,
+    mut rx_imu: DynSubscriber<'static, Ts<Icm42688Sample>>,
+    mut rx_press: DynSubscriber<'static, Ts<PressureSensorSample>>,
+    mut rx_gnss: DynSubscriber<'static, Ts<GnssSensorSample>>,
+    mut rx_mag: DynSubscriber<'static, Ts<MagnetometerSensorSample>>,
+    mut rx_power: DynSubscriber<'static, Ts<PowerStatus>>,
+    mut rx_events: DynSubscriber<'static, EventItem>,
+) {
+    info!("Running logger");
+    let mut framer = RecordFramer::new();
+    let mut last_flush = Instant::now();
+    let mut event_log = EventLog::<EVENT_LOG_LEN>::new();
+
+    log_record(&mut log, &mut framer, &boot_status);
+
+    loop {
+        watchdog::check_in(CriticalTask::Logger);
+
+        while let Some(sample) = rx_imu.try_next_message_pure() {
+            let msg = sample.v.data.to_mavlink(ImuSensorId::Icm42688, sample.t);
+            log_record(&mut log, &mut framer, &msg);
+        }
+
+        while let Some(sample) = rx_press.try_next_message_pure() {
+            let msg = sample.v.to_mavlink(PressureSensorId::Bmp390, sample.t);
+            log_record(&mut log, &mut framer, &msg);
+        }
+
+        while let Some(sample) = rx_gnss.try_next_message_pure() {
+            let msg = sample.v.to_mavlink(GnssSensorId::UbloxM9, sample.t);
+            log_record(&mut log, &mut framer, &msg);
+        }
+
+        while let Some(sample) = rx_mag.try_next_message_pure() {
+            log_record(&mut log, &mut framer, &sample.v.to_mavlink(sample.t));
+        }
+
+        while let Some(status) = rx_power.try_next_message_pure() {
+            log_record(&mut log, &mut framer, &status.v.to_mavlink(status.t));
+        }
+
+        let mut landed = false;
+        while let Some(event) = rx_events.try_next_message_pure() {
+            let ts = crater_gnc::Instant(InstantU64::from_ticks(Instant::now().as_micros()));
+            log_record(&mut log, &mut framer, &event.to_mavlink(ts));
+            event_log.record(ts, EventLogEntry::Event(event));
+
+            landed |= event.event == Event::FlightLanded;
+        }
+
+        // The events of the flight, together at the end of the log: found even if the records
+        // in between are damaged
+        if landed {
+            for entry in event_log.iter() {
+                log_record(&mut log, &mut framer, &entry.v.to_mavlink(entry.t));
+            }
+        }
+
+        if landed || last_flush.elapsed().as_millis() >= LOG_FLUSH_PERIOD_MS {
+            log.flush();
+            last_flush = Instant::now();
+
+            if landed {
+                info!("LOGGER | Landed, log flushed");
+            }
+        }
+
+        Timer::after_millis(5).await;
+    }
+}
+
+/// Copies the records of the flash logger to the SD card, a block at a time. Lower priority than
+/// the flash logger: records are dropped if the card is too slow.
+#[embassy_executor::task]
+async fn sd_logger(mut sd_log: SdLog, mut rx_events: DynSubscriber<'static, EventItem>) {
+    info!("Running SD logger");
+    let mut block = [0u8; 512];
+    let mut last_flush = Instant::now();
+
+    loop {
+        if let Ok(len) = bsp::channels::SD_LOG_RECORDS.try_read(&mut block) {
+            sd_log.write(&block[..len]);
+        }
+
+        let mut landed = false;
+        while let Some(event) = rx_events.try_next_message_pure() {
+            landed |= event.event == Event::FlightLanded;
+        }
+
+        // On landing, so that the card can be removed safely
+        if landed || last_flush.elapsed().as_millis() >= SD_FLUSH_PERIOD_MS {
+            sd_log.flush();
+            last_flush = Instant::now();
+
+            if landed {
+                info!("SD | Landed, log flushed");
+            }
+        }
+
+        Timer::after_millis(10).await;
+    }
+}
+
+/// Drives the fin servos with the latest command of the control components
+#[embassy_executor::task]
+async fn act_servos(mut servos: PwmServos, mut rx: DynSubscriber<'static, Ts<ServoCommand>>) {
+    info!("Running servos");
+    loop {
+        let cmd = rx.next_message_pure().await;
+        servos.apply(&cmd.v);
+    }
+}
+
+/// Forwards the GNC events to the other boards, and sends the heartbeat
+#[embassy_executor::task]
+async fn can_tx(mut can: CanLinkTx, mut rx_events: DynSubscriber<'static, EventItem>) {
+    info!("Running CAN tx");
+    let mut last_heartbeat = Instant::now();
+
+    loop {
+        watchdog::check_in(CriticalTask::CanTx);
+
+        while let Some(event) = rx_events.try_next_message_pure() {
+            can.send(&CanMessage::Event(event.event)).await;
+        }
+
+        if last_heartbeat.elapsed().as_millis() >= CAN_HEARTBEAT_PERIOD_MS {
+            last_heartbeat = Instant::now();
+            can.send(&CanMessage::Heartbeat {
+                uptime_ms: last_heartbeat.as_millis() as u32,
+                error_count: 0,
+            })
+            .await;
+        }
+
+        Timer::after_millis(5).await;
+    }
+}
+
+/// Monitors the supplies, raising an event when the battery gets low
+#[embassy_executor::task]
+async fn power_monitor(
+    mut adc: PowerAdc,
+    mut monitor: PowerMonitor,
+    tx: DynPublisher<'static, Ts<PowerStatus>>,
+    tx_events: DynPublisher<'static, EventItem>,
+) {
+    info!("Running power monitor");
+    loop {
+        let t = Instant::now();
+        let (status, event) = monitor.update(&adc.sample());
+
+        if let Some(event) = event {
+            warn!("POWER | Battery low: {} V", status.battery_v);
+            tx_events.publish_immediate(EventItem {
+                src: ComponentId::PowerMonitor,
+                event,
+            });
+        }
+        tx.publish_immediate(Ts::from_microseconds(t.as_micros(), status));
+
+        Timer::after_millis(POWER_SAMPLE_PERIOD_MS).await;
+    }
+}
+
+/// Resets the board if a critical task gets stuck
+#[embassy_executor::task]
+async fn supervisor(mut supervisor: Supervisor) {
+    info!("Running supervisor");
+    supervisor.run().await;
+}
+
+#[embassy_executor::task]
+async fn can_rx(mut can: CanLinkRx, tx: DynPublisher<'static, Ts<(CanNodeId, CanMessage)>>) {
+    info!("Running CAN rx");
+    loop {
+        match can.recv().await {
+            Ok(msg) => tx.publish_immediate(Ts::from_microseconds(Instant::now().as_micros(), msg)),
+            Err(err) => warn!("CAN | {}", defmt::Display2Format(&err)),
+        }
+    }
+}
+
+/// Sends the events, the GNSS fixes & the downlink messages by priority within the airtime
+/// budget, and publishes the messages received from the ground in between
+#[embassy_executor::task]
+async fn radio(
+    mut lora: Sx127x,
+    mut rx_events: DynSubscriber<'static, EventItem>,
+    mut rx_gnss: DynSubscriber<'static, Ts<GnssSensorSample>>,
+    mut rx_power: DynSubscriber<'static, Ts<PowerStatus>>,
+    mut rx_downlink: DynSubscriber<'static, MavMessage>,
+    tx_uplink: DynPublisher<'static, Ts<MavMessage>>,
+) {
+    info!("Running radio");
+    let mut scheduler = AirtimeScheduler::new(
+        *lora.modulation(),
+        AirtimeBudget::new(LORA_DUTY_CYCLE, LORA_MAX_BURST_US),
+    );
+    let mut packet = [0u8; sx127x::MAX_PAYLOAD_LEN];
+
+    if let Err(err) = lora.start_rx().await {
+        warn!("SX127X | {}", defmt::Display2Format(&err));
+    }
+
+    loop {
+        while let Some(event) = rx_events.try_next_message_pure() {
+            let ts = crater_gnc::Instant(InstantU64::from_ticks(Instant::now().as_micros()));
+            scheduler.push(event.to_mavlink(ts));
+        }
+
+        while let Some(sample) = rx_gnss.try_next_message_pure() {
+            scheduler.push(sample.v.to_mavlink(GnssSensorId::UbloxM9, sample.t));
+        }
+
+        while let Some(status) = rx_power.try_next_message_pure() {
+            scheduler.push(status.v.to_mavlink(status.t));
+        }
+
+        while let Some(msg) = rx_downlink.try_next_message_pure() {
+            scheduler.push(msg);
+        }
+
+        match lora.try_receive(&mut packet).await {
+            Ok(Some(rx)) => {
+                match read_v2_msg::<MavMessage, _>(&mut PeekReader::new(&packet[..rx.len])) {
+                    Ok((_, msg)) => tx_uplink
+                        .publish_immediate(Ts::from_microseconds(Instant::now().as_micros(), msg)),
+                    Err(_) => warn!("RADIO | Invalid uplink packet, RSSI {} dBm", rx.rssi_dbm),
+                }
+            }
+            Ok(None) => {}
+            Err(err) => warn!("SX127X | {}", defmt::Display2Format(&err)),
+        }
+
+        // The receiver is off while transmitting
+        let mut transmitted = false;
+        loop {
+            let ts = crater_gnc::Instant(InstantU64::from_ticks(Instant::now().as_micros()));
+            let Some(frame) = scheduler.next_frame(ts) else {
+                break;
+            };
+
+            if let Err(err) = lora.transmit(frame).await {
+                warn!("SX127X | {}", defmt::Display2Format(&err));
+            }
+            transmitted = true;
+        }
+
+        if transmitted && let Err(err) = lora.start_rx().await {
+            warn!("SX127X | {}", defmt::Display2Format(&err));
+        }
+
+        Timer::after_millis(10).await;
     }
 }
 
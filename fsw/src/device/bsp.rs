@@ -1,7 +1,10 @@
 use core::marker::PhantomData;
 
 use embassy_stm32::{
-    Config, bind_interrupts,
+    Config,
+    adc::{Adc, AdcChannel},
+    bind_interrupts,
+    can::{self, Can, CanRx, CanTx},
     gpio::{self, AnyPin, Input, Output, Pin},
     interrupt::typelevel::{Handler, Interrupt},
     mode::Blocking,
@@ -9,7 +12,12 @@ use embassy_stm32::{
     peripherals,
     spi::{self, Spi},
     time::Hertz,
+    timer::{
+        low_level::CountingMode,
+        simple_pwm::{PwmPin, SimplePwm},
+    },
     usart::{self, BufferedUart, Uart, UartTx},
+    wdg::IndependentWatchdog,
 };
 use embassy_sync::{
     blocking_mutex::raw::{CriticalSectionRawMutex, ThreadModeRawMutex},
@@ -18,7 +26,12 @@ use embassy_sync::{
 use embassy_time::Instant;
 use static_cell::StaticCell;
 
-use crate::HEAP;
+use crater_gnc::mav_crater::ResetCause;
+
+use crate::{
+    HEAP,
+    device::{power_adc::PowerAdc, servo, watchdog},
+};
 
 pub struct BspSensBmp390 {
     pub cs: Output<'static>,
@@ -27,9 +40,70 @@ pub struct BspSensBmp390 {
 pub struct BspSensIcm42688 {
     pub cs: Output<'static>,
 }
+
+pub struct BspSensUblox {
+    pub uart: BufferedUart<'static>,
+}
+
+/// Log flash, alone on SPI2
+pub struct BspFlash {
+    pub spi: Spi<'static, Blocking>,
+    pub cs: Output<'static>,
+}
+
+/// SD card, alone on SPI3
+pub struct BspSd {
+    pub spi: Spi<'static, Blocking>,
+    pub cs: Output<'static>,
+}
+
+/// Fin servos, on the 4 channels of TIM4
+pub struct BspServos {
+    pub pwm: SimplePwm<'static, peripherals::TIM4>,
+}
+
+/// LoRa radio, on SPI1
+pub struct BspLora {
+    pub cs: Output<'static>,
+}
+
+/// Battery, pyro rail & current sense on ADC1
+pub struct BspPower {
+    pub adc: PowerAdc,
+}
+
+/// Bus to the other boards, enabled
+pub struct BspCan {
+    pub tx: CanTx<'static>,
+    pub rx: CanRx<'static>,
+}
+
+/// Independent watchdog, not started
+pub struct BspWatchdog {
+    pub iwdg: IndependentWatchdog<'static, peripherals::IWDG>,
+}
+
+/// Receiver of the debug serial port, on which the simulation sends the sensor samples in HIL
+/// mode
+#[cfg(feature = "hil")]
+pub struct BspHil {
+    pub rx: usart::RingBufferedUartRx<'static>,
+}
+
 pub struct CraterBsp {
+    pub reset_cause: ResetCause,
+    pub watchdog: BspWatchdog,
     pub sens_bmp390: BspSensBmp390,
     pub sens_icm42688: BspSensIcm42688,
+    pub sens_ublox: BspSensUblox,
+    pub flash: BspFlash,
+    pub sd: BspSd,
+    pub servos: BspServos,
+    pub can: BspCan,
+    pub lora: BspLora,
+    pub power: BspPower,
+    #[cfg(feature = "hil")]
+    pub hil: BspHil,
 }
 
 pub mod bus {
@@ -69,22 +143,34 @@ pub mod channels {
         common::Ts,
         components::ada::AdaResult,
         datatypes::{
+            actuators::ServoCommand,
             pin::DigitalInputState,
-            sensors::{ImuSensorSample, PressureSensorSample},
+            power::PowerStatus,
+            sensors::{
+                GnssSensorSample, ImuSensorSample, MagnetometerSensorSample, PressureSensorSample,
+            },
         },
+        io::can_protocol::{CanMessage, CanNodeId},
+        mav_crater::MavMessage,
+    };
+    use embassy_sync::{
+        blocking_mutex::raw::ThreadModeRawMutex, pipe::Pipe, pubsub::PubSubChannel,
     };
-    use embassy_sync::{blocking_mutex::raw::ThreadModeRawMutex, pubsub::PubSubChannel};
 
     use crate::sensors::icm42688::Icm42688Sample;
 
-    pub static EVENTS: PubSubChannel<ThreadModeRawMutex, crater_gnc::events::EventItem, 50, 1, 1> =
+    pub static EVENTS: PubSubChannel<ThreadModeRawMutex, crater_gnc::events::EventItem, 50, 4, 1> =
         PubSubChannel::new();
 
+    /// Log records from the flash logger to the SD logger, dropped when full
+    pub static SD_LOG_RECORDS: Pipe<ThreadModeRawMutex, 4096> = Pipe::new();
+
+    // Sensor samples are read by the telemetry & the logger
     pub static SENS_BMP_390_SAMPLE: PubSubChannel<
         ThreadModeRawMutex,
         Ts<PressureSensorSample>,
         5,
-        1,
+        2,
         1,
     > = PubSubChannel::new();
 
@@ -92,6 +178,18 @@ pub mod channels {
         ThreadModeRawMutex,
         Ts<Icm42688Sample>,
         20,
+        2,
+        1,
+    > = PubSubChannel::new();
+
+    pub static SENS_UBLOX_SAMPLE: PubSubChannel<ThreadModeRawMutex, Ts<GnssSensorSample>, 2, 3, 1> =
+        PubSubChannel::new();
+
+    /// Only produced in HIL mode: the board has no magnetometer
+    pub static SENS_MAG_SAMPLE: PubSubChannel<
+        ThreadModeRawMutex,
+        Ts<MagnetometerSensorSample>,
+        5,
         1,
         1,
     > = PubSubChannel::new();
@@ -101,6 +199,25 @@ pub mod channels {
 
     pub static COMP_ADA_RESULT: PubSubChannel<ThreadModeRawMutex, Ts<AdaResult>, 1, 1, 1> =
         PubSubChannel::new();
+
+    pub static ACT_SERVO_COMMAND: PubSubChannel<ThreadModeRawMutex, Ts<ServoCommand>, 1, 1, 1> =
+        PubSubChannel::new();
+
+    // Read by the logger & the radio
+    pub static POWER_STATUS: PubSubChannel<ThreadModeRawMutex, Ts<PowerStatus>, 1, 2, 1> =
+        PubSubChannel::new();
+
+    /// Messages to the ground station, sent on the radio by priority
+    pub static DOWNLINK: PubSubChannel<ThreadModeRawMutex, MavMessage, 8, 1, 1> =
+        PubSubChannel::new();
+
+    /// Messages received from the ground station
+    pub static UPLINK: PubSubChannel<ThreadModeRawMutex, Ts<MavMessage>, 4, 1, 1> =
+        PubSubChannel::new();
+
+    /// Messages received from the other boards
+    pub static CAN_RX: PubSubChannel<ThreadModeRawMutex, Ts<(CanNodeId, CanMessage)>, 8, 1, 1> =
+        PubSubChannel::new();
 }
 
 struct Icm42688InterruptHandler<I> {
@@ -127,11 +244,22 @@ impl<I: Interrupt> Handler<I> for Icm42688InterruptHandler<I> {
 bind_interrupts!(struct Irqs {
     // USART3 => usart::BufferedInterruptHandler<peripherals::USART3>;
     USART3 => usart::InterruptHandler<peripherals::USART3>;
+    USART2 => usart::BufferedInterruptHandler<peripherals::USART2>;
     EXTI2 =>  Icm42688InterruptHandler<embassy_stm32::interrupt::typelevel::EXTI2>;
+    CAN1_RX0 => can::Rx0InterruptHandler<peripherals::CAN1>;
+    CAN1_RX1 => can::Rx1InterruptHandler<peripherals::CAN1>;
+    CAN1_SCE => can::SceInterruptHandler<peripherals::CAN1>;
+    CAN1_TX => can::TxInterruptHandler<peripherals::CAN1>;
 });
 
 static USART_TX_BUF: StaticCell<[u8; 5600]> = StaticCell::new();
 static USART_RX_BUF: StaticCell<[u8; 5600]> = StaticCell::new();
+static GNSS_TX_BUF: StaticCell<[u8; 128]> = StaticCell::new();
+static GNSS_RX_BUF: StaticCell<[u8; 512]> = StaticCell::new();
+#[cfg(feature = "hil")]
+static HIL_RX_BUF: StaticCell<[u8; 2048]> = StaticCell::new();
+/// The peripheral is disabled when dropped
+static CAN_1: StaticCell<Can<'static>> = StaticCell::new();
 
 impl CraterBsp {
     pub async fn init() -> CraterBsp {
@@ -145,6 +273,11 @@ impl CraterBsp {
 
         let p = embassy_stm32::init(Default::default());
 
+        let reset_cause = watchdog::read_reset_cause();
+        let watchdog = BspWatchdog {
+            iwdg: IndependentWatchdog::new(p.IWDG, watchdog::TIMEOUT_US),
+        };
+
         let pin_icm_42688_drdy = p.PB2.degrade();
         enable_exti_interrupt(&pin_icm_42688_drdy);
         let input = Input::new(pin_icm_42688_drdy, gpio::Pull::Up);
@@ -172,8 +305,43 @@ impl CraterBsp {
         // *bus::DEBUG_SERIAL_TX.lock().await = Some(tx);
         // *bus::DEBUG_SERIAL_RX.lock().await = Some(rx);
 
-        let usart3_tx = UartTx::new(p.USART3, p.PD8, p.DMA1_CH4, usart3_cfg).unwrap();
-        *bus::DEBUG_SERIAL_TX.lock().await = Some(usart3_tx);
+        #[cfg(not(feature = "hil"))]
+        {
+            let usart3_tx = UartTx::new(p.USART3, p.PD8, p.DMA1_CH4, usart3_cfg).unwrap();
+            *bus::DEBUG_SERIAL_TX.lock().await = Some(usart3_tx);
+        }
+
+        // Full duplex in HIL mode, the received bytes are buffered by DMA
+        #[cfg(feature = "hil")]
+        let hil = {
+            let (usart3_tx, usart3_rx) = Uart::new(
+                p.USART3, p.PD9, p.PD8, Irqs, p.DMA1_CH4, p.DMA1_CH1, usart3_cfg,
+            )
+            .unwrap()
+            .split();
+            *bus::DEBUG_SERIAL_TX.lock().await = Some(usart3_tx);
+
+            BspHil {
+                rx: usart3_rx.into_ring_buffered(HIL_RX_BUF.init([0; 2048])),
+            }
+        };
+
+        // u-blox receiver, at its default baudrate
+        let mut gnss_cfg = usart::Config::default();
+        gnss_cfg.baudrate = 38400;
+
+        let sens_ublox = BspSensUblox {
+            uart: BufferedUart::new(
+                p.USART2,
+                Irqs,
+                p.PD6,
+                p.PD5,
+                GNSS_TX_BUF.init([0; 128]),
+                GNSS_RX_BUF.init([0; 512]),
+                gnss_cfg,
+            )
+            .unwrap(),
+        };
 
         let mut config = spi::Config::default();
         config.rise_fall_speed = gpio::Speed::Medium;
@@ -182,6 +350,53 @@ impl CraterBsp {
         *bus::SPI_1.try_lock().unwrap() =
             Some(spi::Spi::new_blocking(p.SPI1, p.PA5, p.PA7, p.PA6, config));
 
+        let mut flash_spi_cfg = spi::Config::default();
+        flash_spi_cfg.rise_fall_speed = gpio::Speed::VeryHigh;
+        flash_spi_cfg.frequency = Hertz(25_000_000);
+
+        let flash = BspFlash {
+            spi: spi::Spi::new_blocking(p.SPI2, p.PB13, p.PB15, p.PB14, flash_spi_cfg),
+            cs: Output::new(
+                AnyPin::from(p.PB12),
+                gpio::Level::High,
+                gpio::Speed::VeryHigh,
+            ),
+        };
+
+        // Initialization clock of the SD card, raised afterwards
+        let mut sd_spi_cfg = spi::Config::default();
+        sd_spi_cfg.rise_fall_speed = gpio::Speed::VeryHigh;
+        sd_spi_cfg.frequency = Hertz(400_000);
+
+        let sd = BspSd {
+            spi: spi::Spi::new_blocking(p.SPI3, p.PC10, p.PC12, p.PC11, sd_spi_cfg),
+            cs: Output::new(
+                AnyPin::from(p.PD2),
+                gpio::Level::High,
+                gpio::Speed::VeryHigh,
+            ),
+        };
+
+        let servos = BspServos {
+            pwm: SimplePwm::new(
+                p.TIM4,
+                Some(PwmPin::new_ch1(p.PD12, gpio::OutputType::PushPull)),
+                Some(PwmPin::new_ch2(p.PD13, gpio::OutputType::PushPull)),
+                Some(PwmPin::new_ch3(p.PD14, gpio::OutputType::PushPull)),
+                Some(PwmPin::new_ch4(p.PD15, gpio::OutputType::PushPull)),
+                servo::FRAME_RATE,
+                CountingMode::EdgeAlignedUp,
+            ),
+        };
+
+        let can1 = CAN_1.init(Can::new(p.CAN1, p.PD0, p.PD1, Irqs));
+        crate::io::can::init(can1).await;
+        let (can_tx, can_rx) = can1.split();
+        let can = BspCan {
+            tx: can_tx,
+            rx: can_rx,
+        };
+
         let sens_bmp390 = BspSensBmp390 {
             cs: Output::new(
                 AnyPin::from(p.PF12),
@@ -201,9 +416,37 @@ impl CraterBsp {
             ),
         };
 
+        let lora = BspLora {
+            cs: Output::new(
+                AnyPin::from(p.PF13),
+                gpio::Level::High,
+                gpio::Speed::VeryHigh,
+            ),
+        };
+
+        let power = BspPower {
+            adc: PowerAdc::new(
+                Adc::new(p.ADC1),
+                p.PA0.degrade_adc(),
+                p.PA3.degrade_adc(),
+                p.PC0.degrade_adc(),
+            ),
+        };
+
         CraterBsp {
+            reset_cause,
+            watchdog,
             sens_bmp390,
             sens_icm42688,
+            sens_ublox,
+            flash,
+            sd,
+            servos,
+            can,
+            lora,
+            power,
+            #[cfg(feature = "hil")]
+            hil,
         }
     }
 }
@@ -1,2 +1,7 @@
 pub mod spi;
-pub mod bsp;
\ No newline at end of file
+pub mod bsp;
+pub mod power_adc;
+pub mod servo;
+pub mod sx127x;
+pub mod w25q;
+pub mod watchdog;
\ No newline at end of file
@@ -0,0 +1,47 @@
+use crater_gnc::datatypes::power::PowerAdcSample;
+use embassy_stm32::{
+    adc::{Adc, AnyAdcChannel, SampleTime},
+    peripherals::ADC1,
+};
+
+/// Analog supply of the ADC, the conversion reference
+const VDDA_V: f32 = 3.3;
+const FULL_SCALE: f32 = 4095.0;
+
+/// Supply monitoring channels of ADC1: battery & pyro rail dividers, current sense amplifier
+pub struct PowerAdc {
+    adc: Adc<'static, ADC1>,
+    battery: AnyAdcChannel<ADC1>,
+    pyro: AnyAdcChannel<ADC1>,
+    current_sense: AnyAdcChannel<ADC1>,
+}
+
+impl PowerAdc {
+    pub fn new(
+        mut adc: Adc<'static, ADC1>,
+        battery: AnyAdcChannel<ADC1>,
+        pyro: AnyAdcChannel<ADC1>,
+        current_sense: AnyAdcChannel<ADC1>,
+    ) -> Self {
+        // The dividers have a high output impedance
+        adc.set_sample_time(SampleTime::CYCLES480);
+
+        PowerAdc {
+            adc,
+            battery,
+            pyro,
+            current_sense,
+        }
+    }
+
+    /// Pin voltages, calibrated by the power monitor
+    pub fn sample(&mut self) -> PowerAdcSample {
+        let to_volts = |raw: u16| raw as f32 / FULL_SCALE * VDDA_V;
+
+        PowerAdcSample {
+            battery_v: to_volts(self.adc.blocking_read(&mut self.battery)),
+            pyro_v: to_volts(self.adc.blocking_read(&mut self.pyro)),
+            current_sense_v: to_volts(self.adc.blocking_read(&mut self.current_sense)),
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use crater_gnc::{
+    datatypes::actuators::NUM_SERVOS,
+    hal::servo::{ServoCalibration, ServoConfig, ServoOutput},
+};
+use embassy_stm32::{
+    peripherals::TIM4,
+    time::Hertz,
+    timer::{Channel, simple_pwm::SimplePwm},
+};
+
+/// Servo frame rate
+pub const FRAME_RATE: Hertz = Hertz(50);
+
+const CHANNELS: [Channel; NUM_SERVOS] = [Channel::Ch1, Channel::Ch2, Channel::Ch3, Channel::Ch4];
+
+/// Fin servos on the 4 channels of a PWM timer, one pulse each frame
+pub struct PwmServos {
+    pwm: SimplePwm<'static, TIM4>,
+    config: ServoConfig,
+}
+
+impl PwmServos {
+    /// `pwm` must run at `FRAME_RATE`. The servos are centered before the outputs are enabled.
+    pub fn new(pwm: SimplePwm<'static, TIM4>, config: ServoConfig) -> Self {
+        let mut servos = PwmServos { pwm, config };
+
+        for channel in 0..NUM_SERVOS {
+            servos.set_position_rad(channel, 0.0);
+            servos.pwm.channel(CHANNELS[channel]).enable();
+        }
+
+        servos
+    }
+}
+
+impl ServoOutput for PwmServos {
+    fn calibration(&self, channel: usize) -> &ServoCalibration {
+        &self.config.channels[channel]
+    }
+
+    fn set_pulse_us(&mut self, channel: usize, pulse_us: f32) {
+        let period_us = 1e6 / FRAME_RATE.0 as f32;
+
+        let mut ch = self.pwm.channel(CHANNELS[channel]);
+        let duty = pulse_us / period_us * ch.max_duty_cycle() as f32;
+        ch.set_duty_cycle(duty as u16);
+    }
+}
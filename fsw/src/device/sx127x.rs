@@ -0,0 +1,329 @@
+use crater_gnc::io::airtime::LoraModulation;
+use defmt::{debug, info};
+use embassy_stm32::mode::Blocking;
+use embassy_time::{Instant, Timer};
+use thiserror::Error;
+
+use crate::device::spi::SpiDevice;
+
+/// Packets are limited by the FIFO
+pub const MAX_PAYLOAD_LEN: usize = 255;
+
+const VERSION: u8 = 0x12;
+const FXOSC_HZ: u64 = 32_000_000;
+
+#[allow(unused)]
+pub mod regs {
+    pub const FIFO: u8 = 0x00;
+    pub const OP_MODE: u8 = 0x01;
+    pub const FRF_MSB: u8 = 0x06;
+    pub const FRF_MID: u8 = 0x07;
+    pub const FRF_LSB: u8 = 0x08;
+    pub const PA_CONFIG: u8 = 0x09;
+    pub const FIFO_ADDR_PTR: u8 = 0x0D;
+    pub const FIFO_TX_BASE_ADDR: u8 = 0x0E;
+    pub const FIFO_RX_BASE_ADDR: u8 = 0x0F;
+    pub const FIFO_RX_CURRENT_ADDR: u8 = 0x10;
+    pub const IRQ_FLAGS: u8 = 0x12;
+    pub const RX_NB_BYTES: u8 = 0x13;
+    pub const PKT_SNR_VALUE: u8 = 0x19;
+    pub const PKT_RSSI_VALUE: u8 = 0x1A;
+    pub const MODEM_CONFIG_1: u8 = 0x1D;
+    pub const MODEM_CONFIG_2: u8 = 0x1E;
+    pub const PREAMBLE_MSB: u8 = 0x20;
+    pub const PREAMBLE_LSB: u8 = 0x21;
+    pub const PAYLOAD_LENGTH: u8 = 0x22;
+    pub const MODEM_CONFIG_3: u8 = 0x26;
+    pub const VERSION: u8 = 0x42;
+}
+
+#[allow(unused)]
+mod mode {
+    /// Selects the LoRa modem. Only writable in sleep mode.
+    pub const LONG_RANGE: u8 = 0x80;
+    pub const SLEEP: u8 = 0x00;
+    pub const STANDBY: u8 = 0x01;
+    pub const TX: u8 = 0x03;
+    pub const RX_CONTINUOUS: u8 = 0x05;
+}
+
+#[allow(unused)]
+mod irq {
+    pub const RX_DONE: u8 = 0x40;
+    pub const PAYLOAD_CRC_ERROR: u8 = 0x20;
+    pub const TX_DONE: u8 = 0x08;
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("SPI transfer failed")]
+    Spi,
+    #[error("Unexpected version {0:#x}")]
+    WrongVersion(u8),
+    #[error("Unsupported modulation")]
+    Modulation,
+    #[error("Packet of {0} bytes is too long")]
+    PayloadTooLong(usize),
+    #[error("Transmission timed out")]
+    TxTimeout,
+    #[error("Packet received with a CRC error")]
+    Crc,
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub frequency_hz: u32,
+    /// On the PA_BOOST pin, 2 to 17 dBm
+    pub tx_power_dbm: u8,
+    pub modulation: LoraModulation,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            frequency_hz: 868_000_000,
+            tx_power_dbm: 14,
+            modulation: LoraModulation::default(),
+        }
+    }
+}
+
+/// Received packet
+#[derive(Debug, Clone, Copy)]
+pub struct RxPacket {
+    pub len: usize,
+    pub rssi_dbm: i16,
+    pub snr_db: f32,
+}
+
+/// Semtech SX1276/77/78/79 LoRa transceiver (RFM95 modules), on the high frequency port.
+///
+/// The interrupt pins are not used: the IRQ flags are polled. Half duplex, so the receiver is
+/// off while transmitting.
+pub struct Sx127x {
+    dev: SpiDevice<Blocking>,
+    config: Config,
+    err_cnt: usize,
+}
+
+impl Sx127x {
+    pub async fn init(dev: SpiDevice<Blocking>, config: Config) -> Result<Self, Error> {
+        let mut sx127x = Sx127x {
+            dev,
+            config,
+            err_cnt: 0,
+        };
+
+        let mut remaining_attempts = 3;
+
+        loop {
+            let version = sx127x.read_reg(regs::VERSION).await?;
+
+            if version == VERSION {
+                break;
+            } else if remaining_attempts == 0 {
+                return Err(Error::WrongVersion(version));
+            }
+
+            debug!("SX127X | Wrong version {=u8:x}. Retrying", version);
+            remaining_attempts -= 1;
+            Timer::after_millis(10).await;
+        }
+
+        sx127x.configure().await?;
+        info!("SX127X | {} Hz", sx127x.config.frequency_hz);
+
+        Ok(sx127x)
+    }
+
+    pub fn err_cnt(&self) -> usize {
+        self.err_cnt
+    }
+
+    pub fn modulation(&self) -> &LoraModulation {
+        &self.config.modulation
+    }
+
+    async fn configure(&mut self) -> Result<(), Error> {
+        let modulation = self.config.modulation;
+
+        let bw = match modulation.bandwidth_hz as u32 {
+            62_500 => 0x6,
+            125_000 => 0x7,
+            250_000 => 0x8,
+            500_000 => 0x9,
+            _ => return Err(Error::Modulation),
+        };
+        if !(6..=12).contains(&modulation.spreading_factor)
+            || !(1..=4).contains(&modulation.coding_rate)
+        {
+            return Err(Error::Modulation);
+        }
+
+        self.write_reg(regs::OP_MODE, mode::SLEEP).await?;
+        self.write_reg(regs::OP_MODE, mode::LONG_RANGE | mode::SLEEP)
+            .await?;
+
+        let frf = ((self.config.frequency_hz as u64) << 19) / FXOSC_HZ;
+        self.write_reg(regs::FRF_MSB, (frf >> 16) as u8).await?;
+        self.write_reg(regs::FRF_MID, (frf >> 8) as u8).await?;
+        self.write_reg(regs::FRF_LSB, frf as u8).await?;
+
+        // PA_BOOST, Pout = 2 + OutputPower
+        let power = self.config.tx_power_dbm.clamp(2, 17) - 2;
+        self.write_reg(regs::PA_CONFIG, 0x80 | power).await?;
+
+        // The whole FIFO is used for each packet
+        self.write_reg(regs::FIFO_TX_BASE_ADDR, 0).await?;
+        self.write_reg(regs::FIFO_RX_BASE_ADDR, 0).await?;
+
+        self.write_reg(
+            regs::MODEM_CONFIG_1,
+            (bw << 4) | (modulation.coding_rate << 1) | !modulation.explicit_header as u8,
+        )
+        .await?;
+        self.write_reg(
+            regs::MODEM_CONFIG_2,
+            (modulation.spreading_factor << 4) | ((modulation.crc as u8) << 2),
+        )
+        .await?;
+        // AGC on
+        self.write_reg(
+            regs::MODEM_CONFIG_3,
+            ((modulation.low_data_rate_optimize as u8) << 3) | 0x04,
+        )
+        .await?;
+
+        self.write_reg(regs::PREAMBLE_MSB, (modulation.preamble_len >> 8) as u8)
+            .await?;
+        self.write_reg(regs::PREAMBLE_LSB, modulation.preamble_len as u8)
+            .await?;
+
+        self.write_reg(regs::OP_MODE, mode::LONG_RANGE | mode::STANDBY)
+            .await
+    }
+
+    /// Sends a packet, waiting for the end of the transmission. The receiver is left off.
+    pub async fn transmit(&mut self, data: &[u8]) -> Result<(), Error> {
+        if data.len() > MAX_PAYLOAD_LEN {
+            return Err(Error::PayloadTooLong(data.len()));
+        }
+
+        self.write_reg(regs::OP_MODE, mode::LONG_RANGE | mode::STANDBY)
+            .await?;
+        self.write_reg(regs::FIFO_ADDR_PTR, 0).await?;
+        self.write_fifo(data).await?;
+        self.write_reg(regs::PAYLOAD_LENGTH, data.len() as u8)
+            .await?;
+
+        self.write_reg(regs::IRQ_FLAGS, 0xFF).await?;
+        self.write_reg(regs::OP_MODE, mode::LONG_RANGE | mode::TX)
+            .await?;
+
+        // Twice the time on air, in case the clocks differ
+        let timeout_us = 2 * self.config.modulation.time_on_air_us(data.len()) as u64 + 10_000;
+        let start = Instant::now();
+
+        loop {
+            if self.read_reg(regs::IRQ_FLAGS).await? & irq::TX_DONE != 0 {
+                self.write_reg(regs::IRQ_FLAGS, irq::TX_DONE).await?;
+                return Ok(());
+            }
+
+            if start.elapsed().as_micros() > timeout_us {
+                self.err_cnt += 1;
+                self.write_reg(regs::OP_MODE, mode::LONG_RANGE | mode::STANDBY)
+                    .await?;
+                return Err(Error::TxTimeout);
+            }
+
+            Timer::after_millis(1).await;
+        }
+    }
+
+    /// Listens until the next transmission
+    pub async fn start_rx(&mut self) -> Result<(), Error> {
+        self.write_reg(regs::IRQ_FLAGS, 0xFF).await?;
+        self.write_reg(regs::OP_MODE, mode::LONG_RANGE | mode::RX_CONTINUOUS)
+            .await
+    }
+
+    /// Copies a received packet to `buf`, if any. The receiver keeps listening.
+    pub async fn try_receive(&mut self, buf: &mut [u8]) -> Result<Option<RxPacket>, Error> {
+        let flags = self.read_reg(regs::IRQ_FLAGS).await?;
+        if flags & irq::RX_DONE == 0 {
+            return Ok(None);
+        }
+
+        self.write_reg(regs::IRQ_FLAGS, irq::RX_DONE | irq::PAYLOAD_CRC_ERROR)
+            .await?;
+        if flags & irq::PAYLOAD_CRC_ERROR != 0 {
+            self.err_cnt += 1;
+            return Err(Error::Crc);
+        }
+
+        let len = (self.read_reg(regs::RX_NB_BYTES).await? as usize).min(buf.len());
+        let addr = self.read_reg(regs::FIFO_RX_CURRENT_ADDR).await?;
+        self.write_reg(regs::FIFO_ADDR_PTR, addr).await?;
+        self.read_fifo(&mut buf[..len]).await?;
+
+        let rssi = self.read_reg(regs::PKT_RSSI_VALUE).await?;
+        let snr = self.read_reg(regs::PKT_SNR_VALUE).await? as i8;
+
+        Ok(Some(RxPacket {
+            len,
+            rssi_dbm: rssi as i16 - 157,
+            snr_db: snr as f32 / 4.0,
+        }))
+    }
+
+    // Reads have the MSB of the address cleared, writes set
+    async fn read_reg(&mut self, reg: u8) -> Result<u8, Error> {
+        let mut buf = [reg & 0x7F, 0];
+        let res = self
+            .dev
+            .start_transaction()
+            .await
+            .transfer_in_place_raw(&mut buf);
+
+        self.check(res)?;
+        Ok(buf[1])
+    }
+
+    async fn write_reg(&mut self, reg: u8, value: u8) -> Result<(), Error> {
+        let res = self
+            .dev
+            .start_transaction()
+            .await
+            .write_raw(&[reg | 0x80, value]);
+
+        self.check(res)
+    }
+
+    async fn write_fifo(&mut self, data: &[u8]) -> Result<(), Error> {
+        let mut transaction = self.dev.start_transaction().await;
+        let res = transaction
+            .write_raw(&[regs::FIFO | 0x80])
+            .and_then(|_| transaction.write_raw(data));
+        drop(transaction);
+
+        self.check(res)
+    }
+
+    async fn read_fifo(&mut self, buf: &mut [u8]) -> Result<(), Error> {
+        let mut transaction = self.dev.start_transaction().await;
+        let res = transaction
+            .write_raw(&[regs::FIFO])
+            .and_then(|_| transaction.read_raw(buf));
+        drop(transaction);
+
+        self.check(res)
+    }
+
+    fn check<T>(&mut self, res: Result<T, embassy_stm32::spi::Error>) -> Result<T, Error> {
+        res.map_err(|_| {
+            self.err_cnt += 1;
+            Error::Spi
+        })
+    }
+}
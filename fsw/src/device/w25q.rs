@@ -0,0 +1,174 @@
+use crater_gnc::io::flash_log::NorFlash;
+use defmt::{debug, info, warn};
+use embassy_stm32::{gpio::Output, mode::Blocking, spi::Spi};
+use embassy_time::Timer;
+use thiserror::Error;
+
+const PAGE_SIZE: u32 = 256;
+const SECTOR_SIZE: u32 = 4096;
+
+const MANUFACTURER_WINBOND: u8 = 0xEF;
+
+#[allow(unused)]
+pub mod cmd {
+    pub const WRITE_ENABLE: u8 = 0x06;
+    pub const READ_STATUS_1: u8 = 0x05;
+    pub const READ_DATA: u8 = 0x03;
+    pub const PAGE_PROGRAM: u8 = 0x02;
+    pub const SECTOR_ERASE_4K: u8 = 0x20;
+    pub const RELEASE_POWER_DOWN: u8 = 0xAB;
+    pub const JEDEC_ID: u8 = 0x9F;
+}
+
+/// Status register 1: erase or program in progress
+const STATUS_BUSY: u8 = 0x01;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("SPI transfer failed")]
+    Spi,
+    #[error("Unexpected JEDEC ID")]
+    WrongId,
+}
+
+/// Winbond W25Q serial NOR flash, on its own SPI bus, with 24 bit addresses (up to 128 Mbit).
+///
+/// Blocking: a sector erase stalls the executor for tens of ms, so sectors should be erased
+/// before flight.
+pub struct W25q {
+    spi: Spi<'static, Blocking>,
+    cs: Output<'static>,
+    capacity: u32,
+    err_cnt: usize,
+}
+
+impl W25q {
+    pub async fn init(spi: Spi<'static, Blocking>, cs: Output<'static>) -> Result<Self, Error> {
+        let mut w25q = W25q {
+            spi,
+            cs,
+            capacity: 0,
+            err_cnt: 0,
+        };
+
+        let mut remaining_attempts = 3;
+
+        let id = loop {
+            w25q.transaction(&[cmd::RELEASE_POWER_DOWN], &mut [])?;
+            Timer::after_micros(10).await;
+
+            let mut id = [0u8; 3];
+            w25q.transaction(&[cmd::JEDEC_ID], &mut id)?;
+
+            if id[0] == MANUFACTURER_WINBOND && (16..=24).contains(&id[2]) {
+                break id;
+            } else if remaining_attempts == 0 {
+                return Err(Error::WrongId);
+            }
+
+            debug!(
+                "W25Q | Wrong JEDEC ID {=u8:x} {=u8:x} {=u8:x}. Retrying",
+                id[0], id[1], id[2]
+            );
+            remaining_attempts -= 1;
+            Timer::after_millis(10).await;
+        };
+
+        w25q.capacity = 1 << id[2];
+        info!("W25Q | {} KiB", w25q.capacity / 1024);
+
+        Ok(w25q)
+    }
+
+    /// Transfers that failed. The data read by a failed transfer is not valid.
+    pub fn error_count(&self) -> usize {
+        self.err_cnt
+    }
+
+    /// Writes `write`, then reads `read`, with cs asserted
+    fn transaction(&mut self, write: &[u8], read: &mut [u8]) -> Result<(), Error> {
+        self.cs.set_low();
+        let res = self
+            .spi
+            .blocking_write(write)
+            .and_then(|_| self.spi.blocking_read(read));
+        self.cs.set_high();
+
+        res.map_err(|_| Error::Spi)
+    }
+
+    fn addr_cmd(cmd: u8, addr: u32) -> [u8; 4] {
+        let addr = addr.to_be_bytes();
+        [cmd, addr[1], addr[2], addr[3]]
+    }
+
+    fn write_enable(&mut self) -> Result<(), Error> {
+        self.transaction(&[cmd::WRITE_ENABLE], &mut [])
+    }
+
+    fn wait_ready(&mut self) -> Result<(), Error> {
+        let mut status = [STATUS_BUSY];
+        while status[0] & STATUS_BUSY != 0 {
+            self.transaction(&[cmd::READ_STATUS_1], &mut status)?;
+        }
+        Ok(())
+    }
+
+    /// Programs up to the end of the page of `addr`
+    fn program_page(&mut self, addr: u32, data: &[u8]) -> Result<(), Error> {
+        self.write_enable()?;
+
+        self.cs.set_low();
+        let res = self
+            .spi
+            .blocking_write(&Self::addr_cmd(cmd::PAGE_PROGRAM, addr))
+            .and_then(|_| self.spi.blocking_write(data));
+        self.cs.set_high();
+        res.map_err(|_| Error::Spi)?;
+
+        self.wait_ready()
+    }
+
+    fn count_error(&mut self, res: Result<(), Error>) {
+        if res.is_err() {
+            self.err_cnt += 1;
+            warn!("W25Q | SPI error");
+        }
+    }
+}
+
+impl NorFlash for W25q {
+    fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    fn sector_size(&self) -> u32 {
+        SECTOR_SIZE
+    }
+
+    fn read(&mut self, addr: u32, buf: &mut [u8]) {
+        let res = self.transaction(&Self::addr_cmd(cmd::READ_DATA, addr), buf);
+        self.count_error(res);
+    }
+
+    fn program(&mut self, mut addr: u32, mut data: &[u8]) {
+        while !data.is_empty() {
+            let page_left = (PAGE_SIZE - addr % PAGE_SIZE) as usize;
+            let (page, rest) = data.split_at(page_left.min(data.len()));
+
+            let res = self.program_page(addr, page);
+            self.count_error(res);
+
+            addr += page.len() as u32;
+            data = rest;
+        }
+    }
+
+    fn erase_sector(&mut self, addr: u32) {
+        let res = self.write_enable().and_then(|_| {
+            self.transaction(&Self::addr_cmd(cmd::SECTOR_ERASE_4K, addr), &mut [])?;
+            self.wait_ready()
+        });
+        self.count_error(res);
+    }
+}
@@ -0,0 +1,95 @@
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use crater_gnc::mav_crater::ResetCause;
+use defmt::{info, warn};
+use embassy_stm32::{pac::RCC, peripherals::IWDG, wdg::IndependentWatchdog};
+use embassy_time::Timer;
+
+/// The board resets if the critical tasks do not all check in within this time
+pub const TIMEOUT_US: u32 = 1_000_000;
+
+/// Aliveness of the critical tasks is checked with this period
+const CHECK_PERIOD_MS: u64 = 250;
+
+/// Tasks that stop the flight software if they get stuck
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
+pub enum CriticalTask {
+    Imu = 0,
+    Logger = 1,
+    CanTx = 2,
+}
+
+impl CriticalTask {
+    const ALL: [CriticalTask; 3] = [CriticalTask::Imu, CriticalTask::Logger, CriticalTask::CanTx];
+
+    fn mask(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+/// Tasks checked in since the last check of the supervisor
+static CHECKED_IN: AtomicU32 = AtomicU32::new(0);
+
+/// Reports `task` as alive. Called at least once per check period by each critical task.
+pub fn check_in(task: CriticalTask) {
+    CHECKED_IN.fetch_or(task.mask(), Ordering::Relaxed);
+}
+
+/// Cause of the last reset, from the RCC flags. The flags are cleared, so that the next reset
+/// is reported correctly.
+pub fn read_reset_cause() -> ResetCause {
+    let csr = RCC.csr().read();
+
+    let cause = if csr.iwdgrstf() || csr.wwdgrstf() {
+        ResetCause::Watchdog
+    } else if csr.sftrstf() {
+        ResetCause::Software
+    } else if csr.porrstf() || csr.borrstf() {
+        // The pin flag is also set on power-on
+        ResetCause::PowerOn
+    } else if csr.pinrstf() {
+        ResetCause::Pin
+    } else {
+        ResetCause::Unknown
+    };
+
+    RCC.csr().modify(|w| w.set_rmvf(true));
+    cause
+}
+
+/// Feeds the independent watchdog, only while all the critical tasks check in
+pub struct Supervisor {
+    iwdg: IndependentWatchdog<'static, IWDG>,
+}
+
+impl Supervisor {
+    /// The watchdog is not started until `run`: initialization may take longer than the timeout
+    pub fn new(iwdg: IndependentWatchdog<'static, IWDG>) -> Self {
+        Supervisor { iwdg }
+    }
+
+    /// Starts the watchdog. It cannot be stopped.
+    pub async fn run(&mut self) -> ! {
+        CHECKED_IN.store(0, Ordering::Relaxed);
+        self.iwdg.unleash();
+        info!("WATCHDOG | Started");
+
+        loop {
+            Timer::after_millis(CHECK_PERIOD_MS).await;
+
+            let checked_in = CHECKED_IN.swap(0, Ordering::Relaxed);
+
+            let mut all_alive = true;
+            for task in CriticalTask::ALL {
+                if checked_in & task.mask() == 0 {
+                    warn!("WATCHDOG | {} did not check in", task);
+                    all_alive = false;
+                }
+            }
+
+            if all_alive {
+                self.iwdg.pet();
+            }
+        }
+    }
+}
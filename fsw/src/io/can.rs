@@ -0,0 +1,68 @@
+use crater_gnc::io::can_protocol::{CanDecodeError, CanFrame, CanMessage, CanNodeId};
+use embassy_stm32::can::{Can, CanRx, CanTx, Fifo, Frame, Id, StandardId, filter::Mask32};
+use thiserror::Error;
+
+pub const BITRATE: u32 = 500_000;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("CAN bus error")]
+    Bus,
+    #[error("Extended identifiers are not used")]
+    ExtendedId,
+    #[error("Invalid message: {0}")]
+    Decode(#[from] CanDecodeError),
+}
+
+/// Accepts all the frames & joins the bus
+pub async fn init(can: &mut Can<'static>) {
+    can.modify_filters()
+        .enable_bank(0, Fifo::Fifo0, Mask32::accept_all());
+
+    can.modify_config().set_bitrate(BITRATE);
+    can.enable().await;
+}
+
+/// Sends messages on behalf of `node`
+pub struct CanLinkTx {
+    tx: CanTx<'static>,
+    node: CanNodeId,
+}
+
+impl CanLinkTx {
+    pub fn new(tx: CanTx<'static>, node: CanNodeId) -> Self {
+        Self { tx, node }
+    }
+
+    /// Waits for a free mailbox. Lower priority frames pending in the mailboxes may be replaced.
+    pub async fn send(&mut self, msg: &CanMessage) {
+        let frame = msg.encode(self.node);
+        let frame = Frame::new_data(StandardId::new(frame.id).unwrap(), frame.data()).unwrap();
+
+        self.tx.write(&frame).await;
+    }
+}
+
+pub struct CanLinkRx {
+    rx: CanRx<'static>,
+}
+
+impl CanLinkRx {
+    pub fn new(rx: CanRx<'static>) -> Self {
+        Self { rx }
+    }
+
+    /// Next message & its sender
+    pub async fn recv(&mut self) -> Result<(CanNodeId, CanMessage), Error> {
+        let envelope = self.rx.read().await.map_err(|_| Error::Bus)?;
+
+        let Id::Standard(id) = envelope.frame.id() else {
+            return Err(Error::ExtendedId);
+        };
+
+        Ok(CanMessage::decode(&CanFrame::new(
+            id.as_raw(),
+            envelope.frame.data(),
+        ))?)
+    }
+}
@@ -1 +1,3 @@
-pub mod channel;
\ No newline at end of file
+pub mod can;
+pub mod channel;
+pub mod sd_log;
@@ -0,0 +1,160 @@
+use core::fmt::Write;
+
+use defmt::{info, warn};
+use embassy_stm32::{gpio::Output, mode::Blocking, spi, spi::Spi, time::Hertz};
+use embassy_time::Delay;
+use embedded_hal_bus::spi::ExclusiveDevice;
+use embedded_sdmmc::{Mode, RawFile, SdCard, TimeSource, Timestamp, VolumeIdx, VolumeManager};
+use heapless::String;
+use thiserror::Error;
+
+/// SD cards are written by blocks
+const BLOCK_SIZE: usize = 512;
+
+/// SPI clock once the card is initialized, which is done at 400 kHz at most
+const SPI_FREQUENCY: Hertz = Hertz(16_000_000);
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("No SD card, or the card did not answer")]
+    Card,
+    #[error("No FAT volume on the card")]
+    Volume,
+    #[error("Could not create the log file")]
+    File,
+}
+
+/// No RTC: files are dated at a fixed time
+pub struct FixedTime;
+
+impl TimeSource for FixedTime {
+    fn get_timestamp(&self) -> Timestamp {
+        Timestamp {
+            year_since_1970: 55,
+            zero_indexed_month: 0,
+            zero_indexed_day: 0,
+            hours: 0,
+            minutes: 0,
+            seconds: 0,
+        }
+    }
+}
+
+type SdSpi = ExclusiveDevice<Spi<'static, Blocking>, Output<'static>, Delay>;
+type SdVolumeManager = VolumeManager<SdCard<SdSpi, Delay>, FixedTime>;
+
+/// Log file on a FAT formatted SD card, a new one each boot: LOG0001.BIN, LOG0002.BIN, ...
+pub struct SdLog {
+    volume_mgr: SdVolumeManager,
+    file: RawFile,
+
+    block: [u8; BLOCK_SIZE],
+    block_len: usize,
+    err_cnt: usize,
+}
+
+impl SdLog {
+    /// `spi` must be clocked at 400 kHz or less for the card initialization
+    pub fn init(spi: Spi<'static, Blocking>, cs: Output<'static>) -> Result<Self, Error> {
+        let sd_spi = ExclusiveDevice::new(spi, cs, Delay).unwrap();
+        let sdcard = SdCard::new(sd_spi, Delay);
+
+        let size = sdcard.num_bytes().map_err(|_| Error::Card)?;
+        info!("SD | {} MiB card", size / (1024 * 1024));
+
+        let mut config = spi::Config::default();
+        config.frequency = SPI_FREQUENCY;
+        sdcard
+            .spi(|dev| dev.bus_mut().set_config(&config))
+            .map_err(|_| Error::Card)?;
+
+        let volume_mgr = VolumeManager::new(sdcard, FixedTime);
+        let volume = volume_mgr
+            .open_raw_volume(VolumeIdx(0))
+            .map_err(|_| Error::Volume)?;
+        let dir = volume_mgr
+            .open_root_dir(volume)
+            .map_err(|_| Error::Volume)?;
+
+        // Next index after the logs already on the card
+        let mut index = 0;
+        volume_mgr
+            .iterate_dir(dir, |entry| {
+                if let Some(i) = Self::log_index(entry.name.base_name(), entry.name.extension()) {
+                    index = index.max(i);
+                }
+            })
+            .map_err(|_| Error::Volume)?;
+
+        let mut name: String<12> = String::new();
+        write!(name, "LOG{:04}.BIN", (index + 1) % 10000).unwrap();
+
+        let file = volume_mgr
+            .open_file_in_dir(dir, name.as_str(), Mode::ReadWriteCreate)
+            .map_err(|_| Error::File)?;
+        info!("SD | Logging to {}", name.as_str());
+
+        Ok(SdLog {
+            volume_mgr,
+            file,
+            block: [0; BLOCK_SIZE],
+            block_len: 0,
+            err_cnt: 0,
+        })
+    }
+
+    /// Index of a "LOGnnnn.BIN" file
+    fn log_index(base_name: &[u8], extension: &[u8]) -> Option<u32> {
+        if extension != b"BIN" {
+            return None;
+        }
+
+        core::str::from_utf8(base_name.strip_prefix(b"LOG")?)
+            .ok()?
+            .parse()
+            .ok()
+    }
+
+    /// Writes or buffers `data`, a whole block at a time
+    pub fn write(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            let n = data.len().min(BLOCK_SIZE - self.block_len);
+            self.block[self.block_len..self.block_len + n].copy_from_slice(&data[..n]);
+            self.block_len += n;
+            data = &data[n..];
+
+            if self.block_len == BLOCK_SIZE {
+                self.write_block();
+            }
+        }
+    }
+
+    fn write_block(&mut self) {
+        if let Err(err) = self
+            .volume_mgr
+            .write(self.file, &self.block[..self.block_len])
+        {
+            self.err_cnt += 1;
+            warn!("SD | Write failed: {:?}", err);
+        }
+        self.block_len = 0;
+    }
+
+    /// Writes the buffered data & updates the file size in the directory, so that the file is
+    /// complete if the card is removed or the power lost
+    pub fn flush(&mut self) {
+        if self.block_len > 0 {
+            self.write_block();
+        }
+
+        if let Err(err) = self.volume_mgr.flush_file(self.file) {
+            self.err_cnt += 1;
+            warn!("SD | Flush failed: {:?}", err);
+        }
+    }
+
+    /// Failed writes or flushes
+    pub fn error_count(&self) -> usize {
+        self.err_cnt
+    }
+}
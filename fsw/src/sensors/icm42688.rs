@@ -6,6 +6,7 @@ use defmt::{info, warn};
 use embassy_stm32::mode::Blocking;
 use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, signal::Signal};
 use embassy_time::{Instant, Timer};
+use nalgebra::Vector3;
 use regs::{
     AccelConfigStatic2, AccelConfigStatic3, AccelConfigStatic4, AccelMode, AddrBank0, AddrBank1,
     AddrBank2, GyroConfigStatic2, GyroConfigStatic3, GyroConfigStatic4, GyroConfigStatic5,
@@ -549,15 +550,28 @@ impl Icm42688 {
             i16::from_be_bytes(buf[12..14].try_into().unwrap()),
         ];
 
+        let accel_m_s2 = Vector3::from(
+            self.convert_accel(&raw_accel).map(|a| a.get::<meter_per_second_squared>()),
+        );
+        let angvel_rad_s = Vector3::from(
+            self.convert_gyro(&raw_angvel)
+                .map(|w| w.get::<degree_per_second>().to_radians()),
+        );
+
         Ts::from_microseconds(
             drdy_ts.as_micros(),
             Icm42688Sample {
                 data: ImuSensorSample {
-                    accel: self.convert_accel(&raw_accel),
-                    ang_vel: self.convert_gyro(&raw_angvel),
-                    temperature: Some(self.convert_temperature(raw_temp)),
+                    accel_m_s2,
+                    angvel_rad_s,
+                    temperature_degc: Some(
+                        self.convert_temperature(raw_temp).get::<degree_celsius>(),
+                    ),
                     int_latency: crater_gnc::DurationU64::micros(latency.as_micros()).into(),
                     overrun_count,
+                    // Only the sampled rates are read: increments integrated from them would
+                    // not carry more information
+                    delta: None,
                 },
             },
         )
@@ -1,2 +1,3 @@
 pub mod bmp390;
-pub mod icm42688;
\ No newline at end of file
+pub mod icm42688;
+pub mod ublox;
\ No newline at end of file
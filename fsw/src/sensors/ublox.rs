@@ -0,0 +1,320 @@
+use crater_gnc::{common::Ts, datatypes::sensors::GnssSensorSample, mav_crater::GnssFixType};
+use defmt::{debug, warn};
+use embassy_time::{Duration, Instant, with_timeout};
+use embedded_io_async::{Read, Write};
+use nalgebra::Vector3;
+use thiserror::Error;
+
+const SYNC: [u8; 2] = [0xB5, 0x62];
+
+/// Longest payload parsed, NAV-PVT. Longer messages are discarded.
+const MAX_PAYLOAD_LEN: usize = 100;
+const NAV_PVT_LEN: usize = 92;
+
+const ACK_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Message (class, id) pairs
+#[allow(unused)]
+pub mod msg {
+    pub const ACK_CLASS: u8 = 0x05;
+    pub const ACK_NAK: (u8, u8) = (0x05, 0x00);
+    pub const ACK_ACK: (u8, u8) = (0x05, 0x01);
+    pub const CFG_VALSET: (u8, u8) = (0x06, 0x8A);
+    pub const NAV_PVT: (u8, u8) = (0x01, 0x07);
+}
+
+/// Configuration keys, for the M9 & later generations
+#[allow(unused)]
+pub mod keys {
+    pub const CFG_RATE_MEAS: u32 = 0x30210001;
+    pub const CFG_NAVSPG_DYNMODEL: u32 = 0x20110021;
+    pub const CFG_UART1OUTPROT_NMEA: u32 = 0x10740002;
+    pub const CFG_MSGOUT_UBX_NAV_PVT_UART1: u32 = 0x20910007;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum DynModel {
+    Portable = 0,
+    Stationary = 2,
+    Automotive = 4,
+    Airborne1g = 6,
+    Airborne2g = 7,
+    Airborne4g = 8,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("UART read or write failed")]
+    Uart,
+    #[error("Configuration rejected by the receiver")]
+    NotAcknowledged,
+    #[error("No answer from the receiver")]
+    Timeout,
+}
+
+pub struct Config {
+    /// Navigation solution period
+    pub meas_period_ms: u16,
+    pub dyn_model: DynModel,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            meas_period_ms: 100,
+            dyn_model: DynModel::Airborne4g,
+        }
+    }
+}
+
+/// Fletcher checksum of a frame, from the class to the end of the payload
+fn checksum(parts: &[&[u8]]) -> [u8; 2] {
+    let mut ck = [0u8; 2];
+    for &byte in parts.iter().flat_map(|p| p.iter()) {
+        ck[0] = ck[0].wrapping_add(byte);
+        ck[1] = ck[1].wrapping_add(ck[0]);
+    }
+    ck
+}
+
+pub struct UbxFrame<'a> {
+    pub class: u8,
+    pub id: u8,
+    pub payload: &'a [u8],
+}
+
+/// Byte by byte UBX frame parser, resynchronizing on the sync chars after invalid frames
+pub struct UbxParser {
+    /// Class, id, length, payload & checksum of the current frame
+    buf: [u8; 4 + MAX_PAYLOAD_LEN + 2],
+    /// Bytes received in the current frame, including the sync chars
+    pos: usize,
+}
+
+impl UbxParser {
+    pub fn new() -> Self {
+        Self {
+            buf: [0; 4 + MAX_PAYLOAD_LEN + 2],
+            pos: 0,
+        }
+    }
+
+    fn payload_len(&self) -> usize {
+        u16::from_le_bytes([self.buf[2], self.buf[3]]) as usize
+    }
+
+    /// Returns true once a frame with a valid checksum is complete
+    pub fn push(&mut self, byte: u8) -> bool {
+        match self.pos {
+            0 => {
+                self.pos = (byte == SYNC[0]) as usize;
+                return false;
+            }
+            1 => {
+                self.pos = match byte {
+                    b if b == SYNC[1] => 2,
+                    b if b == SYNC[0] => 1,
+                    _ => 0,
+                };
+                return false;
+            }
+            _ => {}
+        }
+
+        let i = self.pos - 2;
+        self.buf[i] = byte;
+        self.pos += 1;
+
+        if i < 4 {
+            if i == 3 && self.payload_len() > MAX_PAYLOAD_LEN {
+                self.pos = 0;
+            }
+            return false;
+        }
+
+        let len = self.payload_len();
+        if i < len + 5 {
+            return false;
+        }
+
+        self.pos = 0;
+        if checksum(&[&self.buf[..4 + len]]) != self.buf[4 + len..6 + len] {
+            warn!(
+                "UBLOX | Bad checksum on message {=u8:x} {=u8:x}",
+                self.buf[0], self.buf[1]
+            );
+            return false;
+        }
+        true
+    }
+
+    /// Latest complete frame
+    pub fn frame(&self) -> UbxFrame<'_> {
+        UbxFrame {
+            class: self.buf[0],
+            id: self.buf[1],
+            payload: &self.buf[4..4 + self.payload_len()],
+        }
+    }
+}
+
+impl Default for UbxParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Decodes a NAV-PVT payload. Fixes without the gnssFixOK flag are reported as `NoFix`.
+pub fn decode_nav_pvt(payload: &[u8]) -> Option<GnssSensorSample> {
+    if payload.len() != NAV_PVT_LEN {
+        return None;
+    }
+
+    let u32_at = |i: usize| u32::from_le_bytes(payload[i..i + 4].try_into().unwrap());
+    let i32_at = |i: usize| i32::from_le_bytes(payload[i..i + 4].try_into().unwrap());
+
+    let gnss_fix_ok = payload[21] & 0x01 != 0;
+    let fix_type = match payload[20] {
+        _ if !gnss_fix_ok => GnssFixType::NoFix,
+        1 => GnssFixType::DeadReckoning,
+        2 => GnssFixType::Fix2d,
+        3 => GnssFixType::Fix3d,
+        4 => GnssFixType::GnssDeadReckoning,
+        5 => GnssFixType::TimeOnly,
+        _ => GnssFixType::NoFix,
+    };
+
+    Some(GnssSensorSample {
+        fix_type,
+        num_sats: payload[23],
+        lon_deg_e7: i32_at(24),
+        lat_deg_e7: i32_at(28),
+        alt_msl_m: i32_at(36) as f32 * 1e-3,
+        vel_n_m_s: Vector3::new(i32_at(48), i32_at(52), i32_at(56)).map(|v| v as f32 * 1e-3),
+        h_acc_m: u32_at(40) as f32 * 1e-3,
+        v_acc_m: u32_at(44) as f32 * 1e-3,
+        speed_acc_m_s: u32_at(68) as f32 * 1e-3,
+    })
+}
+
+/// u-blox GNSS receiver, outputting UBX NAV-PVT on its UART
+pub struct Ublox<U> {
+    uart: U,
+    parser: UbxParser,
+
+    rx_buf: [u8; 64],
+    rx_len: usize,
+    rx_pos: usize,
+}
+
+impl<U: Read + Write> Ublox<U> {
+    pub async fn init(uart: U, config: Config) -> Result<Self, Error> {
+        let mut ublox = Self {
+            uart,
+            parser: UbxParser::new(),
+            rx_buf: [0; 64],
+            rx_len: 0,
+            rx_pos: 0,
+        };
+
+        let mut remaining_attempts = 3;
+
+        loop {
+            match ublox.configure(&config).await {
+                Ok(()) => break,
+                Err(err) if remaining_attempts == 0 => return Err(err),
+                Err(_) => {
+                    debug!("UBLOX | Configuration not acknowledged. Retrying");
+                    remaining_attempts -= 1;
+                }
+            }
+        }
+
+        Ok(ublox)
+    }
+
+    /// Sets the navigation rate & model, and the UART output to NAV-PVT only, in RAM
+    async fn configure(&mut self, config: &Config) -> Result<(), Error> {
+        let mut payload = [0u8; 4 + 4 * 5 + 2];
+        // Version 0, RAM layer
+        payload[1] = 0x01;
+
+        let mut len = 4;
+        let mut set = |key: u32, value: &[u8]| {
+            payload[len..len + 4].copy_from_slice(&key.to_le_bytes());
+            payload[len + 4..len + 4 + value.len()].copy_from_slice(value);
+            len += 4 + value.len();
+        };
+
+        set(keys::CFG_RATE_MEAS, &config.meas_period_ms.to_le_bytes());
+        set(keys::CFG_NAVSPG_DYNMODEL, &[config.dyn_model as u8]);
+        set(keys::CFG_UART1OUTPROT_NMEA, &[0]);
+        set(keys::CFG_MSGOUT_UBX_NAV_PVT_UART1, &[1]);
+
+        self.send(msg::CFG_VALSET, &payload[..len]).await?;
+
+        with_timeout(ACK_TIMEOUT, self.wait_ack(msg::CFG_VALSET))
+            .await
+            .map_err(|_| Error::Timeout)?
+    }
+
+    async fn send(&mut self, (class, id): (u8, u8), payload: &[u8]) -> Result<(), Error> {
+        let len = (payload.len() as u16).to_le_bytes();
+        let header = [SYNC[0], SYNC[1], class, id, len[0], len[1]];
+        let ck = checksum(&[&header[2..], payload]);
+
+        for part in [&header[..], payload, &ck[..]] {
+            self.uart.write_all(part).await.map_err(|_| Error::Uart)?;
+        }
+        self.uart.flush().await.map_err(|_| Error::Uart)
+    }
+
+    async fn next_frame(&mut self) -> Result<UbxFrame<'_>, Error> {
+        loop {
+            if self.rx_pos == self.rx_len {
+                self.rx_len = self
+                    .uart
+                    .read(&mut self.rx_buf)
+                    .await
+                    .map_err(|_| Error::Uart)?;
+                self.rx_pos = 0;
+                continue;
+            }
+
+            let byte = self.rx_buf[self.rx_pos];
+            self.rx_pos += 1;
+
+            if self.parser.push(byte) {
+                return Ok(self.parser.frame());
+            }
+        }
+    }
+
+    async fn wait_ack(&mut self, (class, id): (u8, u8)) -> Result<(), Error> {
+        loop {
+            let frame = self.next_frame().await?;
+
+            if frame.class == msg::ACK_CLASS && frame.payload == [class, id] {
+                return if (frame.class, frame.id) == msg::ACK_ACK {
+                    Ok(())
+                } else {
+                    Err(Error::NotAcknowledged)
+                };
+            }
+        }
+    }
+
+    /// Waits for the next navigation solution. Timestamped at the end of its reception: the
+    /// fix is older by the computation & transmission time of the receiver.
+    pub async fn sample(&mut self) -> Result<Ts<GnssSensorSample>, Error> {
+        loop {
+            let frame = self.next_frame().await?;
+
+            if (frame.class, frame.id) == msg::NAV_PVT {
+                if let Some(sample) = decode_nav_pvt(frame.payload) {
+                    return Ok(Ts::from_microseconds(Instant::now().as_micros(), sample));
+                }
+            }
+        }
+    }
+}
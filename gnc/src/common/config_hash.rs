@@ -0,0 +1,100 @@
+use crate::Duration;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// FNV-1a hash of the flight configuration.
+///
+/// Values are hashed by their little endian representation, so that the hash computed on the
+/// flight computer matches the one computed on the ground for the same configuration.
+#[derive(Debug, Clone)]
+pub struct ConfigHasher {
+    state: u64,
+}
+
+impl Default for ConfigHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConfigHasher {
+    pub const fn new() -> Self {
+        Self {
+            state: FNV_OFFSET_BASIS,
+        }
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.state ^= *b as u64;
+            self.state = self.state.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    pub fn write_u8(&mut self, v: u8) {
+        self.write_bytes(&[v]);
+    }
+
+    pub fn write_u64(&mut self, v: u64) {
+        self.write_bytes(&v.to_le_bytes());
+    }
+
+    pub fn write_f32(&mut self, v: f32) {
+        self.write_bytes(&v.to_le_bytes());
+    }
+
+    pub fn write_duration(&mut self, v: Duration) {
+        self.write_u64(v.0.to_micros());
+    }
+
+    pub fn write_opt_f32(&mut self, v: Option<f32>) {
+        match v {
+            Some(v) => {
+                self.write_u8(1);
+                self.write_f32(v);
+            }
+            None => self.write_u8(0),
+        }
+    }
+
+    /// Separates the sections of the configuration, so that moving a value from one section to
+    /// the next changes the hash
+    pub fn write_section(&mut self, name: &str) {
+        self.write_bytes(name.as_bytes());
+        self.write_u8(0);
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.state
+    }
+}
+
+/// Configuration covered by the configuration hash, which is frozen at arming
+pub trait ConfigHash {
+    fn hash_config(&self, hasher: &mut ConfigHasher);
+
+    fn config_hash(&self) -> u64 {
+        let mut hasher = ConfigHasher::new();
+        self.hash_config(&mut hasher);
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fnv1a() {
+        // Reference values of the 64 bit FNV-1a hash
+        let mut hasher = ConfigHasher::new();
+        assert_eq!(hasher.finish(), 0xcbf29ce484222325);
+        hasher.write_bytes(b"a");
+        assert_eq!(hasher.finish(), 0xaf63dc4c8601ec8c);
+
+        let mut hasher = ConfigHasher::new();
+        hasher.write_bytes(b"foobar");
+        assert_eq!(hasher.finish(), 0x85944171f73967e8);
+    }
+}
@@ -0,0 +1,66 @@
+use crate::{Duration, Instant};
+
+/// Confirms a condition once it held continuously over a time window, so that short excursions
+/// (eg. vibrations, sensor glitches) do not trigger it.
+///
+/// Unlike counting consecutive samples, the window does not depend on the sample rate.
+#[derive(Debug, Clone)]
+pub struct ConfirmationWindow {
+    window: Duration,
+    since: Option<Instant>,
+}
+
+impl ConfirmationWindow {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            since: None,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.since = None;
+    }
+
+    /// Whether `condition` held at every update from at least `window` before `t` up to `t`
+    pub fn update(&mut self, t: Instant, condition: bool) -> bool {
+        if !condition {
+            self.since = None;
+            return false;
+        }
+
+        let since = *self.since.get_or_insert(t);
+        t.0 - since.0 >= self.window.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DurationU64, InstantU64};
+
+    fn t_ms(ms: u64) -> Instant {
+        Instant(InstantU64::from_ticks(ms * 1000))
+    }
+
+    #[test]
+    fn test_confirmation_window() {
+        let mut window = ConfirmationWindow::new(DurationU64::millis(50).into());
+
+        assert!(!window.update(t_ms(0), true));
+        assert!(!window.update(t_ms(40), true));
+        // Interrupted: restarts from the next sample
+        assert!(!window.update(t_ms(45), false));
+        assert!(!window.update(t_ms(50), true));
+        assert!(!window.update(t_ms(95), true));
+        assert!(window.update(t_ms(100), true));
+        assert!(window.update(t_ms(105), true));
+
+        window.reset();
+        assert!(!window.update(t_ms(110), true));
+
+        // A zero window confirms at the first sample
+        let mut window = ConfirmationWindow::new(DurationU64::millis(0).into());
+        assert!(window.update(t_ms(0), true));
+    }
+}
@@ -0,0 +1,219 @@
+use core::f32::consts::PI;
+
+use nalgebra::Vector3;
+
+use crate::{
+    Duration, DurationU64, Instant,
+    common::{
+        Ts,
+        config_hash::{ConfigHash, ConfigHasher},
+    },
+    datatypes::{
+        sensors::ImuSensorSample,
+        version::{InterfaceVersion, Versioned},
+    },
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct ImuDecimatorConfig {
+    /// Period of the decimated output, ie. the navigation period
+    pub output_period: Duration,
+    /// Cutoff of the first order low-pass filter applied to the rates, at the input sample rate.
+    /// No filtering if None.
+    pub lpf_cutoff_hz: Option<f32>,
+}
+
+impl ConfigHash for ImuDecimatorConfig {
+    fn hash_config(&self, hasher: &mut ConfigHasher) {
+        hasher.write_section("imu_decimator");
+        hasher.write_duration(self.output_period);
+        hasher.write_opt_f32(self.lpf_cutoff_hz);
+    }
+}
+
+/// Inertial increments over a navigation period
+#[derive(Debug, Clone)]
+pub struct ImuIncrement {
+    pub dt: Duration,
+    /// Rotation vector of the body over the period, coning compensated
+    pub delta_angle_rad: Vector3<f32>,
+    /// Velocity increment in the body frame at the beginning of the period, rotation & sculling
+    /// compensated
+    pub delta_velocity_m_s: Vector3<f32>,
+
+    /// Low-pass filtered rates, at the end of the period
+    pub angvel_rad_s: Vector3<f32>,
+    pub accel_m_s2: Vector3<f32>,
+
+    /// Number of IMU samples integrated over the period
+    pub num_samples: u16,
+}
+
+impl Versioned for ImuIncrement {
+    const NAME: &'static str = "ImuIncrement";
+    const VERSION: InterfaceVersion = InterfaceVersion::new(1, 0);
+}
+
+/// Decimates high-rate IMU samples down to the navigation rate.
+///
+/// The angular rates & specific forces are integrated into delta-angles & delta-velocities,
+/// with first order coning & sculling compensation, so that high frequency motion between two
+/// navigation steps is not aliased nor lost. The rates themselves are low-pass filtered, for
+/// consumers that need them directly.
+#[derive(Debug, Clone)]
+pub struct ImuDecimator {
+    config: ImuDecimatorConfig,
+
+    period_start: Option<Instant>,
+    last_sample: Option<Instant>,
+
+    alpha: Vector3<f32>,
+    upsilon: Vector3<f32>,
+    coning: Vector3<f32>,
+    sculling: Vector3<f32>,
+    num_samples: u16,
+
+    angvel_lpf: Option<Vector3<f32>>,
+    accel_lpf: Option<Vector3<f32>>,
+}
+
+impl ImuDecimator {
+    pub fn new(config: ImuDecimatorConfig) -> Self {
+        Self {
+            config,
+            period_start: None,
+            last_sample: None,
+            alpha: Vector3::zeros(),
+            upsilon: Vector3::zeros(),
+            coning: Vector3::zeros(),
+            sculling: Vector3::zeros(),
+            num_samples: 0,
+            angvel_lpf: None,
+            accel_lpf: None,
+        }
+    }
+
+    fn seconds(d: DurationU64) -> f32 {
+        d.to_micros() as f32 * 1e-6
+    }
+
+    fn lowpass(
+        &self,
+        state: Option<Vector3<f32>>,
+        input: &Vector3<f32>,
+        dt_s: f32,
+    ) -> Vector3<f32> {
+        match (self.config.lpf_cutoff_hz, state) {
+            (Some(cutoff_hz), Some(state)) => {
+                let wc_dt = 2.0 * PI * cutoff_hz * dt_s;
+                state + (input - state) * (wc_dt / (1.0 + wc_dt))
+            }
+            _ => *input,
+        }
+    }
+
+    /// Integrates a sample. Returns the increments over the last navigation period, once the
+    /// period is complete.
+    pub fn push(&mut self, sample: &Ts<ImuSensorSample>) -> Option<Ts<ImuIncrement>> {
+        let t = sample.t;
+
+        let Some(last_sample) = self.last_sample else {
+            // Nothing to integrate until the interval to the next sample is known
+            self.period_start = Some(t);
+            self.last_sample = Some(t);
+            self.angvel_lpf = Some(sample.v.angvel_rad_s);
+            self.accel_lpf = Some(sample.v.accel_m_s2);
+            return None;
+        };
+
+        if t.0 <= last_sample.0 {
+            return None;
+        }
+        let dt_s = Self::seconds(t.0 - last_sample.0);
+        self.last_sample = Some(t);
+
+        // Prefer the increments integrated by the sensor, if available
+        let (d_alpha, d_upsilon) = match &sample.v.delta {
+            Some(delta) => (delta.delta_angle_rad, delta.delta_velocity_m_s),
+            None => (sample.v.angvel_rad_s * dt_s, sample.v.accel_m_s2 * dt_s),
+        };
+
+        self.coning += 0.5 * self.alpha.cross(&d_alpha);
+        self.sculling += 0.5 * (self.alpha.cross(&d_upsilon) + self.upsilon.cross(&d_alpha));
+        self.alpha += d_alpha;
+        self.upsilon += d_upsilon;
+        self.num_samples = self.num_samples.saturating_add(1);
+
+        self.angvel_lpf = Some(self.lowpass(self.angvel_lpf, &sample.v.angvel_rad_s, dt_s));
+        self.accel_lpf = Some(self.lowpass(self.accel_lpf, &sample.v.accel_m_s2, dt_s));
+
+        let period_start = self.period_start.unwrap_or(last_sample);
+        if t.0 - period_start.0 < self.config.output_period.0 {
+            return None;
+        }
+
+        let increment = ImuIncrement {
+            dt: (t.0 - period_start.0).into(),
+            delta_angle_rad: self.alpha + self.coning,
+            delta_velocity_m_s: self.upsilon
+                + 0.5 * self.alpha.cross(&self.upsilon)
+                + self.sculling,
+            angvel_rad_s: self.angvel_lpf.unwrap_or(Vector3::zeros()),
+            accel_m_s2: self.accel_lpf.unwrap_or(Vector3::zeros()),
+            num_samples: self.num_samples,
+        };
+
+        self.period_start = Some(t);
+        self.alpha = Vector3::zeros();
+        self.upsilon = Vector3::zeros();
+        self.coning = Vector3::zeros();
+        self.sculling = Vector3::zeros();
+        self.num_samples = 0;
+
+        Some(Ts::new(t, increment))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Timestamped;
+
+    fn sample(t_us: u64, angvel_rad_s: Vector3<f32>) -> Ts<ImuSensorSample> {
+        Timestamped::from_microseconds(
+            t_us,
+            ImuSensorSample {
+                accel_m_s2: Vector3::new(0.0, 0.0, -9.81),
+                angvel_rad_s,
+                temperature_degc: None,
+                int_latency: DurationU64::micros(0).into(),
+                overrun_count: 0,
+                delta: None,
+            },
+        )
+    }
+
+    #[test]
+    fn test_decimation_8khz_to_200hz() {
+        let mut decimator = ImuDecimator::new(ImuDecimatorConfig {
+            output_period: DurationU64::millis(5).into(),
+            lpf_cutoff_hz: Some(100.0),
+        });
+
+        let angvel = Vector3::new(0.0, 0.0, 1.0);
+        let mut outputs = alloc::vec::Vec::new();
+        for i in 0..=80 {
+            if let Some(out) = decimator.push(&sample(i * 125, angvel)) {
+                outputs.push(out);
+            }
+        }
+
+        assert_eq!(outputs.len(), 2);
+        let inc = &outputs[0].v;
+        assert_eq!(inc.num_samples, 40);
+        assert_eq!(inc.dt.0, DurationU64::millis(5));
+        // Constant rate about a fixed axis: no coning
+        assert!((inc.delta_angle_rad - Vector3::new(0.0, 0.0, 0.005)).norm() < 1e-6);
+        assert!((inc.delta_velocity_m_s - Vector3::new(0.0, 0.0, -0.04905)).norm() < 1e-5);
+    }
+}
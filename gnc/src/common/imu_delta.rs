@@ -0,0 +1,63 @@
+use nalgebra::Vector3;
+
+use crate::{Instant, datatypes::sensors::ImuDeltaSample};
+
+/// Integrates the rates sampled by an IMU into delta-angles & delta-velocities, using the
+/// trapezoidal rule between consecutive samples.
+#[derive(Debug, Clone, Default)]
+pub struct ImuDeltaIntegrator {
+    last: Option<(Instant, Vector3<f32>, Vector3<f32>)>,
+}
+
+impl ImuDeltaIntegrator {
+    /// Integrates up to the provided sample. Returns None for the first sample, or if the
+    /// timestamp did not increase.
+    pub fn integrate(
+        &mut self,
+        t: Instant,
+        angvel_rad_s: &Vector3<f32>,
+        accel_m_s2: &Vector3<f32>,
+    ) -> Option<ImuDeltaSample> {
+        let last = self.last.replace((t, *angvel_rad_s, *accel_m_s2));
+        let (t_last, angvel_last, accel_last) = last?;
+
+        if t.0 <= t_last.0 {
+            return None;
+        }
+        let dt = t.0 - t_last.0;
+        let half_dt_s = dt.to_micros() as f32 * 0.5e-6;
+
+        Some(ImuDeltaSample {
+            dt: dt.into(),
+            delta_angle_rad: (angvel_last + angvel_rad_s) * half_dt_s,
+            delta_velocity_m_s: (accel_last + accel_m_s2) * half_dt_s,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InstantU64;
+
+    #[test]
+    fn test_trapezoidal_increments() {
+        let mut integrator = ImuDeltaIntegrator::default();
+        let t = |us: u64| Instant(InstantU64::from_ticks(us));
+
+        let accel = Vector3::new(0.0, 0.0, -9.81);
+        assert!(
+            integrator
+                .integrate(t(0), &Vector3::zeros(), &accel)
+                .is_none()
+        );
+
+        // Rate ramping up linearly: the trapezoidal rule is exact
+        let delta = integrator
+            .integrate(t(1000), &Vector3::new(2.0, 0.0, 0.0), &accel)
+            .unwrap();
+        assert_eq!(delta.dt.0.to_micros(), 1000);
+        assert!((delta.delta_angle_rad - Vector3::new(0.001, 0.0, 0.0)).norm() < 1e-7);
+        assert!((delta.delta_velocity_m_s - Vector3::new(0.0, 0.0, -0.00981)).norm() < 1e-7);
+    }
+}
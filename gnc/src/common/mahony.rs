@@ -0,0 +1,185 @@
+use nalgebra::{Matrix3, Rotation3, UnitQuaternion, Vector3};
+
+use crate::common::config_hash::{ConfigHash, ConfigHasher};
+
+const G_M_S2: f32 = 9.81;
+
+#[derive(Debug, Clone, Copy)]
+pub struct MahonyConfig {
+    /// Proportional gain of the gravity direction error [rad/s]
+    pub kp_accel: f32,
+    /// Proportional gain of the heading error [rad/s]
+    pub kp_mag: f32,
+    /// Integral gain, estimating the gyro bias [rad/s^2]
+    pub ki: f32,
+    /// The accelerometer is only used while its norm is within this band around g [m/s^2]
+    pub accel_gate_m_s2: f32,
+}
+
+impl Default for MahonyConfig {
+    fn default() -> Self {
+        MahonyConfig {
+            kp_accel: 1.0,
+            kp_mag: 0.5,
+            ki: 0.05,
+            accel_gate_m_s2: 1.0,
+        }
+    }
+}
+
+impl ConfigHash for MahonyConfig {
+    fn hash_config(&self, hasher: &mut ConfigHasher) {
+        hasher.write_section("mahony");
+        hasher.write_f32(self.kp_accel);
+        hasher.write_f32(self.kp_mag);
+        hasher.write_f32(self.ki);
+        hasher.write_f32(self.accel_gate_m_s2);
+    }
+}
+
+/// Mahony complementary filter on the attitude, from the gyro, the accelerometer & the
+/// magnetometer.
+///
+/// The gyro rates are corrected towards the attitude observed by the accelerometer (gravity
+/// direction) & the magnetometer (heading only, so that magnetic disturbances do not tilt the
+/// estimate). The integral of the correction estimates the gyro bias.
+#[derive(Debug, Clone)]
+pub struct Mahony {
+    config: MahonyConfig,
+
+    quat_nb: Option<UnitQuaternion<f32>>,
+    /// Integral of the correction, opposite of the gyro bias
+    integral: Vector3<f32>,
+}
+
+impl Mahony {
+    pub fn new(config: MahonyConfig) -> Self {
+        Self {
+            config,
+            quat_nb: None,
+            integral: Vector3::zeros(),
+        }
+    }
+
+    /// Attitude, once initialized by the first update at rest with a magnetometer sample
+    pub fn quat_nb(&self) -> Option<UnitQuaternion<f32>> {
+        self.quat_nb
+    }
+
+    pub fn gyro_bias_rad_s(&self) -> Vector3<f32> {
+        -self.integral
+    }
+
+    /// Propagates the attitude by `dt_s` with the gyro rates, corrected with the accelerometer
+    /// if `use_accel` & with the magnetometer if a sample is given.
+    ///
+    /// Returns whether the accelerometer was used.
+    pub fn update(
+        &mut self,
+        angvel_rad_s: &Vector3<f32>,
+        accel_m_s2: &Vector3<f32>,
+        mag_b: Option<&Vector3<f32>>,
+        use_accel: bool,
+        dt_s: f32,
+    ) -> bool {
+        let use_accel =
+            use_accel && (accel_m_s2.norm() - G_M_S2).abs() <= self.config.accel_gate_m_s2;
+
+        let Some(quat_nb) = self.quat_nb else {
+            // Triad of the gravity & magnetic field directions: needs both
+            if let Some(mag_b) = mag_b
+                && use_accel
+            {
+                self.quat_nb = triad(accel_m_s2, mag_b);
+            }
+            return false;
+        };
+
+        // Rotation errors, unweighted for the integral
+        let mut error = Vector3::zeros();
+        let mut correction = Vector3::zeros();
+
+        if use_accel {
+            // At rest the accelerometer senses the opposite of gravity
+            let up_b = quat_nb.inverse_transform_vector(&-Vector3::z());
+            let accel_error = accel_m_s2.normalize().cross(&up_b);
+            error += accel_error;
+            correction += self.config.kp_accel * accel_error;
+        }
+
+        if let Some(mag_b) = mag_b.and_then(|m| m.try_normalize(f32::EPSILON)) {
+            // Reference with the inclination of the measurement, pointing north
+            let mag_n = quat_nb.transform_vector(&mag_b);
+            let mag_ref_n = Vector3::new(libm::hypotf(mag_n.x, mag_n.y), 0.0, mag_n.z);
+            let mag_ref_b = quat_nb.inverse_transform_vector(&mag_ref_n);
+            let mag_error = mag_b.cross(&mag_ref_b);
+            error += mag_error;
+            correction += self.config.kp_mag * mag_error;
+        }
+
+        self.integral += self.config.ki * error * dt_s;
+
+        let angvel = angvel_rad_s + correction + self.integral;
+        self.quat_nb = Some(quat_nb * UnitQuaternion::from_scaled_axis(angvel * dt_s));
+
+        use_accel
+    }
+}
+
+/// Attitude from the specific force at rest & the magnetic field, in the body frame
+fn triad(accel_m_s2: &Vector3<f32>, mag_b: &Vector3<f32>) -> Option<UnitQuaternion<f32>> {
+    let down_b = (-accel_m_s2).try_normalize(f32::EPSILON)?;
+    let east_b = down_b.cross(mag_b).try_normalize(f32::EPSILON)?;
+    let north_b = east_b.cross(&down_b);
+
+    // Rows: the NED axes in the body frame
+    let rot_nb = Matrix3::from_rows(&[north_b.transpose(), east_b.transpose(), down_b.transpose()]);
+    Some(UnitQuaternion::from_rotation_matrix(
+        &Rotation3::from_matrix_unchecked(rot_nb),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Field with a 60° inclination, pointing north
+    fn mag_n() -> Vector3<f32> {
+        Vector3::new(0.25, 0.0, 0.43)
+    }
+
+    fn angle_deg(a: &UnitQuaternion<f32>, b: &UnitQuaternion<f32>) -> f32 {
+        a.angle_to(b).to_degrees()
+    }
+
+    #[test]
+    fn test_mahony_convergence() {
+        let truth = UnitQuaternion::from_euler_angles(0.2, -0.1, 1.0);
+        let accel_b = truth.inverse_transform_vector(&Vector3::new(0.0, 0.0, -G_M_S2));
+        let mag_b = truth.inverse_transform_vector(&mag_n());
+        let bias = Vector3::new(0.01, -0.02, 0.015);
+
+        let mut mahony = Mahony::new(MahonyConfig::default());
+
+        // Initialized by the triad
+        assert!(mahony.quat_nb().is_none());
+        mahony.update(&bias, &accel_b, None, true, 0.01);
+        assert!(mahony.quat_nb().is_none());
+        mahony.update(&bias, &accel_b, Some(&mag_b), true, 0.01);
+        assert!(angle_deg(&mahony.quat_nb().unwrap(), &truth) < 0.01);
+
+        // Pulled back to the truth after a large gyro error, and the bias is estimated
+        mahony.update(&Vector3::new(20.0, 0.0, 0.0), &accel_b, None, false, 0.01);
+        assert!(angle_deg(&mahony.quat_nb().unwrap(), &truth) > 10.0);
+
+        for _ in 0..20000 {
+            assert!(mahony.update(&bias, &accel_b, Some(&mag_b), true, 0.01));
+        }
+        assert!(angle_deg(&mahony.quat_nb().unwrap(), &truth) < 0.1);
+        assert!((mahony.gyro_bias_rad_s() - bias).norm() < 1e-3);
+
+        // Accelerations away from g are rejected
+        let accel_thrust_b = accel_b * 3.0;
+        assert!(!mahony.update(&bias, &accel_thrust_b, Some(&mag_b), true, 0.01));
+    }
+}
@@ -1,3 +1,10 @@
+pub mod config_hash;
+pub mod confirmation;
+pub mod imu_decimator;
+pub mod imu_delta;
+pub mod mahony;
+pub mod pid;
+pub mod power_monitor;
 mod timestamped;
 
 pub use timestamped::Timestamped;
@@ -0,0 +1,119 @@
+use crate::common::config_hash::ConfigHasher;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PidGains {
+    pub kp: f32,
+    pub ki: f32,
+    pub kd: f32,
+}
+
+impl PidGains {
+    pub fn hash_config(&self, hasher: &mut ConfigHasher) {
+        hasher.write_f32(self.kp);
+        hasher.write_f32(self.ki);
+        hasher.write_f32(self.kd);
+    }
+}
+
+/// PID controller with a saturated output.
+///
+/// The derivative acts on the measurement, so that setpoint steps do not kick the output. The
+/// integral is frozen while the output saturates in the direction of the error (conditional
+/// integration), so that it does not wind up.
+#[derive(Debug, Clone)]
+pub struct Pid {
+    gains: PidGains,
+    output_limit: f32,
+
+    integral: f32,
+    last_measurement: Option<f32>,
+}
+
+impl Pid {
+    /// The output is limited to +- `output_limit`
+    pub fn new(gains: PidGains, output_limit: f32) -> Self {
+        Self {
+            gains,
+            output_limit,
+            integral: 0.0,
+            last_measurement: None,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.integral = 0.0;
+        self.last_measurement = None;
+    }
+
+    /// Integral term contribution to the output
+    pub fn integral(&self) -> f32 {
+        self.integral
+    }
+
+    /// Output for `measurement`, `dt_s` after the previous update
+    pub fn update(&mut self, setpoint: f32, measurement: f32, dt_s: f32) -> f32 {
+        let error = setpoint - measurement;
+
+        let derivative = match self.last_measurement {
+            Some(last) if dt_s > 0.0 => -(measurement - last) / dt_s,
+            _ => 0.0,
+        };
+        self.last_measurement = Some(measurement);
+
+        let unintegrated = self.gains.kp * error + self.gains.kd * derivative;
+        let integral = self.integral + self.gains.ki * error * dt_s;
+        let output = unintegrated + integral;
+
+        let saturated_high = output > self.output_limit && error > 0.0;
+        let saturated_low = output < -self.output_limit && error < 0.0;
+        if !saturated_high && !saturated_low {
+            self.integral = integral.clamp(-self.output_limit, self.output_limit);
+        }
+
+        (unintegrated + self.integral).clamp(-self.output_limit, self.output_limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pid_anti_windup() {
+        let mut pid = Pid::new(
+            PidGains {
+                kp: 1.0,
+                ki: 2.0,
+                kd: 0.0,
+            },
+            0.5,
+        );
+
+        // Proportional & integral
+        let out = pid.update(0.1, 0.0, 0.1);
+        assert!((out - (0.1 + 2.0 * 0.1 * 0.1)).abs() < 1e-6);
+
+        // Saturated by a large error: the integral holds
+        let integral = pid.integral();
+        for _ in 0..100 {
+            assert_eq!(pid.update(10.0, 0.0, 0.1), 0.5);
+        }
+        assert_eq!(pid.integral(), integral);
+
+        // Recovers as soon as the error reverses
+        let out = pid.update(0.0, 0.1, 0.1);
+        assert!(out < 0.0);
+
+        // The derivative opposes the measurement changes
+        let mut pid = Pid::new(
+            PidGains {
+                kp: 0.0,
+                ki: 0.0,
+                kd: 0.1,
+            },
+            1.0,
+        );
+        assert_eq!(pid.update(0.0, 0.0, 0.01), 0.0);
+        assert!((pid.update(0.0, 0.01, 0.01) + 0.1).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,175 @@
+use crate::{
+    common::config_hash::{ConfigHash, ConfigHasher},
+    datatypes::power::{PowerAdcSample, PowerStatus},
+    events::Event,
+};
+
+/// Linear calibration of an ADC channel: value = gain * pin voltage + offset
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdcCalibration {
+    pub gain: f32,
+    pub offset: f32,
+}
+
+impl AdcCalibration {
+    pub fn apply(&self, pin_v: f32) -> f32 {
+        self.gain * pin_v + self.offset
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PowerMonitorConfig {
+    /// Battery divider [V/V]
+    pub battery: AdcCalibration,
+    /// Pyro rail divider [V/V]
+    pub pyro: AdcCalibration,
+    /// Current sense amplifier [A/V]
+    pub current: AdcCalibration,
+
+    /// The battery is low below this voltage
+    pub battery_low_v: f32,
+    /// The battery is no longer low above `battery_low_v` + `battery_low_hysteresis_v`
+    pub battery_low_hysteresis_v: f32,
+    /// Consecutive samples on the other side of the thresholds to change the low battery state,
+    /// so that current spikes (eg. the servos) do not trigger it
+    pub battery_low_confirmations: u8,
+}
+
+impl Default for PowerMonitorConfig {
+    /// 2S LiPo through a 1:4 divider, 50 mV/A current sense
+    fn default() -> Self {
+        PowerMonitorConfig {
+            battery: AdcCalibration {
+                gain: 4.0,
+                offset: 0.0,
+            },
+            pyro: AdcCalibration {
+                gain: 4.0,
+                offset: 0.0,
+            },
+            current: AdcCalibration {
+                gain: 20.0,
+                offset: 0.0,
+            },
+            battery_low_v: 7.0,
+            battery_low_hysteresis_v: 0.2,
+            battery_low_confirmations: 10,
+        }
+    }
+}
+
+impl ConfigHash for PowerMonitorConfig {
+    fn hash_config(&self, hasher: &mut ConfigHasher) {
+        hasher.write_section("power_monitor");
+        for cal in [&self.battery, &self.pyro, &self.current] {
+            hasher.write_f32(cal.gain);
+            hasher.write_f32(cal.offset);
+        }
+        hasher.write_f32(self.battery_low_v);
+        hasher.write_f32(self.battery_low_hysteresis_v);
+        hasher.write_u8(self.battery_low_confirmations);
+    }
+}
+
+/// Calibrates the ADC samples of the supplies & detects a low battery
+#[derive(Debug, Clone)]
+pub struct PowerMonitor {
+    config: PowerMonitorConfig,
+
+    battery_low: bool,
+    /// Consecutive samples contradicting `battery_low`
+    num_crossing: u8,
+}
+
+impl PowerMonitor {
+    pub fn new(config: PowerMonitorConfig) -> Self {
+        Self {
+            config,
+            battery_low: false,
+            num_crossing: 0,
+        }
+    }
+
+    /// Calibrated status, and the low battery event when the battery becomes low
+    pub fn update(&mut self, sample: &PowerAdcSample) -> (PowerStatus, Option<Event>) {
+        let battery_v = self.config.battery.apply(sample.battery_v);
+
+        let crossing = if self.battery_low {
+            battery_v > self.config.battery_low_v + self.config.battery_low_hysteresis_v
+        } else {
+            battery_v < self.config.battery_low_v
+        };
+
+        self.num_crossing = if crossing {
+            self.num_crossing.saturating_add(1)
+        } else {
+            0
+        };
+
+        let mut event = None;
+        if self.num_crossing >= self.config.battery_low_confirmations.max(1) {
+            self.battery_low = !self.battery_low;
+            self.num_crossing = 0;
+
+            if self.battery_low {
+                event = Some(Event::PowerBatteryLow);
+            }
+        }
+
+        let status = PowerStatus {
+            battery_v,
+            pyro_v: self.config.pyro.apply(sample.pyro_v),
+            current_a: self.config.current.apply(sample.current_sense_v),
+            battery_low: self.battery_low,
+        };
+
+        (status, event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(battery_v: f32) -> PowerAdcSample {
+        PowerAdcSample {
+            battery_v: battery_v / 4.0,
+            pyro_v: 2.0,
+            current_sense_v: 0.05,
+        }
+    }
+
+    #[test]
+    fn test_battery_low() {
+        let mut monitor = PowerMonitor::new(PowerMonitorConfig {
+            battery_low_confirmations: 3,
+            ..Default::default()
+        });
+
+        let (status, event) = monitor.update(&sample(8.0));
+        assert!((status.battery_v - 8.0).abs() < 1e-5);
+        assert!((status.pyro_v - 8.0).abs() < 1e-5);
+        assert!((status.current_a - 1.0).abs() < 1e-5);
+        assert_eq!(event, None);
+
+        // A short dip is ignored
+        monitor.update(&sample(6.5));
+        monitor.update(&sample(6.5));
+        assert!(!monitor.update(&sample(8.0)).0.battery_low);
+
+        let events: [_; 3] = core::array::from_fn(|_| monitor.update(&sample(6.9)).1);
+        assert_eq!(events, [None, None, Some(Event::PowerBatteryLow)]);
+
+        // Within the hysteresis
+        for _ in 0..5 {
+            let (status, event) = monitor.update(&sample(7.1));
+            assert!(status.battery_low);
+            assert_eq!(event, None);
+        }
+
+        for _ in 0..3 {
+            assert_eq!(monitor.update(&sample(7.5)).1, None);
+        }
+        assert!(!monitor.update(&sample(7.5)).0.battery_low);
+    }
+}
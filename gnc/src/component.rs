@@ -1,4 +1,13 @@
-use crate::{Duration, Instant, events::Event, mav_crater::ComponentId};
+use heapless::Vec;
+
+use crate::{
+    Duration, Instant,
+    events::{Event, EventLogEntry},
+    mav_crater::ComponentId,
+};
+
+/// Entries the components can add to the event log per event or step
+const MAX_LOG_ENTRIES: usize = 4;
 
 #[derive(Debug, Clone, Copy)]
 pub struct StepData {
@@ -9,16 +18,30 @@ pub struct StepData {
 
 pub struct LoopContext {
     step: StepData,
+    log_entries: Vec<EventLogEntry, MAX_LOG_ENTRIES>,
 }
 
 impl LoopContext {
     pub fn new(step: StepData) -> Self {
-        Self { step }
+        Self {
+            step,
+            log_entries: Vec::new(),
+        }
     }
 
     pub fn step(&self) -> &StepData {
         &self.step
     }
+
+    /// Adds an entry to the event log, at the step time
+    pub fn log(&mut self, entry: EventLogEntry) {
+        let _ = self.log_entries.push(entry);
+    }
+
+    /// Entries added since the last call
+    pub fn take_log_entries(&mut self) -> Vec<EventLogEntry, MAX_LOG_ENTRIES> {
+        core::mem::take(&mut self.log_entries)
+    }
 }
 
 pub trait Component {
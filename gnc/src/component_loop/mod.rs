@@ -0,0 +1,156 @@
+mod schedule;
+mod supervisor;
+
+pub use schedule::Schedule;
+pub use supervisor::{LoopHealth, SupervisorConfig, SupervisorHarness};
+
+use crate::component::{Component, LoopContext, StepData};
+use crate::events::{
+    EVENT_LOG_LEN, Event, EventItem, EventLog, EventLogDump, EventLogEntry, EventQueue,
+};
+use crate::hal::channel::Sender;
+use crate::mav_crater::{ComponentId, MavMessage};
+use alloc::boxed::Box;
+use heapless::Vec;
+use supervisor::Supervisor;
+use thiserror::Error;
+
+pub struct ComponentLoop<const N: usize> {
+    event_queue: EventQueue,
+    tx_event: Box<dyn Sender<EventItem> + Send>,
+    components: Vec<Box<dyn Component + Send>, N>,
+    schedules: Vec<Schedule, N>,
+    /// Steps of the base loop rate
+    tick: u32,
+
+    /// Every event dispatched, and the entries logged by the components
+    event_log: EventLog<EVENT_LOG_LEN>,
+    event_log_dump: EventLogDump,
+
+    supervisor: Supervisor<N>,
+}
+
+impl<const N: usize> ComponentLoop<N> {
+    pub fn step(&mut self, step: &StepData) {
+        let mut loop_context = LoopContext::new(*step);
+        self.supervisor.begin_step();
+
+        while let Some(event) = self.event_queue.pop_event() {
+            self.event_log
+                .record(event.t, EventLogEntry::Event(event.v));
+            if event.v.event == Event::CmdEventLogDump {
+                self.event_log_dump.start(&self.event_log);
+            }
+
+            for (i, component) in self.components.iter_mut().enumerate() {
+                self.supervisor.time(i, || {
+                    component.handle_event(event.v.event, &mut loop_context)
+                });
+            }
+            self.record_log_entries(&mut loop_context);
+
+            if event.v.src != ComponentId::Ground {
+                let _ = self.tx_event.try_send(event.t, event.v);
+            }
+        }
+
+        // Decimated components only step at their schedule, but handle all the events
+        for (i, component) in self.components.iter_mut().enumerate() {
+            if self.schedules[i].is_due(self.tick) {
+                self.supervisor
+                    .time(i, || component.step(&mut loop_context));
+            }
+        }
+        self.tick = self.tick.wrapping_add(1);
+        self.record_log_entries(&mut loop_context);
+
+        self.event_log_dump.step(step.step_time, &self.event_log);
+        self.supervisor.end_step(step, &self.event_queue);
+    }
+
+    pub fn event_log(&self) -> &EventLog<EVENT_LOG_LEN> {
+        &self.event_log
+    }
+
+    fn record_log_entries(&mut self, loop_context: &mut LoopContext) {
+        for entry in loop_context.take_log_entries() {
+            self.event_log.record(loop_context.step().step_time, entry);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum ComponentLoopBuilderError {
+    #[error("No space for more components")]
+    TooManyComponents,
+
+    #[error("The rate divisor must be at least 1")]
+    InvalidRateDivisor,
+}
+
+pub struct ComponentLoopBuilder<const N: usize> {
+    components: Vec<Box<dyn Component + Send>, N>,
+    schedules: Vec<Schedule, N>,
+}
+
+impl<const N: usize> ComponentLoopBuilder<N> {
+    pub fn new() -> Self {
+        ComponentLoopBuilder {
+            components: Vec::new(),
+            schedules: Vec::new(),
+        }
+    }
+
+    /// Adds a component stepping at the base loop rate
+    pub fn add_component<T: Send>(&mut self, component: T) -> Result<(), ComponentLoopBuilderError>
+    where
+        T: Component + 'static,
+    {
+        self.add_component_decimated(component, 1)
+    }
+
+    /// Adds a component stepping at the base loop rate divided by `rate_divisor`. Its steps are
+    /// staggered with the ones of the other decimated components.
+    pub fn add_component_decimated<T: Send>(
+        &mut self,
+        component: T,
+        rate_divisor: u32,
+    ) -> Result<(), ComponentLoopBuilderError>
+    where
+        T: Component + 'static,
+    {
+        if rate_divisor == 0 {
+            return Err(ComponentLoopBuilderError::InvalidRateDivisor);
+        }
+
+        let schedule = Schedule::staggered(rate_divisor, &self.schedules);
+        if self.components.push(Box::new(component)).is_ok() {
+            let _ = self.schedules.push(schedule);
+            Ok(())
+        } else {
+            Err(ComponentLoopBuilderError::TooManyComponents)
+        }
+    }
+
+    pub fn build(
+        self,
+        event_queue: EventQueue,
+        tx_event: Box<dyn Sender<EventItem> + Send>,
+        tx_event_log: Box<dyn Sender<MavMessage> + Send>,
+        supervisor_harness: SupervisorHarness,
+        supervisor_config: SupervisorConfig,
+    ) -> ComponentLoop<N> {
+        let ids = self.components.iter().map(|c| c.id()).collect();
+
+        ComponentLoop {
+            event_queue,
+            tx_event,
+            components: self.components,
+            schedules: self.schedules,
+            tick: 0,
+            event_log: EventLog::new(),
+            event_log_dump: EventLogDump::new(tx_event_log),
+            supervisor: Supervisor::new(supervisor_harness, supervisor_config, ids),
+        }
+    }
+}
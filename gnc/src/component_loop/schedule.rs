@@ -0,0 +1,80 @@
+/// Ticks of the base loop rate a component steps at: one every `divisor`, starting from `offset`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Schedule {
+    pub divisor: u32,
+    pub offset: u32,
+}
+
+impl Schedule {
+    pub const EVERY_TICK: Schedule = Schedule {
+        divisor: 1,
+        offset: 0,
+    };
+
+    /// Schedule at `divisor`, staggered with the other decimated components: the offset is the
+    /// one sharing its ticks with the fewest of them, so that they do not all step at once.
+    pub fn staggered(divisor: u32, others: &[Schedule]) -> Schedule {
+        let divisor = divisor.max(1);
+        let offset = (0..divisor)
+            .min_by_key(|&offset| {
+                let schedule = Schedule { divisor, offset };
+                others
+                    .iter()
+                    .filter(|other| other.divisor > 1 && schedule.shares_ticks(other))
+                    .count()
+            })
+            .unwrap_or(0);
+
+        Schedule { divisor, offset }
+    }
+
+    pub fn is_due(&self, tick: u32) -> bool {
+        tick % self.divisor == self.offset
+    }
+
+    /// Whether both schedules step at some common tick
+    fn shares_ticks(&self, other: &Schedule) -> bool {
+        let gcd = gcd(self.divisor, other.divisor);
+        self.offset % gcd == other.offset % gcd
+    }
+}
+
+fn gcd(mut a: u32, mut b: u32) -> u32 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_staggered_schedules() {
+        let mut schedules = alloc::vec![Schedule::EVERY_TICK];
+
+        let mut add = |divisor| {
+            let schedule = Schedule::staggered(divisor, &schedules);
+            schedules.push(schedule);
+            schedule.offset
+        };
+
+        // FMM at a fifth of the rate, then two components at a tenth
+        assert_eq!(add(5), 0);
+        assert_eq!(add(10), 1);
+        assert_eq!(add(10), 2);
+        // Clear of all the ones above
+        assert_eq!(add(5), 3);
+        assert_eq!(add(2), 0);
+
+        let ada = Schedule {
+            divisor: 10,
+            offset: 1,
+        };
+        let steps: alloc::vec::Vec<_> = (0..25).filter(|&tick| ada.is_due(tick)).collect();
+        assert_eq!(steps, [1, 11, 21]);
+
+        assert!((0..3).all(|tick| Schedule::EVERY_TICK.is_due(tick)));
+    }
+}
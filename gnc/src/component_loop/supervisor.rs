@@ -0,0 +1,280 @@
+use alloc::boxed::Box;
+use heapless::Vec;
+
+use crate::{
+    Duration, DurationU64, Instant,
+    common::config_hash::{ConfigHash, ConfigHasher},
+    component::StepData,
+    events::{Event, EventQueue},
+    hal::{Hal, channel::Sender},
+    mav_crater::{ComponentHealth_DATA, ComponentId, MavMessage},
+};
+
+pub struct SupervisorHarness {
+    /// Measures the execution time, independently of the step time
+    pub clock: Box<dyn Hal + Send>,
+    /// Health reports, sent to the ground
+    pub tx_health: Box<dyn Sender<MavMessage> + Send>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SupervisorConfig {
+    /// Longest execution of a component in a step, event handling included
+    pub component_budget: Duration,
+    /// Health reports are sent at this period
+    pub report_period: Duration,
+}
+
+impl Default for SupervisorConfig {
+    fn default() -> Self {
+        SupervisorConfig {
+            component_budget: DurationU64::millis(1).into(),
+            report_period: DurationU64::secs(1).into(),
+        }
+    }
+}
+
+impl ConfigHash for SupervisorConfig {
+    fn hash_config(&self, hasher: &mut ConfigHasher) {
+        hasher.write_section("supervisor");
+        hasher.write_duration(self.component_budget);
+        hasher.write_duration(self.report_period);
+    }
+}
+
+/// Execution of the component loop over a report period
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoopHealth {
+    /// Components that stayed within their budget, bit N for the component id N
+    pub health_mask: u32,
+    /// Share of the time spent running the loop [%]
+    pub cpu_usage: f32,
+    /// Steps longer than the step interval
+    pub missed_deadlines: u16,
+    /// Longest step [µs]
+    pub max_loop_time_us: u32,
+}
+
+impl LoopHealth {
+    pub fn to_mavlink(&self, ts: Instant) -> MavMessage {
+        MavMessage::ComponentHealth(ComponentHealth_DATA {
+            timestamp_us: ts.0.duration_since_epoch().to_micros() as i64,
+            health_mask: self.health_mask,
+            cpu_usage: self.cpu_usage,
+            missed_deadlines: self.missed_deadlines,
+            max_loop_time_us: self.max_loop_time_us,
+        })
+    }
+}
+
+/// Execution times of the components, indexed in loop order, accumulated over a report period.
+/// Up to 32 components, with ids below 32.
+#[derive(Debug, Clone)]
+pub struct LoopStats<const N: usize> {
+    budget_us: u32,
+
+    /// Execution time of each component in the current step
+    step_us: [u32; N],
+    /// Components over budget during the period, bit N for the component at index N
+    overruns: u32,
+    busy_us: u64,
+    missed_deadlines: u16,
+    max_loop_time_us: u32,
+}
+
+impl<const N: usize> LoopStats<N> {
+    pub fn new(budget: Duration) -> Self {
+        Self {
+            budget_us: budget.0.to_micros() as u32,
+            step_us: [0; N],
+            overruns: 0,
+            busy_us: 0,
+            missed_deadlines: 0,
+            max_loop_time_us: 0,
+        }
+    }
+
+    pub fn add_time(&mut self, index: usize, time_us: u32) {
+        self.step_us[index] = self.step_us[index].saturating_add(time_us);
+    }
+
+    /// Ends a step of the whole loop, which ran for `loop_time_us`.
+    ///
+    /// Returns the components over budget for the first time in the period, bit N for the
+    /// component at index N.
+    pub fn end_step(&mut self, loop_time_us: u32, step_interval_us: u32) -> u32 {
+        let mut overruns = 0;
+        for (i, time_us) in self.step_us.iter_mut().enumerate() {
+            if *time_us > self.budget_us {
+                overruns |= 1 << i;
+            }
+            *time_us = 0;
+        }
+        let new_overruns = overruns & !self.overruns;
+        self.overruns |= overruns;
+
+        self.busy_us += loop_time_us as u64;
+        self.max_loop_time_us = self.max_loop_time_us.max(loop_time_us);
+        if loop_time_us > step_interval_us {
+            self.missed_deadlines = self.missed_deadlines.saturating_add(1);
+        }
+
+        new_overruns
+    }
+
+    /// Health over a period of `elapsed_us`, of the components with the given ids. Starts a new
+    /// period.
+    pub fn take_health(&mut self, ids: &[ComponentId], elapsed_us: u64) -> LoopHealth {
+        let health_mask = ids
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| self.overruns & (1 << i) == 0)
+            .fold(0, |mask, (_, id)| mask | 1 << *id as u32);
+
+        let health = LoopHealth {
+            health_mask,
+            cpu_usage: if elapsed_us > 0 {
+                100.0 * self.busy_us as f32 / elapsed_us as f32
+            } else {
+                0.0
+            },
+            missed_deadlines: self.missed_deadlines,
+            max_loop_time_us: self.max_loop_time_us,
+        };
+
+        self.reset();
+        health
+    }
+
+    fn reset(&mut self) {
+        self.overruns = 0;
+        self.busy_us = 0;
+        self.missed_deadlines = 0;
+        self.max_loop_time_us = 0;
+    }
+}
+
+/// Tracks the execution time of each component & of the whole loop. Raises an event when a
+/// component exceeds its budget, once per report period, and sends the health of the loop at
+/// each period, which is also a heartbeat for the ground.
+pub struct Supervisor<const N: usize> {
+    harness: SupervisorHarness,
+    config: SupervisorConfig,
+
+    ids: Vec<ComponentId, N>,
+    stats: LoopStats<N>,
+    /// Start of the current step, on the clock
+    step_start: Instant,
+    /// Start of the report period, in step time & on the clock
+    period_start: Option<(Instant, Instant)>,
+}
+
+impl<const N: usize> Supervisor<N> {
+    pub fn new(
+        harness: SupervisorHarness,
+        config: SupervisorConfig,
+        ids: Vec<ComponentId, N>,
+    ) -> Self {
+        let step_start = harness.clock.system_time();
+
+        Self {
+            harness,
+            config,
+            ids,
+            stats: LoopStats::new(config.component_budget),
+            step_start,
+            period_start: None,
+        }
+    }
+
+    pub fn begin_step(&mut self) {
+        self.step_start = self.harness.clock.system_time();
+    }
+
+    /// Runs `f` on behalf of the component at `index`, accounting for its execution time
+    pub fn time<R>(&mut self, index: usize, f: impl FnOnce() -> R) -> R {
+        let start = self.harness.clock.system_time();
+        let result = f();
+        let elapsed = self.harness.clock.system_time().0 - start.0;
+
+        self.stats.add_time(index, elapsed.to_micros() as u32);
+        result
+    }
+
+    pub fn end_step(&mut self, step: &StepData, event_queue: &EventQueue) {
+        let now = self.harness.clock.system_time();
+        let loop_time_us = (now.0 - self.step_start.0).to_micros() as u32;
+        let t = step.step_time;
+
+        let overruns = self
+            .stats
+            .end_step(loop_time_us, step.step_interval.0.to_micros() as u32);
+        for (i, id) in self.ids.iter().enumerate() {
+            if overruns & (1 << i) != 0 {
+                // Raised on behalf of the component, so that it can be told apart
+                event_queue
+                    .get_publisher(*id)
+                    .publish(Event::ComponentOverrun, t);
+            }
+        }
+
+        let (period_t, period_clock) = *self.period_start.get_or_insert((t, self.step_start));
+        if t.0 - period_t.0 >= self.config.report_period.0 {
+            let elapsed_us = (now.0 - period_clock.0).to_micros();
+            let health = self.stats.take_health(&self.ids, elapsed_us);
+            let _ = self.harness.tx_health.try_send(t, health.to_mavlink(t));
+
+            self.period_start = Some((t, now));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loop_stats() {
+        let ids = [
+            ComponentId::FlightModeManager,
+            ComponentId::Navigation,
+            ComponentId::Downlink,
+        ];
+        let mut stats = LoopStats::<3>::new(DurationU64::micros(100).into());
+
+        // Within budget
+        stats.add_time(0, 50);
+        stats.add_time(1, 100);
+        assert_eq!(stats.end_step(200, 1000), 0);
+
+        // Over budget over multiple calls (eg. events & step), reported once per period
+        stats.add_time(2, 60);
+        stats.add_time(2, 60);
+        assert_eq!(stats.end_step(300, 1000), 0b100);
+        stats.add_time(1, 150);
+        stats.add_time(2, 150);
+        assert_eq!(stats.end_step(1500, 1000), 0b010);
+
+        let health = stats.take_health(&ids, 4000);
+        assert_eq!(
+            health,
+            LoopHealth {
+                health_mask: 1 << ComponentId::FlightModeManager as u32,
+                cpu_usage: 50.0,
+                missed_deadlines: 1,
+                max_loop_time_us: 1500,
+            }
+        );
+
+        // New period
+        stats.add_time(2, 150);
+        assert_eq!(stats.end_step(100, 1000), 0b100);
+        let health = stats.take_health(&ids, 1000);
+        assert_eq!(
+            health.health_mask,
+            1 << ComponentId::FlightModeManager as u32 | 1 << ComponentId::Navigation as u32
+        );
+        assert_eq!(health.cpu_usage, 10.0);
+        assert_eq!(health.missed_deadlines, 0);
+    }
+}
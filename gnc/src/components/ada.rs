@@ -1,13 +1,20 @@
 use crate::{
-    Duration, Instant,
-    common::Ts,
+    Duration, DurationU64, Instant,
+    common::{
+        Ts,
+        config_hash::{ConfigHash, ConfigHasher},
+    },
     component::{Component, LoopContext},
-    datatypes::sensors::PressureSensorSample,
+    datatypes::{
+        sensors::PressureSensorSample,
+        version::{InterfaceVersion, Versioned},
+    },
     events::{Event, EventPublisher},
     hal::channel::{Receiver, Sender},
-    mav_crater::ComponentId,
+    mav_crater::{AdaState_DATA, ComponentId, DownlinkProfile, MavMessage},
 };
 use alloc::boxed::Box;
+use nalgebra::{Matrix3, Vector3};
 use statig::prelude::*;
 
 pub struct AdaHarness {
@@ -21,16 +28,12 @@ pub struct AdaComponent {
 }
 
 impl AdaComponent {
-    pub fn new(
-        harness: AdaHarness,
-        event_pub: EventPublisher,
-        shadow_mode_timeout: Duration,
-    ) -> Self {
+    pub fn new(harness: AdaHarness, event_pub: EventPublisher, config: AdaConfig) -> Self {
         let state_machine = AdaStateMachine {
             harness,
             event_pub,
-            shadow_mode_timeout,
-            ada_algo: AdaAlgorithm::default(),
+            calibration_time: config.calibration_time,
+            ada_algo: AdaAlgorithm::new(config),
         }
         .state_machine();
 
@@ -55,7 +58,7 @@ impl Component for AdaComponent {
 struct AdaStateMachine {
     harness: AdaHarness,
     event_pub: EventPublisher,
-    shadow_mode_timeout: Duration,
+    calibration_time: Duration,
 
     ada_algo: AdaAlgorithm,
 }
@@ -87,7 +90,7 @@ impl AdaStateMachine {
                     calib.ref_pressure_pa = press.v.pressure_pa;
                 }
 
-                if context.step().step_time.0 - entry_time.0 >= self.shadow_mode_timeout.0 {
+                if context.step().step_time.0 - entry_time.0 >= self.calibration_time.0 {
                     self.event_pub
                         .publish(Event::AdaCalibrationDone, context.step().step_time);
                     self.ada_algo.update_calib(calib.clone());
@@ -101,26 +104,41 @@ impl AdaStateMachine {
     }
 
     #[state]
-    fn ready(context: &mut LoopContext, event: &Event) -> Response<State> {
+    fn ready(&mut self, context: &mut LoopContext, event: &Event) -> Response<State> {
         match event {
-            Event::FlightLiftoff => Transition(State::shadow_mode(context.step().step_time)),
+            Event::Step => {
+                // Discard the samples collected on the pad, the filter starts at liftoff
+                while self.harness.rx_static_pressure.try_recv().is_some() {}
+                Handled
+            }
+            Event::FlightLiftoff => Transition(State::shadow_mode()),
             _ => Super,
         }
     }
 
+    /// Powered ascent, until the FMM detects the burnout: the pressure transients of the boost
+    /// and of the Mach transition must not trigger the apogee detection
     #[state]
-    fn shadow_mode(
-        &mut self,
-        entry_time: &mut Instant,
-        context: &mut LoopContext,
-        event: &Event,
-    ) -> Response<State> {
+    fn shadow_mode(&mut self, event: &Event) -> Response<State> {
         match event {
             Event::Step => {
+                // Apogee is estimated, but not acted upon
                 self.update_ada();
+                Handled
+            }
+            Event::FlightBurnout => Transition(State::active()),
+            _ => Super,
+        }
+    }
 
-                if context.step().step_time.0 - entry_time.0 >= self.shadow_mode_timeout.0 {
-                    Transition(State::active())
+    #[state]
+    fn active(&mut self, context: &mut LoopContext, event: &Event) -> Response<State> {
+        match event {
+            Event::Step => {
+                if self.update_ada() {
+                    self.event_pub
+                        .publish(Event::AdaApogeeDetected, context.step().step_time);
+                    Transition(State::apogee())
                 } else {
                     Handled
                 }
@@ -130,23 +148,29 @@ impl AdaStateMachine {
     }
 
     #[state]
-    fn active(&mut self, event: &Event) -> Response<State> {
+    fn apogee(&mut self, event: &Event) -> Response<State> {
         match event {
             Event::Step => {
                 self.update_ada();
-
                 Handled
             }
             _ => Super,
         }
     }
 
-    fn update_ada(&mut self) {
-        if let Some(press) = self.harness.rx_static_pressure.try_recv() {
+    /// Processes the pressure samples received since the last step, which may be several when
+    /// decimated. Returns whether apogee is detected.
+    fn update_ada(&mut self) -> bool {
+        let mut apogee = false;
+
+        while let Some(press) = self.harness.rx_static_pressure.try_recv() {
             let out = self.ada_algo.update(press);
+            apogee |= out.v.apogee_detected;
 
             let _ = self.harness.tx_ada_data.try_send(out.t, out.v);
         }
+
+        apogee
     }
 }
 
@@ -163,29 +187,270 @@ impl Default for AdaCalibration {
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+pub struct AdaConfig {
+    /// Duration of the calibration. After liftoff, apogee is estimated but not reported until the
+    /// burnout.
+    pub calibration_time: Duration,
+
+    /// Process noise of the filter, as the standard deviation of the vertical jerk [m/s^3]
+    pub jerk_noise_std: f32,
+    /// Standard deviation of the barometric altitude [m]
+    pub altitude_noise_std: f32,
+
+    /// Primary cue: the vertical speed is below this threshold [m/s]
+    pub vspeed_threshold_m_s: f32,
+    /// Consecutive samples needed to confirm the primary cue
+    pub vspeed_confirmations: u8,
+
+    /// Secondary cue: the vertical speed changed sign after having exceeded this speed on ascent.
+    /// Rejects short estimate excursions, such as those caused by the pressure jumps around the
+    /// Mach transition.
+    pub min_ascent_speed_m_s: f32,
+    /// Consecutive samples needed to confirm the secondary cue
+    pub sign_change_confirmations: u8,
+}
+
+impl Default for AdaConfig {
+    fn default() -> Self {
+        AdaConfig {
+            calibration_time: DurationU64::secs(5).into(),
+            jerk_noise_std: 5.0,
+            altitude_noise_std: 1.0,
+            vspeed_threshold_m_s: 5.0,
+            vspeed_confirmations: 5,
+            min_ascent_speed_m_s: 20.0,
+            sign_change_confirmations: 5,
+        }
+    }
+}
+
+impl ConfigHash for AdaConfig {
+    fn hash_config(&self, hasher: &mut ConfigHasher) {
+        hasher.write_section("ada");
+        hasher.write_duration(self.calibration_time);
+        hasher.write_f32(self.jerk_noise_std);
+        hasher.write_f32(self.altitude_noise_std);
+        hasher.write_f32(self.vspeed_threshold_m_s);
+        hasher.write_u8(self.vspeed_confirmations);
+        hasher.write_f32(self.min_ascent_speed_m_s);
+        hasher.write_u8(self.sign_change_confirmations);
+    }
+}
+
+/// Altitude above the calibration point from the static pressure, in the standard troposphere
+fn pressure_altitude_m(pressure_pa: f32, ref_pressure_pa: f32) -> f32 {
+    const T0_K: f32 = 288.15;
+    const LAPSE_RATE_K_M: f32 = 0.0065;
+    // R * L / g0
+    const EXPONENT: f32 = 0.190263;
+
+    T0_K / LAPSE_RATE_K_M * (1.0 - libm::powf(pressure_pa / ref_pressure_pa, EXPONENT))
+}
+
+/// Kalman filter on the barometric altitude, with altitude, vertical speed & vertical
+/// acceleration as states and a white jerk process model
+#[derive(Debug, Clone)]
+struct AltitudeKalman {
+    x: Vector3<f32>,
+    p: Matrix3<f32>,
+    last_t: Option<Instant>,
+}
+
+impl AltitudeKalman {
+    fn new() -> Self {
+        Self {
+            x: Vector3::zeros(),
+            p: Matrix3::zeros(),
+            last_t: None,
+        }
+    }
+
+    fn update(&mut self, t: Instant, altitude_m: f32, config: &AdaConfig) {
+        let r = config.altitude_noise_std * config.altitude_noise_std;
+
+        let Some(last_t) = self.last_t.replace(t) else {
+            self.x = Vector3::new(altitude_m, 0.0, 0.0);
+            // Initialized after liftoff: speed & acceleration are largely unknown
+            self.p = Matrix3::from_diagonal(&Vector3::new(r, 100.0, 1000.0));
+            return;
+        };
+
+        if t.0 > last_t.0 {
+            let dt = (t.0 - last_t.0).to_micros() as f32 / 1e6;
+            let (dt2, dt3) = (dt * dt, dt * dt * dt);
+
+            #[rustfmt::skip]
+            let f = Matrix3::new(
+                1.0, dt, dt2 / 2.0,
+                0.0, 1.0, dt,
+                0.0, 0.0, 1.0,
+            );
+            #[rustfmt::skip]
+            let q = Matrix3::new(
+                dt3 * dt2 / 20.0, dt2 * dt2 / 8.0, dt3 / 6.0,
+                dt2 * dt2 / 8.0, dt3 / 3.0, dt2 / 2.0,
+                dt3 / 6.0, dt2 / 2.0, dt,
+            ) * (config.jerk_noise_std * config.jerk_noise_std);
+
+            self.x = f * self.x;
+            self.p = f * self.p * f.transpose() + q;
+        }
+
+        // Altitude is directly measured: H = [1, 0, 0]
+        let k = self.p.column(0) / (self.p[(0, 0)] + r);
+        let kp = k * self.p.row(0);
+        self.x += k * (altitude_m - self.x[0]);
+        self.p -= kp;
+    }
+}
+
+/// Apogee detection, requiring both cues to be confirmed
 #[derive(Debug, Clone, Default)]
+struct ApogeeDetector {
+    vspeed_count: u8,
+    sign_change_count: u8,
+    ascent_seen: bool,
+    detected: bool,
+}
+
+impl ApogeeDetector {
+    fn update(&mut self, vspeed_m_s: f32, config: &AdaConfig) -> bool {
+        let count = |c: u8, cond: bool| if cond { c.saturating_add(1) } else { 0 };
+
+        self.vspeed_count = count(self.vspeed_count, vspeed_m_s < config.vspeed_threshold_m_s);
+
+        self.ascent_seen |= vspeed_m_s > config.min_ascent_speed_m_s;
+        self.sign_change_count =
+            count(self.sign_change_count, self.ascent_seen && vspeed_m_s < 0.0);
+
+        self.detected |= self.vspeed_count >= config.vspeed_confirmations
+            && self.sign_change_count >= config.sign_change_confirmations;
+        self.detected
+    }
+}
+
+/// Apogee detection algorithm, on the static pressure
+#[derive(Debug, Clone)]
 pub struct AdaAlgorithm {
+    config: AdaConfig,
     calib: AdaCalibration,
+
+    kalman: AltitudeKalman,
+    detector: ApogeeDetector,
 }
 
 #[derive(Debug, Clone)]
 pub struct AdaResult {
     pub altitude_m: f32,
     pub vertical_speed_m_s: f32,
+    pub vertical_accel_m_s2: f32,
+    /// Apogee was detected, at this or at a previous sample
+    pub apogee_detected: bool,
+}
+
+impl Versioned for AdaResult {
+    const NAME: &'static str = "AdaResult";
+    const VERSION: InterfaceVersion = InterfaceVersion::new(1, 1);
+}
+
+impl AdaResult {
+    pub fn to_mavlink(&self, ts: Instant, profile: DownlinkProfile) -> MavMessage {
+        MavMessage::AdaState(AdaState_DATA {
+            timestamp_us: ts.0.duration_since_epoch().to_micros() as i64,
+            profile,
+            altitude_m: self.altitude_m,
+            vertical_speed_m_s: self.vertical_speed_m_s,
+            vertical_accel_m_s2: self.vertical_accel_m_s2,
+            apogee_detected: self.apogee_detected as u8,
+        })
+    }
 }
 
 impl AdaAlgorithm {
+    pub fn new(config: AdaConfig) -> Self {
+        Self {
+            config,
+            calib: AdaCalibration::default(),
+            kalman: AltitudeKalman::new(),
+            detector: ApogeeDetector::default(),
+        }
+    }
+
+    /// Covariance of the altitude, vertical speed & vertical acceleration estimates
+    pub fn covariance(&self) -> Matrix3<f32> {
+        self.kalman.p
+    }
+
     fn update_calib(&mut self, calib: AdaCalibration) {
         self.calib = calib;
     }
 
-    /// Just a mockup for now
-    fn update(&mut self, press: Ts<PressureSensorSample>) -> Ts<AdaResult> {
+    pub fn update(&mut self, press: Ts<PressureSensorSample>) -> Ts<AdaResult> {
+        let altitude_m = pressure_altitude_m(press.v.pressure_pa, self.calib.ref_pressure_pa);
+        self.kalman.update(press.t, altitude_m, &self.config);
+
+        let x = self.kalman.x;
         let v = AdaResult {
-            altitude_m: (press.v.pressure_pa - self.calib.ref_pressure_pa) / 2f32,
-            vertical_speed_m_s: -press.v.pressure_pa / 100f32,
+            altitude_m: x[0],
+            vertical_speed_m_s: x[1],
+            vertical_accel_m_s2: x[2],
+            apogee_detected: self.detector.update(x[1], &self.config),
         };
 
         Ts::new(press.t, v)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Inverse of `pressure_altitude_m`
+    fn pressure_pa(altitude_m: f32) -> f32 {
+        101325.0 * libm::powf(1.0 - 2.25577e-5 * altitude_m, 5.25588)
+    }
+
+    #[test]
+    fn test_pressure_altitude() {
+        assert!(pressure_altitude_m(101325.0, 101325.0).abs() < 1e-3);
+        assert!((pressure_altitude_m(pressure_pa(1000.0), 101325.0) - 1000.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_apogee_detection() {
+        let mut ada = AdaAlgorithm::new(AdaConfig::default());
+        let (v0, g) = (200.0f32, 9.81f32);
+        let t_apogee = v0 / g;
+
+        let mut detected_at = None;
+        for k in 0..2000u64 {
+            let t = k as f32 * 0.02;
+            let mut altitude = v0 * t - 0.5 * g * t * t;
+            // Pressure jump around the Mach transition, seen as a sudden altitude drop
+            if (100..102).contains(&k) {
+                altitude -= 300.0;
+            }
+
+            let sample = Ts::from_microseconds(
+                k * 20_000,
+                PressureSensorSample {
+                    pressure_pa: pressure_pa(altitude),
+                    temperature_degc: None,
+                },
+            );
+            let out = ada.update(sample);
+
+            if k == 90 {
+                assert!((out.v.vertical_speed_m_s - (v0 - g * t)).abs() < 1.0);
+            }
+            if out.v.apogee_detected && detected_at.is_none() {
+                detected_at = Some(t);
+            }
+        }
+
+        let detected_at = detected_at.unwrap();
+        assert!(detected_at > t_apogee);
+        assert!(detected_at < t_apogee + 0.5);
+    }
+}
@@ -0,0 +1,300 @@
+use alloc::boxed::Box;
+
+use crate::{
+    common::config_hash::{ConfigHash, ConfigHasher},
+    component::{Component, LoopContext},
+    datatypes::{
+        actuators::AirbrakeCommand,
+        gnc::NavigationOutput,
+        version::{InterfaceVersion, Versioned},
+    },
+    events::Event,
+    hal::channel::{Receiver, Sender},
+    mav_crater::ComponentId,
+};
+
+const G_M_S2: f32 = 9.81;
+
+/// Bisection steps of the extension solving for the target apogee: resolution of 1 / 2^N
+const NUM_BISECTIONS: usize = 12;
+
+pub struct AirbrakeGuidanceHarness {
+    pub rx_nav: Box<dyn Receiver<NavigationOutput> + Send>,
+
+    pub tx_airbrake: Box<dyn Sender<AirbrakeCommand> + Send>,
+    /// Apogee prediction & computed extension, sent in shadow mode as well
+    pub tx_guidance: Box<dyn Sender<AirbrakeGuidanceOutput> + Send>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AirbrakeGuidanceOutput {
+    /// Apogee above the launch site with the airbrakes retracted [m]
+    pub predicted_apogee_m: f32,
+    /// Extension reaching the target apogee, from 0 (retracted) to 1 (fully deployed)
+    pub extension: f32,
+    /// The extension was only computed, the airbrakes were not commanded
+    pub shadow_mode: bool,
+}
+
+impl Versioned for AirbrakeGuidanceOutput {
+    const NAME: &'static str = "AirbrakeGuidanceOutput";
+    const VERSION: InterfaceVersion = InterfaceVersion::new(1, 0);
+}
+
+/// Drag of the rocket after burnout, for the apogee prediction
+#[derive(Debug, Clone, Copy)]
+pub struct DragModel {
+    /// Mass after burnout [kg]
+    pub mass_kg: f32,
+    /// Reference area of the drag coefficients [m^2]
+    pub ref_area_m2: f32,
+    /// Drag coefficient with the airbrakes retracted
+    pub cd: f32,
+    /// Drag coefficient increment with the airbrakes fully deployed, linear in the extension
+    pub cd_airbrake: f32,
+    /// Air density at the launch site [kg/m^3]
+    pub ground_density_kg_m3: f32,
+    /// Scale height of the exponential atmosphere [m]
+    pub density_scale_height_m: f32,
+}
+
+impl DragModel {
+    /// Drag deceleration per squared speed [1/m] at `altitude_m` above the launch site
+    fn drag_factor(&self, altitude_m: f32, extension: f32) -> f32 {
+        let density = self.ground_density_kg_m3
+            * libm::expf(-altitude_m.max(0.0) / self.density_scale_height_m);
+        let cd = self.cd + extension * self.cd_airbrake;
+
+        0.5 * density * cd * self.ref_area_m2 / self.mass_kg
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AirbrakeGuidanceConfig {
+    /// Apogee to reach, above the launch site [m]
+    pub target_apogee_m: f32,
+    /// The extension is computed & logged for flight qualification, but the airbrakes stay
+    /// retracted
+    pub shadow_mode: bool,
+    pub drag: DragModel,
+}
+
+impl Default for AirbrakeGuidanceConfig {
+    fn default() -> Self {
+        AirbrakeGuidanceConfig {
+            target_apogee_m: 1000.0,
+            shadow_mode: true,
+            drag: DragModel {
+                mass_kg: 2.0,
+                ref_area_m2: 0.005,
+                cd: 0.45,
+                cd_airbrake: 0.36,
+                ground_density_kg_m3: 1.225,
+                density_scale_height_m: 8500.0,
+            },
+        }
+    }
+}
+
+impl ConfigHash for AirbrakeGuidanceConfig {
+    fn hash_config(&self, hasher: &mut ConfigHasher) {
+        hasher.write_section("airbrake_guidance");
+        hasher.write_f32(self.target_apogee_m);
+        hasher.write_u8(self.shadow_mode as u8);
+        hasher.write_f32(self.drag.mass_kg);
+        hasher.write_f32(self.drag.ref_area_m2);
+        hasher.write_f32(self.drag.cd);
+        hasher.write_f32(self.drag.cd_airbrake);
+        hasher.write_f32(self.drag.ground_density_kg_m3);
+        hasher.write_f32(self.drag.density_scale_height_m);
+    }
+}
+
+/// Apogee above the launch site of a vertical coast with quadratic drag, from the altitude &
+/// vertical speed.
+///
+/// The drag is evaluated at the density halfway to the apogee. Inclined trajectories lose more
+/// vertical speed to drag than predicted, so the apogee is slightly overestimated for them.
+pub fn predict_apogee_m(altitude_m: f32, vspeed_m_s: f32, extension: f32, drag: &DragModel) -> f32 {
+    if vspeed_m_s <= 0.0 {
+        return altitude_m;
+    }
+
+    let climb_m = |k: f32| {
+        let v2 = vspeed_m_s * vspeed_m_s;
+        if k > 0.0 {
+            libm::logf(1.0 + k * v2 / G_M_S2) / (2.0 * k)
+        } else {
+            v2 / (2.0 * G_M_S2)
+        }
+    };
+
+    let climb = climb_m(drag.drag_factor(altitude_m, extension));
+    altitude_m + climb_m(drag.drag_factor(altitude_m + climb / 2.0, extension))
+}
+
+/// Extension whose predicted apogee is the target one, saturated to the extension range
+pub fn solve_extension(altitude_m: f32, vspeed_m_s: f32, config: &AirbrakeGuidanceConfig) -> f32 {
+    let overshoot = |extension: f32| {
+        predict_apogee_m(altitude_m, vspeed_m_s, extension, &config.drag) - config.target_apogee_m
+    };
+
+    if overshoot(0.0) <= 0.0 {
+        return 0.0;
+    }
+    if overshoot(1.0) >= 0.0 {
+        return 1.0;
+    }
+
+    // The apogee decreases with the extension
+    let (mut low, mut high) = (0.0, 1.0);
+    for _ in 0..NUM_BISECTIONS {
+        let mid = (low + high) / 2.0;
+        if overshoot(mid) > 0.0 {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+
+    (low + high) / 2.0
+}
+
+/// Modulates the airbrake extension during the coast to reach the target apogee.
+///
+/// The airbrakes are retracted before burnout and from apogee on. In shadow mode the airbrakes
+/// are never commanded, only the guidance output is sent.
+pub struct AirbrakeGuidanceComponent {
+    harness: AirbrakeGuidanceHarness,
+    config: AirbrakeGuidanceConfig,
+
+    active: bool,
+}
+
+impl AirbrakeGuidanceComponent {
+    pub fn new(harness: AirbrakeGuidanceHarness, config: AirbrakeGuidanceConfig) -> Self {
+        Self {
+            harness,
+            config,
+            active: false,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+}
+
+impl Component for AirbrakeGuidanceComponent {
+    fn id(&self) -> ComponentId {
+        ComponentId::AirbrakeGuidance
+    }
+
+    fn handle_event(&mut self, event: Event, context: &mut LoopContext) {
+        match event {
+            Event::FlightBurnout => self.active = true,
+            Event::AdaApogeeDetected | Event::CmdDeployDrogue if self.active => {
+                self.active = false;
+
+                if !self.config.shadow_mode {
+                    let _ = self
+                        .harness
+                        .tx_airbrake
+                        .try_send(context.step().step_time, AirbrakeCommand { extension: 0.0 });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn step(&mut self, context: &mut LoopContext) {
+        let Some(nav) = self.harness.rx_nav.try_recv_last() else {
+            return;
+        };
+
+        if !self.active {
+            return;
+        }
+
+        // NED frame, origin at the launch site
+        let altitude_m = -nav.v.pos_n_m.z;
+        let vspeed_m_s = -nav.v.vel_n_m_s.z;
+
+        let extension = solve_extension(altitude_m, vspeed_m_s, &self.config);
+        let t = context.step().step_time;
+
+        if !self.config.shadow_mode {
+            let _ = self
+                .harness
+                .tx_airbrake
+                .try_send(t, AirbrakeCommand { extension });
+        }
+
+        let _ = self.harness.tx_guidance.try_send(
+            t,
+            AirbrakeGuidanceOutput {
+                predicted_apogee_m: predict_apogee_m(
+                    altitude_m,
+                    vspeed_m_s,
+                    0.0,
+                    &self.config.drag,
+                ),
+                extension,
+                shadow_mode: self.config.shadow_mode,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Apogee of the vertical coast integrated with small steps
+    fn simulate_apogee_m(mut altitude_m: f32, mut vspeed_m_s: f32, extension: f32) -> f32 {
+        let drag = AirbrakeGuidanceConfig::default().drag;
+        let dt_s = 1e-3;
+
+        while vspeed_m_s > 0.0 {
+            let k = drag.drag_factor(altitude_m, extension);
+            altitude_m += vspeed_m_s * dt_s;
+            vspeed_m_s -= (G_M_S2 + k * vspeed_m_s * vspeed_m_s) * dt_s;
+        }
+
+        altitude_m
+    }
+
+    #[test]
+    fn test_apogee_prediction() {
+        let config = AirbrakeGuidanceConfig::default();
+
+        for (altitude_m, vspeed_m_s) in [(200.0, 150.0), (400.0, 100.0), (800.0, 30.0)] {
+            for extension in [0.0, 0.5, 1.0] {
+                let predicted = predict_apogee_m(altitude_m, vspeed_m_s, extension, &config.drag);
+                let simulated = simulate_apogee_m(altitude_m, vspeed_m_s, extension);
+                assert!((predicted - simulated).abs() < 0.005 * (simulated - altitude_m));
+            }
+        }
+
+        // Past apogee
+        assert_eq!(predict_apogee_m(900.0, -5.0, 0.0, &config.drag), 900.0);
+    }
+
+    #[test]
+    fn test_solve_extension() {
+        let config = AirbrakeGuidanceConfig {
+            target_apogee_m: 1000.0,
+            ..Default::default()
+        };
+
+        // Below the target even when retracted
+        assert_eq!(solve_extension(500.0, 50.0, &config), 0.0);
+        // Above it even when fully deployed
+        assert_eq!(solve_extension(900.0, 150.0, &config), 1.0);
+
+        let extension = solve_extension(300.0, 150.0, &config);
+        assert!(extension > 0.0 && extension < 1.0);
+        let apogee_m = predict_apogee_m(300.0, 150.0, extension, &config.drag);
+        assert!((apogee_m - 1000.0).abs() < 1.0);
+    }
+}
@@ -0,0 +1,106 @@
+use alloc::boxed::Box;
+use nalgebra::Vector3;
+
+use crate::{
+    Instant,
+    common::mahony::{Mahony, MahonyConfig},
+    component::{Component, LoopContext},
+    datatypes::{
+        gnc::AttitudeEstimate,
+        sensors::{ImuSensorSample, MagnetometerSensorSample},
+    },
+    events::Event,
+    hal::channel::{Receiver, Sender},
+    mav_crater::ComponentId,
+};
+
+pub struct AttitudeEstimatorHarness {
+    pub rx_imu: Box<dyn Receiver<ImuSensorSample> + Send>,
+    pub rx_magn: Box<dyn Receiver<MagnetometerSensorSample> + Send>,
+
+    pub tx_attitude: Box<dyn Sender<AttitudeEstimate> + Send>,
+}
+
+/// Lightweight attitude estimate, independent of the navigation filter, to cross-check it or
+/// to fall back to.
+///
+/// In flight the accelerometer senses the thrust & the drag instead of gravity: it only aids
+/// the attitude on the ground, before liftoff & after landing.
+pub struct AttitudeEstimatorComponent {
+    harness: AttitudeEstimatorHarness,
+
+    mahony: Mahony,
+    flying: bool,
+    last_imu: Option<Instant>,
+    /// Applied with the next IMU sample
+    pending_mag: Option<Vector3<f32>>,
+}
+
+impl AttitudeEstimatorComponent {
+    pub fn new(harness: AttitudeEstimatorHarness, config: MahonyConfig) -> Self {
+        Self {
+            harness,
+            mahony: Mahony::new(config),
+            flying: false,
+            last_imu: None,
+            pending_mag: None,
+        }
+    }
+}
+
+impl Component for AttitudeEstimatorComponent {
+    fn id(&self) -> ComponentId {
+        ComponentId::AttitudeEstimator
+    }
+
+    fn handle_event(&mut self, event: Event, _: &mut LoopContext) {
+        match event {
+            Event::FlightLiftoff => self.flying = true,
+            Event::FlightLanded => self.flying = false,
+            _ => {}
+        }
+    }
+
+    fn step(&mut self, context: &mut LoopContext) {
+        if let Some(mag) = self.harness.rx_magn.try_recv_last() {
+            self.pending_mag = Some(mag.v.mag_field_b_gauss);
+        }
+
+        let mut updated = false;
+        let mut accel_aided = false;
+
+        // Multiple or no imu samples may have been received this step
+        while let Some(sample) = self.harness.rx_imu.try_recv() {
+            let dt_s = self
+                .last_imu
+                .map(|last| (sample.t.0 - last.0).to_micros() as f32 / 1e6)
+                .unwrap_or(0.0);
+            self.last_imu = Some(sample.t);
+
+            let mag = self.pending_mag.take();
+            accel_aided |= self.mahony.update(
+                &sample.v.angvel_rad_s,
+                &sample.v.accel_m_s2,
+                mag.as_ref(),
+                !self.flying,
+                dt_s,
+            );
+            updated = true;
+        }
+
+        if !updated {
+            return;
+        }
+
+        if let Some(quat_nb) = self.mahony.quat_nb() {
+            let _ = self.harness.tx_attitude.try_send(
+                context.step().step_time,
+                AttitudeEstimate {
+                    quat_nb,
+                    gyro_bias_b_rad_s: self.mahony.gyro_bias_rad_s(),
+                    accel_aided,
+                },
+            );
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use alloc::boxed::Box;
+
+use crate::{
+    Duration, DurationU64, Instant,
+    common::{
+        Ts,
+        config_hash::{ConfigHash, ConfigHasher},
+    },
+    component::{Component, LoopContext},
+    components::ada::AdaResult,
+    datatypes::{gnc::NavigationOutput, recovery::RecoveryStatus},
+    events::Event,
+    hal::channel::{Receiver, Sender},
+    io::mavlink_scheduler::{MavlinkRateScheduler, MavlinkStream, StreamRates},
+    mav_crater::{ComponentId, ConfigHash_DATA, DownlinkProfile, MavMessage},
+};
+
+pub struct DownlinkHarness {
+    pub rx_nav: Box<dyn Receiver<NavigationOutput> + Send>,
+    pub rx_ada: Box<dyn Receiver<AdaResult> + Send>,
+    pub rx_recovery: Box<dyn Receiver<RecoveryStatus> + Send>,
+
+    pub tx_mavlink: Box<dyn Sender<MavMessage> + Send>,
+}
+
+/// Stream rates of each downlink profile
+#[derive(Debug, Clone, Copy)]
+pub struct DownlinkConfig {
+    pub pad: StreamRates,
+    pub ascent: StreamRates,
+    pub coast: StreamRates,
+    pub critical: StreamRates,
+    pub descent: StreamRates,
+    pub landed: StreamRates,
+
+    /// The critical profile is kept this long after the drogue & main deployments, then the
+    /// descent profile applies
+    pub critical_window: Duration,
+
+    /// The configuration hash is repeated with this period while armed on the pad, so that a
+    /// ground station connecting late still verifies it
+    pub config_hash_period: Duration,
+}
+
+impl Default for DownlinkConfig {
+    fn default() -> Self {
+        DownlinkConfig {
+            pad: StreamRates {
+                nav_hz: 1.0,
+                ada_hz: 1.0,
+                recovery_hz: 0.5,
+            },
+            ascent: StreamRates {
+                nav_hz: 10.0,
+                ada_hz: 5.0,
+                recovery_hz: 1.0,
+            },
+            coast: StreamRates {
+                nav_hz: 2.0,
+                ada_hz: 2.0,
+                recovery_hz: 1.0,
+            },
+            critical: StreamRates {
+                nav_hz: 20.0,
+                ada_hz: 20.0,
+                recovery_hz: 5.0,
+            },
+            descent: StreamRates {
+                nav_hz: 2.0,
+                ada_hz: 2.0,
+                recovery_hz: 1.0,
+            },
+            landed: StreamRates {
+                nav_hz: 0.2,
+                ada_hz: 0.2,
+                recovery_hz: 0.2,
+            },
+            critical_window: DurationU64::secs(5).into(),
+            config_hash_period: DurationU64::secs(1).into(),
+        }
+    }
+}
+
+impl ConfigHash for DownlinkConfig {
+    fn hash_config(&self, hasher: &mut ConfigHasher) {
+        hasher.write_section("downlink");
+        for rates in [
+            self.pad,
+            self.ascent,
+            self.coast,
+            self.critical,
+            self.descent,
+            self.landed,
+        ] {
+            hasher.write_f32(rates.nav_hz);
+            hasher.write_f32(rates.ada_hz);
+            hasher.write_f32(rates.recovery_hz);
+        }
+        hasher.write_duration(self.critical_window);
+        hasher.write_duration(self.config_hash_period);
+    }
+}
+
+impl DownlinkConfig {
+    pub fn rates(&self, profile: DownlinkProfile) -> StreamRates {
+        match profile {
+            DownlinkProfile::Pad => self.pad,
+            DownlinkProfile::Ascent => self.ascent,
+            DownlinkProfile::Coast => self.coast,
+            DownlinkProfile::Critical => self.critical,
+            DownlinkProfile::Descent => self.descent,
+            DownlinkProfile::Landed => self.landed,
+        }
+    }
+}
+
+/// Sends the latest navigation, ADA & recovery outputs to the ground. The rates follow the flight
+/// phase announced by the FMM, so that most of the radio bandwidth goes to apogee and the
+/// parachute deployments.
+pub struct DownlinkComponent {
+    harness: DownlinkHarness,
+    config: DownlinkConfig,
+    scheduler: MavlinkRateScheduler,
+
+    profile: DownlinkProfile,
+    /// End of the critical profile
+    critical_until: Option<Instant>,
+
+    /// Latest samples not downlinked yet
+    nav: Option<Ts<NavigationOutput>>,
+    ada: Option<Ts<AdaResult>>,
+    recovery: Option<Ts<RecoveryStatus>>,
+
+    /// Hash of the flight configuration, downlinked once armed
+    config_hash: u64,
+    armed: bool,
+    config_hash_sent: Option<Instant>,
+}
+
+impl DownlinkComponent {
+    pub fn new(harness: DownlinkHarness, config: DownlinkConfig, config_hash: u64) -> Self {
+        Self {
+            harness,
+            config,
+            scheduler: MavlinkRateScheduler::new(config.pad),
+            profile: DownlinkProfile::Pad,
+            critical_until: None,
+            nav: None,
+            ada: None,
+            recovery: None,
+            config_hash,
+            armed: false,
+            config_hash_sent: None,
+        }
+    }
+
+    pub fn profile(&self) -> DownlinkProfile {
+        self.profile
+    }
+
+    fn set_profile(&mut self, profile: DownlinkProfile) {
+        self.profile = profile;
+        self.scheduler.set_rates(self.config.rates(profile));
+    }
+
+    fn send(&mut self, stream: MavlinkStream, t: Instant) {
+        let msg = match stream {
+            MavlinkStream::Nav => self
+                .nav
+                .take()
+                .map(|nav| nav.v.to_mavlink(nav.t, self.profile)),
+            MavlinkStream::Ada => self
+                .ada
+                .take()
+                .map(|ada| ada.v.to_mavlink(ada.t, self.profile)),
+            MavlinkStream::Recovery => self
+                .recovery
+                .take()
+                .map(|status| status.v.to_mavlink(status.t)),
+        };
+
+        if let Some(msg) = msg {
+            let _ = self.harness.tx_mavlink.try_send(t, msg);
+        }
+    }
+
+    fn send_config_hash(&mut self, t: Instant) {
+        let msg = MavMessage::ConfigHash(ConfigHash_DATA {
+            timestamp_us: t.0.duration_since_epoch().to_micros() as i64,
+            hash: self.config_hash,
+        });
+
+        let _ = self.harness.tx_mavlink.try_send(t, msg);
+        self.config_hash_sent = Some(t);
+    }
+}
+
+impl Component for DownlinkComponent {
+    fn id(&self) -> ComponentId {
+        ComponentId::Downlink
+    }
+
+    fn handle_event(&mut self, event: Event, context: &mut LoopContext) {
+        let profile = match event {
+            Event::FlightStateArmed => {
+                // The configuration is frozen: announce it right away
+                self.armed = true;
+                self.send_config_hash(context.step().step_time);
+                return;
+            }
+            Event::FlightStateReady => {
+                // Disarmed
+                self.armed = false;
+                return;
+            }
+            Event::FlightLiftoff => DownlinkProfile::Ascent,
+            Event::FlightBurnout => DownlinkProfile::Coast,
+            Event::CmdDeployDrogue | Event::RecoveryMainFired => {
+                self.critical_until = Some(Instant(
+                    context.step().step_time.0 + self.config.critical_window.0,
+                ));
+                DownlinkProfile::Critical
+            }
+            Event::FlightLanded => DownlinkProfile::Landed,
+            _ => return,
+        };
+
+        self.set_profile(profile);
+    }
+
+    fn step(&mut self, context: &mut LoopContext) {
+        let t = context.step().step_time;
+
+        if self.profile == DownlinkProfile::Critical
+            && self.critical_until.is_some_and(|until| t.0 >= until.0)
+        {
+            self.critical_until = None;
+            self.set_profile(DownlinkProfile::Descent);
+        }
+
+        if self.armed
+            && self.profile == DownlinkProfile::Pad
+            && self
+                .config_hash_sent
+                .is_some_and(|sent| t.0 - sent.0 >= self.config.config_hash_period.0)
+        {
+            self.send_config_hash(t);
+        }
+
+        if let Some(nav) = self.harness.rx_nav.try_recv_last() {
+            self.nav = Some(nav);
+        }
+        if let Some(ada) = self.harness.rx_ada.try_recv_last() {
+            self.ada = Some(ada);
+        }
+        if let Some(status) = self.harness.rx_recovery.try_recv_last() {
+            self.recovery = Some(status);
+        }
+
+        for stream in [
+            MavlinkStream::Nav,
+            MavlinkStream::Ada,
+            MavlinkStream::Recovery,
+        ] {
+            let available = match stream {
+                MavlinkStream::Nav => self.nav.is_some(),
+                MavlinkStream::Ada => self.ada.is_some(),
+                MavlinkStream::Recovery => self.recovery.is_some(),
+            };
+
+            if available && self.scheduler.poll(stream, t) {
+                self.send(stream, t);
+            }
+        }
+    }
+}
@@ -2,26 +2,122 @@ use alloc::boxed::Box;
 use statig::prelude::*;
 
 use crate::{
+    Duration, DurationU64, Instant,
+    common::{
+        config_hash::{ConfigHash, ConfigHasher},
+        confirmation::ConfirmationWindow,
+    },
     component::{Component, LoopContext},
-    datatypes::pin::{DigitalInputState, DigitalState},
-    events::{Event, EventPublisher},
+    components::ada::AdaResult,
+    datatypes::{
+        gnc::NavigationOutput,
+        pin::{DigitalInputState, DigitalState},
+    },
+    events::{Event, EventLogEntry, EventPublisher},
     hal::channel::Receiver,
-    mav_crater::ComponentId,
+    mav_crater::{ComponentId, FlightMode},
 };
 
 pub struct FmmHarness {
     pub rx_liftoff_pin: Box<dyn Receiver<DigitalInputState> + Send>,
+    /// Used for liftoff & burnout detection, from the axial acceleration
+    pub rx_nav: Box<dyn Receiver<NavigationOutput> + Send>,
+    /// Used for landing detection, from the barometric vertical speed
+    pub rx_ada: Box<dyn Receiver<AdaResult> + Send>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FmmConfig {
+    /// Liftoff, when armed: the axial specific force exceeds this threshold [m/s^2]. On the pad
+    /// it is 1 g. Liftoff is also detected from the liftoff pin, whichever comes first.
+    pub liftoff_accel_threshold_m_s2: f32,
+    /// The axial specific force must stay above the liftoff threshold for this long
+    pub liftoff_confirmation: Duration,
+
+    /// Burnout: the axial specific force drops below this threshold [m/s^2]. Under thrust it is
+    /// positive, while during coast it only accounts for the drag.
+    pub burnout_accel_threshold_m_s2: f32,
+    /// The axial specific force must stay below the burnout threshold for this long
+    pub burnout_confirmation: Duration,
+    /// Burnout is assumed this long after liftoff, if not detected before
+    pub max_burn_time: Duration,
+
+    /// Landing: the vertical speed stays below this threshold [m/s] ...
+    pub landing_speed_m_s: f32,
+    /// ... for this long
+    pub landing_time: Duration,
+}
+
+impl Default for FmmConfig {
+    fn default() -> Self {
+        FmmConfig {
+            liftoff_accel_threshold_m_s2: 30.0,
+            liftoff_confirmation: DurationU64::millis(50).into(),
+            burnout_accel_threshold_m_s2: 0.0,
+            burnout_confirmation: DurationU64::millis(50).into(),
+            max_burn_time: DurationU64::secs(10).into(),
+            landing_speed_m_s: 2.0,
+            landing_time: DurationU64::secs(5).into(),
+        }
+    }
+}
+
+impl ConfigHash for FmmConfig {
+    fn hash_config(&self, hasher: &mut ConfigHasher) {
+        hasher.write_section("fmm");
+        hasher.write_f32(self.liftoff_accel_threshold_m_s2);
+        hasher.write_duration(self.liftoff_confirmation);
+        hasher.write_f32(self.burnout_accel_threshold_m_s2);
+        hasher.write_duration(self.burnout_confirmation);
+        hasher.write_duration(self.max_burn_time);
+        hasher.write_f32(self.landing_speed_m_s);
+        hasher.write_duration(self.landing_time);
+    }
 }
 
 pub struct FlightModeManager {
     state_machine: StateMachine<FMMStateMachine>,
+    /// Last flight mode, to log the transitions
+    flight_mode: FlightMode,
 }
 
 impl FlightModeManager {
-    pub fn new(harness: FmmHarness, event_pub: EventPublisher) -> Self {
-        let state_machine = FMMStateMachine { harness, event_pub }.state_machine();
+    pub fn new(harness: FmmHarness, event_pub: EventPublisher, config: FmmConfig) -> Self {
+        let state_machine = FMMStateMachine {
+            harness,
+            event_pub,
+            config,
+        }
+        .state_machine();
+
+        Self {
+            state_machine,
+            flight_mode: FlightMode::Init,
+        }
+    }
 
-        Self { state_machine }
+    pub fn flight_mode(&self) -> FlightMode {
+        self.flight_mode
+    }
+
+    fn log_transition(&mut self, context: &mut LoopContext) {
+        let flight_mode = match self.state_machine.state() {
+            State::Init { .. } => FlightMode::Init,
+            State::Calibration { .. } => FlightMode::Calibration,
+            State::Ready { .. } => FlightMode::Ready,
+            State::Armed { .. } => FlightMode::Armed,
+            State::Liftoff { .. } => FlightMode::Liftoff,
+            State::PoweredAscent { .. } => FlightMode::PoweredAscent,
+            State::Coast { .. } => FlightMode::Coast,
+            State::ApogeeDescent { .. } => FlightMode::ApogeeDescent,
+            State::MainDescent { .. } => FlightMode::MainDescent,
+            State::Landed { .. } => FlightMode::Landed,
+        };
+
+        if flight_mode != self.flight_mode {
+            self.flight_mode = flight_mode;
+            context.log(EventLogEntry::FmmTransition(flight_mode));
+        }
     }
 }
 
@@ -32,49 +128,62 @@ impl Component for FlightModeManager {
 
     fn handle_event(&mut self, event: Event, context: &mut LoopContext) {
         self.state_machine.handle_with_context(&event, context);
+        self.log_transition(context);
     }
 
     fn step(&mut self, context: &mut LoopContext) {
         self.state_machine
             .handle_with_context(&Event::Step, context);
+        self.log_transition(context);
     }
 }
 
 struct FMMStateMachine {
     harness: FmmHarness,
     event_pub: EventPublisher,
+    config: FmmConfig,
 }
 
 #[state_machine(
-    initial = "State::boot()",
+    initial = "State::init()",
     state(derive(Debug)),
     superstate(derive(Debug))
 )]
 impl FMMStateMachine {
     #[superstate]
-    #[allow(unused)]
-    fn on_ground(context: &mut LoopContext, event: &Event) -> Response<State> {
+    fn on_ground(&mut self, event: &Event) -> Response<State> {
         match event {
+            Event::Step => {
+                self.discard_flight_data();
+                Handled
+            }
             _ => Super,
         }
     }
 
+    /// Discards the navigation & ADA outputs received while they are not used, so that they do
+    /// not pile up on the pad
+    fn discard_flight_data(&mut self) {
+        while self.harness.rx_nav.try_recv().is_some() {}
+        while self.harness.rx_ada.try_recv().is_some() {}
+    }
+
     #[state(superstate = "on_ground")]
-    fn boot(&mut self, event: &Event) -> Response<State> {
+    fn init(&mut self, event: &Event) -> Response<State> {
         match event {
-            Event::CmdFmmCalibrate => Transition(State::calibrating()),
+            Event::CmdFmmCalibrate => Transition(State::calibration()),
             _ => Super,
         }
     }
 
     #[action]
-    fn enter_calibrating(&self, context: &mut LoopContext) {
+    fn enter_calibration(&self, context: &mut LoopContext) {
         self.event_pub
             .publish(Event::CmdAdaCalibrate, context.step().step_time);
     }
 
-    #[state(superstate = "on_ground", entry_action = "enter_calibrating")]
-    fn calibrating(&mut self, event: &Event) -> Response<State> {
+    #[state(superstate = "on_ground", entry_action = "enter_calibration")]
+    fn calibration(&mut self, event: &Event) -> Response<State> {
         match event {
             Event::AdaCalibrationDone => Transition(State::ready()),
             _ => Super,
@@ -90,46 +199,511 @@ impl FMMStateMachine {
     #[state(superstate = "on_ground", entry_action = "enter_ready")]
     fn ready(&mut self, event: &Event) -> Response<State> {
         match event {
-            Event::CmdFmmArm => Transition(State::armed()),
+            Event::CmdFmmArm => Transition(State::armed(ConfirmationWindow::new(
+                self.config.liftoff_confirmation,
+            ))),
             _ => Super,
         }
     }
 
-    #[state(superstate = "on_ground")]
-    fn armed(&mut self, event: &Event) -> Response<State> {
+    #[action]
+    fn enter_armed(&self, context: &mut LoopContext) {
+        self.event_pub
+            .publish(Event::FlightStateArmed, context.step().step_time);
+    }
+
+    #[state(superstate = "on_ground", entry_action = "enter_armed")]
+    fn armed(
+        &mut self,
+        liftoff_window: &mut ConfirmationWindow,
+        context: &mut LoopContext,
+        event: &Event,
+    ) -> Response<State> {
         match event {
             Event::Step => {
+                while self.harness.rx_ada.try_recv().is_some() {}
+
+                // Multiple or no navigation samples may have been received this step
+                while let Some(nav) = self.harness.rx_nav.try_recv() {
+                    let thrust =
+                        nav.v.acc_unbias_b_m_s2[0] > self.config.liftoff_accel_threshold_m_s2;
+                    if liftoff_window.update(nav.t, thrust) {
+                        return Transition(State::liftoff(context.step().step_time));
+                    }
+                }
+
                 // TODO: Avoid spurious state changes
                 if let Some(lo_pin) = self.harness.rx_liftoff_pin.try_recv_last() {
                     if lo_pin.v.0 == DigitalState::Low {
-                        return Transition(State::powered_ascent());
+                        return Transition(State::liftoff(context.step().step_time));
                     }
                 }
 
                 Handled
             }
-            Event::CmdFmmForceLiftoff => Transition(State::powered_ascent()),
+            Event::CmdFmmForceLiftoff => Transition(State::liftoff(context.step().step_time)),
+            Event::CmdFmmDisarm => Transition(State::ready()),
             _ => Super,
         }
     }
 
     #[superstate]
-    fn in_flight(event: &Event) -> Response<State> {
+    fn in_flight(&mut self, event: &Event) -> Response<State> {
         match event {
+            Event::Step => {
+                self.discard_flight_data();
+                Handled
+            }
             _ => Super,
         }
     }
 
     #[action]
-    fn enter_powered_ascent(&self, context: &mut LoopContext) {
+    fn enter_liftoff(&self, context: &mut LoopContext) {
         self.event_pub
             .publish(Event::FlightLiftoff, context.step().step_time);
     }
 
-    #[state(superstate = "in_flight", entry_action = "enter_powered_ascent")]
-    fn powered_ascent(event: &Event) -> Response<State> {
+    /// Liftoff was just detected: the other components are notified before the ascent starts
+    #[state(superstate = "in_flight", entry_action = "enter_liftoff")]
+    fn liftoff(&mut self, liftoff_time: &mut Instant, event: &Event) -> Response<State> {
         match event {
+            Event::Step => Transition(State::powered_ascent(
+                *liftoff_time,
+                ConfirmationWindow::new(self.config.burnout_confirmation),
+            )),
             _ => Super,
         }
     }
+
+    #[state(superstate = "in_flight")]
+    fn powered_ascent(
+        &mut self,
+        liftoff_time: &mut Instant,
+        burnout_window: &mut ConfirmationWindow,
+        context: &mut LoopContext,
+        event: &Event,
+    ) -> Response<State> {
+        match event {
+            Event::Step => {
+                while self.harness.rx_ada.try_recv().is_some() {}
+
+                let mut burnout = false;
+                while let Some(nav) = self.harness.rx_nav.try_recv() {
+                    let coasting =
+                        nav.v.acc_unbias_b_m_s2[0] < self.config.burnout_accel_threshold_m_s2;
+                    burnout |= burnout_window.update(nav.t, coasting);
+                }
+
+                let burn_timeout =
+                    context.step().step_time.0 - liftoff_time.0 >= self.config.max_burn_time.0;
+                if burnout || burn_timeout {
+                    Transition(State::coast())
+                } else {
+                    Handled
+                }
+            }
+            // Published once: the drogue must not wait for the burnout
+            Event::AdaApogeeDetected => Transition(State::apogee_descent(context.step().step_time)),
+            _ => Super,
+        }
+    }
+
+    #[action]
+    fn enter_coast(&self, context: &mut LoopContext) {
+        self.event_pub
+            .publish(Event::FlightBurnout, context.step().step_time);
+    }
+
+    #[state(superstate = "in_flight", entry_action = "enter_coast")]
+    fn coast(context: &mut LoopContext, event: &Event) -> Response<State> {
+        match event {
+            Event::AdaApogeeDetected => Transition(State::apogee_descent(context.step().step_time)),
+            _ => Super,
+        }
+    }
+
+    #[action]
+    fn enter_apogee_descent(&self, context: &mut LoopContext) {
+        self.event_pub
+            .publish(Event::CmdDeployDrogue, context.step().step_time);
+    }
+
+    /// Descent under the drogue parachute, until the recovery deploys the main. Landing is also
+    /// detected here, should the main never be deployed.
+    #[state(superstate = "in_flight", entry_action = "enter_apogee_descent")]
+    fn apogee_descent(
+        &mut self,
+        still_since: &mut Instant,
+        context: &mut LoopContext,
+        event: &Event,
+    ) -> Response<State> {
+        match event {
+            Event::Step => {
+                if self.landing_detected(still_since, context) {
+                    Transition(State::landed())
+                } else {
+                    Handled
+                }
+            }
+            Event::RecoveryMainFired => Transition(State::main_descent(context.step().step_time)),
+            _ => Super,
+        }
+    }
+
+    /// Descent under the main parachute
+    #[state(superstate = "in_flight")]
+    fn main_descent(
+        &mut self,
+        still_since: &mut Instant,
+        context: &mut LoopContext,
+        event: &Event,
+    ) -> Response<State> {
+        match event {
+            Event::Step => {
+                if self.landing_detected(still_since, context) {
+                    Transition(State::landed())
+                } else {
+                    Handled
+                }
+            }
+            _ => Super,
+        }
+    }
+
+    /// The vertical speed stayed below the landing threshold for the landing time, since
+    /// `still_since` at most
+    fn landing_detected(&mut self, still_since: &mut Instant, context: &LoopContext) -> bool {
+        while self.harness.rx_nav.try_recv().is_some() {}
+        while let Some(ada) = self.harness.rx_ada.try_recv() {
+            if ada.v.vertical_speed_m_s.abs() >= self.config.landing_speed_m_s {
+                *still_since = ada.t;
+            }
+        }
+
+        context.step().step_time.0 - still_since.0 >= self.config.landing_time.0
+    }
+
+    #[action]
+    fn enter_landed(&self, context: &mut LoopContext) {
+        self.event_pub
+            .publish(Event::FlightLanded, context.step().step_time);
+    }
+
+    #[state(entry_action = "enter_landed")]
+    fn landed(&mut self, event: &Event) -> Response<State> {
+        match event {
+            Event::Step => {
+                self.discard_flight_data();
+                Handled
+            }
+            _ => Super,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use nalgebra::{UnitQuaternion, Vector3};
+
+    use super::*;
+    use crate::{
+        InstantU64,
+        component::StepData,
+        events::EventQueue,
+        hal::channel::testing::{TestSender, channel},
+    };
+
+    const STEP_MS: u64 = 10;
+
+    struct Fixture {
+        fmm: FlightModeManager,
+        events: EventQueue,
+        tx_pin: TestSender<DigitalInputState>,
+        tx_nav: TestSender<NavigationOutput>,
+        tx_ada: TestSender<AdaResult>,
+        t_ms: u64,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let (tx_pin, rx_pin) = channel();
+            let (tx_nav, rx_nav) = channel();
+            let (tx_ada, rx_ada) = channel();
+            let events = EventQueue::new();
+
+            let fmm = FlightModeManager::new(
+                FmmHarness {
+                    rx_liftoff_pin: Box::new(rx_pin),
+                    rx_nav: Box::new(rx_nav),
+                    rx_ada: Box::new(rx_ada),
+                },
+                events.get_publisher(ComponentId::FlightModeManager),
+                FmmConfig::default(),
+            );
+
+            Self {
+                fmm,
+                events,
+                tx_pin,
+                tx_nav,
+                tx_ada,
+                t_ms: 0,
+            }
+        }
+
+        /// Calibrated & armed on the pad
+        fn armed() -> Self {
+            let mut fix = Self::new();
+            fix.handle(Event::CmdFmmCalibrate);
+            fix.handle(Event::AdaCalibrationDone);
+            fix.handle(Event::CmdFmmArm);
+            fix.published();
+            assert_eq!(fix.fmm.flight_mode(), FlightMode::Armed);
+            fix
+        }
+
+        /// In powered ascent, right after liftoff
+        fn powered_ascent() -> Self {
+            let mut fix = Self::armed();
+            fix.handle(Event::CmdFmmForceLiftoff);
+            fix.step();
+            fix.published();
+            assert_eq!(fix.fmm.flight_mode(), FlightMode::PoweredAscent);
+            fix
+        }
+
+        /// Under the drogue, right after apogee
+        fn apogee_descent() -> Self {
+            let mut fix = Self::powered_ascent();
+            fix.steps_accel(10, -5.0);
+            fix.handle(Event::AdaApogeeDetected);
+            fix.published();
+            assert_eq!(fix.fmm.flight_mode(), FlightMode::ApogeeDescent);
+            fix
+        }
+
+        fn now(&self) -> Instant {
+            Instant(InstantU64::from_ticks(self.t_ms * 1000))
+        }
+
+        fn context(&self) -> LoopContext {
+            LoopContext::new(StepData {
+                step_time: self.now(),
+                step_interval: DurationU64::millis(STEP_MS).into(),
+                step_count: (self.t_ms / STEP_MS) as u32,
+            })
+        }
+
+        fn handle(&mut self, event: Event) {
+            let mut context = self.context();
+            self.fmm.handle_event(event, &mut context);
+        }
+
+        fn step(&mut self) {
+            self.t_ms += STEP_MS;
+            let mut context = self.context();
+            self.fmm.step(&mut context);
+        }
+
+        /// Steps with an axial acceleration sample per step
+        fn steps_accel(&mut self, steps: usize, acc_m_s2: f32) {
+            for _ in 0..steps {
+                let t = Instant(InstantU64::from_ticks((self.t_ms + STEP_MS) * 1000));
+                self.tx_nav.send(
+                    t,
+                    NavigationOutput {
+                        quat_nb: UnitQuaternion::identity(),
+                        pos_n_m: Vector3::zeros(),
+                        vel_n_m_s: Vector3::zeros(),
+                        angvel_unbias_b_rad_s: Vector3::zeros(),
+                        acc_unbias_b_m_s2: Vector3::new(acc_m_s2, 0.0, 0.0),
+                    },
+                );
+                self.step();
+            }
+        }
+
+        /// Steps with a vertical speed sample of the ADA per step
+        fn steps_vertical_speed(&mut self, steps: usize, vertical_speed_m_s: f32) {
+            for _ in 0..steps {
+                let t = Instant(InstantU64::from_ticks((self.t_ms + STEP_MS) * 1000));
+                self.tx_ada.send(
+                    t,
+                    AdaResult {
+                        altitude_m: 0.0,
+                        vertical_speed_m_s,
+                        vertical_accel_m_s2: 0.0,
+                        apogee_detected: false,
+                    },
+                );
+                self.step();
+            }
+        }
+
+        /// Events published since the last call
+        fn published(&mut self) -> Vec<Event> {
+            let mut events = Vec::new();
+            while let Some(item) = self.events.pop_event() {
+                events.push(item.v.event);
+            }
+            events
+        }
+    }
+
+    #[test]
+    fn test_calibration() {
+        let mut fix = Fixture::new();
+
+        // Not calibrated yet
+        fix.handle(Event::CmdFmmArm);
+        fix.step();
+        assert_eq!(fix.fmm.flight_mode(), FlightMode::Init);
+
+        fix.handle(Event::CmdFmmCalibrate);
+        assert_eq!(fix.fmm.flight_mode(), FlightMode::Calibration);
+        assert_eq!(fix.published(), [Event::CmdAdaCalibrate]);
+
+        fix.handle(Event::AdaCalibrationDone);
+        assert_eq!(fix.fmm.flight_mode(), FlightMode::Ready);
+        assert_eq!(fix.published(), [Event::FlightStateReady]);
+    }
+
+    #[test]
+    fn test_arm_disarm() {
+        let mut fix = Fixture::armed();
+
+        fix.handle(Event::CmdFmmDisarm);
+        assert_eq!(fix.fmm.flight_mode(), FlightMode::Ready);
+        assert_eq!(fix.published(), [Event::FlightStateReady]);
+
+        // No liftoff while disarmed
+        fix.tx_pin
+            .send(fix.now(), DigitalInputState(DigitalState::Low));
+        fix.steps_accel(20, 50.0);
+        assert_eq!(fix.fmm.flight_mode(), FlightMode::Ready);
+
+        fix.handle(Event::CmdFmmArm);
+        assert_eq!(fix.fmm.flight_mode(), FlightMode::Armed);
+        assert_eq!(fix.published(), [Event::FlightStateArmed]);
+    }
+
+    #[test]
+    fn test_pin_liftoff() {
+        let mut fix = Fixture::armed();
+
+        fix.tx_pin
+            .send(fix.now(), DigitalInputState(DigitalState::High));
+        fix.step();
+        assert_eq!(fix.fmm.flight_mode(), FlightMode::Armed);
+
+        fix.tx_pin
+            .send(fix.now(), DigitalInputState(DigitalState::Low));
+        fix.step();
+        assert_eq!(fix.fmm.flight_mode(), FlightMode::Liftoff);
+        assert_eq!(fix.published(), [Event::FlightLiftoff]);
+
+        fix.step();
+        assert_eq!(fix.fmm.flight_mode(), FlightMode::PoweredAscent);
+    }
+
+    #[test]
+    fn test_accel_liftoff() {
+        let mut fix = Fixture::armed();
+
+        // 1 g on the pad, then thrust shorter than the confirmation window
+        fix.steps_accel(20, 9.81);
+        fix.steps_accel(3, 50.0);
+        fix.steps_accel(1, 9.81);
+        assert_eq!(fix.fmm.flight_mode(), FlightMode::Armed);
+
+        fix.steps_accel(5, 50.0);
+        assert_eq!(fix.fmm.flight_mode(), FlightMode::Armed);
+
+        fix.steps_accel(1, 50.0);
+        assert_eq!(fix.fmm.flight_mode(), FlightMode::Liftoff);
+        assert_eq!(fix.published(), [Event::FlightLiftoff]);
+    }
+
+    #[test]
+    fn test_burnout() {
+        let mut fix = Fixture::powered_ascent();
+
+        fix.steps_accel(100, 80.0);
+        fix.steps_accel(3, -5.0);
+        assert_eq!(fix.fmm.flight_mode(), FlightMode::PoweredAscent);
+
+        fix.steps_accel(10, -5.0);
+        assert_eq!(fix.fmm.flight_mode(), FlightMode::Coast);
+        assert_eq!(fix.published(), [Event::FlightBurnout]);
+    }
+
+    #[test]
+    fn test_burn_timeout() {
+        let mut fix = Fixture::powered_ascent();
+
+        // No navigation output: burnout after the maximum burn time
+        for _ in 0..980 {
+            fix.step();
+        }
+        assert_eq!(fix.fmm.flight_mode(), FlightMode::PoweredAscent);
+
+        for _ in 0..20 {
+            fix.step();
+        }
+        assert_eq!(fix.fmm.flight_mode(), FlightMode::Coast);
+        assert_eq!(fix.published(), [Event::FlightBurnout]);
+    }
+
+    #[test]
+    fn test_apogee() {
+        let mut fix = Fixture::powered_ascent();
+        fix.steps_accel(10, -5.0);
+        assert_eq!(fix.fmm.flight_mode(), FlightMode::Coast);
+        fix.published();
+
+        fix.handle(Event::AdaApogeeDetected);
+        assert_eq!(fix.fmm.flight_mode(), FlightMode::ApogeeDescent);
+        assert_eq!(fix.published(), [Event::CmdDeployDrogue]);
+    }
+
+    #[test]
+    fn test_apogee_before_burnout() {
+        let mut fix = Fixture::powered_ascent();
+        fix.steps_accel(10, 80.0);
+
+        fix.handle(Event::AdaApogeeDetected);
+        assert_eq!(fix.fmm.flight_mode(), FlightMode::ApogeeDescent);
+        assert_eq!(fix.published(), [Event::CmdDeployDrogue]);
+    }
+
+    #[test]
+    fn test_main_descent() {
+        let mut fix = Fixture::apogee_descent();
+
+        fix.handle(Event::RecoveryMainFired);
+        assert_eq!(fix.fmm.flight_mode(), FlightMode::MainDescent);
+
+        fix.steps_vertical_speed(600, -6.0);
+        fix.steps_vertical_speed(450, -0.5);
+        assert_eq!(fix.fmm.flight_mode(), FlightMode::MainDescent);
+
+        fix.steps_vertical_speed(100, 0.1);
+        assert_eq!(fix.fmm.flight_mode(), FlightMode::Landed);
+        assert_eq!(fix.published(), [Event::FlightLanded]);
+    }
+
+    #[test]
+    fn test_landing_without_main() {
+        let mut fix = Fixture::apogee_descent();
+
+        fix.steps_vertical_speed(600, -25.0);
+        fix.steps_vertical_speed(450, 0.1);
+        assert_eq!(fix.fmm.flight_mode(), FlightMode::ApogeeDescent);
+
+        fix.steps_vertical_speed(100, 0.1);
+        assert_eq!(fix.fmm.flight_mode(), FlightMode::Landed);
+        assert_eq!(fix.published(), [Event::FlightLanded]);
+    }
 }
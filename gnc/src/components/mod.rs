@@ -1,3 +1,8 @@
 pub mod fmm;
 pub mod ada;
+pub mod downlink;
 pub mod navigation;
+pub mod recovery;
+pub mod roll_control;
+pub mod airbrake_guidance;
+pub mod attitude_estimator;
\ No newline at end of file
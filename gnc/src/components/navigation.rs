@@ -3,7 +3,10 @@ use nalgebra::{UnitQuaternion, Vector3};
 use statig::prelude::*;
 
 use crate::{
-    common::Timestamped,
+    common::{
+        Timestamped, Ts,
+        imu_decimator::{ImuDecimator, ImuDecimatorConfig, ImuIncrement},
+    },
     component::{Component, LoopContext},
     datatypes::{
         gnc::NavigationOutput,
@@ -28,9 +31,9 @@ pub struct NavigationComponent {
 }
 
 impl NavigationComponent {
-    pub fn new(harness: NavigationHarness) -> Self {
+    pub fn new(harness: NavigationHarness, imu_config: ImuDecimatorConfig) -> Self {
         Self {
-            state_machine: NavigationStateMachine::new(harness).state_machine(),
+            state_machine: NavigationStateMachine::new(harness, imu_config).state_machine(),
         }
     }
 }
@@ -55,9 +58,9 @@ struct NavigationStateMachine {
 }
 
 impl NavigationStateMachine {
-    fn new(harness: NavigationHarness) -> Self {
+    fn new(harness: NavigationHarness, imu_config: ImuDecimatorConfig) -> Self {
         Self {
-            nav: NavigationAlgorithm::new(harness),
+            nav: NavigationAlgorithm::new(harness, imu_config),
         }
     }
 }
@@ -111,16 +114,26 @@ impl NavigationStateMachine {
 
 struct NavigationAlgorithm {
     harness: NavigationHarness,
+    imu_decimator: ImuDecimator,
+    imu_increment: Option<Ts<ImuIncrement>>,
 }
 
 impl NavigationAlgorithm {
-    fn new(harness: NavigationHarness) -> Self {
-        Self { harness }
+    fn new(harness: NavigationHarness, imu_config: ImuDecimatorConfig) -> Self {
+        Self {
+            harness,
+            imu_decimator: ImuDecimator::new(imu_config),
+            imu_increment: None,
+        }
     }
 
     fn update(&mut self, ts: crate::Instant) {
-        while let Some(Timestamped { t, v }) = self.harness.rx_imu.try_recv() {
-            // Multiple or no imu samples may have been received this step
+        // Multiple or no imu samples may have been received this step: they are integrated down
+        // to the navigation rate
+        while let Some(sample) = self.harness.rx_imu.try_recv() {
+            if let Some(increment) = self.imu_decimator.push(&sample) {
+                self.imu_increment = Some(increment);
+            }
         }
 
         while let Some(Timestamped { t, v }) = self.harness.rx_magn.try_recv() {
@@ -135,8 +148,10 @@ impl NavigationAlgorithm {
         let quat_bn = UnitQuaternion::<f32>::identity();
         let pos_n_m: Vector3<f32> = Vector3::<f32>::zeros();
         let vel_n_m_s: Vector3<f32> = Vector3::<f32>::zeros();
-        let angvel_unbias_b_rad_s: Vector3<f32> = Vector3::<f32>::zeros();
-        let acc_unbias_b_m_s2: Vector3<f32> = Vector3::<f32>::zeros();
+        let (angvel_unbias_b_rad_s, acc_unbias_b_m_s2) = match &self.imu_increment {
+            Some(Timestamped { v, .. }) => (v.angvel_rad_s, v.accel_m_s2),
+            None => (Vector3::zeros(), Vector3::zeros()),
+        };
 
         let nav_out = NavigationOutput {
             quat_nb: quat_bn,
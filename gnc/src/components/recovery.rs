@@ -0,0 +1,433 @@
+use alloc::boxed::Box;
+use statig::prelude::*;
+
+use crate::{
+    Duration, DurationU64, Instant,
+    common::config_hash::{ConfigHash, ConfigHasher},
+    component::{Component, LoopContext},
+    components::ada::AdaResult,
+    datatypes::recovery::{PyroChannel, PyroCommand, RecoveryStatus},
+    events::{Event, EventPublisher},
+    hal::channel::{Receiver, Sender},
+    mav_crater::{ComponentId, RecoveryState},
+};
+
+pub struct RecoveryHarness {
+    pub rx_ada: Box<dyn Receiver<AdaResult> + Send>,
+
+    pub tx_pyro: Box<dyn Sender<PyroCommand> + Send>,
+    pub tx_status: Box<dyn Sender<RecoveryStatus> + Send>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RecoveryConfig {
+    /// Main parachute deployment altitude, above the calibration point [m]
+    pub main_deploy_altitude_m: f32,
+    /// Consecutive ADA samples below the deployment altitude needed to deploy the main
+    pub main_deploy_confirmations: u8,
+    /// Period of the status reports, which are also sent at every state change
+    pub status_period: Duration,
+}
+
+impl Default for RecoveryConfig {
+    fn default() -> Self {
+        RecoveryConfig {
+            main_deploy_altitude_m: 300.0,
+            main_deploy_confirmations: 5,
+            status_period: DurationU64::secs(1).into(),
+        }
+    }
+}
+
+impl ConfigHash for RecoveryConfig {
+    fn hash_config(&self, hasher: &mut ConfigHasher) {
+        hasher.write_section("recovery");
+        hasher.write_f32(self.main_deploy_altitude_m);
+        hasher.write_u8(self.main_deploy_confirmations);
+        hasher.write_duration(self.status_period);
+    }
+}
+
+pub struct RecoveryComponent {
+    state_machine: StateMachine<RecoveryStateMachine>,
+}
+
+impl RecoveryComponent {
+    pub fn new(
+        harness: RecoveryHarness,
+        event_pub: EventPublisher,
+        config: RecoveryConfig,
+    ) -> Self {
+        let state_machine = RecoveryStateMachine {
+            harness,
+            event_pub,
+            config,
+            status: RecoveryStatus {
+                state: RecoveryState::Idle,
+                drogue_fired: false,
+                main_fired: false,
+                altitude_m: None,
+            },
+            last_status: None,
+        }
+        .state_machine();
+
+        Self { state_machine }
+    }
+}
+
+impl Component for RecoveryComponent {
+    fn id(&self) -> ComponentId {
+        ComponentId::Recovery
+    }
+
+    fn handle_event(&mut self, event: Event, context: &mut LoopContext) {
+        self.state_machine.handle_with_context(&event, context);
+    }
+
+    fn step(&mut self, context: &mut LoopContext) {
+        self.state_machine
+            .handle_with_context(&Event::Step, context);
+    }
+}
+
+struct RecoveryStateMachine {
+    harness: RecoveryHarness,
+    event_pub: EventPublisher,
+    config: RecoveryConfig,
+
+    status: RecoveryStatus,
+    last_status: Option<Instant>,
+}
+
+/// Parachute deployment: the drogue is fired on the deployment command of the FMM at apogee,
+/// the main once the ADA altitude drops below the deployment altitude. The ground can command
+/// both deployments.
+#[state_machine(initial = "State::idle()")]
+impl RecoveryStateMachine {
+    /// Pyros are disarmed on the ground
+    #[state]
+    fn idle(&mut self, context: &mut LoopContext, event: &Event) -> Response<State> {
+        match event {
+            Event::Step => {
+                self.update(context);
+                Handled
+            }
+            Event::FlightLiftoff => Transition(State::armed()),
+            _ => Super,
+        }
+    }
+
+    #[action]
+    fn enter_armed(&mut self, context: &mut LoopContext) {
+        self.set_state(RecoveryState::Armed, context);
+    }
+
+    #[state(entry_action = "enter_armed")]
+    fn armed(&mut self, context: &mut LoopContext, event: &Event) -> Response<State> {
+        match event {
+            Event::Step => {
+                self.update(context);
+                Handled
+            }
+            Event::CmdDeployDrogue => Transition(State::drogue(0)),
+            Event::CmdDeployMain => Transition(State::main()),
+            _ => Super,
+        }
+    }
+
+    #[action]
+    fn enter_drogue(&mut self, context: &mut LoopContext) {
+        self.fire(PyroChannel::Drogue, context);
+        self.set_state(RecoveryState::Drogue, context);
+    }
+
+    #[state(entry_action = "enter_drogue")]
+    fn drogue(
+        &mut self,
+        main_deploy_count: &mut u8,
+        context: &mut LoopContext,
+        event: &Event,
+    ) -> Response<State> {
+        match event {
+            Event::Step => {
+                while let Some(ada) = self.harness.rx_ada.try_recv() {
+                    self.status.altitude_m = Some(ada.v.altitude_m);
+                    *main_deploy_count = if ada.v.altitude_m < self.config.main_deploy_altitude_m {
+                        main_deploy_count.saturating_add(1)
+                    } else {
+                        0
+                    };
+                }
+                self.report(context);
+
+                if *main_deploy_count >= self.config.main_deploy_confirmations {
+                    Transition(State::main())
+                } else {
+                    Handled
+                }
+            }
+            Event::CmdDeployMain => Transition(State::main()),
+            _ => Super,
+        }
+    }
+
+    #[action]
+    fn enter_main(&mut self, context: &mut LoopContext) {
+        self.fire(PyroChannel::Main, context);
+        self.set_state(RecoveryState::Main, context);
+    }
+
+    #[state(entry_action = "enter_main")]
+    fn main(&mut self, context: &mut LoopContext, event: &Event) -> Response<State> {
+        match event {
+            Event::Step => {
+                self.update(context);
+                Handled
+            }
+            _ => Super,
+        }
+    }
+
+    fn fire(&mut self, channel: PyroChannel, context: &mut LoopContext) {
+        let t = context.step().step_time;
+        self.harness
+            .tx_pyro
+            .send_immediate(t, PyroCommand { channel });
+
+        let event = match channel {
+            PyroChannel::Drogue => {
+                self.status.drogue_fired = true;
+                Event::RecoveryDrogueFired
+            }
+            PyroChannel::Main => {
+                self.status.main_fired = true;
+                Event::RecoveryMainFired
+            }
+        };
+        self.event_pub.publish(event, t);
+    }
+
+    fn set_state(&mut self, state: RecoveryState, context: &mut LoopContext) {
+        self.status.state = state;
+        self.last_status = None;
+        self.report(context);
+    }
+
+    /// Tracks the ADA altitude and reports the status
+    fn update(&mut self, context: &mut LoopContext) {
+        if let Some(ada) = self.harness.rx_ada.try_recv_last() {
+            self.status.altitude_m = Some(ada.v.altitude_m);
+        }
+        self.report(context);
+    }
+
+    /// Sends the status if it was not sent for a status period
+    fn report(&mut self, context: &mut LoopContext) {
+        let t = context.step().step_time;
+        if self
+            .last_status
+            .is_some_and(|last| t.0 - last.0 < self.config.status_period.0)
+        {
+            return;
+        }
+
+        self.last_status = Some(t);
+        let _ = self.harness.tx_status.try_send(t, self.status.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::{
+        InstantU64,
+        component::StepData,
+        events::EventQueue,
+        hal::channel::testing::{TestReceiver, TestSender, channel},
+    };
+
+    const STEP_MS: u64 = 10;
+
+    struct Fixture {
+        recovery: RecoveryComponent,
+        events: EventQueue,
+        tx_ada: TestSender<AdaResult>,
+        rx_pyro: TestReceiver<PyroCommand>,
+        rx_status: TestReceiver<RecoveryStatus>,
+        t_ms: u64,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let (tx_ada, rx_ada) = channel();
+            let (tx_pyro, rx_pyro) = channel();
+            let (tx_status, rx_status) = channel();
+            let events = EventQueue::new();
+
+            let recovery = RecoveryComponent::new(
+                RecoveryHarness {
+                    rx_ada: Box::new(rx_ada),
+                    tx_pyro: Box::new(tx_pyro),
+                    tx_status: Box::new(tx_status),
+                },
+                events.get_publisher(ComponentId::Recovery),
+                RecoveryConfig::default(),
+            );
+
+            Self {
+                recovery,
+                events,
+                tx_ada,
+                rx_pyro,
+                rx_status,
+                t_ms: 0,
+            }
+        }
+
+        /// Armed at liftoff
+        fn armed() -> Self {
+            let mut fix = Self::new();
+            fix.handle(Event::FlightLiftoff);
+            assert_eq!(fix.state(), Some(RecoveryState::Armed));
+            fix
+        }
+
+        fn context(&self) -> LoopContext {
+            LoopContext::new(StepData {
+                step_time: Instant(InstantU64::from_ticks(self.t_ms * 1000)),
+                step_interval: DurationU64::millis(STEP_MS).into(),
+                step_count: (self.t_ms / STEP_MS) as u32,
+            })
+        }
+
+        fn handle(&mut self, event: Event) {
+            let mut context = self.context();
+            self.recovery.handle_event(event, &mut context);
+        }
+
+        /// Steps with an ADA altitude sample
+        fn step_altitude(&mut self, altitude_m: f32) {
+            self.t_ms += STEP_MS;
+            self.tx_ada.send(
+                Instant(InstantU64::from_ticks(self.t_ms * 1000)),
+                AdaResult {
+                    altitude_m,
+                    vertical_speed_m_s: -20.0,
+                    vertical_accel_m_s2: 0.0,
+                    apogee_detected: true,
+                },
+            );
+            let mut context = self.context();
+            self.recovery.step(&mut context);
+        }
+
+        /// Pyro channels fired since the last call
+        fn fired(&mut self) -> Vec<PyroChannel> {
+            let mut fired = Vec::new();
+            while let Some(pyro) = self.rx_pyro.try_recv() {
+                fired.push(pyro.v.channel);
+            }
+            fired
+        }
+
+        /// Events published since the last call
+        fn published(&mut self) -> Vec<Event> {
+            let mut events = Vec::new();
+            while let Some(item) = self.events.pop_event() {
+                events.push(item.v.event);
+            }
+            events
+        }
+
+        /// Latest state reported
+        fn state(&mut self) -> Option<RecoveryState> {
+            self.rx_status.try_recv_last().map(|status| status.v.state)
+        }
+    }
+
+    #[test]
+    fn test_arming_on_liftoff() {
+        let mut fix = Fixture::new();
+
+        // Disarmed on the ground
+        fix.handle(Event::CmdDeployDrogue);
+        fix.handle(Event::CmdDeployMain);
+        fix.step_altitude(0.0);
+        assert_eq!(fix.state(), Some(RecoveryState::Idle));
+        assert!(fix.fired().is_empty());
+        assert!(fix.published().is_empty());
+
+        fix.handle(Event::FlightLiftoff);
+        assert_eq!(fix.state(), Some(RecoveryState::Armed));
+        assert!(fix.fired().is_empty());
+    }
+
+    #[test]
+    fn test_drogue_on_command() {
+        let mut fix = Fixture::armed();
+
+        // Above the main deployment altitude, the drogue is only fired on command
+        for _ in 0..10 {
+            fix.step_altitude(1000.0);
+        }
+        assert!(fix.fired().is_empty());
+
+        fix.handle(Event::CmdDeployDrogue);
+        assert_eq!(fix.fired(), [PyroChannel::Drogue]);
+        assert_eq!(fix.published(), [Event::RecoveryDrogueFired]);
+        assert_eq!(fix.state(), Some(RecoveryState::Drogue));
+    }
+
+    #[test]
+    fn test_main_deployment_altitude() {
+        let mut fix = Fixture::armed();
+        fix.handle(Event::CmdDeployDrogue);
+        fix.fired();
+        fix.published();
+
+        // Interrupted confirmation
+        for _ in 0..4 {
+            fix.step_altitude(250.0);
+        }
+        fix.step_altitude(350.0);
+        for _ in 0..4 {
+            fix.step_altitude(250.0);
+        }
+        assert!(fix.fired().is_empty());
+
+        fix.step_altitude(250.0);
+        assert_eq!(fix.fired(), [PyroChannel::Main]);
+        assert_eq!(fix.published(), [Event::RecoveryMainFired]);
+        assert_eq!(fix.state(), Some(RecoveryState::Main));
+
+        // Fired once
+        for _ in 0..10 {
+            fix.step_altitude(100.0);
+        }
+        assert!(fix.fired().is_empty());
+    }
+
+    #[test]
+    fn test_ground_override() {
+        // Main commanded before the drogue
+        let mut fix = Fixture::armed();
+        fix.handle(Event::CmdDeployMain);
+        assert_eq!(fix.fired(), [PyroChannel::Main]);
+        assert_eq!(fix.published(), [Event::RecoveryMainFired]);
+
+        // Main commanded under the drogue, above the deployment altitude
+        let mut fix = Fixture::armed();
+        fix.handle(Event::CmdDeployDrogue);
+        fix.step_altitude(1000.0);
+        fix.handle(Event::CmdDeployMain);
+        assert_eq!(fix.fired(), [PyroChannel::Drogue, PyroChannel::Main]);
+        assert_eq!(
+            fix.published(),
+            [Event::RecoveryDrogueFired, Event::RecoveryMainFired]
+        );
+        assert_eq!(fix.state(), Some(RecoveryState::Main));
+    }
+}
@@ -0,0 +1,299 @@
+use alloc::boxed::Box;
+
+use crate::{
+    Instant,
+    common::{
+        config_hash::{ConfigHash, ConfigHasher},
+        pid::{Pid, PidGains},
+    },
+    component::{Component, LoopContext},
+    datatypes::{
+        actuators::{NUM_SERVOS, ServoCommand},
+        sensors::ImuSensorSample,
+    },
+    events::Event,
+    hal::channel::{Receiver, Sender},
+    mav_crater::ComponentId,
+};
+
+pub struct RollControlHarness {
+    pub rx_imu: Box<dyn Receiver<ImuSensorSample> + Send>,
+
+    pub tx_servo: Box<dyn Sender<ServoCommand> + Send>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RollControlConfig {
+    /// Roll rate error [rad/s] to roll deflection [rad]
+    pub gains: PidGains,
+    /// Roll deflection limit [rad]
+    pub max_deflection_rad: f32,
+    /// Deflection of each servo per rad of roll deflection. With the fin mixing of the simulator,
+    /// δ_roll = -(δ_1 + δ_2 + δ_3 + δ_4) / 4.
+    pub roll_mix: [f32; NUM_SERVOS],
+}
+
+impl Default for RollControlConfig {
+    fn default() -> Self {
+        RollControlConfig {
+            gains: PidGains {
+                kp: 0.05,
+                ki: 0.1,
+                kd: 0.0,
+            },
+            max_deflection_rad: 10f32.to_radians(),
+            roll_mix: [-1.0; NUM_SERVOS],
+        }
+    }
+}
+
+impl ConfigHash for RollControlConfig {
+    fn hash_config(&self, hasher: &mut ConfigHasher) {
+        hasher.write_section("roll_control");
+        self.gains.hash_config(hasher);
+        hasher.write_f32(self.max_deflection_rad);
+        for mix in self.roll_mix {
+            hasher.write_f32(mix);
+        }
+    }
+}
+
+/// Holds the roll rate to zero with the canards, from burnout to apogee.
+///
+/// The fins are centered outside of the control window: the thrust phase is flown unguided,
+/// and the canards must not disturb the deployments.
+pub struct RollControlComponent {
+    harness: RollControlHarness,
+    config: RollControlConfig,
+
+    pid: Pid,
+    active: bool,
+    last_sample: Option<Instant>,
+}
+
+impl RollControlComponent {
+    pub fn new(harness: RollControlHarness, config: RollControlConfig) -> Self {
+        Self {
+            harness,
+            config,
+            pid: Pid::new(config.gains, config.max_deflection_rad),
+            active: false,
+            last_sample: None,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    fn send_deflection(&mut self, t: Instant, roll_rad: f32) {
+        let cmd = ServoCommand {
+            pos_rad: self.config.roll_mix.map(|mix| mix * roll_rad),
+        };
+
+        let _ = self.harness.tx_servo.try_send(t, cmd);
+    }
+}
+
+impl Component for RollControlComponent {
+    fn id(&self) -> ComponentId {
+        ComponentId::RollControl
+    }
+
+    fn handle_event(&mut self, event: Event, context: &mut LoopContext) {
+        match event {
+            Event::FlightBurnout if !self.active => {
+                self.pid.reset();
+                self.last_sample = None;
+                self.active = true;
+            }
+            Event::AdaApogeeDetected | Event::CmdDeployDrogue if self.active => {
+                self.active = false;
+                self.send_deflection(context.step().step_time, 0.0);
+            }
+            _ => {}
+        }
+    }
+
+    fn step(&mut self, context: &mut LoopContext) {
+        let Some(sample) = self.harness.rx_imu.try_recv_last() else {
+            return;
+        };
+
+        if !self.active {
+            return;
+        }
+
+        let dt_s = self
+            .last_sample
+            .map(|last| (sample.t.0 - last.0).to_micros() as f32 / 1e6)
+            .unwrap_or(0.0);
+        self.last_sample = Some(sample.t);
+
+        // Body x axis, along the rocket
+        let roll_rate_rad_s = sample.v.angvel_rad_s.x;
+        let roll_rad = self.pid.update(0.0, roll_rate_rad_s, dt_s);
+
+        self.send_deflection(context.step().step_time, roll_rad);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use nalgebra::Vector3;
+
+    use super::*;
+    use crate::{
+        Duration, DurationU64, InstantU64,
+        component::StepData,
+        hal::channel::testing::{TestReceiver, TestSender, channel},
+    };
+
+    const STEP_MS: u64 = 10;
+
+    struct Fixture {
+        roll: RollControlComponent,
+        tx_imu: TestSender<ImuSensorSample>,
+        rx_servo: TestReceiver<ServoCommand>,
+        t_ms: u64,
+    }
+
+    impl Fixture {
+        fn new(config: RollControlConfig) -> Self {
+            let (tx_imu, rx_imu) = channel();
+            let (tx_servo, rx_servo) = channel();
+
+            let roll = RollControlComponent::new(
+                RollControlHarness {
+                    rx_imu: Box::new(rx_imu),
+                    tx_servo: Box::new(tx_servo),
+                },
+                config,
+            );
+
+            Self {
+                roll,
+                tx_imu,
+                rx_servo,
+                t_ms: 0,
+            }
+        }
+
+        fn context(&self) -> LoopContext {
+            LoopContext::new(StepData {
+                step_time: Instant(InstantU64::from_ticks(self.t_ms * 1000)),
+                step_interval: DurationU64::millis(STEP_MS).into(),
+                step_count: (self.t_ms / STEP_MS) as u32,
+            })
+        }
+
+        fn handle(&mut self, event: Event) {
+            let mut context = self.context();
+            self.roll.handle_event(event, &mut context);
+        }
+
+        /// Steps with an IMU sample measuring `roll_rate_rad_s`
+        fn step(&mut self, roll_rate_rad_s: f32) {
+            self.t_ms += STEP_MS;
+            let mut context = self.context();
+            self.tx_imu.send(
+                context.step().step_time,
+                ImuSensorSample {
+                    accel_m_s2: Vector3::zeros(),
+                    angvel_rad_s: Vector3::new(roll_rate_rad_s, 0.0, 0.0),
+                    temperature_degc: None,
+                    int_latency: Duration(DurationU64::micros(0)),
+                    overrun_count: 0,
+                    delta: None,
+                },
+            );
+            self.roll.step(&mut context);
+        }
+
+        fn commands(&mut self) -> Vec<[f32; NUM_SERVOS]> {
+            let mut commands = Vec::new();
+            while let Some(cmd) = self.rx_servo.try_recv() {
+                commands.push(cmd.v.pos_rad);
+            }
+            commands
+        }
+    }
+
+    #[test]
+    fn test_inactive_before_burnout() {
+        let mut fix = Fixture::new(RollControlConfig::default());
+
+        fix.handle(Event::FlightLiftoff);
+        fix.step(1.0);
+        assert!(!fix.roll.is_active());
+        assert!(fix.commands().is_empty());
+
+        // No control surface movement either on apogee before burnout
+        fix.handle(Event::AdaApogeeDetected);
+        assert!(fix.commands().is_empty());
+    }
+
+    #[test]
+    fn test_roll_mix() {
+        let config = RollControlConfig {
+            roll_mix: [1.0, -1.0, 0.5, 0.0],
+            ..Default::default()
+        };
+        let mut fix = Fixture::new(config);
+
+        fix.handle(Event::FlightBurnout);
+        assert!(fix.roll.is_active());
+
+        // First sample: proportional term only, opposing the roll rate
+        fix.step(0.1);
+        let roll_rad = -config.gains.kp * 0.1;
+        let commands = fix.commands();
+        assert_eq!(commands.len(), 1);
+        for (pos, mix) in commands[0].iter().zip(config.roll_mix) {
+            assert!((pos - mix * roll_rad).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_pid_reset_on_activation() {
+        let mut fix = Fixture::new(RollControlConfig::default());
+
+        fix.handle(Event::FlightBurnout);
+        fix.step(0.1);
+        let first = fix.commands();
+        for _ in 0..50 {
+            fix.step(0.1);
+        }
+        // The integral builds up
+        let wound = fix.commands();
+        assert!(wound.last().unwrap()[0] > first[0][0]);
+
+        fix.handle(Event::AdaApogeeDetected);
+        fix.commands();
+
+        // Reactivated: starts again from the proportional term alone
+        fix.handle(Event::FlightBurnout);
+        fix.step(0.1);
+        assert_eq!(fix.commands(), first);
+    }
+
+    #[test]
+    fn test_centered_after_apogee() {
+        for event in [Event::AdaApogeeDetected, Event::CmdDeployDrogue] {
+            let mut fix = Fixture::new(RollControlConfig::default());
+
+            fix.handle(Event::FlightBurnout);
+            fix.step(0.5);
+            assert!(fix.commands()[0].iter().all(|pos| *pos != 0.0));
+
+            fix.handle(event);
+            assert!(!fix.roll.is_active());
+            assert_eq!(fix.commands(), [[0.0; NUM_SERVOS]]);
+
+            // Stays centered
+            fix.step(0.5);
+            assert!(fix.commands().is_empty());
+        }
+    }
+}
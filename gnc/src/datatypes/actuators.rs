@@ -0,0 +1,26 @@
+use super::version::{InterfaceVersion, Versioned};
+
+/// Fin servos, numbered as in the fin mixing of the simulator
+pub const NUM_SERVOS: usize = 4;
+
+/// Commanded fin deflections. Positive according to the right hand rule over the fin hinge axis.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ServoCommand {
+    pub pos_rad: [f32; NUM_SERVOS],
+}
+
+impl Versioned for ServoCommand {
+    const NAME: &'static str = "ServoCommand";
+    const VERSION: InterfaceVersion = InterfaceVersion::new(1, 0);
+}
+
+/// Commanded airbrake extension, from 0 (retracted) to 1 (fully deployed)
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct AirbrakeCommand {
+    pub extension: f32,
+}
+
+impl Versioned for AirbrakeCommand {
+    const NAME: &'static str = "AirbrakeCommand";
+    const VERSION: InterfaceVersion = InterfaceVersion::new(1, 0);
+}
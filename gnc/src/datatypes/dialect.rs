@@ -0,0 +1,6 @@
+// Generated by `cargo xtask codegen` from interfaces/telemetry.toml: do not edit.
+
+use super::version::InterfaceVersion;
+
+/// Version of the mav_crater dialect
+pub const MAV_CRATER_VERSION: InterfaceVersion = InterfaceVersion::new(3, 0);
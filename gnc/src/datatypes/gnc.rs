@@ -1,5 +1,12 @@
 use nalgebra::{UnitQuaternion, Vector3};
 
+use crate::{
+    Instant,
+    mav_crater::{DownlinkProfile, MavMessage, NavState_DATA},
+};
+
+use super::version::{InterfaceVersion, Versioned};
+
 #[derive(Debug, Clone)]
 pub struct NavigationOutput {
     pub quat_nb: UnitQuaternion<f32>,
@@ -10,3 +17,129 @@ pub struct NavigationOutput {
     pub angvel_unbias_b_rad_s: Vector3<f32>,
     pub acc_unbias_b_m_s2: Vector3<f32>,
 }
+
+impl NavigationOutput {
+    pub fn to_mavlink(&self, ts: Instant, profile: DownlinkProfile) -> MavMessage {
+        let q = self.quat_nb.quaternion();
+
+        MavMessage::NavState(NavState_DATA {
+            timestamp_us: ts.0.duration_since_epoch().to_micros() as i64,
+            profile,
+            quat_nb: [q.w, q.i, q.j, q.k],
+            pos_n_m: self.pos_n_m.into(),
+            vel_n_m_s: self.vel_n_m_s.into(),
+        })
+    }
+}
+
+pub type NavOutputV1 = NavigationOutput;
+
+impl Versioned for NavOutputV1 {
+    const NAME: &'static str = "NavOutput";
+    const VERSION: InterfaceVersion = InterfaceVersion::new(1, 0);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NavStatus {
+    #[default]
+    Initializing,
+    Nominal,
+    Degraded,
+}
+
+/// Navigation output with estimated sensor biases and filter status
+#[derive(Debug, Clone)]
+pub struct NavOutputV2 {
+    pub quat_nb: UnitQuaternion<f32>,
+
+    pub pos_n_m: Vector3<f32>,
+    pub vel_n_m_s: Vector3<f32>,
+
+    pub angvel_unbias_b_rad_s: Vector3<f32>,
+    pub acc_unbias_b_m_s2: Vector3<f32>,
+
+    pub gyro_bias_b_rad_s: Vector3<f32>,
+    pub acc_bias_b_m_s2: Vector3<f32>,
+
+    pub status: NavStatus,
+}
+
+impl Versioned for NavOutputV2 {
+    const NAME: &'static str = "NavOutput";
+    const VERSION: InterfaceVersion = InterfaceVersion::new(2, 0);
+}
+
+/// Upgrade shim: biases are unknown and the filter status is assumed nominal
+impl From<NavOutputV1> for NavOutputV2 {
+    fn from(v1: NavOutputV1) -> Self {
+        NavOutputV2 {
+            quat_nb: v1.quat_nb,
+            pos_n_m: v1.pos_n_m,
+            vel_n_m_s: v1.vel_n_m_s,
+            angvel_unbias_b_rad_s: v1.angvel_unbias_b_rad_s,
+            acc_unbias_b_m_s2: v1.acc_unbias_b_m_s2,
+            gyro_bias_b_rad_s: Vector3::zeros(),
+            acc_bias_b_m_s2: Vector3::zeros(),
+            status: NavStatus::Nominal,
+        }
+    }
+}
+
+/// Downgrade shim for consumers still expecting v1
+impl From<NavOutputV2> for NavOutputV1 {
+    fn from(v2: NavOutputV2) -> Self {
+        NavOutputV1 {
+            quat_nb: v2.quat_nb,
+            pos_n_m: v2.pos_n_m,
+            vel_n_m_s: v2.vel_n_m_s,
+            angvel_unbias_b_rad_s: v2.angvel_unbias_b_rad_s,
+            acc_unbias_b_m_s2: v2.acc_unbias_b_m_s2,
+        }
+    }
+}
+
+/// Attitude of the complementary filter, a cross-check of the navigation attitude
+#[derive(Debug, Clone)]
+pub struct AttitudeEstimate {
+    pub quat_nb: UnitQuaternion<f32>,
+    pub gyro_bias_b_rad_s: Vector3<f32>,
+    /// The accelerometer corrected the attitude since the previous estimate
+    pub accel_aided: bool,
+}
+
+impl Versioned for AttitudeEstimate {
+    const NAME: &'static str = "AttitudeEstimate";
+    const VERSION: InterfaceVersion = InterfaceVersion::new(1, 0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nav_output_round_trip() {
+        let v1 = NavOutputV1 {
+            quat_nb: UnitQuaternion::from_euler_angles(0.1, -0.2, 0.3),
+            pos_n_m: Vector3::new(10.0, -20.0, -300.0),
+            vel_n_m_s: Vector3::new(1.0, 2.0, -50.0),
+            angvel_unbias_b_rad_s: Vector3::new(0.01, 0.02, 0.5),
+            acc_unbias_b_m_s2: Vector3::new(60.0, 0.1, -0.2),
+        };
+
+        let v2 = NavOutputV2::from(v1.clone());
+        assert_eq!(v2.gyro_bias_b_rad_s, Vector3::zeros());
+        assert_eq!(v2.acc_bias_b_m_s2, Vector3::zeros());
+        assert_eq!(v2.status, NavStatus::Nominal);
+
+        let back = NavOutputV1::from(v2);
+        assert_eq!(back.quat_nb, v1.quat_nb);
+        assert_eq!(back.pos_n_m, v1.pos_n_m);
+        assert_eq!(back.vel_n_m_s, v1.vel_n_m_s);
+        assert_eq!(back.angvel_unbias_b_rad_s, v1.angvel_unbias_b_rad_s);
+        assert_eq!(back.acc_unbias_b_m_s2, v1.acc_unbias_b_m_s2);
+
+        // A major bump: v2 data cannot be decoded as v1, nor the other way around
+        assert!(NavOutputV1::check_version(NavOutputV2::VERSION).is_err());
+        assert!(NavOutputV2::check_version(NavOutputV1::VERSION).is_err());
+    }
+}
@@ -1,3 +1,8 @@
+pub mod actuators;
+pub mod dialect;
 pub mod gnc;
 pub mod pin;
+pub mod power;
+pub mod recovery;
 pub mod sensors;
+pub mod version;
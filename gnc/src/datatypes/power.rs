@@ -0,0 +1,42 @@
+use crate::{
+    Instant,
+    mav_crater::{MavMessage, PowerStatus_DATA},
+};
+
+use super::version::{InterfaceVersion, Versioned};
+
+/// Voltages at the ADC pins, before calibration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PowerAdcSample {
+    pub battery_v: f32,
+    pub pyro_v: f32,
+    pub current_sense_v: f32,
+}
+
+/// Calibrated supply measurements
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PowerStatus {
+    pub battery_v: f32,
+    pub pyro_v: f32,
+    /// Battery current, positive when discharging
+    pub current_a: f32,
+    /// Battery below the low threshold, debounced
+    pub battery_low: bool,
+}
+
+impl Versioned for PowerStatus {
+    const NAME: &'static str = "PowerStatus";
+    const VERSION: InterfaceVersion = InterfaceVersion::new(1, 0);
+}
+
+impl PowerStatus {
+    pub fn to_mavlink(&self, ts: Instant) -> MavMessage {
+        MavMessage::PowerStatus(PowerStatus_DATA {
+            timestamp_us: ts.0.duration_since_epoch().to_micros() as i64,
+            battery_v: self.battery_v,
+            pyro_v: self.pyro_v,
+            current_a: self.current_a,
+            battery_low: self.battery_low as u8,
+        })
+    }
+}
@@ -0,0 +1,64 @@
+use crate::{
+    Instant,
+    mav_crater::{MavMessage, RecoveryState, RecoveryStatus_DATA},
+};
+
+use super::version::{InterfaceVersion, Versioned};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PyroChannel {
+    Drogue,
+    Main,
+}
+
+/// Fires a pyro channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PyroCommand {
+    pub channel: PyroChannel,
+}
+
+impl Versioned for PyroCommand {
+    const NAME: &'static str = "PyroCommand";
+    const VERSION: InterfaceVersion = InterfaceVersion::new(1, 0);
+}
+
+#[derive(Debug, Clone)]
+pub struct RecoveryStatus {
+    pub state: RecoveryState,
+    pub drogue_fired: bool,
+    pub main_fired: bool,
+    /// Latest ADA altitude, used for the main deployment
+    pub altitude_m: Option<f32>,
+}
+
+impl Versioned for RecoveryStatus {
+    const NAME: &'static str = "RecoveryStatus";
+    const VERSION: InterfaceVersion = InterfaceVersion::new(1, 0);
+}
+
+impl RecoveryStatus {
+    pub fn to_mavlink(&self, ts: Instant) -> MavMessage {
+        MavMessage::RecoveryStatus(RecoveryStatus_DATA {
+            timestamp_us: ts.0.duration_since_epoch().to_micros() as i64,
+            state: self.state,
+            drogue_fired: self.drogue_fired as u8,
+            main_fired: self.main_fired as u8,
+            altitude_m: self.altitude_m.unwrap_or(f32::NAN),
+        })
+    }
+}
+
+impl From<&RecoveryStatus_DATA> for RecoveryStatus {
+    fn from(data: &RecoveryStatus_DATA) -> Self {
+        Self {
+            state: data.state,
+            drogue_fired: data.drogue_fired != 0,
+            main_fired: data.main_fired != 0,
+            altitude_m: if !data.altitude_m.is_nan() {
+                Some(data.altitude_m)
+            } else {
+                None
+            },
+        }
+    }
+}
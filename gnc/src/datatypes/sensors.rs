@@ -2,16 +2,26 @@ use core::f32;
 
 use crate::{
     Duration, DurationU64, Instant,
-    mav_crater::{self, MavMessage, SensImuSample_DATA, SensPressureSample_DATA},
+    mav_crater::{
+        self, GnssFixType, MavMessage, SensGnssSample_DATA, SensImuSample_DATA,
+        SensMagSample_DATA, SensPressureSample_DATA,
+    },
 };
 use nalgebra::Vector3;
 
-#[derive(Debug, Clone)]
+use super::version::{InterfaceVersion, Versioned};
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct PressureSensorSample {
     pub pressure_pa: f32,
     pub temperature_degc: Option<f32>,
 }
 
+impl Versioned for PressureSensorSample {
+    const NAME: &'static str = "PressureSensorSample";
+    const VERSION: InterfaceVersion = InterfaceVersion::new(1, 0);
+}
+
 impl PressureSensorSample {
     pub fn to_mavlink(&self, id: mav_crater::PressureSensorId, ts: Instant) -> MavMessage {
         MavMessage::SensPressureSample(SensPressureSample_DATA {
@@ -42,6 +52,15 @@ impl From<&SensPressureSample_DATA> for PressureSensorSample {
     }
 }
 
+/// Angular & velocity increments over the interval since the previous IMU sample, integrated
+/// from the sampled rates with the trapezoidal rule
+#[derive(Debug, Clone)]
+pub struct ImuDeltaSample {
+    pub dt: Duration,
+    pub delta_angle_rad: Vector3<f32>,
+    pub delta_velocity_m_s: Vector3<f32>,
+}
+
 #[derive(Debug, Clone)]
 pub struct ImuSensorSample {
     pub accel_m_s2: Vector3<f32>,
@@ -49,6 +68,13 @@ pub struct ImuSensorSample {
     pub temperature_degc: Option<f32>,
     pub int_latency: Duration,
     pub overrun_count: u8,
+    /// Integrated increments, if the sensor is configured to output them
+    pub delta: Option<ImuDeltaSample>,
+}
+
+impl Versioned for ImuSensorSample {
+    const NAME: &'static str = "ImuSensorSample";
+    const VERSION: InterfaceVersion = InterfaceVersion::new(1, 1);
 }
 
 impl ImuSensorSample {
@@ -83,6 +109,7 @@ impl From<&SensImuSample_DATA> for ImuSensorSample {
             },
             int_latency: DurationU64::micros(data.latency_us as u64).into(),
             overrun_count: data.overrun_count,
+            delta: None,
         }
     }
 }
@@ -93,7 +120,117 @@ pub struct GpsSensorSample {
     pub vel_n_m_s: Vector3<f32>,
 }
 
+impl Versioned for GpsSensorSample {
+    const NAME: &'static str = "GpsSensorSample";
+    const VERSION: InterfaceVersion = InterfaceVersion::new(1, 0);
+}
+
+/// Fix of a GNSS receiver, in the units of the receiver: geodetic position & NED velocity
+#[derive(Debug, Clone)]
+pub struct GnssSensorSample {
+    pub fix_type: GnssFixType,
+    pub num_sats: u8,
+    /// Latitude & longitude (WGS84) [1e-7 deg]
+    pub lat_deg_e7: i32,
+    pub lon_deg_e7: i32,
+    pub alt_msl_m: f32,
+    pub vel_n_m_s: Vector3<f32>,
+    /// Accuracy estimates of the receiver
+    pub h_acc_m: f32,
+    pub v_acc_m: f32,
+    pub speed_acc_m_s: f32,
+}
+
+impl Versioned for GnssSensorSample {
+    const NAME: &'static str = "GnssSensorSample";
+    const VERSION: InterfaceVersion = InterfaceVersion::new(1, 0);
+}
+
+impl GnssSensorSample {
+    pub fn lat_deg(&self) -> f64 {
+        self.lat_deg_e7 as f64 * 1e-7
+    }
+
+    pub fn lon_deg(&self) -> f64 {
+        self.lon_deg_e7 as f64 * 1e-7
+    }
+
+    /// Whether the position is usable by the navigation
+    pub fn has_3d_fix(&self) -> bool {
+        matches!(
+            self.fix_type,
+            GnssFixType::Fix3d | GnssFixType::GnssDeadReckoning
+        )
+    }
+
+    pub fn to_mavlink(&self, id: mav_crater::GnssSensorId, ts: Instant) -> MavMessage {
+        MavMessage::SensGnssSample(SensGnssSample_DATA {
+            sensor_id: id,
+            timestamp_us: ts.0.duration_since_epoch().to_micros() as i64,
+            fix_type: self.fix_type,
+            num_sats: self.num_sats,
+            lat_deg_e7: self.lat_deg_e7,
+            lon_deg_e7: self.lon_deg_e7,
+            alt_msl_m: self.alt_msl_m,
+            vel_n_m_s: self.vel_n_m_s.into(),
+            h_acc_m: self.h_acc_m,
+            v_acc_m: self.v_acc_m,
+            speed_acc_m_s: self.speed_acc_m_s,
+        })
+    }
+}
+
+impl From<SensGnssSample_DATA> for GnssSensorSample {
+    fn from(data: SensGnssSample_DATA) -> Self {
+        GnssSensorSample::from(&data)
+    }
+}
+
+impl From<&SensGnssSample_DATA> for GnssSensorSample {
+    fn from(data: &SensGnssSample_DATA) -> Self {
+        Self {
+            fix_type: data.fix_type,
+            num_sats: data.num_sats,
+            lat_deg_e7: data.lat_deg_e7,
+            lon_deg_e7: data.lon_deg_e7,
+            alt_msl_m: data.alt_msl_m,
+            vel_n_m_s: data.vel_n_m_s.into(),
+            h_acc_m: data.h_acc_m,
+            v_acc_m: data.v_acc_m,
+            speed_acc_m_s: data.speed_acc_m_s,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct MagnetometerSensorSample {
     pub mag_field_b_gauss: Vector3<f32>,
 }
+
+impl Versioned for MagnetometerSensorSample {
+    const NAME: &'static str = "MagnetometerSensorSample";
+    const VERSION: InterfaceVersion = InterfaceVersion::new(1, 0);
+}
+
+impl MagnetometerSensorSample {
+    pub fn to_mavlink(&self, ts: Instant) -> MavMessage {
+        MavMessage::SensMagSample(SensMagSample_DATA {
+            timestamp_us: ts.0.duration_since_epoch().to_micros() as i64,
+            mag_field_b_gauss: self.mag_field_b_gauss.into(),
+        })
+    }
+}
+
+impl From<SensMagSample_DATA> for MagnetometerSensorSample {
+    fn from(data: SensMagSample_DATA) -> Self {
+        MagnetometerSensorSample::from(&data)
+    }
+}
+
+impl From<&SensMagSample_DATA> for MagnetometerSensorSample {
+    fn from(data: &SensMagSample_DATA) -> Self {
+        Self {
+            mag_field_b_gauss: data.mag_field_b_gauss.into(),
+        }
+    }
+}
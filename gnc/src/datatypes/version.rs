@@ -0,0 +1,74 @@
+use core::fmt;
+
+use thiserror::Error;
+
+/// Semantic version of an interface struct.
+///
+/// The major number is bumped on changes that break the binary layout or the meaning of
+/// existing fields, the minor number when fields are added in a backward compatible way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct InterfaceVersion {
+    pub major: u16,
+    pub minor: u16,
+}
+
+impl InterfaceVersion {
+    pub const fn new(major: u16, minor: u16) -> Self {
+        InterfaceVersion { major, minor }
+    }
+
+    /// Data produced with version `self` can be read by a consumer expecting `expected`
+    pub fn is_compatible_with(&self, expected: &InterfaceVersion) -> bool {
+        self.major == expected.major && self.minor >= expected.minor
+    }
+}
+
+impl fmt::Display for InterfaceVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("Incompatible interface '{name}': expected v{expected}, found v{found}")]
+pub struct VersionMismatch {
+    pub name: &'static str,
+    pub expected: InterfaceVersion,
+    pub found: InterfaceVersion,
+}
+
+/// Interface structs exchanged between sim, fsw and ground tooling
+pub trait Versioned {
+    const NAME: &'static str;
+    const VERSION: InterfaceVersion;
+
+    /// Checks that data tagged with version `found` can be decoded as `Self`
+    fn check_version(found: InterfaceVersion) -> Result<(), VersionMismatch> {
+        if found.is_compatible_with(&Self::VERSION) {
+            Ok(())
+        } else {
+            Err(VersionMismatch {
+                name: Self::NAME,
+                expected: Self::VERSION,
+                found,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compatibility() {
+        let v1_0 = InterfaceVersion::new(1, 0);
+        let v1_2 = InterfaceVersion::new(1, 2);
+        let v2_0 = InterfaceVersion::new(2, 0);
+
+        assert!(v1_2.is_compatible_with(&v1_0));
+        assert!(!v1_0.is_compatible_with(&v1_2));
+        assert!(!v2_0.is_compatible_with(&v1_0));
+        assert!(!v1_0.is_compatible_with(&v2_0));
+    }
+}
@@ -1,4 +1,10 @@
-#[derive(Debug, Clone, Copy, PartialEq)]
+use alloc::format;
+use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
+
+use crate::mav_crater::GroundCommandId;
+
+#[derive(Debug, Clone, Copy, PartialEq, FromPrimitive)]
 pub enum Event {
     Step,
 
@@ -6,15 +12,60 @@ pub enum Event {
     
     // Flight State Transitions
     FlightStateReady,
+    /// Armed, the configuration is frozen
+    FlightStateArmed,
     FlightLiftoff,
+    FlightBurnout,
+    FlightLanded,
 
     // Fmm
     CmdFmmCalibrate,
     CmdFmmArm,
+    CmdFmmDisarm,
     CmdFmmForceLiftoff,
 
     // Ada
     AdaCalibrationDone,
+    AdaApogeeDetected,
 
     CmdAdaCalibrate,
+
+    // Recovery
+    CmdDeployDrogue,
+    CmdDeployMain,
+    RecoveryDrogueFired,
+    RecoveryMainFired,
+
+    // Power
+    PowerBatteryLow,
+
+    // Event log
+    CmdEventLogDump,
+
+    // Supervisor
+    /// A component exceeded its execution time budget. Raised with the component as the source.
+    ComponentOverrun,
+}
+
+impl Event {
+    /// Event with the given variant name, eg. "CmdFmmArm"
+    pub fn from_name(name: &str) -> Option<Event> {
+        (0..)
+            .map_while(Event::from_u32)
+            .find(|event| format!("{event:?}") == name)
+    }
+
+    /// Event raised by a command of the ground station
+    pub fn from_ground_command(command: GroundCommandId) -> Event {
+        match command {
+            GroundCommandId::FmmCalibrate => Event::CmdFmmCalibrate,
+            GroundCommandId::FmmArm => Event::CmdFmmArm,
+            GroundCommandId::FmmDisarm => Event::CmdFmmDisarm,
+            GroundCommandId::FmmForceLiftoff => Event::CmdFmmForceLiftoff,
+            GroundCommandId::AdaCalibrate => Event::CmdAdaCalibrate,
+            GroundCommandId::DeployDrogue => Event::CmdDeployDrogue,
+            GroundCommandId::DeployMain => Event::CmdDeployMain,
+            GroundCommandId::DumpEventLog => Event::CmdEventLogDump,
+        }
+    }
 }
@@ -0,0 +1,186 @@
+use alloc::boxed::Box;
+use heapless::HistoryBuffer;
+
+use crate::{
+    Instant,
+    common::Ts,
+    hal::channel::Sender,
+    mav_crater::{FlightMode, FmmTransition_DATA, MavMessage},
+};
+
+use super::EventItem;
+
+/// Entries kept by the event log of the component loop, about the events of a whole flight
+pub const EVENT_LOG_LEN: usize = 128;
+
+/// Entries sent to the ground per step while dumping the log
+const DUMP_ENTRIES_PER_STEP: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EventLogEntry {
+    Event(EventItem),
+    /// The flight mode manager entered a new flight mode
+    FmmTransition(FlightMode),
+}
+
+impl EventLogEntry {
+    pub fn to_mavlink(&self, ts: Instant) -> MavMessage {
+        match self {
+            EventLogEntry::Event(item) => item.to_mavlink(ts),
+            EventLogEntry::FmmTransition(flight_mode) => {
+                MavMessage::FmmTransition(FmmTransition_DATA {
+                    timestamp_us: ts.0.duration_since_epoch().to_micros() as i64,
+                    flight_mode: *flight_mode,
+                })
+            }
+        }
+    }
+}
+
+/// Black box of the flight: the latest `N` events & flight mode transitions, with their
+/// timestamps. The oldest entries are overwritten once full.
+///
+/// Entries are numbered in recording order, so that a reader can resume where it stopped.
+pub struct EventLog<const N: usize> {
+    entries: HistoryBuffer<Ts<EventLogEntry>, N>,
+    num_recorded: u32,
+}
+
+impl<const N: usize> EventLog<N> {
+    pub fn new() -> Self {
+        Self {
+            entries: HistoryBuffer::new(),
+            num_recorded: 0,
+        }
+    }
+
+    pub fn record(&mut self, t: Instant, entry: EventLogEntry) {
+        self.entries.write(Ts::new(t, entry));
+        self.num_recorded += 1;
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.len() == 0
+    }
+
+    /// Entries overwritten since the start
+    pub fn num_lost(&self) -> u32 {
+        self.num_recorded - self.entries.len() as u32
+    }
+
+    /// Number of the oldest entry still in the log
+    pub fn first_seq(&self) -> u32 {
+        self.num_lost()
+    }
+
+    /// Number of the next entry to be recorded
+    pub fn next_seq(&self) -> u32 {
+        self.num_recorded
+    }
+
+    /// Entries from number `seq` on, oldest first. Entries already overwritten are skipped.
+    pub fn iter_from(&self, seq: u32) -> impl Iterator<Item = &Ts<EventLogEntry>> {
+        let skip = seq.saturating_sub(self.first_seq()) as usize;
+        self.entries.oldest_ordered().skip(skip)
+    }
+
+    /// All the entries, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &Ts<EventLogEntry>> {
+        self.entries.oldest_ordered()
+    }
+}
+
+impl<const N: usize> Default for EventLog<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Sends the event log to the ground a few entries per step, so that the downlink is not
+/// flooded. Entries recorded during the dump are sent as well.
+pub struct EventLogDump {
+    tx: Box<dyn Sender<MavMessage> + Send>,
+    /// Number of the next entry to send, while dumping
+    next: Option<u32>,
+}
+
+impl EventLogDump {
+    pub fn new(tx: Box<dyn Sender<MavMessage> + Send>) -> Self {
+        Self { tx, next: None }
+    }
+
+    /// Restarts the dump from the oldest entry
+    pub fn start<const N: usize>(&mut self, log: &EventLog<N>) {
+        self.next = Some(log.first_seq());
+    }
+
+    pub fn step<const N: usize>(&mut self, t: Instant, log: &EventLog<N>) {
+        let Some(next) = self.next else {
+            return;
+        };
+
+        let mut seq = next.max(log.first_seq());
+        for entry in log.iter_from(seq).take(DUMP_ENTRIES_PER_STEP) {
+            let _ = self.tx.try_send(t, entry.v.to_mavlink(entry.t));
+            seq += 1;
+        }
+
+        self.next = (seq < log.next_seq()).then_some(seq);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InstantU64, events::Event, mav_crater::ComponentId};
+
+    fn event(event: Event) -> EventLogEntry {
+        EventLogEntry::Event(EventItem {
+            src: ComponentId::FlightModeManager,
+            event,
+        })
+    }
+
+    fn t_ms(ms: u64) -> Instant {
+        Instant(InstantU64::from_ticks(ms * 1000))
+    }
+
+    #[test]
+    fn test_event_log_wraparound() {
+        let mut log = EventLog::<3>::new();
+        assert!(log.is_empty());
+
+        log.record(t_ms(0), EventLogEntry::FmmTransition(FlightMode::Armed));
+        log.record(t_ms(1), event(Event::FlightLiftoff));
+        assert_eq!(log.len(), 2);
+        assert_eq!(log.num_lost(), 0);
+
+        log.record(t_ms(2), EventLogEntry::FmmTransition(FlightMode::Liftoff));
+        log.record(t_ms(3), event(Event::FlightBurnout));
+        log.record(t_ms(4), EventLogEntry::FmmTransition(FlightMode::Coast));
+
+        // The two oldest are overwritten
+        assert_eq!(log.len(), 3);
+        assert_eq!(log.num_lost(), 2);
+        assert_eq!((log.first_seq(), log.next_seq()), (2, 5));
+
+        assert!(
+            log.iter()
+                .map(|e| e.t.0)
+                .eq([t_ms(2).0, t_ms(3).0, t_ms(4).0])
+        );
+        assert_eq!(
+            log.iter().last().unwrap().v,
+            EventLogEntry::FmmTransition(FlightMode::Coast)
+        );
+
+        // Resumes from a given entry, skipping the overwritten ones
+        assert_eq!(log.iter_from(3).next().unwrap().t.0, t_ms(3).0);
+        assert_eq!(log.iter_from(0).count(), 3);
+        assert_eq!(log.iter_from(5).count(), 0);
+    }
+}
@@ -1,6 +1,10 @@
 use core::sync::atomic::AtomicBool;
 
-use crate::{Instant, common::Ts, mav_crater::ComponentId};
+use crate::{
+    Instant,
+    common::Ts,
+    mav_crater::{ComponentId, GncEvent_DATA, MavMessage},
+};
 
 use super::event::Event;
 use alloc::sync::Arc;
@@ -15,6 +19,16 @@ pub struct EventItem {
     pub event: Event,
 }
 
+impl EventItem {
+    pub fn to_mavlink(&self, ts: Instant) -> MavMessage {
+        MavMessage::GncEvent(GncEvent_DATA {
+            timestamp_us: ts.0.duration_since_epoch().to_micros() as i64,
+            source: self.src,
+            event: self.event as u16,
+        })
+    }
+}
+
 #[derive(Default)]
 pub struct EventQueue {
     dispatcher: Arc<EventQueueInner>,
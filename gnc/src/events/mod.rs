@@ -1,5 +1,7 @@
 mod event;
+mod event_log;
 mod event_queue;
 
 pub use event::Event;
+pub use event_log::{EVENT_LOG_LEN, EventLog, EventLogDump, EventLogEntry};
 pub use event_queue::{EventItem, EventPublisher, EventQueue};
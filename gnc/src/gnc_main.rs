@@ -3,19 +3,72 @@ use thiserror::Error;
 
 use crate::{
     DurationU64,
+    common::{
+        config_hash::{ConfigHash, ConfigHasher},
+        imu_decimator::ImuDecimatorConfig,
+        mahony::MahonyConfig,
+        power_monitor::PowerMonitorConfig,
+    },
     component::StepData,
-    component_loop::{ComponentLoop, ComponentLoopBuilder, ComponentLoopBuilderError},
+    component_loop::{
+        ComponentLoop, ComponentLoopBuilder, ComponentLoopBuilderError, SupervisorConfig,
+        SupervisorHarness,
+    },
     components::{
-        ada::{AdaComponent, AdaHarness},
-        fmm::{FlightModeManager, FmmHarness},
+        ada::{AdaComponent, AdaConfig, AdaHarness},
+        airbrake_guidance::{
+            AirbrakeGuidanceComponent, AirbrakeGuidanceConfig, AirbrakeGuidanceHarness,
+        },
+        attitude_estimator::{AttitudeEstimatorComponent, AttitudeEstimatorHarness},
+        downlink::{DownlinkComponent, DownlinkConfig, DownlinkHarness},
+        fmm::{FlightModeManager, FmmConfig, FmmHarness},
         navigation::{NavigationComponent, NavigationHarness},
+        recovery::{RecoveryComponent, RecoveryConfig, RecoveryHarness},
+        roll_control::{RollControlComponent, RollControlConfig, RollControlHarness},
     },
-    events::{EventItem, EventQueue},
-    hal::channel::Sender,
-    mav_crater::ComponentId,
+    events::{EVENT_LOG_LEN, EventItem, EventLog, EventQueue},
+    hal::{channel::Sender, servo::ServoConfig},
+    io::{
+        burst_capture::{BurstCaptureComponent, BurstCaptureConfig, BurstCaptureHarness},
+        log_transfer::{LogTransferComponent, LogTransferConfig, LogTransferHarness},
+        mavlink_dispatcher::{CraterMavlinkDispatcher, MavlinkDispatcherHarness},
+    },
+    mav_crater::{ComponentId, MavMessage},
 };
 
-const NUM_COMPONENTS: usize = 3;
+const NUM_COMPONENTS: usize = 11;
+
+/// Divisors of the base loop rate the components step at, staggered by the component loop. The
+/// components not listed step at the base rate.
+#[derive(Debug, Clone, Copy)]
+pub struct RateDivisors {
+    pub fmm: u32,
+    pub ada: u32,
+    pub recovery: u32,
+    pub downlink: u32,
+}
+
+impl Default for RateDivisors {
+    /// At a 500 Hz base rate, the navigation: FMM at 100 Hz, ADA at 50 Hz
+    fn default() -> Self {
+        RateDivisors {
+            fmm: 5,
+            ada: 10,
+            recovery: 5,
+            downlink: 5,
+        }
+    }
+}
+
+impl ConfigHash for RateDivisors {
+    fn hash_config(&self, hasher: &mut ConfigHasher) {
+        hasher.write_section("rate_divisors");
+        hasher.write_u64(self.fmm as u64);
+        hasher.write_u64(self.ada as u64);
+        hasher.write_u64(self.recovery as u64);
+        hasher.write_u64(self.downlink as u64);
+    }
+}
 
 #[derive(Debug, Error, Clone)]
 pub enum CraterLoopError {
@@ -25,9 +78,87 @@ pub enum CraterLoopError {
 
 pub struct CraterLoopHarness {
     pub tx_events: Box<dyn Sender<EventItem> + Send>,
+    /// Event log entries, sent to the ground on request
+    pub tx_event_log: Box<dyn Sender<MavMessage> + Send>,
+    pub supervisor: SupervisorHarness,
     pub fmm: FmmHarness,
     pub ada: AdaHarness,
     pub nav: NavigationHarness,
+    pub recovery: RecoveryHarness,
+    pub downlink: DownlinkHarness,
+    pub dispatcher: MavlinkDispatcherHarness,
+    pub log_transfer: LogTransferHarness,
+    pub burst_capture: BurstCaptureHarness,
+    pub roll_control: RollControlHarness,
+    pub airbrake_guidance: AirbrakeGuidanceHarness,
+    pub attitude: AttitudeEstimatorHarness,
+}
+
+/// Configuration of all the components of the flight software
+#[derive(Debug, Clone, Copy)]
+pub struct CraterConfig {
+    pub fmm: FmmConfig,
+    pub ada: AdaConfig,
+    pub recovery: RecoveryConfig,
+    pub nav_imu: ImuDecimatorConfig,
+    pub downlink: DownlinkConfig,
+    pub log_transfer: LogTransferConfig,
+    pub burst_capture: BurstCaptureConfig,
+    pub roll_control: RollControlConfig,
+    pub airbrake_guidance: AirbrakeGuidanceConfig,
+    /// Complementary attitude filter, independent of the navigation
+    pub attitude: MahonyConfig,
+    /// Execution time budgets of the components
+    pub supervisor: SupervisorConfig,
+    pub rate_divisors: RateDivisors,
+    /// Servo calibration, applied by the servo output driver
+    pub servo: ServoConfig,
+    /// ADC calibration & low battery threshold, applied by the power monitoring task
+    pub power: PowerMonitorConfig,
+}
+
+impl Default for CraterConfig {
+    fn default() -> Self {
+        CraterConfig {
+            fmm: FmmConfig::default(),
+            ada: AdaConfig::default(),
+            recovery: RecoveryConfig::default(),
+            // The IMU is sampled at a much higher rate than navigation runs at
+            nav_imu: ImuDecimatorConfig {
+                output_period: DurationU64::millis(5).into(),
+                lpf_cutoff_hz: Some(100.0),
+            },
+            downlink: DownlinkConfig::default(),
+            log_transfer: LogTransferConfig::default(),
+            burst_capture: BurstCaptureConfig::default(),
+            roll_control: RollControlConfig::default(),
+            airbrake_guidance: AirbrakeGuidanceConfig::default(),
+            attitude: MahonyConfig::default(),
+            supervisor: SupervisorConfig::default(),
+            rate_divisors: RateDivisors::default(),
+            servo: ServoConfig::default(),
+            power: PowerMonitorConfig::default(),
+        }
+    }
+}
+
+impl ConfigHash for CraterConfig {
+    fn hash_config(&self, hasher: &mut ConfigHasher) {
+        self.fmm.hash_config(hasher);
+        self.ada.hash_config(hasher);
+        self.recovery.hash_config(hasher);
+        self.nav_imu.hash_config(hasher);
+        self.downlink.hash_config(hasher);
+        self.log_transfer.hash_config(hasher);
+        self.burst_capture.hash_config(hasher);
+        self.roll_control.hash_config(hasher);
+        self.airbrake_guidance.hash_config(hasher);
+        self.attitude.hash_config(hasher);
+        self.supervisor.hash_config(hasher);
+        self.rate_divisors.hash_config(hasher);
+        self.servo.hash_config(hasher);
+        self.power.hash_config(hasher);
+    }
 }
 
 pub struct CraterLoop {
@@ -38,31 +169,79 @@ impl CraterLoop {
     pub fn new(
         event_queue: EventQueue,
         harness: CraterLoopHarness,
+        config: CraterConfig,
     ) -> Result<Self, CraterLoopError> {
+        // Frozen for the whole run, downlinked at arming for the ground to verify
+        let config_hash = config.config_hash();
+
         let mut loop_builder = ComponentLoopBuilder::<NUM_COMPONENTS>::new();
 
         let fmm = FlightModeManager::new(
             harness.fmm,
             event_queue.get_publisher(ComponentId::FlightModeManager),
+            config.fmm,
         );
-        loop_builder.add_component(fmm)?;
+        loop_builder.add_component_decimated(fmm, config.rate_divisors.fmm)?;
 
         let ada = AdaComponent::new(
             harness.ada,
             event_queue.get_publisher(ComponentId::ApogeeDetectionAlgorithm),
-            DurationU64::secs(5).into(),
+            config.ada,
         );
-        loop_builder.add_component(ada)?;
+        loop_builder.add_component_decimated(ada, config.rate_divisors.ada)?;
 
-        let nav = NavigationComponent::new(harness.nav);
+        let recovery = RecoveryComponent::new(
+            harness.recovery,
+            event_queue.get_publisher(ComponentId::Recovery),
+            config.recovery,
+        );
+        loop_builder.add_component_decimated(recovery, config.rate_divisors.recovery)?;
+
+        let nav = NavigationComponent::new(harness.nav, config.nav_imu);
         loop_builder.add_component(nav)?;
 
+        let downlink = DownlinkComponent::new(harness.downlink, config.downlink, config_hash);
+        loop_builder.add_component_decimated(downlink, config.rate_divisors.downlink)?;
+
+        // Ground commands are dispatched as events from the ground
+        let dispatcher = CraterMavlinkDispatcher::new(
+            harness.dispatcher,
+            event_queue.get_publisher(ComponentId::Ground),
+        );
+        loop_builder.add_component(dispatcher)?;
+
+        let log_transfer = LogTransferComponent::new(harness.log_transfer, config.log_transfer);
+        loop_builder.add_component(log_transfer)?;
+
+        let burst_capture = BurstCaptureComponent::new(harness.burst_capture, config.burst_capture);
+        loop_builder.add_component(burst_capture)?;
+
+        let roll_control = RollControlComponent::new(harness.roll_control, config.roll_control);
+        loop_builder.add_component(roll_control)?;
+
+        let airbrake_guidance =
+            AirbrakeGuidanceComponent::new(harness.airbrake_guidance, config.airbrake_guidance);
+        loop_builder.add_component(airbrake_guidance)?;
+
+        let attitude = AttitudeEstimatorComponent::new(harness.attitude, config.attitude);
+        loop_builder.add_component(attitude)?;
+
         Ok(CraterLoop {
-            component_loop: loop_builder.build(event_queue, harness.tx_events),
+            component_loop: loop_builder.build(
+                event_queue,
+                harness.tx_events,
+                harness.tx_event_log,
+                harness.supervisor,
+                config.supervisor,
+            ),
         })
     }
 
     pub fn step(&mut self, step: &StepData) {
         self.component_loop.step(step);
     }
+
+    pub fn event_log(&self) -> &EventLog<EVENT_LOG_LEN> {
+        self.component_loop.event_log()
+    }
 }
@@ -48,3 +48,81 @@ pub trait Channel<T, const RX: usize, const TX: usize> {
     fn new_sender(&mut self) -> Result<Box<dyn Sender<T>>, ChannelError>;
     fn new_receiver(&mut self) -> Result<Box<dyn Receiver<T>>, ChannelError>;
 }
+
+/// In-memory channel for the component tests
+#[cfg(test)]
+pub(crate) mod testing {
+    use alloc::sync::Arc;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use heapless::mpmc::MpMcQueue;
+
+    use super::*;
+
+    const CAPACITY: usize = 32;
+
+    struct Inner<T> {
+        queue: MpMcQueue<Ts<T>, CAPACITY>,
+        len: AtomicUsize,
+    }
+
+    pub struct TestSender<T>(Arc<Inner<T>>);
+
+    pub struct TestReceiver<T>(Arc<Inner<T>>);
+
+    pub fn channel<T>() -> (TestSender<T>, TestReceiver<T>) {
+        let inner = Arc::new(Inner {
+            queue: MpMcQueue::new(),
+            len: AtomicUsize::new(0),
+        });
+        (TestSender(inner.clone()), TestReceiver(inner))
+    }
+
+    impl<T> TestSender<T> {
+        pub fn send(&self, ts: Instant, item: T) {
+            if self.0.queue.enqueue(Ts::new(ts, item)).is_err() {
+                panic!("Test channel full");
+            }
+            self.0.len.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    impl<T> Sender<T> for TestSender<T> {
+        fn try_send(&mut self, ts: Instant, item: T) -> Result<(), Full<T>> {
+            self.0.queue.enqueue(Ts::new(ts, item)).map_err(Full)?;
+            self.0.len.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn send_immediate(&mut self, ts: Instant, item: T) {
+            self.send(ts, item);
+        }
+    }
+
+    impl<T> Receiver<T> for TestReceiver<T> {
+        fn try_recv(&mut self) -> Option<Ts<T>> {
+            let item = self.0.queue.dequeue()?;
+            self.0.len.fetch_sub(1, Ordering::SeqCst);
+            Some(item)
+        }
+
+        fn len(&self) -> usize {
+            self.0.len.load(Ordering::SeqCst)
+        }
+
+        fn capacity(&self) -> usize {
+            CAPACITY
+        }
+
+        fn is_empty(&self) -> bool {
+            self.len() == 0
+        }
+
+        fn is_full(&self) -> bool {
+            self.len() == CAPACITY
+        }
+
+        fn num_lagged(&self) -> usize {
+            0
+        }
+    }
+}
@@ -6,4 +6,5 @@ pub trait Hal {
 
 }
 
-pub mod channel;
\ No newline at end of file
+pub mod channel;
+pub mod servo;
\ No newline at end of file
@@ -0,0 +1,115 @@
+use crate::{
+    common::config_hash::{ConfigHash, ConfigHasher},
+    datatypes::actuators::{NUM_SERVOS, ServoCommand},
+};
+
+/// Pulse widths of a servo channel
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ServoCalibration {
+    /// Travel limits [µs]
+    pub min_pulse_us: f32,
+    pub max_pulse_us: f32,
+    /// Pulse of the fin at 0 deflection [µs]
+    pub center_pulse_us: f32,
+    /// [µs/rad]. Negative if the servo is mounted reversed.
+    pub gain_us_rad: f32,
+}
+
+impl ServoCalibration {
+    /// Pulse width commanding `pos_rad`, clamped to the travel limits
+    pub fn pulse_us(&self, pos_rad: f32) -> f32 {
+        (self.center_pulse_us + self.gain_us_rad * pos_rad)
+            .clamp(self.min_pulse_us, self.max_pulse_us)
+    }
+}
+
+impl Default for ServoCalibration {
+    /// Standard hobby servo: 1000 to 2000 µs, 10 µs/deg
+    fn default() -> Self {
+        ServoCalibration {
+            min_pulse_us: 1000.0,
+            max_pulse_us: 2000.0,
+            center_pulse_us: 1500.0,
+            gain_us_rad: 10.0 * 180.0 / core::f32::consts::PI,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ServoConfig {
+    pub channels: [ServoCalibration; NUM_SERVOS],
+}
+
+impl ConfigHash for ServoConfig {
+    fn hash_config(&self, hasher: &mut ConfigHasher) {
+        hasher.write_section("servo");
+        for cal in &self.channels {
+            hasher.write_f32(cal.min_pulse_us);
+            hasher.write_f32(cal.max_pulse_us);
+            hasher.write_f32(cal.center_pulse_us);
+            hasher.write_f32(cal.gain_us_rad);
+        }
+    }
+}
+
+/// Servo outputs driven by pulse width, such as PWM timer channels
+pub trait ServoOutput {
+    fn calibration(&self, channel: usize) -> &ServoCalibration;
+
+    /// Outputs a pulse of `pulse_us` on `channel`
+    fn set_pulse_us(&mut self, channel: usize, pulse_us: f32);
+
+    fn set_position_rad(&mut self, channel: usize, pos_rad: f32) {
+        let pulse_us = self.calibration(channel).pulse_us(pos_rad);
+        self.set_pulse_us(channel, pulse_us);
+    }
+
+    /// Applies a command of the control components. NaN deflections are commanded to the center.
+    fn apply(&mut self, cmd: &ServoCommand) {
+        for (channel, pos_rad) in cmd.pos_rad.iter().enumerate() {
+            let pos_rad = if pos_rad.is_nan() { 0.0 } else { *pos_rad };
+            self.set_position_rad(channel, pos_rad);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestServos {
+        config: ServoConfig,
+        pulses_us: [f32; NUM_SERVOS],
+    }
+
+    impl ServoOutput for TestServos {
+        fn calibration(&self, channel: usize) -> &ServoCalibration {
+            &self.config.channels[channel]
+        }
+
+        fn set_pulse_us(&mut self, channel: usize, pulse_us: f32) {
+            self.pulses_us[channel] = pulse_us;
+        }
+    }
+
+    #[test]
+    fn test_servo_output() {
+        let mut config = ServoConfig::default();
+        config.channels[1].gain_us_rad = -1000.0;
+        config.channels[2].center_pulse_us = 1520.0;
+
+        let mut servos = TestServos {
+            config,
+            pulses_us: [0.0; NUM_SERVOS],
+        };
+
+        servos.apply(&ServoCommand {
+            pos_rad: [0.1, 0.1, f32::NAN, 1.0],
+        });
+
+        assert!((servos.pulses_us[0] - (1500.0 + 0.1 * 572.9578)).abs() < 1e-3);
+        assert!((servos.pulses_us[1] - 1400.0).abs() < 1e-3);
+        assert_eq!(servos.pulses_us[2], 1520.0);
+        assert_eq!(servos.pulses_us[3], 2000.0);
+    }
+}
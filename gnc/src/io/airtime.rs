@@ -0,0 +1,289 @@
+use core::mem::discriminant;
+
+use heapless::Vec;
+use libm::ceilf;
+use mavlink::{MavHeader, write_v2_msg};
+
+use crate::{Instant, io::MAVLINK_MSG_MAX_SIZE, mav_crater::MavMessage};
+
+/// Messages waiting for airtime
+const QUEUE_LEN: usize = 12;
+
+/// LoRa modulation parameters, setting the time on air of the packets
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoraModulation {
+    /// Spreading factor, 6 to 12
+    pub spreading_factor: u8,
+    pub bandwidth_hz: f32,
+    /// Coding rate 4/(4 + `coding_rate`), 1 to 4
+    pub coding_rate: u8,
+    pub preamble_len: u16,
+    pub explicit_header: bool,
+    pub crc: bool,
+    /// Mandated for symbols longer than 16 ms
+    pub low_data_rate_optimize: bool,
+}
+
+impl Default for LoraModulation {
+    fn default() -> Self {
+        LoraModulation {
+            spreading_factor: 7,
+            bandwidth_hz: 125_000.0,
+            coding_rate: 1,
+            preamble_len: 8,
+            explicit_header: true,
+            crc: true,
+            low_data_rate_optimize: false,
+        }
+    }
+}
+
+impl LoraModulation {
+    pub fn symbol_time_us(&self) -> f32 {
+        (1u32 << self.spreading_factor) as f32 / self.bandwidth_hz * 1e6
+    }
+
+    /// Time on air of a packet of `payload_len` bytes [µs], as in the SX127x datasheet
+    pub fn time_on_air_us(&self, payload_len: usize) -> f32 {
+        let sf = self.spreading_factor as f32;
+        let de = self.low_data_rate_optimize as u8 as f32;
+        let ih = !self.explicit_header as u8 as f32;
+        let crc = self.crc as u8 as f32;
+
+        let num = 8.0 * payload_len as f32 - 4.0 * sf + 28.0 + 16.0 * crc - 20.0 * ih;
+        let payload_symbols =
+            8.0 + (ceilf(num / (4.0 * (sf - 2.0 * de))) * (self.coding_rate as f32 + 4.0)).max(0.0);
+
+        (self.preamble_len as f32 + 4.25 + payload_symbols) * self.symbol_time_us()
+    }
+}
+
+/// Limits the transmit duty cycle: airtime credit accumulates at `duty_cycle` of the elapsed
+/// time, up to `max_burst_us`
+#[derive(Debug, Clone)]
+pub struct AirtimeBudget {
+    duty_cycle: f32,
+    max_burst_us: f32,
+
+    credit_us: f32,
+    last_update: Option<Instant>,
+}
+
+impl AirtimeBudget {
+    pub fn new(duty_cycle: f32, max_burst_us: f32) -> Self {
+        Self {
+            duty_cycle,
+            max_burst_us,
+            credit_us: max_burst_us,
+            last_update: None,
+        }
+    }
+
+    /// Whether `airtime_us` is available at `t`. If so, it is spent.
+    pub fn try_spend(&mut self, t: Instant, airtime_us: f32) -> bool {
+        if let Some(last) = self.last_update {
+            let elapsed_us = (t.0 - last.0).to_micros() as f32;
+            self.credit_us = (self.credit_us + elapsed_us * self.duty_cycle).min(self.max_burst_us);
+        }
+        self.last_update = Some(t);
+
+        if self.credit_us < airtime_us {
+            return false;
+        }
+
+        self.credit_us -= airtime_us;
+        true
+    }
+}
+
+/// Priority of the downlinked messages, 0 first. None for messages not sent on the radio.
+pub fn downlink_priority(msg: &MavMessage) -> Option<u8> {
+    match msg {
+        MavMessage::GncEvent(_) | MavMessage::CommandAck(_) => Some(0),
+        MavMessage::RecoveryStatus(_) | MavMessage::ConfigHash(_) | MavMessage::BootStatus(_) => {
+            Some(1)
+        }
+        MavMessage::AdaState(_) => Some(2),
+        MavMessage::NavState(_) => Some(3),
+        MavMessage::SensGnssSample(_) | MavMessage::PowerStatus(_) => Some(4),
+        MavMessage::LogEntry(_) | MavMessage::LogData(_) => Some(5),
+        _ => None,
+    }
+}
+
+/// Events & acknowledgements are all sent, of the other messages only the latest of each kind
+fn coalesced(msg: &MavMessage) -> bool {
+    !matches!(msg, MavMessage::GncEvent(_) | MavMessage::CommandAck(_))
+}
+
+/// Sends the radio messages by priority, within an airtime budget. Messages without airtime left
+/// wait, superseded by any newer message of the same kind.
+pub struct AirtimeScheduler {
+    modulation: LoraModulation,
+    budget: AirtimeBudget,
+
+    /// Pending messages with their priority, oldest first
+    queue: Vec<(u8, MavMessage), QUEUE_LEN>,
+    frame: [u8; MAVLINK_MSG_MAX_SIZE],
+    seq_cnt: u8,
+    num_dropped: usize,
+}
+
+impl AirtimeScheduler {
+    pub fn new(modulation: LoraModulation, budget: AirtimeBudget) -> Self {
+        Self {
+            modulation,
+            budget,
+            queue: Vec::new(),
+            frame: [0; MAVLINK_MSG_MAX_SIZE],
+            seq_cnt: 0,
+            num_dropped: 0,
+        }
+    }
+
+    /// Messages dropped because the queue was full
+    pub fn num_dropped(&self) -> usize {
+        self.num_dropped
+    }
+
+    pub fn push(&mut self, msg: MavMessage) {
+        let Some(priority) = downlink_priority(&msg) else {
+            return;
+        };
+
+        if coalesced(&msg)
+            && let Some(pending) = self
+                .queue
+                .iter_mut()
+                .find(|(_, pending)| discriminant(pending) == discriminant(&msg))
+        {
+            pending.1 = msg;
+            return;
+        }
+
+        if self.queue.is_full() {
+            // Makes room by dropping the newest message of the lowest priority
+            let (lowest, _) = self
+                .queue
+                .iter()
+                .enumerate()
+                .max_by_key(|(i, (priority, _))| (*priority, *i))
+                .map(|(i, (priority, _))| (i, *priority))
+                .unwrap();
+            self.num_dropped += 1;
+
+            if self.queue[lowest].0 <= priority {
+                return;
+            }
+            self.queue.remove(lowest);
+        }
+
+        let _ = self.queue.push((priority, msg));
+    }
+
+    /// Frame of the highest priority message, if its airtime is available at `t`
+    pub fn next_frame(&mut self, t: Instant) -> Option<&[u8]> {
+        let (i, _) = self
+            .queue
+            .iter()
+            .enumerate()
+            .min_by_key(|(i, (priority, _))| (*priority, *i))?;
+
+        let header = MavHeader {
+            sequence: self.seq_cnt,
+            ..Default::default()
+        };
+        let Ok(len) = write_v2_msg(&mut &mut self.frame[..], header, &self.queue[i].1) else {
+            self.queue.remove(i);
+            return None;
+        };
+
+        if !self
+            .budget
+            .try_spend(t, self.modulation.time_on_air_us(len))
+        {
+            return None;
+        }
+
+        self.queue.remove(i);
+        self.seq_cnt = self.seq_cnt.wrapping_add(1);
+        Some(&self.frame[..len])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use mavlink::{peek_reader::PeekReader, read_v2_msg};
+
+    use crate::{
+        InstantU64,
+        mav_crater::{AdaState_DATA, GncEvent_DATA, NavState_DATA, SensImuSample_DATA},
+    };
+
+    use super::*;
+
+    fn at_ms(ms: u64) -> Instant {
+        Instant(InstantU64::from_ticks(ms * 1000))
+    }
+
+    #[test]
+    fn test_time_on_air() {
+        // Reference values of the Semtech LoRa calculator
+        let modulation = LoraModulation::default();
+        assert!((modulation.time_on_air_us(10) - 41216.0).abs() < 1.0);
+
+        let modulation = LoraModulation {
+            spreading_factor: 12,
+            low_data_rate_optimize: true,
+            ..Default::default()
+        };
+        assert!((modulation.time_on_air_us(10) - 991232.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_airtime_scheduler() {
+        // 10% duty cycle, 150 ms bursts
+        let mut scheduler = AirtimeScheduler::new(
+            LoraModulation::default(),
+            AirtimeBudget::new(0.1, 150_000.0),
+        );
+
+        scheduler.push(MavMessage::SensImuSample(SensImuSample_DATA::DEFAULT));
+        assert!(scheduler.next_frame(at_ms(0)).is_none());
+
+        for i in 0..3 {
+            scheduler.push(MavMessage::NavState(NavState_DATA {
+                timestamp_us: i,
+                ..NavState_DATA::DEFAULT
+            }));
+        }
+        scheduler.push(MavMessage::AdaState(AdaState_DATA::DEFAULT));
+        scheduler.push(MavMessage::GncEvent(GncEvent_DATA::DEFAULT));
+        scheduler.push(MavMessage::GncEvent(GncEvent_DATA::DEFAULT));
+
+        let mut sent = vec![];
+        let mut t_ms = 0;
+        while let Some(frame) = scheduler.next_frame(at_ms(t_ms)) {
+            // Message id, little endian on 3 bytes
+            sent.push(u32::from_le_bytes([frame[7], frame[8], frame[9], 0]));
+            t_ms += 1;
+        }
+
+        // By priority, within the burst budget: 2 events & the ADA state fit, the nav state waits
+        assert_eq!(sent, [205, 205, 204]);
+
+        // Only the latest nav state is left
+        t_ms += 2000;
+        let frame = scheduler.next_frame(at_ms(t_ms)).unwrap();
+        let (_, msg) = read_v2_msg::<MavMessage, _>(&mut PeekReader::new(frame)).unwrap();
+        assert!(matches!(
+            msg,
+            MavMessage::NavState(NavState_DATA {
+                timestamp_us: 2,
+                ..
+            })
+        ));
+        assert!(scheduler.next_frame(at_ms(t_ms + 2000)).is_none());
+    }
+}
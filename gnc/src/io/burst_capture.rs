@@ -0,0 +1,370 @@
+use alloc::{boxed::Box, collections::VecDeque};
+
+use crate::{
+    Duration, DurationU64, Instant,
+    common::{
+        Ts,
+        config_hash::{ConfigHash, ConfigHasher},
+    },
+    component::{Component, LoopContext},
+    components::ada::AdaResult,
+    datatypes::{
+        gnc::NavigationOutput,
+        sensors::{ImuSensorSample, PressureSensorSample},
+    },
+    events::Event,
+    hal::channel::{Full, Receiver, Sender},
+    mav_crater::{ComponentId, DownlinkProfile, ImuSensorId, MavMessage, PressureSensorId},
+};
+
+pub struct BurstCaptureHarness {
+    pub rx_imu: Box<dyn Receiver<ImuSensorSample> + Send>,
+    pub rx_pressure: Box<dyn Receiver<PressureSensorSample> + Send>,
+    pub rx_nav: Box<dyn Receiver<NavigationOutput> + Send>,
+    pub rx_ada: Box<dyn Receiver<AdaResult> + Send>,
+
+    /// Captured samples, at full resolution, to the recorder
+    pub tx_record: Box<dyn Sender<MavMessage> + Send>,
+}
+
+/// Streams kept in the rolling buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BurstStreams {
+    pub imu: bool,
+    pub pressure: bool,
+    pub nav: bool,
+    pub ada: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BurstCaptureConfig {
+    pub streams: BurstStreams,
+
+    /// Events starting a capture
+    pub triggers: &'static [Event],
+
+    /// Data recorded before and after each trigger
+    pub pre_trigger: Duration,
+    pub post_trigger: Duration,
+
+    /// Samples kept in the rolling buffer at most, which bounds its memory. Must hold the
+    /// pre-trigger window of all the streams, plus the samples waiting to be written.
+    pub buffer_len: usize,
+
+    /// Samples written per step at most, which sets the write rate together with the loop rate
+    pub samples_per_step: u32,
+}
+
+impl Default for BurstCaptureConfig {
+    fn default() -> Self {
+        BurstCaptureConfig {
+            streams: BurstStreams {
+                imu: true,
+                pressure: true,
+                nav: true,
+                ada: true,
+            },
+            // The manual deployments are the abort path of the ground station
+            triggers: &[
+                Event::FlightLiftoff,
+                Event::AdaApogeeDetected,
+                Event::CmdDeployDrogue,
+                Event::CmdDeployMain,
+                Event::RecoveryMainFired,
+            ],
+            pre_trigger: DurationU64::secs(2).into(),
+            post_trigger: DurationU64::secs(3).into(),
+            buffer_len: 8192,
+            samples_per_step: 8,
+        }
+    }
+}
+
+impl ConfigHash for BurstCaptureConfig {
+    fn hash_config(&self, hasher: &mut ConfigHasher) {
+        hasher.write_section("burst_capture");
+        for enabled in [
+            self.streams.imu,
+            self.streams.pressure,
+            self.streams.nav,
+            self.streams.ada,
+        ] {
+            hasher.write_u8(enabled as u8);
+        }
+        for event in self.triggers.iter() {
+            hasher.write_u8(*event as u8);
+        }
+        hasher.write_duration(self.pre_trigger);
+        hasher.write_duration(self.post_trigger);
+        hasher.write_u64(self.buffer_len as u64);
+        hasher.write_u64(self.samples_per_step as u64);
+    }
+}
+
+/// Rolling buffer of the latest samples. Out of a capture, only the pre-trigger window is kept.
+/// A trigger opens a capture window, during which the buffered samples are taken in order until
+/// the end of the post-trigger window. Triggers within a capture extend it.
+#[derive(Debug, Clone)]
+pub struct BurstBuffer {
+    samples: VecDeque<Ts<MavMessage>>,
+    capacity: usize,
+    pre_trigger: DurationU64,
+    post_trigger: DurationU64,
+
+    /// End of the capture window
+    capture_until: Option<Instant>,
+    /// Samples of a capture lost because the buffer was full
+    num_dropped: u32,
+}
+
+impl BurstBuffer {
+    pub fn new(capacity: usize, pre_trigger: Duration, post_trigger: Duration) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+            pre_trigger: pre_trigger.0,
+            post_trigger: post_trigger.0,
+            capture_until: None,
+            num_dropped: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn is_capturing(&self) -> bool {
+        self.capture_until.is_some()
+    }
+
+    pub fn num_dropped(&self) -> u32 {
+        self.num_dropped
+    }
+
+    /// Adds a sample. The oldest one is discarded if the buffer is full.
+    pub fn push(&mut self, t: Instant, msg: MavMessage) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.samples.len() == self.capacity {
+            let oldest = self.samples.pop_front();
+            if oldest.is_some_and(|s| self.capture_until.is_some_and(|until| s.t.0 <= until.0)) {
+                self.num_dropped += 1;
+            }
+        }
+        self.samples.push_back(Ts::new(t, msg));
+    }
+
+    /// Puts back a sample returned by `next` that could not be recorded
+    pub fn unget(&mut self, sample: Ts<MavMessage>) {
+        self.samples.push_front(sample);
+    }
+
+    /// Starts a capture at `t`, or extends the current one
+    pub fn trigger(&mut self, t: Instant) {
+        let until = Instant(t.0 + self.post_trigger);
+        self.capture_until = Some(match self.capture_until {
+            Some(prev) if prev.0 > until.0 => prev,
+            _ => until,
+        });
+    }
+
+    /// Next sample to record at `t`, if any. The capture ends once all its samples are taken.
+    pub fn next(&mut self, t: Instant) -> Option<Ts<MavMessage>> {
+        let until = self.capture_until?;
+
+        if self.samples.front().is_some_and(|s| s.t.0 <= until.0) {
+            return self.samples.pop_front();
+        }
+
+        // Samples after the window are kept for the pre-trigger window of the next capture
+        if t.0 >= until.0 {
+            self.capture_until = None;
+        }
+        None
+    }
+
+    /// Discards the samples older than the pre-trigger window. Nothing is discarded during a
+    /// capture.
+    pub fn trim(&mut self, t: Instant) {
+        if self.capture_until.is_some() {
+            return;
+        }
+
+        while self
+            .samples
+            .front()
+            .is_some_and(|s| s.t.0 + self.pre_trigger < t.0)
+        {
+            self.samples.pop_front();
+        }
+    }
+}
+
+/// Keeps the latest high-rate samples in a rolling buffer and, on the configured events, writes
+/// a window of them around the event to the recorder at full resolution. The rest of the flight
+/// is only logged at the rates the storage can sustain.
+pub struct BurstCaptureComponent {
+    harness: BurstCaptureHarness,
+    config: BurstCaptureConfig,
+    buffer: BurstBuffer,
+}
+
+impl BurstCaptureComponent {
+    pub fn new(harness: BurstCaptureHarness, config: BurstCaptureConfig) -> Self {
+        Self {
+            harness,
+            config,
+            buffer: BurstBuffer::new(config.buffer_len, config.pre_trigger, config.post_trigger),
+        }
+    }
+
+    pub fn buffer(&self) -> &BurstBuffer {
+        &self.buffer
+    }
+
+    fn receive(&mut self) {
+        let streams = self.config.streams;
+
+        while let Some(imu) = self.harness.rx_imu.try_recv() {
+            if streams.imu {
+                let msg = imu.v.to_mavlink(ImuSensorId::Icm42688, imu.t);
+                self.buffer.push(imu.t, msg);
+            }
+        }
+        while let Some(pressure) = self.harness.rx_pressure.try_recv() {
+            if streams.pressure {
+                let msg = pressure.v.to_mavlink(PressureSensorId::Bmp390, pressure.t);
+                self.buffer.push(pressure.t, msg);
+            }
+        }
+        // Recorded as in the critical profile, which is the one at full rate
+        while let Some(nav) = self.harness.rx_nav.try_recv() {
+            if streams.nav {
+                let msg = nav.v.to_mavlink(nav.t, DownlinkProfile::Critical);
+                self.buffer.push(nav.t, msg);
+            }
+        }
+        while let Some(ada) = self.harness.rx_ada.try_recv() {
+            if streams.ada {
+                let msg = ada.v.to_mavlink(ada.t, DownlinkProfile::Critical);
+                self.buffer.push(ada.t, msg);
+            }
+        }
+    }
+}
+
+impl Component for BurstCaptureComponent {
+    fn id(&self) -> ComponentId {
+        ComponentId::BurstCapture
+    }
+
+    fn handle_event(&mut self, event: Event, context: &mut LoopContext) {
+        if self.config.triggers.contains(&event) {
+            self.buffer.trigger(context.step().step_time);
+        }
+    }
+
+    fn step(&mut self, context: &mut LoopContext) {
+        let t = context.step().step_time;
+
+        self.receive();
+
+        for _ in 0..self.config.samples_per_step {
+            let Some(sample) = self.buffer.next(t) else {
+                break;
+            };
+
+            if let Err(Full(sample)) = self.harness.tx_record.try_send(sample.t, sample.v) {
+                // Recorder busy: written again at the next step
+                self.buffer.unget(sample);
+                break;
+            }
+        }
+
+        self.buffer.trim(t);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+
+    use crate::{InstantU64, mav_crater::AdaState_DATA};
+
+    use super::*;
+
+    fn at_ms(ms: u64) -> Instant {
+        Instant(InstantU64::from_ticks(ms * 1000))
+    }
+
+    fn sample(ms: u64) -> MavMessage {
+        MavMessage::AdaState(AdaState_DATA {
+            timestamp_us: ms as i64 * 1000,
+            ..AdaState_DATA::DEFAULT
+        })
+    }
+
+    /// Steps every 10 ms with one sample each, from `from_ms` to `to_ms` excluded. Returns the
+    /// times of the recorded samples [ms].
+    fn run(buffer: &mut BurstBuffer, from_ms: u64, to_ms: u64, per_step: usize) -> Vec<u64> {
+        let mut recorded = vec![];
+        for ms in (from_ms..to_ms).step_by(10) {
+            buffer.push(at_ms(ms), sample(ms));
+            for _ in 0..per_step {
+                let Some(s) = buffer.next(at_ms(ms)) else {
+                    break;
+                };
+                recorded.push(s.t.0.ticks() / 1000);
+            }
+            buffer.trim(at_ms(ms));
+        }
+        recorded
+    }
+
+    #[test]
+    fn test_burst_buffer() {
+        let mut buffer = BurstBuffer::new(
+            1000,
+            DurationU64::millis(100).into(),
+            DurationU64::millis(50).into(),
+        );
+
+        // Only the pre-trigger window is kept
+        assert!(run(&mut buffer, 0, 1000, 4).is_empty());
+        assert_eq!(buffer.len(), 11);
+
+        // 100 ms before & 50 ms after the trigger
+        buffer.trigger(at_ms(1000));
+        let recorded = run(&mut buffer, 1000, 1200, 4);
+        assert_eq!(recorded, (890..=1050).step_by(10).collect::<Vec<_>>());
+        assert!(!buffer.is_capturing());
+
+        // A second trigger within the capture extends it. The write rate is lower than the
+        // sample rate: the capture ends once its backlog is written.
+        assert!(run(&mut buffer, 1200, 2000, 1).is_empty());
+        buffer.trigger(at_ms(2000));
+        let mut recorded = run(&mut buffer, 2000, 2030, 1);
+        buffer.trigger(at_ms(2030));
+        recorded.extend(run(&mut buffer, 2030, 2300, 1));
+        assert_eq!(recorded, (1890..=2080).step_by(10).collect::<Vec<_>>());
+        assert!(!buffer.is_capturing());
+        assert_eq!(buffer.num_dropped(), 0);
+
+        // Samples of a capture lost to a full buffer are counted
+        let mut small = BurstBuffer::new(
+            5,
+            DurationU64::millis(100).into(),
+            DurationU64::millis(100).into(),
+        );
+        small.trigger(at_ms(0));
+        run(&mut small, 0, 100, 0);
+        assert_eq!(small.len(), 5);
+        assert_eq!(small.num_dropped(), 5);
+    }
+}
@@ -0,0 +1,289 @@
+use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
+use thiserror::Error;
+
+use crate::{
+    datatypes::{
+        actuators::{NUM_SERVOS, ServoCommand},
+        sensors::PressureSensorSample,
+    },
+    events::Event,
+};
+
+/// Data bytes of a classic CAN frame
+pub const CAN_MAX_DATA_LEN: usize = 8;
+
+/// Servo deflections resolution on the bus [rad]
+const SERVO_LSB_RAD: f32 = 1e-4;
+
+/// Boards on the bus, in the low 5 bits of the identifiers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive)]
+pub enum CanNodeId {
+    MainFc = 1,
+    Airbrake = 2,
+    Payload = 3,
+}
+
+/// Message kinds, in the high 6 bits of the identifiers: lower kinds win the bus arbitration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive)]
+pub enum CanMessageKind {
+    Event = 0x02,
+    ServoCommand = 0x08,
+    AirbrakeCommand = 0x09,
+    AirbrakeStatus = 0x10,
+    Pressure = 0x18,
+    Heartbeat = 0x30,
+}
+
+/// Frame with an 11 bit standard identifier
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CanFrame {
+    pub id: u16,
+    pub len: u8,
+    pub data: [u8; CAN_MAX_DATA_LEN],
+}
+
+impl CanFrame {
+    pub fn new(id: u16, data: &[u8]) -> Self {
+        let mut frame = CanFrame {
+            id,
+            len: data.len() as u8,
+            data: [0; CAN_MAX_DATA_LEN],
+        };
+        frame.data[..data.len()].copy_from_slice(data);
+        frame
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data[..self.len as usize]
+    }
+}
+
+/// Data exchanged between the flight computer and the other boards, one frame each, little endian
+#[derive(Debug, Clone, PartialEq)]
+pub enum CanMessage {
+    /// GNC event, eg. liftoff, forwarded to the other boards
+    Event(Event),
+    ServoCommand(ServoCommand),
+    /// Commanded airbrake extension, from 0 (retracted) to 1
+    AirbrakeCommand {
+        extension: f32,
+    },
+    AirbrakeStatus {
+        extension: f32,
+        fault: bool,
+    },
+    Pressure(PressureSensorSample),
+    /// Sent periodically by each board
+    Heartbeat {
+        uptime_ms: u32,
+        error_count: u16,
+    },
+}
+
+#[derive(Debug, Error, Clone, PartialEq)]
+pub enum CanDecodeError {
+    #[error("Identifier {0:#x} is not a standard identifier")]
+    Identifier(u16),
+    #[error("Unknown message kind {0:#x}")]
+    UnknownKind(u16),
+    #[error("Unknown node {0}")]
+    UnknownNode(u16),
+    #[error("Wrong data length {len} for message {kind:?}")]
+    Length { kind: CanMessageKind, len: u8 },
+    #[error("Unknown event {0}")]
+    UnknownEvent(u16),
+}
+
+impl CanMessage {
+    pub fn kind(&self) -> CanMessageKind {
+        match self {
+            CanMessage::Event(_) => CanMessageKind::Event,
+            CanMessage::ServoCommand(_) => CanMessageKind::ServoCommand,
+            CanMessage::AirbrakeCommand { .. } => CanMessageKind::AirbrakeCommand,
+            CanMessage::AirbrakeStatus { .. } => CanMessageKind::AirbrakeStatus,
+            CanMessage::Pressure(_) => CanMessageKind::Pressure,
+            CanMessage::Heartbeat { .. } => CanMessageKind::Heartbeat,
+        }
+    }
+
+    /// Frame of the message sent by `src`
+    pub fn encode(&self, src: CanNodeId) -> CanFrame {
+        let id = ((self.kind() as u16) << 5) | src as u16;
+        let mut data = [0u8; CAN_MAX_DATA_LEN];
+
+        let len = match self {
+            CanMessage::Event(event) => {
+                data[..2].copy_from_slice(&(*event as u16).to_le_bytes());
+                2
+            }
+            CanMessage::ServoCommand(cmd) => {
+                for (i, pos_rad) in cmd.pos_rad.iter().enumerate() {
+                    let pos = libm::roundf(pos_rad / SERVO_LSB_RAD) as i16;
+                    data[2 * i..2 * i + 2].copy_from_slice(&pos.to_le_bytes());
+                }
+                2 * NUM_SERVOS
+            }
+            CanMessage::AirbrakeCommand { extension } => {
+                data[..4].copy_from_slice(&extension.to_le_bytes());
+                4
+            }
+            CanMessage::AirbrakeStatus { extension, fault } => {
+                data[..4].copy_from_slice(&extension.to_le_bytes());
+                data[4] = *fault as u8;
+                5
+            }
+            CanMessage::Pressure(sample) => {
+                data[..4].copy_from_slice(&sample.pressure_pa.to_le_bytes());
+                let temperature_degc = sample.temperature_degc.unwrap_or(f32::NAN);
+                data[4..8].copy_from_slice(&temperature_degc.to_le_bytes());
+                8
+            }
+            CanMessage::Heartbeat {
+                uptime_ms,
+                error_count,
+            } => {
+                data[..4].copy_from_slice(&uptime_ms.to_le_bytes());
+                data[4..6].copy_from_slice(&error_count.to_le_bytes());
+                6
+            }
+        };
+
+        CanFrame::new(id, &data[..len])
+    }
+
+    /// Message & sender of a frame
+    pub fn decode(frame: &CanFrame) -> Result<(CanNodeId, CanMessage), CanDecodeError> {
+        if frame.id > 0x7FF {
+            return Err(CanDecodeError::Identifier(frame.id));
+        }
+
+        let kind = CanMessageKind::from_u16(frame.id >> 5)
+            .ok_or(CanDecodeError::UnknownKind(frame.id >> 5))?;
+        let src = CanNodeId::from_u16(frame.id & 0x1F)
+            .ok_or(CanDecodeError::UnknownNode(frame.id & 0x1F))?;
+
+        let expected_len = match kind {
+            CanMessageKind::Event => 2,
+            CanMessageKind::ServoCommand => 2 * NUM_SERVOS,
+            CanMessageKind::AirbrakeCommand => 4,
+            CanMessageKind::AirbrakeStatus => 5,
+            CanMessageKind::Pressure => 8,
+            CanMessageKind::Heartbeat => 6,
+        };
+        if frame.len as usize != expected_len {
+            return Err(CanDecodeError::Length {
+                kind,
+                len: frame.len,
+            });
+        }
+
+        let data = frame.data();
+        let u16_at = |i: usize| u16::from_le_bytes([data[i], data[i + 1]]);
+        let f32_at = |i: usize| f32::from_le_bytes(data[i..i + 4].try_into().unwrap());
+
+        let msg = match kind {
+            CanMessageKind::Event => CanMessage::Event(
+                Event::from_u16(u16_at(0)).ok_or(CanDecodeError::UnknownEvent(u16_at(0)))?,
+            ),
+            CanMessageKind::ServoCommand => CanMessage::ServoCommand(ServoCommand {
+                pos_rad: core::array::from_fn(|i| u16_at(2 * i) as i16 as f32 * SERVO_LSB_RAD),
+            }),
+            CanMessageKind::AirbrakeCommand => CanMessage::AirbrakeCommand {
+                extension: f32_at(0),
+            },
+            CanMessageKind::AirbrakeStatus => CanMessage::AirbrakeStatus {
+                extension: f32_at(0),
+                fault: data[4] != 0,
+            },
+            CanMessageKind::Pressure => CanMessage::Pressure(PressureSensorSample {
+                pressure_pa: f32_at(0),
+                temperature_degc: Some(f32_at(4)).filter(|t| !t.is_nan()),
+            }),
+            CanMessageKind::Heartbeat => CanMessage::Heartbeat {
+                uptime_ms: u32::from_le_bytes(data[..4].try_into().unwrap()),
+                error_count: u16_at(4),
+            },
+        };
+
+        Ok((src, msg))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_can_message_roundtrip() {
+        let messages = [
+            CanMessage::Event(Event::FlightLiftoff),
+            CanMessage::AirbrakeCommand { extension: 0.5 },
+            CanMessage::AirbrakeStatus {
+                extension: 0.25,
+                fault: true,
+            },
+            CanMessage::Pressure(PressureSensorSample {
+                pressure_pa: 101325.0,
+                temperature_degc: None,
+            }),
+            CanMessage::Heartbeat {
+                uptime_ms: 123456,
+                error_count: 7,
+            },
+        ];
+
+        for msg in messages {
+            let frame = msg.encode(CanNodeId::Airbrake);
+            assert!(frame.id <= 0x7FF);
+            assert_eq!(
+                CanMessage::decode(&frame),
+                Ok((CanNodeId::Airbrake, msg.clone())),
+                "{msg:?}"
+            );
+        }
+
+        let cmd = [0.1, -0.2, 0.0, 3.0];
+        let frame =
+            CanMessage::ServoCommand(ServoCommand { pos_rad: cmd }).encode(CanNodeId::MainFc);
+        let Ok((CanNodeId::MainFc, CanMessage::ServoCommand(decoded))) = CanMessage::decode(&frame)
+        else {
+            panic!("Servo command not decoded");
+        };
+        for (pos_rad, expected) in decoded.pos_rad.iter().zip(cmd) {
+            assert!((pos_rad - expected).abs() <= SERVO_LSB_RAD / 2.0);
+        }
+
+        // Events win the arbitration over the periodic data
+        let event = CanMessage::Event(Event::FlightBurnout).encode(CanNodeId::Payload);
+        let heartbeat = CanMessage::Heartbeat {
+            uptime_ms: 0,
+            error_count: 0,
+        }
+        .encode(CanNodeId::MainFc);
+        assert!(event.id < heartbeat.id);
+    }
+
+    #[test]
+    fn test_can_decode_errors() {
+        assert_eq!(
+            CanMessage::decode(&CanFrame::new((0x3F << 5) | 1, &[])),
+            Err(CanDecodeError::UnknownKind(0x3F))
+        );
+        assert_eq!(
+            CanMessage::decode(&CanFrame::new((0x09 << 5) | 31, &[0; 4])),
+            Err(CanDecodeError::UnknownNode(31))
+        );
+        assert_eq!(
+            CanMessage::decode(&CanFrame::new((0x09 << 5) | 1, &[0; 3])),
+            Err(CanDecodeError::Length {
+                kind: CanMessageKind::AirbrakeCommand,
+                len: 3
+            })
+        );
+        assert_eq!(
+            CanMessage::decode(&CanFrame::new((0x02 << 5) | 1, &[0xFF, 0xFF])),
+            Err(CanDecodeError::UnknownEvent(0xFFFF))
+        );
+    }
+}
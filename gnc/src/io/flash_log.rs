@@ -0,0 +1,539 @@
+use alloc::vec::Vec;
+use mavlink::{MavHeader, write_v2_msg};
+
+use crate::{
+    datatypes::{
+        dialect::MAV_CRATER_VERSION,
+        version::{InterfaceVersion, Versioned},
+    },
+    io::{
+        MAVLINK_MSG_MAX_SIZE,
+        log_transfer::{LogInfo, LogStorage},
+    },
+    mav_crater::MavMessage,
+};
+
+const MAGIC: [u8; 4] = *b"CRLG";
+pub const LOG_HEADER_LEN: usize = 16;
+
+/// Bytes buffered before programming them, a page of most NOR flash memories
+const WRITE_BUF_LEN: usize = 256;
+
+/// Bytes checked at the start of a sector to tell whether it is in use
+const SECTOR_PROBE_LEN: usize = LOG_HEADER_LEN;
+
+/// Frames log records as MAVLink v2 messages, with their own sequence number
+pub struct RecordFramer {
+    seq_cnt: u8,
+    frame: [u8; MAVLINK_MSG_MAX_SIZE],
+}
+
+impl RecordFramer {
+    pub fn new() -> Self {
+        Self {
+            seq_cnt: 0,
+            frame: [0; MAVLINK_MSG_MAX_SIZE],
+        }
+    }
+
+    /// Frame of `msg`, valid until the next call. None if it could not be serialized.
+    pub fn frame(&mut self, msg: &MavMessage) -> Option<&[u8]> {
+        let header = MavHeader {
+            component_id: 0,
+            system_id: 0,
+            sequence: self.seq_cnt,
+        };
+
+        let len = write_v2_msg(&mut &mut self.frame[..], header, msg).ok()?;
+        self.seq_cnt = self.seq_cnt.wrapping_add(1);
+
+        Some(&self.frame[..len])
+    }
+}
+
+impl Default for RecordFramer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// NOR flash memory: reads back 0xFF once erased, and programming only clears bits
+pub trait NorFlash {
+    /// [bytes]
+    fn capacity(&self) -> u32;
+
+    /// Smallest erasable unit [bytes]
+    fn sector_size(&self) -> u32;
+
+    fn read(&mut self, addr: u32, buf: &mut [u8]);
+
+    /// Programs `data` at `addr`, which must be erased. May cross page boundaries.
+    fn program(&mut self, addr: u32, data: &[u8]);
+
+    fn erase_sector(&mut self, addr: u32);
+}
+
+/// Log records, the messages of the MAVLink dialect
+impl Versioned for MavMessage {
+    const NAME: &'static str = "mav_crater";
+    const VERSION: InterfaceVersion = MAV_CRATER_VERSION;
+}
+
+/// Header at the start of the first sector of each log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogHeader {
+    /// Increasing with each log, ie. each boot
+    pub seq: u32,
+    /// Seconds since the UNIX epoch. 0 if unknown.
+    pub time_utc: u32,
+    /// Version of the records, to be checked before decoding them. 0.0 for the logs written
+    /// before it was recorded.
+    pub records: InterfaceVersion,
+}
+
+impl LogHeader {
+    pub fn new(seq: u32, time_utc: u32) -> Self {
+        Self {
+            seq,
+            time_utc,
+            records: MavMessage::VERSION,
+        }
+    }
+
+    pub fn to_bytes(self) -> [u8; LOG_HEADER_LEN] {
+        let mut bytes = [0u8; LOG_HEADER_LEN];
+        bytes[0..4].copy_from_slice(&MAGIC);
+        bytes[4..8].copy_from_slice(&self.seq.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.time_utc.to_le_bytes());
+        bytes[12..14].copy_from_slice(&self.records.major.to_le_bytes());
+        bytes[14..16].copy_from_slice(&self.records.minor.to_le_bytes());
+        bytes
+    }
+
+    /// Header at the start of `bytes`, if any
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < LOG_HEADER_LEN || bytes[0..4] != MAGIC {
+            return None;
+        }
+
+        Some(Self {
+            seq: u32::from_le_bytes(bytes[4..8].try_into().unwrap()),
+            time_utc: u32::from_le_bytes(bytes[8..12].try_into().unwrap()),
+            records: InterfaceVersion::new(
+                u16::from_le_bytes(bytes[12..14].try_into().unwrap()),
+                u16::from_le_bytes(bytes[14..16].try_into().unwrap()),
+            ),
+        })
+    }
+}
+
+/// Log stored in consecutive sectors, wrapping at the end of the flash
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogExtent {
+    pub header: LogHeader,
+    pub first_sector: u32,
+    pub num_sectors: u32,
+}
+
+/// Logs of framed binary records (MAVLink v2 messages) in a NOR flash, used as a ring: each log
+/// starts on the sector following the previous one, so that all the sectors wear evenly, and the
+/// oldest logs are overwritten once the flash is full.
+///
+/// Logs end at the first sector which is blank or starts with another log header. A log never
+/// wraps onto its own first sector: records are dropped once it fills the flash. The sectors
+/// left of a log whose first sector was overwritten are counted in the log before them.
+pub struct FlashLog<F> {
+    flash: F,
+    /// Logs found in the flash, oldest first
+    logs: Vec<LogExtent>,
+
+    /// Log being written, with its first sector
+    current: Option<(LogHeader, u32)>,
+    /// Bytes of the current log programmed
+    len: u32,
+    /// Bytes of the current log in sectors ready to be programmed
+    erased_len: u32,
+    buf: Vec<u8>,
+
+    framer: RecordFramer,
+    num_dropped: u32,
+    num_erased: u32,
+}
+
+impl<F: NorFlash> FlashLog<F> {
+    /// Scans the flash for the existing logs
+    pub fn mount(mut flash: F) -> Self {
+        let num_sectors = flash.capacity() / flash.sector_size();
+
+        // Header or blank state of the start of each sector
+        let mut starts = Vec::with_capacity(num_sectors as usize);
+        for sector in 0..num_sectors {
+            let mut probe = [0u8; SECTOR_PROBE_LEN];
+            flash.read(sector * flash.sector_size(), &mut probe);
+
+            let blank = probe.iter().all(|&b| b == 0xFF);
+            starts.push((LogHeader::from_bytes(&probe), blank));
+        }
+
+        let mut logs: Vec<LogExtent> = starts
+            .iter()
+            .enumerate()
+            .filter_map(|(sector, (header, _))| {
+                let header = (*header)?;
+                let num_sectors = 1
+                    + (1..num_sectors)
+                        .map(|i| &starts[(sector + i as usize) % starts.len()])
+                        .take_while(|(header, blank)| header.is_none() && !blank)
+                        .count() as u32;
+
+                Some(LogExtent {
+                    header,
+                    first_sector: sector as u32,
+                    num_sectors,
+                })
+            })
+            .collect();
+        logs.sort_by_key(|log| log.header.seq);
+
+        Self {
+            flash,
+            logs,
+            current: None,
+            len: 0,
+            erased_len: 0,
+            buf: Vec::with_capacity(WRITE_BUF_LEN),
+            framer: RecordFramer::new(),
+            num_dropped: 0,
+            num_erased: 0,
+        }
+    }
+
+    pub fn logs(&self) -> &[LogExtent] {
+        &self.logs
+    }
+
+    /// Records dropped because the log was full, or not started
+    pub fn num_dropped(&self) -> u32 {
+        self.num_dropped
+    }
+
+    /// Sectors erased since mounting
+    pub fn num_erased(&self) -> u32 {
+        self.num_erased
+    }
+
+    fn num_sectors(&self) -> u32 {
+        self.flash.capacity() / self.flash.sector_size()
+    }
+
+    /// Longest log, all the flash but one sector so that it never wraps onto its own start
+    fn max_len(&self) -> u32 {
+        self.flash.capacity() - self.flash.sector_size()
+    }
+
+    /// Starts a new log after the latest one. Erases beforehand the sectors to hold the first
+    /// `preerase_len` bytes, so that no erase (tens of ms each) stalls the logging in flight.
+    /// Blank sectors are not erased again.
+    pub fn begin(&mut self, time_utc: u32, preerase_len: u32) {
+        let (seq, first_sector) = match self.logs.last() {
+            Some(log) => (
+                log.header.seq + 1,
+                (log.first_sector + log.num_sectors) % self.num_sectors(),
+            ),
+            None => (1, 0),
+        };
+        let header = LogHeader::new(seq, time_utc);
+
+        self.current = Some((header, first_sector));
+        self.len = 0;
+        self.erased_len = 0;
+        self.buf.clear();
+
+        self.erase_until(preerase_len.max(LOG_HEADER_LEN as u32));
+        self.append(&header.to_bytes());
+    }
+
+    /// Appends raw bytes to the current log. Returns false if dropped.
+    pub fn append(&mut self, data: &[u8]) -> bool {
+        let len = self.len + (self.buf.len() + data.len()) as u32;
+        if self.current.is_none() || len > self.max_len() {
+            self.num_dropped = self.num_dropped.wrapping_add(1);
+            return false;
+        }
+
+        for chunk in data.chunks(WRITE_BUF_LEN) {
+            let room = WRITE_BUF_LEN - self.buf.len();
+            let (now, later) = chunk.split_at(chunk.len().min(room));
+
+            self.buf.extend_from_slice(now);
+            if self.buf.len() == WRITE_BUF_LEN {
+                self.flush();
+            }
+            self.buf.extend_from_slice(later);
+        }
+
+        true
+    }
+
+    /// Appends a message to the current log, as a MAVLink v2 frame
+    pub fn append_message(&mut self, msg: &MavMessage) -> bool {
+        let mut framer = core::mem::take(&mut self.framer);
+        let appended = match framer.frame(msg) {
+            Some(frame) => self.append(frame),
+            None => {
+                self.num_dropped = self.num_dropped.wrapping_add(1);
+                false
+            }
+        };
+
+        self.framer = framer;
+        appended
+    }
+
+    /// Programs the buffered bytes
+    pub fn flush(&mut self) {
+        if self.buf.is_empty() {
+            return;
+        }
+
+        let end = self.len + self.buf.len() as u32;
+        self.erase_until(end);
+
+        let buf = core::mem::take(&mut self.buf);
+        let addr = self.addr(self.len);
+        let to_wrap = (self.flash.capacity() - addr).min(buf.len() as u32) as usize;
+
+        self.flash.program(addr, &buf[..to_wrap]);
+        if to_wrap < buf.len() {
+            self.flash.program(0, &buf[to_wrap..]);
+        }
+
+        self.len = end;
+        self.buf = buf;
+        self.buf.clear();
+    }
+
+    /// Flash address of `offset` in the current log
+    fn addr(&self, offset: u32) -> u32 {
+        let first_sector = self.current.map_or(0, |(_, sector)| sector);
+        (first_sector * self.flash.sector_size() + offset) % self.flash.capacity()
+    }
+
+    /// Erases the sectors of the current log up to `len` bytes, if not blank already. The logs
+    /// whose first sector is erased are lost.
+    fn erase_until(&mut self, len: u32) {
+        let sector_size = self.flash.sector_size();
+
+        while self.erased_len < len.min(self.max_len()) {
+            let addr = self.addr(self.erased_len);
+
+            if !self.is_blank(addr, sector_size) {
+                self.flash.erase_sector(addr);
+                self.num_erased += 1;
+            }
+            self.logs
+                .retain(|log| log.first_sector * sector_size != addr);
+
+            self.erased_len += sector_size;
+        }
+    }
+
+    fn is_blank(&mut self, addr: u32, len: u32) -> bool {
+        let mut chunk = [0u8; 64];
+
+        for ofs in (0..len).step_by(chunk.len()) {
+            let n = (len - ofs).min(chunk.len() as u32) as usize;
+            self.flash.read(addr + ofs, &mut chunk[..n]);
+
+            if chunk[..n].iter().any(|&b| b != 0xFF) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn read_wrapping(&mut self, addr: u32, buf: &mut [u8]) {
+        let to_wrap = (self.flash.capacity() - addr).min(buf.len() as u32) as usize;
+
+        self.flash.read(addr, &mut buf[..to_wrap]);
+        if to_wrap < buf.len() {
+            self.flash.read(0, &mut buf[to_wrap..]);
+        }
+    }
+
+    /// Mounted logs, then the current one
+    fn extent(&self, id: u16) -> Option<(LogHeader, u32, u32)> {
+        let index = (id as usize).checked_sub(1)?;
+        let sector_size = self.flash.sector_size();
+
+        match self.logs.get(index) {
+            Some(log) => Some((log.header, log.first_sector, log.num_sectors * sector_size)),
+            None if index == self.logs.len() => self.current.map(|(header, first_sector)| {
+                (header, first_sector, self.len + self.buf.len() as u32)
+            }),
+            None => None,
+        }
+    }
+}
+
+/// Logs as stored in the flash: the size of the logs from previous boots is rounded up to whole
+/// sectors, read back as erased bytes past their end.
+impl<F: NorFlash> LogStorage for FlashLog<F> {
+    fn num_logs(&self) -> u16 {
+        (self.logs.len() + self.current.is_some() as usize) as u16
+    }
+
+    fn log_info(&self, id: u16) -> Option<LogInfo> {
+        let (header, _, size) = self.extent(id)?;
+
+        Some(LogInfo {
+            size,
+            time_utc: header.time_utc,
+        })
+    }
+
+    fn read(&mut self, id: u16, offset: u32, buf: &mut [u8]) -> usize {
+        let Some((_, first_sector, size)) = self.extent(id) else {
+            return 0;
+        };
+        if self
+            .current
+            .is_some_and(|(_, sector)| sector == first_sector)
+        {
+            self.flush();
+        }
+
+        let len = (size.saturating_sub(offset) as usize).min(buf.len());
+        let addr = (first_sector * self.flash.sector_size() + offset) % self.flash.capacity();
+        self.read_wrapping(addr, &mut buf[..len]);
+
+        len
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+
+    use crate::mav_crater::{AdaState_DATA, MavMessage};
+
+    use super::*;
+
+    const SECTOR: u32 = 1024;
+
+    struct RamFlash {
+        data: Vec<u8>,
+        erase_count: Vec<u32>,
+    }
+
+    impl RamFlash {
+        fn new(num_sectors: u32) -> Self {
+            Self {
+                data: vec![0xFF; (num_sectors * SECTOR) as usize],
+                erase_count: vec![0; num_sectors as usize],
+            }
+        }
+    }
+
+    impl NorFlash for &mut RamFlash {
+        fn capacity(&self) -> u32 {
+            self.data.len() as u32
+        }
+
+        fn sector_size(&self) -> u32 {
+            SECTOR
+        }
+
+        fn read(&mut self, addr: u32, buf: &mut [u8]) {
+            let addr = addr as usize;
+            buf.copy_from_slice(&self.data[addr..addr + buf.len()]);
+        }
+
+        fn program(&mut self, addr: u32, data: &[u8]) {
+            for (i, &b) in data.iter().enumerate() {
+                let byte = &mut self.data[addr as usize + i];
+                assert_eq!(*byte, 0xFF, "Programming a non erased byte");
+                *byte = b;
+            }
+        }
+
+        fn erase_sector(&mut self, addr: u32) {
+            let start = (addr - addr % SECTOR) as usize;
+            self.data[start..start + SECTOR as usize].fill(0xFF);
+            self.erase_count[start / SECTOR as usize] += 1;
+        }
+    }
+
+    fn message(i: u32) -> MavMessage {
+        MavMessage::AdaState(AdaState_DATA {
+            timestamp_us: i as i64,
+            altitude_m: i as f32,
+            ..AdaState_DATA::DEFAULT
+        })
+    }
+
+    #[test]
+    fn test_flash_log() {
+        let mut flash = RamFlash::new(8);
+
+        // First boot: blank flash, nothing to erase
+        let mut log = FlashLog::mount(&mut flash);
+        assert!(log.logs().is_empty());
+        assert!(!log.append(&[1, 2, 3]));
+
+        log.begin(1000, 2 * SECTOR);
+        assert_eq!(log.num_erased(), 0);
+        for i in 0..40 {
+            assert!(log.append_message(&message(i)));
+        }
+        log.flush();
+
+        // Readable while being written
+        let size = log.log_info(1).unwrap().size as usize;
+        assert!(size > SECTOR as usize && size < 2 * SECTOR as usize);
+        let mut data = vec![0u8; size];
+        assert_eq!(log.read(1, 0, &mut data), size);
+        let header = LogHeader::from_bytes(&data).unwrap();
+        assert_eq!(header.time_utc, 1000);
+        assert_eq!(header.records, MavMessage::VERSION);
+        assert_eq!(data[LOG_HEADER_LEN], 0xFD);
+
+        // Second boot: starts after the first log
+        let mut log = FlashLog::mount(&mut flash);
+        assert_eq!(
+            log.logs(),
+            [LogExtent {
+                header: LogHeader::new(1, 1000),
+                first_sector: 0,
+                num_sectors: 2
+            }]
+        );
+        log.begin(2000, SECTOR);
+        assert!(log.append(&[0u8; 3 * SECTOR as usize]));
+        log.flush();
+
+        let log = FlashLog::mount(&mut flash);
+        assert_eq!(log.logs().len(), 2);
+        assert_eq!(log.logs()[1].first_sector, 2);
+        assert_eq!(log.logs()[1].num_sectors, 4);
+
+        // Third boot: fills the flash, overwriting the oldest log and wrapping around. The
+        // sectors already written are erased, the blank ones are not.
+        let mut log = FlashLog::mount(&mut flash);
+        log.begin(3000, 0);
+        assert!(log.append(&[0u8; 3 * SECTOR as usize]));
+        assert!(!log.append(&[0u8; 4 * SECTOR as usize]));
+        assert_eq!(log.num_dropped(), 1);
+        log.flush();
+        assert_eq!(log.num_erased(), 2);
+
+        let log = FlashLog::mount(&mut flash);
+        assert_eq!(log.logs().len(), 2);
+        assert_eq!(log.logs()[1].header.seq, 3);
+        assert_eq!(log.logs()[1].first_sector, 6);
+        assert_eq!(log.logs()[1].num_sectors, 4);
+        drop(log);
+        assert_eq!(flash.erase_count, [1, 1, 0, 0, 0, 0, 0, 0]);
+    }
+}
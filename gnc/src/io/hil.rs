@@ -0,0 +1,333 @@
+//! Hardware-in-the-loop mode: the simulation streams the samples of its sensors to the flight
+//! computer as MAVLink messages (mav_crater dialect), over the debug serial port. The flight
+//! computer uses them in place of the samples of its sensor drivers.
+//!
+//! The simulation timestamps the samples in simulation time, and periodically sends a
+//! `HilTimeSync` message, echoed back right away by the flight computer. The samples are
+//! rebased to the local clock with the offset estimated from the sync messages.
+
+use heapless::Vec;
+use mavlink::{peek_reader::PeekReader, read_v2_msg};
+
+use crate::{
+    DurationU64, Instant, InstantU64,
+    common::Ts,
+    datatypes::sensors::{
+        GnssSensorSample, ImuSensorSample, MagnetometerSensorSample, PressureSensorSample,
+    },
+    mav_crater::{HilTimeSync_DATA, MavMessage},
+};
+
+const MAVLINK_V2_STX: u8 = 0xFD;
+/// Start marker, length, flags, sequence, system, component & message id
+const MAVLINK_V2_HEADER_LEN: usize = 10;
+const MAVLINK_V2_CHECKSUM_LEN: usize = 2;
+const MAVLINK_V2_SIGNATURE_LEN: usize = 13;
+const MAVLINK_IFLAG_SIGNED: u8 = 0x01;
+
+/// Window over which the smallest clock offset is kept
+const SYNC_WINDOW: DurationU64 = DurationU64::secs(5);
+
+/// Reassembles the MAVLink 2 frames of a byte stream received in chunks of any size, eg. from a
+/// serial port. Bytes outside of a valid frame are skipped.
+///
+/// `N` must fit the largest frame plus a received chunk.
+pub struct MavlinkDeframer<const N: usize> {
+    buf: Vec<u8, N>,
+}
+
+impl<const N: usize> MavlinkDeframer<N> {
+    pub fn new() -> Self {
+        Self { buf: Vec::new() }
+    }
+
+    /// Appends received bytes. If they do not fit, the bytes buffered so far are dropped with the
+    /// frame they were part of, and false is returned.
+    pub fn push(&mut self, bytes: &[u8]) -> bool {
+        if self.buf.extend_from_slice(bytes).is_ok() {
+            return true;
+        }
+
+        self.buf.clear();
+        let _ = self
+            .buf
+            .extend_from_slice(&bytes[bytes.len().saturating_sub(N)..]);
+        false
+    }
+
+    /// Decodes the next complete frame, if any
+    pub fn next_message(&mut self) -> Option<MavMessage> {
+        loop {
+            let Some(start) = self.buf.iter().position(|&b| b == MAVLINK_V2_STX) else {
+                self.buf.clear();
+                return None;
+            };
+            self.consume(start);
+
+            if self.buf.len() < MAVLINK_V2_HEADER_LEN {
+                return None;
+            }
+
+            let mut len = MAVLINK_V2_HEADER_LEN + self.buf[1] as usize + MAVLINK_V2_CHECKSUM_LEN;
+            if self.buf[2] & MAVLINK_IFLAG_SIGNED != 0 {
+                len += MAVLINK_V2_SIGNATURE_LEN;
+            }
+            if self.buf.len() < len {
+                return None;
+            }
+
+            match read_v2_msg::<MavMessage, _>(&mut PeekReader::new(&self.buf[..len])) {
+                Ok((_, msg)) => {
+                    self.consume(len);
+                    return Some(msg);
+                }
+                // Not a start marker after all, or a corrupted frame: resync on the next one
+                Err(_) => self.consume(1),
+            }
+        }
+    }
+
+    fn consume(&mut self, len: usize) {
+        self.buf.copy_within(len.., 0);
+        self.buf.truncate(self.buf.len() - len);
+    }
+}
+
+impl<const N: usize> Default for MavlinkDeframer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Estimates the offset from the simulation time to the local clock, from the sync messages of
+/// the simulation.
+///
+/// The transport delay only ever adds to the measured offsets, so the smallest one is the
+/// closest to the actual offset. It is kept over a window, and replaced by the smallest one of
+/// the last window at its end, to follow the drift between the clocks. The shortest transport
+/// delay stays in the offset: it adds to the latency of the samples, as a sensor's would.
+#[derive(Debug, Clone, Default)]
+pub struct HilClockSync {
+    /// Local time - simulation time [µs]
+    offset_us: Option<i64>,
+    /// Smallest offset measured in the current window
+    window_min_us: Option<i64>,
+    window_start: Option<Instant>,
+    last_sim_time_us: i64,
+}
+
+impl HilClockSync {
+    /// Accounts for a sync message sent at `sim_time_us`, received at `local`
+    pub fn update(&mut self, sim_time_us: i64, local: Instant) {
+        // The simulation restarted
+        if sim_time_us < self.last_sim_time_us {
+            *self = Self::default();
+        }
+        self.last_sim_time_us = sim_time_us;
+
+        let offset_us = local_us(local) - sim_time_us;
+        self.offset_us = Some(self.offset_us.map_or(offset_us, |o| o.min(offset_us)));
+        self.window_min_us = Some(self.window_min_us.map_or(offset_us, |o| o.min(offset_us)));
+
+        let window_start = *self.window_start.get_or_insert(local);
+        if local.0 - window_start.0 >= SYNC_WINDOW {
+            self.offset_us = self.window_min_us.take();
+            self.window_start = Some(local);
+        }
+    }
+
+    pub fn is_synced(&self) -> bool {
+        self.offset_us.is_some()
+    }
+
+    /// Local time of a simulation time, once synced. Never later than `now`.
+    pub fn to_local(&self, sim_time_us: i64, now: Instant) -> Option<Instant> {
+        let t_us = (sim_time_us + self.offset_us?).clamp(0, local_us(now));
+        Some(Instant(InstantU64::from_ticks(t_us as u64)))
+    }
+}
+
+fn local_us(t: Instant) -> i64 {
+    t.0.duration_since_epoch().to_micros() as i64
+}
+
+/// Sample of a simulated sensor, on the local clock
+#[derive(Debug, Clone)]
+pub enum HilSample {
+    Pressure(Ts<PressureSensorSample>),
+    Imu(Ts<ImuSensorSample>),
+    Gnss(Ts<GnssSensorSample>),
+    Mag(Ts<MagnetometerSensorSample>),
+}
+
+/// Sensor source of the HIL mode, in place of the sensor drivers: turns the messages of the
+/// simulation into sensor samples on the local clock. Samples received before the first sync
+/// message are dropped.
+#[derive(Debug, Clone, Default)]
+pub struct HilSensorSource {
+    sync: HilClockSync,
+}
+
+impl HilSensorSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_synced(&self) -> bool {
+        self.sync.is_synced()
+    }
+
+    /// Handles a message of the simulation received at `now`
+    pub fn handle(&mut self, msg: &MavMessage, now: Instant) -> Option<HilSample> {
+        if let MavMessage::HilTimeSync(sync) = msg {
+            self.sync.update(sync.sim_time_us, now);
+            return None;
+        }
+
+        let t = |sim_time_us| self.sync.to_local(sim_time_us, now);
+        let sample = match msg {
+            MavMessage::SensPressureSample(data) => {
+                HilSample::Pressure(Ts::new(t(data.timestamp_us)?, data.into()))
+            }
+            MavMessage::SensImuSample(data) => {
+                HilSample::Imu(Ts::new(t(data.timestamp_us)?, data.into()))
+            }
+            MavMessage::SensGnssSample(data) => {
+                HilSample::Gnss(Ts::new(t(data.timestamp_us)?, data.into()))
+            }
+            MavMessage::SensMagSample(data) => {
+                HilSample::Mag(Ts::new(t(data.timestamp_us)?, data.into()))
+            }
+            _ => return None,
+        };
+
+        Some(sample)
+    }
+}
+
+/// Echo of a sync message of the simulation, received at `now`, from which it measures the round
+/// trip of the link
+pub fn time_sync_reply(sync: &HilTimeSync_DATA, now: Instant) -> MavMessage {
+    MavMessage::HilTimeSync(HilTimeSync_DATA {
+        sim_time_us: sync.sim_time_us,
+        fc_time_us: local_us(now),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use mavlink::{MavHeader, write_v2_msg};
+
+    use crate::mav_crater::{SensImuSample_DATA, SensPressureSample_DATA};
+
+    use super::*;
+
+    fn at_us(us: u64) -> Instant {
+        Instant(InstantU64::from_ticks(us))
+    }
+
+    fn sync(sim_time_us: i64) -> MavMessage {
+        MavMessage::HilTimeSync(HilTimeSync_DATA {
+            sim_time_us,
+            fc_time_us: 0,
+        })
+    }
+
+    fn pressure(timestamp_us: i64) -> MavMessage {
+        MavMessage::SensPressureSample(SensPressureSample_DATA {
+            timestamp_us,
+            pressure_pa: 101325.0,
+            ..SensPressureSample_DATA::DEFAULT
+        })
+    }
+
+    /// Local time of a pressure sample taken at `sim_time_us`, received at `now_us`
+    fn rebased(source: &mut HilSensorSource, sim_time_us: i64, now_us: u64) -> u64 {
+        match source.handle(&pressure(sim_time_us), at_us(now_us)) {
+            Some(HilSample::Pressure(s)) => s.t.0.ticks(),
+            other => panic!("Unexpected sample {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_deframer() {
+        let mut stream = Vec::new();
+        stream.extend_from_slice(&[0x00, 0x42, 0x13]);
+        write_v2_msg(&mut stream, MavHeader::default(), &pressure(1)).unwrap();
+        let corrupted = stream.len() + 12;
+        write_v2_msg(&mut stream, MavHeader::default(), &pressure(2)).unwrap();
+        write_v2_msg(
+            &mut stream,
+            MavHeader::default(),
+            &MavMessage::SensImuSample(SensImuSample_DATA::DEFAULT),
+        )
+        .unwrap();
+        stream[corrupted] ^= 0xFF;
+
+        let mut deframer = MavlinkDeframer::<300>::new();
+        let mut messages = Vec::new();
+        for chunk in stream.chunks(7) {
+            assert!(deframer.push(chunk));
+            while let Some(msg) = deframer.next_message() {
+                messages.push(msg);
+            }
+        }
+
+        // The garbage & the corrupted frame are skipped
+        assert_eq!(messages.len(), 2);
+        assert!(matches!(&messages[0], MavMessage::SensPressureSample(s) if s.timestamp_us == 1));
+        assert!(matches!(messages[1], MavMessage::SensImuSample(_)));
+
+        // Overflow
+        let mut deframer = MavlinkDeframer::<16>::new();
+        assert!(deframer.push(&[0; 10]));
+        assert!(!deframer.push(&[0; 10]));
+    }
+
+    #[test]
+    fn test_hil_time_sync() {
+        let mut source = HilSensorSource::new();
+
+        // Dropped until synced
+        assert!(source.handle(&pressure(0), at_us(1_000_000)).is_none());
+        assert!(!source.is_synced());
+
+        // The local clock is 1 s ahead, the transport takes 2 to 5 ms
+        assert!(source.handle(&sync(100_000), at_us(1_105_000)).is_none());
+        assert!(source.is_synced());
+        assert_eq!(rebased(&mut source, 100_000, 1_106_000), 1_105_000);
+
+        source.handle(&sync(200_000), at_us(1_202_000));
+        source.handle(&sync(300_000), at_us(1_304_000));
+        assert_eq!(rebased(&mut source, 300_000, 1_306_000), 1_302_000);
+
+        // Never in the future
+        assert_eq!(rebased(&mut source, 310_000, 1_306_000), 1_306_000);
+
+        // The local clock drifts ahead: followed within two windows
+        for i in 4..=120 {
+            source.handle(&sync(i * 100_000), at_us(1_005_000 + i as u64 * 100_000));
+        }
+        assert_eq!(rebased(&mut source, 12_000_000, 13_010_000), 13_005_000);
+
+        // The simulation restarted
+        source.handle(&sync(0), at_us(14_003_000));
+        assert_eq!(rebased(&mut source, 0, 14_004_000), 14_003_000);
+
+        let reply = time_sync_reply(
+            &HilTimeSync_DATA {
+                sim_time_us: 5,
+                fc_time_us: 0,
+            },
+            at_us(7),
+        );
+        assert!(matches!(
+            reply,
+            MavMessage::HilTimeSync(HilTimeSync_DATA {
+                sim_time_us: 5,
+                fc_time_us: 7
+            })
+        ));
+    }
+}
@@ -0,0 +1,340 @@
+use alloc::boxed::Box;
+
+use crate::{
+    common::config_hash::{ConfigHash, ConfigHasher},
+    component::{Component, LoopContext},
+    events::Event,
+    hal::channel::{Receiver, Sender},
+    mav_crater::{ComponentId, LogData_DATA, LogEntry_DATA, MavMessage},
+};
+
+/// Log bytes carried by each LogData message
+pub const LOG_DATA_LEN: usize = 90;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogInfo {
+    /// [bytes]
+    pub size: u32,
+    /// Creation time, seconds since the UNIX epoch. 0 if unknown.
+    pub time_utc: u32,
+}
+
+/// Onboard log files, with ids from 1 to `num_logs()`
+pub trait LogStorage {
+    fn num_logs(&self) -> u16;
+
+    fn log_info(&self, id: u16) -> Option<LogInfo>;
+
+    /// Reads log `id` from `offset` into `buf`. Returns the number of bytes read, which is less
+    /// than the length of `buf` only at the end of the log.
+    fn read(&mut self, id: u16, offset: u32, buf: &mut [u8]) -> usize;
+}
+
+pub struct LogTransferHarness {
+    pub storage: Box<dyn LogStorage + Send>,
+    /// Messages received from the ground station
+    pub rx_uplink: Box<dyn Receiver<MavMessage> + Send>,
+    /// Log entries & data, sent to the ground
+    pub tx_log: Box<dyn Sender<MavMessage> + Send>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct LogTransferConfig {
+    /// Messages sent per step at most, which sets the transfer rate together with the loop rate
+    pub messages_per_step: u32,
+}
+
+impl Default for LogTransferConfig {
+    fn default() -> Self {
+        LogTransferConfig {
+            messages_per_step: 2,
+        }
+    }
+}
+
+impl ConfigHash for LogTransferConfig {
+    fn hash_config(&self, hasher: &mut ConfigHasher) {
+        hasher.write_section("log_transfer");
+        hasher.write_u64(self.messages_per_step as u64);
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ListRequest {
+    next: u32,
+    last: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct DataRequest {
+    id: u16,
+    ofs: u32,
+    end: u32,
+}
+
+/// Log download protocol, on the MAVLink log messages: the ground requests the list of the logs,
+/// then ranges of each of them. There are no acknowledgements: the ground requests again the
+/// ranges it missed.
+#[derive(Debug, Clone, Default)]
+pub struct LogServer {
+    list: Option<ListRequest>,
+    data: Option<DataRequest>,
+}
+
+impl LogServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.list.is_none() && self.data.is_none()
+    }
+
+    /// Stops the transfers in progress
+    pub fn cancel(&mut self) {
+        self.list = None;
+        self.data = None;
+    }
+
+    /// Handles a message from the ground. Messages other than log requests are ignored.
+    pub fn handle(&mut self, msg: &MavMessage, storage: &dyn LogStorage) {
+        match msg {
+            MavMessage::LogRequestList(req) => {
+                self.list = Some(ListRequest {
+                    next: req.start.max(1) as u32,
+                    last: req.end.min(storage.num_logs()) as u32,
+                });
+            }
+            MavMessage::LogRequestData(req) => {
+                // A new request replaces the one being streamed
+                self.data = if req.count == 0 {
+                    None
+                } else {
+                    storage.log_info(req.id).map(|info| {
+                        // Requests past the end are answered with an empty chunk
+                        let end = req.ofs.saturating_add(req.count).min(info.size);
+                        DataRequest {
+                            id: req.id,
+                            ofs: req.ofs,
+                            end: end.max(req.ofs),
+                        }
+                    })
+                };
+            }
+            _ => {}
+        }
+    }
+
+    /// Next message to send to the ground, if any. Log entries go first.
+    pub fn next_message(&mut self, storage: &mut dyn LogStorage) -> Option<MavMessage> {
+        if let Some(list) = self.list.as_mut() {
+            let num_logs = storage.num_logs();
+
+            if num_logs == 0 {
+                // An empty entry tells the ground that there are no logs
+                self.list = None;
+                return Some(MavMessage::LogEntry(LogEntry_DATA::DEFAULT));
+            }
+
+            while list.next <= list.last {
+                let id = list.next as u16;
+                list.next += 1;
+
+                if let Some(info) = storage.log_info(id) {
+                    return Some(MavMessage::LogEntry(LogEntry_DATA {
+                        id,
+                        num_logs,
+                        last_log_num: num_logs,
+                        time_utc: info.time_utc,
+                        size: info.size,
+                    }));
+                }
+            }
+            self.list = None;
+        }
+
+        let req = self.data.as_mut()?;
+
+        let mut chunk = LogData_DATA {
+            id: req.id,
+            ofs: req.ofs,
+            ..LogData_DATA::DEFAULT
+        };
+        let len = LOG_DATA_LEN.min((req.end - req.ofs) as usize);
+        let count = storage.read(req.id, req.ofs, &mut chunk.data[..len]);
+        chunk.count = count as u8;
+
+        req.ofs += count as u32;
+        if count < len || req.ofs >= req.end {
+            self.data = None;
+        }
+
+        Some(MavMessage::LogData(chunk))
+    }
+}
+
+/// Serves the onboard logs to the ground station. Transfers are refused from arming to landing,
+/// to leave the link to the flight telemetry.
+pub struct LogTransferComponent {
+    harness: LogTransferHarness,
+    config: LogTransferConfig,
+    server: LogServer,
+    enabled: bool,
+}
+
+impl LogTransferComponent {
+    pub fn new(harness: LogTransferHarness, config: LogTransferConfig) -> Self {
+        Self {
+            harness,
+            config,
+            server: LogServer::new(),
+            enabled: true,
+        }
+    }
+}
+
+impl Component for LogTransferComponent {
+    fn id(&self) -> ComponentId {
+        ComponentId::LogTransfer
+    }
+
+    fn handle_event(&mut self, event: Event, _context: &mut LoopContext) {
+        match event {
+            Event::FlightStateArmed => {
+                self.enabled = false;
+                self.server.cancel();
+            }
+            Event::FlightStateReady | Event::FlightLanded => self.enabled = true,
+            _ => {}
+        }
+    }
+
+    fn step(&mut self, context: &mut LoopContext) {
+        let t = context.step().step_time;
+
+        while let Some(msg) = self.harness.rx_uplink.try_recv() {
+            if self.enabled {
+                self.server.handle(&msg.v, self.harness.storage.as_ref());
+            }
+        }
+
+        for _ in 0..self.config.messages_per_step {
+            let Some(msg) = self.server.next_message(self.harness.storage.as_mut()) else {
+                break;
+            };
+            let _ = self.harness.tx_log.try_send(t, msg);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+
+    use crate::mav_crater::{LogRequestData_DATA, LogRequestList_DATA};
+
+    use super::*;
+
+    struct MemoryStorage {
+        logs: Vec<Vec<u8>>,
+    }
+
+    impl LogStorage for MemoryStorage {
+        fn num_logs(&self) -> u16 {
+            self.logs.len() as u16
+        }
+
+        fn log_info(&self, id: u16) -> Option<LogInfo> {
+            let log = self.logs.get((id as usize).checked_sub(1)?)?;
+            Some(LogInfo {
+                size: log.len() as u32,
+                time_utc: 0,
+            })
+        }
+
+        fn read(&mut self, id: u16, offset: u32, buf: &mut [u8]) -> usize {
+            let log = &self.logs[id as usize - 1];
+            let start = (offset as usize).min(log.len());
+            let len = buf.len().min(log.len() - start);
+            buf[..len].copy_from_slice(&log[start..start + len]);
+            len
+        }
+    }
+
+    fn drain(server: &mut LogServer, storage: &mut MemoryStorage) -> Vec<MavMessage> {
+        let mut out = vec![];
+        while let Some(msg) = server.next_message(storage) {
+            out.push(msg);
+        }
+        out
+    }
+
+    #[test]
+    fn test_log_server() {
+        let log: Vec<u8> = (0..200u32).map(|i| i as u8).collect();
+        let mut storage = MemoryStorage {
+            logs: vec![vec![1, 2, 3], log.clone()],
+        };
+        let mut server = LogServer::new();
+
+        server.handle(
+            &MavMessage::LogRequestList(LogRequestList_DATA {
+                start: 0,
+                end: 0xffff,
+            }),
+            &storage,
+        );
+        let entries = drain(&mut server, &mut storage);
+        assert_eq!(entries.len(), 2);
+        assert!(matches!(
+            &entries[1],
+            MavMessage::LogEntry(e) if e.id == 2 && e.size == 200 && e.num_logs == 2
+        ));
+
+        // Whole log: two full chunks and a partial one
+        server.handle(
+            &MavMessage::LogRequestData(LogRequestData_DATA {
+                id: 2,
+                ofs: 0,
+                count: 0xffffffff,
+            }),
+            &storage,
+        );
+        let mut received = vec![];
+        for msg in drain(&mut server, &mut storage) {
+            let MavMessage::LogData(chunk) = msg else {
+                panic!("Unexpected message {msg:?}");
+            };
+            assert_eq!(chunk.ofs as usize, received.len());
+            received.extend_from_slice(&chunk.data[..chunk.count as usize]);
+        }
+        assert_eq!(received, log);
+
+        // Retransmission of a range, and a request past the end
+        server.handle(
+            &MavMessage::LogRequestData(LogRequestData_DATA {
+                id: 2,
+                ofs: 95,
+                count: 10,
+            }),
+            &storage,
+        );
+        let chunks = drain(&mut server, &mut storage);
+        assert!(matches!(
+            &chunks[..],
+            [MavMessage::LogData(c)] if c.ofs == 95 && c.count == 10 && c.data[0] == 95
+        ));
+
+        server.handle(
+            &MavMessage::LogRequestData(LogRequestData_DATA {
+                id: 2,
+                ofs: 300,
+                count: 90,
+            }),
+            &storage,
+        );
+        let chunks = drain(&mut server, &mut storage);
+        assert!(matches!(&chunks[..], [MavMessage::LogData(c)] if c.count == 0));
+        assert!(server.is_idle());
+    }
+}
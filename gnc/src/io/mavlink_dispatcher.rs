@@ -1,20 +1,284 @@
-use crate::mav_crater;
+use alloc::boxed::Box;
 
-pub struct MavlinkDispatcherHarness {}
+use crate::{
+    component::{Component, LoopContext},
+    events::{Event, EventPublisher},
+    hal::channel::{Receiver, Sender},
+    mav_crater::{CommandAck_DATA, CommandResult, ComponentId, GroundCommandId, MavMessage},
+};
 
+pub struct MavlinkDispatcherHarness {
+    /// Messages received from the ground station
+    pub rx_uplink: Box<dyn Receiver<MavMessage> + Send>,
+    /// Acknowledgements of the commands
+    pub tx_ack: Box<dyn Sender<MavMessage> + Send>,
+}
+
+/// Flight phase, as far as the ground commands are concerned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CommandPhase {
+    /// Before calibration
+    Init,
+    Calibrating,
+    Ready,
+    Armed,
+    InFlight,
+}
+
+/// Turns the commands received from the ground station into events for the other components,
+/// and acknowledges each of them. Commands that make no sense in the current flight phase, such
+/// as a deployment on the pad or a calibration once armed, are denied.
 pub struct CraterMavlinkDispatcher {
     harness: MavlinkDispatcherHarness,
+    /// Publishes the commands as coming from the ground
+    event_pub: EventPublisher,
+    phase: CommandPhase,
 }
 
 impl CraterMavlinkDispatcher {
-    pub fn new(harness: MavlinkDispatcherHarness) -> Self {
-        Self { harness }
+    pub fn new(harness: MavlinkDispatcherHarness, event_pub: EventPublisher) -> Self {
+        Self {
+            harness,
+            event_pub,
+            phase: CommandPhase::Init,
+        }
+    }
+
+    fn is_allowed(&self, command: GroundCommandId) -> bool {
+        match command {
+            // The FMM only starts a calibration from its initial state
+            GroundCommandId::FmmCalibrate => self.phase == CommandPhase::Init,
+            GroundCommandId::AdaCalibrate => {
+                matches!(self.phase, CommandPhase::Init | CommandPhase::Ready)
+            }
+            GroundCommandId::FmmArm => self.phase == CommandPhase::Ready,
+            GroundCommandId::FmmDisarm | GroundCommandId::FmmForceLiftoff => {
+                self.phase == CommandPhase::Armed
+            }
+            GroundCommandId::DeployDrogue | GroundCommandId::DeployMain => {
+                self.phase == CommandPhase::InFlight
+            }
+            GroundCommandId::DumpEventLog => true,
+        }
+    }
+
+    /// Handles a message from the ground station. Messages other than commands are ignored.
+    pub fn dispatch(&mut self, msg: MavMessage, context: &mut LoopContext) {
+        let MavMessage::GroundCommand(cmd) = msg else {
+            return;
+        };
+        let t = context.step().step_time;
+
+        let result = if self.is_allowed(cmd.command) {
+            self.event_pub
+                .publish(Event::from_ground_command(cmd.command), t);
+            CommandResult::Accepted
+        } else {
+            CommandResult::Denied
+        };
+
+        let ack = MavMessage::CommandAck(CommandAck_DATA {
+            timestamp_us: t.0.duration_since_epoch().to_micros() as i64,
+            command: cmd.command,
+            result,
+        });
+        let _ = self.harness.tx_ack.try_send(t, ack);
+    }
+}
+
+impl Component for CraterMavlinkDispatcher {
+    fn id(&self) -> ComponentId {
+        ComponentId::CommandDispatcher
     }
 
-    pub fn dispatch(msg: mav_crater::MavMessage) {
-        match msg {
-            _ => {}
+    fn handle_event(&mut self, event: Event, _context: &mut LoopContext) {
+        self.phase = match event {
+            Event::CmdFmmCalibrate if self.phase == CommandPhase::Init => CommandPhase::Calibrating,
+            Event::FlightStateReady => CommandPhase::Ready,
+            Event::FlightStateArmed => CommandPhase::Armed,
+            Event::FlightLiftoff => CommandPhase::InFlight,
+            _ => return,
+        };
+    }
+
+    fn step(&mut self, context: &mut LoopContext) {
+        while let Some(msg) = self.harness.rx_uplink.try_recv() {
+            self.dispatch(msg.v, context);
         }
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::{
+        DurationU64, Instant, InstantU64,
+        component::StepData,
+        events::EventQueue,
+        hal::channel::testing::{TestReceiver, TestSender, channel},
+        mav_crater::GroundCommand_DATA,
+    };
+
+    const STEP_MS: u64 = 10;
+
+    struct Fixture {
+        dispatcher: CraterMavlinkDispatcher,
+        events: EventQueue,
+        tx_uplink: TestSender<MavMessage>,
+        rx_ack: TestReceiver<MavMessage>,
+        t_ms: u64,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let (tx_uplink, rx_uplink) = channel();
+            let (tx_ack, rx_ack) = channel();
+            let events = EventQueue::new();
+
+            let dispatcher = CraterMavlinkDispatcher::new(
+                MavlinkDispatcherHarness {
+                    rx_uplink: Box::new(rx_uplink),
+                    tx_ack: Box::new(tx_ack),
+                },
+                events.get_publisher(ComponentId::CommandDispatcher),
+            );
+
+            Self {
+                dispatcher,
+                events,
+                tx_uplink,
+                rx_ack,
+                t_ms: 0,
+            }
+        }
+
+        /// Calibrated on the pad
+        fn ready() -> Self {
+            let mut fix = Self::new();
+            fix.handle(Event::FlightStateReady);
+            fix
+        }
+
+        /// Armed on the pad
+        fn armed() -> Self {
+            let mut fix = Self::ready();
+            fix.handle(Event::FlightStateArmed);
+            fix
+        }
+
+        /// In flight, after liftoff
+        fn in_flight() -> Self {
+            let mut fix = Self::armed();
+            fix.handle(Event::FlightLiftoff);
+            fix
+        }
+
+        fn context(&self) -> LoopContext {
+            LoopContext::new(StepData {
+                step_time: Instant(InstantU64::from_ticks(self.t_ms * 1000)),
+                step_interval: DurationU64::millis(STEP_MS).into(),
+                step_count: (self.t_ms / STEP_MS) as u32,
+            })
+        }
+
+        fn handle(&mut self, event: Event) {
+            let mut context = self.context();
+            self.dispatcher.handle_event(event, &mut context);
+        }
+
+        /// Sends the command from the ground. Returns the acknowledged result & the published
+        /// events.
+        fn command(&mut self, command: GroundCommandId) -> (CommandResult, Vec<Event>) {
+            self.t_ms += STEP_MS;
+            let mut context = self.context();
+            self.tx_uplink.send(
+                context.step().step_time,
+                MavMessage::GroundCommand(GroundCommand_DATA { command }),
+            );
+            self.dispatcher.step(&mut context);
+
+            let Some(ack) = self.rx_ack.try_recv() else {
+                panic!("Command not acknowledged");
+            };
+            let MavMessage::CommandAck(ack) = ack.v else {
+                panic!("Expected an ack");
+            };
+            assert_eq!(ack.command, command);
+
+            let mut events = Vec::new();
+            while let Some(item) = self.events.pop_event() {
+                events.push(item.v.event);
+            }
+            (ack.result, events)
+        }
+
+        fn assert_accepted(&mut self, command: GroundCommandId) {
+            let (result, events) = self.command(command);
+            assert_eq!(result, CommandResult::Accepted, "{command:?}");
+            assert_eq!(events, [Event::from_ground_command(command)]);
+        }
+
+        fn assert_denied(&mut self, command: GroundCommandId) {
+            let (result, events) = self.command(command);
+            assert_eq!(result, CommandResult::Denied, "{command:?}");
+            assert!(events.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_calibrate_only_in_init() {
+        let mut fix = Fixture::new();
+        fix.assert_denied(GroundCommandId::FmmArm);
+        fix.assert_accepted(GroundCommandId::FmmCalibrate);
+
+        // Calibrating: the FMM would ignore a second calibration
+        fix.handle(Event::CmdFmmCalibrate);
+        fix.assert_denied(GroundCommandId::FmmCalibrate);
+
+        let mut fix = Fixture::ready();
+        fix.assert_denied(GroundCommandId::FmmCalibrate);
+        fix.assert_accepted(GroundCommandId::AdaCalibrate);
+    }
+
+    #[test]
+    fn test_arm_only_when_ready() {
+        let mut fix = Fixture::ready();
+        fix.assert_denied(GroundCommandId::FmmDisarm);
+        fix.assert_denied(GroundCommandId::FmmForceLiftoff);
+        fix.assert_accepted(GroundCommandId::FmmArm);
+
+        let mut fix = Fixture::armed();
+        fix.assert_denied(GroundCommandId::FmmArm);
+        fix.assert_denied(GroundCommandId::FmmCalibrate);
+    }
+
+    #[test]
+    fn test_disarm_and_liftoff_only_when_armed() {
+        let mut fix = Fixture::armed();
+        fix.assert_accepted(GroundCommandId::FmmForceLiftoff);
+        fix.assert_accepted(GroundCommandId::FmmDisarm);
+
+        // Back to ready once the FMM is disarmed
+        fix.handle(Event::FlightStateReady);
+        fix.assert_denied(GroundCommandId::FmmDisarm);
+        fix.assert_denied(GroundCommandId::FmmForceLiftoff);
+        fix.assert_accepted(GroundCommandId::FmmArm);
+    }
+
+    #[test]
+    fn test_deploy_only_in_flight() {
+        for mut fix in [Fixture::new(), Fixture::ready(), Fixture::armed()] {
+            fix.assert_denied(GroundCommandId::DeployDrogue);
+            fix.assert_denied(GroundCommandId::DeployMain);
+        }
+
+        let mut fix = Fixture::in_flight();
+        fix.assert_accepted(GroundCommandId::DeployDrogue);
+        fix.assert_accepted(GroundCommandId::DeployMain);
+        fix.assert_denied(GroundCommandId::FmmDisarm);
+        fix.assert_denied(GroundCommandId::FmmArm);
+        fix.assert_accepted(GroundCommandId::DumpEventLog);
+    }
+}
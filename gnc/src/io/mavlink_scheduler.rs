@@ -0,0 +1,121 @@
+use crate::{DurationU64, Instant};
+
+/// Message streams sent to the ground
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MavlinkStream {
+    Nav,
+    Ada,
+    Recovery,
+}
+
+impl MavlinkStream {
+    const COUNT: usize = 3;
+
+    fn index(&self) -> usize {
+        match self {
+            MavlinkStream::Nav => 0,
+            MavlinkStream::Ada => 1,
+            MavlinkStream::Recovery => 2,
+        }
+    }
+}
+
+/// Rates of the downlinked streams [Hz]. A zero rate disables the stream.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StreamRates {
+    pub nav_hz: f32,
+    pub ada_hz: f32,
+    pub recovery_hz: f32,
+}
+
+impl StreamRates {
+    fn period(&self, stream: MavlinkStream) -> Option<DurationU64> {
+        let rate_hz = match stream {
+            MavlinkStream::Nav => self.nav_hz,
+            MavlinkStream::Ada => self.ada_hz,
+            MavlinkStream::Recovery => self.recovery_hz,
+        };
+
+        (rate_hz > 0.0).then(|| DurationU64::micros((1_000_000.0 / rate_hz) as u64))
+    }
+}
+
+/// Limits each stream to its rate, so that the messages fit the radio bandwidth
+#[derive(Debug, Clone)]
+pub struct MavlinkRateScheduler {
+    rates: StreamRates,
+    last_sent: [Option<Instant>; MavlinkStream::COUNT],
+}
+
+impl MavlinkRateScheduler {
+    pub fn new(rates: StreamRates) -> Self {
+        Self {
+            rates,
+            last_sent: [None; MavlinkStream::COUNT],
+        }
+    }
+
+    pub fn rates(&self) -> &StreamRates {
+        &self.rates
+    }
+
+    /// Changes the rates. The next message of each stream is due one new period after the last
+    /// one, so that a rate increase takes effect immediately.
+    pub fn set_rates(&mut self, rates: StreamRates) {
+        self.rates = rates;
+    }
+
+    /// Whether a message of `stream` can be sent at `t`. If so, it is accounted as sent.
+    pub fn poll(&mut self, stream: MavlinkStream, t: Instant) -> bool {
+        let Some(period) = self.rates.period(stream) else {
+            return false;
+        };
+
+        let last_sent = &mut self.last_sent[stream.index()];
+        if last_sent.is_some_and(|last| t.0 - last.0 < period) {
+            return false;
+        }
+
+        *last_sent = Some(t);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::InstantU64;
+
+    use super::*;
+
+    fn count_sent(
+        scheduler: &mut MavlinkRateScheduler,
+        stream: MavlinkStream,
+        from_ms: u64,
+    ) -> usize {
+        // 1 s of 1 kHz steps
+        (from_ms..from_ms + 1000)
+            .filter(|ms| scheduler.poll(stream, Instant(InstantU64::from_ticks(ms * 1000))))
+            .count()
+    }
+
+    #[test]
+    fn test_rates() {
+        let mut scheduler = MavlinkRateScheduler::new(StreamRates {
+            nav_hz: 10.0,
+            ada_hz: 0.0,
+            recovery_hz: 1.0,
+        });
+
+        assert_eq!(count_sent(&mut scheduler, MavlinkStream::Nav, 0), 10);
+        assert_eq!(count_sent(&mut scheduler, MavlinkStream::Ada, 0), 0);
+        assert_eq!(count_sent(&mut scheduler, MavlinkStream::Recovery, 0), 1);
+
+        scheduler.set_rates(StreamRates {
+            nav_hz: 50.0,
+            ada_hz: 20.0,
+            recovery_hz: 1.0,
+        });
+        assert_eq!(count_sent(&mut scheduler, MavlinkStream::Nav, 1000), 50);
+        assert_eq!(count_sent(&mut scheduler, MavlinkStream::Ada, 1000), 20);
+    }
+}
@@ -2,8 +2,15 @@ use mavlink::MavHeader;
 
 use crate::mav_crater;
 
+pub mod airtime;
+pub mod burst_capture;
+pub mod can_protocol;
+pub mod flash_log;
+pub mod hil;
+pub mod log_transfer;
 pub mod mavlink_dispatcher;
 pub mod mavlink_reader;
+pub mod mavlink_scheduler;
 pub mod mavlink_writer;
 
 pub const MAVLINK_MSG_MAX_SIZE: usize = 280;
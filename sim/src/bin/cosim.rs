@@ -0,0 +1,33 @@
+use anyhow::Result;
+use crater::{
+    crater::logging::rerun::CraterUiLogConfig, model::CosimCrater, runner::SingleThreadedRunner,
+};
+use log::info;
+use std::{env, path::Path};
+
+/// Runs the simulation in lockstep with an external autopilot, connected through the
+/// co-simulation socket configured in `sim.cosim.address`
+fn main() -> Result<()> {
+    // Default log level to "info"
+    if env::var("RUST_LOG").is_err() {
+        unsafe { env::set_var("RUST_LOG", "info") }
+    }
+
+    pretty_env_logger::init();
+
+    let runner = SingleThreadedRunner::new(
+        CosimCrater {},
+        &Path::new("config/params.toml"),
+        &[],
+        Box::new(CraterUiLogConfig),
+        crater::nodes::ParameterSampling::Perfect,
+        None,
+        None,
+    )?;
+
+    runner.run_blocking()?;
+
+    info!("Co-simulation completed");
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::Result;
+use clap::Parser;
+use crater::{
+    crater::{environment::LaunchSite, logging::dispersion},
+    parameters,
+};
+use log::info;
+
+/// Computes the dispersion statistics & landing ellipses of a Monte Carlo campaign, from its
+/// montecarlo.csv. Writes dispersion.json, dispersion.geojson & dispersion.kml to the campaign
+/// directory.
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// Output directory of the campaign
+    campaign: PathBuf,
+
+    /// Parameters of the campaign, for the launch site
+    #[arg(short, long, default_value = "config/params.toml")]
+    params: PathBuf,
+}
+
+fn main() -> Result<()> {
+    if std::env::var("RUST_LOG").is_err() {
+        unsafe { std::env::set_var("RUST_LOG", "info") }
+    }
+    pretty_env_logger::init();
+
+    let args = Args::parse();
+
+    let params = parameters::parse_string(fs::read_to_string(&args.params)?)?;
+    let site = LaunchSite::from_params(&params)?;
+
+    let dispersion = dispersion::write_campaign(&args.campaign, &site)?;
+    info!(
+        "{} runs, written to '{}'",
+        dispersion.runs,
+        args.campaign.display()
+    );
+    dispersion.log_summary();
+
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::Result;
+use clap::Parser;
+use crater::{crater::planning::dr_budget::DrBudget, model::OpenLoopCrater, parameters};
+
+/// Dead-reckoning accuracy budget: runs the onboard navigation with the GPS lost at randomized
+/// times and reports the horizontal position error against the time since the outage, to size
+/// the recovery search area
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    #[arg(short, long, default_value = "config/params.toml")]
+    params: PathBuf,
+
+    /// Also write the budget to this csv file
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let params = parameters::parse_string(fs::read_to_string(&args.params)?)?;
+    let budget = DrBudget::from_params(OpenLoopCrater {}, params)?;
+
+    let (runs, rows) = budget.run()?;
+
+    let outages: Vec<f64> = runs.iter().map(|r| r.outage_start_s).collect();
+    println!(
+        "{} runs, GPS lost between {:.1} and {:.1} s",
+        runs.len(),
+        outages.iter().copied().fold(f64::INFINITY, f64::min),
+        outages.iter().copied().fold(f64::NEG_INFINITY, f64::max)
+    );
+
+    println!(
+        "{:>8} {:>6} {:>10} {:>10} {:>10} {:>10}",
+        "t[s]", "runs", "p50[m]", "p90[m]", "p99[m]", "max[m]"
+    );
+    for r in rows.iter() {
+        println!(
+            "{:>8.1} {:>6} {:>10.1} {:>10.1} {:>10.1} {:>10.1}",
+            r.t_since_outage_s, r.num_runs, r.p50_m, r.p90_m, r.p99_m, r.max_m
+        );
+    }
+
+    if let Some(output) = args.output {
+        let mut writer = csv::Writer::from_path(output)?;
+        for r in rows.iter() {
+            writer.serialize(r)?;
+        }
+        writer.flush()?;
+    }
+
+    Ok(())
+}
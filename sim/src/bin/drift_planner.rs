@@ -0,0 +1,58 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::Result;
+use clap::Parser;
+use crater::{crater::planning::drift::DriftPlanner, parameters};
+
+/// Pre-flight planner: propagates the descent under canopy from a grid of apogee points
+/// with the forecast winds and recommends a launch rail orientation
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    #[arg(short, long, default_value = "config/params.toml")]
+    params: PathBuf,
+
+    /// Write the landing point of every grid apogee point to this csv file
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let params = parameters::parse_string(fs::read_to_string(&args.params)?)?;
+    let planner = DriftPlanner::from_params(&params)?;
+
+    let plan = planner.plan();
+
+    if let Some(output) = args.output {
+        let mut writer = csv::Writer::from_path(output)?;
+        for point in plan.points.iter() {
+            writer.serialize(point)?;
+        }
+        writer.flush()?;
+    }
+
+    let num_feasible = plan.points.iter().filter(|p| p.feasible).count();
+    println!(
+        "{num_feasible} of {} apogee points land within the recovery area",
+        plan.points.len()
+    );
+
+    match plan.recommended {
+        Some(p) => {
+            println!("Recommended rail orientation:");
+            println!("  azimuth:   {:.1} deg", p.azimuth_deg);
+            println!("  elevation: {:.1} deg", p.elevation_deg);
+            println!(
+                "  apogee (N, E): ({:.0}, {:.0}) m, landing (N, E): ({:.0}, {:.0}) m, miss: {:.0} m",
+                p.apogee_n_m, p.apogee_e_m, p.landing_n_m, p.landing_e_m, p.miss_m
+            );
+        }
+        None => {
+            println!("No rail orientation keeps the landing point within the recovery area!");
+        }
+    }
+
+    Ok(())
+}
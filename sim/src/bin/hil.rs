@@ -0,0 +1,74 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{Result, anyhow};
+use chrono::{SecondsFormat, Utc};
+use clap::Parser;
+use crater::{
+    parameters,
+    scenarios::hil::{self, HilReport, HilStepStatus},
+};
+use log::info;
+
+/// Bench acceptance of the flight hardware: runs the HIL test sequence (sensor sanity, actuator
+/// sweep, full simulated flight) against the hardware connected to the co-simulation bridge,
+/// and writes a machine-readable (JSON) report for the acceptance records.
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    #[arg(short, long, default_value = "config/params.toml")]
+    params: PathBuf,
+
+    /// JSON test report
+    #[arg(short, long, default_value = "hil_report.json")]
+    output: PathBuf,
+
+    /// Run all the steps, even after one did not pass
+    #[arg(long)]
+    keep_going: bool,
+}
+
+fn main() -> Result<()> {
+    if std::env::var("RUST_LOG").is_err() {
+        unsafe { std::env::set_var("RUST_LOG", "info") }
+    }
+    pretty_env_logger::init();
+
+    let args = Args::parse();
+
+    let params = parameters::parse_string(fs::read_to_string(&args.params)?)?;
+    let date = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+
+    let steps = hil::run_sequence(&hil::bench_sequence(), &params, args.keep_going);
+
+    for step in steps.iter() {
+        for result in step.results.iter() {
+            let status = if result.passed { "PASS" } else { "FAIL" };
+            println!(
+                "[{status}] {}: {} ({})",
+                step.name, result.description, result.actual
+            );
+        }
+        if let Some(error) = &step.error {
+            println!("[ERROR] {}: {error}", step.name);
+        }
+        if step.status == HilStepStatus::Skipped {
+            println!("[SKIPPED] {}", step.name);
+        }
+    }
+
+    let report = HilReport {
+        date,
+        params: args.params.display().to_string(),
+        passed: steps.iter().all(|s| s.status == HilStepStatus::Pass),
+        steps,
+    };
+    fs::write(&args.output, serde_json::to_string_pretty(&report)?)?;
+    info!("HIL report written to '{}'", args.output.display());
+
+    if report.passed {
+        info!("HIL sequence passed");
+        Ok(())
+    } else {
+        Err(anyhow!("HIL sequence failed"))
+    }
+}
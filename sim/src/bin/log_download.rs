@@ -0,0 +1,276 @@
+use std::{
+    fs,
+    io::{ErrorKind, Read, Write},
+    net::{TcpStream, UdpSocket},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Result, anyhow};
+use clap::Parser;
+use crater::crater::gnc::{
+    gs_link::{parse_frame, take_frames},
+    log_download::{LogDownload, decode_log, message_counts},
+};
+use crater_gnc::{
+    MavHeader,
+    mav_crater::{LogEntry_DATA, LogRequestData_DATA, LogRequestList_DATA, MavMessage},
+    write_v2_msg,
+};
+use log::{info, warn};
+
+/// Downloads the onboard logs over MAVLink, from the vehicle or from the simulation ground
+/// station link, then decodes them. Each log is saved as is (.bin) and decoded one message per
+/// line (.txt).
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// "udp" or "tcp"
+    #[arg(short, long, default_value = "udp")]
+    transport: String,
+
+    /// Local UDP address, where the vehicle sends its telemetry
+    #[arg(short, long, default_value = "0.0.0.0:14550")]
+    address: String,
+
+    /// Address of the vehicle
+    #[arg(short, long, default_value = "127.0.0.1:14551")]
+    remote: String,
+
+    /// Ids of the logs to download. All of them if not given.
+    #[arg(short, long)]
+    ids: Vec<u16>,
+
+    #[arg(short, long, default_value = "logs")]
+    output: PathBuf,
+
+    /// Missing chunks are requested again after this long without data [ms]
+    #[arg(long, default_value_t = 500)]
+    timeout_ms: u64,
+
+    /// Requests sent at most per log before giving up
+    #[arg(long, default_value_t = 100)]
+    max_requests: usize,
+}
+
+enum Link {
+    Udp(UdpSocket),
+    Tcp(TcpStream),
+}
+
+/// MAVLink connection to the vehicle, as a ground station
+struct GroundStation {
+    link: Link,
+    rx_buf: Vec<u8>,
+    sequence: u8,
+}
+
+impl GroundStation {
+    const POLL_PERIOD: Duration = Duration::from_millis(20);
+
+    fn connect(args: &Args) -> Result<Self> {
+        let link = match args.transport.as_str() {
+            "udp" => {
+                let socket = UdpSocket::bind(&args.address)?;
+                socket.connect(&args.remote)?;
+                socket.set_read_timeout(Some(Self::POLL_PERIOD))?;
+                Link::Udp(socket)
+            }
+            "tcp" => {
+                let stream = TcpStream::connect(&args.remote)?;
+                stream.set_read_timeout(Some(Self::POLL_PERIOD))?;
+                Link::Tcp(stream)
+            }
+            unknown => return Err(anyhow!("Unknown transport '{unknown}'")),
+        };
+
+        Ok(Self {
+            link,
+            rx_buf: vec![],
+            sequence: 0,
+        })
+    }
+
+    fn send(&mut self, msg: &MavMessage) -> Result<()> {
+        let header = MavHeader {
+            system_id: 255,
+            component_id: 190,
+            sequence: self.sequence,
+        };
+        self.sequence = self.sequence.wrapping_add(1);
+
+        let mut frame = vec![];
+        write_v2_msg(&mut frame, header, msg)
+            .map_err(|e| anyhow!("Error encoding a MAVLink message: {e:?}"))?;
+
+        match &mut self.link {
+            Link::Udp(socket) => {
+                socket.send(&frame)?;
+            }
+            Link::Tcp(stream) => stream.write_all(&frame)?,
+        }
+        Ok(())
+    }
+
+    /// Messages received within the poll period
+    fn receive(&mut self) -> Result<Vec<MavMessage>> {
+        let mut chunk = [0u8; 4096];
+        let res = match &mut self.link {
+            Link::Udp(socket) => socket.recv(&mut chunk),
+            Link::Tcp(stream) => stream.read(&mut chunk),
+        };
+
+        match res {
+            Ok(0) if matches!(self.link, Link::Tcp(_)) => return Err(anyhow!("Link closed")),
+            Ok(len) => self.rx_buf.extend_from_slice(&chunk[..len]),
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            // Nobody listening on the other side of the UDP link yet
+            Err(e) if e.kind() == ErrorKind::ConnectionRefused => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        Ok(take_frames(&mut self.rx_buf)
+            .iter()
+            .filter_map(|f| parse_frame(f))
+            .collect())
+    }
+
+    fn list_logs(&mut self, timeout: Duration, max_requests: usize) -> Result<Vec<LogEntry_DATA>> {
+        let mut entries: Vec<LogEntry_DATA> = vec![];
+
+        for _ in 0..max_requests {
+            self.send(&MavMessage::LogRequestList(LogRequestList_DATA {
+                start: 0,
+                end: 0xffff,
+            }))?;
+
+            let mut last_rx = Instant::now();
+            while last_rx.elapsed() < timeout {
+                for msg in self.receive()? {
+                    let MavMessage::LogEntry(entry) = msg else {
+                        continue;
+                    };
+                    last_rx = Instant::now();
+
+                    if entry.num_logs == 0 {
+                        return Ok(vec![]);
+                    }
+                    if !entries.iter().any(|e| e.id == entry.id) {
+                        entries.push(entry);
+                    }
+                    if entries.len() == entry.num_logs as usize {
+                        entries.sort_by_key(|e| e.id);
+                        return Ok(entries);
+                    }
+                }
+            }
+        }
+
+        Err(anyhow!(
+            "Incomplete log list after {max_requests} requests ({} entries)",
+            entries.len()
+        ))
+    }
+
+    /// Downloads a log, requesting the missing ranges until it is complete
+    fn download(
+        &mut self,
+        entry: &LogEntry_DATA,
+        timeout: Duration,
+        max_requests: usize,
+    ) -> Result<LogDownload> {
+        let mut download = LogDownload::new(entry);
+
+        for _ in 0..max_requests {
+            let Some(&(ofs, count)) = download.missing().first() else {
+                break;
+            };
+            self.send(&MavMessage::LogRequestData(LogRequestData_DATA {
+                id: entry.id,
+                ofs,
+                count,
+            }))?;
+
+            // Chunks lost within the range only show up once the stream stops
+            let mut last_rx = Instant::now();
+            while last_rx.elapsed() < timeout && download.is_missing(ofs, count) {
+                for msg in self.receive()? {
+                    if let MavMessage::LogData(chunk) = msg {
+                        last_rx = Instant::now();
+                        download.on_data(&chunk);
+                    }
+                }
+            }
+        }
+
+        if !download.is_complete() {
+            return Err(anyhow!(
+                "Log {} incomplete after {max_requests} requests: {} of {} bytes missing",
+                entry.id,
+                download.missing().iter().map(|(_, c)| c).sum::<u32>(),
+                download.size()
+            ));
+        }
+
+        Ok(download)
+    }
+}
+
+fn main() -> Result<()> {
+    if std::env::var("RUST_LOG").is_err() {
+        unsafe { std::env::set_var("RUST_LOG", "info") }
+    }
+    pretty_env_logger::init();
+
+    let args = Args::parse();
+    let timeout = Duration::from_millis(args.timeout_ms);
+
+    let mut gs = GroundStation::connect(&args)?;
+
+    let entries = gs.list_logs(timeout, args.max_requests)?;
+    info!("{} logs onboard", entries.len());
+    for e in entries.iter() {
+        info!("  log {}: {} bytes", e.id, e.size);
+    }
+
+    fs::create_dir_all(&args.output)?;
+
+    for entry in entries
+        .iter()
+        .filter(|e| args.ids.is_empty() || args.ids.contains(&e.id))
+    {
+        let start = Instant::now();
+        let download = match gs.download(entry, timeout, args.max_requests) {
+            Ok(download) => download,
+            Err(e) => {
+                warn!("{e}");
+                continue;
+            }
+        };
+        info!(
+            "Log {} downloaded in {:.1} s",
+            entry.id,
+            start.elapsed().as_secs_f64()
+        );
+
+        let path = args.output.join(format!("log_{:04}.bin", entry.id));
+        fs::write(&path, download.data())?;
+
+        let messages = match decode_log(download.data()) {
+            Ok(messages) => messages,
+            Err(e) => {
+                warn!("Log {} not decoded: {e}", entry.id);
+                continue;
+            }
+        };
+        let text: String = messages.iter().map(|m| format!("{m:?}\n")).collect();
+        fs::write(path.with_extension("txt"), text)?;
+
+        info!("  {} messages decoded", messages.len());
+        for (name, count) in message_counts(&messages) {
+            info!("    {name}: {count}");
+        }
+    }
+
+    Ok(())
+}
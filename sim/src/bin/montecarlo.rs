@@ -1,4 +1,5 @@
 use anyhow::Result;
+use clap::Parser;
 use crater::{
     crater::logging::rerun::CraterUiLogConfig, model::OpenLoopCrater,
     montecarlorunner::MonteCarloRunner,
@@ -9,6 +10,19 @@ use std::{
     path::{Path, PathBuf},
 };
 
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// Number of runs
+    #[arg(short, long, default_value_t = 500)]
+    runs: usize,
+
+    /// Seed of the campaign, from which the seed of each run is derived. Random if not
+    /// provided: reuse the logged one to reproduce a campaign.
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
 fn main() -> Result<()> {
     // Default log level to "info"
     if env::var("RUST_LOG").is_err() {
@@ -16,6 +30,7 @@ fn main() -> Result<()> {
     }
 
     pretty_env_logger::init();
+    let args = Args::parse();
     crater();
 
     let mut out_dir = PathBuf::from("out");
@@ -30,8 +45,9 @@ fn main() -> Result<()> {
         OpenLoopCrater {},
         &Path::new("config/params.toml"),
         CraterUiLogConfig,
-        500,
+        args.runs,
         None,
+        args.seed,
         out_dir,
     )?;
 
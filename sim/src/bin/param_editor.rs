@@ -0,0 +1,31 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::Result;
+use clap::Parser;
+use crater::parameters::{self, editor::ParameterEditor, schema::ParameterSchema};
+
+/// Interactive editor of a parameter file, checking the values against the schema
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    #[arg(short, long, default_value = "config/params.toml")]
+    params: PathBuf,
+
+    /// Units & valid ranges of the parameters
+    #[arg(long, default_value = "config/params_schema.toml")]
+    schema: PathBuf,
+
+    /// File the edited parameters are saved to. Defaults to the parameter file.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let params = parameters::parse_string(fs::read_to_string(&args.params)?)?;
+    let schema = ParameterSchema::parse_string(&fs::read_to_string(&args.schema)?)?;
+
+    let output = args.output.unwrap_or(args.params);
+    ParameterEditor::new(params, schema, &output).run_blocking()
+}
@@ -0,0 +1,48 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::Result;
+use clap::Parser;
+use crater::{crater::planning::rail_sweep::RailSweep, model::OpenLoopCrater, parameters};
+
+/// Sweeps the launch rail orientation under the configured wind model, reporting apogee,
+/// maximum angle of attack off the rail and landing point for each setting
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    #[arg(short, long, default_value = "config/params.toml")]
+    params: PathBuf,
+
+    /// Also write the results to this csv file
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let params = parameters::parse_string(fs::read_to_string(&args.params)?)?;
+    let sweep = RailSweep::from_params(OpenLoopCrater {}, params)?;
+
+    let results = sweep.run()?;
+
+    println!(
+        "{:>8} {:>8} {:>10} {:>10} {:>10} {:>10}",
+        "az[deg]", "el[deg]", "apogee[m]", "aoa[deg]", "land_n[m]", "land_e[m]"
+    );
+    for r in results.iter() {
+        println!(
+            "{:>8.1} {:>8.1} {:>10.1} {:>10.2} {:>10.1} {:>10.1}",
+            r.azimuth_deg, r.elevation_deg, r.apogee_m, r.max_aoa_deg, r.landing_n_m, r.landing_e_m
+        );
+    }
+
+    if let Some(output) = args.output {
+        let mut writer = csv::Writer::from_path(output)?;
+        for r in results.iter() {
+            writer.serialize(r)?;
+        }
+        writer.flush()?;
+    }
+
+    Ok(())
+}
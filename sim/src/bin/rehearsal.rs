@@ -0,0 +1,136 @@
+use std::{
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow};
+use clap::Parser;
+use crater::{
+    crater::{aero::forecast, logging::report::html::render_html},
+    model::GsLinkedModel,
+    parameters,
+    scenarios::{self, Scenario, ScenarioOutcome},
+};
+use log::info;
+
+/// Flight in a box: rehearses the whole flight with the flight parameters before deployment.
+///
+/// The flight software runs in the loop, while the orchestrator plays the ground segment,
+/// scripting the countdown: its commands are uplinked & the flight software followed through the
+/// radio link model, with the airtime of the flight radio. Each rehearsal stage is checked, and a
+/// pass/fail report is written.
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    #[arg(short, long, default_value = "config/params.toml")]
+    params: PathBuf,
+
+    /// Pass/fail rehearsal report
+    #[arg(short, long, default_value = "rehearsal.md")]
+    output: PathBuf,
+
+    /// Also write the HTML flight report of each stage in this directory
+    #[arg(long)]
+    flight_reports: Option<PathBuf>,
+
+    /// Also expose the vehicle to the ground station on `sim.gs_link`, behind the radio link, so
+    /// that it follows the countdown & can send its own commands
+    #[arg(long)]
+    gs_link: bool,
+}
+
+fn main() -> Result<()> {
+    if std::env::var("RUST_LOG").is_err() {
+        unsafe { std::env::set_var("RUST_LOG", "info") }
+    }
+    pretty_env_logger::init();
+
+    let args = Args::parse();
+
+    let mut params = parameters::parse_string(fs::read_to_string(&args.params)?)?;
+    forecast::apply_forecast(&mut params)?;
+
+    if let Some(dir) = &args.flight_reports {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut outcomes: Vec<(&'static str, &'static str, ScenarioOutcome)> = vec![];
+    for stage in scenarios::rehearsal() {
+        let stage = if args.gs_link {
+            Scenario {
+                model: Box::new(GsLinkedModel { model: stage.model }),
+                ..stage
+            }
+        } else {
+            stage
+        };
+
+        info!("Rehearsal stage '{}': {}", stage.name, stage.description);
+        let outcome = stage.run(&params)?;
+
+        for result in outcome.results.iter() {
+            let status = if result.passed { "PASS" } else { "FAIL" };
+            println!("[{status}] {}: {} ({})", stage.name, result.description, result.actual);
+        }
+
+        if let Some(dir) = &args.flight_reports {
+            fs::write(
+                dir.join(format!("{}.html", stage.name)),
+                render_html(&outcome.report),
+            )?;
+        }
+
+        outcomes.push((stage.name, stage.description, outcome));
+    }
+
+    let passed = outcomes.iter().all(|(_, _, outcome)| outcome.passed());
+    fs::write(&args.output, render_report(&args.params, &outcomes, passed)?)?;
+    info!("Rehearsal report written to '{}'", args.output.display());
+
+    if passed {
+        info!("Rehearsal passed");
+        Ok(())
+    } else {
+        Err(anyhow!("Rehearsal failed"))
+    }
+}
+
+fn render_report(
+    params: &Path,
+    outcomes: &[(&'static str, &'static str, ScenarioOutcome)],
+    passed: bool,
+) -> Result<String> {
+    let status = |passed: bool| if passed { "PASS" } else { "FAIL" };
+
+    let mut out = String::new();
+    writeln!(out, "# Flight rehearsal: {}\n", status(passed))?;
+    writeln!(out, "Parameters: `{}`\n", params.display())?;
+
+    for (name, description, outcome) in outcomes.iter() {
+        let m = &outcome.report.metrics;
+
+        writeln!(out, "## {name}: {}\n", status(outcome.passed()))?;
+        writeln!(out, "{description}\n")?;
+        writeln!(
+            out,
+            "Apogee {:.1} m at T+{:.2} s, max Mach {:.2}, max acceleration {:.1} g\n",
+            m.apogee_m, m.t_apogee_s, m.max_mach, m.max_accel_g
+        )?;
+
+        writeln!(out, "| Check | Result | Actual |")?;
+        writeln!(out, "|---|---|---|")?;
+        for result in outcome.results.iter() {
+            writeln!(
+                out,
+                "| {} | {} | {} |",
+                result.description,
+                status(result.passed),
+                result.actual
+            )?;
+        }
+        writeln!(out)?;
+    }
+
+    Ok(out)
+}
@@ -0,0 +1,136 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use chrono::TimeDelta;
+use clap::Parser;
+use crater::{
+    crater::{
+        gnc::log_download::decode_log,
+        logging::report::{FlightData, FlightReport, FlightReportBuilder, html::render_html},
+    },
+    model::{ModelBuilder, OpenLoopCrater},
+    nodes::{FtlOrderedExecutor, NodeManager, ParameterSampling},
+    parameters::{self, ParameterMap},
+    telemetry::{TelemetryService, recording::read_recording},
+};
+use log::info;
+
+/// Runs a simulation, or loads a recorded one or a flight log, and generates an HTML report with
+/// trajectory plots, event timeline, envelope metrics, navigation errors and the parameters used
+/// for the run
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    #[arg(short, long, default_value = "config/params.toml")]
+    params: PathBuf,
+
+    /// Sample random parameters using this seed. Nominal parameters are used if not provided.
+    #[arg(short, long)]
+    seed: Option<u64>,
+
+    /// Telemetry recording of a run (--record-telemetry of the sim) to report on, instead of
+    /// running a simulation
+    #[arg(long, conflicts_with_all = ["seed", "flight_log"])]
+    recording: Option<PathBuf>,
+
+    /// Onboard log downloaded from the flight computer (log_download) to report on, instead of
+    /// running a simulation
+    #[arg(long, conflicts_with = "seed")]
+    flight_log: Option<PathBuf>,
+
+    #[arg(short, long, default_value = "report.html")]
+    output: PathBuf,
+}
+
+/// Landing mass of the recorded flights, not in the recordings: the nominal dry mass
+fn dry_mass_kg(params: &ParameterMap) -> Result<f64> {
+    Ok(params
+        .get_param("sim.rocket.mass")?
+        .value_randfloat()?
+        .value())
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map_or(path.display().to_string(), |name| {
+        name.to_string_lossy().to_string()
+    })
+}
+
+/// Report of a recorded run or a flight log, if any given
+fn recorded_report(args: &Args, params: &ParameterMap) -> Result<Option<FlightReport>> {
+    let (path, data) = match (&args.recording, &args.flight_log) {
+        (Some(path), _) => {
+            info!("Loading recording '{}'...", path.display());
+            let data = FlightData::from_recording(&read_recording(path)?, dry_mass_kg(params)?)?;
+            (path, data)
+        }
+        (None, Some(path)) => {
+            info!("Loading flight log '{}'...", path.display());
+            let messages = decode_log(&fs::read(path)?)?;
+            (
+                path,
+                FlightData::from_flight_log(&messages, dry_mass_kg(params)?),
+            )
+        }
+        (None, None) => return Ok(None),
+    };
+
+    let title = format!("Crater flight report ({})", file_name(path));
+    Ok(Some(FlightReport::new(&title, data, params)))
+}
+
+fn main() -> Result<()> {
+    if std::env::var("RUST_LOG").is_err() {
+        unsafe { std::env::set_var("RUST_LOG", "info") }
+    }
+    pretty_env_logger::init();
+
+    let args = Args::parse();
+
+    let params = parameters::parse_string(fs::read_to_string(&args.params)?)?;
+
+    let report = match recorded_report(&args, &params)? {
+        Some(report) => report,
+        None => simulated_report(&args, params)?,
+    };
+
+    for check in report.compliance.iter() {
+        let status = if check.passed { "PASS" } else { "FAIL" };
+        info!("[{status}] {} ({})", check.rule, check.actual);
+    }
+
+    fs::write(&args.output, render_html(&report))?;
+    info!("Report written to '{}'", args.output.display());
+
+    Ok(())
+}
+
+fn simulated_report(args: &Args, params: ParameterMap) -> Result<FlightReport> {
+    let dt_sec = params.get_param("sim.dt")?.value_float()?;
+    let dt = (dt_sec * 1000000.0) as i64;
+
+    let ts = TelemetryService::default();
+    let report_builder = FlightReportBuilder::new(&ts)?;
+
+    let (sampling, seed) = match args.seed {
+        Some(seed) => (ParameterSampling::Random, seed),
+        None => (ParameterSampling::Perfect, 0),
+    };
+
+    let mut nm = NodeManager::new(ts, params, sampling, seed);
+    OpenLoopCrater {}.build(&mut nm)?;
+
+    let run_params = nm.parameters();
+
+    info!("Running simulation...");
+    FtlOrderedExecutor::run_blocking(nm, TimeDelta::microseconds(dt))?;
+
+    let title = match args.seed {
+        Some(seed) => format!("Crater flight report (seed {seed})"),
+        None => "Crater flight report (nominal)".to_string(),
+    };
+    Ok(report_builder.build(&title, &run_params))
+}
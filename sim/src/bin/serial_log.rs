@@ -9,7 +9,7 @@ use clap::Parser;
 use crater::{
     core::time::Timestamp,
     crater::logging::rerun::{
-        RerunWrite,
+        ClockAlignment, RerunWrite,
         crater_log_impl::{ImuSensorSampleLog, PressureSensorSampleLog},
     },
 };
@@ -35,6 +35,16 @@ struct Args {
 
     #[arg(short, long)]
     output: Option<PathBuf>,
+
+    /// Align target timestamps to the simulation time and log them on the "sim_time" timeline,
+    /// shared with the simulation truth. The alignment is estimated from the HIL time syncs
+    /// echoed by the flight computer.
+    #[arg(short, long, default_value_t = false)]
+    align: bool,
+
+    /// Number of time-sync samples used to estimate the target clock offset and drift
+    #[arg(long, default_value_t = 1000)]
+    align_window: usize,
 }
 
 fn main() -> Result<()> {
@@ -76,6 +86,12 @@ fn main() -> Result<()> {
     let mut initial_system_time = None;
     let log_start_time = Instant::now();
 
+    let mut alignment = if args.align {
+        Some(ClockAlignment::new(args.align_window))
+    } else {
+        None
+    };
+
     while let Err(_) = ctrlc_rx.try_recv() {
         match read_v2_msg::<MavMessage, _>(&mut reader) {
             Ok((_, msg)) => {
@@ -83,7 +99,7 @@ fn main() -> Result<()> {
                 if Instant::now() - log_start_time < Duration::from_millis(500) {
                     continue;
                 }
-                handle_message(&mut rec, &msg, &mut initial_system_time)?;
+                handle_message(&mut rec, &msg, &mut initial_system_time, alignment.as_mut())?;
             }
             // Err(MessageReadError::Io(io)) => {}
             Err(err) => {
@@ -100,7 +116,27 @@ fn handle_message(
     rec: &mut RecordingStream,
     msg: &MavMessage,
     initial_time: &mut Option<i64>,
+    mut alignment: Option<&mut ClockAlignment>,
 ) -> Result<()> {
+    // Echo of a HIL time sync: the same instant in simulation & flight computer time
+    if let MavMessage::HilTimeSync(sync) = msg {
+        if let Some(alignment) = alignment.as_deref_mut() {
+            alignment.add_sync(
+                Timestamp::from_micros(sync.fc_time_us),
+                Timestamp::from_micros(sync.sim_time_us),
+            );
+        }
+        return Ok(());
+    }
+
+    // Target timestamps, mapped to the simulation timeline once synced
+    let timestamp = |ts_us: i64| match alignment.as_deref() {
+        Some(alignment) if alignment.is_synced() => {
+            ("sim_time", alignment.align(Timestamp::from_micros(ts_us)))
+        }
+        _ => ("system_time", Timestamp::from_micros(ts_us)),
+    };
+
     fn rec_log_time(rec: &mut RecordingStream, ts: i64, initial_time: &mut Option<i64>) {
         match initial_time {
             Some(initial_time) => {
@@ -124,12 +160,13 @@ fn handle_message(
             };
 
             rec_log_time(rec, data.timestamp_us, initial_time);
+            let (timeline, ts) = timestamp(data.timestamp_us);
 
             PressureSensorSampleLog.write(
                 rec,
-                "system_time",
+                timeline,
                 format!("sensors/{sensors_name}").as_str(),
-                Timestamp::from_micros(data.timestamp_us),
+                ts,
                 data.into(),
             )
         }
@@ -138,12 +175,13 @@ fn handle_message(
                 ImuSensorId::Icm42688 => "icm42688",
             };
             rec_log_time(rec, data.timestamp_us, initial_time);
+            let (timeline, ts) = timestamp(data.timestamp_us);
 
             ImuSensorSampleLog.write(
                 rec,
-                "system_time",
+                timeline,
                 format!("sensors/{sensors_name}").as_str(),
-                Timestamp::from_micros(data.timestamp_us),
+                ts,
                 data.into(),
             )
         }
@@ -0,0 +1,80 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{Result, anyhow};
+use clap::Parser;
+use crater::{crater::soak::run_soak, model::SoakCrater, parameters};
+use log::info;
+
+/// Soak test: holds the rocket on the pad for hours of simulated time, with the flight software
+/// running, and monitors the drift of the state, of the ADA filter covariance and the memory
+/// usage. Catches the slow divergences and leaks that short runs never expose.
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    #[arg(short, long, default_value = "config/params.toml")]
+    params: PathBuf,
+
+    /// Simulated duration of the soak
+    #[arg(long, default_value_t = 4.0)]
+    hours: f64,
+
+    /// Also write the monitors at every check to this csv file
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    if std::env::var("RUST_LOG").is_err() {
+        unsafe { std::env::set_var("RUST_LOG", "info") }
+    }
+    pretty_env_logger::init();
+
+    let args = Args::parse();
+
+    let params = parameters::parse_string(fs::read_to_string(&args.params)?)?;
+
+    info!("Soaking for {:.1} h of simulated time", args.hours);
+    let report = run_soak(&SoakCrater {}, &params, args.hours * 3600.0)?;
+
+    println!(
+        "{:>8} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
+        "t[h]", "pos[m]", "att[deg]", "vel[m/s]", "ada_std[m]", "ada_cov[-]", "mem[MB]"
+    );
+    for s in report.statuses.iter() {
+        println!(
+            "{:>8.2} {:>10.2e} {:>10.2e} {:>10.2e} {:>10.3} {:>10.2e} {:>10.1}",
+            s.t_s / 3600.0,
+            s.position_drift_m,
+            s.attitude_drift_deg,
+            s.velocity_m_s,
+            s.ada_altitude_std_m,
+            s.ada_cov_drift,
+            s.memory_growth_mb
+        );
+    }
+
+    if let Some(output) = args.output {
+        let mut writer = csv::Writer::from_path(output)?;
+        for s in report.statuses.iter() {
+            writer.serialize(s)?;
+        }
+        writer.flush()?;
+    }
+
+    for d in report.drifts.iter() {
+        println!(
+            "[FAIL] {} drift {:.3e} above {:.3e} at T+{:.2} h",
+            d.monitor,
+            d.value,
+            d.limit,
+            d.t_s / 3600.0
+        );
+    }
+
+    if report.passed() {
+        info!("Soak passed");
+        Ok(())
+    } else {
+        Err(anyhow!("Soak failed"))
+    }
+}
@@ -0,0 +1,79 @@
+use std::{fs, path::PathBuf, thread, time::Duration};
+
+use anyhow::Result;
+use chrono::TimeDelta;
+use clap::Parser;
+use crater::{
+    crater::logging::watch::{DEFAULT_CHANNELS, WatchView},
+    model::{ModelBuilder, OpenLoopCrater},
+    nodes::{FtlOrderedExecutor, NodeManager, ParameterSampling, RealtimePacer},
+    parameters,
+    telemetry::TelemetryService,
+};
+
+/// Runs a simulation showing the live values of a set of channels in the terminal
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    #[arg(short, long, default_value = "config/params.toml")]
+    params: PathBuf,
+
+    /// Channels to watch. Defaults to the rocket state, aerodynamic state, ADA and navigation.
+    #[arg(short, long, value_delimiter = ',')]
+    channels: Vec<String>,
+
+    /// Sample random parameters using this seed. Nominal parameters are used if not provided.
+    #[arg(short, long)]
+    seed: Option<u64>,
+
+    /// Simulation speed wrt real time. Set to 0 to run as fast as possible.
+    #[arg(long, default_value_t = 1.0)]
+    speed: f64,
+
+    #[arg(long, default_value_t = 100)]
+    refresh_ms: u64,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let params = parameters::parse_string(fs::read_to_string(&args.params)?)?;
+
+    let dt_sec = params.get_param("sim.dt")?.value_float()?;
+    let dt = (dt_sec * 1000000.0) as i64;
+
+    let channels: Vec<&str> = if args.channels.is_empty() {
+        DEFAULT_CHANNELS.to_vec()
+    } else {
+        args.channels.iter().map(|c| c.as_str()).collect()
+    };
+
+    let ts = TelemetryService::default();
+    let view = WatchView::new(&ts, &channels)?;
+
+    let (sampling, seed) = match args.seed {
+        Some(seed) => (ParameterSampling::Random, seed),
+        None => (ParameterSampling::Perfect, 0),
+    };
+
+    let mut nm = NodeManager::new(ts, params, sampling, seed);
+    OpenLoopCrater {}.build(&mut nm)?;
+
+    if args.speed > 0.0 {
+        let speed = args.speed;
+        nm.add_node("pacer", move |_| Ok(Box::new(RealtimePacer::new(speed))))?;
+    }
+
+    let simulation = thread::spawn(move || -> Result<()> {
+        FtlOrderedExecutor::run_blocking(nm, TimeDelta::microseconds(dt))
+    });
+
+    view.run_blocking(Duration::from_millis(args.refresh_ms))?;
+
+    // The view may be closed before the end of the simulation: don't wait for it
+    if simulation.is_finished() {
+        simulation.join().unwrap()?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,152 @@
+use anyhow::Result;
+use chrono::TimeDelta;
+
+use crate::{
+    core::time::{Clock, Timestamp},
+    crater::{
+        actuators::command_link::{CommandLink, CommandLinkParams},
+        channels,
+        gnc::AirbrakePosition,
+    },
+    nodes::{Node, NodeContext, StepResult},
+    parameters::ParameterMap,
+    telemetry::{LatestReceiver, TelemetrySender, Timestamped},
+};
+
+#[derive(Debug, Clone)]
+pub struct AirbrakeParams {
+    /// Time constant of the first order response to the command
+    pub time_constant_s: f64,
+    /// Maximum extension rate, in fraction of the full extension per second
+    pub max_rate_1_s: f64,
+}
+
+impl AirbrakeParams {
+    pub fn from_params(params: &ParameterMap) -> Result<Self> {
+        Ok(Self {
+            time_constant_s: params.get_param("time_constant")?.value_float()?,
+            max_rate_1_s: params.get_param("max_rate")?.value_float()?,
+        })
+    }
+}
+
+/// Extension dynamics of the airbrakes: first order lag, limited in rate
+#[derive(Debug, Clone)]
+pub struct AirbrakeDynamics {
+    params: AirbrakeParams,
+    extension: f64,
+}
+
+impl AirbrakeDynamics {
+    pub fn new(params: AirbrakeParams) -> Self {
+        Self {
+            params,
+            extension: 0.0,
+        }
+    }
+
+    /// Advances the extension by `dt_s` towards the command. Returns the new extension.
+    pub fn step(&mut self, cmd: f64, dt_s: f64) -> f64 {
+        let p = &self.params;
+        let cmd = cmd.clamp(0.0, 1.0);
+
+        let rate = if p.time_constant_s > 0.0 {
+            (cmd - self.extension) / p.time_constant_s
+        } else if dt_s > 0.0 {
+            (cmd - self.extension) / dt_s
+        } else {
+            0.0
+        };
+        let rate = rate.clamp(-p.max_rate_1_s, p.max_rate_1_s);
+
+        // Do not overshoot the command with large steps
+        let step = rate * dt_s;
+        self.extension = if step.abs() > (cmd - self.extension).abs() {
+            cmd
+        } else {
+            self.extension + step
+        };
+
+        self.extension
+    }
+}
+
+/// Airbrake actuator, commanded by GNC through its command link. Holds the last command until a
+/// new one is received.
+#[derive(Debug)]
+pub struct Airbrake {
+    rx_cmd: LatestReceiver<AirbrakePosition>,
+    tx_pos: TelemetrySender<AirbrakePosition>,
+
+    link: CommandLink,
+    dynamics: AirbrakeDynamics,
+}
+
+impl Airbrake {
+    pub fn new(ctx: NodeContext) -> Result<Self> {
+        let rx_cmd = ctx
+            .telemetry()
+            .subscribe_latest(channels::gnc::AIRBRAKE_COMMAND)?;
+        let tx_pos = ctx
+            .telemetry()
+            .publish(channels::actuators::AIRBRAKE_POSITION)?;
+
+        let airbrake_params = ctx.parameters().get_map("sim.rocket.airbrake")?;
+        let params = AirbrakeParams::from_params(airbrake_params)?;
+        let link = CommandLink::new(
+            CommandLinkParams::from_params(airbrake_params.get_map("link")?)?,
+            1,
+            ctx.get_rng_256(),
+        );
+
+        Ok(Self {
+            rx_cmd,
+            tx_pos,
+            link,
+            dynamics: AirbrakeDynamics::new(params),
+        })
+    }
+}
+
+impl Node for Airbrake {
+    fn step(&mut self, _: usize, dt: TimeDelta, clock: &dyn Clock) -> Result<StepResult> {
+        let cmd = self
+            .rx_cmd
+            .get()
+            .map_or(0.0, |Timestamped(_, cmd)| cmd.extension);
+
+        let t = Timestamp::now(clock);
+        let cmd = self.link.step(&[cmd], t.monotonic.elapsed_seconds_f64())[0];
+
+        let extension = self.dynamics.step(cmd, dt.num_microseconds().unwrap() as f64 / 1e6);
+        self.tx_pos.send(t, AirbrakePosition { extension });
+
+        Ok(StepResult::Continue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limited_lag() {
+        let mut dynamics = AirbrakeDynamics::new(AirbrakeParams {
+            time_constant_s: 0.1,
+            max_rate_1_s: 2.0,
+        });
+
+        // Far from the command the rate limit dominates
+        let ext = dynamics.step(1.0, 0.01);
+        assert!((ext - 0.02).abs() < 1e-12);
+
+        // Close to it, the first order lag
+        for _ in 0..1000 {
+            dynamics.step(1.0, 0.01);
+        }
+        let ext = dynamics.step(0.5, 0.01);
+        assert!((ext - 0.98).abs() < 1e-9);
+
+        assert_eq!(dynamics.step(-1.0, 10.0), 0.0);
+    }
+}
@@ -0,0 +1,249 @@
+use anyhow::{Result, anyhow};
+use rand::Rng;
+use rand_xoshiro::Xoshiro256StarStar;
+
+use crate::parameters::ParameterMap;
+
+/// PWM servo signal: one pulse per frame, its width encoding the command
+#[derive(Debug, Clone)]
+pub struct PwmParams {
+    pub period_s: f64,
+    /// Pulse width of the zero command [µs]
+    pub center_us: f64,
+    /// Pulse width change per unit of the command [µs]
+    pub gain_us: f64,
+    pub min_pulse_us: f64,
+    pub max_pulse_us: f64,
+    /// Pulse width resolution of the timer generating the signal [µs]
+    pub resolution_us: f64,
+}
+
+impl PwmParams {
+    pub fn from_params(params: &ParameterMap) -> Result<Self> {
+        let float = |name: &str| -> Result<f64> { Ok(params.get_param(name)?.value_float()?) };
+
+        Ok(Self {
+            period_s: 1.0 / float("rate")?,
+            center_us: float("center")?,
+            gain_us: float("gain")?,
+            min_pulse_us: float("min_pulse")?,
+            max_pulse_us: float("max_pulse")?,
+            resolution_us: float("resolution")?,
+        })
+    }
+
+    fn encode(&self, cmd: f64) -> f64 {
+        let pulse_us =
+            (self.center_us + cmd * self.gain_us).clamp(self.min_pulse_us, self.max_pulse_us);
+
+        let pulse_us = if self.resolution_us > 0.0 {
+            (pulse_us / self.resolution_us).round() * self.resolution_us
+        } else {
+            pulse_us
+        };
+
+        (pulse_us - self.center_us) / self.gain_us
+    }
+}
+
+/// Digital serial bus: the commands are sent as fixed width integers over the command range,
+/// and frames failing the CRC check are discarded by the actuator
+#[derive(Debug, Clone)]
+pub struct SerialBusParams {
+    pub period_s: f64,
+    pub min: f64,
+    pub max: f64,
+    pub bits: u32,
+    /// Probability of a frame being corrupted
+    pub crc_error_rate: f64,
+}
+
+impl SerialBusParams {
+    pub fn from_params(params: &ParameterMap) -> Result<Self> {
+        let float = |name: &str| -> Result<f64> { Ok(params.get_param(name)?.value_float()?) };
+
+        let bits = params.get_param("bits")?.value_int()?;
+        if !(1..=32).contains(&bits) {
+            return Err(anyhow!(
+                "Serial bus command width must be 1 to 32 bits, got {bits}"
+            ));
+        }
+
+        Ok(Self {
+            period_s: 1.0 / float("rate")?,
+            min: float("min")?,
+            max: float("max")?,
+            bits: bits as u32,
+            crc_error_rate: float("crc_error_rate")?,
+        })
+    }
+
+    fn encode(&self, cmd: f64) -> f64 {
+        let levels = ((1u64 << self.bits) - 1) as f64;
+        let lsb = (self.max - self.min) / levels;
+
+        let code = ((cmd.clamp(self.min, self.max) - self.min) / lsb).round();
+        self.min + code * lsb
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum CommandLinkParams {
+    /// Commands reach the actuator as soon as they are produced, at full precision
+    Ideal,
+    Pwm(PwmParams),
+    Serial(SerialBusParams),
+}
+
+impl CommandLinkParams {
+    pub fn from_params(params: &ParameterMap) -> Result<Self> {
+        let link = params.get_param("type")?.value_string()?;
+
+        match link.as_str() {
+            "ideal" => Ok(Self::Ideal),
+            "pwm" => Ok(Self::Pwm(PwmParams::from_params(params.get_map("pwm")?)?)),
+            "serial" => Ok(Self::Serial(SerialBusParams::from_params(
+                params.get_map("serial")?,
+            )?)),
+            _ => Err(anyhow!("Unknown command link '{link}'")),
+        }
+    }
+}
+
+/// Transport of the commands from the flight computer to an actuator.
+///
+/// The commands are sampled once per frame of the link, quantized to its resolution, and held
+/// by the actuator until the next valid frame.
+#[derive(Debug)]
+pub struct CommandLink {
+    params: CommandLinkParams,
+    rng: Xoshiro256StarStar,
+
+    /// Commands as decoded by the actuator
+    output: Vec<f64>,
+    last_frame: Option<u64>,
+    crc_errors: u64,
+}
+
+impl CommandLink {
+    /// Link carrying `channels` commands in each frame
+    pub fn new(params: CommandLinkParams, channels: usize, rng: Xoshiro256StarStar) -> Self {
+        Self {
+            params,
+            rng,
+            output: vec![0.0; channels],
+            last_frame: None,
+            crc_errors: 0,
+        }
+    }
+
+    /// Latest commands `cmd` at time `t_s`. Returns the commands seen by the actuator.
+    pub fn step(&mut self, cmd: &[f64], t_s: f64) -> &[f64] {
+        match &self.params {
+            CommandLinkParams::Ideal => self.output.copy_from_slice(cmd),
+            CommandLinkParams::Pwm(pwm) => {
+                if new_frame(&mut self.last_frame, pwm.period_s, t_s) {
+                    for (out, cmd) in self.output.iter_mut().zip(cmd) {
+                        *out = pwm.encode(*cmd);
+                    }
+                }
+            }
+            CommandLinkParams::Serial(serial) => {
+                if new_frame(&mut self.last_frame, serial.period_s, t_s) {
+                    if self.rng.random_bool(serial.crc_error_rate.clamp(0.0, 1.0)) {
+                        self.crc_errors += 1;
+                    } else {
+                        for (out, cmd) in self.output.iter_mut().zip(cmd) {
+                            *out = serial.encode(*cmd);
+                        }
+                    }
+                }
+            }
+        }
+
+        &self.output
+    }
+
+    /// Frames discarded by the actuator since the start
+    pub fn crc_errors(&self) -> u64 {
+        self.crc_errors
+    }
+}
+
+/// Whether a frame of the link starts since the last one at `t_s`. Frames missed within a long
+/// sim step carried the same command: only the latest one is decoded. The tolerance keeps the
+/// frames on sim steps aligned with the period.
+fn new_frame(last_frame: &mut Option<u64>, period_s: f64, t_s: f64) -> bool {
+    let frame = (t_s / period_s + 1e-6).floor() as u64;
+    if last_frame.is_some_and(|last| frame <= last) {
+        return false;
+    }
+
+    *last_frame = Some(frame);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+
+    fn link(params: CommandLinkParams) -> CommandLink {
+        CommandLink::new(params, 2, Xoshiro256StarStar::seed_from_u64(0))
+    }
+
+    #[test]
+    fn test_pwm_quantization() {
+        let mut link = link(CommandLinkParams::Pwm(PwmParams {
+            period_s: 0.02,
+            center_us: 1500.0,
+            gain_us: 10.0,
+            min_pulse_us: 1000.0,
+            max_pulse_us: 2000.0,
+            resolution_us: 1.0,
+        }));
+
+        // 1 µs steps at 10 µs per unit, clamped to the pulse range
+        let out = link.step(&[1.23, -80.0], 0.0).to_vec();
+        assert!((out[0] - 1.2).abs() < 1e-9);
+        assert!((out[1] + 50.0).abs() < 1e-9);
+
+        // Held until the next frame
+        let out = link.step(&[2.0, 0.0], 0.01).to_vec();
+        assert!((out[0] - 1.2).abs() < 1e-9);
+
+        let out = link.step(&[2.0, 0.0], 0.02).to_vec();
+        assert!((out[0] - 2.0).abs() < 1e-9);
+        assert_eq!(out[1], 0.0);
+    }
+
+    #[test]
+    fn test_serial_crc_errors() {
+        let mut link = link(CommandLinkParams::Serial(SerialBusParams {
+            period_s: 0.005,
+            min: 0.0,
+            max: 7.0,
+            bits: 3,
+            crc_error_rate: 0.1,
+        }));
+
+        let mut held = 0;
+        let mut last = 0.0;
+        for k in 0..1000 {
+            // Different code in consecutive frames, and from the initial zero
+            let cmd = ((k + 1) % 8) as f64 + 0.3;
+            let out = link.step(&[cmd, 1.0], k as f64 * 0.005)[0];
+
+            // Unit steps over the range
+            assert_eq!(out.fract(), 0.0);
+            if out == last {
+                held += 1;
+            }
+            last = out;
+        }
+
+        assert_eq!(held, link.crc_errors());
+        assert!((50..150).contains(&link.crc_errors()));
+    }
+}
@@ -1,3 +0,0 @@
-mod servo;
-
-pub use servo::IdealServo;
\ No newline at end of file
@@ -1 +1,4 @@
-pub mod ideal;
\ No newline at end of file
+pub mod airbrake;
+pub mod command_link;
+pub mod servo;
+pub mod thermal;
@@ -0,0 +1,302 @@
+use anyhow::Result;
+use chrono::TimeDelta;
+use crossbeam_channel::Receiver;
+use log::warn;
+use nalgebra::Vector4;
+
+use crate::{
+    core::time::{Clock, Timestamp},
+    crater::{
+        actuators::{
+            command_link::{CommandLink, CommandLinkParams},
+            thermal::{ServoPower, ServoThermalModel, ServoThermalParams},
+        },
+        channels,
+        events::SimEvent,
+        gnc::ServoPosition,
+    },
+    nodes::{Node, NodeContext, StepResult},
+    parameters::{Parameter, ParameterMap, service::ParameterService},
+    telemetry::{TelemetryReceiver, TelemetrySender, Timestamped},
+    utils::capacity::Capacity::Unbounded,
+};
+
+#[derive(Debug, Clone)]
+pub struct ServoDynamicsParams {
+    pub natural_freq_rad_s: f64,
+    pub damping: f64,
+    /// Maximum angular rate of the output shaft
+    pub max_rate_rad_s: f64,
+    /// Command changes smaller than this are ignored
+    pub deadband_rad: f64,
+    /// Total play between the motor and the fin
+    pub backlash_rad: f64,
+    /// Position resolution of the servo feedback loop. 0 disables the quantization.
+    pub resolution_rad: f64,
+}
+
+impl ServoDynamicsParams {
+    pub fn from_params(params: &ParameterMap) -> Result<Self> {
+        let float = |name: &str| -> Result<f64> { Ok(params.get_param(name)?.value_float()?) };
+
+        Ok(Self {
+            natural_freq_rad_s: float("natural_freq")? * 2.0 * std::f64::consts::PI,
+            damping: float("damping")?,
+            max_rate_rad_s: float("max_rate")?.to_radians(),
+            deadband_rad: float("deadband")?.to_radians(),
+            backlash_rad: float("backlash")?.to_radians(),
+            resolution_rad: float("resolution")?.to_radians(),
+        })
+    }
+}
+
+/// Dynamics of the four fin servos.
+///
+/// The motor follows the command as a rate limited second order system. The command is only
+/// updated when it moves out of the deadband around the current target, and the fin is driven
+/// through a backlash of the configured total width. The fin position is finally quantized to
+/// the resolution of the servo.
+#[derive(Debug, Clone)]
+pub struct ServoDynamics {
+    params: ServoDynamicsParams,
+    target_rad: Vector4<f64>,
+    motor_rad: Vector4<f64>,
+    motor_rate_rad_s: Vector4<f64>,
+    fin_rad: Vector4<f64>,
+}
+
+impl ServoDynamics {
+    pub fn new(params: ServoDynamicsParams) -> Self {
+        Self {
+            params,
+            target_rad: Vector4::zeros(),
+            motor_rad: Vector4::zeros(),
+            motor_rate_rad_s: Vector4::zeros(),
+            fin_rad: Vector4::zeros(),
+        }
+    }
+
+    /// Replaces the parameters, keeping the state of the servos
+    pub fn set_params(&mut self, params: ServoDynamicsParams) {
+        self.params = params;
+    }
+
+    /// Advances the servos by `dt_s` towards the command. Returns the fin positions.
+    pub fn step(&mut self, cmd_rad: &Vector4<f64>, dt_s: f64) -> Vector4<f64> {
+        let p = &self.params;
+
+        // Keep the explicit integration stable for stiff servos & long sim steps
+        let substeps = (dt_s * p.natural_freq_rad_s / 0.1).ceil().max(1.0);
+        let h = dt_s / substeps;
+
+        for i in 0..4 {
+            if (cmd_rad[i] - self.target_rad[i]).abs() > p.deadband_rad {
+                self.target_rad[i] = cmd_rad[i];
+            }
+
+            for _ in 0..substeps as usize {
+                let wn = p.natural_freq_rad_s;
+                let accel = wn.powi(2) * (self.target_rad[i] - self.motor_rad[i])
+                    - 2.0 * p.damping * wn * self.motor_rate_rad_s[i];
+
+                self.motor_rate_rad_s[i] = (self.motor_rate_rad_s[i] + accel * h)
+                    .clamp(-p.max_rate_rad_s, p.max_rate_rad_s);
+                self.motor_rad[i] += self.motor_rate_rad_s[i] * h;
+            }
+
+            // The fin only moves once the motor takes up the play on either side
+            let half_play = p.backlash_rad / 2.0;
+            let offset = self.motor_rad[i] - self.fin_rad[i];
+            if offset > half_play {
+                self.fin_rad[i] = self.motor_rad[i] - half_play;
+            } else if offset < -half_play {
+                self.fin_rad[i] = self.motor_rad[i] + half_play;
+            }
+        }
+
+        if p.resolution_rad > 0.0 {
+            self.fin_rad.map(|pos| (pos / p.resolution_rad).round() * p.resolution_rad)
+        } else {
+            self.fin_rad
+        }
+    }
+}
+
+/// Fin servos with dynamics, between the GNC servo command and the position applied to the
+/// rocket. The commands reach the servos through their command link, in degrees. Optionally
+/// limited by their current draw & winding temperature. The dynamics parameters can be changed
+/// live.
+#[derive(Debug)]
+pub struct ServoModel {
+    rx_control: TelemetryReceiver<ServoPosition>,
+    tx_servo_pos: TelemetrySender<ServoPosition>,
+    tx_servo_power: TelemetrySender<ServoPower>,
+    tx_sim_event: TelemetrySender<SimEvent>,
+
+    param_service: ParameterService,
+    rx_dynamics_params: Receiver<Parameter>,
+
+    link: CommandLink,
+    dynamics: ServoDynamics,
+    thermal: Option<ServoThermalModel>,
+    cmd_rad: Vector4<f64>,
+}
+
+impl ServoModel {
+    pub fn new(ctx: NodeContext) -> Result<Self> {
+        let rx_control = ctx
+            .telemetry()
+            .subscribe(channels::gnc::SERVO_COMMAND, Unbounded)?;
+
+        let tx_servo_pos = ctx
+            .telemetry()
+            .publish(channels::actuators::IDEAL_SERVO_POSITION)?;
+        let tx_servo_power = ctx.telemetry().publish(channels::actuators::SERVO_POWER)?;
+        let tx_sim_event = ctx.telemetry().publish_mp(channels::sim::SIM_EVENTS)?;
+
+        let link = CommandLink::new(
+            CommandLinkParams::from_params(ctx.parameters().get_map("sim.rocket.servo.link")?)?,
+            4,
+            ctx.get_rng_256(),
+        );
+        let dynamics = ServoDynamics::new(ServoDynamicsParams::from_params(
+            ctx.parameters().get_map("sim.rocket.servo.dynamics")?,
+        )?);
+
+        let param_service = ctx.parameter_service().clone();
+        let rx_dynamics_params = param_service.watch("sim.rocket.servo.dynamics");
+
+        let thermal_params = ctx.parameters().get_map("sim.rocket.servo.thermal")?;
+        let thermal = if thermal_params.get_param("enabled")?.value_bool()? {
+            Some(ServoThermalModel::new(ServoThermalParams::from_params(
+                thermal_params,
+            )?))
+        } else {
+            None
+        };
+
+        Ok(Self {
+            rx_control,
+            tx_servo_pos,
+            tx_servo_power,
+            tx_sim_event,
+            param_service,
+            rx_dynamics_params,
+            link,
+            dynamics,
+            thermal,
+            cmd_rad: Vector4::zeros(),
+        })
+    }
+}
+
+impl Node for ServoModel {
+    fn step(&mut self, _: usize, dt: TimeDelta, clock: &dyn Clock) -> Result<StepResult> {
+        while let Ok(Timestamped(_, cmd)) = self.rx_control.try_recv() {
+            self.cmd_rad = cmd.pos_rad;
+        }
+
+        if self.rx_dynamics_params.try_iter().count() > 0 {
+            let params = self.param_service.parameters();
+            match ServoDynamicsParams::from_params(params.get_map("sim.rocket.servo.dynamics")?) {
+                Ok(p) => self.dynamics.set_params(p),
+                Err(e) => warn!("Invalid servo dynamics parameters, not applied: {e}"),
+            }
+        }
+
+        let t = Timestamp::now(clock);
+        let dt_s = dt.num_microseconds().unwrap() as f64 / 1e6;
+
+        let t_s = t.monotonic.elapsed_seconds_f64();
+        let cmd_deg = self.cmd_rad.map(f64::to_degrees);
+        let cmd_rad = Vector4::from_column_slice(self.link.step(cmd_deg.as_slice(), t_s))
+            .map(f64::to_radians);
+
+        // The thermal model limits the travel available to the servo, the dynamics then track
+        // the limited command
+        let cmd_rad = match self.thermal.as_mut() {
+            Some(thermal) => {
+                let mut transitions = vec![];
+                let (cmd_rad, power) = thermal.step(&cmd_rad, dt_s, &mut transitions);
+
+                for (servo, state) in transitions {
+                    self.tx_sim_event.send(t, SimEvent::ServoThermal { servo, state });
+                }
+                self.tx_servo_power.send(t, power);
+
+                cmd_rad
+            }
+            None => cmd_rad,
+        };
+
+        let pos_rad = self.dynamics.step(&cmd_rad, dt_s);
+        self.tx_servo_pos.send(t, pos_rad.into());
+
+        Ok(StepResult::Continue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> ServoDynamicsParams {
+        ServoDynamicsParams {
+            natural_freq_rad_s: 150.0,
+            // Critically damped: no overshoot held by the backlash
+            damping: 1.0,
+            max_rate_rad_s: 5.0,
+            deadband_rad: 0.002,
+            backlash_rad: 0.01,
+            resolution_rad: 0.001,
+        }
+    }
+
+    #[test]
+    fn test_step_response() {
+        let mut servo = ServoDynamics::new(params());
+        let cmd = Vector4::new(0.2, -0.2, 0.001, 0.0);
+
+        // Slew rate limited at the start of a large step
+        let pos = servo.step(&cmd, 0.01);
+        assert!(pos[0] > 0.0 && pos[0] <= 0.05);
+        assert!(pos[1] < 0.0 && pos[1] >= -0.05);
+        // Within the deadband
+        assert_eq!(pos[2], 0.0);
+
+        // Settles on the command, short of half the backlash, on the resolution grid
+        let mut pos = pos;
+        for _ in 0..100 {
+            pos = servo.step(&cmd, 0.01);
+        }
+        assert!((pos[0] - 0.195).abs() < 1e-9);
+        assert!((pos[1] + 0.195).abs() < 1e-9);
+        assert_eq!(pos[3], 0.0);
+    }
+
+    #[test]
+    fn test_backlash_on_reversal() {
+        let mut servo = ServoDynamics::new(ServoDynamicsParams {
+            resolution_rad: 0.0,
+            ..params()
+        });
+
+        let settle = |servo: &mut ServoDynamics, cmd: f64| {
+            let mut pos = Vector4::zeros();
+            for _ in 0..200 {
+                pos = servo.step(&Vector4::repeat(cmd), 0.01);
+            }
+            pos[0]
+        };
+
+        let pos = settle(&mut servo, 0.1);
+        assert!((pos - 0.095).abs() < 1e-6);
+
+        // Reversing by less than the play does not move the fin
+        let pos = settle(&mut servo, 0.093);
+        assert!((pos - 0.095).abs() < 1e-6);
+
+        let pos = settle(&mut servo, 0.05);
+        assert!((pos - 0.055).abs() < 1e-6);
+    }
+}
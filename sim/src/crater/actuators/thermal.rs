@@ -0,0 +1,213 @@
+use anyhow::Result;
+use nalgebra::Vector4;
+
+use crate::parameters::ParameterMap;
+
+/// Thermal condition of a servo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServoThermalState {
+    Nominal,
+    /// The winding is above the derating temperature, and the commanded deflection exceeds the
+    /// reduced travel that is still available
+    Limited,
+    /// The winding reached the shutdown temperature: the servo is unpowered and holds its last
+    /// position until it cools below the derating temperature
+    Shutdown,
+}
+
+/// Electrical & thermal state of the servos, published by the servo model
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ServoPower {
+    pub current_a: Vector4<f64>,
+    pub winding_temp_degc: Vector4<f64>,
+    /// Fraction of the full travel available after thermal derating
+    pub derating: Vector4<f64>,
+    /// Total electrical power drawn from the servo supply
+    pub power_w: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct ServoThermalParams {
+    pub supply_voltage_v: f64,
+    pub idle_current_a: f64,
+    /// Current needed to move the servo, per unit of angular rate
+    pub rate_current_a_s_rad: f64,
+    /// Current needed to hold the fin against the aerodynamic load, which is assumed to grow
+    /// linearly with the deflection
+    pub hold_current_a_rad: f64,
+    pub max_current_a: f64,
+
+    pub winding_resistance_ohm: f64,
+    /// Winding to ambient thermal resistance
+    pub thermal_resistance_k_w: f64,
+    pub thermal_capacity_j_k: f64,
+    pub ambient_temp_degc: f64,
+
+    pub max_deflection_rad: f64,
+    /// Above this temperature, the available travel is reduced linearly, reaching zero at the
+    /// shutdown temperature
+    pub derate_temp_degc: f64,
+    pub shutdown_temp_degc: f64,
+}
+
+impl ServoThermalParams {
+    pub fn from_params(params: &ParameterMap) -> Result<Self> {
+        let float = |name: &str| -> Result<f64> { Ok(params.get_param(name)?.value_float()?) };
+
+        Ok(Self {
+            supply_voltage_v: float("supply_voltage")?,
+            idle_current_a: float("idle_current")?,
+            rate_current_a_s_rad: float("rate_current")?,
+            hold_current_a_rad: float("hold_current")?,
+            max_current_a: float("max_current")?,
+            winding_resistance_ohm: float("winding_resistance")?,
+            thermal_resistance_k_w: float("thermal_resistance")?,
+            thermal_capacity_j_k: float("thermal_capacity")?,
+            ambient_temp_degc: float("ambient_temp")?,
+            max_deflection_rad: float("max_deflection")?.to_radians(),
+            derate_temp_degc: float("derate_temp")?,
+            shutdown_temp_degc: float("shutdown_temp")?,
+        })
+    }
+}
+
+/// Current draw and winding temperature estimation for the four fin servos.
+///
+/// The current is the sum of an idle term, a term proportional to the angular rate and a holding
+/// term proportional to the deflection. The winding temperature follows a first order model,
+/// heated by the Joule losses and cooled towards the ambient temperature.
+#[derive(Debug, Clone)]
+pub struct ServoThermalModel {
+    params: ServoThermalParams,
+    pos_rad: Vector4<f64>,
+    temp_degc: Vector4<f64>,
+    states: [ServoThermalState; 4],
+}
+
+impl ServoThermalModel {
+    pub fn new(params: ServoThermalParams) -> Self {
+        Self {
+            pos_rad: Vector4::zeros(),
+            temp_degc: Vector4::repeat(params.ambient_temp_degc),
+            states: [ServoThermalState::Nominal; 4],
+            params,
+        }
+    }
+
+    fn derating(&self, temp_degc: f64) -> f64 {
+        let p = &self.params;
+        ((p.shutdown_temp_degc - temp_degc) / (p.shutdown_temp_degc - p.derate_temp_degc))
+            .clamp(0.0, 1.0)
+    }
+
+    /// Advances the model by `dt_s` with the provided command. Returns the achieved servo
+    /// positions and the power state. Servos whose thermal state changed are reported in
+    /// `transitions`.
+    pub fn step(
+        &mut self,
+        cmd_rad: &Vector4<f64>,
+        dt_s: f64,
+        transitions: &mut Vec<(usize, ServoThermalState)>,
+    ) -> (Vector4<f64>, ServoPower) {
+        let p = &self.params;
+        let mut power = ServoPower::default();
+
+        for i in 0..4 {
+            let temp = self.temp_degc[i];
+            let derating = self.derating(temp);
+            let limit = derating * p.max_deflection_rad;
+
+            let state = match self.states[i] {
+                ServoThermalState::Shutdown if temp > p.derate_temp_degc => {
+                    ServoThermalState::Shutdown
+                }
+                _ if temp >= p.shutdown_temp_degc => ServoThermalState::Shutdown,
+                _ if cmd_rad[i].abs() > limit => ServoThermalState::Limited,
+                _ => ServoThermalState::Nominal,
+            };
+
+            if state != self.states[i] {
+                transitions.push((i, state));
+                self.states[i] = state;
+            }
+
+            let current = if state == ServoThermalState::Shutdown {
+                0.0
+            } else {
+                let pos = cmd_rad[i].clamp(-limit, limit);
+                let rate = if dt_s > 0.0 {
+                    (pos - self.pos_rad[i]).abs() / dt_s
+                } else {
+                    0.0
+                };
+                self.pos_rad[i] = pos;
+
+                (p.idle_current_a
+                    + p.rate_current_a_s_rad * rate
+                    + p.hold_current_a_rad * pos.abs())
+                .min(p.max_current_a)
+            };
+
+            let heat_w = current.powi(2) * p.winding_resistance_ohm;
+            let cooling_w = (temp - p.ambient_temp_degc) / p.thermal_resistance_k_w;
+            self.temp_degc[i] = temp + (heat_w - cooling_w) / p.thermal_capacity_j_k * dt_s;
+
+            power.current_a[i] = current;
+            power.derating[i] = derating;
+            power.power_w += current * p.supply_voltage_v;
+        }
+        power.winding_temp_degc = self.temp_degc;
+
+        (self.pos_rad, power)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> ServoThermalParams {
+        ServoThermalParams {
+            supply_voltage_v: 7.4,
+            idle_current_a: 0.1,
+            rate_current_a_s_rad: 0.2,
+            hold_current_a_rad: 10.0,
+            max_current_a: 3.0,
+            winding_resistance_ohm: 2.0,
+            thermal_resistance_k_w: 10.0,
+            thermal_capacity_j_k: 5.0,
+            ambient_temp_degc: 20.0,
+            max_deflection_rad: 0.3,
+            derate_temp_degc: 60.0,
+            shutdown_temp_degc: 90.0,
+        }
+    }
+
+    #[test]
+    fn test_sustained_load_is_derated() {
+        let p = params();
+        let (derate_temp, shutdown_temp) = (p.derate_temp_degc, p.shutdown_temp_degc);
+
+        let mut model = ServoThermalModel::new(p);
+        let cmd = Vector4::new(0.25, 0.0, 0.0, 0.0);
+        let mut transitions = vec![];
+
+        let (pos, power) = model.step(&cmd, 0.01, &mut transitions);
+        assert_eq!(pos, cmd);
+        assert!(power.current_a[0] > power.current_a[1]);
+        assert!(transitions.is_empty());
+
+        // Holding this deflection would overheat the servo: the travel is reduced until the
+        // winding temperature settles below the shutdown limit
+        let mut power = ServoPower::default();
+        for _ in 0..100_000 {
+            (_, power) = model.step(&cmd, 0.01, &mut transitions);
+        }
+
+        assert_eq!(transitions, vec![(0, ServoThermalState::Limited)]);
+        assert!(power.winding_temp_degc[0] > derate_temp);
+        assert!(power.winding_temp_degc[0] < shutdown_temp);
+        assert!(power.derating[0] < 1.0);
+        assert_eq!(power.derating[1], 1.0);
+    }
+}
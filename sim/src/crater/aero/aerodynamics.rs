@@ -1,8 +1,13 @@
 use std::f64;
 
+use anyhow::{Result, anyhow};
 use nalgebra::{Vector3, vector};
 
-use crate::crater::gnc::ServoPosition;
+use crate::{
+    crater::gnc::ServoPosition,
+    math::interp::{find_index, interpolate},
+    parameters::ParameterMap,
+};
 
 #[derive(Debug, Clone)]
 pub struct AerodynamicActions {
@@ -25,6 +30,8 @@ pub struct AeroState {
     pub altitude_m: f64,
 
     pub servo_pos: ServoPosition,
+    /// Airbrake extension, from 0 (retracted) to 1 (fully deployed)
+    pub airbrake_extension: f64,
 }
 
 impl AeroState {
@@ -35,6 +42,7 @@ impl AeroState {
         mach: f64,
         air_density_kg_m3: f64,
         servo_pos: ServoPosition,
+        airbrake_extension: f64,
     ) -> AeroState {
         let v_air_norm_m_s = v_air_b_m_s.norm();
         AeroState {
@@ -46,6 +54,7 @@ impl AeroState {
             w_b_rad_s,
             altitude_m,
             servo_pos,
+            airbrake_extension,
         }
     }
 }
@@ -78,6 +87,72 @@ pub struct AeroCoefficientsValues {
 
 pub trait AerodynamicsCoefficients {
     fn coefficients(&self, state: &AeroState) -> AeroCoefficientsValues;
+
+    /// Whether the coefficients already account for the airbrake extension, in which case the
+    /// airbrake drag increment must not be added on top
+    fn includes_airbrake(&self) -> bool {
+        false
+    }
+}
+
+/// Axial force coefficient increment due to the airbrakes, as a function of their extension
+#[derive(Debug, Clone)]
+pub struct AirbrakeDrag {
+    extension: Vec<f64>,
+    cd_delta: Vec<f64>,
+}
+
+impl AirbrakeDrag {
+    pub fn from_params(params: &ParameterMap) -> Result<Self> {
+        let extension = params.get_param("extension")?.value_float_arr()?.to_vec();
+        let cd_delta = params.get_param("cd_delta")?.value_float_arr()?.to_vec();
+
+        if extension.is_empty() || extension.len() != cd_delta.len() {
+            return Err(anyhow!(
+                "Airbrake drag table must be non-empty and have as many values as extension breakpoints"
+            ));
+        }
+
+        Ok(Self {
+            extension,
+            cd_delta,
+        })
+    }
+
+    pub fn delta_ca(&self, extension: f64) -> f64 {
+        interpolate(&self.cd_delta, find_index(&self.extension, extension)).0
+    }
+}
+
+/// Roll moment of fins canted by a fixed angle, and the roll damping opposing the induced roll
+/// rate
+#[derive(Debug, Clone)]
+pub struct FinCant {
+    /// Cant angle, as a mixed roll deflection (see `MixedServoPosition`)
+    cant_rad: f64,
+    /// Roll moment coefficient per radian of cant
+    cl_delta: f64,
+    /// Roll damping derivative, added to the one of the aerodynamic model
+    cl_p: f64,
+}
+
+impl FinCant {
+    pub fn from_params(params: &ParameterMap) -> Result<Self> {
+        Ok(Self {
+            cant_rad: params
+                .get_param("cant")?
+                .value_randfloat()?
+                .sampled()
+                .to_radians(),
+            cl_delta: params.get_param("cl_delta")?.value_float()?,
+            cl_p: params.get_param("cl_p")?.value_float()?,
+        })
+    }
+
+    pub fn apply(&self, coeffs: &mut AeroCoefficientsValues) {
+        coeffs.cl += self.cl_delta * self.cant_rad;
+        coeffs.cl_p += self.cl_p;
+    }
 }
 
 pub struct Aerodynamics {
@@ -188,3 +263,51 @@ impl AeroAngles {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::parameters::parse_string;
+
+    #[test]
+    fn test_fin_cant() -> Result<()> {
+        let mut params = parse_string(
+            r#"
+            cant = { val = 0.5, type = "randfloat", dist = { type = "normal", mean = 0.5, std_dev = 0.1 } }
+            cl_delta = { val = 2.0, type = "float" }
+            cl_p = { val = -20.0, type = "float" }
+            "#
+            .to_string(),
+        )?;
+        params.resample_perfect();
+        let fin_cant = FinCant::from_params(&params)?;
+
+        let mut coeffs = AeroCoefficientsValues {
+            cA: 0.3,
+            cY: 0.0,
+            cY_r: 0.0,
+            cY_bd: 0.0,
+            cN: 0.0,
+            cN_q: 0.0,
+            cN_ad: 0.0,
+            cl: 0.01,
+            cl_p: -5.0,
+            cl_r: 0.0,
+            cm: 0.0,
+            cm_q: 0.0,
+            cm_ad: 0.0,
+            cn: 0.0,
+            cn_r: 0.0,
+            cn_bd: 0.0,
+        };
+        fin_cant.apply(&mut coeffs);
+
+        assert_relative_eq!(coeffs.cl, 0.01 + 2.0 * 0.5f64.to_radians(), epsilon = 1e-12);
+        assert_relative_eq!(coeffs.cl_p, -25.0, epsilon = 1e-12);
+        assert_eq!(coeffs.cA, 0.3);
+
+        Ok(())
+    }
+}
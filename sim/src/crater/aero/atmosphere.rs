@@ -1,4 +1,17 @@
-pub trait Atmosphere {
+use std::{fmt::Debug, path::Path};
+
+use anyhow::{Result, anyhow};
+use serde::Deserialize;
+
+use crate::{
+    math::interp::{find_index, interpolate},
+    parameters::ParameterMap,
+};
+
+/// Specific gas constant of dry air [J/(kg K)]
+const R_AIR: f64 = 287.052874;
+
+pub trait Atmosphere: Debug {
     fn pressure_pa(&self, alt_m: f64) -> f64;
     fn density_kg_m3(&self, alt_m: f64) -> f64;
     fn temperature_k(&self, alt_m: f64) -> f64;
@@ -34,6 +47,8 @@ pub struct AtmosphereIsa {
     g_0: f64,
     specific_gas_constant: f64,
     a: f64,
+    /// Deviation of the temperature from the standard profile, at any altitude [K]
+    temperature_offset: f64,
 }
 
 impl Default for AtmosphereIsa {
@@ -44,8 +59,9 @@ impl Default for AtmosphereIsa {
             density_0: 1.2250,
             alt_0: 0.0,
             g_0: 9.80665,
-            specific_gas_constant: 287.052874,
+            specific_gas_constant: R_AIR,
             a: -0.0065,
+            temperature_offset: 0.0,
         }
     }
 }
@@ -68,25 +84,144 @@ impl AtmosphereIsa {
             g_0,
             specific_gas_constant: molar_gas_constant,
             a,
+            temperature_offset: 0.0,
         }
     }
 }
 
+impl AtmosphereIsa {
+    /// ISA lapse rate, with the provided temperature & pressure at the reference altitude.
+    /// The reference density follows from the ideal gas law.
+    pub fn from_params(params: &ParameterMap) -> Result<Self> {
+        let default = Self::default();
+
+        let pressure_0 = params.get_param("pressure_0")?.value_float()?;
+        let temperature_0 = params.get_param("temperature_0")?.value_float()?;
+
+        Ok(Self {
+            pressure_0,
+            temperature_0,
+            density_0: pressure_0 / (default.specific_gas_constant * temperature_0),
+            ..default
+        })
+    }
+
+    /// ISA+ΔT atmosphere: the temperature is offset by `offset_k` at all altitudes, with the
+    /// same pressure profile, and the density follows from the ideal gas law
+    pub fn with_temperature_offset(mut self, offset_k: f64) -> Self {
+        self.temperature_offset = offset_k;
+        self
+    }
+
+    /// Temperature of the standard profile, without the offset
+    fn standard_temperature_k(&self, alt: f64) -> f64 {
+        self.temperature_0 + self.a * (alt - self.alt_0)
+    }
+}
+
 impl Atmosphere for AtmosphereIsa {
     fn pressure_pa(&self, alt: f64) -> f64 {
         let exponent = -self.g_0 / (self.a * self.specific_gas_constant);
-        let t = self.temperature_k(alt);
+        let t = self.standard_temperature_k(alt);
         (t / self.temperature_0).powf(exponent) * self.pressure_0
     }
 
     fn temperature_k(&self, alt: f64) -> f64 {
-        self.temperature_0 + self.a * (alt - self.alt_0)
+        self.standard_temperature_k(alt) + self.temperature_offset
     }
 
     fn density_kg_m3(&self, alt: f64) -> f64 {
         let exponent = -(self.g_0 / (self.a * self.specific_gas_constant) + 1.0);
-        let t = self.temperature_k(alt);
-        (t / self.temperature_0).powf(exponent) * self.density_0
+        let t = self.standard_temperature_k(alt);
+        (t / self.temperature_0).powf(exponent) * self.density_0 * t / self.temperature_k(alt)
+    }
+
+    fn speed_of_sound_m_s(&self, alt_m: f64) -> f64 {
+        f64::sqrt(1.4 * self.pressure_pa(alt_m) / self.density_kg_m3(alt_m))
+    }
+}
+
+/// Row of an atmosphere table. The density is optional, computed from the ideal gas law if
+/// missing.
+#[derive(Debug, Deserialize)]
+struct AtmosphereRow {
+    altitude_m: f64,
+    temperature_k: f64,
+    pressure_pa: f64,
+    #[serde(default)]
+    density_kg_m3: Option<f64>,
+}
+
+/// Atmosphere tabulated as a function of altitude, eg. from a radiosonde sounding. Linearly
+/// interpolated, and held constant beyond the ends of the table.
+#[derive(Debug, Clone)]
+pub struct TabulatedAtmosphere {
+    altitude_m: Vec<f64>,
+    temperature_k: Vec<f64>,
+    pressure_pa: Vec<f64>,
+    density_kg_m3: Vec<f64>,
+    /// Altitude of the launch site in the table
+    altitude_offset_m: f64,
+}
+
+impl TabulatedAtmosphere {
+    /// Reads the table from a CSV file with the columns altitude_m, temperature_k, pressure_pa
+    /// and optionally density_kg_m3
+    pub fn from_csv(path: &Path, altitude_offset_m: f64) -> Result<Self> {
+        let rows = csv::Reader::from_path(path)?
+            .deserialize()
+            .collect::<Result<Vec<AtmosphereRow>, _>>()
+            .map_err(|e| {
+                anyhow!(
+                    "Error reading the atmosphere from '{}': {e}",
+                    path.display()
+                )
+            })?;
+
+        if rows.is_empty() {
+            return Err(anyhow!("Atmosphere table '{}' is empty", path.display()));
+        }
+        if rows.windows(2).any(|w| w[1].altitude_m <= w[0].altitude_m) {
+            return Err(anyhow!(
+                "Atmosphere table altitudes must be strictly increasing"
+            ));
+        }
+
+        Ok(Self {
+            altitude_m: rows.iter().map(|r| r.altitude_m).collect(),
+            temperature_k: rows.iter().map(|r| r.temperature_k).collect(),
+            pressure_pa: rows.iter().map(|r| r.pressure_pa).collect(),
+            density_kg_m3: rows
+                .iter()
+                .map(|r| {
+                    r.density_kg_m3
+                        .unwrap_or(r.pressure_pa / (R_AIR * r.temperature_k))
+                })
+                .collect(),
+            altitude_offset_m,
+        })
+    }
+
+    fn interpolate(&self, values: &[f64], alt_m: f64) -> f64 {
+        interpolate(
+            values,
+            find_index(&self.altitude_m, alt_m + self.altitude_offset_m),
+        )
+        .0
+    }
+}
+
+impl Atmosphere for TabulatedAtmosphere {
+    fn pressure_pa(&self, alt_m: f64) -> f64 {
+        self.interpolate(&self.pressure_pa, alt_m)
+    }
+
+    fn density_kg_m3(&self, alt_m: f64) -> f64 {
+        self.interpolate(&self.density_kg_m3, alt_m)
+    }
+
+    fn temperature_k(&self, alt_m: f64) -> f64 {
+        self.interpolate(&self.temperature_k, alt_m)
     }
 
     fn speed_of_sound_m_s(&self, alt_m: f64) -> f64 {
@@ -94,6 +229,27 @@ impl Atmosphere for AtmosphereIsa {
     }
 }
 
+/// Atmosphere selected by `model` in `sim.atmosphere`: "isa", offset by `isa_offset` [K], or
+/// "tabulated", read from the `tabulated.csv_path` file
+pub fn atmosphere_from_params(params: &ParameterMap) -> Result<Box<dyn Atmosphere + Send>> {
+    let model = params.get_param("model")?.value_string()?;
+
+    match model.as_str() {
+        "isa" => Ok(Box::new(
+            AtmosphereIsa::from_params(params)?
+                .with_temperature_offset(params.get_param("isa_offset")?.value_float()?),
+        )),
+        "tabulated" => {
+            let tabulated = params.get_map("tabulated")?;
+            Ok(Box::new(TabulatedAtmosphere::from_csv(
+                Path::new(&tabulated.get_param("csv_path")?.value_string()?),
+                tabulated.get_param("altitude_offset")?.value_float()?,
+            )?))
+        }
+        _ => Err(anyhow!("Unknown atmosphere model '{model}'")),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -131,4 +287,48 @@ mod tests {
         assert_relative_eq!(isa.density_kg_m3(4572.0), 0.7708, epsilon = 0.0001);
         assert_relative_eq!(isa.density_kg_m3(10668.0), 0.3796, epsilon = 0.0001);
     }
+
+    #[test]
+    fn test_isa_offset() {
+        let isa = AtmosphereIsa::default();
+        let hot = AtmosphereIsa::default().with_temperature_offset(15.0);
+
+        for alt in [0.0, 1000.0, 5000.0] {
+            assert_relative_eq!(hot.temperature_k(alt), isa.temperature_k(alt) + 15.0);
+            assert_relative_eq!(hot.pressure_pa(alt), isa.pressure_pa(alt));
+            assert_relative_eq!(
+                hot.density_kg_m3(alt),
+                hot.pressure_pa(alt) / (R_AIR * hot.temperature_k(alt)),
+                epsilon = 1e-6
+            );
+        }
+        assert!(hot.speed_of_sound_m_s(0.0) > isa.speed_of_sound_m_s(0.0));
+    }
+
+    #[test]
+    fn test_tabulated_atmosphere() -> Result<()> {
+        let path = std::env::temp_dir().join("crater_test_atmosphere.csv");
+        std::fs::write(
+            &path,
+            "altitude_m,temperature_k,pressure_pa\n\
+             1000.0,283.0,90000.0\n\
+             2000.0,277.0,80000.0\n",
+        )?;
+        let atmosphere = TabulatedAtmosphere::from_csv(&path, 1000.0)?;
+        std::fs::remove_file(&path)?;
+
+        assert_relative_eq!(atmosphere.temperature_k(500.0), 280.0);
+        assert_relative_eq!(atmosphere.pressure_pa(500.0), 85000.0);
+        assert_relative_eq!(
+            atmosphere.density_kg_m3(0.0),
+            90000.0 / (R_AIR * 283.0),
+            epsilon = 1e-9
+        );
+
+        // Held beyond the ends
+        assert_relative_eq!(atmosphere.pressure_pa(-200.0), 90000.0);
+        assert_relative_eq!(atmosphere.temperature_k(3000.0), 277.0);
+
+        Ok(())
+    }
 }
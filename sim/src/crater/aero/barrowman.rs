@@ -0,0 +1,307 @@
+use anyhow::{Result, anyhow};
+
+use super::aerodynamics::{AeroCoefficientsValues, AeroState, AerodynamicsCoefficients};
+use crate::parameters::{ParameterMap, ParameterTree};
+
+/// Highest Mach number used in the Prandtl-Glauert correction of the fins
+const MAX_PG_MACH: f64 = 0.8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoseShape {
+    Cone,
+    Ogive,
+    Parabolic,
+}
+
+#[derive(Debug, Clone)]
+pub struct Nose {
+    pub shape: NoseShape,
+    pub length_m: f64,
+}
+
+/// Conical shoulder or boattail
+#[derive(Debug, Clone)]
+pub struct Transition {
+    /// Position of the fore end, from the nose tip
+    pub x_m: f64,
+    pub length_m: f64,
+    pub d_fore_m: f64,
+    pub d_aft_m: f64,
+}
+
+/// Set of identical trapezoidal fins
+#[derive(Debug, Clone)]
+pub struct Fins {
+    pub count: usize,
+    /// Position of the root chord leading edge, from the nose tip
+    pub x_m: f64,
+    pub root_chord_m: f64,
+    pub tip_chord_m: f64,
+    pub span_m: f64,
+    /// Axial distance between the root & tip leading edges
+    pub sweep_m: f64,
+}
+
+/// Geometry of the rocket, as needed by the Barrowman equations. All the positions are measured
+/// from the nose tip, positive aft.
+#[derive(Debug, Clone)]
+pub struct RocketGeometry {
+    /// Body diameter, also the reference length of the coefficients
+    pub diameter_m: f64,
+    pub base_diameter_m: f64,
+    pub nose: Nose,
+    pub transitions: Vec<Transition>,
+    pub fins: Fins,
+}
+
+impl RocketGeometry {
+    pub fn from_params(params: &ParameterMap, diameter_m: f64) -> Result<Self> {
+        let nose = params.get_map("nose")?;
+        let shape = match nose.get_param("shape")?.value_string()?.as_str() {
+            "cone" => NoseShape::Cone,
+            "ogive" => NoseShape::Ogive,
+            "parabolic" => NoseShape::Parabolic,
+            unknown => return Err(anyhow!("Unknown nose shape: {unknown}")),
+        };
+
+        let mut transitions = vec![];
+        if params.contains_key("transitions") {
+            for (_, tree) in params.get_map("transitions")?.iter() {
+                if let ParameterTree::Node(t) = tree {
+                    transitions.push(Transition {
+                        x_m: t.get_param("x")?.value_float()?,
+                        length_m: t.get_param("length")?.value_float()?,
+                        d_fore_m: t.get_param("d_fore")?.value_float()?,
+                        d_aft_m: t.get_param("d_aft")?.value_float()?,
+                    });
+                }
+            }
+        }
+
+        let body = params.get_map("body")?;
+        let fins = params.get_map("fins")?;
+
+        Ok(Self {
+            diameter_m,
+            base_diameter_m: body.get_param("base_diameter")?.value_float()?,
+            nose: Nose {
+                shape,
+                length_m: nose.get_param("length")?.value_float()?,
+            },
+            transitions,
+            fins: Fins {
+                count: fins.get_param("count")?.value_int()? as usize,
+                x_m: fins.get_param("x")?.value_float()?,
+                root_chord_m: fins.get_param("root_chord")?.value_float()?,
+                tip_chord_m: fins.get_param("tip_chord")?.value_float()?,
+                span_m: fins.get_param("span")?.value_float()?,
+                sweep_m: fins.get_param("sweep")?.value_float()?,
+            },
+        })
+    }
+
+    /// Normal force slope [1/rad] & center of pressure of each component, at `mach`
+    pub fn components(&self, mach: f64) -> Vec<(f64, f64)> {
+        let mut components = vec![self.nose_cn(), self.fins_cn(mach)];
+        components.extend(self.transitions.iter().map(|t| self.transition_cn(t)));
+        components
+    }
+
+    /// Normal force slope [1/rad] & center of pressure of the whole rocket, at `mach`
+    pub fn cp(&self, mach: f64) -> (f64, f64) {
+        let components = self.components(mach);
+
+        let cn_alpha: f64 = components.iter().map(|(cn, _)| cn).sum();
+        let x_cp = components.iter().map(|(cn, x)| cn * x).sum::<f64>() / cn_alpha;
+
+        (cn_alpha, x_cp)
+    }
+
+    fn nose_cn(&self) -> (f64, f64) {
+        let k = match self.nose.shape {
+            NoseShape::Cone => 2.0 / 3.0,
+            NoseShape::Ogive => 0.466,
+            NoseShape::Parabolic => 0.5,
+        };
+
+        (2.0, k * self.nose.length_m)
+    }
+
+    fn transition_cn(&self, t: &Transition) -> (f64, f64) {
+        let cn =
+            2.0 * ((t.d_aft_m / self.diameter_m).powi(2) - (t.d_fore_m / self.diameter_m).powi(2));
+
+        let r = t.d_fore_m / t.d_aft_m;
+        let x = t.x_m + t.length_m / 3.0 * (1.0 + (1.0 - r) / (1.0 - r.powi(2)));
+
+        (cn, x)
+    }
+
+    fn fins_cn(&self, mach: f64) -> (f64, f64) {
+        let f = &self.fins;
+        let radius = self.diameter_m / 2.0;
+
+        let midchord = (f.span_m.powi(2)
+            + (f.sweep_m + (f.tip_chord_m - f.root_chord_m) / 2.0).powi(2))
+        .sqrt();
+        let interference = 1.0 + radius / (f.span_m + radius);
+        let compressibility = 1.0 / (1.0 - mach.min(MAX_PG_MACH).powi(2)).sqrt();
+
+        let cn = interference
+            * compressibility
+            * 4.0
+            * f.count as f64
+            * (f.span_m / self.diameter_m).powi(2)
+            / (1.0 + (1.0 + (2.0 * midchord / (f.root_chord_m + f.tip_chord_m)).powi(2)).sqrt());
+
+        let chords = f.root_chord_m + f.tip_chord_m;
+        let x = f.x_m
+            + f.sweep_m * (f.root_chord_m + 2.0 * f.tip_chord_m) / (3.0 * chords)
+            + (chords - f.root_chord_m * f.tip_chord_m / chords) / 6.0;
+
+        (cn, x)
+    }
+
+    /// Base drag, referred to the body cross section
+    pub fn base_drag(&self, mach: f64) -> f64 {
+        let cd_base = if mach < 1.0 {
+            0.12 + 0.13 * mach.powi(2)
+        } else {
+            0.25 / mach
+        };
+
+        cd_base * (self.base_diameter_m / self.diameter_m).powi(2)
+    }
+}
+
+/// Coefficients estimated from the geometry of the rocket with the Barrowman equations, for when
+/// no CFD or DATCOM tables are available. The moments are about `ref_x_m`, the control surfaces
+/// are not modeled and the estimates hold in subsonic flight only.
+#[allow(nonstandard_style)]
+pub struct BarrowmanAeroCoefficients {
+    geometry: RocketGeometry,
+    ref_x_m: f64,
+    /// Axial force coefficient, other than the base drag
+    cA_friction: f64,
+}
+
+impl BarrowmanAeroCoefficients {
+    #[allow(non_snake_case)]
+    pub fn new(geometry: RocketGeometry, ref_x_m: f64, cA_friction: f64) -> Self {
+        Self {
+            geometry,
+            ref_x_m,
+            cA_friction,
+        }
+    }
+
+    pub fn from_params(params: &ParameterMap, diameter_m: f64, ref_x_m: f64) -> Result<Self> {
+        Ok(Self::new(
+            RocketGeometry::from_params(params, diameter_m)?,
+            ref_x_m,
+            params.get_param("cA_friction")?.value_float()?,
+        ))
+    }
+
+    pub fn geometry(&self) -> &RocketGeometry {
+        &self.geometry
+    }
+}
+
+impl AerodynamicsCoefficients for BarrowmanAeroCoefficients {
+    #[allow(non_snake_case)]
+    fn coefficients(&self, state: &AeroState) -> AeroCoefficientsValues {
+        let alpha = state.angles.alpha_rad;
+        let beta = state.angles.beta_rad;
+        let d = self.geometry.diameter_m;
+
+        // Static & damping derivatives, summed over the components
+        let (mut cN_a, mut cm_a, mut cN_q, mut cm_q) = (0.0, 0.0, 0.0, 0.0);
+        for (cn, x) in self.geometry.components(state.mach) {
+            let arm = (x - self.ref_x_m) / d;
+
+            cN_a += cn;
+            cm_a -= cn * arm;
+            cN_q += 2.0 * cn * arm;
+            cm_q -= 2.0 * cn * arm.powi(2);
+        }
+
+        AeroCoefficientsValues {
+            cA: self.cA_friction + self.geometry.base_drag(state.mach),
+
+            cY: -cN_a * beta,
+            cY_r: cN_q,
+            cY_bd: 0.0,
+
+            cN: cN_a * alpha,
+            cN_q,
+            cN_ad: 0.0,
+
+            cl: 0.0,
+            cl_p: 0.0,
+            cl_r: 0.0,
+
+            cm: cm_a * alpha,
+            cm_q,
+            cm_ad: 0.0,
+
+            cn: -cm_a * beta,
+            cn_r: cm_q,
+            cn_bd: 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    fn geometry() -> RocketGeometry {
+        RocketGeometry {
+            diameter_m: 0.08,
+            base_diameter_m: 0.06,
+            nose: Nose {
+                shape: NoseShape::Cone,
+                length_m: 0.2,
+            },
+            transitions: vec![Transition {
+                x_m: 0.94,
+                length_m: 0.06,
+                d_fore_m: 0.08,
+                d_aft_m: 0.06,
+            }],
+            fins: Fins {
+                count: 4,
+                x_m: 0.9,
+                root_chord_m: 0.1,
+                tip_chord_m: 0.05,
+                span_m: 0.08,
+                sweep_m: 0.05,
+            },
+        }
+    }
+
+    #[test]
+    fn test_barrowman_cp() {
+        let geometry = geometry();
+
+        let components = geometry.components(0.0);
+        assert_eq!(components[0], (2.0, 0.2 * 2.0 / 3.0));
+        assert_relative_eq!(components[1].0, 8.534598, epsilon = 1e-6);
+        assert_relative_eq!(components[1].1, 0.941667, epsilon = 1e-6);
+        assert_relative_eq!(components[2].0, -0.875, epsilon = 1e-9);
+        assert_relative_eq!(components[2].1, 0.968571, epsilon = 1e-6);
+
+        let (cn_alpha, x_cp) = geometry.cp(0.0);
+        assert_relative_eq!(cn_alpha, 9.659598, epsilon = 1e-6);
+        assert_relative_eq!(x_cp, 0.771866, epsilon = 1e-6);
+
+        // The fins gain normal force with Mach, moving the CP aft
+        let (cn_alpha_05, x_cp_05) = geometry.cp(0.5);
+        assert!(cn_alpha_05 > cn_alpha && x_cp_05 > x_cp);
+
+        assert_relative_eq!(geometry.base_drag(0.5), 0.1525 * 0.5625, epsilon = 1e-9);
+    }
+}
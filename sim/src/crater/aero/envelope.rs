@@ -0,0 +1,132 @@
+use anyhow::Result;
+use chrono::TimeDelta;
+
+use crate::{
+    core::time::Clock,
+    crater::{aero::aerodynamics::AeroState, channels},
+    nodes::{Node, NodeContext, StepResult},
+    telemetry::{TelemetryReceiver, TelemetrySender, Timestamped},
+    utils::capacity::Capacity::Unbounded,
+};
+
+/// Quantities of the structural & aerodynamic envelope checks, at one instant, with their
+/// maxima since the start of the flight
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FlightEnvelopeSample {
+    pub dynamic_pressure_pa: f64,
+    pub mach: f64,
+    pub altitude_m: f64,
+    /// Angle between the body x axis and the air velocity [deg]
+    pub total_aoa_deg: f64,
+    /// Dynamic pressure times the total angle of attack, proportional to the bending loads
+    pub q_alpha_pa_deg: f64,
+
+    pub max_dynamic_pressure_pa: f64,
+    pub max_mach: f64,
+    pub max_q_alpha_pa_deg: f64,
+}
+
+impl FlightEnvelopeSample {
+    /// Sample at `aero`, following `prev`
+    pub fn next(prev: &FlightEnvelopeSample, aero: &AeroState) -> Self {
+        let dynamic_pressure_pa = 0.5 * aero.air_density_kg_m3 * aero.v_air_norm_m_s.powi(2);
+
+        let total_aoa_deg = if aero.v_air_norm_m_s > 0.0 {
+            (aero.v_air_b_m_s[0] / aero.v_air_norm_m_s)
+                .clamp(-1.0, 1.0)
+                .acos()
+                .to_degrees()
+        } else {
+            0.0
+        };
+        let q_alpha_pa_deg = dynamic_pressure_pa * total_aoa_deg;
+
+        Self {
+            dynamic_pressure_pa,
+            mach: aero.mach,
+            altitude_m: aero.altitude_m,
+            total_aoa_deg,
+            q_alpha_pa_deg,
+            max_dynamic_pressure_pa: prev.max_dynamic_pressure_pa.max(dynamic_pressure_pa),
+            max_mach: prev.max_mach.max(aero.mach),
+            max_q_alpha_pa_deg: prev.max_q_alpha_pa_deg.max(q_alpha_pa_deg),
+        }
+    }
+}
+
+/// Computes the flight envelope quantities from the aerodynamic state, for logging
+pub struct FlightEnvelope {
+    rx_aero: TelemetryReceiver<AeroState>,
+    tx_envelope: TelemetrySender<FlightEnvelopeSample>,
+
+    last: FlightEnvelopeSample,
+}
+
+impl FlightEnvelope {
+    pub fn new(ctx: NodeContext) -> Result<Self> {
+        Ok(Self {
+            rx_aero: ctx
+                .telemetry()
+                .subscribe(channels::rocket::AERO_STATE, Unbounded)?,
+            tx_envelope: ctx.telemetry().publish(channels::rocket::ENVELOPE)?,
+            last: FlightEnvelopeSample::default(),
+        })
+    }
+}
+
+impl Node for FlightEnvelope {
+    fn step(&mut self, _: usize, _: TimeDelta, _: &dyn Clock) -> Result<StepResult> {
+        while let Ok(Timestamped(t, aero)) = self.rx_aero.try_recv() {
+            self.last = FlightEnvelopeSample::next(&self.last, &aero);
+            self.tx_envelope.send(t, self.last.clone());
+        }
+
+        Ok(StepResult::Continue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use nalgebra::Vector3;
+
+    use super::*;
+    use crate::crater::gnc::ServoPosition;
+
+    fn aero(v_air_b_m_s: Vector3<f64>, mach: f64) -> AeroState {
+        AeroState::new(
+            v_air_b_m_s,
+            Vector3::zeros(),
+            1000.0,
+            mach,
+            1.0,
+            ServoPosition::default(),
+            0.0,
+        )
+    }
+
+    #[test]
+    fn test_envelope_sample() {
+        // 100 m/s, 3° off the body axis
+        let aoa = 3f64.to_radians();
+        let v = Vector3::new(aoa.cos(), 0.6 * aoa.sin(), 0.8 * aoa.sin()) * 100.0;
+        let sample = FlightEnvelopeSample::next(&FlightEnvelopeSample::default(), &aero(v, 0.3));
+
+        assert_relative_eq!(sample.dynamic_pressure_pa, 5000.0, epsilon = 1e-9);
+        assert_relative_eq!(sample.total_aoa_deg, 3.0, epsilon = 1e-9);
+        assert_relative_eq!(sample.q_alpha_pa_deg, 15000.0, epsilon = 1e-6);
+        assert_eq!(sample.altitude_m, 1000.0);
+
+        // Slower, but the maxima are kept
+        let sample = FlightEnvelopeSample::next(&sample, &aero(Vector3::x() * 50.0, 0.15));
+        assert_relative_eq!(sample.dynamic_pressure_pa, 1250.0, epsilon = 1e-9);
+        assert_eq!(sample.total_aoa_deg, 0.0);
+        assert_relative_eq!(sample.max_dynamic_pressure_pa, 5000.0, epsilon = 1e-9);
+        assert_eq!(sample.max_mach, 0.3);
+        assert_relative_eq!(sample.max_q_alpha_pa_deg, 15000.0, epsilon = 1e-6);
+
+        // At rest
+        let sample = FlightEnvelopeSample::next(&sample, &aero(Vector3::zeros(), 0.0));
+        assert_eq!(sample.total_aoa_deg, 0.0);
+    }
+}
@@ -0,0 +1,285 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{Result, anyhow};
+use chrono::Utc;
+use log::info;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    crater::environment::LaunchSite,
+    parameters::{ParameterMap, ParameterValue},
+};
+
+/// Pressure levels requested to build the wind profile, up to ~3 km above sea level
+const PRESSURE_LEVELS_HPA: [u32; 8] = [1000, 975, 950, 925, 900, 850, 800, 700];
+
+/// Where and when to get the launch day conditions from
+#[derive(Debug, Clone)]
+pub struct ForecastConfig {
+    /// Forecast endpoint, accepting Open-Meteo style queries
+    pub url: String,
+    pub latitude_deg: f64,
+    pub longitude_deg: f64,
+    /// Launch time, UTC, formatted as "YYYY-MM-DDTHH:00"
+    pub launch_time: String,
+    /// Run manifest where the conditions are cached
+    pub manifest: PathBuf,
+}
+
+impl ForecastConfig {
+    /// Reads the configuration from the root parameter map. Returns None if the forecast is
+    /// disabled.
+    pub fn from_params(params: &ParameterMap) -> Result<Option<Self>> {
+        let forecast = params.get_map("sim.forecast")?;
+        if !forecast.get_param("enabled")?.value_bool()? {
+            return Ok(None);
+        }
+
+        let site = LaunchSite::from_params(params)?;
+        Ok(Some(Self {
+            url: forecast.get_param("url")?.value_string()?,
+            latitude_deg: site.latitude_deg,
+            longitude_deg: site.longitude_deg,
+            launch_time: forecast.get_param("launch_time")?.value_string()?,
+            manifest: PathBuf::from(forecast.get_param("manifest")?.value_string()?),
+        }))
+    }
+}
+
+/// Atmospheric conditions at the launch site, derived from a forecast
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LaunchConditions {
+    pub url: String,
+    pub latitude_deg: f64,
+    pub longitude_deg: f64,
+    pub launch_time: String,
+    pub fetched_at: String,
+
+    pub surface_pressure_pa: f64,
+    pub surface_temperature_k: f64,
+
+    /// Wind profile, with altitudes above the launch site. Direction the wind is blowing from,
+    /// clockwise from north
+    pub altitude_m: Vec<f64>,
+    pub speed_m_s: Vec<f64>,
+    pub direction_deg: Vec<f64>,
+
+    /// Raw response of the forecast service
+    pub response: Value,
+}
+
+impl LaunchConditions {
+    /// Loads the conditions from the run manifest if they were already fetched for the same
+    /// site & launch time, otherwise fetches them and stores them in the manifest. Runs using the
+    /// same manifest are therefore reproducible, even when the forecast is updated.
+    pub fn load_or_fetch(config: &ForecastConfig) -> Result<Self> {
+        if let Ok(manifest) = fs::read_to_string(&config.manifest) {
+            let cached: LaunchConditions = serde_json::from_str(&manifest)?;
+
+            if cached.matches(config) {
+                info!(
+                    "Using launch conditions cached in '{}', fetched at {}",
+                    config.manifest.display(),
+                    cached.fetched_at
+                );
+                return Ok(cached);
+            }
+        }
+
+        info!("Fetching launch conditions from '{}'", config.url);
+        let conditions = Self::fetch(config)?;
+        fs::write(&config.manifest, serde_json::to_string_pretty(&conditions)?)?;
+
+        Ok(conditions)
+    }
+
+    fn matches(&self, config: &ForecastConfig) -> bool {
+        self.url == config.url
+            && self.latitude_deg == config.latitude_deg
+            && self.longitude_deg == config.longitude_deg
+            && self.launch_time == config.launch_time
+    }
+
+    fn fetch(config: &ForecastConfig) -> Result<Self> {
+        let mut hourly = vec![
+            "surface_pressure".to_string(),
+            "temperature_2m".to_string(),
+            "wind_speed_10m".to_string(),
+            "wind_direction_10m".to_string(),
+        ];
+        for level in PRESSURE_LEVELS_HPA {
+            hourly.push(format!("wind_speed_{level}hPa"));
+            hourly.push(format!("wind_direction_{level}hPa"));
+            hourly.push(format!("geopotential_height_{level}hPa"));
+        }
+
+        let response: Value = ureq::get(&config.url)
+            .query("latitude", &config.latitude_deg.to_string())
+            .query("longitude", &config.longitude_deg.to_string())
+            .query("hourly", &hourly.join(","))
+            .query("wind_speed_unit", "ms")
+            .query("timezone", "UTC")
+            .query("start_hour", &config.launch_time)
+            .query("end_hour", &config.launch_time)
+            .call()?
+            .into_json()?;
+
+        Self::from_response(config, response)
+    }
+
+    /// Parses an Open-Meteo style hourly forecast
+    pub fn from_response(config: &ForecastConfig, response: Value) -> Result<Self> {
+        let hourly = &response["hourly"];
+
+        let times = hourly["time"]
+            .as_array()
+            .ok_or(anyhow!("Forecast response has no hourly data"))?;
+        let i = times
+            .iter()
+            .position(|t| t.as_str() == Some(config.launch_time.as_str()))
+            .ok_or(anyhow!(
+                "Forecast response has no data for {}",
+                config.launch_time
+            ))?;
+
+        let value = |name: &str| hourly[name][i].as_f64();
+        let required = |name: &str| value(name).ok_or(anyhow!("Forecast is missing '{name}'"));
+
+        // Pressure levels are referenced to sea level, the wind profile to the launch site
+        let elevation_m = response["elevation"].as_f64().unwrap_or(0.0);
+
+        let mut profile = vec![(
+            10.0,
+            required("wind_speed_10m")?,
+            required("wind_direction_10m")?,
+        )];
+        for level in PRESSURE_LEVELS_HPA {
+            let (Some(height), Some(speed), Some(dir)) = (
+                value(&format!("geopotential_height_{level}hPa")),
+                value(&format!("wind_speed_{level}hPa")),
+                value(&format!("wind_direction_{level}hPa")),
+            ) else {
+                continue;
+            };
+
+            // Levels below ground are extrapolated by the model: skip them
+            let altitude_m = height - elevation_m;
+            if altitude_m > profile.last().unwrap().0 {
+                profile.push((altitude_m, speed, dir));
+            }
+        }
+
+        Ok(Self {
+            url: config.url.clone(),
+            latitude_deg: config.latitude_deg,
+            longitude_deg: config.longitude_deg,
+            launch_time: config.launch_time.clone(),
+            fetched_at: Utc::now().to_rfc3339(),
+            surface_pressure_pa: required("surface_pressure")? * 100.0,
+            surface_temperature_k: required("temperature_2m")? + 273.15,
+            altitude_m: profile.iter().map(|p| p.0).collect(),
+            speed_m_s: profile.iter().map(|p| p.1).collect(),
+            direction_deg: profile.iter().map(|p| p.2).collect(),
+            response,
+        })
+    }
+
+    /// Replaces the atmosphere & wind parameters with these conditions
+    pub fn apply(&self, params: &mut ParameterMap) -> Result<()> {
+        let float = |val: f64| ParameterValue::Float { val };
+        let float_arr = |val: &[f64]| ParameterValue::FloatArray { val: val.to_vec() };
+
+        params.set_param(
+            "sim.atmosphere.model",
+            ParameterValue::String {
+                val: "isa".to_string(),
+            },
+        )?;
+        params.set_param("sim.atmosphere.isa_offset", float(0.0))?;
+        params.set_param("sim.atmosphere.pressure_0", float(self.surface_pressure_pa))?;
+        params.set_param("sim.atmosphere.temperature_0", float(self.surface_temperature_k))?;
+
+        params.set_param(
+            "sim.wind.model",
+            ParameterValue::String {
+                val: "tabulated".to_string(),
+            },
+        )?;
+        params.set_param("sim.wind.tabulated.altitude_m", float_arr(&self.altitude_m))?;
+        params.set_param("sim.wind.tabulated.speed_m_s", float_arr(&self.speed_m_s))?;
+        params.set_param(
+            "sim.wind.tabulated.direction_deg",
+            float_arr(&self.direction_deg),
+        )?;
+
+        Ok(())
+    }
+}
+
+/// If the forecast is enabled, loads the launch day conditions and applies them to the
+/// parameters, before the nodes are created.
+pub fn apply_forecast(params: &mut ParameterMap) -> Result<Option<LaunchConditions>> {
+    let Some(config) = ForecastConfig::from_params(params)? else {
+        return Ok(None);
+    };
+
+    let conditions = LaunchConditions::load_or_fetch(&config)?;
+    conditions.apply(params)?;
+
+    info!(
+        "Launch conditions for {}: {:.1} hPa, {:.1} °C, surface wind {:.1} m/s from {:.0}°",
+        conditions.launch_time,
+        conditions.surface_pressure_pa / 100.0,
+        conditions.surface_temperature_k - 273.15,
+        conditions.speed_m_s[0],
+        conditions.direction_deg[0]
+    );
+
+    Ok(Some(conditions))
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_open_meteo() {
+        let config = ForecastConfig {
+            url: "http://localhost".to_string(),
+            latitude_deg: 41.8,
+            longitude_deg: 14.0,
+            launch_time: "2025-06-01T10:00".to_string(),
+            manifest: PathBuf::new(),
+        };
+
+        let response = json!({
+            "elevation": 200.0,
+            "hourly": {
+                "time": ["2025-06-01T09:00", "2025-06-01T10:00"],
+                "surface_pressure": [990.0, 989.0],
+                "temperature_2m": [20.0, 21.0],
+                "wind_speed_10m": [2.0, 3.0],
+                "wind_direction_10m": [180.0, 190.0],
+                "geopotential_height_1000hPa": [110.0, 110.0],
+                "wind_speed_1000hPa": [4.0, 4.0],
+                "wind_direction_1000hPa": [200.0, 200.0],
+                "geopotential_height_900hPa": [1000.0, 1000.0],
+                "wind_speed_900hPa": [8.0, 9.0],
+                "wind_direction_900hPa": [250.0, 260.0],
+            }
+        });
+
+        let conditions = LaunchConditions::from_response(&config, response).unwrap();
+
+        assert_relative_eq!(conditions.surface_pressure_pa, 98900.0);
+        assert_relative_eq!(conditions.surface_temperature_k, 294.15);
+        // The 1000 hPa level is below ground
+        assert_eq!(conditions.altitude_m, vec![10.0, 800.0]);
+        assert_eq!(conditions.speed_m_s, vec![3.0, 9.0]);
+        assert_eq!(conditions.direction_deg, vec![190.0, 260.0]);
+    }
+}
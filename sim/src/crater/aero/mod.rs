@@ -2,3 +2,8 @@ pub mod tabulated_aerodynamics;
 pub mod linear_aerodynamics;
 pub mod aerodynamics;
 pub mod atmosphere;
+pub mod barrowman;
+pub mod envelope;
+pub mod forecast;
+pub mod stability;
+pub mod wind;
@@ -0,0 +1,168 @@
+use anyhow::Result;
+use nalgebra::Vector3;
+
+use crate::{
+    core::time::Timestamp,
+    crater::{channels, events::SimEvent},
+    nodes::NodeTelemetry,
+    parameters::ParameterMap,
+    telemetry::TelemetrySender,
+};
+
+use super::aerodynamics::{AeroState, AerodynamicsCoefficients};
+
+/// Angle of attack the CP is evaluated at when flying straight, as the normal force vanishes
+const PROBE_AOA_RAD: f64 = 1.0f64.to_radians();
+
+/// Center of pressure & static margin. Positions measured from the nose, positive aft.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StabilitySample {
+    pub x_cp_m: f64,
+    pub x_cg_m: f64,
+    /// Distance between CP & CG, in calibers. Positive if stable.
+    pub static_margin_cal: f64,
+}
+
+impl StabilitySample {
+    pub fn new(x_cp_m: f64, x_cg_m: f64, diameter_m: f64) -> Self {
+        Self {
+            x_cp_m,
+            x_cg_m,
+            static_margin_cal: (x_cp_m - x_cg_m) / diameter_m,
+        }
+    }
+}
+
+/// Center of pressure of the rocket in `state`, from the coefficients about `ref_x_m`. The
+/// normal & side forces are combined, so that it holds for any roll angle. When flying straight,
+/// the coefficients are evaluated at a small angle of attack instead. None if the model gives no
+/// normal force.
+pub fn center_of_pressure(
+    model: &dyn AerodynamicsCoefficients,
+    state: &AeroState,
+    ref_x_m: f64,
+    ref_length_m: f64,
+) -> Option<f64> {
+    let cp = |state: &AeroState| {
+        let c = model.coefficients(state);
+        let normal_sq = c.cN.powi(2) + c.cY.powi(2);
+
+        (normal_sq > 1e-9).then(|| ref_x_m - ref_length_m * (c.cm * c.cN + c.cn * c.cY) / normal_sq)
+    };
+
+    cp(state).or_else(|| {
+        let v_norm = state.v_air_norm_m_s.max(1.0);
+        let probe = AeroState::new(
+            Vector3::new(PROBE_AOA_RAD.cos(), 0.0, PROBE_AOA_RAD.sin()) * v_norm,
+            Vector3::zeros(),
+            state.altitude_m,
+            state.mach,
+            state.air_density_kg_m3,
+            state.servo_pos.clone(),
+            state.airbrake_extension,
+        );
+        cp(&probe)
+    })
+}
+
+/// Warns with a `LowStaticMargin` event when the static margin drops below the threshold in
+/// `sim.rocket.aero.stability`, once each time it does
+pub struct StaticMarginMonitor {
+    tx_sim_event: TelemetrySender<SimEvent>,
+    min_static_margin_cal: Option<f64>,
+    below: bool,
+}
+
+impl StaticMarginMonitor {
+    pub fn new(telemetry: &NodeTelemetry, params: &ParameterMap) -> Result<Self> {
+        let min_static_margin_cal = if params.get_param("warn")?.value_bool()? {
+            Some(params.get_param("min_static_margin")?.value_float()?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            tx_sim_event: telemetry.publish_mp(channels::sim::SIM_EVENTS)?,
+            min_static_margin_cal,
+            below: false,
+        })
+    }
+
+    pub fn update(&mut self, t: Timestamp, sample: &StabilitySample) {
+        let Some(threshold_cal) = self.min_static_margin_cal else {
+            return;
+        };
+
+        let below = sample.static_margin_cal < threshold_cal;
+        if below && !self.below {
+            self.tx_sim_event.send(
+                t,
+                SimEvent::LowStaticMargin {
+                    static_margin_cal: sample.static_margin_cal,
+                    threshold_cal,
+                },
+            );
+        }
+        self.below = below;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::crater::{
+        aero::barrowman::{BarrowmanAeroCoefficients, Fins, Nose, NoseShape, RocketGeometry},
+        gnc::ServoPosition,
+    };
+
+    #[test]
+    fn test_center_of_pressure() {
+        let geometry = RocketGeometry {
+            diameter_m: 0.08,
+            base_diameter_m: 0.08,
+            nose: Nose {
+                shape: NoseShape::Ogive,
+                length_m: 0.24,
+            },
+            transitions: vec![],
+            fins: Fins {
+                count: 4,
+                x_m: 0.86,
+                root_chord_m: 0.1,
+                tip_chord_m: 0.05,
+                span_m: 0.07,
+                sweep_m: 0.05,
+            },
+        };
+        let (_, x_cp) = geometry.cp(0.3);
+        let model = BarrowmanAeroCoefficients::new(geometry, 0.5, 0.3);
+
+        let state = |v_air_b_m_s: Vector3<f64>| {
+            AeroState::new(
+                v_air_b_m_s,
+                Vector3::zeros(),
+                1000.0,
+                0.3,
+                1.0,
+                ServoPosition::default(),
+                0.0,
+            )
+        };
+
+        // Pitch plane, yaw plane, flying straight & both angles: the same CP
+        for v in [
+            Vector3::new(100.0, 0.0, 5.0),
+            Vector3::new(100.0, -5.0, 0.0),
+            Vector3::new(100.0, 0.0, 0.0),
+            Vector3::new(100.0, 3.0, -4.0),
+        ] {
+            let cp = center_of_pressure(&model, &state(v), 0.5, 0.08).unwrap();
+            assert_relative_eq!(cp, x_cp, epsilon = 1e-9);
+        }
+
+        let sample = StabilitySample::new(x_cp, 0.55, 0.08);
+        assert_relative_eq!(sample.static_margin_cal, (x_cp - 0.55) / 0.08);
+    }
+}
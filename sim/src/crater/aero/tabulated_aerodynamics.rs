@@ -3,7 +3,10 @@ use hdf5_metno::File;
 use std::{array, f64, path::Path};
 use strum::{AsRefStr, EnumIter, IntoEnumIterator};
 
-use crate::math::interp::Interpolator;
+use crate::{
+    math::interp::{Extrapolation, Interpolator},
+    parameters::ParameterMap,
+};
 
 use super::aerodynamics::{AeroCoefficientsValues, AeroState, AerodynamicsCoefficients};
 
@@ -31,7 +34,7 @@ enum Coefficients {
     CLNR,
 }
 
-#[derive(Debug, Clone, Copy, AsRefStr, EnumIter)]
+#[derive(Debug, Clone, Copy, AsRefStr)]
 enum States {
     #[strum(serialize = "alpha")]
     Alpha,
@@ -49,10 +52,66 @@ enum States {
     Delta3,
     #[strum(serialize = "fin2.delta4")]
     Delta4,
+    #[strum(serialize = "fin2.delta")]
+    Delta,
+    #[strum(serialize = "airbrake.extension")]
+    AirbrakeExtension,
+}
+
+/// Independent variables of the coefficient tables
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableAxes {
+    /// Alpha, Mach, beta, altitude and the deflection of each of the four fins
+    Full,
+    /// Alpha, Mach, beta and the mean deflection of the four fins
+    FinDeflection,
+    /// Alpha, Mach, beta and the airbrake extension
+    AirbrakeExtension,
+}
+
+impl TableAxes {
+    fn states(&self) -> &'static [States] {
+        match self {
+            TableAxes::Full => &[
+                States::Alpha,
+                States::Mach,
+                States::Beta,
+                States::Altitude,
+                States::Delta1,
+                States::Delta2,
+                States::Delta3,
+                States::Delta4,
+            ],
+            TableAxes::FinDeflection => &[States::Alpha, States::Mach, States::Beta, States::Delta],
+            TableAxes::AirbrakeExtension => &[
+                States::Alpha,
+                States::Mach,
+                States::Beta,
+                States::AirbrakeExtension,
+            ],
+        }
+    }
+
+    /// Value of the fourth table variable
+    fn control(&self, state: &AeroState) -> f32 {
+        match self {
+            TableAxes::Full => unreachable!("Full tables have no single control variable"),
+            TableAxes::FinDeflection => {
+                (state.servo_pos.pos_rad.iter().sum::<f64>() / 4.0).to_degrees() as f32
+            }
+            TableAxes::AirbrakeExtension => state.airbrake_extension as f32,
+        }
+    }
+}
+
+enum Table {
+    Full(Interpolator<f32, 8>),
+    Control(Interpolator<f32, 4>),
 }
 
 pub struct TabulatedAeroCoefficients {
-    interp: Interpolator<f32, 8>,
+    axes: TableAxes,
+    table: Table,
     coeffs: Vec<Vec<f32>>,
 }
 
@@ -60,21 +119,58 @@ impl AerodynamicsCoefficients for TabulatedAeroCoefficients {
     fn coefficients(&self, state: &AeroState) -> AeroCoefficientsValues {
         self.interpolate(state)
     }
+
+    fn includes_airbrake(&self) -> bool {
+        self.axes == TableAxes::AirbrakeExtension
+    }
 }
 
 impl TabulatedAeroCoefficients {
-    pub fn from_h5(file_main: &Path, file_derivatives: &Path) -> Result<Self> {
+    /// Reads the tables from the files in `coeffs_main` & `coeffs_dynamic`, with the variables
+    /// in `axes` (full, fin_deflection or airbrake_extension) and the `extrapolation` (clamp or
+    /// linear) outside of their range
+    pub fn from_params(params: &ParameterMap) -> Result<Self> {
+        let axes = match params.get_param("axes")?.value_string()?.as_str() {
+            "full" => TableAxes::Full,
+            "fin_deflection" => TableAxes::FinDeflection,
+            "airbrake_extension" => TableAxes::AirbrakeExtension,
+            unknown => return Err(anyhow!("Unknown aerodynamic table axes: {unknown}")),
+        };
+        let extrapolation = match params.get_param("extrapolation")?.value_string()?.as_str() {
+            "clamp" => Extrapolation::Clamp,
+            "linear" => Extrapolation::Linear,
+            unknown => {
+                return Err(anyhow!(
+                    "Unknown aerodynamic table extrapolation: {unknown}"
+                ));
+            }
+        };
+
+        Self::from_h5(
+            Path::new(&params.get_param("coeffs_main")?.value_string()?),
+            Path::new(&params.get_param("coeffs_dynamic")?.value_string()?),
+            axes,
+            extrapolation,
+        )
+    }
+
+    pub fn from_h5(
+        file_main: &Path,
+        file_derivatives: &Path,
+        axes: TableAxes,
+        extrapolation: Extrapolation,
+    ) -> Result<Self> {
         let h5_main = File::open(file_main)?;
         let h5_derivatives = File::open(file_derivatives)?;
 
         let mut states = vec![];
-        for state in States::iter() {
+        for state in axes.states() {
             let v = h5_main.dataset(state.as_ref())?.read_raw::<f32>()?;
             states.push(v);
         }
 
         let mut states2 = vec![];
-        for state in States::iter() {
+        for state in axes.states() {
             let v = h5_derivatives.dataset(state.as_ref())?.read_raw::<f32>()?;
             states2.push(v);
         }
@@ -101,38 +197,73 @@ impl TabulatedAeroCoefficients {
             }
         }
 
-        let interp = Interpolator::<f32, 8>::new(array::from_fn(|i| states[i].as_slice()))
-            .ok_or_else(|| anyhow!("Bad interpolator"))?;
+        let table = match axes {
+            TableAxes::Full => Table::Full(
+                Interpolator::<f32, 8>::new(array::from_fn(|i| states[i].as_slice()))
+                    .ok_or_else(|| anyhow!("Bad interpolator"))?
+                    .with_extrapolation(extrapolation),
+            ),
+            TableAxes::FinDeflection | TableAxes::AirbrakeExtension => Table::Control(
+                Interpolator::<f32, 4>::new(array::from_fn(|i| states[i].as_slice()))
+                    .ok_or_else(|| anyhow!("Bad interpolator"))?
+                    .with_extrapolation(extrapolation),
+            ),
+        };
 
         Ok(Self {
-            interp,
+            axes,
+            table,
             coeffs,
         })
     }
 
     fn interpolate(&self, state: &AeroState) -> AeroCoefficientsValues {
-        let state1 = [
-            state.angles.alpha_rad.to_degrees() as f32,
-            state.mach as f32,
-            state.angles.beta_rad.to_degrees() as f32,
-            state.altitude_m as f32,
-            state.servo_pos.pos_rad[0].to_degrees() as f32,
-            state.servo_pos.pos_rad[1].to_degrees() as f32,
-            state.servo_pos.pos_rad[2].to_degrees() as f32,
-            state.servo_pos.pos_rad[3].to_degrees() as f32,
-        ];
+        let alpha_deg = state.angles.alpha_rad.to_degrees() as f32;
+        let beta_deg = state.angles.beta_rad.to_degrees() as f32;
+        let beta_tan_deg = state.angles.beta_tan_rad.to_degrees() as f32;
+        let mach = state.mach as f32;
 
-        let state2 = [
-            state.angles.beta_tan_rad.to_degrees() as f32,
-            state.mach as f32,
-            state.angles.alpha_rad.to_degrees() as f32,
-            state.altitude_m as f32,
-            state.servo_pos.pos_rad[0].to_degrees() as f32,
-            state.servo_pos.pos_rad[1].to_degrees() as f32,
-            state.servo_pos.pos_rad[2].to_degrees() as f32,
-            state.servo_pos.pos_rad[3].to_degrees() as f32,
-        ];
+        // The lateral derivatives are read from the longitudinal tables, with alpha & beta swapped
+        match &self.table {
+            Table::Full(interp) => {
+                let deltas: [f32; 4] =
+                    array::from_fn(|i| state.servo_pos.pos_rad[i].to_degrees() as f32);
+                let altitude_m = state.altitude_m as f32;
+
+                let state1 = [
+                    alpha_deg, mach, beta_deg, altitude_m, deltas[0], deltas[1], deltas[2],
+                    deltas[3],
+                ];
+                let state2 = [
+                    beta_tan_deg,
+                    mach,
+                    alpha_deg,
+                    altitude_m,
+                    deltas[0],
+                    deltas[1],
+                    deltas[2],
+                    deltas[3],
+                ];
+
+                self.interpolate_table(interp, &state1, &state2)
+            }
+            Table::Control(interp) => {
+                let control = self.axes.control(state);
+
+                let state1 = [alpha_deg, mach, beta_deg, control];
+                let state2 = [beta_tan_deg, mach, alpha_deg, control];
+
+                self.interpolate_table(interp, &state1, &state2)
+            }
+        }
+    }
 
+    fn interpolate_table<const D: usize>(
+        &self,
+        interp: &Interpolator<f32, D>,
+        state1: &[f32; D],
+        state2: &[f32; D],
+    ) -> AeroCoefficientsValues {
         let c1 = [
             self.coeffs[Coefficients::CA as usize].as_slice(),
             self.coeffs[Coefficients::CY as usize].as_slice(),
@@ -158,8 +289,8 @@ impl TabulatedAeroCoefficients {
         ];
         let mut v2: [f32; 4] = [0f32; 4];
 
-        self.interp.interpn(&state1, &c1, &mut v1);
-        self.interp.interpn(&state2, &c2, &mut v2);
+        interp.interpn(state1, &c1, &mut v1);
+        interp.interpn(state2, &c2, &mut v2);
 
         AeroCoefficientsValues {
             cA: v1[0] as f64,
@@ -0,0 +1,125 @@
+use anyhow::{Result, anyhow};
+use nalgebra::Vector3;
+
+use crate::{
+    math::interp::{find_index, interpolate},
+    parameters::ParameterMap,
+};
+
+pub trait WindModel {
+    /// Wind velocity in the NED frame at the provided altitude above the launch site
+    fn wind_n(&self, alt_m: f64) -> Vector3<f64>;
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NoWind;
+
+impl WindModel for NoWind {
+    fn wind_n(&self, _: f64) -> Vector3<f64> {
+        Vector3::zeros()
+    }
+}
+
+/// Wind profile tabulated as a function of altitude above the launch site.
+/// Direction follows the meteorological convention: it is the direction the wind
+/// is blowing *from*, measured clockwise from north.
+#[derive(Debug, Clone)]
+pub struct TabulatedWind {
+    altitude_m: Vec<f64>,
+    wind_n_m_s: Vec<f64>,
+    wind_e_m_s: Vec<f64>,
+}
+
+impl TabulatedWind {
+    pub fn new(altitude_m: &[f64], speed_m_s: &[f64], direction_deg: &[f64]) -> Result<Self> {
+        if altitude_m.is_empty()
+            || altitude_m.len() != speed_m_s.len()
+            || altitude_m.len() != direction_deg.len()
+        {
+            return Err(anyhow!(
+                "Wind table must be non-empty and all columns must have the same length"
+            ));
+        }
+
+        if altitude_m.windows(2).any(|w| w[1] <= w[0]) {
+            return Err(anyhow!("Wind table altitudes must be strictly increasing"));
+        }
+
+        // Interpolate in cartesian components to avoid issues when the direction wraps around
+        let (wind_n_m_s, wind_e_m_s) = speed_m_s
+            .iter()
+            .zip(direction_deg.iter())
+            .map(|(speed, dir)| {
+                let dir = dir.to_radians();
+                (-speed * dir.cos(), -speed * dir.sin())
+            })
+            .unzip();
+
+        Ok(Self {
+            altitude_m: altitude_m.to_vec(),
+            wind_n_m_s,
+            wind_e_m_s,
+        })
+    }
+
+    pub fn from_params(params: &ParameterMap) -> Result<Self> {
+        Self::new(
+            params.get_param("altitude_m")?.value_float_arr()?,
+            params.get_param("speed_m_s")?.value_float_arr()?,
+            params.get_param("direction_deg")?.value_float_arr()?,
+        )
+    }
+
+    /// Profile rotated by `direction_offset_deg` (clockwise), with the speeds scaled by
+    /// `speed_scale`
+    pub fn perturbed(&self, direction_offset_deg: f64, speed_scale: f64) -> Self {
+        let (sin, cos) = direction_offset_deg.to_radians().sin_cos();
+
+        let (wind_n_m_s, wind_e_m_s) = self
+            .wind_n_m_s
+            .iter()
+            .zip(self.wind_e_m_s.iter())
+            .map(|(n, e)| {
+                (
+                    speed_scale * (n * cos - e * sin),
+                    speed_scale * (n * sin + e * cos),
+                )
+            })
+            .unzip();
+
+        Self {
+            altitude_m: self.altitude_m.clone(),
+            wind_n_m_s,
+            wind_e_m_s,
+        }
+    }
+}
+
+impl WindModel for TabulatedWind {
+    fn wind_n(&self, alt_m: f64) -> Vector3<f64> {
+        let pos = find_index(&self.altitude_m, alt_m);
+
+        Vector3::new(
+            interpolate(&self.wind_n_m_s, pos).0,
+            interpolate(&self.wind_e_m_s, pos).0,
+            0.0,
+        )
+    }
+}
+
+/// Builds the wind model selected in the `model` parameter of the provided map. The tabulated
+/// profile is perturbed by the sampled forecast uncertainty (`direction_offset`, `speed_scale`).
+pub fn wind_from_params(params: &ParameterMap) -> Result<Box<dyn WindModel + Send>> {
+    let model = params.get_param("model")?.value_string()?;
+
+    match model.as_str() {
+        "none" => Ok(Box::new(NoWind)),
+        "tabulated" => Ok(Box::new(
+            TabulatedWind::from_params(params.get_map("tabulated")?)?.perturbed(
+                params.get_param("direction_offset")?.value_float()?,
+                params.get_param("speed_scale")?.value_float()?,
+            ),
+        )),
+        _ => Err(anyhow!("Unknown wind model '{model}'")),
+    }
+}
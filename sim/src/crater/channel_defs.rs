@@ -0,0 +1,383 @@
+// Generated by `cargo xtask codegen` from interfaces/telemetry.toml: do not edit.
+
+use crate::telemetry::{ChannelUnits, TelemetryService};
+
+pub mod sim {
+    pub const SIM_EVENTS: &str = "/sim/events";
+    /// Round trip of each lockstep step with the external autopilot
+    pub const COSIM_LINK: &str = "/sim/cosim/link";
+    /// Time sync round trip with the flight computer in the loop, and lag of the simulation behind the wall clock
+    pub const HIL_LINK: &str = "/sim/hil/link";
+    /// MAVLink messages sent by the ground station, before the radio link to the flight software uplink
+    pub const GROUND_UPLINK: &str = "/sim/radio/uplink";
+    /// MAVLink messages of the flight software received by the ground station through the radio link
+    pub const GROUND_DOWNLINK: &str = "/sim/radio/downlink";
+    /// Drift monitors of the soak runs
+    pub const SOAK_STATUS: &str = "/sim/soak";
+    /// Wall-clock time taken by each node in each step
+    pub const PROFILING: &str = "/sim/profiling";
+    /// Channels of the model, with their producers, subscribers & message rates
+    pub const CHANNELS: &str = "/sim/channels";
+    /// Launch site, origin of the NED frame. Sticky: published once, at the start.
+    pub const LAUNCH_SITE: &str = "/sim/environment/launch_site";
+}
+
+pub mod rocket {
+    pub const STATE: &str = "/rocket/state";
+    pub const ACTIONS: &str = "/rocket/actions";
+    pub const ACCEL: &str = "/rocket/accel";
+    pub const AERO_STATE: &str = "/rocket/aerostate";
+    /// Dynamic pressure, Mach & total angle of attack, with their maxima, for the envelope checks
+    pub const ENVELOPE: &str = "/rocket/envelope";
+    /// Center of pressure, CG & static margin
+    pub const STABILITY: &str = "/rocket/stability";
+    pub const MASS_ROCKET: &str = "/rocket/mass/rocket";
+    pub const MASS_ENGINE: &str = "/rocket/mass/engine";
+    /// State at the configured reference point, instead of the CG
+    pub const STATE_REF: &str = "/rocket/ref/state";
+    /// Accelerations at the configured reference point, instead of the CG
+    pub const ACCEL_REF: &str = "/rocket/ref/accel";
+}
+
+pub mod gnc {
+    pub const GNC_EVENTS: &str = "/gnc/events";
+    pub const ADA_OUTPUT: &str = "/gnc/ada";
+    pub const NAV_OUTPUT: &str = "/gnc/nav";
+    pub const SERVO_COMMAND: &str = "/gnc/contro/servo_command";
+    /// Attitude of the complementary filter, for comparison with the navigation attitude
+    pub const ATTITUDE_ESTIMATE: &str = "/gnc/attitude";
+    pub const AIRBRAKE_COMMAND: &str = "/gnc/control/airbrake_command";
+    /// Fin commands of the roll controller of the flight software
+    pub const ROLL_CONTROL: &str = "/gnc/control/roll";
+    /// Airbrake commands of the apogee targeting guidance of the flight software
+    pub const AIRBRAKE_CONTROL: &str = "/gnc/control/airbrake";
+    /// Apogee prediction and extension computed by the airbrake guidance, also in shadow mode
+    pub const AIRBRAKE_GUIDANCE: &str = "/gnc/control/airbrake_guidance";
+    /// Pyro channels fired by the recovery, forwarded by the voter in the dual-FC configuration
+    pub const PYRO_COMMAND: &str = "/gnc/recovery/pyro";
+    pub const RECOVERY_STATUS: &str = "/gnc/recovery/status";
+    /// MAVLink messages sent to the ground, at the rates of the current downlink profile
+    pub const DOWNLINK: &str = "/gnc/downlink";
+    /// MAVLink messages received from the ground station
+    pub const UPLINK: &str = "/gnc/uplink";
+    /// Acknowledgements of the ground commands, sent to the ground next to the downlink
+    pub const COMMAND_ACK: &str = "/gnc/command_ack";
+    /// Onboard log list & data, sent to the ground on request
+    pub const LOG_TRANSFER: &str = "/gnc/log_transfer";
+    /// Events & flight mode transitions of the onboard event log, sent to the ground on request
+    pub const EVENT_LOG: &str = "/gnc/event_log";
+    /// Execution time budgets & CPU usage of the flight software components, sent periodically
+    pub const COMPONENT_HEALTH: &str = "/gnc/component_health";
+    /// MAVLink messages captured at full rate around the trigger events, to the onboard recorder
+    pub const BURST_CAPTURE: &str = "/gnc/burst_capture";
+}
+
+pub mod dual_fc {
+    /// Outputs of the redundant flight computer A
+    pub const A_EVENTS: &str = "/gnc/fc_a/events";
+    pub const A_ADA_OUTPUT: &str = "/gnc/fc_a/ada";
+    pub const A_NAV_OUTPUT: &str = "/gnc/fc_a/nav";
+    pub const A_RECOVERY_STATUS: &str = "/gnc/fc_a/recovery";
+    pub const A_PYRO_COMMAND: &str = "/gnc/fc_a/pyro";
+    pub const A_DOWNLINK: &str = "/gnc/fc_a/downlink";
+    pub const A_COMMAND_ACK: &str = "/gnc/fc_a/command_ack";
+    pub const A_LOG_TRANSFER: &str = "/gnc/fc_a/log_transfer";
+    pub const A_EVENT_LOG: &str = "/gnc/fc_a/event_log";
+    pub const A_COMPONENT_HEALTH: &str = "/gnc/fc_a/component_health";
+    pub const A_BURST_CAPTURE: &str = "/gnc/fc_a/burst_capture";
+    pub const A_ROLL_CONTROL: &str = "/gnc/fc_a/roll_control";
+    pub const A_AIRBRAKE_CONTROL: &str = "/gnc/fc_a/airbrake_control";
+    pub const A_AIRBRAKE_GUIDANCE: &str = "/gnc/fc_a/airbrake_guidance";
+    pub const A_ATTITUDE_ESTIMATE: &str = "/gnc/fc_a/attitude";
+    /// Outputs of the redundant flight computer B
+    pub const B_EVENTS: &str = "/gnc/fc_b/events";
+    pub const B_ADA_OUTPUT: &str = "/gnc/fc_b/ada";
+    pub const B_NAV_OUTPUT: &str = "/gnc/fc_b/nav";
+    pub const B_RECOVERY_STATUS: &str = "/gnc/fc_b/recovery";
+    pub const B_PYRO_COMMAND: &str = "/gnc/fc_b/pyro";
+    pub const B_DOWNLINK: &str = "/gnc/fc_b/downlink";
+    pub const B_COMMAND_ACK: &str = "/gnc/fc_b/command_ack";
+    pub const B_LOG_TRANSFER: &str = "/gnc/fc_b/log_transfer";
+    pub const B_EVENT_LOG: &str = "/gnc/fc_b/event_log";
+    pub const B_COMPONENT_HEALTH: &str = "/gnc/fc_b/component_health";
+    pub const B_BURST_CAPTURE: &str = "/gnc/fc_b/burst_capture";
+    pub const B_ROLL_CONTROL: &str = "/gnc/fc_b/roll_control";
+    pub const B_AIRBRAKE_CONTROL: &str = "/gnc/fc_b/airbrake_control";
+    pub const B_AIRBRAKE_GUIDANCE: &str = "/gnc/fc_b/airbrake_guidance";
+    pub const B_ATTITUDE_ESTIMATE: &str = "/gnc/fc_b/attitude";
+    pub const VOTER_STATUS: &str = "/gnc/voter/status";
+}
+
+pub mod sensors {
+    pub const LIFTOFF_PIN: &str = "/sensors/liftoff_pin";
+    pub const IDEAL_STATIC_PRESSURE: &str = "/sensors/ideal/static_pressure";
+    pub const STATIC_PRESSURE: &str = "/sensors/static_pressure";
+    pub const IDEAL_GPS: &str = "/sensors/ideal/gps";
+    pub const GPS: &str = "/sensors/gps";
+    pub const IDEAL_IMU: &str = "/sensors/ideal/imu";
+    pub const IDEAL_IMU_CG: &str = "/sensors/ideal/imu_cg";
+    pub const IMU: &str = "/sensors/imu";
+    pub const IDEAL_MAGNETOMETER: &str = "/sensors/ideal/magnetometer";
+    pub const MAGNETOMETER: &str = "/sensors/magnetometer";
+    pub const IDEAL_NAV_OUTPUT: &str = "/sensors/ideal_nav";
+    pub const CAMERA_POINTING: &str = "/sensors/camera/pointing";
+    pub const TABLE_SENSORS: &str = "/sensors/table";
+}
+
+pub mod actuators {
+    pub const IDEAL_SERVO_POSITION: &str = "/actuators/ideal_servo_position";
+    pub const SERVO_POWER: &str = "/actuators/servo_power";
+    pub const AIRBRAKE_POSITION: &str = "/actuators/airbrake_position";
+}
+
+/// Attaches unit metadata to the channels, used to label the logged series.
+/// Field paths match the entity paths used by the loggers, so units refer to logged values.
+pub fn register_units(ts: &TelemetryService) {
+    ts.set_units(
+        sim::COSIM_LINK,
+        ChannelUnits::new().field("round_trip_ms", "ms"),
+    );
+    ts.set_units(
+        sim::HIL_LINK,
+        ChannelUnits::new()
+            .field("round_trip_ms", "ms")
+            .field("lag_ms", "ms"),
+    );
+    ts.set_units(
+        sim::SOAK_STATUS,
+        ChannelUnits::new()
+            .field("position_drift_m", "m")
+            .field("attitude_drift_deg", "deg")
+            .field("velocity_m_s", "m/s")
+            .field("ada_altitude_std_m", "m")
+            .field("ada_cov_drift", "-")
+            .field("memory_growth_mb", "MB"),
+    );
+    ts.set_units(sim::PROFILING, ChannelUnits::new().field("total_us", "us"));
+    ts.set_units(
+        rocket::STATE,
+        ChannelUnits::new()
+            .vector3("pos_n", "m")
+            .vector3("vel_n", "m/s")
+            .vector3("vel_b", "m/s")
+            .field("vel_norm", "m/s")
+            .vector3("ang_vel_b", "rad/s")
+            .euler("orient/euler", "deg"),
+    );
+    ts.set_units(
+        rocket::ACTIONS,
+        ChannelUnits::new()
+            .vector3("thrust_b_n", "N")
+            .vector3("aero_force_b_n", "N")
+            .vector3("aero_moments_b_nm", "N·m")
+            .vector3("parachute_force_n_n", "N"),
+    );
+    ts.set_units(
+        rocket::ACCEL,
+        ChannelUnits::new()
+            .vector3("acc_b", "m/s²")
+            .vector3("acc_n", "m/s²"),
+    );
+    ts.set_units(
+        rocket::AERO_STATE,
+        ChannelUnits::new()
+            .field("alpha_deg", "deg")
+            .field("beta_deg", "deg")
+            .field("beta_tan_deg", "deg")
+            .field("mach", "-")
+            .field("air_density_kg_m3", "kg/m³")
+            .field("altitude_m", "m")
+            .field("v_air_norm_m_s", "m/s")
+            .vector3("v_air_b_m_s", "m/s")
+            .vector3("w_b_rad_s", "rad/s"),
+    );
+    ts.set_units(
+        rocket::ENVELOPE,
+        ChannelUnits::new()
+            .field("dynamic_pressure_pa", "Pa")
+            .field("mach", "-")
+            .field("altitude_m", "m")
+            .field("total_aoa_deg", "deg")
+            .field("q_alpha_pa_deg", "Pa·deg")
+            .field("max_dynamic_pressure_pa", "Pa")
+            .field("max_mach", "-")
+            .field("max_q_alpha_pa_deg", "Pa·deg"),
+    );
+    ts.set_units(
+        rocket::STABILITY,
+        ChannelUnits::new()
+            .field("x_cp_m", "m")
+            .field("x_cg_m", "m")
+            .field("static_margin_cal", "cal"),
+    );
+    ts.set_units(
+        rocket::STATE_REF,
+        ChannelUnits::new()
+            .vector3("pos_n", "m")
+            .vector3("vel_n", "m/s")
+            .vector3("vel_b", "m/s")
+            .field("vel_norm", "m/s")
+            .vector3("ang_vel_b", "rad/s")
+            .euler("orient/euler", "deg"),
+    );
+    ts.set_units(
+        rocket::ACCEL_REF,
+        ChannelUnits::new()
+            .vector3("acc_b", "m/s²")
+            .vector3("acc_n", "m/s²"),
+    );
+    ts.set_units(
+        gnc::ADA_OUTPUT,
+        ChannelUnits::new()
+            .field("altitude_m", "m")
+            .field("vertical_speed_m_s", "m/s")
+            .field("vertical_accel_m_s2", "m/s²"),
+    );
+    ts.set_units(
+        gnc::NAV_OUTPUT,
+        ChannelUnits::new()
+            .vector3("pos_n_m", "m")
+            .vector3("vel_n_m_s", "m/s")
+            .vector3("angvel_unbias_b_rad_s", "rad/s")
+            .vector3("acc_unbias_b_m_s2", "m/s²")
+            .euler("euler", "deg"),
+    );
+    ts.set_units(
+        gnc::ATTITUDE_ESTIMATE,
+        ChannelUnits::new()
+            .euler("euler", "deg")
+            .vector3("gyro_bias_b_rad_s", "rad/s"),
+    );
+    ts.set_units(
+        gnc::AIRBRAKE_COMMAND,
+        ChannelUnits::new().field("extension", "-"),
+    );
+    ts.set_units(
+        gnc::AIRBRAKE_CONTROL,
+        ChannelUnits::new().field("extension", "-"),
+    );
+    ts.set_units(
+        gnc::AIRBRAKE_GUIDANCE,
+        ChannelUnits::new()
+            .field("predicted_apogee_m", "m")
+            .field("extension", "-"),
+    );
+    ts.set_units(
+        dual_fc::A_ADA_OUTPUT,
+        ChannelUnits::new()
+            .field("altitude_m", "m")
+            .field("vertical_speed_m_s", "m/s")
+            .field("vertical_accel_m_s2", "m/s²"),
+    );
+    ts.set_units(
+        dual_fc::A_NAV_OUTPUT,
+        ChannelUnits::new()
+            .vector3("pos_n_m", "m")
+            .vector3("vel_n_m_s", "m/s")
+            .vector3("angvel_unbias_b_rad_s", "rad/s")
+            .vector3("acc_unbias_b_m_s2", "m/s²")
+            .euler("euler", "deg"),
+    );
+    ts.set_units(
+        dual_fc::A_AIRBRAKE_CONTROL,
+        ChannelUnits::new().field("extension", "-"),
+    );
+    ts.set_units(
+        dual_fc::A_AIRBRAKE_GUIDANCE,
+        ChannelUnits::new()
+            .field("predicted_apogee_m", "m")
+            .field("extension", "-"),
+    );
+    ts.set_units(
+        dual_fc::A_ATTITUDE_ESTIMATE,
+        ChannelUnits::new()
+            .euler("euler", "deg")
+            .vector3("gyro_bias_b_rad_s", "rad/s"),
+    );
+    ts.set_units(
+        dual_fc::B_ADA_OUTPUT,
+        ChannelUnits::new()
+            .field("altitude_m", "m")
+            .field("vertical_speed_m_s", "m/s")
+            .field("vertical_accel_m_s2", "m/s²"),
+    );
+    ts.set_units(
+        dual_fc::B_NAV_OUTPUT,
+        ChannelUnits::new()
+            .vector3("pos_n_m", "m")
+            .vector3("vel_n_m_s", "m/s")
+            .vector3("angvel_unbias_b_rad_s", "rad/s")
+            .vector3("acc_unbias_b_m_s2", "m/s²")
+            .euler("euler", "deg"),
+    );
+    ts.set_units(
+        dual_fc::B_AIRBRAKE_CONTROL,
+        ChannelUnits::new().field("extension", "-"),
+    );
+    ts.set_units(
+        dual_fc::B_AIRBRAKE_GUIDANCE,
+        ChannelUnits::new()
+            .field("predicted_apogee_m", "m")
+            .field("extension", "-"),
+    );
+    ts.set_units(
+        dual_fc::B_ATTITUDE_ESTIMATE,
+        ChannelUnits::new()
+            .euler("euler", "deg")
+            .vector3("gyro_bias_b_rad_s", "rad/s"),
+    );
+    ts.set_units(
+        dual_fc::VOTER_STATUS,
+        ChannelUnits::new()
+            .field("divergent", "-")
+            .field("altitude_diff_m", "m")
+            .field("position_diff_m", "m"),
+    );
+    ts.set_units(
+        sensors::IDEAL_IMU,
+        ChannelUnits::new()
+            .vector3("acc_m_s2", "m/s²")
+            .vector3("gyro_deg_s", "deg/s"),
+    );
+    ts.set_units(
+        sensors::IDEAL_IMU_CG,
+        ChannelUnits::new()
+            .vector3("acc_m_s2", "m/s²")
+            .vector3("gyro_deg_s", "deg/s"),
+    );
+    ts.set_units(
+        sensors::IDEAL_MAGNETOMETER,
+        ChannelUnits::new().vector3("", "G"),
+    );
+    ts.set_units(
+        sensors::IDEAL_NAV_OUTPUT,
+        ChannelUnits::new()
+            .vector3("pos_n_m", "m")
+            .vector3("vel_n_m_s", "m/s")
+            .vector3("angvel_unbias_b_rad_s", "rad/s")
+            .vector3("acc_unbias_b_m_s2", "m/s²")
+            .euler("euler", "deg"),
+    );
+    ts.set_units(
+        sensors::CAMERA_POINTING,
+        ChannelUnits::new()
+            .field("in_view", "-")
+            .field("off_axis_deg", "deg")
+            .field("image_h_deg", "deg")
+            .field("image_v_deg", "deg")
+            .field("range_m", "m")
+            .field("time_in_view_s", "s"),
+    );
+    ts.set_units(
+        actuators::SERVO_POWER,
+        ChannelUnits::new()
+            .components("current_a", &["1", "2", "3", "4"], "A")
+            .components("winding_temp_degc", &["1", "2", "3", "4"], "°C")
+            .components("derating", &["1", "2", "3", "4"], "-")
+            .field("power_w", "W"),
+    );
+    ts.set_units(
+        actuators::AIRBRAKE_POSITION,
+        ChannelUnits::new().field("extension", "-"),
+    );
+}
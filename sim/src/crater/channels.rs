@@ -1,43 +1,59 @@
-pub mod sim {
-    pub const SIM_EVENTS: &str = "/sim/events";
+use anyhow::Result;
+
+use crate::{
+    parameters::ParameterMap,
+    telemetry::{ChannelAccessPolicy, TelemetryService},
+};
+
+pub use super::channel_defs::{actuators, dual_fc, gnc, register_units, rocket, sensors, sim};
+
+/// Producers allowed on the safety relevant channels: ignition, FMM commands & transitions, ground
+/// commands, pyro and control surface commands
+pub fn flight_access_policy() -> ChannelAccessPolicy {
+    ChannelAccessPolicy::new()
+        .allow(
+            sim::SIM_EVENTS,
+            &[
+                "orchestrator",
+                "rocket",
+                "cosim",
+                "servo",
+                "fc_voter",
+                "soak_monitor",
+            ],
+        )
+        .allow(
+            gnc::GNC_EVENTS,
+            &["orchestrator", "rocket", "fsw", "fc_voter"],
+        )
+        .allow(sim::GROUND_UPLINK, &["gs_link", "orchestrator"])
+        .allow(gnc::UPLINK, &["gs_link", "radio_link"])
+        .allow(gnc::PYRO_COMMAND, &["fsw", "fc_voter"])
+        .allow(gnc::SERVO_COMMAND, &["openloop_control", "cosim", "fsw"])
+        .allow(gnc::AIRBRAKE_COMMAND, &["openloop_control", "cosim", "fsw"])
 }
 
-pub mod rocket {
-    pub const STATE: &str = "/rocket/state";
-    pub const ACTIONS: &str = "/rocket/actions";
-    pub const ACCEL: &str = "/rocket/accel";
-    pub const AERO_STATE: &str = "/rocket/aerostate";
-    pub const MASS_ROCKET: &str = "/rocket/mass/rocket";
-    pub const MASS_ENGINE: &str = "/rocket/mass/engine";
+/// Flight access policy, extended with the scenario player injecting the scripted sim & GNC events
+pub fn test_access_policy() -> ChannelAccessPolicy {
+    flight_access_policy()
+        .allow(sim::SIM_EVENTS, &["scenario_player"])
+        .allow(gnc::GNC_EVENTS, &["scenario_player"])
 }
 
-pub mod gnc {
-    pub const GNC_EVENTS: &str = "/gnc/events";
-    pub const ADA_OUTPUT: &str = "/gnc/ada";
-
-    pub const NAV_OUTPUT: &str = "/gnc/nav";
-    pub const SERVO_COMMAND: &str = "/gnc/contro/servo_command";
-}
-
-pub mod sensors {
-    pub const LIFTOFF_PIN: &str = "/sensors/liftoff_pin";
-
-    pub const IDEAL_STATIC_PRESSURE: &str = "/sensors/ideal/static_pressure";
-    pub const STATIC_PRESSURE: &str = "/sensors/static_pressure";
-
-    pub const IDEAL_GPS: &str = "/sensors/ideal/gps";
-    pub const GPS: &str = "/sensors/gps";
-
-    pub const IDEAL_IMU: &str = "/sensors/ideal/imu";
-    pub const IDEAL_IMU_CG: &str = "/sensors/ideal/imu_cg";
-    pub const IMU: &str = "/sensors/imu";
-
-    pub const IDEAL_MAGNETOMETER: &str = "/sensors/ideal/magnetometer";
-    pub const MAGNETOMETER: &str = "/sensors/magnetometer";
-
-    pub const IDEAL_NAV_OUTPUT: &str = "/sensors/ideal_nav";
-}
-
-pub mod actuators {
-    pub const IDEAL_SERVO_POSITION: &str = "/actuators/ideal_servo_position";
+/// Enforces the access policy if `sim.access_control` is enabled, as in flight & HIL
+/// configurations. Producers created by other nodes on those channels are rejected. Runs playing
+/// a scenario (`sim.orchestrator.scenario`) get the test policy, the others the flight one.
+pub fn configure_access(ts: &TelemetryService, params: &ParameterMap) -> Result<()> {
+    if params.get_param("sim.access_control")?.value_bool()? {
+        let scenario = params
+            .get_param("sim.orchestrator.scenario")?
+            .value_string()?;
+        ts.set_access_policy(if scenario.is_empty() {
+            flight_access_policy()
+        } else {
+            test_access_policy()
+        });
+    }
+
+    Ok(())
 }
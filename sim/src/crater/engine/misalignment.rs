@@ -0,0 +1,98 @@
+use anyhow::Result;
+use nalgebra::{UnitQuaternion, Vector3};
+
+use crate::parameters::ParameterMap;
+
+/// Thrust line deviating from the rocket centerline: tilted by the misalignment angles and
+/// applied at a nozzle offset laterally from the axis. Both produce pitch & yaw torques about
+/// the CG.
+#[derive(Debug, Clone)]
+pub struct ThrustMisalignment {
+    /// Rotation from the nominal to the actual thrust direction
+    q_misalignment: UnitQuaternion<f64>,
+    /// Axial position of the nozzle, from the nose
+    nozzle_x_m: f64,
+    /// Lateral offset of the nozzle from the centerline, along the body y & z axes
+    offset_yz_m: (f64, f64),
+}
+
+impl ThrustMisalignment {
+    pub fn new(pitch_rad: f64, yaw_rad: f64, nozzle_x_m: f64, offset_yz_m: (f64, f64)) -> Self {
+        Self {
+            q_misalignment: UnitQuaternion::from_euler_angles(0.0, pitch_rad, yaw_rad),
+            nozzle_x_m,
+            offset_yz_m,
+        }
+    }
+
+    /// Reads `sim.rocket.engine.misalignment`, with the angles in degrees
+    pub fn from_params(params: &ParameterMap) -> Result<Self> {
+        let sampled = |name: &str| -> Result<f64> {
+            Ok(params.get_param(name)?.value_randfloat()?.sampled())
+        };
+
+        Ok(Self::new(
+            sampled("pitch")?.to_radians(),
+            sampled("yaw")?.to_radians(),
+            params.get_param("nozzle_x")?.value_float()?,
+            (sampled("offset_y")?, sampled("offset_z")?),
+        ))
+    }
+
+    /// Actual thrust vector, from the nominal one in body axes
+    pub fn thrust_b(&self, nominal_thrust_b_n: &Vector3<f64>) -> Vector3<f64> {
+        self.q_misalignment.transform_vector(nominal_thrust_b_n)
+    }
+
+    /// Torque of the thrust `thrust_b_n` about the CG at `xcg_m` (from the nose)
+    pub fn moment_b(&self, thrust_b_n: &Vector3<f64>, xcg_m: &Vector3<f64>) -> Vector3<f64> {
+        let arm_b_m = Vector3::new(
+            xcg_m[0] - self.nozzle_x_m,
+            self.offset_yz_m.0,
+            self.offset_yz_m.1,
+        );
+
+        arm_b_m.cross(thrust_b_n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_thrust_misalignment() {
+        let thrust = Vector3::new(100.0, 0.0, 0.0);
+        let xcg = Vector3::new(0.55, 0.0, 0.0);
+
+        // Aligned thrust through the centerline: no torque
+        let aligned = ThrustMisalignment::new(0.0, 0.0, 1.0, (0.0, 0.0));
+        assert_eq!(aligned.thrust_b(&thrust), thrust);
+        assert_eq!(aligned.moment_b(&thrust, &xcg), Vector3::zeros());
+
+        // Nozzle 1 mm below the axis: pitch up
+        let offset = ThrustMisalignment::new(0.0, 0.0, 1.0, (0.0, 0.001));
+        assert_relative_eq!(
+            offset.moment_b(&thrust, &xcg),
+            Vector3::new(0.0, 0.1, 0.0),
+            epsilon = 1e-12
+        );
+
+        // Thrust tilted upwards at the tail: pitch down, with the axial thrust slightly reduced
+        let angle = 0.5f64.to_radians();
+        let tilted = ThrustMisalignment::new(angle, 0.0, 1.0, (0.0, 0.0));
+        let thrust_b = tilted.thrust_b(&thrust);
+        assert_relative_eq!(
+            thrust_b,
+            Vector3::new(100.0 * angle.cos(), 0.0, -100.0 * angle.sin()),
+            epsilon = 1e-12
+        );
+        assert_relative_eq!(
+            tilted.moment_b(&thrust_b, &xcg),
+            Vector3::new(0.0, -45.0 * angle.sin(), 0.0),
+            epsilon = 1e-12
+        );
+    }
+}
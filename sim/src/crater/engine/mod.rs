@@ -1,4 +1,5 @@
 pub mod engine;
+pub mod misalignment;
 mod simplerocketengine;
 mod tabulatedrocketengine;
 
@@ -1,6 +1,7 @@
 use super::engine::{RocketEngine, RocketEngineMassProperties};
 use crate::math::interp::{find_index, interpolate};
 
+use anyhow::{Result, anyhow};
 use nalgebra::{Matrix3, Vector3};
 use serde_json::Value;
 
@@ -85,6 +86,97 @@ impl TabRocketEngine {
 
         Ok(engine)
     }
+
+    /// Reads a RASP `.eng` motor file
+    pub fn from_eng(path: &str) -> Result<Self> {
+        Self::from_eng_str(&std::fs::read_to_string(path)?)
+            .map_err(|e| anyhow!("Error reading motor file '{path}': {e}"))
+    }
+
+    /// Parses a RASP `.eng` motor file: a header line (name, diameter & length [mm], delays,
+    /// propellant & total mass [kg], manufacturer) followed by the thrust curve, one time [s] &
+    /// thrust [N] pair per line. Comments start with ';'.
+    ///
+    /// As with the JSON tables, only the propellant mass is modeled: the casing is expected in
+    /// the dry mass of the rocket. The propellant burns proportionally to the delivered
+    /// impulse, as a solid cylinder of the motor size with its CG on the engine reference.
+    pub fn from_eng_str(eng: &str) -> Result<Self> {
+        let mut lines = eng
+            .lines()
+            .map(|l| l.split(';').next().unwrap_or_default().trim())
+            .filter(|l| !l.is_empty());
+
+        let header: Vec<&str> = lines
+            .next()
+            .ok_or_else(|| anyhow!("Missing header"))?
+            .split_whitespace()
+            .collect();
+        if header.len() < 7 {
+            return Err(anyhow!("Malformed header, expected 7 fields"));
+        }
+        let diameter_m = header[1].parse::<f64>()? / 1000.0;
+        let length_m = header[2].parse::<f64>()? / 1000.0;
+        let propellant_kg = header[4].parse::<f64>()?;
+
+        let mut thrust_time = vec![0.0];
+        let mut thrust_value = vec![0.0];
+        for line in lines {
+            let point: Vec<&str> = line.split_whitespace().collect();
+            let [t, thrust] = point[..] else {
+                return Err(anyhow!("Malformed thrust point: '{line}'"));
+            };
+            let (t, thrust) = (t.parse::<f64>()?, thrust.parse::<f64>()?);
+
+            if t <= *thrust_time.last().unwrap() {
+                // The curve may start at t = 0 already
+                if t == 0.0 && thrust_time.len() == 1 {
+                    thrust_value[0] = thrust;
+                    continue;
+                }
+                return Err(anyhow!("Thrust curve times must be strictly increasing"));
+            }
+            thrust_time.push(t);
+            thrust_value.push(thrust);
+        }
+        if thrust_time.len() < 2 {
+            return Err(anyhow!("Empty thrust curve"));
+        }
+
+        // Delivered impulse, by trapezoidal integration
+        let mut impulse = vec![0.0];
+        for i in 1..thrust_time.len() {
+            let dt = thrust_time[i] - thrust_time[i - 1];
+            impulse.push(impulse[i - 1] + 0.5 * (thrust_value[i] + thrust_value[i - 1]) * dt);
+        }
+        let total_impulse = *impulse.last().unwrap();
+
+        let mass_value: Vec<f64> = impulse
+            .iter()
+            .map(|i| propellant_kg * (1.0 - i / total_impulse))
+            .collect();
+
+        let r2 = (diameter_m / 2.0).powi(2);
+        let inertia_xx_value: Vec<f64> = mass_value.iter().map(|m| m * r2 / 2.0).collect();
+        let inertia_yy_value: Vec<f64> = mass_value
+            .iter()
+            .map(|m| m * (3.0 * r2 + length_m.powi(2)) / 12.0)
+            .collect();
+
+        Ok(TabRocketEngine {
+            xcg_time: thrust_time.clone(),
+            xcg_value: vec![0.0; thrust_time.len()],
+            mass_time: thrust_time.clone(),
+            mass_value,
+            inertia_xx_time: thrust_time.clone(),
+            inertia_xx_value,
+            inertia_yy_time: thrust_time.clone(),
+            inertia_yy_value: inertia_yy_value.clone(),
+            inertia_zz_time: thrust_time.clone(),
+            inertia_zz_value: inertia_yy_value,
+            thrust_time,
+            thrust_value,
+        })
+    }
 }
 
 impl RocketEngine for TabRocketEngine {
@@ -168,4 +260,29 @@ mod tests {
         assert_eq!(engine.inertia_zz_time, vec![0.0, 0.0, 0.0]);
         assert_eq!(engine.inertia_zz_value, vec![0.0, 1.0, 2.0]);
     }
+
+    #[test]
+    fn test_from_eng() {
+        let eng = "\
+            ; Test motor\n\
+            H100 38 200 0-5-10 0.2 0.4 Test\n\
+            0.1 100.0 ; ramp up\n\
+            1.0 100.0\n\
+            1.1 0.0\n";
+
+        let engine = TabRocketEngine::from_eng_str(eng).unwrap();
+
+        assert_eq!(engine.thrust_time, vec![0.0, 0.1, 1.0, 1.1]);
+        assert_eq!(engine.thrust_value, vec![0.0, 100.0, 100.0, 0.0]);
+
+        // 100 N s in total, 5 N s after the ramp up
+        let mass = engine.mass(0.1);
+        assert!((mass.mass_kg - 0.2 * 0.95).abs() < 1e-12);
+        assert!((mass.mass_dot_kg_s + 0.2).abs() < 1e-12);
+        assert_eq!(engine.mass(1.1).mass_kg, 0.0);
+        assert!((engine.inertia_xx_value[0] - 0.2 * 0.019f64.powi(2) / 2.0).abs() < 1e-12);
+
+        assert!(TabRocketEngine::from_eng_str("H100 38 200 0 0.2 0.4 Test\n").is_err());
+        assert!(TabRocketEngine::from_eng_str("H100 38 200 0 0.2 0.4 Test\n1.0\n").is_err());
+    }
 }
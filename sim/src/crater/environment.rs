@@ -0,0 +1,127 @@
+use anyhow::Result;
+use chrono::TimeDelta;
+use map_3d::{Ellipsoid, geodetic2ned, ned2geodetic};
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::time::{Clock, Timestamp},
+    crater::channels,
+    nodes::{Node, NodeContext, StepResult},
+    parameters::ParameterMap,
+    telemetry::TelemetrySender,
+};
+
+/// Launch site, the origin of the NED frame
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LaunchSite {
+    pub latitude_deg: f64,
+    pub longitude_deg: f64,
+    /// WGS84 altitude [m]
+    pub altitude_m: f64,
+}
+
+impl LaunchSite {
+    /// Reads the launch site from `sim.environment.launch_site` of the root parameter map
+    pub fn from_params(params: &ParameterMap) -> Result<Self> {
+        let site = params.get_map("sim.environment.launch_site")?;
+        Ok(Self {
+            latitude_deg: site.get_param("latitude")?.value_float()?,
+            longitude_deg: site.get_param("longitude")?.value_float()?,
+            altitude_m: site.get_param("altitude")?.value_float()?,
+        })
+    }
+
+    /// Geodetic coordinates of a point in the NED frame [rad, rad, m]
+    pub fn geodetic(&self, pos_n_m: &Vector3<f64>) -> (f64, f64, f64) {
+        ned2geodetic(
+            pos_n_m[0],
+            pos_n_m[1],
+            pos_n_m[2],
+            self.latitude_deg.to_radians(),
+            self.longitude_deg.to_radians(),
+            self.altitude_m,
+            Ellipsoid::WGS84,
+        )
+    }
+
+    /// Point in the NED frame of geodetic coordinates [deg, deg, m]
+    pub fn ned(&self, lat_deg: f64, lon_deg: f64, alt_m: f64) -> Vector3<f64> {
+        let (n, e, d) = geodetic2ned(
+            lat_deg.to_radians(),
+            lon_deg.to_radians(),
+            alt_m,
+            self.latitude_deg.to_radians(),
+            self.longitude_deg.to_radians(),
+            self.altitude_m,
+            Ellipsoid::WGS84,
+        );
+        Vector3::new(n, e, d)
+    }
+
+    /// (latitude, longitude) of a point on the ground [deg]
+    pub fn lat_lon_deg(&self, ne_m: [f64; 2]) -> (f64, f64) {
+        let (lat, lon, _) = self.geodetic(&Vector3::new(ne_m[0], ne_m[1], 0.0));
+        (lat.to_degrees(), lon.to_degrees())
+    }
+}
+
+/// Publishes the launch site, on a sticky channel so that the nodes & loggers created after it
+/// receive it too
+pub struct Environment {
+    _tx_launch_site: TelemetrySender<LaunchSite>,
+}
+
+impl Environment {
+    pub fn new(ctx: NodeContext) -> Result<Self> {
+        let site = LaunchSite::from_params(ctx.parameters())?;
+
+        Ok(Self {
+            _tx_launch_site: ctx.telemetry().publish_sticky(
+                channels::sim::LAUNCH_SITE,
+                Timestamp::from_micros(0),
+                site,
+            )?,
+        })
+    }
+}
+
+impl Node for Environment {
+    fn step(&mut self, _: usize, _: TimeDelta, _: &dyn Clock) -> Result<StepResult> {
+        Ok(StepResult::Continue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_launch_site_geodetic() {
+        let site = LaunchSite {
+            latitude_deg: 41.8,
+            longitude_deg: 14.0,
+            altitude_m: 1400.0,
+        };
+
+        let (lat, lon, alt) = site.geodetic(&Vector3::zeros());
+        assert_relative_eq!(lat.to_degrees(), 41.8, epsilon = 1e-9);
+        assert_relative_eq!(lon.to_degrees(), 14.0, epsilon = 1e-9);
+        assert_relative_eq!(alt, 1400.0, epsilon = 1e-6);
+
+        // 1 km north & east, 100 m up
+        let (lat, lon, alt) = site.geodetic(&Vector3::new(1000.0, 1000.0, -100.0));
+        assert_relative_eq!(lat.to_degrees() - 41.8, 1000.0 / 111_090.0, epsilon = 1e-4);
+        assert_relative_eq!(lon.to_degrees() - 14.0, 1000.0 / 83_000.0, epsilon = 1e-4);
+        assert_relative_eq!(alt, 1500.0, epsilon = 0.5);
+
+        let (lat_deg, lon_deg) = site.lat_lon_deg([1000.0, 1000.0]);
+        assert_relative_eq!(lat_deg, lat.to_degrees(), epsilon = 1e-6);
+        assert_relative_eq!(lon_deg, lon.to_degrees(), epsilon = 1e-6);
+
+        let ned = site.ned(lat.to_degrees(), lon.to_degrees(), alt);
+        assert_relative_eq!(ned, Vector3::new(1000.0, 1000.0, -100.0), epsilon = 1e-3);
+    }
+}
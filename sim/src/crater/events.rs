@@ -1,4 +1,6 @@
-use crater_gnc::mav_crater::ComponentId;
+use crater_gnc::{datatypes::recovery::PyroChannel, mav_crater::ComponentId};
+
+use super::{actuators::thermal::ServoThermalState, gnc::dual_fc::FcUnit};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum SimEvent {
@@ -8,6 +10,45 @@ pub enum SimEvent {
         target: String,
     },
     StartEngine,
+    /// A servo changed thermal condition, eg. the control demands exceed what it can sustain
+    ServoThermal {
+        servo: usize,
+        state: ServoThermalState,
+    },
+    /// The outputs of the redundant flight computers disagree
+    FcDivergence {
+        altitude_diff_m: f64,
+        position_diff_m: f64,
+    },
+    /// The active flight computer stopped responding, the other one took over
+    FcFailover {
+        from: FcUnit,
+        to: FcUnit,
+    },
+    /// A soak drift monitor exceeded its limit
+    SoakDrift {
+        monitor: String,
+        value: f64,
+        limit: f64,
+    },
+    /// Scripted release of the part `name` configured in `sim.rocket.ejections`
+    EjectMass {
+        name: String,
+    },
+    /// Scripted loss of the samples of `sensor` ("gps", "imu", "magnetometer", "pressure")
+    SensorDropout {
+        sensor: String,
+        duration_s: f64,
+    },
+    /// The aft rail button left the launch rail
+    RailExit {
+        velocity_m_s: f64,
+    },
+    /// The static margin dropped below the configured minimum [cal]
+    LowStaticMargin {
+        static_margin_cal: f64,
+        threshold_cal: f64,
+    },
 }
 
 pub type GncEvent = crater_gnc::events::Event;
@@ -17,6 +58,8 @@ pub enum Event {
     Step,
     Sim(SimEvent),
     Gnc(GncEvent, ComponentId),
+    /// A recovery pyro channel was fired by the flight software
+    Pyro(PyroChannel),
 }
 
 impl From<GncEventItem> for Event {
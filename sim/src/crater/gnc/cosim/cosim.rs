@@ -0,0 +1,162 @@
+use std::{
+    io::{BufRead, BufReader, BufWriter, Write},
+    net::{TcpListener, TcpStream},
+    time::Instant,
+};
+
+use anyhow::{Context, Result, anyhow};
+use chrono::TimeDelta;
+use crater_gnc::datatypes::{
+    actuators::ServoCommand,
+    sensors::{ImuSensorSample, MagnetometerSensorSample, PressureSensorSample},
+};
+use log::info;
+
+use super::protocol::{CosimAck, CosimImu, CosimInterface, CosimStep, PROTOCOL_VERSION};
+use crate::{
+    core::time::{Clock, Timestamp},
+    crater::{channels, events::SimEvent, gnc::ServoPosition},
+    nodes::{Node, NodeContext, StepResult},
+    telemetry::{TelemetryReceiver, TelemetrySender, Timestamped},
+    utils::capacity::Capacity::Unbounded,
+};
+
+/// Timing of a lockstep step with the external autopilot
+#[derive(Debug, Clone, Default)]
+pub struct CosimLinkStatus {
+    /// Wall time from sending the step to receiving its ack
+    pub round_trip_ms: f64,
+}
+
+/// Flies an external autopilot against the simulated rocket, in lockstep, over a TCP socket.
+/// Replaces the built-in flight software & control nodes.
+pub struct CosimBridge {
+    reader: BufReader<TcpStream>,
+    writer: BufWriter<TcpStream>,
+
+    rx_imu: TelemetryReceiver<ImuSensorSample>,
+    rx_pressure: TelemetryReceiver<PressureSensorSample>,
+    rx_mag: TelemetryReceiver<MagnetometerSensorSample>,
+
+    tx_servo_cmd: TelemetrySender<ServoPosition>,
+    tx_sim_event: TelemetrySender<SimEvent>,
+    tx_link: TelemetrySender<CosimLinkStatus>,
+    engine_started: bool,
+
+    test: Option<String>,
+}
+
+impl CosimBridge {
+    pub fn new(ctx: NodeContext) -> Result<Self> {
+        let address = ctx
+            .parameters()
+            .get_param("sim.cosim.address")?
+            .value_string()?;
+        let test = ctx
+            .parameters()
+            .get_param("sim.cosim.test")?
+            .value_string()?;
+
+        let listener = TcpListener::bind(&address).context(format!("address={address}"))?;
+
+        info!("Waiting for co-simulation client on {address}...");
+        let (stream, client) = listener.accept()?;
+        stream.set_nodelay(true)?;
+        info!("Co-simulation client connected from {client}");
+
+        Ok(Self {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: BufWriter::new(stream),
+            rx_imu: ctx
+                .telemetry()
+                .subscribe(channels::sensors::IDEAL_IMU, Unbounded)?,
+            rx_pressure: ctx
+                .telemetry()
+                .subscribe(channels::sensors::IDEAL_STATIC_PRESSURE, Unbounded)?,
+            rx_mag: ctx
+                .telemetry()
+                .subscribe(channels::sensors::IDEAL_MAGNETOMETER, Unbounded)?,
+            tx_servo_cmd: ctx.telemetry().publish(channels::gnc::SERVO_COMMAND)?,
+            tx_sim_event: ctx.telemetry().publish_mp(channels::sim::SIM_EVENTS)?,
+            tx_link: ctx.telemetry().publish(channels::sim::COSIM_LINK)?,
+            engine_started: false,
+            test: (!test.is_empty()).then_some(test),
+        })
+    }
+
+    fn latest<T>(rx: &TelemetryReceiver<T>) -> Option<T> {
+        let mut latest = None;
+        while let Ok(Timestamped(_, v)) = rx.try_recv() {
+            latest = Some(v);
+        }
+        latest
+    }
+}
+
+impl Node for CosimBridge {
+    fn step(&mut self, i: usize, _: TimeDelta, clock: &dyn Clock) -> Result<StepResult> {
+        let t = Timestamp::now(clock);
+
+        let msg = CosimStep {
+            version: PROTOCOL_VERSION,
+            step: i as u64,
+            t_us: t.monotonic.elapsed().num_microseconds().unwrap_or(i64::MAX),
+            imu: Self::latest(&self.rx_imu).map(|imu| CosimImu {
+                accel_m_s2: imu.accel_m_s2.into(),
+                angvel_rad_s: imu.angvel_rad_s.into(),
+            }),
+            static_pressure_pa: Self::latest(&self.rx_pressure).map(|p| p.pressure_pa),
+            mag_field_b_gauss: Self::latest(&self.rx_mag).map(|m| m.mag_field_b_gauss.into()),
+            test: self.test.clone(),
+            interfaces: if i == 0 {
+                vec![
+                    CosimInterface::of::<ImuSensorSample>(),
+                    CosimInterface::of::<PressureSensorSample>(),
+                    CosimInterface::of::<MagnetometerSensorSample>(),
+                ]
+            } else {
+                vec![]
+            },
+        };
+
+        let sent = Instant::now();
+        serde_json::to_writer(&mut self.writer, &msg)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()?;
+
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(anyhow!("Co-simulation client disconnected"));
+        }
+        let round_trip = sent.elapsed();
+
+        let ack: CosimAck = serde_json::from_str(&line).context(format!("ack={line}"))?;
+        if ack.step != msg.step {
+            return Err(anyhow!(
+                "Co-simulation out of sync: expected ack for step {}, got {}",
+                msg.step,
+                ack.step
+            ));
+        }
+        CosimInterface::check::<ServoCommand>(&ack.interfaces)?;
+
+        self.tx_servo_cmd.send(t, ack.servo_cmd_rad.into());
+        self.tx_link.send(
+            t,
+            CosimLinkStatus {
+                round_trip_ms: round_trip.as_secs_f64() * 1000.0,
+            },
+        );
+
+        if ack.start_engine && !self.engine_started {
+            self.tx_sim_event.send(t, SimEvent::StartEngine);
+            self.engine_started = true;
+        }
+
+        if ack.stop {
+            Ok(StepResult::Stop)
+        } else {
+            Ok(StepResult::Continue)
+        }
+    }
+}
@@ -0,0 +1,4 @@
+mod cosim;
+pub mod protocol;
+
+pub use cosim::{CosimBridge, CosimLinkStatus};
@@ -0,0 +1,113 @@
+//! Lockstep co-simulation protocol.
+//!
+//! Messages are exchanged as newline-delimited JSON over a TCP socket. At every simulation
+//! step the simulator sends a [`CosimStep`] with the latest sensor samples, then blocks until
+//! the external autopilot replies with a [`CosimAck`] carrying the actuator commands for the
+//! same step. The simulation does not advance until the ack is received, so the co-simulation
+//! is deterministic regardless of how long the autopilot takes to compute its outputs.
+//!
+//! The first step & ack also declare the versions of the interfaces each side sends, which the
+//! other side checks before decoding them.
+
+use crater_gnc::datatypes::version::{InterfaceVersion, VersionMismatch, Versioned};
+use serde::{Deserialize, Serialize};
+
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Version of an interface struct, as declared over the link
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CosimInterface {
+    pub name: String,
+    pub major: u16,
+    pub minor: u16,
+}
+
+impl CosimInterface {
+    pub fn of<T: Versioned>() -> Self {
+        Self {
+            name: T::NAME.to_string(),
+            major: T::VERSION.major,
+            minor: T::VERSION.minor,
+        }
+    }
+
+    /// Checks the version declared for `T` among `interfaces`, if any
+    pub fn check<T: Versioned>(interfaces: &[CosimInterface]) -> Result<(), VersionMismatch> {
+        match interfaces.iter().find(|i| i.name == T::NAME) {
+            Some(i) => T::check_version(InterfaceVersion::new(i.major, i.minor)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CosimImu {
+    pub accel_m_s2: [f32; 3],
+    pub angvel_rad_s: [f32; 3],
+}
+
+/// Simulator -> autopilot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CosimStep {
+    pub version: u32,
+    pub step: u64,
+    pub t_us: i64,
+
+    /// Sensor samples produced during this step, if any
+    pub imu: Option<CosimImu>,
+    pub static_pressure_pa: Option<f32>,
+    pub mag_field_b_gauss: Option<[f32; 3]>,
+
+    /// Bench test the autopilot must run instead of its flight logic, when sequenced by the HIL
+    /// test framework (eg. "sensor_sanity", "actuator_sweep")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub test: Option<String>,
+
+    /// Versions of the sensor samples, declared in the first step only
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub interfaces: Vec<CosimInterface>,
+}
+
+/// Autopilot -> simulator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CosimAck {
+    /// Must match the step being acknowledged
+    pub step: u64,
+
+    /// Commanded fin deflections
+    pub servo_cmd_rad: [f64; 4],
+
+    /// Ignite the engine. Without the built-in flight software, the external autopilot is
+    /// responsible for the launch sequence.
+    #[serde(default)]
+    pub start_engine: bool,
+
+    /// Request the simulation to stop after this step
+    #[serde(default)]
+    pub stop: bool,
+
+    /// Versions of the actuator commands, declared in the first ack
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub interfaces: Vec<CosimInterface>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crater_gnc::datatypes::actuators::ServoCommand;
+
+    use super::*;
+
+    #[test]
+    fn test_interface_check() {
+        let servo = CosimInterface::of::<ServoCommand>();
+        assert!(CosimInterface::check::<ServoCommand>(&[servo.clone()]).is_ok());
+        // Not declared
+        assert!(CosimInterface::check::<ServoCommand>(&[]).is_ok());
+
+        let newer = CosimInterface {
+            major: servo.major + 1,
+            ..servo
+        };
+        assert!(CosimInterface::check::<ServoCommand>(&[newer]).is_err());
+    }
+}
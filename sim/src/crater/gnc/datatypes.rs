@@ -1,3 +1,4 @@
+use crater_gnc::datatypes::actuators::{AirbrakeCommand, ServoCommand};
 use nalgebra::{Matrix4, Vector4, matrix};
 
 /// From fin deflections to mixed deflections
@@ -52,6 +53,26 @@ impl From<[f64; 4]> for ServoPosition {
     }
 }
 
+/// Airbrake extension, from 0 (retracted) to 1 (fully deployed)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AirbrakePosition {
+    pub extension: f64,
+}
+
+impl From<AirbrakeCommand> for AirbrakePosition {
+    fn from(cmd: AirbrakeCommand) -> Self {
+        AirbrakePosition {
+            extension: cmd.extension as f64,
+        }
+    }
+}
+
+impl From<ServoCommand> for ServoPosition {
+    fn from(cmd: ServoCommand) -> Self {
+        cmd.pos_rad.map(|p| p as f64).into()
+    }
+}
+
 /// Fin mixing
 /// ```txt
 ///      Yaw                   Pitch                   Roll                 Squeeze          
@@ -0,0 +1,364 @@
+use anyhow::Result;
+use chrono::TimeDelta;
+use crater_gnc::{
+    components::ada::AdaResult,
+    datatypes::{gnc::NavigationOutput, recovery::PyroCommand},
+};
+
+use crate::{
+    core::time::{Clock, Timestamp},
+    crater::{
+        channels,
+        events::{GncEventItem, SimEvent},
+        gnc::fsw::FswOutputs,
+    },
+    nodes::{Node, NodeContext, StepResult},
+    parameters::ParameterMap,
+    telemetry::{TelemetryReceiver, TelemetrySender, Timestamped},
+    utils::capacity::Capacity::Unbounded,
+};
+
+/// One of the two flight computers of the dual-redundant architecture
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FcUnit {
+    A,
+    B,
+}
+
+impl FcUnit {
+    pub fn outputs(&self) -> FswOutputs {
+        match self {
+            FcUnit::A => FswOutputs {
+                events: channels::dual_fc::A_EVENTS,
+                ada: channels::dual_fc::A_ADA_OUTPUT,
+                nav: channels::dual_fc::A_NAV_OUTPUT,
+                recovery: channels::dual_fc::A_RECOVERY_STATUS,
+                pyro: channels::dual_fc::A_PYRO_COMMAND,
+                downlink: channels::dual_fc::A_DOWNLINK,
+                command_ack: channels::dual_fc::A_COMMAND_ACK,
+                log_transfer: channels::dual_fc::A_LOG_TRANSFER,
+                event_log: channels::dual_fc::A_EVENT_LOG,
+                component_health: channels::dual_fc::A_COMPONENT_HEALTH,
+                burst_capture: channels::dual_fc::A_BURST_CAPTURE,
+                roll_control: channels::dual_fc::A_ROLL_CONTROL,
+                servo_command: None,
+                airbrake_control: channels::dual_fc::A_AIRBRAKE_CONTROL,
+                airbrake_command: None,
+                airbrake_guidance: channels::dual_fc::A_AIRBRAKE_GUIDANCE,
+                attitude: channels::dual_fc::A_ATTITUDE_ESTIMATE,
+            },
+            FcUnit::B => FswOutputs {
+                events: channels::dual_fc::B_EVENTS,
+                ada: channels::dual_fc::B_ADA_OUTPUT,
+                nav: channels::dual_fc::B_NAV_OUTPUT,
+                recovery: channels::dual_fc::B_RECOVERY_STATUS,
+                pyro: channels::dual_fc::B_PYRO_COMMAND,
+                downlink: channels::dual_fc::B_DOWNLINK,
+                command_ack: channels::dual_fc::B_COMMAND_ACK,
+                log_transfer: channels::dual_fc::B_LOG_TRANSFER,
+                event_log: channels::dual_fc::B_EVENT_LOG,
+                component_health: channels::dual_fc::B_COMPONENT_HEALTH,
+                burst_capture: channels::dual_fc::B_BURST_CAPTURE,
+                roll_control: channels::dual_fc::B_ROLL_CONTROL,
+                servo_command: None,
+                airbrake_control: channels::dual_fc::B_AIRBRAKE_CONTROL,
+                airbrake_command: None,
+                airbrake_guidance: channels::dual_fc::B_AIRBRAKE_GUIDANCE,
+                attitude: channels::dual_fc::B_ATTITUDE_ESTIMATE,
+            },
+        }
+    }
+
+    /// Suffix of the per-unit parameters
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            FcUnit::A => "a",
+            FcUnit::B => "b",
+        }
+    }
+
+    fn other(&self) -> FcUnit {
+        match self {
+            FcUnit::A => FcUnit::B,
+            FcUnit::B => FcUnit::A,
+        }
+    }
+}
+
+/// Comparison of the two flight computers, published by the voter
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoterStatus {
+    /// Flight computer whose outputs are forwarded to the rocket
+    pub active: FcUnit,
+    /// The outputs of the two flight computers disagree
+    pub divergent: bool,
+    pub altitude_diff_m: f64,
+    pub position_diff_m: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct VoterParams {
+    /// ADA altitude difference above which the outputs are considered divergent
+    pub altitude_tolerance_m: f64,
+    /// Navigation position difference above which the outputs are considered divergent
+    pub position_tolerance_m: f64,
+    /// Consecutive divergent comparisons needed to flag a divergence
+    pub divergence_confirmations: u32,
+    /// The active flight computer is considered failed when silent for this long
+    pub failover_timeout_s: f64,
+}
+
+impl VoterParams {
+    pub fn from_params(params: &ParameterMap) -> Result<Self> {
+        Ok(Self {
+            altitude_tolerance_m: params.get_param("altitude_tolerance")?.value_float()?,
+            position_tolerance_m: params.get_param("position_tolerance")?.value_float()?,
+            divergence_confirmations: params.get_param("divergence_confirmations")?.value_int()?
+                as u32,
+            failover_timeout_s: params.get_param("failover_timeout")?.value_float()?,
+        })
+    }
+}
+
+/// Outputs of a flight computer, received by the voter
+#[derive(Debug)]
+struct FcOutputs {
+    rx_events: TelemetryReceiver<GncEventItem>,
+    rx_ada: TelemetryReceiver<AdaResult>,
+    rx_nav: TelemetryReceiver<NavigationOutput>,
+    rx_pyro: TelemetryReceiver<PyroCommand>,
+
+    /// Outputs received since the last step
+    events: Vec<Timestamped<GncEventItem>>,
+    ada: Vec<Timestamped<AdaResult>>,
+    nav: Vec<Timestamped<NavigationOutput>>,
+    pyro: Vec<Timestamped<PyroCommand>>,
+
+    /// Latest outputs, for the comparison
+    last_ada: Option<AdaResult>,
+    last_nav: Option<NavigationOutput>,
+    /// Last time any output was received
+    last_output_s: Option<f64>,
+}
+
+impl FcOutputs {
+    fn new(ctx: &NodeContext, unit: FcUnit) -> Result<Self> {
+        let outputs = unit.outputs();
+
+        Ok(Self {
+            rx_events: ctx.telemetry().subscribe_mp(outputs.events, Unbounded)?,
+            rx_ada: ctx.telemetry().subscribe(outputs.ada, Unbounded)?,
+            rx_nav: ctx.telemetry().subscribe(outputs.nav, Unbounded)?,
+            rx_pyro: ctx.telemetry().subscribe_mp(outputs.pyro, Unbounded)?,
+            events: vec![],
+            ada: vec![],
+            nav: vec![],
+            pyro: vec![],
+            last_ada: None,
+            last_nav: None,
+            last_output_s: None,
+        })
+    }
+
+    /// Collects the outputs produced since the last step. Returns whether there were any.
+    fn receive(&mut self, t_s: f64) -> bool {
+        self.events.clear();
+        self.ada.clear();
+        self.nav.clear();
+        self.pyro.clear();
+
+        while let Ok(ev) = self.rx_events.try_recv() {
+            self.events.push(ev);
+        }
+        while let Ok(ada) = self.rx_ada.try_recv() {
+            self.last_ada = Some(ada.1.clone());
+            self.ada.push(ada);
+        }
+        while let Ok(nav) = self.rx_nav.try_recv() {
+            self.last_nav = Some(nav.1.clone());
+            self.nav.push(nav);
+        }
+        while let Ok(pyro) = self.rx_pyro.try_recv() {
+            self.pyro.push(pyro);
+        }
+
+        let received = !(self.events.is_empty()
+            && self.ada.is_empty()
+            && self.nav.is_empty()
+            && self.pyro.is_empty());
+        if received {
+            self.last_output_s = Some(t_s);
+        }
+        received
+    }
+
+    /// No outputs received for longer than `timeout_s`, or ever
+    fn is_silent(&self, t_s: f64, timeout_s: f64) -> bool {
+        self.last_output_s.is_none_or(|last| t_s - last > timeout_s)
+    }
+}
+
+/// Comparator of a dual-redundant flight computer architecture.
+///
+/// Both flight computers are fed the same sensor streams. The outputs of the active one,
+/// including its pyro commands, are forwarded to the rocket, while the ADA & navigation outputs
+/// of the two are compared to flag divergences. With two units a divergence cannot be
+/// attributed, so it is only reported: the voter fails over to the other unit when the active
+/// one stops producing outputs.
+#[derive(Debug)]
+pub struct FcVoter {
+    params: VoterParams,
+    units: [FcOutputs; 2],
+    active: FcUnit,
+
+    tx_events: TelemetrySender<GncEventItem>,
+    tx_ada: TelemetrySender<AdaResult>,
+    tx_nav: TelemetrySender<NavigationOutput>,
+    tx_pyro: TelemetrySender<PyroCommand>,
+    tx_status: TelemetrySender<VoterStatus>,
+    tx_sim_event: TelemetrySender<SimEvent>,
+
+    divergent_count: u32,
+    divergent: bool,
+}
+
+impl FcVoter {
+    pub fn new(ctx: NodeContext) -> Result<Self> {
+        let params = VoterParams::from_params(ctx.parameters().get_map("sim.rocket.gnc.dual_fc")?)?;
+
+        Ok(Self {
+            params,
+            units: [
+                FcOutputs::new(&ctx, FcUnit::A)?,
+                FcOutputs::new(&ctx, FcUnit::B)?,
+            ],
+            active: FcUnit::A,
+            tx_events: ctx.telemetry().publish_mp(channels::gnc::GNC_EVENTS)?,
+            tx_ada: ctx.telemetry().publish(channels::gnc::ADA_OUTPUT)?,
+            tx_nav: ctx.telemetry().publish(channels::gnc::NAV_OUTPUT)?,
+            tx_pyro: ctx.telemetry().publish_mp(channels::gnc::PYRO_COMMAND)?,
+            tx_status: ctx.telemetry().publish(channels::dual_fc::VOTER_STATUS)?,
+            tx_sim_event: ctx.telemetry().publish_mp(channels::sim::SIM_EVENTS)?,
+            divergent_count: 0,
+            divergent: false,
+        })
+    }
+
+    fn unit(&mut self, unit: FcUnit) -> &mut FcOutputs {
+        match unit {
+            FcUnit::A => &mut self.units[0],
+            FcUnit::B => &mut self.units[1],
+        }
+    }
+
+    /// Switches to the other unit if the active one has been silent for too long
+    fn check_failover(&mut self, t: Timestamp, t_s: f64) {
+        let timeout_s = self.params.failover_timeout_s;
+
+        let standby = self.active.other();
+        if self.unit(self.active).is_silent(t_s, timeout_s)
+            && !self.unit(standby).is_silent(t_s, timeout_s)
+        {
+            self.tx_sim_event.send(
+                t,
+                SimEvent::FcFailover {
+                    from: self.active,
+                    to: standby,
+                },
+            );
+            self.active = standby;
+        }
+    }
+
+    /// Compares the latest outputs of the two units, while both are running
+    fn compare(&mut self, t: Timestamp, t_s: f64) -> (f64, f64) {
+        let [a, b] = &self.units;
+        let timeout_s = self.params.failover_timeout_s;
+        if a.is_silent(t_s, timeout_s) || b.is_silent(t_s, timeout_s) {
+            self.divergent_count = 0;
+            self.divergent = false;
+            return (0.0, 0.0);
+        }
+
+        let altitude_diff_m = match (&a.last_ada, &b.last_ada) {
+            (Some(a), Some(b)) => (a.altitude_m - b.altitude_m).abs() as f64,
+            _ => 0.0,
+        };
+        let position_diff_m = match (&a.last_nav, &b.last_nav) {
+            (Some(a), Some(b)) => (a.pos_n_m - b.pos_n_m).norm() as f64,
+            _ => 0.0,
+        };
+
+        let divergent = altitude_diff_m > self.params.altitude_tolerance_m
+            || position_diff_m > self.params.position_tolerance_m;
+        self.divergent_count = if divergent {
+            self.divergent_count.saturating_add(1)
+        } else {
+            0
+        };
+
+        let flagged = self.divergent_count >= self.params.divergence_confirmations;
+        if flagged && !self.divergent {
+            self.tx_sim_event.send(
+                t,
+                SimEvent::FcDivergence {
+                    altitude_diff_m,
+                    position_diff_m,
+                },
+            );
+        }
+        self.divergent = flagged;
+
+        (altitude_diff_m, position_diff_m)
+    }
+}
+
+impl Node for FcVoter {
+    fn step(&mut self, _: usize, _: TimeDelta, clock: &dyn Clock) -> Result<StepResult> {
+        let t = Timestamp::now(clock);
+        let t_s = t.monotonic.elapsed_seconds_f64();
+
+        let active_received = self.unit(self.active).receive(t_s);
+        self.unit(self.active.other()).receive(t_s);
+
+        if !active_received {
+            self.check_failover(t, t_s);
+        }
+
+        let (altitude_diff_m, position_diff_m) = self.compare(t, t_s);
+
+        // Forward the outputs of the active unit. Those of the standby unit are dropped, so that
+        // a failover does not replay stale commands.
+        let active = self.active;
+        let unit = self.unit(active);
+        let events: Vec<_> = unit.events.drain(..).collect();
+        let ada: Vec<_> = unit.ada.drain(..).collect();
+        let nav: Vec<_> = unit.nav.drain(..).collect();
+        let pyro: Vec<_> = unit.pyro.drain(..).collect();
+
+        for Timestamped(t_ev, ev) in events {
+            self.tx_events.send(t_ev, ev);
+        }
+        for Timestamped(t_ada, ada) in ada {
+            self.tx_ada.send(t_ada, ada);
+        }
+        for Timestamped(t_nav, nav) in nav {
+            self.tx_nav.send(t_nav, nav);
+        }
+        for Timestamped(t_pyro, pyro) in pyro {
+            self.tx_pyro.send(t_pyro, pyro);
+        }
+
+        self.tx_status.send(
+            t,
+            VoterStatus {
+                active: self.active,
+                divergent: self.divergent,
+                altitude_diff_m,
+                position_diff_m,
+            },
+        );
+
+        Ok(StepResult::Continue)
+    }
+}
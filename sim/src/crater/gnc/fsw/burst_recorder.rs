@@ -0,0 +1,81 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::{Context, Result, anyhow};
+use chrono::TimeDelta;
+use crater_gnc::{MavHeader, mav_crater::MavMessage, write_v2_msg};
+
+use crate::{
+    core::time::Clock,
+    crater::channels,
+    nodes::{Node, NodeContext, StepResult},
+    telemetry::{TelemetryReceiver, Timestamped},
+    utils::capacity::Capacity,
+};
+
+/// Onboard recorder of the burst captures of the flight software. The captured messages are
+/// written as MAVLink frames, like the onboard log, to the file set by
+/// `sim.rocket.gnc.burst_log`. Nothing is written if the path is empty.
+pub struct BurstRecorder {
+    rx_burst: TelemetryReceiver<MavMessage>,
+    file: Option<BufWriter<File>>,
+    sequence: u8,
+}
+
+impl BurstRecorder {
+    pub fn new(ctx: NodeContext) -> Result<Self> {
+        let path = ctx
+            .parameters()
+            .get_param("sim.rocket.gnc.burst_log")?
+            .value_string()?;
+
+        let file = if path.is_empty() {
+            None
+        } else {
+            let file = File::create(Path::new(&path))
+                .with_context(|| format!("Cannot create the burst log {path}"))?;
+            Some(BufWriter::new(file))
+        };
+
+        Ok(Self {
+            rx_burst: ctx
+                .telemetry()
+                .subscribe(channels::gnc::BURST_CAPTURE, Capacity::Unbounded)?,
+            file,
+            sequence: 0,
+        })
+    }
+}
+
+impl Node for BurstRecorder {
+    fn step(&mut self, _: usize, _: TimeDelta, _: &dyn Clock) -> Result<StepResult> {
+        let Some(file) = self.file.as_mut() else {
+            // Drained anyway, so that the channel does not grow
+            while self.rx_burst.try_recv().is_ok() {}
+            return Ok(StepResult::Continue);
+        };
+
+        let mut frames = vec![];
+        while let Ok(Timestamped(_, msg)) = self.rx_burst.try_recv() {
+            let header = MavHeader {
+                system_id: 1,
+                component_id: 1,
+                sequence: self.sequence,
+            };
+            self.sequence = self.sequence.wrapping_add(1);
+
+            write_v2_msg(&mut frames, header, &msg)
+                .map_err(|e| anyhow!("Error encoding a MAVLink message: {e:?}"))?;
+        }
+
+        if !frames.is_empty() {
+            file.write_all(&frames)?;
+            file.flush()?;
+        }
+
+        Ok(StepResult::Continue)
+    }
+}
@@ -1,67 +1,312 @@
 use chrono::TimeDelta;
 use crater_gnc::{
-    DurationU64, InstantU64,
+    Duration, DurationU64, InstantU64,
+    common::pid::PidGains,
     component::StepData,
-    components::{ada::AdaHarness, fmm::FmmHarness, navigation::NavigationHarness},
+    component_loop::SupervisorHarness,
+    components::{
+        ada::AdaHarness, airbrake_guidance::AirbrakeGuidanceHarness,
+        attitude_estimator::AttitudeEstimatorHarness, downlink::DownlinkHarness, fmm::FmmHarness,
+        navigation::NavigationHarness, recovery::RecoveryHarness, roll_control::RollControlHarness,
+    },
     events::{EventItem, EventPublisher, EventQueue},
-    gnc_main::{CraterLoop, CraterLoopHarness},
+    gnc_main::{CraterConfig, CraterLoop, CraterLoopHarness},
+    io::{
+        burst_capture::BurstCaptureHarness, log_transfer::LogTransferHarness,
+        mavlink_dispatcher::MavlinkDispatcherHarness,
+    },
     mav_crater::ComponentId,
 };
 
+use super::{
+    fsw_channel::ActuatorSender,
+    host_clock::HostClock,
+    log_storage::DirLogStorage,
+    sil_timing::{LoopJitter, LoopTime, QuantizedSender, SensorLinks, SilTiming},
+};
 use crate::{
     core::time::Clock,
-    crater::channels,
+    crater::{
+        channels,
+        gnc::{
+            datatypes::{AirbrakePosition, ServoPosition},
+            dual_fc::FcUnit,
+        },
+    },
     nodes::{Node, NodeContext, StepResult},
-    telemetry::{TelemetryReceiver, Timestamped},
+    telemetry::{TelemetryReceiver, TelemetrySender, Timestamped},
     utils::capacity::Capacity,
 };
-use anyhow::Result;
+use anyhow::{Result, anyhow};
+use std::path::Path;
+
+/// Channels the flight software publishes its outputs on
+#[derive(Debug, Clone, Copy)]
+pub struct FswOutputs {
+    pub events: &'static str,
+    pub ada: &'static str,
+    pub nav: &'static str,
+    pub recovery: &'static str,
+    /// Pyro channels fired by the recovery
+    pub pyro: &'static str,
+    pub downlink: &'static str,
+    pub command_ack: &'static str,
+    pub log_transfer: &'static str,
+    pub event_log: &'static str,
+    pub component_health: &'static str,
+    pub burst_capture: &'static str,
+    /// Fin commands of the roll controller, quantized to the servo resolution
+    pub roll_control: &'static str,
+    /// Servo commands, fed with the fin commands when the roll control loop is closed
+    /// (`sim.rocket.gnc.roll_control.closed_loop`). None for a flight computer that does not
+    /// drive the servos on its own.
+    pub servo_command: Option<&'static str>,
+    /// Airbrake commands of the guidance, quantized to the airbrake resolution
+    pub airbrake_control: &'static str,
+    /// Airbrake commands, fed with the ones of the guidance outside of shadow mode
+    /// (`sim.rocket.gnc.airbrake_guidance.shadow_mode`). None for a flight computer that does not
+    /// drive the airbrakes on its own.
+    pub airbrake_command: Option<&'static str>,
+    pub airbrake_guidance: &'static str,
+    pub attitude: &'static str,
+}
+
+impl FswOutputs {
+    /// Single flight computer, directly in the loop
+    pub const SINGLE: FswOutputs = FswOutputs {
+        events: channels::gnc::GNC_EVENTS,
+        ada: channels::gnc::ADA_OUTPUT,
+        nav: channels::gnc::NAV_OUTPUT,
+        recovery: channels::gnc::RECOVERY_STATUS,
+        pyro: channels::gnc::PYRO_COMMAND,
+        downlink: channels::gnc::DOWNLINK,
+        command_ack: channels::gnc::COMMAND_ACK,
+        log_transfer: channels::gnc::LOG_TRANSFER,
+        event_log: channels::gnc::EVENT_LOG,
+        component_health: channels::gnc::COMPONENT_HEALTH,
+        burst_capture: channels::gnc::BURST_CAPTURE,
+        roll_control: channels::gnc::ROLL_CONTROL,
+        servo_command: Some(channels::gnc::SERVO_COMMAND),
+        airbrake_control: channels::gnc::AIRBRAKE_CONTROL,
+        airbrake_command: Some(channels::gnc::AIRBRAKE_COMMAND),
+        airbrake_guidance: channels::gnc::AIRBRAKE_GUIDANCE,
+        attitude: channels::gnc::ATTITUDE_ESTIMATE,
+    };
+}
 
 pub struct FlightSoftware {
     crater: CraterLoop,
     rx_gnc_events: TelemetryReceiver<EventItem>,
     ev_pub: EventPublisher,
+
+    /// Step times of the loop, late of the simulation steps by the loop jitter
+    jitter: LoopJitter,
+    /// Time of the current step, up to which the sensor samples are received
+    loop_time: LoopTime,
+
+    /// The flight computer stops running at this time [s], to simulate its failure
+    fail_time_s: Option<f64>,
 }
 
 impl FlightSoftware {
     pub fn new(ctx: NodeContext) -> Result<Self> {
+        Self::with_outputs(ctx, FswOutputs::SINGLE, None)
+    }
+
+    /// One of the two redundant flight computers, whose outputs are voted by `FcVoter`
+    pub fn new_redundant(ctx: NodeContext, unit: FcUnit) -> Result<Self> {
+        let fail_time_s = ctx
+            .parameters()
+            .get_param(&format!(
+                "sim.rocket.gnc.dual_fc.fail_time_{}",
+                unit.suffix()
+            ))?
+            .value_float()?;
+
+        Self::with_outputs(
+            ctx,
+            unit.outputs(),
+            (fail_time_s >= 0.0).then_some(fail_time_s),
+        )
+    }
+
+    fn with_outputs(
+        ctx: NodeContext,
+        outputs: FswOutputs,
+        fail_time_s: Option<f64>,
+    ) -> Result<Self> {
+        let pressure_channel = match ctx
+            .parameters()
+            .get_param("sim.rocket.gnc.pressure_sensor")?
+            .value_string()?
+            .as_str()
+        {
+            "ideal" => channels::sensors::IDEAL_STATIC_PRESSURE,
+            "barometer" => channels::sensors::STATIC_PRESSURE,
+            unknown => return Err(anyhow!("Unknown pressure sensor for the FSW: '{unknown}'")),
+        };
+
+        let mock_nav = match ctx
+            .parameters()
+            .get_param("sim.rocket.gnc.navigation")?
+            .value_string()?
+            .as_str()
+        {
+            "ideal" => true,
+            "onboard" => false,
+            unknown => return Err(anyhow!("Unknown navigation for the FSW: '{unknown}'")),
+        };
+
+        let log_dir = ctx
+            .parameters()
+            .get_param("sim.rocket.gnc.log_dir")?
+            .value_string()?;
+
+        let gnc_params = ctx.parameters().get_map("sim.rocket.gnc")?;
+        let closed_loop = gnc_params
+            .get_param("roll_control.closed_loop")?
+            .value_bool()?;
+        let tx_servo_cmd: Option<TelemetrySender<ServoPosition>> = match outputs.servo_command {
+            Some(channel) if closed_loop => Some(ctx.telemetry().publish(channel)?),
+            _ => None,
+        };
+        let shadow_mode = gnc_params
+            .get_param("airbrake_guidance.shadow_mode")?
+            .value_bool()?;
+        let tx_airbrake_cmd: Option<TelemetrySender<AirbrakePosition>> =
+            match outputs.airbrake_command {
+                Some(channel) if !shadow_mode => Some(ctx.telemetry().publish(channel)?),
+                _ => None,
+            };
+
+        let timing = SilTiming::from_params(&ctx)?;
+        let loop_time = LoopTime::default();
+        let links = SensorLinks::new(&timing, &loop_time);
+
         let harness = CraterLoopHarness {
-            tx_events: Box::new(ctx.telemetry().publish_mp(channels::gnc::GNC_EVENTS)?),
+            tx_events: Box::new(ctx.telemetry().publish_mp(outputs.events)?),
+            tx_event_log: Box::new(ctx.telemetry().publish(outputs.event_log)?),
+            supervisor: SupervisorHarness {
+                clock: Box::new(HostClock::new()),
+                tx_health: Box::new(ctx.telemetry().publish(outputs.component_health)?),
+            },
             fmm: FmmHarness {
                 rx_liftoff_pin: Box::new(
                     ctx.telemetry()
                         .subscribe(channels::sensors::LIFTOFF_PIN, Capacity::Unbounded)?,
                 ),
+                rx_nav: Box::new(ctx.telemetry().subscribe(outputs.nav, Capacity::Unbounded)?),
+                rx_ada: Box::new(ctx.telemetry().subscribe(outputs.ada, Capacity::Unbounded)?),
             },
             ada: AdaHarness {
-                rx_static_pressure: Box::new(ctx.telemetry().subscribe(
-                    channels::sensors::IDEAL_STATIC_PRESSURE,
-                    Capacity::Unbounded,
-                )?),
-                tx_ada_data: Box::new(ctx.telemetry().publish(channels::gnc::ADA_OUTPUT)?),
+                rx_static_pressure: Box::new(links.subscribe(&ctx, pressure_channel)?),
+                tx_ada_data: Box::new(ctx.telemetry().publish(outputs.ada)?),
             },
             nav: NavigationHarness {
-                rx_gps: Box::new(
+                rx_gps: Box::new(links.subscribe(&ctx, channels::sensors::IDEAL_GPS)?),
+                rx_imu: Box::new(links.subscribe(&ctx, channels::sensors::IDEAL_IMU)?),
+                rx_magn: Box::new(links.subscribe(&ctx, channels::sensors::IDEAL_MAGNETOMETER)?),
+                rx_mock_nav_out: if mock_nav {
+                    Some(Box::new(ctx.telemetry().subscribe(
+                        channels::sensors::IDEAL_NAV_OUTPUT,
+                        Capacity::Unbounded,
+                    )?))
+                } else {
+                    None
+                },
+
+                tx_nav_out: Box::new(ctx.telemetry().publish(outputs.nav)?),
+            },
+            recovery: RecoveryHarness {
+                rx_ada: Box::new(
                     ctx.telemetry()
-                        .subscribe(channels::sensors::IDEAL_GPS, Capacity::Unbounded)?,
+                        .subscribe(outputs.ada, Capacity::Unbounded)?,
                 ),
-                rx_imu: Box::new(
+                tx_pyro: Box::new(ctx.telemetry().publish_mp(outputs.pyro)?),
+                tx_status: Box::new(ctx.telemetry().publish(outputs.recovery)?),
+            },
+            downlink: DownlinkHarness {
+                rx_nav: Box::new(ctx.telemetry().subscribe(outputs.nav, Capacity::Unbounded)?),
+                rx_ada: Box::new(ctx.telemetry().subscribe(outputs.ada, Capacity::Unbounded)?),
+                rx_recovery: Box::new(
                     ctx.telemetry()
-                        .subscribe(channels::sensors::IDEAL_IMU, Capacity::Unbounded)?,
+                        .subscribe(outputs.recovery, Capacity::Unbounded)?,
                 ),
-                rx_magn: Box::new(
+                tx_mavlink: Box::new(ctx.telemetry().publish(outputs.downlink)?),
+            },
+            dispatcher: MavlinkDispatcherHarness {
+                rx_uplink: Box::new(
                     ctx.telemetry()
-                        .subscribe(channels::sensors::IDEAL_MAGNETOMETER, Capacity::Unbounded)?,
+                        .subscribe_feedback(channels::gnc::UPLINK, Capacity::Unbounded)?,
                 ),
-                rx_mock_nav_out: Some(Box::new(
+                tx_ack: Box::new(ctx.telemetry().publish(outputs.command_ack)?),
+            },
+            log_transfer: LogTransferHarness {
+                storage: Box::new(DirLogStorage::new(Path::new(&log_dir))?),
+                rx_uplink: Box::new(
                     ctx.telemetry()
-                        .subscribe(channels::sensors::IDEAL_NAV_OUTPUT, Capacity::Unbounded)?,
+                        .subscribe_feedback(channels::gnc::UPLINK, Capacity::Unbounded)?,
+                ),
+                tx_log: Box::new(ctx.telemetry().publish(outputs.log_transfer)?),
+            },
+            burst_capture: BurstCaptureHarness {
+                rx_imu: Box::new(links.subscribe(&ctx, channels::sensors::IDEAL_IMU)?),
+                rx_pressure: Box::new(links.subscribe(&ctx, pressure_channel)?),
+                rx_nav: Box::new(ctx.telemetry().subscribe(outputs.nav, Capacity::Unbounded)?),
+                rx_ada: Box::new(ctx.telemetry().subscribe(outputs.ada, Capacity::Unbounded)?),
+                tx_record: Box::new(ctx.telemetry().publish(outputs.burst_capture)?),
+            },
+            roll_control: RollControlHarness {
+                rx_imu: Box::new(links.subscribe(&ctx, channels::sensors::IDEAL_IMU)?),
+                tx_servo: Box::new(QuantizedSender::new(
+                    ActuatorSender::new(
+                        ctx.telemetry().publish(outputs.roll_control)?,
+                        tx_servo_cmd,
+                    ),
+                    &timing,
                 )),
-
-                tx_nav_out: Box::new(ctx.telemetry().publish(channels::gnc::NAV_OUTPUT)?),
             },
+            airbrake_guidance: AirbrakeGuidanceHarness {
+                rx_nav: Box::new(ctx.telemetry().subscribe(outputs.nav, Capacity::Unbounded)?),
+                tx_airbrake: Box::new(QuantizedSender::new(
+                    ActuatorSender::new(
+                        ctx.telemetry().publish(outputs.airbrake_control)?,
+                        tx_airbrake_cmd,
+                    ),
+                    &timing,
+                )),
+                tx_guidance: Box::new(ctx.telemetry().publish(outputs.airbrake_guidance)?),
+            },
+            attitude: AttitudeEstimatorHarness {
+                rx_imu: Box::new(links.subscribe(&ctx, channels::sensors::IDEAL_IMU)?),
+                rx_magn: Box::new(links.subscribe(&ctx, channels::sensors::IDEAL_MAGNETOMETER)?),
+                tx_attitude: Box::new(ctx.telemetry().publish(outputs.attitude)?),
+            },
+        };
+
+        let param =
+            |path: &str| -> Result<f32> { Ok(gnc_params.get_param(path)?.value_float()? as f32) };
+
+        let duration = |path: &str| -> Result<Duration> {
+            Ok(DurationU64::micros((param(path)? * 1e6) as u64).into())
         };
 
+        let mut config = CraterConfig::default();
+        config.fmm.liftoff_accel_threshold_m_s2 = param("fmm.liftoff_accel_threshold")?;
+        config.fmm.liftoff_confirmation = duration("fmm.liftoff_confirmation")?;
+        config.fmm.burnout_accel_threshold_m_s2 = param("fmm.burnout_accel_threshold")?;
+        config.fmm.burnout_confirmation = duration("fmm.burnout_confirmation")?;
+        config.fmm.max_burn_time = duration("fmm.max_burn_time")?;
+        config.roll_control.gains = PidGains {
+            kp: param("roll_control.kp")?,
+            ki: param("roll_control.ki")?,
+            kd: param("roll_control.kd")?,
+        };
+        config.roll_control.max_deflection_rad = param("roll_control.max_deflection")?.to_radians();
+        config.airbrake_guidance.target_apogee_m = param("airbrake_guidance.target_apogee")?;
+        config.airbrake_guidance.shadow_mode = shadow_mode;
+        config.supervisor.component_budget = duration("supervisor.component_budget")?;
+
         let event_queue = EventQueue::default();
         let ev_pub = event_queue.get_publisher(ComponentId::Ground);
         let rx_gnc_events = ctx
@@ -69,15 +314,26 @@ impl FlightSoftware {
             .subscribe_mp(channels::gnc::GNC_EVENTS, Capacity::Unbounded)?;
 
         Ok(Self {
-            crater: CraterLoop::new(event_queue, harness)?,
+            crater: CraterLoop::new(event_queue, harness, config)?,
             ev_pub,
             rx_gnc_events,
+            jitter: LoopJitter::new(timing.loop_jitter_us, ctx.get_rng_256()),
+            loop_time,
+            fail_time_s,
         })
     }
 }
 
 impl Node for FlightSoftware {
     fn step(&mut self, i: usize, dt: TimeDelta, clock: &dyn Clock) -> Result<StepResult> {
+        if self
+            .fail_time_s
+            .is_some_and(|t| clock.monotonic().elapsed_seconds_f64() >= t)
+        {
+            // Dead flight computer: no outputs
+            return Ok(StepResult::Continue);
+        }
+
         while let Ok(Timestamped(_, ev)) = self.rx_gnc_events.try_recv() {
             if ev.src == ComponentId::Ground {
                 self.ev_pub.publish(
@@ -87,12 +343,15 @@ impl Node for FlightSoftware {
             }
         }
 
+        let (step_time_us, step_interval_us) = self.jitter.step(
+            clock.monotonic().elapsed().num_microseconds().unwrap() as u64,
+            dt.num_microseconds().unwrap() as u64,
+        );
+        self.loop_time.set(step_time_us);
+
         self.crater.step(&StepData {
-            step_time: InstantU64::from_ticks(
-                clock.monotonic().elapsed().num_microseconds().unwrap() as u64,
-            )
-            .into(),
-            step_interval: DurationU64::micros(dt.num_microseconds().unwrap() as u64).into(),
+            step_time: InstantU64::from_ticks(step_time_us).into(),
+            step_interval: DurationU64::micros(step_interval_us).into(),
             step_count: i as u32,
         });
 
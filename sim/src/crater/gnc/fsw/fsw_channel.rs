@@ -30,6 +30,36 @@ impl<T: 'static + Clone> Sender<T> for TelemetrySender<T> {
     }
 }
 
+/// Output of the flight software, also applied to an actuator of the simulation if any
+pub struct ActuatorSender<T, A> {
+    tx: TelemetrySender<T>,
+    tx_actuator: Option<TelemetrySender<A>>,
+}
+
+impl<T, A> ActuatorSender<T, A> {
+    pub fn new(tx: TelemetrySender<T>, tx_actuator: Option<TelemetrySender<A>>) -> Self {
+        Self { tx, tx_actuator }
+    }
+}
+
+impl<T: 'static + Clone, A: 'static + Clone + From<T>> Sender<T> for ActuatorSender<T, A> {
+    fn try_send(&mut self, ts: crater_gnc::Instant, item: T) -> Result<(), Full<T>> {
+        if let Some(tx_actuator) = self.tx_actuator.as_mut() {
+            let _ = tx_actuator.try_send(ts, item.clone().into());
+        }
+
+        self.tx.try_send(ts, item)
+    }
+
+    fn send_immediate(&mut self, ts: crater_gnc::Instant, item: T) {
+        if let Some(tx_actuator) = self.tx_actuator.as_mut() {
+            tx_actuator.send_immediate(ts, item.clone().into());
+        }
+
+        self.tx.send_immediate(ts, item)
+    }
+}
+
 impl<T: 'static + Clone> Receiver<T> for TelemetryReceiver<T> {
     fn try_recv(&mut self) -> Option<Ts<T>> {
         if let Ok(v) = TelemetryReceiver::try_recv(&self) {
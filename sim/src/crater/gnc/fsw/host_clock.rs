@@ -0,0 +1,24 @@
+use std::time::Instant;
+
+use crater_gnc::{InstantU64, hal::Hal};
+
+/// Clock of the host running the simulation, for the execution times of the flight software: the
+/// simulated step time does not advance while a step runs
+#[derive(Debug, Clone)]
+pub struct HostClock {
+    start: Instant,
+}
+
+impl HostClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Hal for HostClock {
+    fn system_time(&self) -> crater_gnc::Instant {
+        InstantU64::from_ticks(self.start.elapsed().as_micros() as u64).into()
+    }
+}
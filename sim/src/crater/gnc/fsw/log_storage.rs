@@ -0,0 +1,92 @@
+use std::{
+    fs::{self, File},
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use anyhow::{Context, Result};
+use crater_gnc::io::log_transfer::{LogInfo, LogStorage};
+use log::warn;
+
+/// Onboard logs of the simulated flight computer: the files of a directory, in name order. The
+/// list is taken when the flight software starts.
+#[derive(Debug, Default)]
+pub struct DirLogStorage {
+    logs: Vec<(PathBuf, LogInfo)>,
+}
+
+impl DirLogStorage {
+    /// No logs if `dir` is empty
+    pub fn new(dir: &Path) -> Result<Self> {
+        if dir.as_os_str().is_empty() {
+            return Ok(Self::default());
+        }
+
+        let mut paths = fs::read_dir(dir)
+            .with_context(|| format!("Cannot list the onboard logs in {}", dir.display()))?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        paths.retain(|p| p.is_file());
+        paths.sort();
+
+        let mut logs = vec![];
+        for path in paths {
+            let meta = fs::metadata(&path)?;
+            let time_utc = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_secs() as u32);
+
+            logs.push((
+                path,
+                LogInfo {
+                    size: meta.len() as u32,
+                    time_utc,
+                },
+            ));
+        }
+
+        Ok(Self { logs })
+    }
+
+    fn log(&self, id: u16) -> Option<&(PathBuf, LogInfo)> {
+        self.logs.get((id as usize).checked_sub(1)?)
+    }
+}
+
+impl LogStorage for DirLogStorage {
+    fn num_logs(&self) -> u16 {
+        self.logs.len() as u16
+    }
+
+    fn log_info(&self, id: u16) -> Option<LogInfo> {
+        self.log(id).map(|(_, info)| *info)
+    }
+
+    fn read(&mut self, id: u16, offset: u32, buf: &mut [u8]) -> usize {
+        let Some((path, _)) = self.log(id) else {
+            return 0;
+        };
+
+        read_at(path, offset, buf).unwrap_or_else(|e| {
+            warn!("Error reading the onboard log {}: {e}", path.display());
+            0
+        })
+    }
+}
+
+fn read_at(path: &Path, offset: u32, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(offset as u64))?;
+
+    let mut len = 0;
+    while len < buf.len() {
+        match file.read(&mut buf[len..])? {
+            0 => break,
+            n => len += n,
+        }
+    }
+    Ok(len)
+}
@@ -1,4 +1,9 @@
+mod burst_recorder;
 mod fsw;
 mod fsw_channel;
+mod host_clock;
+mod log_storage;
+mod sil_timing;
 
-pub use fsw::FlightSoftware;
\ No newline at end of file
+pub use burst_recorder::BurstRecorder;
+pub use fsw::{FlightSoftware, FswOutputs};
\ No newline at end of file
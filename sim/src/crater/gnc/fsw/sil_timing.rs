@@ -0,0 +1,348 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use anyhow::{Result, anyhow};
+use crater_gnc::{
+    Instant,
+    common::Ts,
+    datatypes::actuators::{AirbrakeCommand, ServoCommand},
+    hal::channel::{Full, Receiver, Sender},
+};
+use rand::Rng;
+use rand_xoshiro::Xoshiro256StarStar;
+
+use crate::{nodes::NodeContext, telemetry::TelemetryReceiver, utils::capacity::Capacity};
+
+/// Timing of the flight computer modeled around the flight software in the loop, so that it sees
+/// its inputs & drives its outputs as onboard rather than in lockstep with the simulation.
+///
+/// ```toml
+/// [sim.rocket.gnc.sil]
+/// sampling_latency = { val = 0.5e-3, type = "float" }
+/// transport_delay = { val = 1e-3, type = "float" }
+/// loop_jitter = { val = 0.2e-3, type = "float" }
+/// servo_resolution = { val = 0.09, type = "float" }
+/// airbrake_resolution = { val = 0.001, type = "float" }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SilTiming {
+    /// From a sensor measuring to the sample being timestamped by its driver, eg. the group
+    /// delay of its filters: not accounted for by the flight software [µs]
+    pub sampling_latency_us: u64,
+    /// From a sample being timestamped to it being received by the components, eg. the bus
+    /// transfer & the dispatch to the tasks [µs]
+    pub transport_delay_us: u64,
+    /// Largest delay of a step of the loop from its nominal time [µs]
+    pub loop_jitter_us: u64,
+    /// Fin command resolution, eg. of the servo PWM [rad]. 0 for none.
+    pub servo_resolution_rad: f32,
+    /// Airbrake extension command resolution. 0 for none.
+    pub airbrake_resolution: f32,
+}
+
+impl SilTiming {
+    pub fn from_params(ctx: &NodeContext) -> Result<Self> {
+        let params = ctx.parameters().get_map("sim.rocket.gnc.sil")?;
+        let float = |name: &str| -> Result<f64> {
+            let v = params.get_param(name)?.value_float()?;
+            if v < 0.0 {
+                return Err(anyhow!("SIL timing '{name}' must not be negative"));
+            }
+            Ok(v)
+        };
+        let micros = |name: &str| -> Result<u64> { Ok((float(name)? * 1e6).round() as u64) };
+
+        Ok(Self {
+            sampling_latency_us: micros("sampling_latency")?,
+            transport_delay_us: micros("transport_delay")?,
+            loop_jitter_us: micros("loop_jitter")?,
+            servo_resolution_rad: (float("servo_resolution")? as f32).to_radians(),
+            airbrake_resolution: float("airbrake_resolution")? as f32,
+        })
+    }
+}
+
+/// Time of the current step of the flight software loop, shared with the inputs it reads
+#[derive(Debug, Clone, Default)]
+pub struct LoopTime(Arc<AtomicU64>);
+
+impl LoopTime {
+    pub fn set(&self, t_us: u64) {
+        self.0.store(t_us, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Samples in flight from a sensor to the flight software, in the order they were measured
+#[derive(Debug, Clone)]
+struct DelayLine<T> {
+    sampling_latency_us: u64,
+    transport_delay_us: u64,
+    /// Samples with their timestamp as seen by the flight software
+    in_flight: VecDeque<(u64, T)>,
+}
+
+impl<T> DelayLine<T> {
+    fn new(timing: &SilTiming) -> Self {
+        Self {
+            sampling_latency_us: timing.sampling_latency_us,
+            transport_delay_us: timing.transport_delay_us,
+            in_flight: VecDeque::new(),
+        }
+    }
+
+    /// A sample measured at `t_us`
+    fn push(&mut self, t_us: u64, v: T) {
+        self.in_flight
+            .push_back((t_us + self.sampling_latency_us, v));
+    }
+
+    /// The oldest sample received by `now_us`, with its timestamp
+    fn pop(&mut self, now_us: u64) -> Option<(u64, T)> {
+        let (t_us, _) = self.in_flight.front()?;
+        if t_us + self.transport_delay_us > now_us {
+            return None;
+        }
+        self.in_flight.pop_front()
+    }
+}
+
+/// Sensor input of the flight software, delayed by the sampling latency & the transport delay
+pub struct DelayedReceiver<T> {
+    rx: TelemetryReceiver<T>,
+    line: DelayLine<T>,
+    now: LoopTime,
+}
+
+/// Links from the simulated sensors to the flight software
+#[derive(Debug, Clone)]
+pub struct SensorLinks {
+    timing: SilTiming,
+    now: LoopTime,
+}
+
+impl SensorLinks {
+    pub fn new(timing: &SilTiming, now: &LoopTime) -> Self {
+        Self {
+            timing: timing.clone(),
+            now: now.clone(),
+        }
+    }
+
+    pub fn subscribe<T: 'static + Send>(
+        &self,
+        ctx: &NodeContext,
+        channel: &str,
+    ) -> Result<DelayedReceiver<T>> {
+        Ok(DelayedReceiver {
+            rx: ctx.telemetry().subscribe(channel, Capacity::Unbounded)?,
+            line: DelayLine::new(&self.timing),
+            now: self.now.clone(),
+        })
+    }
+}
+
+impl<T: 'static + Clone> Receiver<T> for DelayedReceiver<T> {
+    fn try_recv(&mut self) -> Option<Ts<T>> {
+        while let Some(Ts { t, v }) = Receiver::try_recv(&mut self.rx) {
+            self.line.push(t.0.ticks(), v);
+        }
+
+        let (t_us, v) = self.line.pop(self.now.get())?;
+        Some(Ts::from_microseconds(t_us, v))
+    }
+
+    fn capacity(&self) -> usize {
+        usize::MAX
+    }
+
+    fn is_empty(&self) -> bool {
+        self.line.in_flight.is_empty()
+    }
+
+    fn is_full(&self) -> bool {
+        false
+    }
+
+    fn len(&self) -> usize {
+        self.line.in_flight.len()
+    }
+
+    fn num_lagged(&self) -> usize {
+        0
+    }
+}
+
+/// Start times of the steps of the flight software loop: each one runs late of its nominal time
+/// by a random delay up to the jitter, the steps staying in order.
+#[derive(Debug)]
+pub struct LoopJitter {
+    jitter_us: u64,
+    rng: Xoshiro256StarStar,
+    last_us: Option<u64>,
+}
+
+impl LoopJitter {
+    pub fn new(jitter_us: u64, rng: Xoshiro256StarStar) -> Self {
+        Self {
+            jitter_us,
+            rng,
+            last_us: None,
+        }
+    }
+
+    /// Start time & interval from the previous one of the step due at `nominal_us`
+    pub fn step(&mut self, nominal_us: u64, nominal_interval_us: u64) -> (u64, u64) {
+        let delay_us = if self.jitter_us > 0 {
+            self.rng.random_range(0..=self.jitter_us)
+        } else {
+            0
+        };
+
+        let t_us = (nominal_us + delay_us).max(self.last_us.unwrap_or(0));
+        let interval_us = self.last_us.map_or(nominal_interval_us, |last| t_us - last);
+        self.last_us = Some(t_us);
+
+        (t_us, interval_us)
+    }
+}
+
+/// Rounds to the nearest multiple of the resolution, if any
+fn quantize(v: f32, resolution: f32) -> f32 {
+    if resolution > 0.0 {
+        (v / resolution).round() * resolution
+    } else {
+        v
+    }
+}
+
+/// Actuator command of the flight software, with its resolution onboard
+pub trait Quantize {
+    fn quantize(self, timing: &SilTiming) -> Self;
+}
+
+impl Quantize for ServoCommand {
+    fn quantize(self, timing: &SilTiming) -> Self {
+        ServoCommand {
+            pos_rad: self
+                .pos_rad
+                .map(|p| quantize(p, timing.servo_resolution_rad)),
+        }
+    }
+}
+
+impl Quantize for AirbrakeCommand {
+    fn quantize(self, timing: &SilTiming) -> Self {
+        AirbrakeCommand {
+            extension: quantize(self.extension, timing.airbrake_resolution),
+        }
+    }
+}
+
+/// Actuator output of the flight software, quantized to the resolution of the commands onboard
+pub struct QuantizedSender<S> {
+    tx: S,
+    timing: SilTiming,
+}
+
+impl<S> QuantizedSender<S> {
+    pub fn new(tx: S, timing: &SilTiming) -> Self {
+        Self {
+            tx,
+            timing: timing.clone(),
+        }
+    }
+}
+
+impl<T: Quantize, S: Sender<T>> Sender<T> for QuantizedSender<S> {
+    fn try_send(&mut self, ts: Instant, item: T) -> Result<(), Full<T>> {
+        self.tx.try_send(ts, item.quantize(&self.timing))
+    }
+
+    fn send_immediate(&mut self, ts: Instant, item: T) {
+        self.tx.send_immediate(ts, item.quantize(&self.timing))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn test_delay_line() {
+        let timing = SilTiming {
+            sampling_latency_us: 500,
+            transport_delay_us: 1000,
+            ..Default::default()
+        };
+        let mut line = DelayLine::new(&timing);
+
+        line.push(0, 'a');
+        line.push(1000, 'b');
+
+        // Received 1.5 ms after being measured, stamped 0.5 ms late
+        assert_eq!(line.pop(1499), None);
+        assert_eq!(line.pop(1500), Some((500, 'a')));
+        assert_eq!(line.pop(2000), None);
+        assert_eq!(line.pop(3000), Some((1500, 'b')));
+        assert_eq!(line.pop(10000), None);
+
+        // No delays: received right away
+        let mut line = DelayLine::new(&SilTiming::default());
+        line.push(1000, 'c');
+        assert_eq!(line.pop(1000), Some((1000, 'c')));
+    }
+
+    #[test]
+    fn test_loop_jitter() {
+        let rng = Xoshiro256StarStar::seed_from_u64(0);
+        let mut jitter = LoopJitter::new(300, rng);
+
+        let mut last_us = 0;
+        for i in 0..1000 {
+            let (t_us, interval_us) = jitter.step(i * 1000, 1000);
+            assert!((i * 1000..=i * 1000 + 300).contains(&t_us));
+            assert!(t_us >= last_us);
+            if i > 0 {
+                assert_eq!(interval_us, t_us - last_us);
+            }
+            last_us = t_us;
+        }
+
+        let mut none = LoopJitter::new(0, Xoshiro256StarStar::seed_from_u64(0));
+        assert_eq!(none.step(0, 1000), (0, 1000));
+        assert_eq!(none.step(1000, 1000), (1000, 1000));
+    }
+
+    #[test]
+    fn test_quantize() {
+        let timing = SilTiming {
+            servo_resolution_rad: 0.01,
+            airbrake_resolution: 0.25,
+            ..Default::default()
+        };
+
+        let servo = ServoCommand {
+            pos_rad: [0.012, -0.016, 0.0, 0.1],
+        }
+        .quantize(&timing);
+        for (p, expected) in servo.pos_rad.iter().zip([0.01, -0.02, 0.0, 0.1]) {
+            assert!((p - expected).abs() < 1e-6, "{p}");
+        }
+
+        let airbrake = AirbrakeCommand { extension: 0.4 }.quantize(&timing);
+        assert_eq!(airbrake.extension, 0.5);
+
+        assert_eq!(quantize(0.123, 0.0), 0.123);
+    }
+}
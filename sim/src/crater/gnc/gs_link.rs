@@ -0,0 +1,467 @@
+use std::{
+    io::{ErrorKind, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream, UdpSocket},
+};
+
+use anyhow::{Context, Result, anyhow};
+use chrono::TimeDelta;
+use crater_gnc::{
+    Instant, InstantU64, MavHeader,
+    datatypes::sensors::{ImuSensorSample, PressureSensorSample},
+    mav_crater::{ImuSensorId, MavMessage, PressureSensorId},
+    peek_reader::PeekReader,
+    read_v2_msg, write_v2_msg,
+};
+use log::{debug, info, warn};
+
+use crate::{
+    core::time::{Clock, Timestamp},
+    crater::{channels, events::GncEventItem},
+    nodes::{Node, NodeContext, StepResult},
+    telemetry::{TelemetryReceiver, TelemetrySender, Timestamped},
+    utils::capacity::Capacity::Unbounded,
+};
+
+const MAVLINK_V2_STX: u8 = 0xFD;
+/// Start marker, length, flags, sequence, system, component & message id
+const MAVLINK_V2_HEADER_LEN: usize = 10;
+const MAVLINK_V2_CHECKSUM_LEN: usize = 2;
+const MAVLINK_V2_SIGNATURE_LEN: usize = 13;
+const MAVLINK_IFLAG_SIGNED: u8 = 0x01;
+
+/// Splits the complete MAVLink 2 frames off the front of `buf`. Bytes before a start marker are
+/// discarded, incomplete frames are kept for the next call.
+pub fn take_frames(buf: &mut Vec<u8>) -> Vec<Vec<u8>> {
+    let mut frames = vec![];
+
+    loop {
+        match buf.iter().position(|&b| b == MAVLINK_V2_STX) {
+            Some(start) => {
+                buf.drain(..start);
+            }
+            None => {
+                buf.clear();
+                return frames;
+            }
+        }
+
+        if buf.len() < MAVLINK_V2_HEADER_LEN {
+            return frames;
+        }
+
+        let mut len = MAVLINK_V2_HEADER_LEN + buf[1] as usize + MAVLINK_V2_CHECKSUM_LEN;
+        if buf[2] & MAVLINK_IFLAG_SIGNED != 0 {
+            len += MAVLINK_V2_SIGNATURE_LEN;
+        }
+        if buf.len() < len {
+            return frames;
+        }
+
+        frames.push(buf.drain(..len).collect());
+    }
+}
+
+pub fn parse_frame(frame: &[u8]) -> Option<MavMessage> {
+    let mut reader: PeekReader<&[u8]> = PeekReader::new(frame);
+    match read_v2_msg::<MavMessage, _>(&mut reader) {
+        Ok((_, msg)) => Some(msg),
+        Err(e) => {
+            debug!("Invalid MAVLink frame: {e:?}");
+            None
+        }
+    }
+}
+
+enum GsTransport {
+    /// Sends to the ground station address, receives from anyone
+    Udp {
+        socket: UdpSocket,
+        remote: SocketAddr,
+    },
+    /// Serves one ground station at a time
+    Tcp {
+        listener: TcpListener,
+        client: Option<TcpStream>,
+    },
+}
+
+impl GsTransport {
+    fn new(transport: &str, address: &str, remote: &str) -> Result<Self> {
+        match transport {
+            "udp" => {
+                let socket = UdpSocket::bind(address)
+                    .with_context(|| format!("Cannot bind the GS link to {address}"))?;
+                socket.set_nonblocking(true)?;
+
+                let remote = remote
+                    .parse()
+                    .with_context(|| format!("Invalid ground station address '{remote}'"))?;
+
+                info!("GS link: MAVLink over UDP on {address}, sending to {remote}");
+                Ok(GsTransport::Udp { socket, remote })
+            }
+            "tcp" => {
+                let listener = TcpListener::bind(address)
+                    .with_context(|| format!("Cannot bind the GS link to {address}"))?;
+                listener.set_nonblocking(true)?;
+
+                info!("GS link: MAVLink over TCP, listening on {address}");
+                Ok(GsTransport::Tcp {
+                    listener,
+                    client: None,
+                })
+            }
+            _ => Err(anyhow!(
+                "Unknown GS link transport '{transport}' (expected 'udp' or 'tcp')"
+            )),
+        }
+    }
+
+    /// Reads the bytes received so far, without blocking
+    fn receive(&mut self, buf: &mut Vec<u8>) -> Result<()> {
+        let mut chunk = [0u8; 2048];
+
+        match self {
+            GsTransport::Udp { socket, .. } => loop {
+                match socket.recv(&mut chunk) {
+                    Ok(len) => buf.extend_from_slice(&chunk[..len]),
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(()),
+                    Err(e) if e.kind() == ErrorKind::ConnectionReset => continue,
+                    Err(e) => return Err(e.into()),
+                }
+            },
+            GsTransport::Tcp { listener, client } => {
+                if client.is_none() {
+                    match listener.accept() {
+                        Ok((stream, addr)) => {
+                            stream.set_nonblocking(true)?;
+                            stream.set_nodelay(true)?;
+                            info!("Ground station connected from {addr}");
+                            buf.clear();
+                            *client = Some(stream);
+                        }
+                        Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(()),
+                        Err(e) => return Err(e.into()),
+                    }
+                }
+
+                let Some(stream) = client.as_mut() else {
+                    return Ok(());
+                };
+                loop {
+                    match stream.read(&mut chunk) {
+                        Ok(0) => {
+                            info!("Ground station disconnected");
+                            *client = None;
+                            return Ok(());
+                        }
+                        Ok(len) => buf.extend_from_slice(&chunk[..len]),
+                        Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(()),
+                        Err(e) => {
+                            warn!("Ground station connection lost: {e}");
+                            *client = None;
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Sends the frames, if a ground station is there to receive them. Frames that cannot be sent
+    /// right away are dropped, as a radio link would.
+    fn send(&mut self, frames: &[u8]) {
+        match self {
+            GsTransport::Udp { socket, remote } => {
+                // Refused while nobody listens on the ground station port
+                let _ = socket.send_to(frames, *remote);
+            }
+            GsTransport::Tcp { client, .. } => {
+                if let Some(stream) = client.as_mut() {
+                    match stream.write(frames) {
+                        Ok(len) if len == frames.len() => {}
+                        Ok(_) => {
+                            // A partial frame would desync the stream
+                            warn!("Ground station not keeping up with the link, disconnecting");
+                            *client = None;
+                        }
+                        Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                        Err(e) => {
+                            warn!("Ground station connection lost: {e}");
+                            *client = None;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Outputs of the flight software streamed to the ground station
+enum GsSource {
+    /// Straight from the flight software
+    Vehicle {
+        rx_downlink: TelemetryReceiver<MavMessage>,
+        rx_acks: TelemetryReceiver<MavMessage>,
+        rx_logs: TelemetryReceiver<MavMessage>,
+        rx_event_log: TelemetryReceiver<MavMessage>,
+        rx_health: TelemetryReceiver<MavMessage>,
+        rx_events: TelemetryReceiver<GncEventItem>,
+        rx_imu: TelemetryReceiver<ImuSensorSample>,
+        rx_pressure: TelemetryReceiver<PressureSensorSample>,
+    },
+    /// What the radio link model lets through
+    Radio {
+        rx_ground: TelemetryReceiver<MavMessage>,
+    },
+}
+
+/// Exposes the vehicle as a MAVLink endpoint (mav_crater dialect), so that the ground station
+/// software can be run against the simulation. Streams the flight software downlink, its events,
+/// the health of its components and the sensor samples. Messages from the ground station go to the flight software uplink,
+/// whose command acknowledgements, log transfers and event log dumps are sent back.
+///
+/// With the radio link model (`sim.radio_link.enabled`), the ground station only gets what the
+/// flight radio sends within its airtime, and its messages are sent over the link.
+pub struct GsLink {
+    transport: GsTransport,
+    rx_buf: Vec<u8>,
+    sequence: u8,
+
+    source: GsSource,
+    tx_uplink: TelemetrySender<MavMessage>,
+
+    sensor_period_s: f64,
+    next_sensor_s: f64,
+}
+
+impl GsLink {
+    pub fn new(ctx: NodeContext) -> Result<Self> {
+        let params = ctx.parameters().get_map("sim.gs_link")?;
+        let transport = GsTransport::new(
+            &params.get_param("transport")?.value_string()?,
+            &params.get_param("address")?.value_string()?,
+            &params.get_param("remote")?.value_string()?,
+        )?;
+        let sensor_rate = params.get_param("sensor_rate")?.value_float()?;
+
+        let radio = ctx
+            .parameters()
+            .get_param("sim.radio_link.enabled")?
+            .value_bool()?;
+        let (source, tx_uplink) = if radio {
+            (
+                GsSource::Radio {
+                    rx_ground: ctx
+                        .telemetry()
+                        .subscribe_feedback(channels::sim::GROUND_DOWNLINK, Unbounded)?,
+                },
+                ctx.telemetry().publish_mp(channels::sim::GROUND_UPLINK)?,
+            )
+        } else {
+            (
+                GsSource::Vehicle {
+                    rx_downlink: ctx
+                        .telemetry()
+                        .subscribe(channels::gnc::DOWNLINK, Unbounded)?,
+                    rx_acks: ctx
+                        .telemetry()
+                        .subscribe(channels::gnc::COMMAND_ACK, Unbounded)?,
+                    rx_logs: ctx
+                        .telemetry()
+                        .subscribe(channels::gnc::LOG_TRANSFER, Unbounded)?,
+                    rx_event_log: ctx
+                        .telemetry()
+                        .subscribe(channels::gnc::EVENT_LOG, Unbounded)?,
+                    rx_health: ctx
+                        .telemetry()
+                        .subscribe(channels::gnc::COMPONENT_HEALTH, Unbounded)?,
+                    rx_events: ctx
+                        .telemetry()
+                        .subscribe_mp(channels::gnc::GNC_EVENTS, Unbounded)?,
+                    rx_imu: ctx
+                        .telemetry()
+                        .subscribe(channels::sensors::IDEAL_IMU, Unbounded)?,
+                    rx_pressure: ctx
+                        .telemetry()
+                        .subscribe(channels::sensors::STATIC_PRESSURE, Unbounded)?,
+                },
+                ctx.telemetry().publish(channels::gnc::UPLINK)?,
+            )
+        };
+
+        Ok(Self {
+            transport,
+            rx_buf: vec![],
+            sequence: 0,
+            source,
+            tx_uplink,
+            sensor_period_s: if sensor_rate > 0.0 {
+                1.0 / sensor_rate
+            } else {
+                f64::INFINITY
+            },
+            next_sensor_s: 0.0,
+        })
+    }
+
+    fn latest<T>(rx: &TelemetryReceiver<T>) -> Option<T> {
+        let mut latest = None;
+        while let Ok(Timestamped(_, v)) = rx.try_recv() {
+            latest = Some(v);
+        }
+        latest
+    }
+}
+
+impl Node for GsLink {
+    fn step(&mut self, _: usize, _: TimeDelta, clock: &dyn Clock) -> Result<StepResult> {
+        let t = Timestamp::now(clock);
+        let t_s = t.monotonic.elapsed_seconds_f64();
+        let t_gnc = |t: Timestamp| -> Instant {
+            InstantU64::from_ticks(t.monotonic.elapsed().num_microseconds().unwrap_or(0) as u64)
+                .into()
+        };
+
+        self.transport.receive(&mut self.rx_buf)?;
+        for frame in take_frames(&mut self.rx_buf) {
+            if let Some(msg) = parse_frame(&frame) {
+                if let MavMessage::GroundCommand(cmd) = &msg {
+                    info!("Ground station command: {:?}", cmd.command);
+                }
+                self.tx_uplink.send(t, msg);
+            }
+        }
+
+        let mut messages: Vec<MavMessage> = vec![];
+        match &self.source {
+            GsSource::Vehicle {
+                rx_downlink,
+                rx_acks,
+                rx_logs,
+                rx_event_log,
+                rx_health,
+                rx_events,
+                rx_imu,
+                rx_pressure,
+            } => {
+                while let Ok(Timestamped(_, msg)) = rx_downlink.try_recv() {
+                    messages.push(msg);
+                }
+                while let Ok(Timestamped(_, ack)) = rx_acks.try_recv() {
+                    messages.push(ack);
+                }
+                while let Ok(Timestamped(_, log)) = rx_logs.try_recv() {
+                    messages.push(log);
+                }
+                while let Ok(Timestamped(_, entry)) = rx_event_log.try_recv() {
+                    messages.push(entry);
+                }
+                while let Ok(Timestamped(_, health)) = rx_health.try_recv() {
+                    messages.push(health);
+                }
+                while let Ok(Timestamped(t_ev, item)) = rx_events.try_recv() {
+                    messages.push(item.to_mavlink(t_gnc(t_ev)));
+                }
+
+                let imu = Self::latest(rx_imu);
+                let pressure = Self::latest(rx_pressure);
+                if t_s >= self.next_sensor_s {
+                    self.next_sensor_s = t_s + self.sensor_period_s;
+
+                    if let Some(imu) = imu {
+                        messages.push(imu.to_mavlink(ImuSensorId::Icm42688, t_gnc(t)));
+                    }
+                    if let Some(pressure) = pressure {
+                        messages.push(pressure.to_mavlink(PressureSensorId::Bmp390, t_gnc(t)));
+                    }
+                }
+            }
+            GsSource::Radio { rx_ground } => {
+                while let Ok(Timestamped(_, msg)) = rx_ground.try_recv() {
+                    messages.push(msg);
+                }
+            }
+        }
+
+        if messages.is_empty() {
+            return Ok(StepResult::Continue);
+        }
+
+        let mut frames = vec![];
+        for msg in messages.iter() {
+            let header = MavHeader {
+                system_id: 1,
+                component_id: 1,
+                sequence: self.sequence,
+            };
+            self.sequence = self.sequence.wrapping_add(1);
+
+            write_v2_msg(&mut frames, header, msg)
+                .map_err(|e| anyhow!("Error encoding a MAVLink message: {e:?}"))?;
+        }
+        self.transport.send(&frames);
+
+        Ok(StepResult::Continue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(payload_len: u8, incompat_flags: u8) -> Vec<u8> {
+        let mut frame = vec![MAVLINK_V2_STX, payload_len, incompat_flags];
+        frame.resize(MAVLINK_V2_HEADER_LEN, 1);
+        frame.resize(frame.len() + payload_len as usize, 0xAA);
+        frame.extend([0x55; MAVLINK_V2_CHECKSUM_LEN]);
+        if incompat_flags & MAVLINK_IFLAG_SIGNED != 0 {
+            frame.extend([0x33; MAVLINK_V2_SIGNATURE_LEN]);
+        }
+        frame
+    }
+
+    #[test]
+    fn test_take_frames() {
+        let frame_a = frame(2, 0);
+        let frame_b = frame(0, MAVLINK_IFLAG_SIGNED);
+
+        // Leading garbage, two frames and the start of a third one
+        let mut buf = vec![0x00, 0x42];
+        buf.extend(&frame_a);
+        buf.extend(&frame_b);
+        buf.extend([MAVLINK_V2_STX, 5, 0]);
+
+        assert_eq!(take_frames(&mut buf), vec![frame_a, frame_b]);
+        assert_eq!(buf, vec![MAVLINK_V2_STX, 5, 0]);
+
+        // Garbage only
+        let mut buf = vec![1, 2, 3];
+        assert!(take_frames(&mut buf).is_empty());
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_ground_command_roundtrip() {
+        let msg = MavMessage::GroundCommand(crater_gnc::mav_crater::GroundCommand_DATA {
+            command: crater_gnc::mav_crater::GroundCommandId::FmmArm,
+        });
+        let header = MavHeader {
+            system_id: 255,
+            component_id: 190,
+            sequence: 7,
+        };
+
+        let mut buf = vec![];
+        write_v2_msg(&mut buf, header, &msg).unwrap();
+        write_v2_msg(&mut buf, header, &msg).unwrap();
+
+        let frames = take_frames(&mut buf);
+        assert_eq!(frames.len(), 2);
+        assert!(matches!(
+            parse_frame(&frames[0]),
+            Some(MavMessage::GroundCommand(cmd))
+                if cmd.command == crater_gnc::mav_crater::GroundCommandId::FmmArm
+        ));
+    }
+}
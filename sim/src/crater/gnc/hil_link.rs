@@ -0,0 +1,327 @@
+use std::{
+    collections::VecDeque,
+    io::{Read, Write},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result, anyhow};
+use chrono::TimeDelta;
+use crater_gnc::{
+    InstantU64, MavHeader,
+    datatypes::sensors::{
+        GnssSensorSample, GpsSensorSample, ImuSensorSample, MagnetometerSensorSample,
+        PressureSensorSample,
+    },
+    mav_crater::{
+        GnssFixType, GnssSensorId, HilTimeSync_DATA, ImuSensorId, MavMessage, PressureSensorId,
+    },
+    write_v2_msg,
+};
+use log::{info, warn};
+use serialport::SerialPort;
+
+use crate::{
+    core::time::{Clock, Timestamp},
+    crater::{
+        channels,
+        environment::LaunchSite,
+        gnc::gs_link::{parse_frame, take_frames},
+    },
+    nodes::{Node, NodeContext, StepResult},
+    telemetry::{TelemetryReceiver, TelemetrySender, Timestamped},
+    utils::capacity::Capacity::Unbounded,
+};
+
+/// Fix reported for the ideal GPS: a good 3D fix
+const GNSS_NUM_SATS: u8 = 12;
+const GNSS_H_ACC_M: f32 = 2.5;
+const GNSS_V_ACC_M: f32 = 4.0;
+const GNSS_SPEED_ACC_M_S: f32 = 0.3;
+
+/// Sync messages waiting for their echo, the older ones are considered lost
+const MAX_PENDING_SYNCS: usize = 16;
+
+/// Timing of the link with the flight computer in the loop
+#[derive(Debug, Clone, Default)]
+pub struct HilLinkStatus {
+    /// Wall time from sending a time sync to receiving its echo
+    pub round_trip_ms: f64,
+    /// Simulation time behind the wall clock, when the simulation cannot keep up
+    pub lag_ms: f64,
+}
+
+/// Decimates a stream to a rate, on average
+#[derive(Debug, Clone)]
+struct Rate {
+    period_s: f64,
+    next_s: f64,
+}
+
+impl Rate {
+    /// Never due at a rate of 0
+    fn new(rate_hz: f64) -> Self {
+        if rate_hz > 0.0 {
+            Self {
+                period_s: 1.0 / rate_hz,
+                next_s: 0.0,
+            }
+        } else {
+            Self {
+                period_s: f64::INFINITY,
+                next_s: f64::INFINITY,
+            }
+        }
+    }
+
+    fn is_due(&mut self, t_s: f64) -> bool {
+        if t_s < self.next_s {
+            return false;
+        }
+        self.next_s = (self.next_s + self.period_s).max(t_s);
+        true
+    }
+}
+
+fn sim_time_us(t: Timestamp) -> i64 {
+    t.monotonic.elapsed().num_microseconds().unwrap_or(0)
+}
+
+/// Hardware-in-the-loop: streams the samples of the simulated sensors to the flight computer, as
+/// MAVLink messages over a serial port, in place of its sensors (fsw `hil` feature).
+///
+/// The flight computer runs on its own clock, so the simulation is paced to the wall clock. Time
+/// sync messages let the flight computer rebase the samples to its clock, and their echoes
+/// measure the round trip of the link.
+pub struct HilLink {
+    port: Box<dyn SerialPort>,
+    rx_buf: Vec<u8>,
+    sequence: u8,
+    site: LaunchSite,
+
+    rx_imu: TelemetryReceiver<ImuSensorSample>,
+    rx_pressure: TelemetryReceiver<PressureSensorSample>,
+    rx_gps: TelemetryReceiver<GpsSensorSample>,
+    rx_mag: TelemetryReceiver<MagnetometerSensorSample>,
+    tx_link: TelemetrySender<HilLinkStatus>,
+
+    imu_rate: Rate,
+    pressure_rate: Rate,
+    gnss_rate: Rate,
+    mag_rate: Rate,
+    sync_rate: Rate,
+
+    /// Wall time of the first step
+    wall_start: Option<Instant>,
+    /// Sync messages sent, by simulation time, with their wall time
+    pending_syncs: VecDeque<(i64, Instant)>,
+    lag_ms: f64,
+}
+
+impl HilLink {
+    pub fn new(ctx: NodeContext) -> Result<Self> {
+        let params = ctx.parameters().get_map("sim.hil")?;
+        let port_name = params.get_param("port")?.value_string()?;
+        let baudrate = params.get_param("baudrate")?.value_int()?;
+        let rate =
+            |name: &str| -> Result<Rate> { Ok(Rate::new(params.get_param(name)?.value_float()?)) };
+
+        let port = serialport::new(&port_name, baudrate as u32)
+            .timeout(Duration::from_millis(10))
+            .open()
+            .with_context(|| format!("Cannot open the HIL serial port {port_name}"))?;
+        port.clear(serialport::ClearBuffer::All)?;
+        info!("HIL link: MAVLink over {port_name} at {baudrate} baud");
+
+        Ok(Self {
+            port,
+            rx_buf: vec![],
+            sequence: 0,
+            site: LaunchSite::from_params(ctx.parameters())?,
+            rx_imu: ctx
+                .telemetry()
+                .subscribe(channels::sensors::IDEAL_IMU, Unbounded)?,
+            rx_pressure: ctx
+                .telemetry()
+                .subscribe(channels::sensors::STATIC_PRESSURE, Unbounded)?,
+            rx_gps: ctx
+                .telemetry()
+                .subscribe(channels::sensors::IDEAL_GPS, Unbounded)?,
+            rx_mag: ctx
+                .telemetry()
+                .subscribe(channels::sensors::IDEAL_MAGNETOMETER, Unbounded)?,
+            tx_link: ctx.telemetry().publish(channels::sim::HIL_LINK)?,
+            imu_rate: rate("imu_rate")?,
+            pressure_rate: rate("pressure_rate")?,
+            gnss_rate: rate("gnss_rate")?,
+            mag_rate: rate("mag_rate")?,
+            sync_rate: rate("sync_rate")?,
+            wall_start: None,
+            pending_syncs: VecDeque::new(),
+            lag_ms: 0.0,
+        })
+    }
+
+    fn latest<T>(rx: &TelemetryReceiver<T>) -> Option<Timestamped<T>> {
+        let mut latest = None;
+        while let Ok(sample) = rx.try_recv() {
+            latest = Some(sample);
+        }
+        latest
+    }
+
+    /// Sleeps until the wall clock catches up with the simulation time
+    fn pace(&mut self, t_s: f64) {
+        let wall_start = *self.wall_start.get_or_insert_with(Instant::now);
+        let ahead_s = t_s - wall_start.elapsed().as_secs_f64();
+
+        if ahead_s > 0.0 {
+            thread::sleep(Duration::from_secs_f64(ahead_s));
+            self.lag_ms = 0.0;
+        } else {
+            self.lag_ms = -ahead_s * 1000.0;
+        }
+    }
+
+    /// Handles the echoes of the time syncs. The other messages of the flight computer are
+    /// ignored.
+    fn receive(&mut self, t: Timestamp) -> Result<()> {
+        let mut chunk = [0u8; 1024];
+        while self.port.bytes_to_read()? > 0 {
+            let len = self.port.read(&mut chunk)?;
+            self.rx_buf.extend_from_slice(&chunk[..len]);
+        }
+
+        for frame in take_frames(&mut self.rx_buf) {
+            let Some(MavMessage::HilTimeSync(sync)) = parse_frame(&frame) else {
+                continue;
+            };
+
+            let Some(i) = self
+                .pending_syncs
+                .iter()
+                .position(|(sim_time_us, _)| *sim_time_us == sync.sim_time_us)
+            else {
+                continue;
+            };
+            let (_, sent) = self.pending_syncs[i];
+            self.pending_syncs.drain(..=i);
+
+            self.tx_link.send(
+                t,
+                HilLinkStatus {
+                    round_trip_ms: sent.elapsed().as_secs_f64() * 1000.0,
+                    lag_ms: self.lag_ms,
+                },
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Fix of a receiver at the position of the ideal GPS
+    fn gnss_fix(&self, gps: &GpsSensorSample) -> GnssSensorSample {
+        let (lat, lon, alt) = self.site.geodetic(&gps.pos_n_m.map(|v| v as f64));
+
+        GnssSensorSample {
+            fix_type: GnssFixType::Fix3d,
+            num_sats: GNSS_NUM_SATS,
+            lat_deg_e7: (lat.to_degrees() * 1e7).round() as i32,
+            lon_deg_e7: (lon.to_degrees() * 1e7).round() as i32,
+            alt_msl_m: alt as f32,
+            vel_n_m_s: gps.vel_n_m_s,
+            h_acc_m: GNSS_H_ACC_M,
+            v_acc_m: GNSS_V_ACC_M,
+            speed_acc_m_s: GNSS_SPEED_ACC_M_S,
+        }
+    }
+}
+
+impl Node for HilLink {
+    fn step(&mut self, _: usize, _: TimeDelta, clock: &dyn Clock) -> Result<StepResult> {
+        let t = Timestamp::now(clock);
+        let t_s = t.monotonic.elapsed_seconds_f64();
+        let t_gnc = |t: Timestamp| -> crater_gnc::Instant {
+            InstantU64::from_ticks(sim_time_us(t) as u64).into()
+        };
+
+        self.pace(t_s);
+        self.receive(t)?;
+
+        let mut messages: Vec<MavMessage> = vec![];
+
+        if self.sync_rate.is_due(t_s) {
+            messages.push(MavMessage::HilTimeSync(HilTimeSync_DATA {
+                sim_time_us: sim_time_us(t),
+                fc_time_us: 0,
+            }));
+
+            self.pending_syncs
+                .push_back((sim_time_us(t), Instant::now()));
+            if self.pending_syncs.len() > MAX_PENDING_SYNCS {
+                self.pending_syncs.pop_front();
+            }
+        }
+
+        if let Some(Timestamped(ts, imu)) = Self::latest(&self.rx_imu)
+            && self.imu_rate.is_due(t_s)
+        {
+            messages.push(imu.to_mavlink(ImuSensorId::Icm42688, t_gnc(ts)));
+        }
+        if let Some(Timestamped(ts, pressure)) = Self::latest(&self.rx_pressure)
+            && self.pressure_rate.is_due(t_s)
+        {
+            messages.push(pressure.to_mavlink(PressureSensorId::Bmp390, t_gnc(ts)));
+        }
+        if let Some(Timestamped(ts, gps)) = Self::latest(&self.rx_gps)
+            && self.gnss_rate.is_due(t_s)
+        {
+            let fix = self.gnss_fix(&gps);
+            messages.push(fix.to_mavlink(GnssSensorId::UbloxM9, t_gnc(ts)));
+        }
+        if let Some(Timestamped(ts, mag)) = Self::latest(&self.rx_mag)
+            && self.mag_rate.is_due(t_s)
+        {
+            messages.push(mag.to_mavlink(t_gnc(ts)));
+        }
+
+        if messages.is_empty() {
+            return Ok(StepResult::Continue);
+        }
+
+        let mut frames = vec![];
+        for msg in messages.iter() {
+            let header = MavHeader {
+                system_id: 1,
+                component_id: 1,
+                sequence: self.sequence,
+            };
+            self.sequence = self.sequence.wrapping_add(1);
+
+            write_v2_msg(&mut frames, header, msg)
+                .map_err(|e| anyhow!("Error encoding a MAVLink message: {e:?}"))?;
+        }
+
+        if let Err(e) = self.port.write_all(&frames) {
+            warn!("HIL link: cannot write to the serial port: {e}");
+        }
+
+        Ok(StepResult::Continue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate() {
+        // 200 Hz out of 3 ms steps
+        let mut rate = Rate::new(200.0);
+        let sent = (0..1000).filter(|i| rate.is_due(*i as f64 * 0.003)).count();
+        assert!((595..=605).contains(&sent), "{sent}");
+
+        let mut never = Rate::new(0.0);
+        assert!(!(0..100).any(|i| never.is_due(i as f64)));
+    }
+}
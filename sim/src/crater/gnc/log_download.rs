@@ -0,0 +1,197 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Result, anyhow};
+use crater_gnc::{
+    Message,
+    datatypes::version::Versioned,
+    io::{
+        flash_log::{LOG_HEADER_LEN, LogHeader},
+        log_transfer::LOG_DATA_LEN,
+    },
+    mav_crater::{LogData_DATA, LogEntry_DATA, MavMessage},
+};
+
+use super::gs_link::{parse_frame, take_frames};
+
+/// Download of an onboard log, chunk by chunk. Chunks may be lost or arrive out of order: the
+/// missing ranges are requested again until the log is complete.
+#[derive(Debug, Clone)]
+pub struct LogDownload {
+    pub id: u16,
+    pub time_utc: u32,
+    data: Vec<u8>,
+    received: Vec<bool>,
+}
+
+impl LogDownload {
+    pub fn new(entry: &LogEntry_DATA) -> Self {
+        let size = entry.size as usize;
+        Self {
+            id: entry.id,
+            time_utc: entry.time_utc,
+            data: vec![0; size],
+            received: vec![false; size.div_ceil(LOG_DATA_LEN)],
+        }
+    }
+
+    pub fn size(&self) -> u32 {
+        self.data.len() as u32
+    }
+
+    /// Stores a chunk of the log. Chunks of other logs, and chunks not aligned on the chunk
+    /// size, as only requested by other clients, are ignored.
+    pub fn on_data(&mut self, chunk: &LogData_DATA) {
+        let ofs = chunk.ofs as usize;
+        let count = chunk.count as usize;
+        if chunk.id != self.id || count == 0 || ofs % LOG_DATA_LEN != 0 {
+            return;
+        }
+
+        let end = (ofs + count).min(self.data.len());
+        if ofs >= end {
+            return;
+        }
+        self.data[ofs..end].copy_from_slice(&chunk.data[..end - ofs]);
+
+        // The last chunk of the log is the only short one
+        if end - ofs == LOG_DATA_LEN || end == self.data.len() {
+            self.received[ofs / LOG_DATA_LEN] = true;
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.received.iter().all(|&r| r)
+    }
+
+    /// Whether any byte of the range is still to be received
+    pub fn is_missing(&self, ofs: u32, count: u32) -> bool {
+        self.missing()
+            .iter()
+            .any(|&(o, c)| o < ofs.saturating_add(count) && o + c > ofs)
+    }
+
+    /// Ranges still to be received, as (offset, count) in bytes
+    pub fn missing(&self) -> Vec<(u32, u32)> {
+        let mut ranges: Vec<(u32, u32)> = vec![];
+
+        for (i, _) in self.received.iter().enumerate().filter(|(_, r)| !**r) {
+            let ofs = (i * LOG_DATA_LEN) as u32;
+            let count = LOG_DATA_LEN.min(self.data.len() - i * LOG_DATA_LEN) as u32;
+
+            match ranges.last_mut() {
+                Some((last_ofs, last_count)) if *last_ofs + *last_count == ofs => {
+                    *last_count += count
+                }
+                _ => ranges.push((ofs, count)),
+            }
+        }
+
+        ranges
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// Decodes a log as written by the flight software: a log header, then MAVLink frames. Fails if
+/// the records are of an incompatible version. Bytes that are not valid frames are skipped.
+pub fn decode_log(data: &[u8]) -> Result<Vec<MavMessage>> {
+    let header = LogHeader::from_bytes(data).ok_or_else(|| anyhow!("No log header"))?;
+    MavMessage::check_version(header.records)?;
+
+    let mut buf = data[LOG_HEADER_LEN..].to_vec();
+    Ok(take_frames(&mut buf)
+        .iter()
+        .filter_map(|frame| parse_frame(frame))
+        .collect())
+}
+
+/// Number of messages of each type
+pub fn message_counts(messages: &[MavMessage]) -> BTreeMap<&'static str, usize> {
+    let mut counts = BTreeMap::new();
+    for msg in messages.iter() {
+        *counts.entry(msg.message_name()).or_default() += 1;
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use crater_gnc::{
+        datatypes::version::InterfaceVersion, io::flash_log::RecordFramer,
+        mav_crater::AdaState_DATA,
+    };
+
+    use super::*;
+
+    fn chunk(ofs: u32, log: &[u8]) -> LogData_DATA {
+        let count = LOG_DATA_LEN.min(log.len() - ofs as usize);
+        let mut chunk = LogData_DATA {
+            id: 3,
+            ofs,
+            count: count as u8,
+            ..LogData_DATA::DEFAULT
+        };
+        chunk.data[..count].copy_from_slice(&log[ofs as usize..ofs as usize + count]);
+        chunk
+    }
+
+    #[test]
+    fn test_log_download() {
+        let log: Vec<u8> = (0..400u32).map(|i| (i % 251) as u8).collect();
+        let mut download = LogDownload::new(&LogEntry_DATA {
+            id: 3,
+            size: 400,
+            ..LogEntry_DATA::DEFAULT
+        });
+        assert_eq!(download.missing(), vec![(0, 400)]);
+
+        // Chunks 1 & 2 lost
+        for ofs in [0, 270, 360] {
+            download.on_data(&chunk(ofs, &log));
+        }
+        assert!(!download.is_complete());
+        assert_eq!(download.missing(), vec![(90, 180)]);
+        assert!(download.is_missing(0, 100));
+        assert!(!download.is_missing(270, 130));
+
+        // Retransmitted
+        for ofs in [90, 180] {
+            download.on_data(&chunk(ofs, &log));
+        }
+        assert!(download.is_complete());
+        assert!(download.missing().is_empty());
+        assert_eq!(download.data(), log.as_slice());
+    }
+
+    #[test]
+    fn test_decode_log() {
+        let msg = MavMessage::AdaState(AdaState_DATA {
+            altitude_m: 100.0,
+            ..AdaState_DATA::DEFAULT
+        });
+        let log = |header: LogHeader| -> Vec<u8> {
+            let mut log = header.to_bytes().to_vec();
+            log.extend_from_slice(RecordFramer::new().frame(&msg).unwrap());
+            log
+        };
+
+        let messages = decode_log(&log(LogHeader::new(1, 0))).unwrap();
+        assert!(matches!(messages[..], [MavMessage::AdaState(_)]));
+
+        // Written before the versions were recorded, or with a newer dialect
+        for records in [
+            InterfaceVersion::new(0, 0),
+            InterfaceVersion::new(MavMessage::VERSION.major + 1, 0),
+        ] {
+            let header = LogHeader {
+                records,
+                ..LogHeader::new(1, 0)
+            };
+            assert!(decode_log(&log(header)).is_err());
+        }
+
+        assert!(decode_log(&[]).is_err());
+    }
+}
@@ -2,7 +2,13 @@ pub mod openloop;
 
 mod datatypes;
 
-pub use datatypes::{ServoPosition, MixedServoPosition};
+pub use datatypes::{AirbrakePosition, ServoPosition, MixedServoPosition};
 
 pub mod fsw;
-pub mod orchestrator;
\ No newline at end of file
+pub mod orchestrator;
+pub mod cosim;
+pub mod dual_fc;
+pub mod gs_link;
+pub mod radio_link;
+pub mod hil_link;
+pub mod log_download;
\ No newline at end of file
@@ -1,3 +1,5 @@
 mod orchestrator;
+mod scenario_player;
 
 pub use orchestrator::Orchestrator;
+pub use scenario_player::{EventScript, ScenarioPlayer, ScriptedEvent};
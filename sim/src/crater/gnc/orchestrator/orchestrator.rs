@@ -1,9 +1,15 @@
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use chrono::TimeDelta;
-use crater_gnc::{events::EventItem, mav_crater::ComponentId};
+use crater_gnc::{
+    events::EventItem,
+    mav_crater::{CommandResult, ComponentId, GroundCommand_DATA, GroundCommandId, MavMessage},
+};
+use log::{error, info, warn};
+use num_traits::FromPrimitive;
 use statig::prelude::*;
 use strum::AsRefStr;
 
+use super::scenario_player::EventScript;
 use crate::{
     core::time::{Clock, Timestamp},
     crater::{
@@ -15,36 +21,140 @@ use crate::{
 };
 
 pub struct Orchestrator {
-    rx_gnc_event: TelemetryReceiver<crater_gnc::events::EventItem>,
+    /// GNC events, unless the flight software is followed through the radio link
+    rx_gnc_event: Option<TelemetryReceiver<crater_gnc::events::EventItem>>,
+    /// Flight software downlink, as received by the ground station with the radio link
+    rx_downlink: TelemetryReceiver<MavMessage>,
     fsm: StateMachine<OrchestratorFsm>,
+
+    /// Latest configuration hash downlinked by the flight software
+    config_hash: Option<u64>,
+}
+
+/// Parses the approved configuration hash, as hex digits. Empty if no configuration is approved.
+fn parse_config_hash(hash: &str) -> Result<Option<u64>> {
+    let hash = hash.trim();
+    if hash.is_empty() {
+        return Ok(None);
+    }
+
+    u64::from_str_radix(hash.trim_start_matches("0x"), 16)
+        .map(Some)
+        .map_err(|_| anyhow!("Invalid configuration hash '{hash}', expected hex digits"))
+}
+
+/// Path of the countdown commands to the flight software
+enum CommandUplink {
+    /// Injected as events from the ground
+    Direct(TelemetrySender<GncEventItem>),
+    /// Sent as by the ground station, over the radio link
+    Radio(TelemetrySender<MavMessage>),
+}
+
+impl CommandUplink {
+    fn send(&self, t: Timestamp, command: GroundCommandId) {
+        match self {
+            CommandUplink::Direct(tx) => tx.send(
+                t,
+                EventItem {
+                    src: ComponentId::Ground,
+                    event: GncEvent::from_ground_command(command),
+                },
+            ),
+            CommandUplink::Radio(tx) => {
+                info!("Sending ground command {command:?}");
+                tx.send(t, MavMessage::GroundCommand(GroundCommand_DATA { command }));
+            }
+        }
+    }
 }
 
 impl Orchestrator {
     pub fn new(ctx: NodeContext) -> Result<Self> {
+        let radio = ctx
+            .parameters()
+            .get_param("sim.radio_link.enabled")?
+            .value_bool()?;
+        let uplink = if radio {
+            CommandUplink::Radio(ctx.telemetry().publish_mp(channels::sim::GROUND_UPLINK)?)
+        } else {
+            CommandUplink::Direct(ctx.telemetry().publish_mp(channels::gnc::GNC_EVENTS)?)
+        };
+
         let fsm = OrchestratorFsm {
+            abort_before_ignition: ctx
+                .parameters()
+                .get_param("sim.orchestrator.abort_before_ignition")?
+                .value_bool()?,
+            approved_config_hash: parse_config_hash(
+                &ctx.parameters()
+                    .get_param("sim.orchestrator.approved_config_hash")?
+                    .value_string()?,
+            )?,
+            scripted_ignition: EventScript::from_params(ctx.parameters())?
+                .is_some_and(|script| script.starts_engine()),
             tx_sim_event: ctx.telemetry().publish_mp(channels::sim::SIM_EVENTS)?,
-            tx_gnc_event: ctx.telemetry().publish_mp(channels::gnc::GNC_EVENTS)?,
+            uplink,
         }
         .state_machine();
 
         Ok(Self {
-            rx_gnc_event: ctx.telemetry().subscribe_mp(
-                channels::gnc::GNC_EVENTS,
+            rx_gnc_event: if radio {
+                None
+            } else {
+                Some(ctx.telemetry().subscribe_mp(
+                    channels::gnc::GNC_EVENTS,
+                    crate::utils::capacity::Capacity::Unbounded,
+                )?)
+            },
+            rx_downlink: ctx.telemetry().subscribe_feedback(
+                if radio {
+                    channels::sim::GROUND_DOWNLINK
+                } else {
+                    channels::gnc::DOWNLINK
+                },
                 crate::utils::capacity::Capacity::Unbounded,
             )?,
             fsm,
+            config_hash: None,
         })
     }
 }
 
 impl Node for Orchestrator {
     fn step(&mut self, _i: usize, _dt: TimeDelta, clock: &dyn Clock) -> Result<StepResult> {
+        // Events & acknowledgements are only in the downlink received through the radio link
+        let mut events = vec![];
+        while let Ok(msg) = self.rx_downlink.try_recv() {
+            match msg.1 {
+                MavMessage::ConfigHash(data) => self.config_hash = Some(data.hash),
+                MavMessage::GncEvent(data) => {
+                    if let Some(event) = GncEvent::from_u16(data.event) {
+                        events.push(EventItem {
+                            src: data.source,
+                            event,
+                        });
+                    }
+                }
+                MavMessage::CommandAck(ack) if ack.result != CommandResult::Accepted => {
+                    error!("Ground command {:?} denied", ack.command);
+                }
+                _ => {}
+            }
+        }
+        if let Some(rx_gnc_event) = &self.rx_gnc_event {
+            while let Ok(ev) = rx_gnc_event.try_recv() {
+                events.push(ev.1);
+            }
+        }
+
         let mut step_ctx = StepContext {
             time: Timestamp::now(clock),
+            config_hash: self.config_hash,
         };
 
-        while let Ok(ev) = self.rx_gnc_event.try_recv() {
-            self.fsm.handle_with_context(&ev.1.into(), &mut step_ctx);
+        for ev in events {
+            self.fsm.handle_with_context(&ev.into(), &mut step_ctx);
         }
 
         self.fsm.handle_with_context(&Event::Step, &mut step_ctx);
@@ -55,10 +165,19 @@ impl Node for Orchestrator {
 
 pub struct StepContext {
     time: Timestamp,
+    config_hash: Option<u64>,
 }
 
 pub struct OrchestratorFsm {
-    tx_gnc_event: TelemetrySender<GncEventItem>,
+    /// Scrub the launch at the end of the arming delay instead of starting the engine
+    abort_before_ignition: bool,
+    /// Hash of the reviewed flight configuration. The launch is scrubbed if the flight software
+    /// reports another one at arming.
+    approved_config_hash: Option<u64>,
+    /// The engine is started by the scenario file instead of at the end of the arming delay
+    scripted_ignition: bool,
+
+    uplink: CommandUplink,
     tx_sim_event: TelemetrySender<SimEvent>,
 }
 
@@ -73,13 +192,8 @@ impl OrchestratorFsm {
     fn init(&mut self, context: &mut StepContext, event: &Event) -> Response<State> {
         match event {
             Event::Step => {
-                self.tx_gnc_event.send(
-                    context.time,
-                    EventItem {
-                        src: ComponentId::Ground,
-                        event: GncEvent::CmdFmmCalibrate,
-                    },
-                );
+                self.uplink
+                    .send(context.time, GroundCommandId::FmmCalibrate);
                 Transition(State::wait_ready())
             }
             _ => Super,
@@ -97,13 +211,7 @@ impl OrchestratorFsm {
 
     #[action]
     fn enter_arm(&mut self, context: &mut StepContext) {
-        self.tx_gnc_event.send(
-            context.time,
-            EventItem {
-                src: ComponentId::Ground,
-                event: crater_gnc::events::Event::CmdFmmArm,
-            },
-        );
+        self.uplink.send(context.time, GroundCommandId::FmmArm);
     }
 
     #[state(entry_action = "enter_arm")]
@@ -116,7 +224,13 @@ impl OrchestratorFsm {
         match event {
             Event::Step => {
                 if context.time.monotonic - entry_time.monotonic > TimeDelta::seconds(1) {
-                    self.tx_sim_event.send(context.time, SimEvent::StartEngine);
+                    if self.abort_before_ignition || !self.verify_config(context.config_hash) {
+                        return Transition(State::aborted());
+                    }
+
+                    if !self.scripted_ignition {
+                        self.tx_sim_event.send(context.time, SimEvent::StartEngine);
+                    }
                     Transition(State::flying(context.time))
                 } else {
                     Handled
@@ -126,6 +240,14 @@ impl OrchestratorFsm {
         }
     }
 
+    #[state]
+    fn aborted(event: &Event) -> Response<State> {
+        match event {
+            Event::Step => Handled,
+            _ => Super,
+        }
+    }
+
     #[state]
     fn flying(
         entry_time: &mut Timestamp,
@@ -144,6 +266,33 @@ impl OrchestratorFsm {
 }
 
 impl OrchestratorFsm {
+    /// Whether the configuration frozen by the flight software at arming is the approved one
+    fn verify_config(&self, config_hash: Option<u64>) -> bool {
+        match (config_hash, self.approved_config_hash) {
+            (Some(hash), Some(approved)) if hash == approved => {
+                info!("Flight configuration {hash:016x} verified");
+                true
+            }
+            (Some(hash), Some(approved)) => {
+                error!(
+                    "Flight configuration {hash:016x} does not match the approved one ({approved:016x})"
+                );
+                false
+            }
+            (None, Some(_)) => {
+                error!("Flight configuration hash not received, cannot verify it");
+                false
+            }
+            (hash, None) => {
+                warn!(
+                    "Flight configuration {} not verified: no approved configuration in 'sim.orchestrator.approved_config_hash'",
+                    hash.map_or("(not received)".to_string(), |hash| format!("{hash:016x}"))
+                );
+                true
+            }
+        }
+    }
+
     fn after_transition(&mut self, source: &State, target: &State, context: &mut StepContext) {
         self.tx_sim_event.send(
             context.time,
@@ -155,3 +304,22 @@ impl OrchestratorFsm {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config_hash() {
+        assert_eq!(parse_config_hash("").unwrap(), None);
+        assert_eq!(
+            parse_config_hash("0x00000000deadbeef").unwrap(),
+            Some(0xdeadbeef)
+        );
+        assert_eq!(
+            parse_config_hash("cbf29ce484222325").unwrap(),
+            Some(0xcbf29ce484222325)
+        );
+        assert!(parse_config_hash("not a hash").is_err());
+    }
+}
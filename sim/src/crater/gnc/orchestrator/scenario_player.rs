@@ -0,0 +1,229 @@
+use std::fs;
+
+use anyhow::{Context, Result, anyhow};
+use chrono::TimeDelta;
+use crater_gnc::{events::EventItem, mav_crater::ComponentId};
+use log::info;
+use serde::Deserialize;
+
+use crate::{
+    core::time::{Clock, Timestamp},
+    crater::{
+        channels,
+        events::{GncEvent, GncEventItem, SimEvent},
+    },
+    nodes::{Node, NodeContext, StepResult},
+    parameters::ParameterMap,
+    telemetry::TelemetrySender,
+};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ScenarioAction {
+    /// Sim event without data, by name (eg. "StartEngine")
+    Sim(String),
+    /// GNC event by name, sent from the ground (eg. "CmdDeployMain")
+    Gnc(String),
+    /// No samples from `sensor` for `duration` seconds
+    Dropout { sensor: String, duration: f64 },
+    /// Release of a part configured in `sim.rocket.ejections`
+    Eject(String),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ScenarioEntry {
+    time: f64,
+    #[serde(flatten)]
+    action: ScenarioAction,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ScenarioFile {
+    events: Vec<ScenarioEntry>,
+}
+
+/// Event injected by a scenario
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptedEvent {
+    Sim(SimEvent),
+    Gnc(GncEvent),
+}
+
+/// Timed events of a scenario file, in chronological order:
+///
+/// ```toml
+/// [[events]]
+/// time = 0.5
+/// sim = "StartEngine"
+///
+/// [[events]]
+/// time = 12
+/// dropout = { sensor = "gps", duration = 3 }
+///
+/// [[events]]
+/// time = 30
+/// gnc = "CmdDeployMain"
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EventScript {
+    events: Vec<(f64, ScriptedEvent)>,
+    next: usize,
+}
+
+impl EventScript {
+    pub fn from_toml(script: &str) -> Result<Self> {
+        let file: ScenarioFile = toml::from_str(script)?;
+
+        let mut events = file
+            .events
+            .into_iter()
+            .map(|entry| Ok((entry.time, Self::event(entry.action)?)))
+            .collect::<Result<Vec<_>>>()?;
+        // Stable, so that simultaneous events are sent in the order they are written
+        events.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        Ok(Self { events, next: 0 })
+    }
+
+    /// Script configured in `sim.orchestrator.scenario`. None if empty.
+    pub fn from_params(params: &ParameterMap) -> Result<Option<Self>> {
+        let path = params
+            .get_param("sim.orchestrator.scenario")?
+            .value_string()?;
+        if path.is_empty() {
+            return Ok(None);
+        }
+
+        let script = fs::read_to_string(&path).context(format!("path={path}"))?;
+        Ok(Some(
+            Self::from_toml(&script).context(format!("Invalid scenario file '{path}'"))?,
+        ))
+    }
+
+    fn event(action: ScenarioAction) -> Result<ScriptedEvent> {
+        Ok(match action {
+            ScenarioAction::Sim(name) => ScriptedEvent::Sim(match name.as_str() {
+                "StartEngine" => SimEvent::StartEngine,
+                _ => return Err(anyhow!("Unknown or non scriptable sim event '{name}'")),
+            }),
+            ScenarioAction::Gnc(name) => ScriptedEvent::Gnc(
+                GncEvent::from_name(&name).ok_or(anyhow!("Unknown GNC event '{name}'"))?,
+            ),
+            ScenarioAction::Dropout { sensor, duration } => {
+                ScriptedEvent::Sim(SimEvent::SensorDropout {
+                    sensor,
+                    duration_s: duration,
+                })
+            }
+            ScenarioAction::Eject(name) => ScriptedEvent::Sim(SimEvent::EjectMass { name }),
+        })
+    }
+
+    /// Whether the scenario starts the engine, instead of the orchestrator
+    pub fn starts_engine(&self) -> bool {
+        self.events
+            .iter()
+            .any(|(_, event)| *event == ScriptedEvent::Sim(SimEvent::StartEngine))
+    }
+
+    /// Events due by `t_s`, not returned yet
+    pub fn due(&mut self, t_s: f64) -> &[(f64, ScriptedEvent)] {
+        let first = self.next;
+        while self.events.get(self.next).is_some_and(|(t, _)| *t <= t_s) {
+            self.next += 1;
+        }
+
+        &self.events[first..self.next]
+    }
+}
+
+/// Plays the scenario file in `sim.orchestrator.scenario`, publishing its events at their
+/// scheduled time. Does nothing if no scenario is configured.
+pub struct ScenarioPlayer {
+    script: EventScript,
+
+    tx_sim_event: TelemetrySender<SimEvent>,
+    tx_gnc_event: TelemetrySender<GncEventItem>,
+}
+
+impl ScenarioPlayer {
+    pub fn new(ctx: NodeContext) -> Result<Self> {
+        Ok(Self {
+            script: EventScript::from_params(ctx.parameters())?.unwrap_or_default(),
+            tx_sim_event: ctx.telemetry().publish_mp(channels::sim::SIM_EVENTS)?,
+            tx_gnc_event: ctx.telemetry().publish_mp(channels::gnc::GNC_EVENTS)?,
+        })
+    }
+}
+
+impl Node for ScenarioPlayer {
+    fn step(&mut self, _i: usize, _dt: TimeDelta, clock: &dyn Clock) -> Result<StepResult> {
+        let t = Timestamp::now(clock);
+
+        for (_, event) in self.script.due(t.monotonic.elapsed_seconds_f64()) {
+            info!("Scenario event: {event:?}");
+
+            match event {
+                ScriptedEvent::Sim(event) => self.tx_sim_event.send(t, event.clone()),
+                ScriptedEvent::Gnc(event) => self.tx_gnc_event.send(
+                    t,
+                    EventItem {
+                        src: ComponentId::Ground,
+                        event: *event,
+                    },
+                ),
+            }
+        }
+
+        Ok(StepResult::Continue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_script() -> Result<()> {
+        let mut script = EventScript::from_toml(
+            r#"
+            [[events]]
+            time = 30
+            gnc = "CmdDeployMain"
+
+            [[events]]
+            time = 0.5
+            sim = "StartEngine"
+
+            [[events]]
+            time = 12
+            dropout = { sensor = "gps", duration = 3 }
+            "#,
+        )?;
+        assert!(script.starts_engine());
+
+        assert!(script.due(0.4).is_empty());
+        assert_eq!(
+            script.due(0.5),
+            [(0.5, ScriptedEvent::Sim(SimEvent::StartEngine))]
+        );
+        assert!(script.due(0.6).is_empty());
+
+        let due = script.due(100.0);
+        assert_eq!(due.len(), 2);
+        assert_eq!(
+            due[0].1,
+            ScriptedEvent::Sim(SimEvent::SensorDropout {
+                sensor: "gps".to_string(),
+                duration_s: 3.0
+            })
+        );
+        assert_eq!(due[1].1, ScriptedEvent::Gnc(GncEvent::CmdDeployMain));
+        assert!(script.due(200.0).is_empty());
+
+        assert!(EventScript::from_toml("[[events]]\ntime = 1\ngnc = \"CmdFly\"").is_err());
+        assert!(EventScript::from_toml("[[events]]\ntime = 1\nsim = \"RailExit\"").is_err());
+
+        Ok(())
+    }
+}
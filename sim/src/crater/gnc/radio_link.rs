@@ -0,0 +1,249 @@
+use std::collections::VecDeque;
+
+use anyhow::{Result, anyhow};
+use chrono::TimeDelta;
+use crater_gnc::{
+    Instant, InstantU64, MavHeader,
+    io::airtime::{AirtimeBudget, AirtimeScheduler, LoraModulation},
+    mav_crater::MavMessage,
+    write_v2_msg,
+};
+use log::{info, warn};
+
+use super::gs_link::parse_frame;
+use crate::{
+    core::time::{Clock, Timestamp},
+    crater::{channels, events::GncEventItem},
+    nodes::{Node, NodeContext, StepResult},
+    telemetry::{TelemetryReceiver, TelemetrySender, Timestamped},
+    utils::capacity::Capacity::Unbounded,
+};
+
+/// Ground messages on the air, received by the flight computer once sent in full
+#[derive(Debug, Clone)]
+struct UplinkPackets {
+    modulation: LoraModulation,
+    /// Messages with the time they are received [µs], in the order they were sent
+    on_air: VecDeque<(u64, MavMessage)>,
+    sequence: u8,
+}
+
+impl UplinkPackets {
+    fn new(modulation: LoraModulation) -> Self {
+        Self {
+            modulation,
+            on_air: VecDeque::new(),
+            sequence: 0,
+        }
+    }
+
+    /// Sends a message at `t_us`, after the ones still on the air
+    fn send(&mut self, t_us: u64, msg: MavMessage) -> Result<()> {
+        let header = MavHeader {
+            system_id: 255,
+            component_id: 190,
+            sequence: self.sequence,
+        };
+        self.sequence = self.sequence.wrapping_add(1);
+
+        let mut frame = vec![];
+        write_v2_msg(&mut frame, header, &msg)
+            .map_err(|e| anyhow!("Error encoding a MAVLink message: {e:?}"))?;
+
+        let start_us = self
+            .on_air
+            .back()
+            .map_or(t_us, |(end_us, _)| t_us.max(*end_us));
+        let end_us = start_us + self.modulation.time_on_air_us(frame.len()).ceil() as u64;
+        self.on_air.push_back((end_us, msg));
+
+        Ok(())
+    }
+
+    /// The oldest message received by `now_us`
+    fn receive(&mut self, now_us: u64) -> Option<MavMessage> {
+        let (end_us, _) = self.on_air.front()?;
+        if *end_us > now_us {
+            return None;
+        }
+        self.on_air.pop_front().map(|(_, msg)| msg)
+    }
+}
+
+/// LoRa link between the flight computer & the ground segment, with the airtime scheduler of the
+/// flight radio: the flight software outputs reach the ground by priority within the airtime
+/// budget, and the ground messages reach the flight software uplink after their time on air.
+///
+/// ```toml
+/// [sim.radio_link]
+/// enabled = { val = true, type = "bool" }
+/// spreading_factor = { val = 7, type = "int" }
+/// bandwidth = { val = 125000.0, type = "float" }
+/// duty_cycle = { val = 0.1, type = "float" }
+/// max_burst = { val = 0.5, type = "float" }
+/// ```
+pub struct RadioLink {
+    scheduler: AirtimeScheduler,
+    uplink: UplinkPackets,
+    num_dropped: usize,
+
+    rx_events: TelemetryReceiver<GncEventItem>,
+    rx_downlink: TelemetryReceiver<MavMessage>,
+    rx_acks: TelemetryReceiver<MavMessage>,
+    rx_logs: TelemetryReceiver<MavMessage>,
+    rx_ground: TelemetryReceiver<MavMessage>,
+    tx_ground: TelemetrySender<MavMessage>,
+    tx_uplink: TelemetrySender<MavMessage>,
+}
+
+impl RadioLink {
+    pub fn new(ctx: NodeContext) -> Result<Self> {
+        let params = ctx.parameters().get_map("sim.radio_link")?;
+
+        let spreading_factor = params.get_param("spreading_factor")?.value_int()?;
+        if !(6..=12).contains(&spreading_factor) {
+            return Err(anyhow!(
+                "Radio link spreading factor {spreading_factor} out of 6 to 12"
+            ));
+        }
+        let duty_cycle = params.get_param("duty_cycle")?.value_float()?;
+        if duty_cycle <= 0.0 || duty_cycle > 1.0 {
+            return Err(anyhow!("Radio link duty cycle {duty_cycle} out of ]0, 1]"));
+        }
+
+        let modulation = LoraModulation {
+            spreading_factor: spreading_factor as u8,
+            bandwidth_hz: params.get_param("bandwidth")?.value_float()? as f32,
+            ..Default::default()
+        };
+        let modulation = LoraModulation {
+            low_data_rate_optimize: modulation.symbol_time_us() > 16_000.0,
+            ..modulation
+        };
+        let max_burst_us = (params.get_param("max_burst")?.value_float()? * 1e6) as f32;
+
+        info!(
+            "Radio link: SF{} at {:.0} kHz, {:.0} % duty cycle",
+            modulation.spreading_factor,
+            modulation.bandwidth_hz / 1000.0,
+            duty_cycle * 100.0
+        );
+
+        Ok(Self {
+            scheduler: AirtimeScheduler::new(
+                modulation,
+                AirtimeBudget::new(duty_cycle as f32, max_burst_us),
+            ),
+            uplink: UplinkPackets::new(modulation),
+            num_dropped: 0,
+            rx_events: ctx
+                .telemetry()
+                .subscribe_mp(channels::gnc::GNC_EVENTS, Unbounded)?,
+            rx_downlink: ctx
+                .telemetry()
+                .subscribe(channels::gnc::DOWNLINK, Unbounded)?,
+            rx_acks: ctx
+                .telemetry()
+                .subscribe(channels::gnc::COMMAND_ACK, Unbounded)?,
+            rx_logs: ctx
+                .telemetry()
+                .subscribe(channels::gnc::LOG_TRANSFER, Unbounded)?,
+            rx_ground: ctx
+                .telemetry()
+                .subscribe_mp(channels::sim::GROUND_UPLINK, Unbounded)?,
+            tx_ground: ctx.telemetry().publish(channels::sim::GROUND_DOWNLINK)?,
+            tx_uplink: ctx.telemetry().publish(channels::gnc::UPLINK)?,
+        })
+    }
+}
+
+impl Node for RadioLink {
+    fn step(&mut self, _: usize, _: TimeDelta, clock: &dyn Clock) -> Result<StepResult> {
+        let t = Timestamp::now(clock);
+        let t_us = t.monotonic.elapsed().num_microseconds().unwrap_or(0) as u64;
+        let t_gnc: Instant = InstantU64::from_ticks(t_us).into();
+
+        while let Ok(Timestamped(_, msg)) = self.rx_ground.try_recv() {
+            self.uplink.send(t_us, msg)?;
+        }
+        while let Some(msg) = self.uplink.receive(t_us) {
+            self.tx_uplink.send(t, msg);
+        }
+
+        while let Ok(Timestamped(t_ev, item)) = self.rx_events.try_recv() {
+            let t_ev = t_ev.monotonic.elapsed().num_microseconds().unwrap_or(0) as u64;
+            self.scheduler
+                .push(item.to_mavlink(InstantU64::from_ticks(t_ev).into()));
+        }
+        while let Ok(Timestamped(_, ack)) = self.rx_acks.try_recv() {
+            self.scheduler.push(ack);
+        }
+        while let Ok(Timestamped(_, msg)) = self.rx_downlink.try_recv() {
+            self.scheduler.push(msg);
+        }
+        while let Ok(Timestamped(_, log)) = self.rx_logs.try_recv() {
+            self.scheduler.push(log);
+        }
+
+        if self.scheduler.num_dropped() > self.num_dropped {
+            self.num_dropped = self.scheduler.num_dropped();
+            warn!(
+                "Radio link: {} downlink messages dropped, queue full",
+                self.num_dropped
+            );
+        }
+
+        while let Some(frame) = self.scheduler.next_frame(t_gnc) {
+            if let Some(msg) = parse_frame(frame) {
+                self.tx_ground.send(t, msg);
+            }
+        }
+
+        Ok(StepResult::Continue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crater_gnc::mav_crater::{GroundCommand_DATA, GroundCommandId};
+
+    use super::*;
+
+    fn command(command: GroundCommandId) -> MavMessage {
+        MavMessage::GroundCommand(GroundCommand_DATA { command })
+    }
+
+    #[test]
+    fn test_uplink_packets() {
+        let mut uplink = UplinkPackets::new(LoraModulation::default());
+
+        uplink
+            .send(0, command(GroundCommandId::FmmCalibrate))
+            .unwrap();
+        uplink.send(0, command(GroundCommandId::FmmArm)).unwrap();
+
+        // Received once on the air in full, one after the other
+        let first_us = uplink.on_air[0].0;
+        let second_us = uplink.on_air[1].0;
+        assert!(first_us > 10_000);
+        assert_eq!(second_us, 2 * first_us);
+
+        assert!(uplink.receive(first_us - 1).is_none());
+        assert!(matches!(
+            uplink.receive(first_us),
+            Some(MavMessage::GroundCommand(cmd)) if cmd.command == GroundCommandId::FmmCalibrate
+        ));
+        assert!(uplink.receive(first_us).is_none());
+        assert!(matches!(
+            uplink.receive(second_us),
+            Some(MavMessage::GroundCommand(cmd)) if cmd.command == GroundCommandId::FmmArm
+        ));
+        assert!(uplink.receive(u64::MAX).is_none());
+
+        // Sent after the link went idle
+        uplink
+            .send(1_000_000, command(GroundCommandId::FmmArm))
+            .unwrap();
+        assert_eq!(uplink.on_air[0].0, 1_000_000 + first_us);
+    }
+}
@@ -0,0 +1,104 @@
+use anyhow::Result;
+
+use crater_gnc::{
+    components::ada::AdaResult,
+    datatypes::{
+        gnc::{AttitudeEstimate, NavigationOutput},
+        sensors::{ImuSensorSample, MagnetometerSensorSample},
+    },
+};
+
+use crate::crater::{
+    actuators::thermal::ServoPower,
+    aero::{aerodynamics::AeroState, envelope::FlightEnvelopeSample, stability::StabilitySample},
+    channels,
+    engine::engine::RocketEngineMassProperties,
+    events::{GncEventItem, SimEvent},
+    gnc::{AirbrakePosition, ServoPosition, dual_fc::VoterStatus},
+    logging::rerun::CraterUiLogConfig,
+    rocket::{
+        mass::RocketMassProperties,
+        rocket_data::{RocketAccelerations, RocketActions, RocketState},
+    },
+    sensors::{camera::CameraPointing, table::TableSensorSample},
+};
+
+use super::{
+    crater_csv_impl::{
+        AdaOutputCsv, AeroStateCsv, AirbrakePositionCsv, AttitudeEstimateCsv, CameraPointingCsv,
+        FlightEnvelopeCsv, GncEventCsv, ImuSampleCsv, MagnetometerSampleCsv, NavigationOutputCsv,
+        RocketAccelCsv, RocketActionsCsv, RocketEngineMassPropertiesCsv, RocketMassPropertiesCsv,
+        RocketStateCsv, ServoPositionCsv, ServoPowerCsv, SimEventCsv, StabilityCsv, TableSensorCsv,
+        VoterStatusCsv,
+    },
+    csv_logger::{CsvLogConfig, CsvLoggerBuilder},
+};
+
+/// Same channels as the Rerun UI, one file each
+impl CsvLogConfig for CraterUiLogConfig {
+    fn subscribe_csv(&self, builder: &mut CsvLoggerBuilder) -> Result<()> {
+        builder.log_telemetry::<RocketState>(channels::rocket::STATE, RocketStateCsv)?;
+        builder.log_telemetry::<RocketState>(channels::rocket::STATE_REF, RocketStateCsv)?;
+        builder.log_telemetry::<AeroState>(channels::rocket::AERO_STATE, AeroStateCsv)?;
+        builder
+            .log_telemetry::<FlightEnvelopeSample>(channels::rocket::ENVELOPE, FlightEnvelopeCsv)?;
+        builder.log_telemetry::<StabilitySample>(channels::rocket::STABILITY, StabilityCsv)?;
+        builder.log_telemetry::<RocketActions>(channels::rocket::ACTIONS, RocketActionsCsv)?;
+        builder.log_telemetry::<RocketAccelerations>(channels::rocket::ACCEL, RocketAccelCsv)?;
+        builder
+            .log_telemetry::<RocketAccelerations>(channels::rocket::ACCEL_REF, RocketAccelCsv)?;
+        builder.log_telemetry::<ServoPosition>(channels::gnc::SERVO_COMMAND, ServoPositionCsv)?;
+        builder.log_telemetry::<ServoPosition>(
+            channels::actuators::IDEAL_SERVO_POSITION,
+            ServoPositionCsv,
+        )?;
+        builder.log_telemetry::<ServoPower>(channels::actuators::SERVO_POWER, ServoPowerCsv)?;
+        builder.log_telemetry::<AirbrakePosition>(
+            channels::gnc::AIRBRAKE_COMMAND,
+            AirbrakePositionCsv,
+        )?;
+        builder.log_telemetry::<AirbrakePosition>(
+            channels::actuators::AIRBRAKE_POSITION,
+            AirbrakePositionCsv,
+        )?;
+        builder.log_telemetry::<RocketMassProperties>(
+            channels::rocket::MASS_ROCKET,
+            RocketMassPropertiesCsv,
+        )?;
+        builder.log_telemetry::<RocketEngineMassProperties>(
+            channels::rocket::MASS_ENGINE,
+            RocketEngineMassPropertiesCsv,
+        )?;
+        builder.log_telemetry::<ImuSensorSample>(channels::sensors::IDEAL_IMU, ImuSampleCsv)?;
+        builder.log_telemetry::<ImuSensorSample>(channels::sensors::IDEAL_IMU_CG, ImuSampleCsv)?;
+        builder.log_telemetry::<MagnetometerSensorSample>(
+            channels::sensors::IDEAL_MAGNETOMETER,
+            MagnetometerSampleCsv,
+        )?;
+        builder.log_telemetry::<CameraPointing>(
+            channels::sensors::CAMERA_POINTING,
+            CameraPointingCsv,
+        )?;
+        builder.log_telemetry_mp::<TableSensorSample>(
+            channels::sensors::TABLE_SENSORS,
+            TableSensorCsv,
+        )?;
+        builder.log_telemetry_mp::<SimEvent>(channels::sim::SIM_EVENTS, SimEventCsv)?;
+        builder.log_telemetry_mp::<GncEventItem>(channels::gnc::GNC_EVENTS, GncEventCsv)?;
+        builder.log_telemetry::<AdaResult>(channels::gnc::ADA_OUTPUT, AdaOutputCsv)?;
+        builder.log_telemetry::<NavigationOutput>(
+            channels::sensors::IDEAL_NAV_OUTPUT,
+            NavigationOutputCsv,
+        )?;
+        builder
+            .log_telemetry::<NavigationOutput>(channels::gnc::NAV_OUTPUT, NavigationOutputCsv)?;
+        builder.log_telemetry::<AttitudeEstimate>(
+            channels::gnc::ATTITUDE_ESTIMATE,
+            AttitudeEstimateCsv,
+        )?;
+        builder.log_telemetry::<AdaResult>(channels::dual_fc::A_ADA_OUTPUT, AdaOutputCsv)?;
+        builder.log_telemetry::<AdaResult>(channels::dual_fc::B_ADA_OUTPUT, AdaOutputCsv)?;
+        builder.log_telemetry::<VoterStatus>(channels::dual_fc::VOTER_STATUS, VoterStatusCsv)?;
+        Ok(())
+    }
+}
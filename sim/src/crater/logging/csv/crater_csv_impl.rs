@@ -0,0 +1,388 @@
+use anyhow::Result;
+use crater_gnc::{
+    components::ada::AdaResult,
+    datatypes::{
+        gnc::{AttitudeEstimate, NavigationOutput},
+        sensors::{ImuSensorSample, MagnetometerSensorSample},
+    },
+};
+
+use crate::crater::{
+    actuators::thermal::ServoPower,
+    aero::{aerodynamics::AeroState, envelope::FlightEnvelopeSample, stability::StabilitySample},
+    engine::engine::RocketEngineMassProperties,
+    events::{GncEventItem, SimEvent},
+    gnc::{
+        AirbrakePosition, ServoPosition,
+        dual_fc::{FcUnit, VoterStatus},
+    },
+    rocket::{
+        mass::RocketMassProperties,
+        rocket_data::{RocketAccelerations, RocketActions, RocketState},
+    },
+    sensors::{camera::CameraPointing, table::TableSensorSample},
+};
+
+use super::csv_logger::{CsvRow, CsvWrite};
+
+const SERVOS: [&str; 4] = ["1", "2", "3", "4"];
+
+#[derive(Default)]
+pub struct RocketStateCsv;
+
+impl CsvWrite for RocketStateCsv {
+    type Telem = RocketState;
+
+    fn write(&mut self, row: &mut CsvRow, state: RocketState) -> Result<()> {
+        let quat_nb = state.quat_nb();
+        let vel_b = state.vel_b_m_s(&quat_nb);
+
+        row.vector3("pos_n", &state.pos_n_m())
+            .vector3("vel_n", &state.vel_n_m_s())
+            .vector3("vel_b", &vel_b)
+            .value("vel_norm", vel_b.norm())
+            .vector3("ang_vel_b", &state.angvel_b_rad_s())
+            .quat("orient/quat", &quat_nb)
+            .euler("orient/euler", &quat_nb);
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct AeroStateCsv;
+
+impl CsvWrite for AeroStateCsv {
+    type Telem = AeroState;
+
+    fn write(&mut self, row: &mut CsvRow, state: AeroState) -> Result<()> {
+        row.value("alpha_deg", state.angles.alpha_rad.to_degrees())
+            .value("beta_deg", state.angles.beta_rad.to_degrees())
+            .value("beta_tan_deg", state.angles.beta_tan_rad.to_degrees())
+            .value("mach", state.mach)
+            .value("air_density_kg_m3", state.air_density_kg_m3)
+            .value("altitude_m", state.altitude_m)
+            .value("v_air_norm_m_s", state.v_air_norm_m_s)
+            .vector3("v_air_b_m_s", &state.v_air_b_m_s)
+            .vector3("w_b_rad_s", &state.w_b_rad_s);
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct FlightEnvelopeCsv;
+
+impl CsvWrite for FlightEnvelopeCsv {
+    type Telem = FlightEnvelopeSample;
+
+    fn write(&mut self, row: &mut CsvRow, envelope: FlightEnvelopeSample) -> Result<()> {
+        row.value("dynamic_pressure_pa", envelope.dynamic_pressure_pa)
+            .value("mach", envelope.mach)
+            .value("altitude_m", envelope.altitude_m)
+            .value("total_aoa_deg", envelope.total_aoa_deg)
+            .value("q_alpha_pa_deg", envelope.q_alpha_pa_deg)
+            .value("max_dynamic_pressure_pa", envelope.max_dynamic_pressure_pa)
+            .value("max_mach", envelope.max_mach)
+            .value("max_q_alpha_pa_deg", envelope.max_q_alpha_pa_deg);
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct StabilityCsv;
+
+impl CsvWrite for StabilityCsv {
+    type Telem = StabilitySample;
+
+    fn write(&mut self, row: &mut CsvRow, stability: StabilitySample) -> Result<()> {
+        row.value("x_cp_m", stability.x_cp_m)
+            .value("x_cg_m", stability.x_cg_m)
+            .value("static_margin_cal", stability.static_margin_cal);
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct RocketActionsCsv;
+
+impl CsvWrite for RocketActionsCsv {
+    type Telem = RocketActions;
+
+    fn write(&mut self, row: &mut CsvRow, actions: RocketActions) -> Result<()> {
+        row.vector3("thrust_b_n", &actions.thrust_b_n)
+            .vector3("aero_force_b_n", &actions.aero_actions.forces_b_n)
+            .vector3("aero_moments_b_nm", &actions.aero_actions.moments_b_nm)
+            .vector3("parachute_force_n_n", &actions.parachute_force_n_n)
+            .vector3("tot_force_n_n", &actions.tot_force_n_n)
+            .vector3("tot_force_b_n", &actions.tot_force_b_n)
+            .vector3("tot_moment_b_nm", &actions.tot_moment_b_nm);
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct RocketAccelCsv;
+
+impl CsvWrite for RocketAccelCsv {
+    type Telem = RocketAccelerations;
+
+    fn write(&mut self, row: &mut CsvRow, accel: RocketAccelerations) -> Result<()> {
+        row.vector3("acc_b", &accel.acc_b_m_s2)
+            .vector3("acc_n", &accel.acc_n_m_s2)
+            .vector3("ang_acc_b", &accel.ang_acc_b_rad_s2);
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct ServoPositionCsv;
+
+impl CsvWrite for ServoPositionCsv {
+    type Telem = ServoPosition;
+
+    fn write(&mut self, row: &mut CsvRow, servo_pos: ServoPosition) -> Result<()> {
+        let mixed = servo_pos.mix();
+
+        row.vector("raw", &servo_pos.pos_rad.map(|x| x.to_degrees()), SERVOS)
+            .vector(
+                "mixed",
+                &mixed.pos_rad.map(|x| x.to_degrees()),
+                ["yaw", "pitch", "roll", "squeeze"],
+            );
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct AirbrakePositionCsv;
+
+impl CsvWrite for AirbrakePositionCsv {
+    type Telem = AirbrakePosition;
+
+    fn write(&mut self, row: &mut CsvRow, pos: AirbrakePosition) -> Result<()> {
+        row.value("extension", pos.extension);
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct ServoPowerCsv;
+
+impl CsvWrite for ServoPowerCsv {
+    type Telem = ServoPower;
+
+    fn write(&mut self, row: &mut CsvRow, power: ServoPower) -> Result<()> {
+        row.vector("current_a", &power.current_a, SERVOS)
+            .vector("winding_temp_degc", &power.winding_temp_degc, SERVOS)
+            .vector("derating", &power.derating, SERVOS)
+            .value("power_w", power.power_w);
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct VoterStatusCsv;
+
+impl CsvWrite for VoterStatusCsv {
+    type Telem = VoterStatus;
+
+    fn write(&mut self, row: &mut CsvRow, status: VoterStatus) -> Result<()> {
+        let active = match status.active {
+            FcUnit::A => "A",
+            FcUnit::B => "B",
+        };
+
+        row.value("active", active)
+            .value("divergent", status.divergent as u8)
+            .value("altitude_diff_m", status.altitude_diff_m)
+            .value("position_diff_m", status.position_diff_m);
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct RocketMassPropertiesCsv;
+
+impl CsvWrite for RocketMassPropertiesCsv {
+    type Telem = RocketMassProperties;
+
+    fn write(&mut self, row: &mut CsvRow, mass: RocketMassProperties) -> Result<()> {
+        row.vector3("xcg_tot_m", &mass.xcg_total_m)
+            .value("mass_tot_kg", mass.mass_kg)
+            .value("mass_dot_kg_s", mass.mass_dot_kg_s)
+            .matrix3("inertia_kgm2", &mass.inertia_kgm2)
+            .matrix3("inertia_dot_kgm2_s", &mass.inertia_dot_kgm2_s);
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct RocketEngineMassPropertiesCsv;
+
+impl CsvWrite for RocketEngineMassPropertiesCsv {
+    type Telem = RocketEngineMassProperties;
+
+    fn write(&mut self, row: &mut CsvRow, mass: RocketEngineMassProperties) -> Result<()> {
+        row.value("xcg_eng_frame_m", mass.xcg_eng_frame_m)
+            .value("xcg_dot_eng_frame_m", mass.xcg_dot_eng_frame_m)
+            .value("mass_kg", mass.mass_kg)
+            .value("mass_dot_kg_s", mass.mass_dot_kg_s)
+            .matrix3("inertia_eng_frame_kgm2", &mass.inertia_eng_frame_kgm2)
+            .matrix3(
+                "inertia_dot_eng_frame_kgm2",
+                &mass.inertia_dot_eng_frame_kgm2,
+            );
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct ImuSampleCsv;
+
+impl CsvWrite for ImuSampleCsv {
+    type Telem = ImuSensorSample;
+
+    fn write(&mut self, row: &mut CsvRow, imu: ImuSensorSample) -> Result<()> {
+        row.vector3("acc_m_s2", &imu.accel_m_s2)
+            .vector3("gyro_deg_s", &imu.angvel_rad_s.map(|x| x.to_degrees()));
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct MagnetometerSampleCsv;
+
+impl CsvWrite for MagnetometerSampleCsv {
+    type Telem = MagnetometerSensorSample;
+
+    fn write(&mut self, row: &mut CsvRow, mag: MagnetometerSensorSample) -> Result<()> {
+        row.vector3("mag_field_b_gauss", &mag.mag_field_b_gauss);
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct AdaOutputCsv;
+
+impl CsvWrite for AdaOutputCsv {
+    type Telem = AdaResult;
+
+    fn write(&mut self, row: &mut CsvRow, ada: AdaResult) -> Result<()> {
+        row.value("altitude_m", ada.altitude_m)
+            .value("vertical_speed_m_s", ada.vertical_speed_m_s)
+            .value("vertical_accel_m_s2", ada.vertical_accel_m_s2)
+            .value("apogee_detected", ada.apogee_detected as u8);
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct NavigationOutputCsv;
+
+impl CsvWrite for NavigationOutputCsv {
+    type Telem = NavigationOutput;
+
+    fn write(&mut self, row: &mut CsvRow, data: NavigationOutput) -> Result<()> {
+        row.quat("quat", &data.quat_nb)
+            .euler("euler", &data.quat_nb)
+            .vector3("pos_n_m", &data.pos_n_m)
+            .vector3("vel_n_m_s", &data.vel_n_m_s)
+            .vector3("angvel_unbias_b_rad_s", &data.angvel_unbias_b_rad_s)
+            .vector3("acc_unbias_b_m_s2", &data.acc_unbias_b_m_s2);
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct AttitudeEstimateCsv;
+
+impl CsvWrite for AttitudeEstimateCsv {
+    type Telem = AttitudeEstimate;
+
+    fn write(&mut self, row: &mut CsvRow, data: AttitudeEstimate) -> Result<()> {
+        row.quat("quat", &data.quat_nb)
+            .euler("euler", &data.quat_nb)
+            .vector3("gyro_bias_b_rad_s", &data.gyro_bias_b_rad_s)
+            .value("accel_aided", data.accel_aided as u8);
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct CameraPointingCsv;
+
+impl CsvWrite for CameraPointingCsv {
+    type Telem = CameraPointing;
+
+    fn write(&mut self, row: &mut CsvRow, pointing: CameraPointing) -> Result<()> {
+        row.value("in_view", pointing.in_view as u8)
+            .value("off_axis_deg", pointing.off_axis_rad.to_degrees())
+            .value("image_h_deg", pointing.image_h_rad.to_degrees())
+            .value("image_v_deg", pointing.image_v_rad.to_degrees())
+            .value("range_m", pointing.range_m)
+            .value("time_in_view_s", pointing.time_in_view_s)
+            .vector3("boresight_n", &pointing.boresight_n);
+
+        if let Some(ground_point_n) = pointing.ground_point_n {
+            row.vector3("ground_point_n_m", &ground_point_n);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct TableSensorCsv;
+
+impl CsvWrite for TableSensorCsv {
+    type Telem = TableSensorSample;
+
+    fn write(&mut self, row: &mut CsvRow, sample: TableSensorSample) -> Result<()> {
+        row.value("sensor", sample.sensor).value("value", sample.value);
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct GncEventCsv;
+
+impl CsvWrite for GncEventCsv {
+    type Telem = GncEventItem;
+
+    fn write(&mut self, row: &mut CsvRow, event: GncEventItem) -> Result<()> {
+        row.value("event", format!("{:?}", event.event))
+            .value("src", format!("{:?}", event.src));
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct SimEventCsv;
+
+impl CsvWrite for SimEventCsv {
+    type Telem = SimEvent;
+
+    fn write(&mut self, row: &mut CsvRow, event: SimEvent) -> Result<()> {
+        row.value("event", format!("{:?}", event));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,445 @@
+use std::{
+    collections::HashSet,
+    fmt::Display,
+    fs::{self, File},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, anyhow};
+use log::warn;
+use nalgebra::{Matrix3, RealField, SVector, Scalar, UnitQuaternion};
+
+use crate::{
+    core::time::Timestamp,
+    crater::logging::filter::{ChannelLogFilter, Decimator},
+    telemetry::{
+        ChannelUnits, TelemetryReceiver, TelemetryService, Timestamped, selector::Selector,
+    },
+    utils::capacity::Capacity,
+};
+
+/// Values of a CSV row, as named columns. Nested fields are flattened into `field/component`
+/// columns, using the same paths as the Rerun entities so that the channel units apply.
+#[derive(Debug, Default)]
+pub struct CsvRow {
+    names: Vec<String>,
+    values: Vec<String>,
+}
+
+impl CsvRow {
+    pub fn value(&mut self, name: &str, value: impl Display) -> &mut Self {
+        self.names.push(name.to_string());
+        self.values.push(value.to_string());
+        self
+    }
+
+    /// Components of a vector, named `name/component`
+    pub fn vector<T: Scalar + Display, const N: usize>(
+        &mut self,
+        name: &str,
+        v: &SVector<T, N>,
+        components: [&str; N],
+    ) -> &mut Self {
+        for (c, value) in components.iter().zip(v.iter()) {
+            self.value(&format!("{name}/{c}"), value);
+        }
+        self
+    }
+
+    pub fn vector3<T: Scalar + Display>(&mut self, name: &str, v: &SVector<T, 3>) -> &mut Self {
+        self.vector(name, v, ["x", "y", "z"])
+    }
+
+    /// Rows & columns of a 3x3 matrix, named `name/xy`
+    pub fn matrix3<T: Scalar + Display>(&mut self, name: &str, m: &Matrix3<T>) -> &mut Self {
+        let axes = ["x", "y", "z"];
+        for (r, row) in axes.iter().enumerate() {
+            for (c, col) in axes.iter().enumerate() {
+                self.value(&format!("{name}/{row}{col}"), &m[(r, c)]);
+            }
+        }
+        self
+    }
+
+    pub fn quat<T: RealField + Display>(&mut self, name: &str, q: &UnitQuaternion<T>) -> &mut Self {
+        let q = q.quaternion();
+        self.value(&format!("{name}/w"), &q.w);
+        self.value(&format!("{name}/x"), &q.i);
+        self.value(&format!("{name}/y"), &q.j);
+        self.value(&format!("{name}/z"), &q.k)
+    }
+
+    /// Yaw, pitch & roll of an attitude, in degrees
+    pub fn euler<T: RealField + Copy + Into<f64>>(
+        &mut self,
+        name: &str,
+        q: &UnitQuaternion<T>,
+    ) -> &mut Self {
+        let (roll, pitch, yaw) = q.euler_angles();
+        self.value(&format!("{name}/yaw"), yaw.into().to_degrees());
+        self.value(&format!("{name}/pitch"), pitch.into().to_degrees());
+        self.value(&format!("{name}/roll"), roll.into().to_degrees())
+    }
+}
+
+/// Flattens a telemetry type into the columns of a CSV row
+pub trait CsvWrite {
+    type Telem;
+
+    fn write(&mut self, row: &mut CsvRow, data: Self::Telem) -> Result<()>;
+}
+
+/// CSV file of a channel. The file is created on the first sample, and its columns are those of
+/// the first row: columns missing from later rows are left empty.
+struct CsvFile {
+    path: PathBuf,
+    units: Option<ChannelUnits>,
+    writer: Option<(csv::Writer<File>, Vec<String>)>,
+    unknown_columns: HashSet<String>,
+}
+
+impl CsvFile {
+    fn write_row(&mut self, ts: Timestamp, row: CsvRow) -> Result<()> {
+        if self.writer.is_none() {
+            let mut writer = csv::Writer::from_path(&self.path)
+                .with_context(|| format!("Cannot create '{}'", self.path.display()))?;
+
+            let header = std::iter::once("t_s".to_string()).chain(row.names.iter().map(|name| {
+                match self.units.as_ref().and_then(|units| units.get(name)) {
+                    Some(unit) => format!("{name} [{unit}]"),
+                    None => name.clone(),
+                }
+            }));
+            writer.write_record(header)?;
+
+            self.writer = Some((writer, row.names.clone()));
+        }
+
+        let (writer, columns) = self.writer.as_mut().unwrap();
+        let t_s = ts.monotonic.elapsed_seconds_f64().to_string();
+
+        if row.names == *columns {
+            writer.write_record(std::iter::once(&t_s).chain(row.values.iter()))?;
+        } else {
+            let mut values = vec![String::new(); columns.len()];
+            for (name, value) in row.names.into_iter().zip(row.values) {
+                match columns.iter().position(|c| *c == name) {
+                    Some(i) => values[i] = value,
+                    None => {
+                        if self.unknown_columns.insert(name.clone()) {
+                            warn!(
+                                "Column '{name}' is not in the header of '{}', it will not be logged",
+                                self.path.display()
+                            );
+                        }
+                    }
+                }
+            }
+            writer.write_record(std::iter::once(&t_s).chain(values.iter()))?;
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        if let Some((writer, _)) = self.writer.as_mut() {
+            writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+trait CsvChannel {
+    fn disconnected(&self) -> bool;
+
+    fn recv<'a>(&'a mut self, selector: Selector<'a>) -> Selector<'a>;
+
+    /// First error of the channel, which stops its logging
+    fn take_error(&mut self) -> Option<anyhow::Error>;
+
+    fn flush(&mut self) -> Result<()>;
+}
+
+struct CsvChannelImpl<T, W> {
+    receiver: TelemetryReceiver<T>,
+    decimator: Decimator,
+    csv_writer: W,
+    file: CsvFile,
+    error: Option<anyhow::Error>,
+    disconnected: bool,
+}
+
+impl<T, W> CsvChannelImpl<T, W>
+where
+    W: CsvWrite<Telem = T>,
+{
+    fn write(&mut self, ts: Timestamp, data: T) -> Result<()> {
+        let mut row = CsvRow::default();
+        self.csv_writer.write(&mut row, data)?;
+        self.file.write_row(ts, row)
+    }
+}
+
+impl<T, W> CsvChannel for CsvChannelImpl<T, W>
+where
+    T: 'static + Send,
+    W: CsvWrite<Telem = T>,
+{
+    fn disconnected(&self) -> bool {
+        self.disconnected
+    }
+
+    fn recv<'a>(&'a mut self, selector: Selector<'a>) -> Selector<'a> {
+        selector.recv(self.receiver.inner(), |v| {
+            if let Ok(Timestamped(ts, data)) = v {
+                if !self.decimator.keep() {
+                    return;
+                }
+                if let Err(err) = self.write(ts, data) {
+                    self.error.get_or_insert(err);
+                    self.disconnected = true;
+                }
+            } else {
+                self.disconnected = true;
+            }
+        })
+    }
+
+    fn take_error(&mut self) -> Option<anyhow::Error> {
+        self.error.take()
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.file.flush()
+    }
+}
+
+pub struct CsvLoggerBuilder {
+    telem: TelemetryService,
+    out_dir: PathBuf,
+    filter: ChannelLogFilter,
+    channels: Vec<Box<dyn CsvChannel + Send>>,
+    files: HashSet<PathBuf>,
+}
+
+impl CsvLoggerBuilder {
+    /// Logs to one CSV file per channel in `out_dir`, which is created if needed
+    pub fn new(telem: &TelemetryService, out_dir: &Path) -> Result<Self> {
+        fs::create_dir_all(out_dir)
+            .with_context(|| format!("Cannot create log directory '{}'", out_dir.display()))?;
+
+        Ok(Self {
+            telem: telem.clone(),
+            out_dir: out_dir.to_path_buf(),
+            filter: ChannelLogFilter::default(),
+            channels: Vec::new(),
+            files: HashSet::new(),
+        })
+    }
+
+    /// Skips or decimates the channels logged afterwards, as configured in `filter`
+    pub fn with_filter(mut self, filter: ChannelLogFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// File a channel is logged to: `/rocket/state` is logged to `rocket_state.csv`
+    pub fn channel_file(&self, channel_name: &str) -> PathBuf {
+        let name = channel_name.trim_start_matches('/').replace('/', "_");
+        self.out_dir.join(format!("{name}.csv"))
+    }
+
+    fn add_channel<T: 'static + Send>(
+        &mut self,
+        channel_name: &str,
+        receiver: TelemetryReceiver<T>,
+        csv_writer: impl CsvWrite<Telem = T> + Send + 'static,
+    ) -> Result<()> {
+        let path = self.channel_file(channel_name);
+        if !self.files.insert(path.clone()) {
+            return Err(anyhow!(
+                "Channel '{channel_name}' is already logged to '{}'",
+                path.display()
+            ));
+        }
+
+        self.channels.push(Box::new(CsvChannelImpl {
+            receiver,
+            decimator: Decimator::new(self.filter.get(channel_name).decimation),
+            csv_writer,
+            file: CsvFile {
+                path,
+                units: self.telem.units(channel_name),
+                writer: None,
+                unknown_columns: HashSet::new(),
+            },
+            error: None,
+            disconnected: false,
+        }));
+
+        Ok(())
+    }
+
+    pub fn log_telemetry<T: 'static + Send>(
+        &mut self,
+        channel_name: &str,
+        csv_writer: impl CsvWrite<Telem = T> + Send + 'static,
+    ) -> Result<()> {
+        if !self.filter.get(channel_name).enabled {
+            return Ok(());
+        }
+        let receiver = self
+            .telem
+            .subscribe::<T>(channel_name, Capacity::Unbounded)?;
+
+        self.add_channel(channel_name, receiver, csv_writer)
+    }
+
+    pub fn log_telemetry_mp<T: 'static + Send>(
+        &mut self,
+        channel_name: &str,
+        csv_writer: impl CsvWrite<Telem = T> + Send + 'static,
+    ) -> Result<()> {
+        if !self.filter.get(channel_name).enabled {
+            return Ok(());
+        }
+        let receiver = self
+            .telem
+            .subscribe_mp::<T>(channel_name, Capacity::Unbounded)?;
+
+        self.add_channel(channel_name, receiver, csv_writer)
+    }
+
+    pub fn build(self) -> CsvLogger {
+        CsvLogger {
+            channels: self.channels,
+        }
+    }
+}
+
+pub struct CsvLogger {
+    channels: Vec<Box<dyn CsvChannel + Send>>,
+}
+
+impl CsvLogger {
+    /// Logs until all the telemetry channels are closed. Writing CSV rows is cheap compared to
+    /// the simulation, so all the channels are logged from the calling thread.
+    pub fn log_blocking(mut self) -> Result<()> {
+        loop {
+            let mut selector: Selector<'_> = Selector::new();
+            let mut num_recv = 0usize;
+
+            for channel in self.channels.iter_mut() {
+                if !channel.disconnected() {
+                    selector = channel.recv(selector);
+                    num_recv += 1;
+                }
+            }
+
+            if num_recv > 0 {
+                selector.ready();
+            } else {
+                break;
+            }
+        }
+
+        for channel in self.channels.iter_mut() {
+            channel.flush()?;
+        }
+
+        match self.channels.iter_mut().find_map(|c| c.take_error()) {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Selects the channels logged to CSV, the counterpart of
+/// [`RerunLogConfig`](super::super::rerun::RerunLogConfig) for the CSV backend
+pub trait CsvLogConfig {
+    fn subscribe_csv(&self, builder: &mut CsvLoggerBuilder) -> Result<()>;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use nalgebra::Vector3;
+
+    use super::*;
+
+    #[derive(Clone)]
+    struct Sample {
+        speed: f64,
+        pos: Vector3<f64>,
+        note: Option<&'static str>,
+    }
+
+    struct SampleCsv;
+
+    impl CsvWrite for SampleCsv {
+        type Telem = Sample;
+
+        fn write(&mut self, row: &mut CsvRow, data: Sample) -> Result<()> {
+            row.value("speed", data.speed).vector3("pos", &data.pos);
+            if let Some(note) = data.note {
+                row.value("note", note);
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_csv_logger() -> Result<()> {
+        let out_dir = env::temp_dir().join(format!("crater_csv_{}", process::id()));
+
+        let ts = TelemetryService::default();
+        ts.set_units(
+            "/test/sample",
+            ChannelUnits::new()
+                .field("speed", "m/s")
+                .vector3("pos", "m"),
+        );
+
+        let tx = ts.publish::<Sample>("/test/sample")?;
+        let mut builder = CsvLoggerBuilder::new(&ts, &out_dir)?;
+        builder.log_telemetry("/test/sample", SampleCsv)?;
+        assert!(builder.log_telemetry("/test/sample", SampleCsv).is_err());
+        let logger = builder.build();
+
+        tx.send(
+            Timestamp::from_micros(500_000),
+            Sample {
+                speed: 1.5,
+                pos: Vector3::new(1.0, 2.0, 3.0),
+                note: Some("first"),
+            },
+        );
+        tx.send(
+            Timestamp::from_micros(1_000_000),
+            Sample {
+                speed: 2.0,
+                pos: Vector3::new(4.0, 5.0, 6.0),
+                note: None,
+            },
+        );
+        // Closes the channels, as at the end of a simulation
+        drop(tx);
+        drop(ts);
+        logger.log_blocking()?;
+
+        let csv = fs::read_to_string(out_dir.join("test_sample.csv"))?;
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "t_s,speed [m/s],pos/x [m],pos/y [m],pos/z [m],note",
+                "0.5,1.5,1,2,3,first",
+                "1,2,4,5,6,",
+            ]
+        );
+
+        fs::remove_dir_all(&out_dir)?;
+        Ok(())
+    }
+}
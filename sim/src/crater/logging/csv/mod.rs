@@ -0,0 +1,6 @@
+mod crater_configs;
+pub mod crater_csv_impl;
+
+mod csv_logger;
+
+pub use csv_logger::{CsvLogConfig, CsvLogger, CsvLoggerBuilder, CsvRow, CsvWrite};
@@ -0,0 +1,336 @@
+use std::{fmt::Write, fs, path::Path};
+
+use anyhow::{Result, anyhow};
+use log::info;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use super::landing_scatter::Ellipse;
+use crate::crater::environment::LaunchSite;
+
+/// Outcome of a Monte Carlo run, as written to montecarlo.csv
+#[derive(Debug, Clone, Deserialize)]
+pub struct RunOutcome {
+    pub index: usize,
+    pub seed: u64,
+    pub landing_n_m: f64,
+    pub landing_e_m: f64,
+    pub apogee_m: f64,
+    /// Missing in campaigns run before it was recorded
+    #[serde(default)]
+    pub max_q_pa: Option<f64>,
+}
+
+/// Reads the run outcomes of a Monte Carlo campaign, sorted by run index
+pub fn read_runs(path: &Path) -> Result<Vec<RunOutcome>> {
+    let mut runs = csv::Reader::from_path(path)?
+        .deserialize()
+        .collect::<Result<Vec<RunOutcome>, _>>()
+        .map_err(|e| anyhow!("Error reading the runs from '{}': {e}", path.display()))?;
+    runs.sort_by_key(|r| r.index);
+    Ok(runs)
+}
+
+/// Distribution of a value over the runs
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Stats {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub std_dev: f64,
+    pub p1: f64,
+    pub p5: f64,
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+}
+
+impl Stats {
+    /// None without values
+    pub fn of(values: &[f64]) -> Option<Stats> {
+        if values.is_empty() {
+            return None;
+        }
+
+        let mut sorted = values.to_vec();
+        sorted.sort_by(f64::total_cmp);
+
+        let n = sorted.len() as f64;
+        let mean = sorted.iter().sum::<f64>() / n;
+        let var = sorted.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0).max(1.0);
+
+        Some(Stats {
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            mean,
+            std_dev: var.sqrt(),
+            p1: percentile(&sorted, 1.0),
+            p5: percentile(&sorted, 5.0),
+            p50: percentile(&sorted, 50.0),
+            p95: percentile(&sorted, 95.0),
+            p99: percentile(&sorted, 99.0),
+        })
+    }
+}
+
+/// Percentile `p` [%] of sorted values, interpolated between the closest ranks
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let pos = p / 100.0 * (sorted.len() - 1) as f64;
+    let (i, frac) = (pos.floor() as usize, pos.fract());
+    match sorted.get(i + 1) {
+        Some(next) => sorted[i] + frac * (next - sorted[i]),
+        None => sorted[i],
+    }
+}
+
+/// Dispersion statistics of a Monte Carlo campaign
+#[derive(Debug, Clone, Serialize)]
+pub struct Dispersion {
+    pub runs: usize,
+    pub apogee_m: Option<Stats>,
+    pub max_q_pa: Option<Stats>,
+    pub landing_n_m: Option<Stats>,
+    pub landing_e_m: Option<Stats>,
+    /// Landing ellipses of 1 & 3 standard deviations
+    pub ellipse_1sigma: Option<Ellipse>,
+    pub ellipse_3sigma: Option<Ellipse>,
+}
+
+impl Dispersion {
+    pub fn new(runs: &[RunOutcome]) -> Self {
+        let values = |f: fn(&RunOutcome) -> Option<f64>| {
+            Stats::of(&runs.iter().filter_map(f).collect::<Vec<_>>())
+        };
+        let points: Vec<[f64; 2]> = runs
+            .iter()
+            .map(|r| [r.landing_n_m, r.landing_e_m])
+            .collect();
+
+        Self {
+            runs: runs.len(),
+            apogee_m: values(|r| Some(r.apogee_m)),
+            max_q_pa: values(|r| r.max_q_pa),
+            landing_n_m: values(|r| Some(r.landing_n_m)),
+            landing_e_m: values(|r| Some(r.landing_e_m)),
+            ellipse_1sigma: Ellipse::fit(&points, sigma_probability(1.0)),
+            ellipse_3sigma: Ellipse::fit(&points, sigma_probability(3.0)),
+        }
+    }
+
+    /// Landing ellipses, with their names
+    pub fn ellipses(&self) -> impl Iterator<Item = (&'static str, &Ellipse)> {
+        [
+            ("1sigma", &self.ellipse_1sigma),
+            ("3sigma", &self.ellipse_3sigma),
+        ]
+        .into_iter()
+        .filter_map(|(name, e)| e.as_ref().map(|e| (name, e)))
+    }
+
+    /// Logs the apogee & max Q distributions and the landing ellipses
+    pub fn log_summary(&self) {
+        if let Some(s) = self.apogee_m {
+            info!(
+                "Apogee: {:.0} m mean, {:.0} m std dev, {:.0} / {:.0} / {:.0} m (p5 / p50 / p95)",
+                s.mean, s.std_dev, s.p5, s.p50, s.p95
+            );
+        }
+        if let Some(s) = self.max_q_pa {
+            info!(
+                "Max Q: {:.0} Pa mean, {:.0} Pa std dev, {:.0} / {:.0} / {:.0} Pa (p5 / p50 / p95)",
+                s.mean, s.std_dev, s.p5, s.p50, s.p95
+            );
+        }
+        for (name, e) in self.ellipses() {
+            info!(
+                "Landing ellipse {name}: {:.0} x {:.0} m, major axis at {:.0} deg",
+                2.0 * e.semi_major_m,
+                2.0 * e.semi_minor_m,
+                e.orientation_deg
+            );
+        }
+    }
+}
+
+/// Probability within `n_sigma` standard deviations of a 2D normal distribution
+fn sigma_probability(n_sigma: f64) -> f64 {
+    1.0 - (-n_sigma * n_sigma / 2.0).exp()
+}
+
+/// GeoJSON feature collection with the landing points and the 1σ & 3σ ellipses
+pub fn to_geojson(dispersion: &Dispersion, runs: &[RunOutcome], site: &LaunchSite) -> Value {
+    let lon_lat = |ne: [f64; 2]| {
+        let (lat, lon) = site.lat_lon_deg(ne);
+        json!([lon, lat])
+    };
+
+    let mut features = vec![json!({
+        "type": "Feature",
+        "geometry": {
+            "type": "MultiPoint",
+            "coordinates": runs
+                .iter()
+                .map(|r| lon_lat([r.landing_n_m, r.landing_e_m]))
+                .collect::<Vec<_>>(),
+        },
+        "properties": {
+            "kind": "landings",
+            "runs": runs.iter().map(|r| r.index).collect::<Vec<_>>(),
+        },
+    })];
+
+    for (name, ellipse) in dispersion.ellipses() {
+        features.push(json!({
+            "type": "Feature",
+            "geometry": {
+                "type": "Polygon",
+                "coordinates": [ellipse.outline(72).into_iter().map(lon_lat).collect::<Vec<_>>()],
+            },
+            "properties": {
+                "kind": "ellipse",
+                "name": name,
+                "semi_major_m": ellipse.semi_major_m,
+                "semi_minor_m": ellipse.semi_minor_m,
+                "orientation_deg": ellipse.orientation_deg,
+            },
+        }));
+    }
+
+    json!({
+        "type": "FeatureCollection",
+        "features": features,
+    })
+}
+
+/// KML document with the landing points and the 1σ & 3σ ellipses
+pub fn to_kml(dispersion: &Dispersion, runs: &[RunOutcome], site: &LaunchSite) -> Result<String> {
+    let coords = |ne: [f64; 2]| {
+        let (lat, lon) = site.lat_lon_deg(ne);
+        format!("{lon:.7},{lat:.7},0")
+    };
+
+    let mut kml = String::new();
+    writeln!(kml, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(kml, r#"<kml xmlns="http://www.opengis.net/kml/2.2">"#)?;
+    writeln!(kml, "<Document>")?;
+    writeln!(
+        kml,
+        "<name>Monte Carlo dispersion ({} runs)</name>",
+        runs.len()
+    )?;
+
+    // Colors are aabbggrr
+    for (id, color) in [
+        ("landing", "ff00ffff"),
+        ("1sigma", "ff00ff00"),
+        ("3sigma", "ff0000ff"),
+    ] {
+        writeln!(
+            kml,
+            r#"<Style id="{id}"><IconStyle><color>{color}</color><scale>0.5</scale></IconStyle><LineStyle><color>{color}</color><width>2</width></LineStyle><PolyStyle><fill>0</fill></PolyStyle></Style>"#
+        )?;
+    }
+
+    writeln!(kml, "<Folder><name>Landings</name>")?;
+    for run in runs.iter() {
+        writeln!(
+            kml,
+            "<Placemark><name>{}</name><styleUrl>#landing</styleUrl><Point><coordinates>{}</coordinates></Point></Placemark>",
+            run.index,
+            coords([run.landing_n_m, run.landing_e_m])
+        )?;
+    }
+    writeln!(kml, "</Folder>")?;
+
+    for (name, ellipse) in dispersion.ellipses() {
+        let outline: Vec<String> = ellipse.outline(72).into_iter().map(coords).collect();
+        writeln!(
+            kml,
+            "<Placemark><name>{name}</name><styleUrl>#{name}</styleUrl><Polygon><outerBoundaryIs><LinearRing><coordinates>{}</coordinates></LinearRing></outerBoundaryIs></Polygon></Placemark>",
+            outline.join(" ")
+        )?;
+    }
+
+    writeln!(kml, "</Document>")?;
+    writeln!(kml, "</kml>")?;
+
+    Ok(kml)
+}
+
+/// Computes the dispersion of the campaign in `dir` from its montecarlo.csv, and writes it to
+/// dispersion.json, with the landing points and ellipses in dispersion.geojson & dispersion.kml
+pub fn write_campaign(dir: &Path, site: &LaunchSite) -> Result<Dispersion> {
+    let runs = read_runs(&dir.join("montecarlo.csv"))?;
+    let dispersion = Dispersion::new(&runs);
+
+    fs::write(
+        dir.join("dispersion.json"),
+        serde_json::to_string_pretty(&dispersion)?,
+    )?;
+    fs::write(
+        dir.join("dispersion.geojson"),
+        serde_json::to_string_pretty(&to_geojson(&dispersion, &runs, site))?,
+    )?;
+    fs::write(
+        dir.join("dispersion.kml"),
+        to_kml(&dispersion, &runs, site)?,
+    )?;
+
+    Ok(dispersion)
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_dispersion() {
+        let stats = Stats::of(&[4.0, 1.0, 3.0, 2.0, 5.0]).unwrap();
+        assert_eq!((stats.min, stats.max, stats.p50), (1.0, 5.0, 3.0));
+        assert_relative_eq!(stats.p5, 1.2, epsilon = 1e-12);
+        assert_relative_eq!(stats.p95, 4.8, epsilon = 1e-12);
+        assert_relative_eq!(stats.std_dev, 2.5f64.sqrt(), epsilon = 1e-12);
+        assert!(Stats::of(&[]).is_none());
+
+        // Landings on a circle of radius sqrt(2), whose standard deviation is 1 along any axis
+        let runs: Vec<RunOutcome> = (0..360)
+            .map(|i| {
+                let a = (i as f64).to_radians();
+                RunOutcome {
+                    index: i,
+                    seed: 0,
+                    landing_n_m: 2f64.sqrt() * a.cos(),
+                    landing_e_m: 2f64.sqrt() * a.sin(),
+                    apogee_m: 1000.0 + i as f64,
+                    max_q_pa: Some(50e3),
+                }
+            })
+            .collect();
+
+        let dispersion = Dispersion::new(&runs);
+        assert_eq!(dispersion.runs, 360);
+        assert_relative_eq!(dispersion.apogee_m.unwrap().p50, 1179.5, epsilon = 1e-9);
+        assert_relative_eq!(
+            dispersion.ellipse_1sigma.unwrap().semi_major_m,
+            1.0,
+            epsilon = 1e-2
+        );
+        assert_relative_eq!(
+            dispersion.ellipse_3sigma.unwrap().semi_minor_m,
+            3.0,
+            epsilon = 1e-2
+        );
+
+        let site = LaunchSite {
+            latitude_deg: 45.0,
+            longitude_deg: 9.0,
+            altitude_m: 0.0,
+        };
+        let kml = to_kml(&dispersion, &runs, &site).unwrap();
+        assert_eq!(kml.matches("<Placemark>").count(), 362);
+        let geojson = to_geojson(&dispersion, &runs, &site);
+        assert_eq!(geojson["features"].as_array().unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+
+use anyhow::{Result, anyhow};
+
+use crate::parameters::{ParameterMap, ParameterTree};
+
+/// How a channel is logged by the Rerun & CSV loggers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelLogging {
+    pub enabled: bool,
+    /// One sample out of `decimation` is logged
+    pub decimation: usize,
+}
+
+impl Default for ChannelLogging {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            decimation: 1,
+        }
+    }
+}
+
+/// Per-channel logging configuration, from `logging.channels`: one map per channel, with the
+/// channel name and optionally `enabled` & `decimation`. Channels not listed are logged at full
+/// rate.
+#[derive(Debug, Clone, Default)]
+pub struct ChannelLogFilter {
+    channels: HashMap<String, ChannelLogging>,
+}
+
+impl ChannelLogFilter {
+    /// Reads the configuration from the root parameter map. Without a `logging.channels` map,
+    /// every channel is logged at full rate.
+    pub fn from_params(params: &ParameterMap) -> Result<Self> {
+        if !params.contains_key("logging") || !params.get_map("logging")?.contains_key("channels") {
+            return Ok(Self::default());
+        }
+
+        let mut channels = HashMap::new();
+        for (_, tree) in params.get_map("logging.channels")?.iter() {
+            let ParameterTree::Node(map) = tree else {
+                continue;
+            };
+
+            let channel = map.get_param("channel")?.value_string()?;
+            let mut logging = ChannelLogging::default();
+            if map.contains_key("enabled") {
+                logging.enabled = map.get_param("enabled")?.value_bool()?;
+            }
+            if map.contains_key("decimation") {
+                let decimation = map.get_param("decimation")?.value_int()?;
+                if decimation < 1 {
+                    return Err(anyhow!(
+                        "Decimation of channel '{channel}' must be at least 1, got {decimation}"
+                    ));
+                }
+                logging.decimation = decimation as usize;
+            }
+
+            if channels.insert(channel.clone(), logging).is_some() {
+                return Err(anyhow!("Logging of channel '{channel}' configured twice"));
+            }
+        }
+
+        Ok(Self { channels })
+    }
+
+    pub fn get(&self, channel_name: &str) -> ChannelLogging {
+        self.channels.get(channel_name).copied().unwrap_or_default()
+    }
+}
+
+/// Keeps one sample out of `factor`, starting with the first one
+#[derive(Debug, Clone)]
+pub struct Decimator {
+    factor: usize,
+    count: usize,
+}
+
+impl Decimator {
+    pub fn new(factor: usize) -> Self {
+        Self {
+            factor: factor.max(1),
+            count: 0,
+        }
+    }
+
+    pub fn keep(&mut self) -> bool {
+        let keep = self.count == 0;
+        self.count = (self.count + 1) % self.factor;
+        keep
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parameters::parse_string;
+
+    #[test]
+    fn test_channel_log_filter() -> Result<()> {
+        let params = parse_string(
+            r#"
+            [logging.channels.imu]
+            channel = { val = "/sensors/ideal/imu", type = "str" }
+            decimation = { val = 3, type = "int" }
+
+            [logging.channels.accel]
+            channel = { val = "/rocket/accel", type = "str" }
+            enabled = { val = false, type = "bool" }
+            "#
+            .to_string(),
+        )?;
+        let filter = ChannelLogFilter::from_params(&params)?;
+
+        assert_eq!(filter.get("/sensors/ideal/imu").decimation, 3);
+        assert!(!filter.get("/rocket/accel").enabled);
+        assert_eq!(filter.get("/rocket/state"), ChannelLogging::default());
+
+        let mut decimator = Decimator::new(filter.get("/sensors/ideal/imu").decimation);
+        let kept: Vec<bool> = (0..7).map(|_| decimator.keep()).collect();
+        assert_eq!(kept, [true, false, false, true, false, false, true]);
+
+        let unconfigured = ChannelLogFilter::from_params(&ParameterMap::default())?;
+        assert_eq!(unconfigured.get("/rocket/state"), ChannelLogging::default());
+
+        Ok(())
+    }
+}
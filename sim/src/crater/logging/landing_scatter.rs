@@ -0,0 +1,332 @@
+use std::f64::consts::PI;
+
+use anyhow::Result;
+use rerun::RecordingStream;
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::{
+    crater::{aero::wind::WindModel, environment::LaunchSite},
+    parameters::ParameterMap,
+};
+
+/// Runs with a mean wind below this speed are not assigned a wind direction [m/s]
+const CALM_WIND_M_S: f64 = 0.5;
+
+/// Landing point of a Monte Carlo run, with the wind it flew through
+#[derive(Debug, Clone, Serialize)]
+pub struct LandingSample {
+    pub index: usize,
+    pub landing_n_m: f64,
+    pub landing_e_m: f64,
+    /// Direction the mean wind blows from, clockwise from north. None if calm.
+    pub wind_from_deg: Option<f64>,
+    pub wind_speed_m_s: f64,
+}
+
+/// Mean wind between the ground and `max_alt_m`, as the direction it blows from [deg] and its
+/// speed [m/s]. The direction is None if the wind is calm.
+pub fn mean_wind(wind: &dyn WindModel, max_alt_m: f64) -> (Option<f64>, f64) {
+    const NUM_LEVELS: usize = 100;
+
+    let mean = (0..=NUM_LEVELS)
+        .map(|i| wind.wind_n(max_alt_m.max(0.0) * i as f64 / NUM_LEVELS as f64))
+        .sum::<nalgebra::Vector3<f64>>()
+        / (NUM_LEVELS + 1) as f64;
+
+    let speed = mean.xy().norm();
+    let from_deg =
+        (speed >= CALM_WIND_M_S).then(|| (-mean[1]).atan2(-mean[0]).to_degrees().rem_euclid(360.0));
+
+    (from_deg, speed)
+}
+
+/// Dispersion ellipse of the landing points, on the north-east plane
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Ellipse {
+    pub center_ne_m: [f64; 2],
+    pub semi_major_m: f64,
+    pub semi_minor_m: f64,
+    /// Direction of the major axis, clockwise from north
+    pub orientation_deg: f64,
+}
+
+impl Ellipse {
+    /// Ellipse containing `probability` of the normal distribution fitted to the points. None
+    /// with fewer than 3 points.
+    pub fn fit(points: &[[f64; 2]], probability: f64) -> Option<Ellipse> {
+        if points.len() < 3 {
+            return None;
+        }
+
+        let n = points.len() as f64;
+        let mean_n = points.iter().map(|p| p[0]).sum::<f64>() / n;
+        let mean_e = points.iter().map(|p| p[1]).sum::<f64>() / n;
+
+        let (mut c_nn, mut c_ee, mut c_ne) = (0.0, 0.0, 0.0);
+        for p in points.iter() {
+            let (dn, de) = (p[0] - mean_n, p[1] - mean_e);
+            c_nn += dn * dn;
+            c_ee += de * de;
+            c_ne += dn * de;
+        }
+        let (c_nn, c_ee, c_ne) = (c_nn / (n - 1.0), c_ee / (n - 1.0), c_ne / (n - 1.0));
+
+        // Eigenvalues of the 2x2 covariance
+        let half_trace = (c_nn + c_ee) / 2.0;
+        let delta = (((c_nn - c_ee) / 2.0).powi(2) + c_ne * c_ne).sqrt();
+        let (l_major, l_minor) = (half_trace + delta, (half_trace - delta).max(0.0));
+
+        // Quantile of the chi-squared distribution with 2 degrees of freedom
+        let k = (-2.0 * (1.0 - probability).ln()).sqrt();
+
+        Some(Ellipse {
+            center_ne_m: [mean_n, mean_e],
+            semi_major_m: k * l_major.sqrt(),
+            semi_minor_m: k * l_minor.sqrt(),
+            orientation_deg: (0.5 * (2.0 * c_ne).atan2(c_nn - c_ee))
+                .to_degrees()
+                .rem_euclid(180.0),
+        })
+    }
+
+    /// Closed outline of the ellipse, as north-east points
+    pub fn outline(&self, num_points: usize) -> Vec<[f64; 2]> {
+        let (sin, cos) = self.orientation_deg.to_radians().sin_cos();
+
+        (0..=num_points)
+            .map(|i| {
+                let t = 2.0 * PI * i as f64 / num_points as f64;
+                let (a, b) = (self.semi_major_m * t.cos(), self.semi_minor_m * t.sin());
+                [
+                    self.center_ne_m[0] + a * cos - b * sin,
+                    self.center_ne_m[1] + a * sin + b * cos,
+                ]
+            })
+            .collect()
+    }
+}
+
+/// Runs whose wind blows from the same sector
+#[derive(Debug, Clone)]
+pub struct WindSector {
+    /// Name of the layer, eg. "wind_from_090" or "calm"
+    pub name: String,
+    /// Center of the sector [deg]. None for the calm runs.
+    pub from_deg: Option<f64>,
+    pub samples: Vec<LandingSample>,
+    pub ellipse: Option<Ellipse>,
+}
+
+/// Buckets the runs in `num_sectors` wind sectors, the first one centered on north, plus the
+/// calm runs. Empty sectors are omitted.
+pub fn bucket_by_wind(
+    samples: &[LandingSample],
+    num_sectors: usize,
+    probability: f64,
+) -> Vec<WindSector> {
+    let width = 360.0 / num_sectors as f64;
+    let sector_of = |from_deg: f64| ((from_deg + width / 2.0) / width) as usize % num_sectors;
+
+    let mut sectors: Vec<WindSector> = (0..num_sectors)
+        .map(|i| WindSector {
+            name: format!("wind_from_{:03.0}", i as f64 * width),
+            from_deg: Some(i as f64 * width),
+            samples: vec![],
+            ellipse: None,
+        })
+        .collect();
+    sectors.push(WindSector {
+        name: "calm".to_string(),
+        from_deg: None,
+        samples: vec![],
+        ellipse: None,
+    });
+
+    for sample in samples.iter() {
+        let i = sample.wind_from_deg.map_or(num_sectors, sector_of);
+        sectors[i].samples.push(sample.clone());
+    }
+
+    sectors.retain(|s| !s.samples.is_empty());
+    for sector in sectors.iter_mut() {
+        let points: Vec<[f64; 2]> = sector
+            .samples
+            .iter()
+            .map(|s| [s.landing_n_m, s.landing_e_m])
+            .collect();
+        sector.ellipse = Ellipse::fit(&points, probability);
+    }
+
+    sectors
+}
+
+/// GeoJSON feature collection with, for each wind sector, the landing points and the dispersion
+/// ellipse. Features carry the sector name, to be used as a layer.
+pub fn to_geojson(sectors: &[WindSector], site: &LaunchSite) -> Value {
+    let lon_lat = |ne: [f64; 2]| {
+        let (lat, lon) = site.lat_lon_deg(ne);
+        json!([lon, lat])
+    };
+
+    let mut features = vec![];
+    for sector in sectors.iter() {
+        features.push(json!({
+            "type": "Feature",
+            "geometry": {
+                "type": "MultiPoint",
+                "coordinates": sector
+                    .samples
+                    .iter()
+                    .map(|s| lon_lat([s.landing_n_m, s.landing_e_m]))
+                    .collect::<Vec<_>>(),
+            },
+            "properties": {
+                "layer": sector.name,
+                "kind": "landings",
+                "wind_from_deg": sector.from_deg,
+                "runs": sector.samples.iter().map(|s| s.index).collect::<Vec<_>>(),
+            },
+        }));
+
+        if let Some(ellipse) = &sector.ellipse {
+            features.push(json!({
+                "type": "Feature",
+                "geometry": {
+                    "type": "Polygon",
+                    "coordinates": [ellipse.outline(72).into_iter().map(lon_lat).collect::<Vec<_>>()],
+                },
+                "properties": {
+                    "layer": sector.name,
+                    "kind": "ellipse",
+                    "wind_from_deg": sector.from_deg,
+                    "semi_major_m": ellipse.semi_major_m,
+                    "semi_minor_m": ellipse.semi_minor_m,
+                    "orientation_deg": ellipse.orientation_deg,
+                },
+            }));
+        }
+    }
+
+    json!({
+        "type": "FeatureCollection",
+        "features": features,
+    })
+}
+
+/// Color of a wind sector, by direction around the color wheel. Calm runs are grey.
+fn sector_color(sector: &WindSector) -> rerun::Color {
+    let Some(from_deg) = sector.from_deg else {
+        return rerun::Color::from_rgb(128, 128, 128);
+    };
+
+    let h = from_deg / 60.0;
+    let x = ((1.0 - (h % 2.0 - 1.0).abs()) * 255.0) as u8;
+    match h as usize {
+        0 => rerun::Color::from_rgb(255, x, 0),
+        1 => rerun::Color::from_rgb(x, 255, 0),
+        2 => rerun::Color::from_rgb(0, 255, x),
+        3 => rerun::Color::from_rgb(0, x, 255),
+        4 => rerun::Color::from_rgb(x, 0, 255),
+        _ => rerun::Color::from_rgb(255, 0, x),
+    }
+}
+
+/// Logs a layer per wind sector, both on the map and in the NED frame
+pub fn log_rerun(rec: &RecordingStream, sectors: &[WindSector], site: &LaunchSite) -> Result<()> {
+    for sector in sectors.iter() {
+        let color = sector_color(sector);
+        let points: Vec<[f64; 2]> = sector
+            .samples
+            .iter()
+            .map(|s| [s.landing_n_m, s.landing_e_m])
+            .collect();
+
+        rec.log(
+            format!("landing/{}/geodetic", sector.name),
+            &rerun::GeoPoints::from_lat_lon(points.iter().map(|&p| site.lat_lon_deg(p)))
+                .with_radii([rerun::Radius::new_ui_points(4.0)])
+                .with_colors([color]),
+        )?;
+        rec.log(
+            format!("landing/{}/ned", sector.name),
+            &rerun::Points3D::new(points.iter().map(|p| [p[0] as f32, p[1] as f32, 0.0]))
+                .with_colors([color]),
+        )?;
+
+        if let Some(ellipse) = &sector.ellipse {
+            let outline = ellipse.outline(72);
+            rec.log(
+                format!("landing/{}/ellipse_geodetic", sector.name),
+                &rerun::GeoLineStrings::from_lat_lon([outline
+                    .iter()
+                    .map(|&p| site.lat_lon_deg(p))
+                    .collect::<Vec<_>>()])
+                .with_colors([color]),
+            )?;
+            rec.log(
+                format!("landing/{}/ellipse_ned", sector.name),
+                &rerun::LineStrips3D::new([outline
+                    .iter()
+                    .map(|p| [p[0] as f32, p[1] as f32, 0.0])
+                    .collect::<Vec<_>>()])
+                .with_colors([color]),
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::crater::aero::wind::TabulatedWind;
+
+    #[test]
+    fn test_ellipse_fit() {
+        // Points spread along the north-east diagonal
+        let points: Vec<[f64; 2]> = (-10..=10)
+            .flat_map(|i| {
+                let t = i as f64;
+                [[10.0 + t, 20.0 + t + 0.1], [10.0 + t, 20.0 + t - 0.1]]
+            })
+            .collect();
+
+        let ellipse = Ellipse::fit(&points, 0.95).unwrap();
+        assert_relative_eq!(ellipse.center_ne_m[0], 10.0, epsilon = 1e-9);
+        assert_relative_eq!(ellipse.center_ne_m[1], 20.0, epsilon = 1e-9);
+        assert_relative_eq!(ellipse.orientation_deg, 45.0, epsilon = 0.1);
+        assert!(ellipse.semi_major_m > 20.0 * ellipse.semi_minor_m);
+    }
+
+    #[test]
+    fn test_bucket_by_wind() {
+        let wind = TabulatedWind::new(&[0.0, 1000.0], &[5.0, 5.0], &[80.0, 80.0]).unwrap();
+        let (from_deg, speed) = mean_wind(&wind, 800.0);
+        assert_relative_eq!(from_deg.unwrap(), 80.0, epsilon = 1e-9);
+        assert_relative_eq!(speed, 5.0, epsilon = 1e-9);
+
+        let sample = |index, wind_from_deg| LandingSample {
+            index,
+            landing_n_m: index as f64,
+            landing_e_m: 0.0,
+            wind_from_deg,
+            wind_speed_m_s: 5.0,
+        };
+        let samples = [
+            sample(0, Some(350.0)),
+            sample(1, Some(10.0)),
+            sample(2, Some(80.0)),
+            sample(3, None),
+        ];
+
+        let sectors = bucket_by_wind(&samples, 4, 0.95);
+        let names: Vec<&str> = sectors.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["wind_from_000", "wind_from_090", "calm"]);
+        assert_eq!(sectors[0].samples.len(), 2);
+        assert!(sectors[0].ellipse.is_none());
+    }
+}
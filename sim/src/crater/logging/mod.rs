@@ -1 +1,7 @@
-pub mod rerun;
\ No newline at end of file
+pub mod csv;
+pub mod dispersion;
+pub mod filter;
+pub mod landing_scatter;
+pub mod rerun;
+pub mod report;
+pub mod watch;
@@ -0,0 +1,177 @@
+use anyhow::Result;
+use nalgebra::Vector3;
+use serde::Serialize;
+
+use super::flight_report::Sample;
+use crate::parameters::ParameterMap;
+
+/// Time after a deployment before the descent rate is measured, to exclude the inflation and the
+/// opening shock
+const DESCENT_SETTLING_TIME_S: f64 = 3.0;
+
+/// Recovery limits set by the competition or launch rules, from `sim.compliance`
+#[derive(Debug, Clone)]
+pub struct ComplianceLimits {
+    pub drogue_rate_m_s: [f64; 2],
+    pub main_rate_m_s: [f64; 2],
+    pub max_touchdown_energy_j: f64,
+}
+
+impl ComplianceLimits {
+    pub fn from_params(params: &ParameterMap) -> Result<Self> {
+        let params = params.get_map("sim.compliance")?;
+        let range = |name: &str| -> Result<[f64; 2]> {
+            let map = params.get_map(name)?;
+            Ok([
+                map.get_param("min")?.value_float()?,
+                map.get_param("max")?.value_float()?,
+            ])
+        };
+
+        Ok(Self {
+            drogue_rate_m_s: range("drogue_rate")?,
+            main_rate_m_s: range("main_rate")?,
+            max_touchdown_energy_j: params.get_param("max_touchdown_energy")?.value_float()?,
+        })
+    }
+}
+
+/// Descent under each parachute and touchdown conditions
+#[derive(Debug, Clone, Default)]
+pub struct DescentMetrics {
+    /// Mean vertical speed under the drogue, once settled [m/s]. None if it never deployed.
+    pub drogue_rate_m_s: Option<f64>,
+    /// Mean vertical speed under the main, once settled [m/s]. None if it never deployed.
+    pub main_rate_m_s: Option<f64>,
+    /// None if the run ended before touchdown
+    pub touchdown_speed_m_s: Option<f64>,
+    pub touchdown_energy_j: Option<f64>,
+}
+
+impl DescentMetrics {
+    /// Computes the descent metrics from the trajectory, the deployment times and the mass at
+    /// touchdown. The vehicle lands as a single section.
+    pub fn compute(
+        pos_n_m: &[Sample<Vector3<f64>>],
+        vel_n_m_s: &[Sample<Vector3<f64>>],
+        t_drogue: Option<f64>,
+        t_main: Option<f64>,
+        mass_kg: f64,
+    ) -> DescentMetrics {
+        let mean_rate = |from: f64, to: f64| {
+            let rates: Vec<f64> = vel_n_m_s
+                .iter()
+                .filter(|(t, _)| *t >= from + DESCENT_SETTLING_TIME_S && *t < to)
+                .map(|(_, vel)| vel[2])
+                .collect();
+
+            (!rates.is_empty()).then(|| rates.iter().sum::<f64>() / rates.len() as f64)
+        };
+
+        let t_end = vel_n_m_s.last().map_or(0.0, |(t, _)| *t);
+        let drogue_rate_m_s = t_drogue.and_then(|t| mean_rate(t, t_main.unwrap_or(t_end)));
+        let main_rate_m_s = t_main.and_then(|t| mean_rate(t, t_end));
+
+        // The simulation stops at the first step below the ground
+        let landed = pos_n_m.last().is_some_and(|(_, pos)| pos[2] >= 0.0);
+        let touchdown_speed_m_s = vel_n_m_s
+            .last()
+            .filter(|_| landed)
+            .map(|(_, vel)| vel.norm());
+
+        DescentMetrics {
+            drogue_rate_m_s,
+            main_rate_m_s,
+            touchdown_speed_m_s,
+            touchdown_energy_j: touchdown_speed_m_s.map(|v| 0.5 * mass_kg * v * v),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ComplianceCheck {
+    pub rule: String,
+    pub passed: bool,
+    pub actual: String,
+}
+
+/// Checks the descent against the limits. Missing phases fail the check: the evidence must come
+/// from a complete recovery.
+pub fn check_compliance(
+    descent: &DescentMetrics,
+    limits: &ComplianceLimits,
+) -> Vec<ComplianceCheck> {
+    let rate_check = |name: &str, rate: Option<f64>, [min, max]: [f64; 2]| ComplianceCheck {
+        rule: format!("{name} descent rate in [{min:.1}, {max:.1}] m/s"),
+        passed: rate.is_some_and(|r| r >= min && r <= max),
+        actual: rate.map_or("not deployed".to_string(), |r| format!("{r:.1} m/s")),
+    };
+
+    vec![
+        rate_check("drogue", descent.drogue_rate_m_s, limits.drogue_rate_m_s),
+        rate_check("main", descent.main_rate_m_s, limits.main_rate_m_s),
+        ComplianceCheck {
+            rule: format!(
+                "touchdown kinetic energy <= {:.0} J",
+                limits.max_touchdown_energy_j
+            ),
+            passed: descent
+                .touchdown_energy_j
+                .is_some_and(|e| e <= limits.max_touchdown_energy_j),
+            actual: match (descent.touchdown_energy_j, descent.touchdown_speed_m_s) {
+                (Some(e), Some(v)) => format!("{e:.0} J at {v:.1} m/s"),
+                _ => "no touchdown".to_string(),
+            },
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_descent_compliance() {
+        // Drogue at 10 s, main at 30 s, touchdown at 60 s
+        let mut pos = vec![];
+        let mut vel = vec![];
+        for i in 0..=600 {
+            let t = i as f64 * 0.1;
+            let vz = match t {
+                t if t < 10.0 => 0.0,
+                // Opening transient, excluded
+                t if t < 13.0 => 60.0,
+                t if t < 30.0 => 25.0,
+                t if t < 33.0 => 2.0,
+                _ => 6.0,
+            };
+            vel.push((t, Vector3::new(1.0, 0.0, vz)));
+            pos.push((
+                t,
+                Vector3::new(0.0, 0.0, if i == 600 { 0.1 } else { -100.0 }),
+            ));
+        }
+
+        let descent = DescentMetrics::compute(&pos, &vel, Some(10.0), Some(30.0), 20.0);
+        assert_relative_eq!(descent.drogue_rate_m_s.unwrap(), 25.0);
+        assert_relative_eq!(descent.main_rate_m_s.unwrap(), 6.0);
+        assert_relative_eq!(descent.touchdown_energy_j.unwrap(), 0.5 * 20.0 * 37.0);
+
+        let limits = ComplianceLimits {
+            drogue_rate_m_s: [15.0, 30.0],
+            main_rate_m_s: [4.0, 9.0],
+            max_touchdown_energy_j: 300.0,
+        };
+        let checks = check_compliance(&descent, &limits);
+        assert!(checks[0].passed && checks[1].passed);
+        assert!(!checks[2].passed);
+
+        // Run ended under the drogue
+        let descent = DescentMetrics::compute(&pos[..200], &vel[..200], Some(10.0), None, 20.0);
+        assert!(descent.main_rate_m_s.is_none());
+        assert!(descent.touchdown_energy_j.is_none());
+        assert!(!check_compliance(&descent, &limits)[1].passed);
+    }
+}
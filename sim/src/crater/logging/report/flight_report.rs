@@ -0,0 +1,594 @@
+use anyhow::Result;
+use crater_gnc::{
+    datatypes::{gnc::NavigationOutput, sensors::GnssSensorSample},
+    events::Event,
+    mav_crater::MavMessage,
+};
+use log::warn;
+use nalgebra::Vector3;
+use num_traits::FromPrimitive;
+
+use super::compliance::{ComplianceCheck, ComplianceLimits, DescentMetrics, check_compliance};
+
+use crate::{
+    crater::{
+        aero::aerodynamics::AeroState,
+        channels,
+        environment::LaunchSite,
+        events::{GncEventItem, SimEvent},
+        rocket::{
+            mass::RocketMassProperties,
+            rocket_data::{RocketAccelerations, RocketState},
+        },
+    },
+    parameters::{ParameterMap, ParameterTree},
+    telemetry::{
+        TelemetryReceiver, TelemetryService, Timestamped,
+        recording::{RecordLine, decode_channel},
+    },
+    utils::capacity::Capacity::Unbounded,
+};
+
+/// Time series sample: (time in seconds, value)
+pub type Sample<T> = (f64, T);
+
+#[derive(Debug, Clone, Default)]
+pub struct EnvelopeMetrics {
+    pub apogee_m: f64,
+    pub t_apogee_s: f64,
+    pub max_speed_m_s: f64,
+    pub max_mach: f64,
+    pub max_accel_g: f64,
+    pub max_dyn_pressure_pa: f64,
+    pub flight_time_s: f64,
+    pub landing_n_m: Vector3<f64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct NavErrors {
+    pub pos_err_m: Vec<Sample<f64>>,
+    pub vel_err_m_s: Vec<Sample<f64>>,
+    pub att_err_deg: Vec<Sample<f64>>,
+}
+
+/// Summary of a single flight, either simulated or reconstructed from a flight log
+#[derive(Debug, Clone)]
+pub struct FlightReport {
+    pub title: String,
+    pub pos_n_m: Vec<Sample<Vector3<f64>>>,
+    pub speed_m_s: Vec<Sample<f64>>,
+    pub mach: Vec<Sample<f64>>,
+    /// Angular rate about the body x axis
+    pub roll_rate_deg_s: Vec<Sample<f64>>,
+    pub events: Vec<Sample<String>>,
+    pub metrics: EnvelopeMetrics,
+    /// Navigation errors wrt the ground truth. Only available in simulation.
+    pub nav_errors: Option<NavErrors>,
+    pub descent: DescentMetrics,
+    /// Recovery checks against the limits in `sim.compliance`
+    pub compliance: Vec<ComplianceCheck>,
+    /// Flattened (path, value) list of the parameters used for the run
+    pub parameters: Vec<(String, String)>,
+}
+
+/// Time series of a flight, whatever their source, from which the report is built
+#[derive(Debug, Clone, Default)]
+pub struct FlightData {
+    pub pos_n_m: Vec<Sample<Vector3<f64>>>,
+    pub vel_n_m_s: Vec<Sample<Vector3<f64>>>,
+    /// Angular rate about the body x axis
+    pub roll_rate_deg_s: Vec<Sample<f64>>,
+    /// Norm of the acceleration
+    pub accel_m_s2: Vec<Sample<f64>>,
+    pub mach: Vec<Sample<f64>>,
+    pub dyn_pressure_pa: Vec<Sample<f64>>,
+    /// Timeline of the flight, as displayed
+    pub events: Vec<Sample<String>>,
+    pub t_drogue_s: Option<f64>,
+    pub t_main_s: Option<f64>,
+    /// Mass at touchdown
+    pub mass_kg: f64,
+    pub nav_errors: Option<NavErrors>,
+}
+
+impl FlightData {
+    /// Reconstructs the flight from a telemetry recording of the flight software (see
+    /// `crater::recording::record_gnc`). The trajectory is the ground truth if recorded, else the
+    /// navigation output. The airspeed is not recorded, and the mass is taken as given.
+    pub fn from_recording(lines: &[RecordLine], mass_kg: f64) -> Result<Self> {
+        let truth = samples(decode_channel::<NavigationOutput>(
+            lines,
+            channels::sensors::IDEAL_NAV_OUTPUT,
+        )?);
+        let nav = samples(decode_channel::<NavigationOutput>(
+            lines,
+            channels::gnc::NAV_OUTPUT,
+        )?);
+        let events = samples(decode_channel::<GncEventItem>(
+            lines,
+            channels::gnc::GNC_EVENTS,
+        )?);
+
+        let trajectory = if truth.is_empty() { &nav } else { &truth };
+        let to_f64 = |v: &Vector3<f32>| v.map(|x| x as f64);
+
+        Ok(Self {
+            pos_n_m: trajectory
+                .iter()
+                .map(|(t, n)| (*t, to_f64(&n.pos_n_m)))
+                .collect(),
+            vel_n_m_s: trajectory
+                .iter()
+                .map(|(t, n)| (*t, to_f64(&n.vel_n_m_s)))
+                .collect(),
+            roll_rate_deg_s: trajectory
+                .iter()
+                .map(|(t, n)| (*t, (n.angvel_unbias_b_rad_s.x as f64).to_degrees()))
+                .collect(),
+            accel_m_s2: trajectory
+                .iter()
+                .map(|(t, n)| (*t, n.acc_unbias_b_m_s2.norm() as f64))
+                .collect(),
+            t_drogue_s: first_event(&events, Event::RecoveryDrogueFired),
+            t_main_s: first_event(&events, Event::RecoveryMainFired),
+            events: events
+                .iter()
+                .map(|(t, ev)| (*t, format!("[gnc] {:?} from {:?}", ev.event, ev.src)))
+                .collect(),
+            mass_kg,
+            nav_errors: FlightReportBuilder::nav_errors(&truth, &nav),
+            ..Default::default()
+        })
+    }
+
+    /// Reconstructs the flight from the records of an onboard log (see
+    /// `crater::gnc::log_download::decode_log`), on the flight computer clock. The trajectory is
+    /// the one of the GNSS fixes, from the first one. The airspeed is not logged, and the mass is
+    /// taken as given.
+    pub fn from_flight_log(messages: &[MavMessage], mass_kg: f64) -> Self {
+        let mut data = Self {
+            mass_kg,
+            ..Default::default()
+        };
+        let mut origin: Option<LaunchSite> = None;
+        let mut events: Vec<Sample<GncEventItem>> = vec![];
+
+        for msg in messages.iter() {
+            match msg {
+                MavMessage::SensGnssSample(fix) => {
+                    let t = fix.timestamp_us as f64 * 1e-6;
+                    let fix = GnssSensorSample::from(fix);
+                    if !fix.has_3d_fix() {
+                        continue;
+                    }
+
+                    let origin = origin.get_or_insert(LaunchSite {
+                        latitude_deg: fix.lat_deg(),
+                        longitude_deg: fix.lon_deg(),
+                        altitude_m: fix.alt_msl_m as f64,
+                    });
+                    data.pos_n_m.push((
+                        t,
+                        origin.ned(fix.lat_deg(), fix.lon_deg(), fix.alt_msl_m as f64),
+                    ));
+                    data.vel_n_m_s.push((t, fix.vel_n_m_s.map(|v| v as f64)));
+                }
+                MavMessage::SensImuSample(imu) => {
+                    let t = imu.timestamp_us as f64 * 1e-6;
+                    let accel = Vector3::from(imu.accel_m_s2).map(|a| a as f64);
+                    data.roll_rate_deg_s.push((t, imu.ang_vel_deg_s[0] as f64));
+                    data.accel_m_s2.push((t, accel.norm()));
+                }
+                MavMessage::GncEvent(ev) => {
+                    let t = ev.timestamp_us as f64 * 1e-6;
+                    let Some(event) = Event::from_u16(ev.event) else {
+                        warn!("Unknown GNC event {} in the log", ev.event);
+                        continue;
+                    };
+                    let item = GncEventItem {
+                        src: ev.source,
+                        event,
+                    };
+
+                    // The event log is written again at the end of the flight
+                    if !events.contains(&(t, item)) {
+                        events.push((t, item));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        events.sort_by(|a, b| a.0.total_cmp(&b.0));
+        data.t_drogue_s = first_event(&events, Event::RecoveryDrogueFired);
+        data.t_main_s = first_event(&events, Event::RecoveryMainFired);
+        data.events = events
+            .into_iter()
+            .map(|(t, ev)| (t, format!("[gnc] {:?} from {:?}", ev.event, ev.src)))
+            .collect();
+
+        data
+    }
+}
+
+fn samples<T>(values: Vec<Timestamped<T>>) -> Vec<Sample<T>> {
+    values
+        .into_iter()
+        .map(|Timestamped(t, v)| (t.monotonic.elapsed_seconds_f64(), v))
+        .collect()
+}
+
+fn first_event(events: &[Sample<GncEventItem>], event: Event) -> Option<f64> {
+    events
+        .iter()
+        .find_map(|(t, ev)| (ev.event == event).then_some(*t))
+}
+
+impl FlightReport {
+    pub fn new(title: &str, data: FlightData, params: &ParameterMap) -> Self {
+        let mut metrics = EnvelopeMetrics {
+            apogee_m: f64::NEG_INFINITY,
+            ..Default::default()
+        };
+
+        for (t, pos) in data.pos_n_m.iter() {
+            if -pos[2] > metrics.apogee_m {
+                metrics.apogee_m = -pos[2];
+                metrics.t_apogee_s = *t;
+            }
+            metrics.flight_time_s = *t;
+            metrics.landing_n_m = *pos;
+        }
+
+        let speed_m_s: Vec<Sample<f64>> = data
+            .vel_n_m_s
+            .iter()
+            .map(|(t, vel)| (*t, vel.norm()))
+            .collect();
+        let max = |series: &[Sample<f64>]| series.iter().fold(0.0, |max, (_, v)| v.max(max));
+        metrics.max_speed_m_s = max(&speed_m_s);
+        metrics.max_accel_g = max(&data.accel_m_s2) / FlightReportBuilder::G0;
+        metrics.max_dyn_pressure_pa = max(&data.dyn_pressure_pa);
+        metrics.max_mach = max(&data.mach);
+
+        let descent = DescentMetrics::compute(
+            &data.pos_n_m,
+            &data.vel_n_m_s,
+            data.t_drogue_s,
+            data.t_main_s,
+            data.mass_kg,
+        );
+        let compliance = match ComplianceLimits::from_params(params) {
+            Ok(limits) => check_compliance(&descent, &limits),
+            Err(e) => {
+                warn!("Recovery compliance not checked: {e}");
+                vec![]
+            }
+        };
+
+        let mut events = data.events;
+        events.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut parameters = vec![];
+        flatten_params(params, &mut parameters);
+
+        FlightReport {
+            title: title.to_string(),
+            pos_n_m: data.pos_n_m,
+            speed_m_s,
+            mach: data.mach,
+            roll_rate_deg_s: data.roll_rate_deg_s,
+            events,
+            metrics,
+            nav_errors: data.nav_errors,
+            descent,
+            compliance,
+            parameters,
+        }
+    }
+}
+
+/// Subscribes to the telemetry channels needed for the report. Must be created before
+/// running the simulation, then built once the simulation is over.
+pub struct FlightReportBuilder {
+    rx_state: TelemetryReceiver<RocketState>,
+    rx_accel: TelemetryReceiver<RocketAccelerations>,
+    rx_aerostate: TelemetryReceiver<AeroState>,
+    rx_mass: TelemetryReceiver<RocketMassProperties>,
+    rx_sim_events: TelemetryReceiver<SimEvent>,
+    rx_gnc_events: TelemetryReceiver<GncEventItem>,
+    rx_ideal_nav: TelemetryReceiver<NavigationOutput>,
+    rx_nav: TelemetryReceiver<NavigationOutput>,
+}
+
+impl FlightReportBuilder {
+    const G0: f64 = 9.80665;
+
+    pub fn new(ts: &TelemetryService) -> Result<Self> {
+        Ok(Self {
+            rx_state: ts.subscribe(channels::rocket::STATE, Unbounded)?,
+            rx_accel: ts.subscribe(channels::rocket::ACCEL, Unbounded)?,
+            rx_aerostate: ts.subscribe(channels::rocket::AERO_STATE, Unbounded)?,
+            rx_mass: ts.subscribe(channels::rocket::MASS_ROCKET, Unbounded)?,
+            rx_sim_events: ts.subscribe_mp(channels::sim::SIM_EVENTS, Unbounded)?,
+            rx_gnc_events: ts.subscribe_mp(channels::gnc::GNC_EVENTS, Unbounded)?,
+            rx_ideal_nav: ts.subscribe(channels::sensors::IDEAL_NAV_OUTPUT, Unbounded)?,
+            rx_nav: ts.subscribe(channels::gnc::NAV_OUTPUT, Unbounded)?,
+        })
+    }
+
+    fn drain<T>(rx: &TelemetryReceiver<T>) -> Vec<Sample<T>> {
+        let mut out = vec![];
+        while let Ok(Timestamped(t, v)) = rx.try_recv() {
+            out.push((t.monotonic.elapsed_seconds_f64(), v));
+        }
+        out
+    }
+
+    pub fn build(self, title: &str, params: &ParameterMap) -> FlightReport {
+        FlightReport::new(title, self.drain_data(), params)
+    }
+
+    /// Flight data of the simulation, from the ground truth
+    fn drain_data(self) -> FlightData {
+        let states = Self::drain(&self.rx_state);
+        let aerostates = Self::drain(&self.rx_aerostate);
+
+        let mut data = FlightData::default();
+        for (t, state) in states.iter() {
+            data.pos_n_m.push((*t, state.pos_n_m()));
+            data.vel_n_m_s.push((*t, state.vel_n_m_s()));
+            data.roll_rate_deg_s
+                .push((*t, state.angvel_b_rad_s().x.to_degrees()));
+        }
+
+        data.accel_m_s2 = Self::drain(&self.rx_accel)
+            .into_iter()
+            .map(|(t, accel)| (t, accel.acc_b_m_s2.norm()))
+            .collect();
+
+        for (t, aero) in aerostates.iter() {
+            let q = 0.5 * aero.air_density_kg_m3 * aero.v_air_norm_m_s.powi(2);
+            data.dyn_pressure_pa.push((*t, q));
+            data.mach.push((*t, aero.mach));
+        }
+
+        let sim_events = Self::drain(&self.rx_sim_events);
+        let deployment = |chute: &str| {
+            sim_events.iter().find_map(|(t, ev)| match ev {
+                SimEvent::FsmTransition { fsm, target, .. }
+                    if fsm == "rocket" && target == chute =>
+                {
+                    Some(*t)
+                }
+                _ => None,
+            })
+        };
+        data.t_drogue_s = deployment("DescentDrogue");
+        data.t_main_s = deployment("DescentMain");
+        data.mass_kg = Self::drain(&self.rx_mass)
+            .last()
+            .map_or(0.0, |(_, mass)| mass.mass_kg);
+
+        data.events = sim_events
+            .into_iter()
+            .map(|(t, ev)| match ev {
+                SimEvent::FsmTransition {
+                    fsm,
+                    source,
+                    target,
+                } => (t, format!("[sim] {fsm}: {source} -> {target}")),
+                ev => (t, format!("[sim] {ev:?}")),
+            })
+            .chain(
+                Self::drain(&self.rx_gnc_events)
+                    .into_iter()
+                    .map(|(t, ev)| (t, format!("[gnc] {:?} from {:?}", ev.event, ev.src))),
+            )
+            .collect();
+
+        data.nav_errors =
+            Self::nav_errors(&Self::drain(&self.rx_ideal_nav), &Self::drain(&self.rx_nav));
+
+        data
+    }
+
+    /// Compares navigation outputs with the ground truth, matching samples by timestamp
+    fn nav_errors(
+        truth: &[Sample<NavigationOutput>],
+        nav: &[Sample<NavigationOutput>],
+    ) -> Option<NavErrors> {
+        if truth.is_empty() || nav.is_empty() {
+            return None;
+        }
+
+        let mut errors = NavErrors {
+            pos_err_m: vec![],
+            vel_err_m_s: vec![],
+            att_err_deg: vec![],
+        };
+
+        let mut i_truth = 0;
+        for (t, nav) in nav.iter() {
+            while i_truth + 1 < truth.len() && truth[i_truth + 1].0 <= *t {
+                i_truth += 1;
+            }
+            let truth = &truth[i_truth].1;
+
+            errors
+                .pos_err_m
+                .push((*t, (nav.pos_n_m - truth.pos_n_m).norm() as f64));
+            errors
+                .vel_err_m_s
+                .push((*t, (nav.vel_n_m_s - truth.vel_n_m_s).norm() as f64));
+            errors
+                .att_err_deg
+                .push((*t, nav.quat_nb.angle_to(&truth.quat_nb).to_degrees() as f64));
+        }
+
+        Some(errors)
+    }
+}
+
+fn flatten_params(params: &ParameterMap, out: &mut Vec<(String, String)>) {
+    for (_, elem) in params.iter() {
+        match elem {
+            ParameterTree::Node(map) => flatten_params(map, out),
+            ParameterTree::Leaf(param) => {
+                out.push((param.path().to_string(), format!("{:?}", param.value())))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use crater_gnc::mav_crater::{
+        ComponentId, GncEvent_DATA, GnssFixType, SensGnssSample_DATA, SensImuSample_DATA,
+    };
+    use nalgebra::UnitQuaternion;
+
+    use super::*;
+    use crate::telemetry::recording::Recordable;
+
+    /// Ballistic flight straight up: apogee of 500 m at 10 s, back on the ground at 20 s
+    fn trajectory(t: f64) -> (Vector3<f64>, Vector3<f64>) {
+        (
+            Vector3::new(0.0, 0.0, -(100.0 * t - 5.0 * t * t)),
+            Vector3::new(0.0, 0.0, -(100.0 - 10.0 * t)),
+        )
+    }
+
+    const DROGUE: GncEventItem = GncEventItem {
+        src: ComponentId::Recovery,
+        event: Event::RecoveryDrogueFired,
+    };
+
+    #[test]
+    fn test_report_from_recording() {
+        let nav = |pos: Vector3<f64>, vel: Vector3<f64>| NavigationOutput {
+            quat_nb: UnitQuaternion::identity(),
+            pos_n_m: pos.cast(),
+            vel_n_m_s: vel.cast(),
+            angvel_unbias_b_rad_s: Vector3::new(0.1, 0.0, 0.0),
+            acc_unbias_b_m_s2: Vector3::new(0.0, 0.0, 20.0),
+        };
+        let line = |t_us: i64, channel: &str, value: serde_json::Value| RecordLine {
+            t_us,
+            channel: channel.to_string(),
+            value,
+        };
+
+        let mut lines = vec![];
+        for i in 0..=200 {
+            let t_us = i * 100_000;
+            let (pos, vel) = trajectory(t_us as f64 * 1e-6);
+            let truth = nav(pos, vel).to_record();
+            let estimate = nav(pos + Vector3::new(1.0, 0.0, 0.0), vel).to_record();
+
+            lines.push(line(
+                t_us,
+                channels::sensors::IDEAL_NAV_OUTPUT,
+                serde_json::to_value(truth).unwrap(),
+            ));
+            lines.push(line(
+                t_us,
+                channels::gnc::NAV_OUTPUT,
+                serde_json::to_value(estimate).unwrap(),
+            ));
+        }
+        lines.push(line(
+            10_000_000,
+            channels::gnc::GNC_EVENTS,
+            serde_json::to_value(DROGUE.to_record()).unwrap(),
+        ));
+
+        let data = FlightData::from_recording(&lines, 2.0).unwrap();
+        let report = FlightReport::new("Recorded", data, &ParameterMap::default());
+
+        assert_relative_eq!(report.metrics.apogee_m, 500.0, epsilon = 1e-3);
+        assert_relative_eq!(report.metrics.t_apogee_s, 10.0);
+        assert_relative_eq!(report.metrics.max_speed_m_s, 100.0, epsilon = 1e-3);
+        assert_relative_eq!(report.metrics.max_accel_g, 20.0 / 9.80665, epsilon = 1e-6);
+        assert_relative_eq!(
+            report.roll_rate_deg_s[0].1,
+            0.1f64.to_degrees(),
+            epsilon = 1e-5
+        );
+        assert_eq!(
+            report.events,
+            [(10.0, "[gnc] RecoveryDrogueFired from Recovery".to_string())]
+        );
+        assert!(report.descent.drogue_rate_m_s.is_some());
+        assert!(report.descent.touchdown_energy_j.is_some());
+
+        let nav_errors = report.nav_errors.unwrap();
+        assert_eq!(nav_errors.pos_err_m.len(), 201);
+        assert!(
+            nav_errors
+                .pos_err_m
+                .iter()
+                .all(|(_, e)| (e - 1.0).abs() < 1e-3)
+        );
+    }
+
+    #[test]
+    fn test_report_from_flight_log() {
+        let pad = LaunchSite {
+            latitude_deg: 41.8,
+            longitude_deg: 14.0,
+            altitude_m: 1400.0,
+        };
+
+        let mut messages = vec![MavMessage::SensGnssSample(SensGnssSample_DATA {
+            fix_type: GnssFixType::NoFix,
+            ..SensGnssSample_DATA::DEFAULT
+        })];
+        for i in 0..=20 {
+            let t = i as f64;
+            let (pos, vel) = trajectory(t);
+            let (lat, lon, alt) = pad.geodetic(&pos);
+
+            messages.push(MavMessage::SensGnssSample(SensGnssSample_DATA {
+                timestamp_us: i * 1_000_000,
+                fix_type: GnssFixType::Fix3d,
+                lat_deg_e7: (lat.to_degrees() * 1e7).round() as i32,
+                lon_deg_e7: (lon.to_degrees() * 1e7).round() as i32,
+                alt_msl_m: alt as f32,
+                vel_n_m_s: vel.cast::<f32>().into(),
+                ..SensGnssSample_DATA::DEFAULT
+            }));
+            messages.push(MavMessage::SensImuSample(SensImuSample_DATA {
+                timestamp_us: i * 1_000_000,
+                accel_m_s2: [0.0, 0.0, -9.80665],
+                ang_vel_deg_s: [5.0, 0.0, 0.0],
+                ..SensImuSample_DATA::DEFAULT
+            }));
+        }
+
+        // Logged when fired, then again with the event log at the end of the flight
+        let drogue = MavMessage::GncEvent(GncEvent_DATA {
+            timestamp_us: 10_000_000,
+            source: DROGUE.src,
+            event: DROGUE.event as u16,
+        });
+        messages.insert(25, drogue.clone());
+        messages.push(drogue);
+
+        let data = FlightData::from_flight_log(&messages, 2.0);
+        let report = FlightReport::new("Flight", data, &ParameterMap::default());
+
+        assert_eq!(report.pos_n_m.len(), 21);
+        assert_relative_eq!(report.metrics.apogee_m, 500.0, epsilon = 0.1);
+        assert_relative_eq!(report.metrics.t_apogee_s, 10.0);
+        assert_relative_eq!(report.metrics.landing_n_m.norm(), 0.0, epsilon = 0.1);
+        assert_relative_eq!(report.metrics.max_accel_g, 1.0, epsilon = 1e-6);
+        assert_eq!(report.roll_rate_deg_s[0].1, 5.0);
+        assert_eq!(
+            report.events,
+            [(10.0, "[gnc] RecoveryDrogueFired from Recovery".to_string())]
+        );
+        assert!(report.nav_errors.is_none());
+    }
+}
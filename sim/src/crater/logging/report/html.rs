@@ -0,0 +1,239 @@
+use std::fmt::Write;
+
+use super::flight_report::{FlightReport, Sample};
+
+const PLOT_WIDTH: f64 = 640.0;
+const PLOT_HEIGHT: f64 = 320.0;
+const PLOT_MARGIN: f64 = 50.0;
+const PLOT_MAX_POINTS: usize = 2000;
+const PLOT_COLORS: [&str; 4] = ["#1f77b4", "#d62728", "#2ca02c", "#ff7f0e"];
+
+/// Renders the report as a self-contained HTML page, with inline SVG plots
+pub fn render_html(report: &FlightReport) -> String {
+    let mut html = String::new();
+    let m = &report.metrics;
+
+    writeln!(
+        html,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n\
+         <style>body {{ font-family: sans-serif; margin: 2em; }} \
+         table {{ border-collapse: collapse; }} \
+         td, th {{ border: 1px solid #ccc; padding: 2px 8px; text-align: left; }}</style>\n\
+         </head>\n<body>\n<h1>{0}</h1>",
+        escape(&report.title)
+    )
+    .unwrap();
+
+    html.push_str("<h2>Flight envelope</h2>\n<table>\n");
+    let rows = [
+        ("Apogee", format!("{:.1} m @ T+{:.2} s", m.apogee_m, m.t_apogee_s)),
+        ("Max speed", format!("{:.1} m/s", m.max_speed_m_s)),
+        ("Max Mach", format!("{:.3}", m.max_mach)),
+        ("Max acceleration", format!("{:.2} g", m.max_accel_g)),
+        ("Max dynamic pressure", format!("{:.0} Pa", m.max_dyn_pressure_pa)),
+        ("Flight time", format!("{:.2} s", m.flight_time_s)),
+        (
+            "Landing point (N, E)",
+            format!("({:.1}, {:.1}) m", m.landing_n_m[0], m.landing_n_m[1]),
+        ),
+    ];
+    for (name, value) in rows {
+        writeln!(html, "<tr><th>{name}</th><td>{value}</td></tr>").unwrap();
+    }
+    html.push_str("</table>\n");
+
+    html.push_str("<h2>Recovery</h2>\n<table>\n");
+    let d = &report.descent;
+    let value =
+        |v: Option<f64>, unit: &str| v.map_or("-".to_string(), |v| format!("{v:.1} {unit}"));
+    let rows = [
+        ("Drogue descent rate", value(d.drogue_rate_m_s, "m/s")),
+        ("Main descent rate", value(d.main_rate_m_s, "m/s")),
+        ("Touchdown speed", value(d.touchdown_speed_m_s, "m/s")),
+        ("Touchdown kinetic energy", value(d.touchdown_energy_j, "J")),
+    ];
+    for (name, value) in rows {
+        writeln!(html, "<tr><th>{name}</th><td>{value}</td></tr>").unwrap();
+    }
+    html.push_str("</table>\n");
+
+    if !report.compliance.is_empty() {
+        html.push_str(
+            "<h3>Compliance</h3>\n<table>\n<tr><th>Rule</th><th>Result</th><th>Actual</th></tr>\n",
+        );
+        for check in report.compliance.iter() {
+            let (status, color) = if check.passed {
+                ("PASS", "#2ca02c")
+            } else {
+                ("FAIL", "#d62728")
+            };
+            writeln!(
+                html,
+                "<tr><td>{}</td><td style=\"color: {color}\">{status}</td><td>{}</td></tr>",
+                escape(&check.rule),
+                escape(&check.actual)
+            )
+            .unwrap();
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("<h2>Trajectory</h2>\n");
+    let altitude: Vec<Sample<f64>> = report.pos_n_m.iter().map(|(t, p)| (*t, -p[2])).collect();
+    html.push_str(&svg_plot("Altitude", "t [s]", "[m]", &[("altitude", &altitude)]));
+
+    let ground_track: Vec<Sample<f64>> = report.pos_n_m.iter().map(|(_, p)| (p[1], p[0])).collect();
+    html.push_str(&svg_plot(
+        "Ground track",
+        "east [m]",
+        "north [m]",
+        &[("track", &ground_track)],
+    ));
+
+    html.push_str(&svg_plot(
+        "Speed",
+        "t [s]",
+        "[m/s]",
+        &[("speed", &report.speed_m_s)],
+    ));
+    html.push_str(&svg_plot("Mach", "t [s]", "[-]", &[("mach", &report.mach)]));
+
+    if let Some(nav) = &report.nav_errors {
+        html.push_str("<h2>Navigation errors</h2>\n");
+        html.push_str(&svg_plot(
+            "Position error",
+            "t [s]",
+            "[m]",
+            &[("pos", &nav.pos_err_m)],
+        ));
+        html.push_str(&svg_plot(
+            "Velocity error",
+            "t [s]",
+            "[m/s]",
+            &[("vel", &nav.vel_err_m_s)],
+        ));
+        html.push_str(&svg_plot(
+            "Attitude error",
+            "t [s]",
+            "[deg]",
+            &[("att", &nav.att_err_deg)],
+        ));
+    }
+
+    html.push_str("<h2>Events</h2>\n<table>\n<tr><th>Time [s]</th><th>Event</th></tr>\n");
+    for (t, event) in report.events.iter() {
+        writeln!(html, "<tr><td>{t:.3}</td><td>{}</td></tr>", escape(event)).unwrap();
+    }
+    html.push_str("</table>\n");
+
+    html.push_str("<h2>Parameters</h2>\n<table>\n<tr><th>Path</th><th>Value</th></tr>\n");
+    for (path, value) in report.parameters.iter() {
+        writeln!(
+            html,
+            "<tr><td>{}</td><td>{}</td></tr>",
+            escape(path),
+            escape(value)
+        )
+        .unwrap();
+    }
+    html.push_str("</table>\n</body>\n</html>\n");
+
+    html
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Simple line plot of one or more (x, y) series
+fn svg_plot(title: &str, xlabel: &str, ylabel: &str, series: &[(&str, &[Sample<f64>])]) -> String {
+    let points = series.iter().flat_map(|(_, s)| s.iter());
+
+    let (mut xmin, mut xmax, mut ymin, mut ymax) = (
+        f64::INFINITY,
+        f64::NEG_INFINITY,
+        f64::INFINITY,
+        f64::NEG_INFINITY,
+    );
+    for (x, y) in points.filter(|(x, y)| x.is_finite() && y.is_finite()) {
+        xmin = xmin.min(*x);
+        xmax = xmax.max(*x);
+        ymin = ymin.min(*y);
+        ymax = ymax.max(*y);
+    }
+
+    if xmin > xmax {
+        return format!("<p>{}: no data</p>\n", escape(title));
+    }
+    if xmax - xmin < f64::EPSILON {
+        xmax = xmin + 1.0;
+    }
+    if ymax - ymin < f64::EPSILON {
+        ymax = ymin + 1.0;
+    }
+
+    let w = PLOT_WIDTH - 2.0 * PLOT_MARGIN;
+    let h = PLOT_HEIGHT - 2.0 * PLOT_MARGIN;
+    let sx = |x: f64| PLOT_MARGIN + (x - xmin) / (xmax - xmin) * w;
+    let sy = |y: f64| PLOT_HEIGHT - PLOT_MARGIN - (y - ymin) / (ymax - ymin) * h;
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        "<h3>{}</h3>\n<svg width=\"{PLOT_WIDTH}\" height=\"{PLOT_HEIGHT}\" \
+         xmlns=\"http://www.w3.org/2000/svg\" font-size=\"11\">\n\
+         <rect x=\"{PLOT_MARGIN}\" y=\"{PLOT_MARGIN}\" width=\"{w}\" height=\"{h}\" \
+         fill=\"none\" stroke=\"#888\"/>",
+        escape(title)
+    )
+    .unwrap();
+
+    // Axis limits & labels
+    writeln!(
+        svg,
+        "<text x=\"{PLOT_MARGIN}\" y=\"{:.1}\">{xmin:.4}</text>\n\
+         <text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\">{xmax:.4}</text>\n\
+         <text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\">{ymin:.4}</text>\n\
+         <text x=\"{:.1}\" y=\"{PLOT_MARGIN}\" text-anchor=\"end\">{ymax:.4}</text>\n\
+         <text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}</text>\n\
+         <text x=\"5\" y=\"{:.1}\">{}</text>",
+        PLOT_HEIGHT - PLOT_MARGIN + 15.0,
+        PLOT_WIDTH - PLOT_MARGIN,
+        PLOT_HEIGHT - PLOT_MARGIN + 15.0,
+        PLOT_MARGIN - 5.0,
+        PLOT_HEIGHT - PLOT_MARGIN,
+        PLOT_MARGIN - 5.0,
+        PLOT_WIDTH / 2.0,
+        PLOT_HEIGHT - 10.0,
+        escape(xlabel),
+        PLOT_MARGIN - 15.0,
+        escape(ylabel),
+    )
+    .unwrap();
+
+    for (i, (name, samples)) in series.iter().enumerate() {
+        let color = PLOT_COLORS[i % PLOT_COLORS.len()];
+        let step = (samples.len() / PLOT_MAX_POINTS).max(1);
+
+        let polyline: Vec<String> = samples
+            .iter()
+            .step_by(step)
+            .filter(|(x, y)| x.is_finite() && y.is_finite())
+            .map(|(x, y)| format!("{:.1},{:.1}", sx(*x), sy(*y)))
+            .collect();
+
+        writeln!(
+            svg,
+            "<polyline fill=\"none\" stroke=\"{color}\" points=\"{}\"><title>{}</title></polyline>",
+            polyline.join(" "),
+            escape(name)
+        )
+        .unwrap();
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
@@ -0,0 +1,5 @@
+pub mod compliance;
+pub mod flight_report;
+pub mod html;
+
+pub use flight_report::{FlightData, FlightReport, FlightReportBuilder};
@@ -3,30 +3,33 @@ use anyhow::Result;
 use crater_gnc::{
     components::ada::AdaResult,
     datatypes::{
-        gnc::NavigationOutput,
+        gnc::{AttitudeEstimate, NavigationOutput},
         sensors::{ImuSensorSample, MagnetometerSensorSample},
     },
 };
 use rerun::RecordingStream;
 
 use crate::crater::{
-    aero::aerodynamics::AeroState,
+    actuators::thermal::ServoPower,
+    aero::{aerodynamics::AeroState, envelope::FlightEnvelopeSample, stability::StabilitySample},
     channels,
     engine::engine::RocketEngineMassProperties,
     events::{GncEventItem, SimEvent},
-    gnc::ServoPosition,
+    gnc::{AirbrakePosition, ServoPosition, dual_fc::VoterStatus},
     rocket::{
         mass::RocketMassProperties,
         rocket_data::{RocketAccelerations, RocketActions, RocketState},
     },
+    sensors::{camera::CameraPointing, table::TableSensorSample},
 };
 
 use super::{
     crater_log_impl::{
-        AdaOutputLog, AeroStateLog, GncEventLog, IMUSampleLog, MagnetometerSampleLog,
+        AdaOutputLog, AeroStateLog, AirbrakePositionLog, AttitudeEstimateLog, CameraPointingLog,
+        FinDeflectionLog, FlightEnvelopeLog, GncEventLog, IMUSampleLog, MagnetometerSampleLog,
         NavigationOutputLog, RocketAccelLog, RocketActionsLog, RocketEngineMassPropertiesLog,
         RocketMassPropertiesLog, RocketStateRawLog, RocketStateUILog, ServoPositionLog,
-        SimEventLog,
+        ServoPowerLog, SimEventLog, StabilityLog, TableSensorLog, VoterStatusLog,
     },
     rerun_logger::{ChannelName, RerunLogConfig, RerunLoggerBuilder},
 };
@@ -53,15 +56,30 @@ impl RerunLogConfig for CraterUiLogConfig {
             ChannelName::from_base_path(channels::rocket::STATE, "timeseries"),
             RocketStateRawLog::default(),
         )?;
+        let rx_launch_site = builder
+            .telemetry()
+            .subscribe_latest(channels::sim::LAUNCH_SITE)?;
         builder.log_telemetry::<RocketState>(
             ChannelName::from_base_path(channels::rocket::STATE, "timeseries"),
-            RocketStateUILog::default(),
+            RocketStateUILog::new(rx_launch_site),
+        )?;
+        builder.log_telemetry::<RocketState>(
+            ChannelName::from_base_path(channels::rocket::STATE_REF, "timeseries"),
+            RocketStateRawLog::default(),
         )?;
 
         builder.log_telemetry::<AeroState>(
             ChannelName::from_base_path(channels::rocket::AERO_STATE, "timeseries"),
             AeroStateLog::default(),
         )?;
+        builder.log_telemetry::<FlightEnvelopeSample>(
+            ChannelName::from_base_path(channels::rocket::ENVELOPE, "timeseries"),
+            FlightEnvelopeLog::default(),
+        )?;
+        builder.log_telemetry::<StabilitySample>(
+            ChannelName::from_base_path(channels::rocket::STABILITY, "timeseries"),
+            StabilityLog::default(),
+        )?;
         builder.log_telemetry::<RocketActions>(
             ChannelName::from_base_path(channels::rocket::ACTIONS, "timeseries"),
             RocketActionsLog::default(),
@@ -70,6 +88,10 @@ impl RerunLogConfig for CraterUiLogConfig {
             ChannelName::from_base_path(channels::rocket::ACCEL, "timeseries"),
             RocketAccelLog::default(),
         )?;
+        builder.log_telemetry::<RocketAccelerations>(
+            ChannelName::from_base_path(channels::rocket::ACCEL_REF, "timeseries"),
+            RocketAccelLog::default(),
+        )?;
         builder.log_telemetry::<ServoPosition>(
             ChannelName::from_base_path(channels::gnc::SERVO_COMMAND, "timeseries"),
             ServoPositionLog::default(),
@@ -78,6 +100,22 @@ impl RerunLogConfig for CraterUiLogConfig {
             ChannelName::from_base_path(channels::actuators::IDEAL_SERVO_POSITION, "timeseries"),
             ServoPositionLog::default(),
         )?;
+        builder.log_telemetry::<ServoPosition>(
+            ChannelName::from_parts(channels::actuators::IDEAL_SERVO_POSITION, "rocket/fins"),
+            FinDeflectionLog::default(),
+        )?;
+        builder.log_telemetry::<ServoPower>(
+            ChannelName::from_base_path(channels::actuators::SERVO_POWER, "timeseries"),
+            ServoPowerLog::default(),
+        )?;
+        builder.log_telemetry::<AirbrakePosition>(
+            ChannelName::from_base_path(channels::gnc::AIRBRAKE_COMMAND, "timeseries"),
+            AirbrakePositionLog::default(),
+        )?;
+        builder.log_telemetry::<AirbrakePosition>(
+            ChannelName::from_base_path(channels::actuators::AIRBRAKE_POSITION, "timeseries"),
+            AirbrakePositionLog::default(),
+        )?;
         builder.log_telemetry::<RocketMassProperties>(
             ChannelName::from_base_path(channels::rocket::MASS_ROCKET, "timeseries"),
             RocketMassPropertiesLog::default(),
@@ -98,6 +136,14 @@ impl RerunLogConfig for CraterUiLogConfig {
             ChannelName::from_base_path(channels::sensors::IDEAL_MAGNETOMETER, "timeseries"),
             MagnetometerSampleLog::default(),
         )?;
+        builder.log_telemetry::<CameraPointing>(
+            ChannelName::from_base_path(channels::sensors::CAMERA_POINTING, "timeseries"),
+            CameraPointingLog::default(),
+        )?;
+        builder.log_telemetry_mp::<TableSensorSample>(
+            ChannelName::from_base_path(channels::sensors::TABLE_SENSORS, "timeseries"),
+            TableSensorLog::default(),
+        )?;
         builder.log_telemetry_mp::<SimEvent>(
             ChannelName::from_base_path(channels::sim::SIM_EVENTS, "log"),
             SimEventLog::default(),
@@ -118,6 +164,22 @@ impl RerunLogConfig for CraterUiLogConfig {
             ChannelName::from_base_path(channels::gnc::NAV_OUTPUT, "timeseries"),
             NavigationOutputLog::default(),
         )?;
+        builder.log_telemetry::<AttitudeEstimate>(
+            ChannelName::from_base_path(channels::gnc::ATTITUDE_ESTIMATE, "timeseries"),
+            AttitudeEstimateLog::default(),
+        )?;
+        builder.log_telemetry::<AdaResult>(
+            ChannelName::from_base_path(channels::dual_fc::A_ADA_OUTPUT, "timeseries"),
+            AdaOutputLog::default(),
+        )?;
+        builder.log_telemetry::<AdaResult>(
+            ChannelName::from_base_path(channels::dual_fc::B_ADA_OUTPUT, "timeseries"),
+            AdaOutputLog::default(),
+        )?;
+        builder.log_telemetry::<VoterStatus>(
+            ChannelName::from_base_path(channels::dual_fc::VOTER_STATUS, "timeseries"),
+            VoterStatusLog::default(),
+        )?;
         Ok(())
     }
 }
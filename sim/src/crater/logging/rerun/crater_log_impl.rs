@@ -1,12 +1,11 @@
 use crater_gnc::{
     components::ada::AdaResult,
     datatypes::{
-        gnc::NavigationOutput,
+        gnc::{AttitudeEstimate, NavigationOutput},
         sensors::{ImuSensorSample, MagnetometerSensorSample, PressureSensorSample},
     },
 };
-use map_3d::ned2geodetic;
-use nalgebra::{Matrix3, RealField, SMatrix, UnitQuaternion, Vector3, Vector4};
+use nalgebra::{Matrix3, RealField, SMatrix, Unit, UnitQuaternion, Vector3, Vector4};
 use num_traits::{AsPrimitive, Float};
 use rerun::{
     Quaternion, RecordingStream, TensorData, TextLogLevel, components::RotationQuat,
@@ -16,15 +15,24 @@ use rerun::{
 use crate::{
     core::time::Timestamp,
     crater::{
-        aero::aerodynamics::AeroState,
+        actuators::thermal::ServoPower,
+        aero::{
+            aerodynamics::AeroState, envelope::FlightEnvelopeSample, stability::StabilitySample,
+        },
         engine::engine::RocketEngineMassProperties,
+        environment::LaunchSite,
         events::{GncEventItem, SimEvent},
-        gnc::ServoPosition,
+        gnc::{
+            AirbrakePosition, ServoPosition,
+            dual_fc::{FcUnit, VoterStatus},
+        },
         rocket::{
             mass::RocketMassProperties,
             rocket_data::{RocketAccelerations, RocketActions, RocketState},
         },
+        sensors::{camera::CameraPointing, table::TableSensorSample},
     },
+    telemetry::{DynamicMessage, LatestReceiver},
 };
 
 use super::rerun_logger::RerunWrite;
@@ -65,13 +73,25 @@ impl RerunWrite for RocketStateRawLog {
     }
 }
 
-#[derive(Default)]
 pub struct RocketStateUILog {
+    /// Origin of the geodetic trajectory. Without it, only the NED trajectory is logged.
+    rx_launch_site: LatestReceiver<LaunchSite>,
     trajectory_ned_3d: Vec<[f32; 3]>,
     trajectory_geodetic: Vec<[f64; 2]>,
     ts_last_element: f64,
 }
 
+impl RocketStateUILog {
+    pub fn new(rx_launch_site: LatestReceiver<LaunchSite>) -> Self {
+        Self {
+            rx_launch_site,
+            trajectory_ned_3d: Vec::new(),
+            trajectory_geodetic: Vec::new(),
+            ts_last_element: 0.0,
+        }
+    }
+}
+
 impl RerunWrite for RocketStateUILog {
     type Telem = RocketState;
 
@@ -83,23 +103,13 @@ impl RerunWrite for RocketStateUILog {
         ts: Timestamp,
         state: RocketState,
     ) -> Result<()> {
-        let origin = [
-            f64::to_radians(41.8080239),
-            f64::to_radians(14.0548082),
-            1411.211,
-        ];
         let pos = state.pos_n_m();
         let pos_f32_arr: [f32; 3] = pos.map(|v| v as f32).into();
 
-        let (lat, lon, _) = ned2geodetic(
-            pos[0],
-            pos[1],
-            pos[2],
-            origin[0],
-            origin[1],
-            origin[2],
-            map_3d::Ellipsoid::WGS84,
-        );
+        let lat_lon = self.rx_launch_site.get().map(|site| {
+            let (lat, lon, _) = site.1.geodetic(&pos);
+            (lat.to_degrees(), lon.to_degrees())
+        });
 
         let ts_seconds = ts.monotonic.elapsed_seconds_f64();
         rec.set_duration_secs(timeline, ts_seconds);
@@ -128,29 +138,32 @@ impl RerunWrite for RocketStateUILog {
             self.trajectory_ned_3d
                 .push([pos[0] as f32, pos[1] as f32, pos[2] as f32]);
 
-            // Keep history of latitude and longitude to display trajectory over a map
-            self.trajectory_geodetic
-                .push([lat.to_degrees(), lon.to_degrees()]);
-
             // Trajectory lines
             rec.log(
                 "trajectory/ned_3d",
                 &rerun::LineStrips3D::new([self.trajectory_ned_3d.as_slice()]),
             )?;
 
-            rec.log(
-                "trajectory/geodetic",
-                &rerun::GeoLineStrings::from_lat_lon([self.trajectory_geodetic.as_slice()]),
-            )?;
+            // Keep history of latitude and longitude to display trajectory over a map
+            if let Some((lat, lon)) = lat_lon {
+                self.trajectory_geodetic.push([lat, lon]);
+
+                rec.log(
+                    "trajectory/geodetic",
+                    &rerun::GeoLineStrings::from_lat_lon([self.trajectory_geodetic.as_slice()]),
+                )?;
+            }
         }
 
         // Current location point for map plot
-        rec.log(
-            "objects/position_geodetic",
-            &rerun::GeoPoints::from_lat_lon([(lat, lon)])
-                .with_radii([rerun::Radius::new_ui_points(10.0)])
-                .with_colors([rerun::Color::from_rgb(255, 0, 0)]),
-        )?;
+        if let Some((lat, lon)) = lat_lon {
+            rec.log(
+                "objects/position_geodetic",
+                &rerun::GeoPoints::from_lat_lon([(lat, lon)])
+                    .with_radii([rerun::Radius::new_ui_points(10.0)])
+                    .with_colors([rerun::Color::from_rgb(255, 0, 0)]),
+            )?;
+        }
 
         // Velocity vector
         let arrow_vec: [f32; 3] = (state.vel_b_m_s(&state.quat_nb()) / 10.0)
@@ -268,6 +281,11 @@ impl RerunWrite for RocketActionsLog {
             format!("{ent_path}/aero_moments_b_nm"),
             &actions.aero_actions.moments_b_nm,
         )?;
+        log_vector3_timeseries(
+            rec,
+            format!("{ent_path}/parachute_force_n_n"),
+            &actions.parachute_force_n_n,
+        )?;
 
         let thrust_scaled: [f32; 3] = (actions.thrust_b_n / 20.0).map(|v| v as f32).into();
         let aero_force_scaled: [f32; 3] = (actions.aero_actions.forces_b_n / 1.0)
@@ -357,6 +375,363 @@ impl RerunWrite for ServoPositionLog {
     }
 }
 
+#[derive(Default)]
+pub struct AirbrakePositionLog;
+
+impl RerunWrite for AirbrakePositionLog {
+    type Telem = AirbrakePosition;
+
+    fn write(
+        &mut self,
+        rec: &mut RecordingStream,
+        timeline: &str,
+        ent_path: &str,
+        ts: Timestamp,
+        pos: AirbrakePosition,
+    ) -> Result<()> {
+        rec.set_duration_secs(timeline, ts.monotonic.elapsed_seconds_f64());
+        rec.log(format!("{ent_path}/extension"), &rerun::Scalars::single(pos.extension))?;
+
+        Ok(())
+    }
+}
+
+/// Logs the envelope quantities as time series, and the Mach vs altitude curve as a line in
+/// "envelope/mach_altitude", with the altitude in km, upwards
+#[derive(Default)]
+pub struct FlightEnvelopeLog {
+    mach_altitude: Vec<[f32; 2]>,
+    ts_last_element: f64,
+}
+
+impl RerunWrite for FlightEnvelopeLog {
+    type Telem = FlightEnvelopeSample;
+
+    fn write(
+        &mut self,
+        rec: &mut RecordingStream,
+        timeline: &str,
+        ent_path: &str,
+        ts: Timestamp,
+        envelope: FlightEnvelopeSample,
+    ) -> Result<()> {
+        let ts_seconds = ts.monotonic.elapsed_seconds_f64();
+        rec.set_duration_secs(timeline, ts_seconds);
+
+        for (name, value) in [
+            ("dynamic_pressure_pa", envelope.dynamic_pressure_pa),
+            ("mach", envelope.mach),
+            ("altitude_m", envelope.altitude_m),
+            ("total_aoa_deg", envelope.total_aoa_deg),
+            ("q_alpha_pa_deg", envelope.q_alpha_pa_deg),
+            ("max_dynamic_pressure_pa", envelope.max_dynamic_pressure_pa),
+            ("max_mach", envelope.max_mach),
+            ("max_q_alpha_pa_deg", envelope.max_q_alpha_pa_deg),
+        ] {
+            rec.log(format!("{ent_path}/{name}"), &rerun::Scalars::single(value))?;
+        }
+
+        // As the trajectory, the whole curve is logged each time: only every 0.1 s
+        if self.ts_last_element == 0.0 || ts_seconds - self.ts_last_element >= 0.1 {
+            self.ts_last_element = ts_seconds;
+
+            // 2D views have the y axis pointing down
+            self.mach_altitude
+                .push([envelope.mach as f32, -(envelope.altitude_m / 1000.0) as f32]);
+
+            rec.log(
+                "envelope/mach_altitude",
+                &rerun::LineStrips2D::new([self.mach_altitude.as_slice()]),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct StabilityLog;
+
+impl RerunWrite for StabilityLog {
+    type Telem = StabilitySample;
+
+    fn write(
+        &mut self,
+        rec: &mut RecordingStream,
+        timeline: &str,
+        ent_path: &str,
+        ts: Timestamp,
+        stability: StabilitySample,
+    ) -> Result<()> {
+        rec.set_duration_secs(timeline, ts.monotonic.elapsed_seconds_f64());
+
+        for (name, value) in [
+            ("x_cp_m", stability.x_cp_m),
+            ("x_cg_m", stability.x_cg_m),
+            ("static_margin_cal", stability.static_margin_cal),
+        ] {
+            rec.log(format!("{ent_path}/{name}"), &rerun::Scalars::single(value))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Fins drawn on the 3D model, sized to the rocket mesh: hinge position along the body axis,
+/// root radius, span, and chords ahead & behind the hinge [m]
+const FIN_HINGE_X: f32 = -1.3;
+const FIN_ROOT_R: f32 = 0.13;
+const FIN_SPAN: f32 = 0.2;
+const FIN_ROOT_CHORD: (f32, f32) = (0.1, 0.2);
+const FIN_TIP_CHORD: (f32, f32) = (0.05, 0.1);
+
+/// Renders the four fins as separate meshes, under the rocket entity, each rotated around its
+/// hinge axis by its deflection. The hinge axes are radial, at 45° from the body Y & Z axes
+/// (see the fin numbering of `ServoPosition`).
+#[derive(Default)]
+pub struct FinDeflectionLog {
+    meshes_logged: bool,
+}
+
+impl FinDeflectionLog {
+    /// Hinge axes of the fins, in the body frame
+    fn hinge_axes() -> [Vector3<f32>; 4] {
+        [
+            Vector3::new(0.0, -1.0, 1.0),
+            Vector3::new(0.0, -1.0, -1.0),
+            Vector3::new(0.0, 1.0, -1.0),
+            Vector3::new(0.0, 1.0, 1.0),
+        ]
+        .map(|axis| axis.normalize())
+    }
+
+    fn log_meshes(rec: &mut RecordingStream, ent_path: &str) -> Result<()> {
+        for (i, axis) in Self::hinge_axes().iter().enumerate() {
+            // Around the hinge, which the deflection rotates the fin about
+            let vertex = |x: f32, r: f32| -> [f32; 3] { (Vector3::x() * x + axis * r).into() };
+            let r_tip = FIN_ROOT_R + FIN_SPAN;
+
+            rec.log_static(
+                format!("{ent_path}/{}", i + 1),
+                &rerun::Mesh3D::new([
+                    vertex(FIN_ROOT_CHORD.0, FIN_ROOT_R),
+                    vertex(-FIN_ROOT_CHORD.1, FIN_ROOT_R),
+                    vertex(-FIN_TIP_CHORD.1, r_tip),
+                    vertex(FIN_TIP_CHORD.0, r_tip),
+                ])
+                .with_triangle_indices([[0, 1, 2], [0, 2, 3]])
+                .with_vertex_colors([rerun::Color::from_rgb(255, 140, 0); 4]),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl RerunWrite for FinDeflectionLog {
+    type Telem = ServoPosition;
+
+    fn write(
+        &mut self,
+        rec: &mut RecordingStream,
+        timeline: &str,
+        ent_path: &str,
+        ts: Timestamp,
+        servo_pos: ServoPosition,
+    ) -> Result<()> {
+        if !self.meshes_logged {
+            Self::log_meshes(rec, ent_path)?;
+            self.meshes_logged = true;
+        }
+
+        rec.set_duration_secs(timeline, ts.monotonic.elapsed_seconds_f64());
+
+        for (i, axis) in Self::hinge_axes().iter().enumerate() {
+            let quat = UnitQuaternion::from_axis_angle(
+                &Unit::new_unchecked(*axis),
+                servo_pos.pos_rad[i] as f32,
+            );
+
+            rec.log(
+                format!("{ent_path}/{}", i + 1),
+                &rerun::Transform3D::from_translation_rotation(
+                    [FIN_HINGE_X, 0.0, 0.0],
+                    rerun::Rotation3D::Quaternion(RotationQuat(Quaternion([
+                        quat.i, quat.j, quat.k, quat.w,
+                    ]))),
+                ),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Logs every numeric field of the channels of a prefix subscription, under the channel name
+#[derive(Default)]
+pub struct DynamicMessageLog;
+
+impl RerunWrite for DynamicMessageLog {
+    type Telem = DynamicMessage;
+
+    fn write(
+        &mut self,
+        rec: &mut RecordingStream,
+        timeline: &str,
+        ent_path: &str,
+        ts: Timestamp,
+        msg: DynamicMessage,
+    ) -> Result<()> {
+        rec.set_duration_secs(timeline, ts.monotonic.elapsed_seconds_f64());
+
+        for (field, value) in msg.numeric_fields() {
+            let path = match field.as_str() {
+                "" => format!("{ent_path}{}", msg.channel),
+                _ => format!("{ent_path}{}/{field}", msg.channel),
+            };
+            rec.log(path, &rerun::Scalars::single(value))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct CameraPointingLog {
+    ground_trace_ned_3d: Vec<[f32; 3]>,
+    ts_last_element: f64,
+}
+
+impl RerunWrite for CameraPointingLog {
+    type Telem = CameraPointing;
+
+    fn write(
+        &mut self,
+        rec: &mut RecordingStream,
+        timeline: &str,
+        ent_path: &str,
+        ts: Timestamp,
+        pointing: CameraPointing,
+    ) -> Result<()> {
+        let ts_seconds = ts.monotonic.elapsed_seconds_f64();
+        rec.set_duration_secs(timeline, ts_seconds);
+
+        rec.log(
+            format!("{ent_path}/in_view"),
+            &rerun::Scalars::single(pointing.in_view as u8 as f64),
+        )?;
+        rec.log(
+            format!("{ent_path}/off_axis_deg"),
+            &rerun::Scalars::single(pointing.off_axis_rad.to_degrees()),
+        )?;
+        rec.log(
+            format!("{ent_path}/image_h_deg"),
+            &rerun::Scalars::single(pointing.image_h_rad.to_degrees()),
+        )?;
+        rec.log(
+            format!("{ent_path}/image_v_deg"),
+            &rerun::Scalars::single(pointing.image_v_rad.to_degrees()),
+        )?;
+        rec.log(
+            format!("{ent_path}/range_m"),
+            &rerun::Scalars::single(pointing.range_m),
+        )?;
+        rec.log(
+            format!("{ent_path}/time_in_view_s"),
+            &rerun::Scalars::single(pointing.time_in_view_s),
+        )?;
+
+        // Where the camera looks on the ground, throttled like the trajectory
+        if let Some(ground_point_n) = pointing.ground_point_n
+            && (self.ts_last_element == 0.0 || ts_seconds - self.ts_last_element >= 0.1)
+        {
+            self.ts_last_element = ts_seconds;
+            self.ground_trace_ned_3d
+                .push(ground_point_n.map(|v| v as f32).into());
+
+            rec.log(
+                "trajectory/camera_ground_trace",
+                &rerun::LineStrips3D::new([self.ground_trace_ned_3d.as_slice()])
+                    .with_colors([rerun::Color::from_rgb(255, 165, 0)]),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct VoterStatusLog;
+
+impl RerunWrite for VoterStatusLog {
+    type Telem = VoterStatus;
+
+    fn write(
+        &mut self,
+        rec: &mut RecordingStream,
+        timeline: &str,
+        ent_path: &str,
+        ts: Timestamp,
+        status: VoterStatus,
+    ) -> Result<()> {
+        rec.set_duration_secs(timeline, ts.monotonic.elapsed_seconds_f64());
+
+        let active = match status.active {
+            FcUnit::A => 0.0,
+            FcUnit::B => 1.0,
+        };
+        rec.log(
+            format!("{ent_path}/active"),
+            &rerun::Scalars::single(active),
+        )?;
+        rec.log(
+            format!("{ent_path}/divergent"),
+            &rerun::Scalars::single(if status.divergent { 1.0 } else { 0.0 }),
+        )?;
+        rec.log(
+            format!("{ent_path}/altitude_diff_m"),
+            &rerun::Scalars::single(status.altitude_diff_m),
+        )?;
+        rec.log(
+            format!("{ent_path}/position_diff_m"),
+            &rerun::Scalars::single(status.position_diff_m),
+        )?;
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct ServoPowerLog;
+
+impl RerunWrite for ServoPowerLog {
+    type Telem = ServoPower;
+
+    fn write(
+        &mut self,
+        rec: &mut RecordingStream,
+        timeline: &str,
+        ent_path: &str,
+        ts: Timestamp,
+        power: ServoPower,
+    ) -> Result<()> {
+        rec.set_duration_secs(timeline, ts.monotonic.elapsed_seconds_f64());
+
+        log_matrix_timeseries(rec, format!("{ent_path}/current_a"), &power.current_a, None, None)?;
+        log_matrix_timeseries(
+            rec,
+            format!("{ent_path}/winding_temp_degc"),
+            &power.winding_temp_degc,
+            None,
+            None,
+        )?;
+        log_matrix_timeseries(rec, format!("{ent_path}/derating"), &power.derating, None, None)?;
+        rec.log(format!("{ent_path}/power_w"), &rerun::Scalars::single(power.power_w))?;
+
+        Ok(())
+    }
+}
+
 #[derive(Default)]
 pub struct RocketMassPropertiesLog;
 
@@ -520,6 +895,31 @@ impl RerunWrite for GncEventLog {
     }
 }
 
+#[derive(Default)]
+pub struct TableSensorLog;
+
+impl RerunWrite for TableSensorLog {
+    type Telem = TableSensorSample;
+
+    fn write(
+        &mut self,
+        rec: &mut RecordingStream,
+        timeline: &str,
+        ent_path: &str,
+        ts: Timestamp,
+        sample: TableSensorSample,
+    ) -> Result<()> {
+        rec.set_duration_secs(timeline, ts.monotonic.elapsed_seconds_f64());
+
+        rec.log(
+            format!("{ent_path}/{}", sample.sensor),
+            &rerun::Scalars::single(sample.value),
+        )?;
+
+        Ok(())
+    }
+}
+
 #[derive(Default)]
 pub struct SimEventLog;
 
@@ -571,6 +971,11 @@ impl RerunWrite for AdaOutputLog {
             &rerun::Scalars::single(ada.vertical_speed_m_s as f64),
         )?;
 
+        rec.log(
+            format!("{}/vertical_accel_m_s2", ent_path),
+            &rerun::Scalars::single(ada.vertical_accel_m_s2 as f64),
+        )?;
+
         Ok(())
     }
 }
@@ -630,6 +1035,19 @@ impl RerunWrite for ImuSensorSampleLog {
             &data.angvel_rad_s.map(|v| v.to_degrees()),
         )?;
 
+        if let Some(delta) = &data.delta {
+            log_vector3_timeseries(
+                rec,
+                format!("{}/delta_angle_deg", ent_path),
+                &delta.delta_angle_rad.map(|v| v.to_degrees()),
+            )?;
+            log_vector3_timeseries(
+                rec,
+                format!("{}/delta_velocity_m_s", ent_path),
+                &delta.delta_velocity_m_s,
+            )?;
+        }
+
         if let Some(temp) = data.temperature_degc {
             rec.log(
                 format!("{}/temperature_degc", ent_path),
@@ -691,6 +1109,42 @@ impl RerunWrite for NavigationOutputLog {
     }
 }
 
+#[derive(Default)]
+pub struct AttitudeEstimateLog;
+
+impl RerunWrite for AttitudeEstimateLog {
+    type Telem = AttitudeEstimate;
+
+    fn write(
+        &mut self,
+        rec: &mut RecordingStream,
+        timeline: &str,
+        ent_path: &str,
+        ts: Timestamp,
+        data: Self::Telem,
+    ) -> Result<()> {
+        rec.set_duration_secs(timeline, ts.monotonic.elapsed_seconds_f64());
+
+        log_quat_timeseries::<f32>(
+            rec,
+            &data.quat_nb,
+            format!("{}/quat", ent_path),
+            format!("{}/euler", ent_path),
+        )?;
+        log_vector3_timeseries(
+            rec,
+            format!("{}/gyro_bias_b_rad_s", ent_path),
+            &data.gyro_bias_b_rad_s,
+        )?;
+        rec.log(
+            format!("{}/accel_aided", ent_path),
+            &rerun::Scalars::single(data.accel_aided as u8 as f64),
+        )?;
+
+        Ok(())
+    }
+}
+
 fn log_matrix_timeseries<T: Float + AsPrimitive<f64>, const R: usize, const C: usize>(
     rec: &mut RecordingStream,
     ent_path: String,
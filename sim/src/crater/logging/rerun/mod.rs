@@ -2,7 +2,11 @@ mod crater_configs;
 pub mod crater_log_impl;
 
 mod rerun_logger;
+mod time_alignment;
 
-pub use rerun_logger::{RerunLoggerBuilder, RerunLogger, RerunWrite, RerunLogConfig};
+pub use rerun_logger::{
+    LogPipelineConfig, OverloadPolicy, RerunLogConfig, RerunLogger, RerunLoggerBuilder, RerunWrite,
+};
 
-pub use crater_configs::CraterUiLogConfig;
\ No newline at end of file
+pub use crater_configs::CraterUiLogConfig;
+pub use time_alignment::ClockAlignment;
\ No newline at end of file
@@ -1,12 +1,26 @@
-use std::cell::RefCell;
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+};
 
 use crate::{
     core::time::Timestamp,
-    telemetry::{TelemetryReceiver, TelemetryService, Timestamped, selector::Selector},
+    crater::logging::filter::{ChannelLogFilter, Decimator},
+    telemetry::{
+        ChannelUnits, DynamicMessage, TelemetryReceiver, TelemetryService, Timestamped,
+        selector::Selector,
+    },
     utils::capacity::Capacity,
 };
 
 use anyhow::Result;
+use crossbeam_channel::{Receiver, Sender, TrySendError, bounded};
+use log::warn;
 use rerun::RecordingStream;
 
 pub trait RerunWrite {
@@ -22,6 +36,117 @@ pub trait RerunWrite {
     ) -> Result<()>;
 }
 
+/// What to do when a logging queue is full, ie the workers cannot keep up with the telemetry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverloadPolicy {
+    /// Drop the oldest queued sample to make room for the new one. Memory stays bounded and the
+    /// most recent data is always logged.
+    DropOldest,
+    /// Wait for the workers to catch up. Lossless, but samples pile up in the telemetry channels.
+    Block,
+}
+
+/// Configuration of the logging pipeline: telemetry is forwarded from the simulation channels to
+/// bounded per-channel queues, which are drained by a pool of workers doing the conversion and
+/// logging to Rerun.
+#[derive(Debug, Clone, Copy)]
+pub struct LogPipelineConfig {
+    pub num_workers: usize,
+    pub queue_capacity: usize,
+    pub overload: OverloadPolicy,
+}
+
+impl Default for LogPipelineConfig {
+    fn default() -> Self {
+        Self {
+            num_workers: thread::available_parallelism().map_or(1, |n| n.get().min(4)),
+            queue_capacity: 4096,
+            overload: OverloadPolicy::DropOldest,
+        }
+    }
+}
+
+/// Bounded queue between the forwarder and the worker logging a channel
+struct LogQueue<T> {
+    tx: Sender<Timestamped<T>>,
+    /// Receiving end of the queue, used to pop the oldest sample when the queue is full
+    rx: Receiver<Timestamped<T>>,
+    overload: OverloadPolicy,
+    dropped: Arc<AtomicUsize>,
+}
+
+impl<T> LogQueue<T> {
+    fn new(capacity: usize, overload: OverloadPolicy) -> (Self, Receiver<Timestamped<T>>) {
+        let (tx, rx) = bounded(capacity.max(1));
+
+        (
+            Self {
+                tx,
+                rx: rx.clone(),
+                overload,
+                dropped: Arc::new(AtomicUsize::new(0)),
+            },
+            rx,
+        )
+    }
+
+    fn push(&self, mut item: Timestamped<T>) {
+        match self.overload {
+            OverloadPolicy::Block => {
+                // Only fails if the worker is gone, nothing else to do with the data
+                let _ = self.tx.send(item);
+            }
+            OverloadPolicy::DropOldest => loop {
+                match self.tx.try_send(item) {
+                    Ok(()) | Err(TrySendError::Disconnected(_)) => break,
+                    Err(TrySendError::Full(rejected)) => {
+                        // The worker may have emptied a slot in the meantime
+                        if self.rx.try_recv().is_ok() {
+                            self.dropped.fetch_add(1, Ordering::Relaxed);
+                        }
+                        item = rejected;
+                    }
+                }
+            },
+        }
+    }
+}
+
+/// Moves telemetry from a simulation channel to its logging queue
+trait Forwarder {
+    fn disconnected(&self) -> bool;
+
+    fn recv<'a>(&'a mut self, selector: Selector<'a>) -> Selector<'a>;
+}
+
+struct ChannelForwarder<T> {
+    receiver: TelemetryReceiver<T>,
+    queue: LogQueue<T>,
+    decimator: Decimator,
+    disconnected: bool,
+}
+
+impl<T> Forwarder for ChannelForwarder<T>
+where
+    T: 'static + Send,
+{
+    fn disconnected(&self) -> bool {
+        self.disconnected
+    }
+
+    fn recv<'a>(&'a mut self, selector: Selector<'a>) -> Selector<'a> {
+        selector.recv(self.receiver.inner(), |v| {
+            if let Ok(item) = v {
+                if self.decimator.keep() {
+                    self.queue.push(item);
+                }
+            } else {
+                self.disconnected = true;
+            }
+        })
+    }
+}
+
 trait SelectorReceiver {
     fn disconnected(&self) -> bool;
 
@@ -33,14 +158,14 @@ trait SelectorReceiver {
 }
 
 struct TelemetryLogFunction<T, L> {
-    receiver: TelemetryReceiver<T>,
+    receiver: Receiver<Timestamped<T>>,
     data_logger: RefCell<L>,
     ent_path: String,
     disconnected: bool,
 }
 
 impl<T, L> TelemetryLogFunction<T, L> {
-    fn new(receiver: TelemetryReceiver<T>, logger: L, ent_path: &str) -> Self {
+    fn new(receiver: Receiver<Timestamped<T>>, logger: L, ent_path: &str) -> Self {
         Self {
             receiver,
             data_logger: RefCell::new(logger),
@@ -64,7 +189,7 @@ where
         selector: Selector<'a>,
         rec: &'a RefCell<RecordingStream>,
     ) -> Selector<'a> {
-        selector.recv(self.receiver.inner(), |v| {
+        selector.recv(&self.receiver, |v| {
             if let Ok(Timestamped(ts, state)) = v {
                 self.data_logger
                     .borrow_mut()
@@ -119,31 +244,96 @@ impl ChannelName {
     }
 }
 
+/// Pipeline stages for a single logged channel
+struct LoggedChannel {
+    ent_path: String,
+    forwarder: Box<dyn Forwarder + Send>,
+    log_fn: Box<dyn SelectorReceiver + Send>,
+    dropped: Arc<AtomicUsize>,
+}
+
 pub struct RerunLoggerBuilder {
     telem: TelemetryService,
-    sel_receivers: Vec<Box<dyn SelectorReceiver>>,
+    config: LogPipelineConfig,
+    filter: ChannelLogFilter,
+    channels: Vec<LoggedChannel>,
+    units: BTreeMap<String, ChannelUnits>,
 }
 
 impl RerunLoggerBuilder {
     pub fn new(telem: &TelemetryService) -> Self {
+        Self::with_config(telem, LogPipelineConfig::default())
+    }
+
+    pub fn with_config(telem: &TelemetryService, config: LogPipelineConfig) -> Self {
         Self {
             telem: telem.clone(),
-            sel_receivers: Vec::new(),
+            config,
+            filter: ChannelLogFilter::default(),
+            channels: Vec::new(),
+            units: BTreeMap::new(),
+        }
+    }
+
+    /// Skips or decimates the channels logged afterwards, as configured in `filter`
+    pub fn with_filter(mut self, filter: ChannelLogFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Telemetry of the model, for the loggers that need more than the channel they log
+    pub fn telemetry(&self) -> &TelemetryService {
+        &self.telem
+    }
+
+    fn add_units(&mut self, channel: &ChannelName) {
+        if let Some(units) = self.telem.units(&channel.channel_name) {
+            self.units.insert(channel.entity_path.clone(), units);
         }
     }
 
+    fn add_channel<T: 'static + Send>(
+        &mut self,
+        channel: ChannelName,
+        receiver: TelemetryReceiver<T>,
+        logger: impl RerunWrite<Telem = T> + Send + 'static,
+    ) {
+        self.add_units(&channel);
+        let decimation = self.filter.get(&channel.channel_name).decimation;
+
+        let (queue, rx_queue) = LogQueue::new(self.config.queue_capacity, self.config.overload);
+        let dropped = queue.dropped.clone();
+
+        self.channels.push(LoggedChannel {
+            ent_path: channel.entity_path.clone(),
+            forwarder: Box::new(ChannelForwarder {
+                receiver,
+                queue,
+                decimator: Decimator::new(decimation),
+                disconnected: false,
+            }),
+            log_fn: Box::new(TelemetryLogFunction::new(
+                rx_queue,
+                logger,
+                &channel.entity_path,
+            )),
+            dropped,
+        });
+    }
+
     pub fn log_telemetry<T: 'static + Send>(
         &mut self,
         channel: ChannelName,
-        logger: impl RerunWrite<Telem = T> + 'static,
+        logger: impl RerunWrite<Telem = T> + Send + 'static,
     ) -> Result<()> {
+        if !self.filter.get(&channel.channel_name).enabled {
+            return Ok(());
+        }
         let receiver = self
             .telem
             .subscribe::<T>(&channel.channel_name, Capacity::Unbounded)?;
 
-        let log_fn = TelemetryLogFunction::new(receiver, logger, &channel.entity_path);
-
-        self.sel_receivers.push(Box::new(log_fn));
+        self.add_channel(channel, receiver, logger);
 
         Ok(())
     }
@@ -151,41 +341,128 @@ impl RerunLoggerBuilder {
     pub fn log_telemetry_mp<T: 'static + Send>(
         &mut self,
         channel: ChannelName,
-        logger: impl RerunWrite<Telem = T> + 'static,
+        logger: impl RerunWrite<Telem = T> + Send + 'static,
     ) -> Result<()> {
+        if !self.filter.get(&channel.channel_name).enabled {
+            return Ok(());
+        }
         let receiver = self
             .telem
             .subscribe_mp::<T>(&channel.channel_name, Capacity::Unbounded)?;
 
-        let log_fn = TelemetryLogFunction::new(receiver, logger, &channel.entity_path);
+        self.add_channel(channel, receiver, logger);
 
-        self.sel_receivers.push(Box::new(log_fn));
+        Ok(())
+    }
+
+    /// Logs all the channels matching `channel.channel_name`, a prefix pattern (see
+    /// `TelemetryService::subscribe_prefix`), without registering each one. Only the channel
+    /// types registered for the prefix subscriptions are logged.
+    pub fn log_prefix(
+        &mut self,
+        channel: ChannelName,
+        logger: impl RerunWrite<Telem = DynamicMessage> + Send + 'static,
+    ) -> Result<()> {
+        let receiver = self
+            .telem
+            .subscribe_prefix(&channel.channel_name, Capacity::Unbounded)?;
+
+        self.add_channel(channel, receiver, logger);
 
         Ok(())
     }
 
     pub fn build(self, rec: RecordingStream) -> Result<RerunLogger> {
+        // Label the series with their units
+        for (ent_path, units) in self.units.iter() {
+            for (field, unit) in units.iter() {
+                let name = field.rsplit('/').next().unwrap_or(field);
+
+                rec.log_static(
+                    format!("{ent_path}/{field}"),
+                    &rerun::SeriesLines::new().with_names([format!("{name} [{unit}]")]),
+                )?;
+            }
+        }
+
+        // Channels are assigned to the workers round-robin. Each channel is always handled by
+        // the same worker, so its samples are logged in order.
+        let num_workers = self.config.num_workers.clamp(1, self.channels.len().max(1));
+        let mut workers: Vec<Vec<Box<dyn SelectorReceiver + Send>>> =
+            (0..num_workers).map(|_| Vec::new()).collect();
+
+        let mut forwarders = Vec::new();
+        let mut dropped = Vec::new();
+
+        for (i, channel) in self.channels.into_iter().enumerate() {
+            forwarders.push(channel.forwarder);
+            workers[i % num_workers].push(channel.log_fn);
+            dropped.push((channel.ent_path, channel.dropped));
+        }
+
         Ok(RerunLogger {
-            sel_receivers: self.sel_receivers,
-            rec: RefCell::new(rec),
+            forwarders,
+            workers,
+            dropped,
+            rec,
         })
     }
 }
 
 pub struct RerunLogger {
-    sel_receivers: Vec<Box<dyn SelectorReceiver>>,
-    rec: RefCell<RecordingStream>,
+    forwarders: Vec<Box<dyn Forwarder + Send>>,
+    workers: Vec<Vec<Box<dyn SelectorReceiver + Send>>>,
+    dropped: Vec<(String, Arc<AtomicUsize>)>,
+    rec: RecordingStream,
 }
 
 impl RerunLogger {
-    pub fn log_blocking(mut self) -> Result<()> {
+    /// Logs until all the telemetry channels are closed and the queues have been drained.
+    /// Telemetry is forwarded to the queues from the calling thread, while the workers run on
+    /// their own threads.
+    pub fn log_blocking(self) -> Result<()> {
+        let RerunLogger {
+            forwarders,
+            workers,
+            dropped,
+            rec,
+        } = self;
+
+        thread::scope(|s| {
+            let handles: Vec<_> = workers
+                .into_iter()
+                .map(|sel_receivers| {
+                    let rec = rec.clone();
+                    s.spawn(move || Self::run_worker(sel_receivers, rec))
+                })
+                .collect();
+
+            // Returns once the simulation is over, closing the queues
+            Self::run_forwarder(forwarders);
+
+            for handle in handles {
+                handle.join().unwrap();
+            }
+        });
+
+        for (ent_path, dropped) in dropped.iter() {
+            let dropped = dropped.load(Ordering::Relaxed);
+            if dropped > 0 {
+                warn!("Rerun logger overloaded: dropped {dropped} samples of '{ent_path}'");
+            }
+        }
+
+        Ok(())
+    }
+
+    fn run_forwarder(mut forwarders: Vec<Box<dyn Forwarder + Send>>) {
         loop {
             let mut selector: Selector<'_> = Selector::new();
             let mut num_recv = 0usize;
 
-            for sel_recv in self.sel_receivers.iter_mut() {
-                if !sel_recv.disconnected() {
-                    selector = sel_recv.recv(selector, &self.rec);
+            for forwarder in forwarders.iter_mut() {
+                if !forwarder.disconnected() {
+                    selector = forwarder.recv(selector);
                     num_recv += 1;
                 }
             }
@@ -196,8 +473,28 @@ impl RerunLogger {
                 break;
             }
         }
+    }
 
-        Ok(())
+    fn run_worker(mut sel_receivers: Vec<Box<dyn SelectorReceiver + Send>>, rec: RecordingStream) {
+        let rec = RefCell::new(rec);
+
+        loop {
+            let mut selector: Selector<'_> = Selector::new();
+            let mut num_recv = 0usize;
+
+            for sel_recv in sel_receivers.iter_mut() {
+                if !sel_recv.disconnected() {
+                    selector = sel_recv.recv(selector, &rec);
+                    num_recv += 1;
+                }
+            }
+
+            if num_recv > 0 {
+                selector.ready();
+            } else {
+                break;
+            }
+        }
     }
 }
 
@@ -206,3 +503,21 @@ pub trait RerunLogConfig {
 
     fn subscribe_telem(&self, builder: &mut RerunLoggerBuilder) -> Result<()>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drop_oldest() {
+        let (queue, rx) = LogQueue::<i32>::new(3, OverloadPolicy::DropOldest);
+
+        for i in 0..5 {
+            queue.push(Timestamped(Timestamp::from_micros(i), i as i32));
+        }
+
+        let values: Vec<i32> = rx.try_iter().map(|Timestamped(_, v)| v).collect();
+        assert_eq!(values, vec![2, 3, 4]);
+        assert_eq!(queue.dropped.load(Ordering::Relaxed), 2);
+    }
+}
@@ -0,0 +1,107 @@
+use std::collections::VecDeque;
+
+use crate::core::time::Timestamp;
+
+/// Estimates the mapping between a target clock (eg. the flight computer in HIL) and the
+/// reference clock used for the sim truth, as `reference = target + offset + drift * target`.
+///
+/// The estimate is a least squares fit over a sliding window of time-sync pairs, so it keeps
+/// tracking the target clock as it drifts during the run.
+#[derive(Debug, Clone)]
+pub struct ClockAlignment {
+    window: VecDeque<(f64, f64)>,
+    capacity: usize,
+    offset_s: f64,
+    drift: f64,
+}
+
+impl ClockAlignment {
+    pub fn new(window_size: usize) -> Self {
+        Self {
+            window: VecDeque::with_capacity(window_size),
+            capacity: window_size.max(2),
+            offset_s: 0.0,
+            drift: 0.0,
+        }
+    }
+
+    /// Adds a time-sync estimate: the same instant, as seen by the target and the reference clocks
+    pub fn add_sync(&mut self, target: Timestamp, reference: Timestamp) {
+        let t = target.monotonic.elapsed_seconds_f64();
+        let offset = reference.monotonic.elapsed_seconds_f64() - t;
+
+        if self.window.len() == self.capacity {
+            self.window.pop_front();
+        }
+        self.window.push_back((t, offset));
+
+        self.fit();
+    }
+
+    fn fit(&mut self) {
+        let n = self.window.len() as f64;
+        let mean_t = self.window.iter().map(|(t, _)| t).sum::<f64>() / n;
+        let mean_o = self.window.iter().map(|(_, o)| o).sum::<f64>() / n;
+
+        let (mut cov, mut var) = (0.0, 0.0);
+        for (t, o) in self.window.iter() {
+            cov += (t - mean_t) * (o - mean_o);
+            var += (t - mean_t).powi(2);
+        }
+
+        self.drift = if var > f64::EPSILON { cov / var } else { 0.0 };
+        self.offset_s = mean_o - self.drift * mean_t;
+    }
+
+    /// Whether a time-sync estimate was received yet
+    pub fn is_synced(&self) -> bool {
+        !self.window.is_empty()
+    }
+
+    pub fn offset_s(&self) -> f64 {
+        self.offset_s
+    }
+
+    pub fn drift(&self) -> f64 {
+        self.drift
+    }
+
+    /// Converts a target timestamp to the reference timeline
+    pub fn align(&self, target: Timestamp) -> Timestamp {
+        let t = target.monotonic.elapsed_seconds_f64();
+        let aligned = t + self.offset_s + self.drift * t;
+
+        Timestamp::from_micros((aligned * 1.0e6).round() as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_offset_and_drift() {
+        let mut alignment = ClockAlignment::new(50);
+        assert!(!alignment.is_synced());
+
+        // Reference clock is 2 s ahead and runs 100 ppm faster
+        for i in 0..100 {
+            let t = i as f64 * 0.1;
+            alignment.add_sync(
+                Timestamp::from_micros((t * 1.0e6) as i64),
+                Timestamp::from_micros(((t * 1.0001 + 2.0) * 1.0e6) as i64),
+            );
+        }
+
+        assert_relative_eq!(alignment.offset_s(), 2.0, epsilon = 1e-5);
+        assert_relative_eq!(alignment.drift(), 1.0e-4, epsilon = 1e-6);
+
+        let aligned = alignment.align(Timestamp::from_micros(20_000_000));
+        assert_relative_eq!(
+            aligned.monotonic.elapsed_seconds_f64(),
+            22.002,
+            epsilon = 1e-5
+        );
+    }
+}
@@ -0,0 +1,326 @@
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use crater_gnc::{components::ada::AdaResult, datatypes::gnc::NavigationOutput};
+use nalgebra::Vector3;
+use ratatui::{
+    Frame,
+    crossterm::event::{self, Event as TermEvent, KeyCode},
+    layout::{Constraint, Layout},
+    style::{Style, Stylize},
+    widgets::{Block, Paragraph, Row, Table},
+};
+
+use crate::{
+    core::time::Timestamp,
+    crater::{
+        aero::aerodynamics::AeroState,
+        channels,
+        events::{GncEvent, GncEventItem},
+        gnc::ServoPosition,
+        rocket::rocket_data::{RocketAccelerations, RocketState},
+    },
+    telemetry::{TelemetryError, TelemetryReceiver, TelemetryService, Timestamped},
+    utils::capacity::Capacity::Unbounded,
+};
+
+/// Channels shown when none are specified
+pub const DEFAULT_CHANNELS: [&str; 4] = [
+    channels::rocket::STATE,
+    channels::rocket::AERO_STATE,
+    channels::gnc::ADA_OUTPUT,
+    channels::gnc::NAV_OUTPUT,
+];
+
+/// Textual representation of a telemetry sample, as (field, value) pairs
+pub trait WatchFormat {
+    fn fields(&self) -> Vec<(&'static str, String)>;
+}
+
+fn fmt_vec3<T: std::fmt::Display>(v: &Vector3<T>) -> String {
+    format!("{:>10.3} {:>10.3} {:>10.3}", v[0], v[1], v[2])
+}
+
+impl WatchFormat for RocketState {
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        let (roll, pitch, yaw) = self.quat_nb().euler_angles();
+
+        vec![
+            ("pos_n [m]", fmt_vec3(&self.pos_n_m())),
+            ("vel_n [m/s]", fmt_vec3(&self.vel_n_m_s())),
+            (
+                "ypr [deg]",
+                fmt_vec3(&Vector3::new(yaw, pitch, roll).map(f64::to_degrees)),
+            ),
+            (
+                "ang_vel_b [deg/s]",
+                fmt_vec3(&self.angvel_b_rad_s().map(f64::to_degrees)),
+            ),
+        ]
+    }
+}
+
+impl WatchFormat for RocketAccelerations {
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("acc_b [m/s²]", fmt_vec3(&self.acc_b_m_s2)),
+            ("acc_n [m/s²]", fmt_vec3(&self.acc_n_m_s2)),
+        ]
+    }
+}
+
+impl WatchFormat for AeroState {
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("altitude [m]", format!("{:.1}", self.altitude_m)),
+            ("v_air [m/s]", format!("{:.2}", self.v_air_norm_m_s)),
+            ("mach [-]", format!("{:.3}", self.mach)),
+            ("alpha [deg]", format!("{:.2}", self.angles.alpha_rad.to_degrees())),
+            ("beta [deg]", format!("{:.2}", self.angles.beta_rad.to_degrees())),
+        ]
+    }
+}
+
+impl WatchFormat for NavigationOutput {
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("pos_n [m]", fmt_vec3(&self.pos_n_m)),
+            ("vel_n [m/s]", fmt_vec3(&self.vel_n_m_s)),
+            ("ang_vel_b [rad/s]", fmt_vec3(&self.angvel_unbias_b_rad_s)),
+        ]
+    }
+}
+
+impl WatchFormat for AdaResult {
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("altitude [m]", format!("{:.1}", self.altitude_m)),
+            ("vertical speed [m/s]", format!("{:.2}", self.vertical_speed_m_s)),
+            ("vertical accel [m/s²]", format!("{:.2}", self.vertical_accel_m_s2)),
+            ("apogee", self.apogee_detected.to_string()),
+        ]
+    }
+}
+
+impl WatchFormat for ServoPosition {
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        let deg = self.pos_rad.map(f64::to_degrees);
+
+        vec![(
+            "servo [deg]",
+            format!(
+                "{:>8.2} {:>8.2} {:>8.2} {:>8.2}",
+                deg[0], deg[1], deg[2], deg[3]
+            ),
+        )]
+    }
+}
+
+trait WatchedChannel {
+    fn name(&self) -> &str;
+
+    /// Reads all the pending samples, keeping the latest. Returns false once the channel is closed.
+    fn poll(&mut self) -> bool;
+
+    fn latest(&self) -> Option<&(Timestamp, Vec<(&'static str, String)>)>;
+}
+
+struct Watched<T> {
+    name: String,
+    receiver: TelemetryReceiver<T>,
+    latest: Option<(Timestamp, Vec<(&'static str, String)>)>,
+}
+
+impl<T: WatchFormat> WatchedChannel for Watched<T> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn poll(&mut self) -> bool {
+        let mut last = None;
+        let connected = loop {
+            match self.receiver.try_recv() {
+                Ok(Timestamped(ts, v)) => last = Some((ts, v)),
+                Err(TelemetryError::Empty) => break true,
+                Err(_) => break false,
+            }
+        };
+
+        // Only format the latest sample
+        if let Some((ts, v)) = last {
+            self.latest = Some((ts, v.fields()));
+        }
+
+        connected
+    }
+
+    fn latest(&self) -> Option<&(Timestamp, Vec<(&'static str, String)>)> {
+        self.latest.as_ref()
+    }
+}
+
+/// Live terminal view of the latest values of a set of channels, and of the flight mode
+/// manager state. A lightweight alternative to Rerun, usable over SSH.
+pub struct WatchView {
+    channels: Vec<Box<dyn WatchedChannel>>,
+    rx_gnc_events: TelemetryReceiver<GncEventItem>,
+    fmm_state: &'static str,
+    sim_time: Option<Timestamp>,
+    running: bool,
+}
+
+impl WatchView {
+    /// Subscribes to the provided channels. Must be created before the simulation starts.
+    pub fn new(ts: &TelemetryService, channel_names: &[&str]) -> Result<Self> {
+        let mut channels: Vec<Box<dyn WatchedChannel>> = vec![];
+
+        for &name in channel_names.iter() {
+            channels.push(match name {
+                channels::rocket::STATE | channels::rocket::STATE_REF => {
+                    Self::watch::<RocketState>(ts, name)?
+                }
+                channels::rocket::ACCEL | channels::rocket::ACCEL_REF => {
+                    Self::watch::<RocketAccelerations>(ts, name)?
+                }
+                channels::rocket::AERO_STATE => Self::watch::<AeroState>(ts, name)?,
+                channels::gnc::ADA_OUTPUT => Self::watch::<AdaResult>(ts, name)?,
+                channels::gnc::NAV_OUTPUT | channels::sensors::IDEAL_NAV_OUTPUT => {
+                    Self::watch::<NavigationOutput>(ts, name)?
+                }
+                channels::gnc::SERVO_COMMAND | channels::actuators::IDEAL_SERVO_POSITION => {
+                    Self::watch::<ServoPosition>(ts, name)?
+                }
+                unknown => return Err(anyhow!("Channel '{unknown}' cannot be watched")),
+            });
+        }
+
+        Ok(Self {
+            channels,
+            rx_gnc_events: ts.subscribe_mp(channels::gnc::GNC_EVENTS, Unbounded)?,
+            fmm_state: "Boot",
+            sim_time: None,
+            running: true,
+        })
+    }
+
+    fn watch<T: 'static + Send + WatchFormat>(
+        ts: &TelemetryService,
+        name: &str,
+    ) -> Result<Box<dyn WatchedChannel>> {
+        Ok(Box::new(Watched::<T> {
+            name: name.to_string(),
+            receiver: ts.subscribe(name, Unbounded)?,
+            latest: None,
+        }))
+    }
+
+    /// Refreshes the view until the user quits with 'q' or Esc. The last values are kept on
+    /// screen after the simulation ends.
+    pub fn run_blocking(mut self, refresh: Duration) -> Result<()> {
+        let mut terminal = ratatui::init();
+
+        let res = loop {
+            self.poll();
+
+            if let Err(e) = terminal.draw(|frame| self.draw(frame)) {
+                break Err(e.into());
+            }
+
+            match event::poll(refresh) {
+                Ok(true) => {
+                    if let Ok(TermEvent::Key(key)) = event::read() {
+                        if matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) {
+                            break Ok(());
+                        }
+                    }
+                }
+                Ok(false) => {}
+                Err(e) => break Err(e.into()),
+            }
+        };
+
+        ratatui::restore();
+        res
+    }
+
+    fn poll(&mut self) {
+        let mut connected = false;
+        for channel in self.channels.iter_mut() {
+            connected |= channel.poll();
+
+            if let Some((ts, _)) = channel.latest() {
+                if self.sim_time.is_none_or(|t| ts.monotonic > t.monotonic) {
+                    self.sim_time = Some(*ts);
+                }
+            }
+        }
+
+        // The FMM does not publish its state, infer it from the events it emits or receives
+        while let Ok(Timestamped(_, ev)) = self.rx_gnc_events.try_recv() {
+            self.fmm_state = match ev.event {
+                GncEvent::CmdAdaCalibrate => "Calibrating",
+                GncEvent::FlightStateReady => "Ready",
+                GncEvent::FlightStateArmed => "Armed",
+                GncEvent::FlightLiftoff => "PoweredAscent",
+                GncEvent::FlightBurnout => "Coast",
+                GncEvent::CmdDeployDrogue => "ApogeeDescent",
+                GncEvent::RecoveryMainFired => "MainDescent",
+                GncEvent::FlightLanded => "Landed",
+                _ => self.fmm_state,
+            };
+        }
+
+        self.running = connected;
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [header, body] =
+            Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(frame.area());
+
+        let status = if self.running {
+            "running".green()
+        } else {
+            "ended".yellow()
+        };
+        let t = self
+            .sim_time
+            .map_or(0.0, |t| t.monotonic.elapsed_seconds_f64());
+
+        frame.render_widget(
+            Paragraph::new(format!("T+{t:.3} s   FMM: {}   ", self.fmm_state))
+                .block(Block::bordered().title(" crater watch (q to quit) ").title_bottom(status)),
+            header,
+        );
+
+        let mut rows = vec![];
+        for channel in self.channels.iter() {
+            rows.push(Row::new(vec![channel.name().to_string()]).style(Style::new().bold()));
+
+            match channel.latest() {
+                Some((_, fields)) => {
+                    for (field, value) in fields.iter() {
+                        rows.push(Row::new(vec![
+                            String::new(),
+                            field.to_string(),
+                            value.clone(),
+                        ]));
+                    }
+                }
+                None => rows.push(Row::new(vec![String::new(), "no data".to_string()])),
+            }
+        }
+
+        frame.render_widget(
+            Table::new(
+                rows,
+                [
+                    Constraint::Length(30),
+                    Constraint::Length(22),
+                    Constraint::Min(0),
+                ],
+            )
+            .block(Block::bordered()),
+            body,
+        );
+    }
+}
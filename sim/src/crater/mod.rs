@@ -1,12 +1,18 @@
 pub mod rocket;
 pub mod aero;
 pub mod engine;
+pub mod environment;
 
 pub mod actuators;
 pub mod gnc;
 pub mod sensors;
 
+pub mod planning;
+pub mod soak;
+
 
 pub mod logging;
+pub mod recording;
 pub mod events;
-pub mod channels;
\ No newline at end of file
+pub mod channels;
+mod channel_defs;
\ No newline at end of file
@@ -0,0 +1,242 @@
+use anyhow::Result;
+use chrono::TimeDelta;
+use crater_gnc::datatypes::gnc::NavigationOutput;
+use rand::{Rng, RngCore, SeedableRng};
+use rand_xoshiro::Xoshiro256StarStar;
+use serde::Serialize;
+
+use crate::{
+    crater::channels,
+    model::ModelBuilder,
+    nodes::{FtlOrderedExecutor, NodeManager, ParameterSampling},
+    parameters::{ParameterMap, ParameterValue},
+    telemetry::{TelemetryReceiver, TelemetryService, Timestamped},
+    utils::capacity::Capacity::Unbounded,
+};
+
+/// Position error growth of a single run, after the GPS outage
+#[derive(Debug, Clone)]
+pub struct OutageRun {
+    pub seed: u64,
+    pub outage_start_s: f64,
+    /// (time since the outage [s], horizontal position error [m])
+    pub horiz_err_m: Vec<(f64, f64)>,
+}
+
+/// Statistics of the horizontal position error over a bin of time since the outage, across
+/// all the runs still flying at that time. Each run contributes its largest error in the bin.
+#[derive(Debug, Clone, Serialize)]
+pub struct BudgetRow {
+    /// End of the bin [s]
+    pub t_since_outage_s: f64,
+    pub num_runs: usize,
+    pub p50_m: f64,
+    pub p90_m: f64,
+    pub p99_m: f64,
+    pub max_m: f64,
+}
+
+/// Dead-reckoning accuracy budget: runs the onboard navigation with the GPS lost at randomized
+/// times, and aggregates the growth of the position error with the time since the outage
+pub struct DrBudget<M> {
+    model: M,
+    params: ParameterMap,
+    num_runs: usize,
+    seed: u64,
+    outage_window_s: [f64; 2],
+    horizon_s: f64,
+    bin_width_s: f64,
+}
+
+impl<M: ModelBuilder> DrBudget<M> {
+    pub fn from_params(model: M, params: ParameterMap) -> Result<Self> {
+        let budget = params.get_map("planner.dr_budget")?;
+
+        let window = budget.get_param("outage_window")?.value_float_arr()?;
+        anyhow::ensure!(
+            window.len() == 2 && window[0] <= window[1],
+            "planner.dr_budget.outage_window must be [start, end]"
+        );
+
+        Ok(Self {
+            num_runs: usize::try_from(budget.get_param("num_runs")?.value_int()?)?,
+            seed: budget.get_param("seed")?.value_int()? as u64,
+            outage_window_s: [window[0], window[1]],
+            horizon_s: budget.get_param("horizon")?.value_float()?,
+            bin_width_s: budget.get_param("bin_width")?.value_float()?,
+            model,
+            params,
+        })
+    }
+
+    pub fn run(&self) -> Result<(Vec<OutageRun>, Vec<BudgetRow>)> {
+        // Outage times and run seeds are drawn from the configured seed, so that the budget is
+        // reproducible
+        let mut rng = Xoshiro256StarStar::seed_from_u64(self.seed);
+
+        let mut runs = vec![];
+        for _ in 0..self.num_runs {
+            let outage_start_s = if self.outage_window_s[0] < self.outage_window_s[1] {
+                rng.random_range(self.outage_window_s[0]..self.outage_window_s[1])
+            } else {
+                self.outage_window_s[0]
+            };
+
+            runs.push(self.run_outage(rng.next_u64(), outage_start_s)?);
+        }
+
+        let budget = aggregate(&runs, self.bin_width_s, self.horizon_s);
+        Ok((runs, budget))
+    }
+
+    fn run_outage(&self, seed: u64, outage_start_s: f64) -> Result<OutageRun> {
+        let mut params = self.params.clone();
+        params.set_param(
+            "sim.rocket.gps.outage_start",
+            ParameterValue::Float {
+                val: outage_start_s,
+            },
+        )?;
+        params.set_param(
+            "sim.rocket.gnc.navigation",
+            ParameterValue::String {
+                val: "onboard".to_string(),
+            },
+        )?;
+
+        let dt_sec = params.get_param("sim.dt")?.value_float()?;
+        let dt = (dt_sec * 1000000.0) as i64;
+
+        let ts = TelemetryService::default();
+        let rx_truth =
+            ts.subscribe::<NavigationOutput>(channels::sensors::IDEAL_NAV_OUTPUT, Unbounded)?;
+        let rx_nav = ts.subscribe::<NavigationOutput>(channels::gnc::NAV_OUTPUT, Unbounded)?;
+
+        let mut nm = NodeManager::new(ts, params, ParameterSampling::Random, seed);
+        self.model.build(&mut nm)?;
+
+        FtlOrderedExecutor::run_blocking(nm, TimeDelta::microseconds(dt))?;
+
+        Ok(OutageRun {
+            seed,
+            outage_start_s,
+            horiz_err_m: horizontal_errors(&drain(&rx_truth), &drain(&rx_nav), outage_start_s),
+        })
+    }
+}
+
+fn drain(rx: &TelemetryReceiver<NavigationOutput>) -> Vec<(f64, NavigationOutput)> {
+    let mut out = vec![];
+    while let Ok(Timestamped(t, v)) = rx.try_recv() {
+        out.push((t.monotonic.elapsed_seconds_f64(), v));
+    }
+    out
+}
+
+/// Horizontal position error of the navigation samples after the outage, matched to the
+/// latest ground truth sample
+fn horizontal_errors(
+    truth: &[(f64, NavigationOutput)],
+    nav: &[(f64, NavigationOutput)],
+    outage_start_s: f64,
+) -> Vec<(f64, f64)> {
+    let mut errors = vec![];
+    if truth.is_empty() {
+        return errors;
+    }
+
+    let mut i_truth = 0;
+    for (t, nav) in nav.iter().filter(|(t, _)| *t >= outage_start_s) {
+        while i_truth + 1 < truth.len() && truth[i_truth + 1].0 <= *t {
+            i_truth += 1;
+        }
+        let err = nav.pos_n_m - truth[i_truth].1.pos_n_m;
+
+        errors.push((t - outage_start_s, err.xy().norm() as f64));
+    }
+
+    errors
+}
+
+/// Linear interpolation between the closest ranks of a sorted sample
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = p / 100.0 * (sorted.len() - 1) as f64;
+    let lo = rank.floor() as usize;
+    let hi = rank.ceil() as usize;
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (rank - lo as f64)
+}
+
+/// Aggregates the errors of the runs in bins of time since the outage, up to the horizon.
+/// Bins where no run is still flying are omitted.
+pub fn aggregate(runs: &[OutageRun], bin_width_s: f64, horizon_s: f64) -> Vec<BudgetRow> {
+    let num_bins = (horizon_s / bin_width_s).ceil() as usize;
+
+    let mut bins: Vec<Vec<f64>> = vec![vec![]; num_bins];
+    for run in runs.iter() {
+        let mut run_max = vec![None::<f64>; num_bins];
+        for &(t, err) in run.horiz_err_m.iter() {
+            let bin = (t / bin_width_s) as usize;
+            if bin < num_bins {
+                run_max[bin] = Some(run_max[bin].map_or(err, |m| m.max(err)));
+            }
+        }
+
+        for (bin, err) in run_max.into_iter().enumerate() {
+            if let Some(err) = err {
+                bins[bin].push(err);
+            }
+        }
+    }
+
+    bins.into_iter()
+        .enumerate()
+        .filter(|(_, errs)| !errs.is_empty())
+        .map(|(i, mut errs)| {
+            errs.sort_by(f64::total_cmp);
+            BudgetRow {
+                t_since_outage_s: (i + 1) as f64 * bin_width_s,
+                num_runs: errs.len(),
+                p50_m: percentile(&errs, 50.0),
+                p90_m: percentile(&errs, 90.0),
+                p99_m: percentile(&errs, 99.0),
+                max_m: *errs.last().unwrap(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_aggregate() {
+        // Error growing linearly at 1..=5 m/s, the last run landing after 4 s
+        let runs: Vec<OutageRun> = (1..=5)
+            .map(|k| OutageRun {
+                seed: k,
+                outage_start_s: 10.0,
+                horiz_err_m: (0..100)
+                    .map(|i| i as f64 * 0.1)
+                    .filter(|&t| k < 5 || t < 4.0)
+                    .map(|t| (t, t * k as f64))
+                    .collect(),
+            })
+            .collect();
+
+        let budget = aggregate(&runs, 2.0, 20.0);
+        assert_eq!(budget.len(), 5);
+
+        assert_eq!(budget[0].num_runs, 5);
+        assert_relative_eq!(budget[0].t_since_outage_s, 2.0);
+        assert_relative_eq!(budget[0].p50_m, 1.9 * 3.0, epsilon = 1e-9);
+        assert_relative_eq!(budget[0].max_m, 1.9 * 5.0, epsilon = 1e-9);
+
+        assert_eq!(budget[4].num_runs, 4);
+        assert_relative_eq!(budget[4].p50_m, 9.9 * 2.5, epsilon = 1e-9);
+        assert_relative_eq!(budget[4].p90_m, 9.9 * 3.7, epsilon = 1e-9);
+        assert_relative_eq!(budget[4].max_m, 9.9 * 4.0, epsilon = 1e-9);
+    }
+}
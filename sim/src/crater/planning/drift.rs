@@ -0,0 +1,309 @@
+use anyhow::Result;
+use nalgebra::{SVector, Vector2, Vector3};
+use serde::Serialize;
+
+use crate::{
+    crater::{
+        aero::{
+            atmosphere::{Atmosphere, AtmosphereIsa},
+            wind::{WindModel, wind_from_params},
+        },
+        environment::LaunchSite,
+    },
+    math::ode::{OdeProblem, OdeSolver, RungeKutta4},
+    parameters::ParameterMap,
+};
+
+/// Recovery system configuration, with descent rates specified at the launch site altitude
+#[derive(Debug, Clone)]
+pub struct RecoveryConfig {
+    pub drogue_descent_rate_m_s: f64,
+    pub main_descent_rate_m_s: f64,
+    pub main_deploy_alt_m: f64,
+}
+
+impl RecoveryConfig {
+    pub fn from_params(params: &ParameterMap) -> Result<Self> {
+        Ok(Self {
+            drogue_descent_rate_m_s: params.get_param("drogue_descent_rate")?.value_float()?,
+            main_descent_rate_m_s: params.get_param("main_descent_rate")?.value_float()?,
+            main_deploy_alt_m: params.get_param("main_deploy_altitude")?.value_float()?,
+        })
+    }
+}
+
+/// Point-mass descent under canopy. State is [pos_n, vel_n], relative to the launch site.
+struct DescentProblem<'a> {
+    wind: &'a dyn WindModel,
+    atmosphere: &'a AtmosphereIsa,
+    origin_alt_m: f64,
+    g_m_s2: f64,
+    /// Drag coefficient normalized by the density at the launch site (CdS / m / rho_0)
+    drag_k: f64,
+}
+
+impl OdeProblem<f64, 6> for DescentProblem<'_> {
+    fn odefun(&self, _: f64, y: SVector<f64, 6>) -> SVector<f64, 6> {
+        let vel_n = y.fixed_rows::<3>(3).clone_owned();
+        let alt_m = -y[2];
+
+        let rho = self.atmosphere.density_kg_m3(self.origin_alt_m + alt_m);
+        let v_air_n = vel_n - self.wind.wind_n(alt_m);
+
+        let acc_n = Vector3::new(0.0, 0.0, self.g_m_s2)
+            - v_air_n * (self.drag_k * rho * v_air_n.norm());
+
+        let mut dy = SVector::<f64, 6>::zeros();
+        dy.fixed_rows_mut::<3>(0).copy_from(&vel_n);
+        dy.fixed_rows_mut::<3>(3).copy_from(&acc_n);
+        dy
+    }
+}
+
+/// Fast 3-DoF descent propagation from apogee to the ground
+pub struct DescentPropagator {
+    recovery: RecoveryConfig,
+    wind: Box<dyn WindModel + Send>,
+    atmosphere: AtmosphereIsa,
+    origin_alt_m: f64,
+    g_m_s2: f64,
+    dt: f64,
+}
+
+impl DescentPropagator {
+    const MAX_DESCENT_TIME_S: f64 = 3600.0;
+
+    pub fn new(
+        recovery: RecoveryConfig,
+        wind: Box<dyn WindModel + Send>,
+        origin_alt_m: f64,
+        dt: f64,
+    ) -> Self {
+        Self {
+            recovery,
+            wind,
+            atmosphere: AtmosphereIsa::default(),
+            origin_alt_m,
+            g_m_s2: 9.81,
+            dt,
+        }
+    }
+
+    fn drag_k(&self, descent_rate_m_s: f64) -> f64 {
+        // Terminal velocity at the launch site: g = k * rho_0 * v^2
+        let rho_0 = self.atmosphere.density_kg_m3(self.origin_alt_m);
+        self.g_m_s2 / (rho_0 * descent_rate_m_s.powi(2))
+    }
+
+    /// Propagates the descent starting at rest from the provided apogee position (NED,
+    /// relative to the launch site) and returns the landing position
+    pub fn landing_point(&self, apogee_n: &Vector3<f64>) -> Vector3<f64> {
+        let drogue_k = self.drag_k(self.recovery.drogue_descent_rate_m_s);
+        let main_k = self.drag_k(self.recovery.main_descent_rate_m_s);
+
+        let mut y = SVector::<f64, 6>::zeros();
+        y.fixed_rows_mut::<3>(0).copy_from(apogee_n);
+
+        let mut t = 0.0;
+        while y[2] < 0.0 && t < Self::MAX_DESCENT_TIME_S {
+            let drag_k = if -y[2] > self.recovery.main_deploy_alt_m {
+                drogue_k
+            } else {
+                main_k
+            };
+
+            let problem = DescentProblem {
+                wind: self.wind.as_ref(),
+                atmosphere: &self.atmosphere,
+                origin_alt_m: self.origin_alt_m,
+                g_m_s2: self.g_m_s2,
+                drag_k,
+            };
+
+            y = RungeKutta4.solve(&problem, t, self.dt, y);
+            t += self.dt;
+        }
+
+        Vector3::new(y[0], y[1], 0.0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DriftPoint {
+    pub apogee_n_m: f64,
+    pub apogee_e_m: f64,
+    pub landing_n_m: f64,
+    pub landing_e_m: f64,
+    /// Distance of the landing point from the center of the recovery area
+    pub miss_m: f64,
+    pub azimuth_deg: f64,
+    pub elevation_deg: f64,
+    pub feasible: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct DriftPlan {
+    pub points: Vec<DriftPoint>,
+    pub recommended: Option<DriftPoint>,
+}
+
+/// Propagates the descent from a grid of apogee points and selects the rail orientation
+/// that keeps the landing point within the recovery area
+pub struct DriftPlanner {
+    propagator: DescentPropagator,
+    apogee_alt_m: f64,
+    grid_half_width_m: f64,
+    grid_step_m: f64,
+    zone_center_ne_m: Vector2<f64>,
+    zone_radius_m: f64,
+    min_elevation_deg: f64,
+    downrange_gain: f64,
+}
+
+impl DriftPlanner {
+    pub fn from_params(params: &ParameterMap) -> Result<Self> {
+        let planner = params.get_map("planner.drift")?;
+
+        // The descent would never end, or the grid never be filled
+        let dt = planner.get_param("dt")?.value_float()?;
+        anyhow::ensure!(dt > 0.0, "planner.drift.dt must be positive, got {dt}");
+        let grid_step_m = planner.get_param("grid_step")?.value_float()?;
+        anyhow::ensure!(
+            grid_step_m > 0.0,
+            "planner.drift.grid_step must be positive, got {grid_step_m}"
+        );
+
+        let propagator = DescentPropagator::new(
+            RecoveryConfig::from_params(params.get_map("sim.rocket.recovery")?)?,
+            wind_from_params(params.get_map("sim.wind")?)?,
+            LaunchSite::from_params(params)?.altitude_m,
+            dt,
+        );
+
+        Ok(Self {
+            propagator,
+            apogee_alt_m: planner.get_param("apogee_altitude")?.value_float()?,
+            grid_half_width_m: planner.get_param("grid_half_width")?.value_float()?,
+            grid_step_m,
+            zone_center_ne_m: Vector2::from_column_slice(
+                planner.get_param("zone_center_ne")?.value_float_arr()?,
+            ),
+            zone_radius_m: planner.get_param("zone_radius")?.value_float()?,
+            min_elevation_deg: planner.get_param("min_elevation_deg")?.value_float()?,
+            downrange_gain: planner.get_param("downrange_gain")?.value_float()?,
+        })
+    }
+
+    /// Rail orientation needed to reach the provided horizontal apogee offset, assuming
+    /// the offset scales as `downrange_gain * apogee_alt / tan(elevation)`
+    fn rail_orientation(&self, apogee_ne: &Vector2<f64>) -> (f64, f64) {
+        let azimuth_deg = apogee_ne[1].atan2(apogee_ne[0]).to_degrees().rem_euclid(360.0);
+        let elevation_deg = (self.downrange_gain * self.apogee_alt_m)
+            .atan2(apogee_ne.norm())
+            .to_degrees();
+
+        (azimuth_deg, elevation_deg)
+    }
+
+    pub fn plan(&self) -> DriftPlan {
+        let n = (self.grid_half_width_m / self.grid_step_m).floor() as i64;
+
+        let mut points = vec![];
+        for i in -n..=n {
+            for j in -n..=n {
+                let apogee_ne =
+                    Vector2::new(i as f64 * self.grid_step_m, j as f64 * self.grid_step_m);
+                let apogee_n = Vector3::new(apogee_ne[0], apogee_ne[1], -self.apogee_alt_m);
+
+                let landing_n = self.propagator.landing_point(&apogee_n);
+                let miss_m = (landing_n.xy() - self.zone_center_ne_m).norm();
+                let (azimuth_deg, elevation_deg) = self.rail_orientation(&apogee_ne);
+
+                points.push(DriftPoint {
+                    apogee_n_m: apogee_ne[0],
+                    apogee_e_m: apogee_ne[1],
+                    landing_n_m: landing_n[0],
+                    landing_e_m: landing_n[1],
+                    miss_m,
+                    azimuth_deg,
+                    elevation_deg,
+                    feasible: miss_m <= self.zone_radius_m
+                        && elevation_deg >= self.min_elevation_deg,
+                });
+            }
+        }
+
+        let recommended = points
+            .iter()
+            .filter(|p| p.feasible)
+            .min_by(|a, b| a.miss_m.total_cmp(&b.miss_m))
+            .cloned();
+
+        DriftPlan {
+            points,
+            recommended,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{
+        crater::aero::wind::{NoWind, TabulatedWind},
+        parameters::{self, ParameterValue},
+    };
+    use approx::assert_relative_eq;
+
+    fn recovery() -> RecoveryConfig {
+        RecoveryConfig {
+            drogue_descent_rate_m_s: 25.0,
+            main_descent_rate_m_s: 6.0,
+            main_deploy_alt_m: 300.0,
+        }
+    }
+
+    #[test]
+    fn test_no_wind_lands_below_apogee() {
+        let propagator = DescentPropagator::new(recovery(), Box::new(NoWind), 0.0, 0.1);
+
+        let landing = propagator.landing_point(&Vector3::new(100.0, -50.0, -1000.0));
+        assert_relative_eq!(landing, Vector3::new(100.0, -50.0, 0.0), epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_wind_drifts_downwind() {
+        // Constant 5 m/s wind from the north
+        let wind =
+            TabulatedWind::new(&[0.0, 3000.0], &[5.0, 5.0], &[0.0, 0.0]).unwrap();
+        let propagator = DescentPropagator::new(recovery(), Box::new(wind), 0.0, 0.1);
+
+        let landing = propagator.landing_point(&Vector3::new(0.0, 0.0, -1000.0));
+        assert!(landing[0] < -100.0);
+        assert_relative_eq!(landing[1], 0.0, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_invalid_steps() {
+        let params = parameters::parse_string(
+            fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/config/params.toml"))
+                .unwrap(),
+        )
+        .unwrap();
+        assert!(DriftPlanner::from_params(&params).is_ok());
+
+        for (name, val) in [
+            ("planner.drift.dt", 0.0),
+            ("planner.drift.grid_step", 0.0),
+            ("planner.drift.grid_step", -25.0),
+        ] {
+            let mut params = params.clone();
+            params
+                .set_param(name, ParameterValue::Float { val })
+                .unwrap();
+            assert!(DriftPlanner::from_params(&params).is_err(), "{name} = {val}");
+        }
+    }
+}
@@ -0,0 +1,3 @@
+pub mod dr_budget;
+pub mod drift;
+pub mod rail_sweep;
@@ -0,0 +1,130 @@
+use anyhow::Result;
+use chrono::TimeDelta;
+use serde::Serialize;
+
+use crate::{
+    crater::{
+        aero::aerodynamics::AeroState, channels, events::SimEvent, rocket::rocket_data::RocketState,
+    },
+    model::ModelBuilder,
+    nodes::{FtlOrderedExecutor, NodeManager, ParameterSampling},
+    parameters::{FloatDistribution, ParameterMap, ParameterValue, RandFloat},
+    telemetry::{TelemetryService, Timestamped},
+    utils::capacity::Capacity::Unbounded,
+};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RailSweepResult {
+    pub azimuth_deg: f64,
+    pub elevation_deg: f64,
+    pub apogee_m: f64,
+    /// Maximum total angle of attack between rail exit and apogee
+    pub max_aoa_deg: f64,
+    pub landing_n_m: f64,
+    pub landing_e_m: f64,
+}
+
+/// Sweeps the launch rail azimuth & elevation, running a full (nominal) simulation for each
+/// setting under the configured wind model
+pub struct RailSweep<M> {
+    model: M,
+    params: ParameterMap,
+    azimuths_deg: Vec<f64>,
+    elevations_deg: Vec<f64>,
+}
+
+impl<M: ModelBuilder> RailSweep<M> {
+    pub fn from_params(model: M, params: ParameterMap) -> Result<Self> {
+        let sweep = params.get_map("planner.rail_sweep")?;
+
+        let azimuths_deg = sweep.get_param("azimuth_deg")?.value_float_arr()?.to_vec();
+        let elevations_deg = sweep.get_param("elevation_deg")?.value_float_arr()?.to_vec();
+
+        Ok(Self {
+            model,
+            params,
+            azimuths_deg,
+            elevations_deg,
+        })
+    }
+
+    pub fn run(&self) -> Result<Vec<RailSweepResult>> {
+        let mut results = vec![];
+
+        for &elevation_deg in self.elevations_deg.iter() {
+            for &azimuth_deg in self.azimuths_deg.iter() {
+                results.push(self.run_setting(azimuth_deg, elevation_deg)?);
+            }
+        }
+
+        Ok(results)
+    }
+
+    fn run_setting(&self, azimuth_deg: f64, elevation_deg: f64) -> Result<RailSweepResult> {
+        let mut params = self.params.clone();
+
+        // Parameters are sampled perfectly, so the distribution is irrelevant
+        let fixed = |val| {
+            ParameterValue::RandFloat(RandFloat::new(
+                val,
+                FloatDistribution::Normal {
+                    mean: val,
+                    std_dev: 0.0,
+                },
+            ))
+        };
+        params.set_param("sim.rocket.init.azimuth", fixed(azimuth_deg))?;
+        params.set_param("sim.rocket.init.elevation", fixed(elevation_deg))?;
+
+        let dt_sec = params.get_param("sim.dt")?.value_float()?;
+        let dt = (dt_sec * 1000000.0) as i64;
+
+        let ts = TelemetryService::default();
+        let rx_state = ts.subscribe::<RocketState>(channels::rocket::STATE, Unbounded)?;
+        let rx_aerostate = ts.subscribe::<AeroState>(channels::rocket::AERO_STATE, Unbounded)?;
+        let rx_sim_event = ts.subscribe_mp::<SimEvent>(channels::sim::SIM_EVENTS, Unbounded)?;
+
+        let mut nm = NodeManager::new(ts, params, ParameterSampling::Perfect, 0);
+        self.model.build(&mut nm)?;
+
+        FtlOrderedExecutor::run_blocking(nm, TimeDelta::microseconds(dt))?;
+
+        let mut t_rail_exit = f64::INFINITY;
+        while let Ok(Timestamped(t, event)) = rx_sim_event.try_recv() {
+            if let SimEvent::RailExit { .. } = event {
+                t_rail_exit = t.monotonic.elapsed_seconds_f64();
+            }
+        }
+
+        let mut apogee_m = f64::NEG_INFINITY;
+        let mut t_apogee = 0.0;
+        let mut landing = RocketState::default();
+        while let Ok(Timestamped(t, state)) = rx_state.try_recv() {
+            if -state.pos_n_m()[2] > apogee_m {
+                apogee_m = -state.pos_n_m()[2];
+                t_apogee = t.monotonic.elapsed_seconds_f64();
+            }
+            landing = state;
+        }
+
+        let mut max_aoa_rad: f64 = 0.0;
+        while let Ok(Timestamped(t, aerostate)) = rx_aerostate.try_recv() {
+            let t = t.monotonic.elapsed_seconds_f64();
+
+            if t >= t_rail_exit && t <= t_apogee {
+                let v = aerostate.v_air_b_m_s;
+                let aoa_rad = (v[1].powi(2) + v[2].powi(2)).sqrt().atan2(v[0]);
+                max_aoa_rad = max_aoa_rad.max(aoa_rad);
+            }
+        }
+
+        Ok(RailSweepResult {
+            azimuth_deg,
+            elevation_deg,
+            apogee_m,
+            max_aoa_deg: max_aoa_rad.to_degrees(),
+            landing_n_m: landing.pos_n_m()[0],
+            landing_e_m: landing.pos_n_m()[1],
+        })
+    }
+}
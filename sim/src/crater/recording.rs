@@ -0,0 +1,403 @@
+//! Records of the flight software inputs & outputs, to regression test the GNC against recorded
+//! flights and simulations without re-running the dynamics
+
+use anyhow::{Result, anyhow};
+use crater_gnc::{
+    DurationU64,
+    components::ada::AdaResult,
+    datatypes::{
+        gnc::NavigationOutput,
+        pin::{DigitalInputState, DigitalState},
+        recovery::{PyroChannel, PyroCommand},
+        sensors::{
+            GpsSensorSample, ImuDeltaSample, ImuSensorSample, MagnetometerSensorSample,
+            PressureSensorSample,
+        },
+    },
+    events::Event,
+    mav_crater::ComponentId,
+};
+use nalgebra::{Quaternion, UnitQuaternion, Vector3};
+use num_traits::FromPrimitive;
+use serde::{Deserialize, Serialize};
+
+use super::{channels, events::GncEventItem};
+use crate::{
+    nodes::NodeTelemetry,
+    telemetry::{
+        recording::{
+            Producers, Recordable, RecordingEndpoint, TelemetryRecorder, TelemetryReplayer,
+            register_recordable,
+        },
+        TelemetryService,
+        udp::UdpTelemetryBridge,
+    },
+};
+
+fn to_array(v: &Vector3<f32>) -> [f32; 3] {
+    [v[0], v[1], v[2]]
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PressureRecord {
+    pub pressure_pa: f32,
+    pub temperature_degc: Option<f32>,
+}
+
+impl Recordable for PressureSensorSample {
+    type Record = PressureRecord;
+
+    fn to_record(&self) -> PressureRecord {
+        PressureRecord {
+            pressure_pa: self.pressure_pa,
+            temperature_degc: self.temperature_degc,
+        }
+    }
+
+    fn from_record(record: PressureRecord) -> Result<Self> {
+        Ok(Self {
+            pressure_pa: record.pressure_pa,
+            temperature_degc: record.temperature_degc,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImuDeltaRecord {
+    pub dt_us: u64,
+    pub delta_angle_rad: [f32; 3],
+    pub delta_velocity_m_s: [f32; 3],
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImuRecord {
+    pub accel_m_s2: [f32; 3],
+    pub angvel_rad_s: [f32; 3],
+    pub temperature_degc: Option<f32>,
+    pub int_latency_us: u64,
+    pub overrun_count: u8,
+    pub delta: Option<ImuDeltaRecord>,
+}
+
+impl Recordable for ImuSensorSample {
+    type Record = ImuRecord;
+
+    fn to_record(&self) -> ImuRecord {
+        ImuRecord {
+            accel_m_s2: to_array(&self.accel_m_s2),
+            angvel_rad_s: to_array(&self.angvel_rad_s),
+            temperature_degc: self.temperature_degc,
+            int_latency_us: self.int_latency.0.to_micros(),
+            overrun_count: self.overrun_count,
+            delta: self.delta.as_ref().map(|delta| ImuDeltaRecord {
+                dt_us: delta.dt.0.to_micros(),
+                delta_angle_rad: to_array(&delta.delta_angle_rad),
+                delta_velocity_m_s: to_array(&delta.delta_velocity_m_s),
+            }),
+        }
+    }
+
+    fn from_record(record: ImuRecord) -> Result<Self> {
+        Ok(Self {
+            accel_m_s2: record.accel_m_s2.into(),
+            angvel_rad_s: record.angvel_rad_s.into(),
+            temperature_degc: record.temperature_degc,
+            int_latency: DurationU64::micros(record.int_latency_us).into(),
+            overrun_count: record.overrun_count,
+            delta: record.delta.map(|delta| ImuDeltaSample {
+                dt: DurationU64::micros(delta.dt_us).into(),
+                delta_angle_rad: delta.delta_angle_rad.into(),
+                delta_velocity_m_s: delta.delta_velocity_m_s.into(),
+            }),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GpsRecord {
+    pub pos_n_m: [f32; 3],
+    pub vel_n_m_s: [f32; 3],
+}
+
+impl Recordable for GpsSensorSample {
+    type Record = GpsRecord;
+
+    fn to_record(&self) -> GpsRecord {
+        GpsRecord {
+            pos_n_m: to_array(&self.pos_n_m),
+            vel_n_m_s: to_array(&self.vel_n_m_s),
+        }
+    }
+
+    fn from_record(record: GpsRecord) -> Result<Self> {
+        Ok(Self {
+            pos_n_m: record.pos_n_m.into(),
+            vel_n_m_s: record.vel_n_m_s.into(),
+        })
+    }
+}
+
+impl Recordable for MagnetometerSensorSample {
+    type Record = [f32; 3];
+
+    fn to_record(&self) -> [f32; 3] {
+        to_array(&self.mag_field_b_gauss)
+    }
+
+    fn from_record(record: [f32; 3]) -> Result<Self> {
+        Ok(Self {
+            mag_field_b_gauss: record.into(),
+        })
+    }
+}
+
+impl Recordable for DigitalInputState {
+    type Record = bool;
+
+    fn to_record(&self) -> bool {
+        self.0 == DigitalState::High
+    }
+
+    fn from_record(high: bool) -> Result<Self> {
+        Ok(DigitalInputState(if high {
+            DigitalState::High
+        } else {
+            DigitalState::Low
+        }))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NavigationRecord {
+    /// [w, x, y, z]
+    pub quat_nb: [f32; 4],
+    pub pos_n_m: [f32; 3],
+    pub vel_n_m_s: [f32; 3],
+    pub angvel_unbias_b_rad_s: [f32; 3],
+    pub acc_unbias_b_m_s2: [f32; 3],
+}
+
+impl Recordable for NavigationOutput {
+    type Record = NavigationRecord;
+
+    fn to_record(&self) -> NavigationRecord {
+        let q = self.quat_nb;
+        NavigationRecord {
+            quat_nb: [q.w, q.i, q.j, q.k],
+            pos_n_m: to_array(&self.pos_n_m),
+            vel_n_m_s: to_array(&self.vel_n_m_s),
+            angvel_unbias_b_rad_s: to_array(&self.angvel_unbias_b_rad_s),
+            acc_unbias_b_m_s2: to_array(&self.acc_unbias_b_m_s2),
+        }
+    }
+
+    fn from_record(record: NavigationRecord) -> Result<Self> {
+        let [w, x, y, z] = record.quat_nb;
+        Ok(Self {
+            quat_nb: UnitQuaternion::from_quaternion(Quaternion::new(w, x, y, z)),
+            pos_n_m: record.pos_n_m.into(),
+            vel_n_m_s: record.vel_n_m_s.into(),
+            angvel_unbias_b_rad_s: record.angvel_unbias_b_rad_s.into(),
+            acc_unbias_b_m_s2: record.acc_unbias_b_m_s2.into(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdaRecord {
+    pub altitude_m: f32,
+    pub vertical_speed_m_s: f32,
+    pub vertical_accel_m_s2: f32,
+    pub apogee_detected: bool,
+}
+
+impl Recordable for AdaResult {
+    type Record = AdaRecord;
+
+    fn to_record(&self) -> AdaRecord {
+        AdaRecord {
+            altitude_m: self.altitude_m,
+            vertical_speed_m_s: self.vertical_speed_m_s,
+            vertical_accel_m_s2: self.vertical_accel_m_s2,
+            apogee_detected: self.apogee_detected,
+        }
+    }
+
+    fn from_record(record: AdaRecord) -> Result<Self> {
+        Ok(Self {
+            altitude_m: record.altitude_m,
+            vertical_speed_m_s: record.vertical_speed_m_s,
+            vertical_accel_m_s2: record.vertical_accel_m_s2,
+            apogee_detected: record.apogee_detected,
+        })
+    }
+}
+
+/// Events are recorded by name, which is stable across changes of the event list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GncEventRecord {
+    pub src: u32,
+    pub event: String,
+}
+
+impl Recordable for GncEventItem {
+    type Record = GncEventRecord;
+
+    fn to_record(&self) -> GncEventRecord {
+        GncEventRecord {
+            src: self.src as u32,
+            event: format!("{:?}", self.event),
+        }
+    }
+
+    fn from_record(record: GncEventRecord) -> Result<Self> {
+        Ok(Self {
+            src: ComponentId::from_u32(record.src)
+                .ok_or(anyhow!("Unknown component id {}", record.src))?,
+            event: Event::from_name(&record.event)
+                .ok_or(anyhow!("Unknown GNC event '{}'", record.event))?,
+        })
+    }
+}
+
+impl Recordable for PyroCommand {
+    type Record = String;
+
+    fn to_record(&self) -> String {
+        format!("{:?}", self.channel)
+    }
+
+    fn from_record(record: String) -> Result<Self> {
+        let channel = match record.as_str() {
+            "Drogue" => PyroChannel::Drogue,
+            "Main" => PyroChannel::Main,
+            _ => return Err(anyhow!("Unknown pyro channel '{record}'")),
+        };
+
+        Ok(Self { channel })
+    }
+}
+
+/// Makes the channels of the recordable types available to the prefix subscriptions
+pub fn register_recordables(ts: &TelemetryService) {
+    register_recordable::<DigitalInputState>(ts);
+    register_recordable::<PressureSensorSample>(ts);
+    register_recordable::<ImuSensorSample>(ts);
+    register_recordable::<GpsSensorSample>(ts);
+    register_recordable::<MagnetometerSensorSample>(ts);
+    register_recordable::<NavigationOutput>(ts);
+    register_recordable::<AdaResult>(ts);
+    register_recordable::<GncEventItem>(ts);
+    register_recordable::<PyroCommand>(ts);
+}
+
+/// Sensor inputs of the flight software
+pub fn add_gnc_inputs(endpoint: &mut impl RecordingEndpoint, telem: &NodeTelemetry) -> Result<()> {
+    use channels::sensors;
+
+    endpoint.add_channel::<DigitalInputState>(telem, sensors::LIFTOFF_PIN, Producers::Single)?;
+    endpoint.add_channel::<PressureSensorSample>(
+        telem,
+        sensors::IDEAL_STATIC_PRESSURE,
+        Producers::Single,
+    )?;
+    endpoint.add_channel::<PressureSensorSample>(
+        telem,
+        sensors::STATIC_PRESSURE,
+        Producers::Single,
+    )?;
+    endpoint.add_channel::<ImuSensorSample>(telem, sensors::IDEAL_IMU, Producers::Single)?;
+    endpoint.add_channel::<GpsSensorSample>(telem, sensors::IDEAL_GPS, Producers::Single)?;
+    endpoint.add_channel::<MagnetometerSensorSample>(
+        telem,
+        sensors::IDEAL_MAGNETOMETER,
+        Producers::Single,
+    )?;
+    endpoint.add_channel::<NavigationOutput>(
+        telem,
+        sensors::IDEAL_NAV_OUTPUT,
+        Producers::Single,
+    )?;
+
+    Ok(())
+}
+
+/// Outputs of the flight software, to be compared with those of the replay
+pub fn add_gnc_outputs(endpoint: &mut impl RecordingEndpoint, telem: &NodeTelemetry) -> Result<()> {
+    endpoint.add_channel::<AdaResult>(telem, channels::gnc::ADA_OUTPUT, Producers::Single)?;
+    endpoint.add_channel::<NavigationOutput>(
+        telem,
+        channels::gnc::NAV_OUTPUT,
+        Producers::Single,
+    )?;
+    endpoint.add_channel::<PyroCommand>(telem, channels::gnc::PYRO_COMMAND, Producers::Multiple)?;
+
+    Ok(())
+}
+
+/// Records the inputs & outputs of the flight software, and all the GNC events
+pub fn record_gnc(recorder: &mut TelemetryRecorder, telem: &NodeTelemetry) -> Result<()> {
+    add_gnc_inputs(recorder, telem)?;
+    add_gnc_outputs(recorder, telem)?;
+    recorder.add_channel::<GncEventItem>(telem, channels::gnc::GNC_EVENTS, Producers::Multiple)
+}
+
+/// Replays the inputs of the flight software. Of the GNC events, only the ground commands are
+/// replayed: the others are produced again by the replayed flight software.
+pub fn replay_gnc_inputs(replayer: &mut TelemetryReplayer, telem: &NodeTelemetry) -> Result<()> {
+    add_gnc_inputs(replayer, telem)?;
+    replayer.add_channel_filtered::<GncEventItem>(
+        telem,
+        channels::gnc::GNC_EVENTS,
+        Producers::Multiple,
+        |item| item.src == ComponentId::Ground,
+    )
+}
+
+/// Streams the inputs & outputs of the flight software and all the GNC events over UDP.
+/// Optionally accepts ground commands (GNC events from the ground) from the remote end.
+pub fn bridge_gnc(
+    bridge: &mut UdpTelemetryBridge,
+    telem: &NodeTelemetry,
+    commands: bool,
+) -> Result<()> {
+    add_gnc_inputs(bridge, telem)?;
+    add_gnc_outputs(bridge, telem)?;
+    bridge.add_channel::<GncEventItem>(telem, channels::gnc::GNC_EVENTS, Producers::Multiple)?;
+
+    if commands {
+        bridge.add_command_channel::<GncEventItem>(
+            telem,
+            channels::gnc::GNC_EVENTS,
+            Producers::Multiple,
+            |item| item.src == ComponentId::Ground,
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gnc_event_record() {
+        let item = GncEventItem {
+            src: ComponentId::Ground,
+            event: Event::CmdFmmArm,
+        };
+
+        let record = item.to_record();
+        assert_eq!(record.event, "CmdFmmArm");
+        assert_eq!(GncEventItem::from_record(record).unwrap(), item);
+
+        assert!(
+            GncEventItem::from_record(GncEventRecord {
+                src: ComponentId::Ground as u32,
+                event: "NotAnEvent".to_string(),
+            })
+            .is_err()
+        );
+    }
+}
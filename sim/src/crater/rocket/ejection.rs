@@ -0,0 +1,189 @@
+use anyhow::{Result, anyhow};
+use nalgebra::{Matrix3, Vector3};
+
+use crate::{
+    crater::events::{GncEvent, SimEvent},
+    parameters::{ParameterMap, ParameterTree},
+};
+
+use super::{mass::RocketMassProperties, rocket_data::RocketParams};
+
+/// Part of the rocket released in flight (nose cone, payload...), removed from the dry mass
+/// properties when triggered
+#[derive(Debug, Clone)]
+pub struct MassEjection {
+    pub name: String,
+    pub mass_kg: f64,
+    /// CG of the ejected part, from the nose
+    pub xcg_m: Vector3<f64>,
+    /// Inertia of the ejected part, about its own CG
+    pub inertia_kgm2: Matrix3<f64>,
+    /// GNC event releasing the part, if any. It can always be released by a scripted
+    /// `EjectMass` sim event.
+    pub gnc_event: Option<String>,
+}
+
+impl MassEjection {
+    pub fn from_params(name: &str, params: &ParameterMap) -> Result<Self> {
+        Ok(Self {
+            name: name.to_string(),
+            mass_kg: params.get_param("mass")?.value_float()?,
+            xcg_m: params.get_vector3("xcg")?,
+            inertia_kgm2: params.get_matrix3("inertia")?,
+            gnc_event: if params.contains_key("gnc_event") {
+                Some(params.get_param("gnc_event")?.value_string()?)
+            } else {
+                None
+            },
+        })
+    }
+
+    /// Mass, CG & inertia about the CG of the rest of a body, after releasing this part
+    pub fn remaining(
+        &self,
+        mass_kg: f64,
+        xcg_m: &Vector3<f64>,
+        inertia_kgm2: &Matrix3<f64>,
+    ) -> Result<(f64, Vector3<f64>, Matrix3<f64>)> {
+        let rest_kg = mass_kg - self.mass_kg;
+        if rest_kg <= 0.0 {
+            return Err(anyhow!(
+                "Ejecting '{}' ({} kg) would leave no mass on the rocket",
+                self.name,
+                self.mass_kg
+            ));
+        }
+
+        let rest_xcg_m = (mass_kg * xcg_m - self.mass_kg * self.xcg_m) / rest_kg;
+        let rest_inertia_kgm2 = inertia_kgm2
+            - self.inertia_kgm2
+            - self.mass_kg * RocketMassProperties::parallel_axis_matrix(self.xcg_m - xcg_m)
+            - rest_kg * RocketMassProperties::parallel_axis_matrix(rest_xcg_m - xcg_m);
+
+        Ok((rest_kg, rest_xcg_m, rest_inertia_kgm2))
+    }
+
+    /// Removes the part from the dry mass of the rocket
+    pub fn apply(&self, params: &mut RocketParams) -> Result<()> {
+        let (mass_kg, xcg_m, inertia_kgm2) = self.remaining(
+            params.mass_body_kg,
+            &params.xcg_body_m,
+            &params.inertia_body_b_kgm2,
+        )?;
+
+        params.mass_body_kg = mass_kg;
+        params.xcg_body_m = xcg_m;
+        params.inertia_body_b_kgm2 = inertia_kgm2;
+
+        Ok(())
+    }
+}
+
+/// Mass ejections configured in `sim.rocket.ejections`, each released once
+#[derive(Debug, Clone, Default)]
+pub struct MassEjections {
+    pending: Vec<MassEjection>,
+}
+
+impl MassEjections {
+    /// One map per part, named after it. None configured if `ejections` is missing.
+    pub fn from_params(params: &ParameterMap) -> Result<Self> {
+        let mut pending = vec![];
+        if params.contains_key("ejections") {
+            for (name, tree) in params.get_map("ejections")?.iter() {
+                if let ParameterTree::Node(map) = tree {
+                    pending.push(MassEjection::from_params(name, map)?);
+                }
+            }
+        }
+
+        Ok(Self { pending })
+    }
+
+    fn take(&mut self, pred: impl Fn(&MassEjection) -> bool) -> Vec<MassEjection> {
+        let (taken, pending) = self.pending.drain(..).partition(pred);
+        self.pending = pending;
+        taken
+    }
+
+    /// Parts released by a scripted sim event
+    pub fn on_sim_event(&mut self, event: &SimEvent) -> Vec<MassEjection> {
+        match event {
+            SimEvent::EjectMass { name } => self.take(|e| &e.name == name),
+            _ => vec![],
+        }
+    }
+
+    /// Parts released by a GNC event
+    pub fn on_gnc_event(&mut self, event: &GncEvent) -> Vec<MassEjection> {
+        let event = format!("{event:?}");
+        self.take(|e| e.gnc_event.as_ref() == Some(&event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_mass_ejection() -> Result<()> {
+        let nose = MassEjection {
+            name: "nose".to_string(),
+            mass_kg: 0.2,
+            xcg_m: Vector3::new(0.15, 0.0, 0.0),
+            inertia_kgm2: Matrix3::from_diagonal(&Vector3::new(1e-4, 5e-4, 5e-4)),
+            gnc_event: Some("RecoveryDrogueFired".to_string()),
+        };
+
+        let xcg = Vector3::new(0.55, 0.0, 0.0);
+        let inertia = Matrix3::from_diagonal(&Vector3::new(0.005, 0.1, 0.1));
+        let (mass, rest_xcg, rest_inertia) = nose.remaining(2.0, &xcg, &inertia)?;
+
+        assert_relative_eq!(mass, 1.8);
+        assert_relative_eq!(
+            rest_xcg[0],
+            (2.0 * 0.55 - 0.2 * 0.15) / 1.8,
+            epsilon = 1e-12
+        );
+
+        // Parallel axis theorem about the original CG, on the pitch axis
+        let shift = rest_xcg[0] - 0.55;
+        assert_relative_eq!(
+            rest_inertia[(1, 1)],
+            0.1 - 5e-4 - 0.2 * 0.4f64.powi(2) - 1.8 * shift.powi(2),
+            epsilon = 1e-12
+        );
+        assert_relative_eq!(rest_inertia[(0, 0)], 0.005 - 1e-4, epsilon = 1e-12);
+        assert_eq!(rest_inertia[(0, 1)], 0.0);
+
+        assert!(nose.remaining(0.2, &xcg, &inertia).is_err());
+
+        // Released once, by its GNC event or by name
+        let mut ejections = MassEjections {
+            pending: vec![nose.clone()],
+        };
+        assert!(ejections.on_gnc_event(&GncEvent::FlightBurnout).is_empty());
+        assert_eq!(
+            ejections.on_gnc_event(&GncEvent::RecoveryDrogueFired).len(),
+            1
+        );
+        assert!(
+            ejections
+                .on_gnc_event(&GncEvent::RecoveryDrogueFired)
+                .is_empty()
+        );
+
+        let mut ejections = MassEjections {
+            pending: vec![nose],
+        };
+        let event = SimEvent::EjectMass {
+            name: "nose".to_string(),
+        };
+        assert_eq!(ejections.on_sim_event(&event).len(), 1);
+        assert!(ejections.on_sim_event(&event).is_empty());
+
+        Ok(())
+    }
+}
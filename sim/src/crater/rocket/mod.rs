@@ -1,4 +1,8 @@
 pub mod rocket;
 pub mod rocket_data;
 pub mod rocket_output;
-pub mod mass;
\ No newline at end of file
+pub mod mass;
+pub mod reference_point;
+pub mod recovery;
+pub mod ejection;
+pub mod rail;
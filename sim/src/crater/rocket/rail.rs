@@ -0,0 +1,152 @@
+use anyhow::{Result, anyhow};
+use nalgebra::{UnitQuaternion, Vector3};
+
+use crate::parameters::ParameterMap;
+
+use super::rocket_data::RocketParams;
+
+/// Along-rail speed below which the rocket is considered at rest [m/s]
+const REST_SPEED_M_S: f64 = 1e-6;
+
+/// Launch rail, pointing along the initial attitude of the rocket. The rocket slides along it,
+/// with its rotation locked, until the aft rail button leaves the rail.
+#[derive(Debug, Clone)]
+pub struct LaunchRail {
+    /// Position of the rocket at rest at the bottom of the rail
+    origin_n_m: Vector3<f64>,
+    /// Direction of the rail, pointing up
+    versor_n: Vector3<f64>,
+    /// Distance travelled by the aft button before it leaves the rail
+    length_m: f64,
+    /// Sliding friction coefficient of the rail buttons
+    friction_coeff: f64,
+}
+
+impl LaunchRail {
+    pub fn new(
+        origin_n_m: Vector3<f64>,
+        azimuth_rad: f64,
+        elevation_rad: f64,
+        length_m: f64,
+        friction_coeff: f64,
+    ) -> Result<Self> {
+        if length_m <= 0.0 || friction_coeff < 0.0 {
+            return Err(anyhow!(
+                "Launch rail length must be positive and friction non-negative"
+            ));
+        }
+
+        let q_nb = UnitQuaternion::from_euler_angles(0.0, elevation_rad, azimuth_rad);
+
+        Ok(Self {
+            origin_n_m,
+            versor_n: q_nb.transform_vector(&Vector3::x()).normalize(),
+            length_m,
+            friction_coeff,
+        })
+    }
+
+    /// Rail along the initial orientation of the rocket, with its length & friction from
+    /// `sim.rocket.rail`
+    pub fn from_params(params: &ParameterMap, rocket_params: &RocketParams) -> Result<Self> {
+        Self::new(
+            rocket_params.p0_n,
+            rocket_params.azimuth,
+            rocket_params.elevation,
+            params.get_param("length")?.value_float()?,
+            params.get_param("friction")?.value_float()?,
+        )
+    }
+
+    pub fn versor_n(&self) -> Vector3<f64> {
+        self.versor_n
+    }
+
+    /// Distance travelled along the rail
+    pub fn travel_m(&self, pos_n_m: &Vector3<f64>) -> f64 {
+        (pos_n_m - self.origin_n_m).dot(&self.versor_n)
+    }
+
+    /// Whether the aft button has left the rail
+    pub fn cleared(&self, pos_n_m: &Vector3<f64>) -> bool {
+        self.travel_m(pos_n_m) >= self.length_m
+    }
+
+    /// Net force on the rocket while on the rail, given the sum `force_n_n` of all the other
+    /// forces. The rail reacts to the component normal to it, which loads the buttons and
+    /// opposes the sliding with friction. At rest, the rocket stays put until the force along
+    /// the rail overcomes the friction, and the rail stop holds it at the bottom end.
+    pub fn constrained_force_n(
+        &self,
+        force_n_n: &Vector3<f64>,
+        pos_n_m: &Vector3<f64>,
+        vel_n_m_s: &Vector3<f64>,
+    ) -> Vector3<f64> {
+        let along_n = self.versor_n.dot(force_n_n);
+        let friction_n = self.friction_coeff * (force_n_n - along_n * self.versor_n).norm();
+        let speed_m_s = self.versor_n.dot(vel_n_m_s);
+
+        let net_n = if speed_m_s > REST_SPEED_M_S {
+            along_n - friction_n
+        } else if speed_m_s < -REST_SPEED_M_S {
+            along_n + friction_n
+        } else if along_n.abs() > friction_n {
+            along_n - friction_n * along_n.signum()
+        } else {
+            0.0
+        };
+
+        if self.travel_m(pos_n_m) <= 0.0 && net_n < 0.0 {
+            Vector3::zeros()
+        } else {
+            net_n * self.versor_n
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_rail_constraint() -> Result<()> {
+        // Pointing north, 60° up
+        let rail = LaunchRail::new(Vector3::zeros(), 0.0, 60f64.to_radians(), 5.0, 0.2)?;
+        let versor = rail.versor_n();
+        assert_relative_eq!(
+            versor,
+            Vector3::new(0.5, 0.0, -0.75f64.sqrt()),
+            epsilon = 1e-12
+        );
+
+        let weight = Vector3::new(0.0, 0.0, 10.0);
+        let along_weight = versor.dot(&weight);
+        let friction_weight = 0.2 * (weight - along_weight * versor).norm();
+
+        // At rest at the bottom, weight alone: held by the rail stop
+        let zero = Vector3::zeros();
+        assert_eq!(rail.constrained_force_n(&weight, &zero, &zero), zero);
+
+        // Thrust along the rail, sliding up: the normal load is still the weight
+        let force = weight + versor * 30.0;
+        let vel = versor * 10.0;
+        let pos = versor * 2.0;
+        assert_relative_eq!(
+            rail.constrained_force_n(&force, &pos, &vel),
+            versor * (30.0 + along_weight - friction_weight),
+            epsilon = 1e-12
+        );
+        assert!(!rail.cleared(&pos));
+
+        // At rest halfway up the rail, friction is enough to hold it
+        let force = weight + versor * (-along_weight + 0.5 * friction_weight);
+        assert_eq!(rail.constrained_force_n(&force, &pos, &zero), zero);
+
+        assert_relative_eq!(rail.travel_m(&(versor * 5.0 + Vector3::y())), 5.0);
+        assert!(rail.cleared(&(versor * 5.1)));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,131 @@
+use anyhow::{Result, anyhow};
+use nalgebra::Vector3;
+
+use crate::{
+    math::interp::{find_index, interpolate},
+    parameters::ParameterMap,
+};
+
+/// Parachute drag model.
+///
+/// The nominal drag area (CD·S) is tabulated against the Mach number. After deployment, the
+/// canopy inflates over `inflation_time_s` (drag area growing quadratically), then the opening
+/// shock is modeled as an overshoot of the drag area by `opening_shock_factor`, decaying back
+/// to the nominal value with a time constant equal to the inflation time.
+#[derive(Debug, Clone)]
+pub struct Parachute {
+    mach: Vec<f64>,
+    cd_s_m2: Vec<f64>,
+    inflation_time_s: f64,
+    opening_shock_factor: f64,
+}
+
+impl Parachute {
+    pub fn new(
+        mach: &[f64],
+        cd_s_m2: &[f64],
+        inflation_time_s: f64,
+        opening_shock_factor: f64,
+    ) -> Result<Self> {
+        if mach.is_empty() || mach.len() != cd_s_m2.len() {
+            return Err(anyhow!(
+                "Parachute CD·S table must be non-empty and have as many values as Mach breakpoints"
+            ));
+        }
+
+        Ok(Self {
+            mach: mach.to_vec(),
+            cd_s_m2: cd_s_m2.to_vec(),
+            inflation_time_s,
+            opening_shock_factor,
+        })
+    }
+
+    pub fn from_params(params: &ParameterMap) -> Result<Self> {
+        Self::new(
+            params.get_param("mach")?.value_float_arr()?,
+            params.get_param("cd_s")?.value_float_arr()?,
+            params.get_param("inflation_time")?.value_float()?,
+            params.get_param("opening_shock_factor")?.value_float()?,
+        )
+    }
+
+    /// Drag area, `t_deploy_s` seconds after deployment
+    pub fn cd_s_m2(&self, t_deploy_s: f64, mach: f64) -> f64 {
+        if t_deploy_s < 0.0 {
+            return 0.0;
+        }
+
+        let cd_s = interpolate(&self.cd_s_m2, find_index(&self.mach, mach)).0;
+
+        if self.inflation_time_s <= 0.0 {
+            return cd_s;
+        }
+
+        let inflation = if t_deploy_s < self.inflation_time_s {
+            (t_deploy_s / self.inflation_time_s).powi(2) * self.opening_shock_factor
+        } else {
+            1.0 + (self.opening_shock_factor - 1.0)
+                * (-(t_deploy_s - self.inflation_time_s) / self.inflation_time_s).exp()
+        };
+
+        cd_s * inflation
+    }
+}
+
+/// Drogue & main parachutes. Deployment is commanded through GNC events, handled by the rocket
+/// state machine, which keeps track of the deployment times.
+#[derive(Debug, Clone)]
+pub struct Recovery {
+    pub drogue: Parachute,
+    pub main: Parachute,
+
+    /// Deploy the chutes at apogee & at the main deployment altitude without waiting for the
+    /// commands, as a backup altimeter would
+    pub backup_deploy: bool,
+    pub main_deploy_alt_m: f64,
+}
+
+impl Recovery {
+    pub fn from_params(params: &ParameterMap) -> Result<Self> {
+        Ok(Self {
+            drogue: Parachute::from_params(params.get_map("drogue")?)?,
+            main: Parachute::from_params(params.get_map("main")?)?,
+            backup_deploy: params.get_param("backup_deploy")?.value_bool()?,
+            main_deploy_alt_m: params.get_param("main_deploy_altitude")?.value_float()?,
+        })
+    }
+
+    /// Total parachute drag force in the NED frame. Times since deployment are None if the
+    /// parachute has not been deployed.
+    /// The force is applied at the CG: the attitude dynamics under canopy are not modeled.
+    pub fn drag_force_n(
+        &self,
+        t_drogue_s: Option<f64>,
+        t_main_s: Option<f64>,
+        v_air_n_m_s: &Vector3<f64>,
+        air_density_kg_m3: f64,
+        mach: f64,
+    ) -> Vector3<f64> {
+        let cd_s_m2 = t_drogue_s.map_or(0.0, |t| self.drogue.cd_s_m2(t, mach))
+            + t_main_s.map_or(0.0, |t| self.main.cd_s_m2(t, mach));
+
+        -0.5 * air_density_kg_m3 * cd_s_m2 * v_air_n_m_s.norm() * v_air_n_m_s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_opening_shock() {
+        let chute = Parachute::new(&[0.0, 1.0], &[1.0, 1.0], 0.5, 1.8).unwrap();
+
+        assert_relative_eq!(chute.cd_s_m2(-1.0, 0.1), 0.0);
+        assert_relative_eq!(chute.cd_s_m2(0.25, 0.1), 0.25 * 1.8);
+        assert_relative_eq!(chute.cd_s_m2(0.5, 0.1), 1.8);
+        assert_relative_eq!(chute.cd_s_m2(100.0, 0.1), 1.0, epsilon = 1e-9);
+    }
+}
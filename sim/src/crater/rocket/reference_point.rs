@@ -0,0 +1,104 @@
+use std::cell::Cell;
+
+use anyhow::{Result, anyhow};
+use nalgebra::Vector3;
+
+use crate::parameters::ParameterMap;
+
+use super::rocket_data::{RocketAccelerations, RocketState};
+
+/// Point of the rocket body the published state is expressed at.
+///
+/// Positions use the same convention as the other rocket parameters (`xcg_body`, `imu.pos_r`):
+/// measured from the nose, so the lever arm from the CG to a point `p` in body axes is
+/// `xcg - p`.
+#[derive(Debug, Clone)]
+pub enum ReferencePoint {
+    /// Instantaneous center of gravity (the integrated state)
+    Cg,
+    /// Center of gravity at t0. Latched on the first update.
+    Cg0(Cell<Option<Vector3<f64>>>),
+    /// Fixed point of the body (nose, IMU...)
+    Fixed(Vector3<f64>),
+}
+
+impl ReferencePoint {
+    /// Reads the reference point from the rocket parameters
+    pub fn from_params(params: &ParameterMap) -> Result<Self> {
+        match params
+            .get_param("output.reference_point")?
+            .value_string()?
+            .as_str()
+        {
+            "cg" => Ok(ReferencePoint::Cg),
+            "cg0" => Ok(ReferencePoint::Cg0(Cell::new(None))),
+            "nose" => Ok(ReferencePoint::Fixed(Vector3::zeros())),
+            "imu" => Ok(ReferencePoint::Fixed(params.get_vector3("imu.pos_r")?)),
+            unknown => Err(anyhow!("Unknown state reference point: {unknown}")),
+        }
+    }
+
+    /// Expresses the CG state and accelerations at the reference point.
+    /// The motion of the CG in the body frame (due to propellant burn) is neglected.
+    pub fn transform(
+        &self,
+        state: &RocketState,
+        accel: &RocketAccelerations,
+        xcg_m: &Vector3<f64>,
+    ) -> (RocketState, RocketAccelerations) {
+        let point = match self {
+            ReferencePoint::Cg => return (state.clone(), accel.clone()),
+            ReferencePoint::Cg0(point) => {
+                let xcg0 = point.get().unwrap_or(*xcg_m);
+                point.set(Some(xcg0));
+                xcg0
+            }
+            ReferencePoint::Fixed(point) => *point,
+        };
+
+        let r_b = xcg_m - point;
+
+        let q_nb = state.quat_nb();
+        let w_b = state.angvel_b_rad_s();
+
+        let mut ref_state = state.clone();
+        ref_state.set_pos_n_m(&(state.pos_n_m() + q_nb.transform_vector(&r_b)));
+        ref_state.set_vel_n_m_s(&(state.vel_n_m_s() + q_nb.transform_vector(&w_b.cross(&r_b))));
+
+        let acc_b_m_s2 =
+            accel.acc_b_m_s2 + accel.ang_acc_b_rad_s2.cross(&r_b) + w_b.cross(&w_b.cross(&r_b));
+
+        let ref_accel = RocketAccelerations {
+            acc_b_m_s2,
+            acc_n_m_s2: q_nb.transform_vector(&acc_b_m_s2),
+            ang_acc_b_rad_s2: accel.ang_acc_b_rad_s2,
+        };
+
+        (ref_state, ref_accel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use nalgebra::{UnitQuaternion, vector};
+
+    #[test]
+    fn test_nose_reference() {
+        let mut state = RocketState::default();
+        state.set_quat_nb_vec(&UnitQuaternion::<f64>::identity().into_inner().coords);
+        state.set_angvel_b_rad_s(&vector![0.0, 0.0, 1.0]);
+
+        let accel = RocketAccelerations::default();
+        let xcg = vector![0.5, 0.0, 0.0];
+
+        let (state_ref, accel_ref) =
+            ReferencePoint::Fixed(Vector3::zeros()).transform(&state, &accel, &xcg);
+
+        assert_relative_eq!(state_ref.pos_n_m(), vector![0.5, 0.0, 0.0]);
+        assert_relative_eq!(state_ref.vel_n_m_s(), vector![0.0, 0.5, 0.0]);
+        assert_relative_eq!(accel_ref.acc_b_m_s2, vector![-0.5, 0.0, 0.0]);
+        assert_relative_eq!(accel_ref.acc_n_m_s2, vector![-0.5, 0.0, 0.0]);
+    }
+}
@@ -1,6 +1,11 @@
 use super::{
+    ejection::{MassEjection, MassEjections},
     mass::RocketMassProperties,
-    rocket_data::{RocketAccelerations, RocketActions, RocketParams, RocketState},
+    rail::LaunchRail,
+    recovery::Recovery,
+    rocket_data::{
+        AttitudeIntegrator, RocketAccelerations, RocketActions, RocketParams, RocketState,
+    },
     rocket_output::RocketOutput,
 };
 use crate::{
@@ -9,20 +14,25 @@ use crate::{
         aero::{
             aerodynamics::{
                 AeroCoefficientsValues, AeroState, Aerodynamics, AerodynamicsCoefficients,
+                AirbrakeDrag, FinCant,
             },
-            atmosphere::{Atmosphere, AtmosphereIsa, AtmosphereProperties, mach_number},
+            atmosphere::{Atmosphere, AtmosphereProperties, atmosphere_from_params, mach_number},
+            barrowman::BarrowmanAeroCoefficients,
             linear_aerodynamics::LinearizedAeroCoefficients,
+            stability::StaticMarginMonitor,
             tabulated_aerodynamics::TabulatedAeroCoefficients,
+            wind::{WindModel, wind_from_params},
         },
         channels,
         engine::{
             SimpleRocketEngine, TabRocketEngine,
             engine::{RocketEngine, RocketEngineMassProperties},
+            misalignment::ThrustMisalignment,
         },
         events::{Event, GncEvent, GncEventItem, SimEvent},
-        gnc::ServoPosition,
+        gnc::{AirbrakePosition, ServoPosition},
     },
-    math::ode::{OdeProblem, OdeSolver, RungeKutta4},
+    math::ode::{AttitudeOdeProblem, MuntheKaas4, OdeProblem, OdeSolver, RungeKutta4},
     nodes::{Node, NodeContext, StepResult},
     telemetry::{TelemetryReceiver, TelemetrySender, Timestamped},
     utils::capacity::Capacity::Unbounded,
@@ -30,10 +40,12 @@ use crate::{
 use anyhow::{Result, anyhow};
 use chrono::TimeDelta;
 use core::f64;
-use crater_gnc::mav_crater::ComponentId;
+use crater_gnc::{
+    datatypes::recovery::{PyroChannel, PyroCommand},
+    mav_crater::ComponentId,
+};
 use nalgebra::{Quaternion, SVector, UnitQuaternion, Vector3, Vector4};
 use statig::prelude::*;
-use std::{path::PathBuf, str::FromStr};
 use strum::AsRefStr;
 
 pub struct Rocket {
@@ -42,22 +54,34 @@ pub struct Rocket {
     pub(super) step_state: StepState,
 
     pub(super) engine: Box<dyn RocketEngine + Send>,
+    pub(super) thrust_misalignment: ThrustMisalignment,
     pub(super) aero_coeffs: Box<dyn AerodynamicsCoefficients + Send>,
     pub(super) aerodynamics: Aerodynamics,
+    pub(super) airbrake_drag: AirbrakeDrag,
+    pub(super) fin_cant: FinCant,
     pub(super) atmosphere: Box<dyn Atmosphere + Send>,
+    pub(super) wind: Box<dyn WindModel + Send>,
+    pub(super) recovery: Recovery,
+    pub(super) rail: LaunchRail,
+    ejections: MassEjections,
 
     pub(super) fsm: StateMachine<RocketFsm>,
 
     rx_servo_pos: TelemetryReceiver<ServoPosition>,
+    rx_airbrake_pos: TelemetryReceiver<AirbrakePosition>,
     rx_sim_event: TelemetryReceiver<SimEvent>,
+    rx_gnc_event: TelemetryReceiver<GncEventItem>,
+    rx_pyro: TelemetryReceiver<PyroCommand>,
 
     output: RocketOutput,
+    margin_monitor: StaticMarginMonitor,
 }
 
 /// Variables allowed to change between steps, but not within a step (more precisely, during integration of a single step)
 #[derive(Debug, Clone, Default)]
 pub(super) struct StepState {
     servo_pos: ServoPosition,
+    airbrake_extension: f64,
 }
 
 impl Rocket {
@@ -70,7 +94,7 @@ impl Rocket {
         // Initialize state with initial conditions from parameters
         let state = RocketState::from_params(&rocket_params);
 
-        // Select which engine to use based on the config file (currently only one option)
+        // Select which engine to use based on the config file
         let engine: Box<dyn RocketEngine + Send> = match params_map
             .get_param("engine.engine_type")?
             .value_string()?
@@ -90,6 +114,12 @@ impl Rocket {
                     .value_string()?
                     .as_str(),
             )?),
+            "rasp" => Box::new(TabRocketEngine::from_eng(
+                params_map
+                    .get_param("engine.rasp.eng_path")?
+                    .value_string()?
+                    .as_str(),
+            )?),
             unknown => {
                 return Err(anyhow!(
                     "Unknown engine type selected for rocket '{name}': {unknown}"
@@ -97,25 +127,22 @@ impl Rocket {
             }
         };
 
+        let thrust_misalignment =
+            ThrustMisalignment::from_params(params_map.get_map("engine.misalignment")?)?;
+
         let aero_coeffs: Box<dyn AerodynamicsCoefficients + Send> =
             match params_map.get_param("aero.model")?.value_string()?.as_str() {
                 "linear" => Box::new(LinearizedAeroCoefficients::from_params(
                     params_map.get_map("sim.rocket.aero.linear")?,
                 )?),
-                "tabulated" => {
-                    let coeffs_main_path = params_map
-                        .get_param("aero.tabulated.coeffs_main")?
-                        .value_string()?;
-                    let coeffs_dynamic_path = params_map
-                        .get_param("aero.tabulated.coeffs_dynamic")?
-                        .value_string()?;
-
-                    // let aero_params = rocket_params.get_map("aero")?;
-                    // let aero_coefficients = AeroCoefficients::from_params(aero_params)?;
-                    let file1 = PathBuf::from_str(&coeffs_main_path).unwrap();
-                    let file2 = PathBuf::from_str(&coeffs_dynamic_path).unwrap();
-                    Box::new(TabulatedAeroCoefficients::from_h5(&file1, &file2)?)
-                }
+                "tabulated" => Box::new(TabulatedAeroCoefficients::from_params(
+                    params_map.get_map("aero.tabulated")?,
+                )?),
+                "barrowman" => Box::new(BarrowmanAeroCoefficients::from_params(
+                    params_map.get_map("aero.barrowman")?,
+                    rocket_params.diameter,
+                    rocket_params.datcom_ref_pos_m[0],
+                )?),
                 unknown => {
                     return Err(anyhow!(
                         "Unknown aerodynamics model selected for rocket '{name}': {unknown}"
@@ -123,36 +150,103 @@ impl Rocket {
                 }
             };
 
-        let atmosphere = Box::new(AtmosphereIsa::default());
+        let atmosphere = atmosphere_from_params(ctx.parameters().get_map("sim.atmosphere")?)?;
+        let wind = wind_from_params(ctx.parameters().get_map("sim.wind")?)?;
+        let recovery = Recovery::from_params(params_map.get_map("recovery")?)?;
+        let airbrake_drag = AirbrakeDrag::from_params(params_map.get_map("airbrake")?)?;
+        let fin_cant = FinCant::from_params(params_map.get_map("aero.fin_cant")?)?;
+        let rail = LaunchRail::from_params(params_map.get_map("rail")?, &rocket_params)?;
+        let ejections = MassEjections::from_params(params_map)?;
 
         let rx_servo_pos = ctx
             .telemetry()
-            .subscribe(channels::actuators::IDEAL_SERVO_POSITION, Unbounded)?;
+            .subscribe_feedback(channels::actuators::IDEAL_SERVO_POSITION, Unbounded)?;
+        let rx_airbrake_pos = ctx
+            .telemetry()
+            .subscribe_feedback(channels::actuators::AIRBRAKE_POSITION, Unbounded)?;
 
         let rx_sim_event = ctx
             .telemetry()
             .subscribe_mp(channels::sim::SIM_EVENTS, Unbounded)?;
+        let rx_gnc_event = ctx
+            .telemetry()
+            .subscribe_mp(channels::gnc::GNC_EVENTS, Unbounded)?;
+        let rx_pyro = ctx
+            .telemetry()
+            .subscribe_mp(channels::gnc::PYRO_COMMAND, Unbounded)?;
         let tx_gnc_event = ctx.telemetry().publish_mp(channels::gnc::GNC_EVENTS)?;
         let tx_sim_event = ctx.telemetry().publish_mp(channels::sim::SIM_EVENTS)?;
 
-        let fsm = RocketFsm::new(tx_gnc_event, tx_sim_event).state_machine();
+        let fsm =
+            RocketFsm::new(tx_gnc_event, tx_sim_event, &recovery, rail.clone()).state_machine();
 
-        let output = RocketOutput::new(ctx.telemetry())?;
+        let output = RocketOutput::new(ctx.telemetry(), params_map)?;
+        let margin_monitor =
+            StaticMarginMonitor::new(ctx.telemetry(), params_map.get_map("aero.stability")?)?;
 
         Ok(Rocket {
             engine,
+            thrust_misalignment,
             aerodynamics: Aerodynamics::new(rocket_params.diameter, rocket_params.surface),
+            airbrake_drag,
+            fin_cant,
             params: rocket_params,
             aero_coeffs,
             atmosphere,
+            wind,
+            recovery,
+            rail,
+            ejections,
             state,
             rx_servo_pos,
+            rx_airbrake_pos,
             rx_sim_event,
+            rx_gnc_event,
+            rx_pyro,
             fsm,
             output,
+            margin_monitor,
             step_state: StepState::default(),
         })
     }
+
+    /// Whether the rocket is still on the launch rail, with its rotation locked
+    pub(super) fn on_rail(&self) -> bool {
+        matches!(
+            self.fsm.state(),
+            State::OnPad {} | State::LiftingOff {} | State::FlyingRamp {}
+        )
+    }
+
+    /// Releases a part of the rocket. The state follows the CG, which jumps with the mass
+    /// properties.
+    fn eject(&mut self, t_s: f64, ejection: &MassEjection) -> Result<()> {
+        let mass_engine = self.engine.mass(self.fsm.t_from_ignition(t_s));
+        let xcg_before_m = RocketMassProperties::calc_mass(&mass_engine, &self.params).xcg_total_m;
+
+        ejection.apply(&mut self.params)?;
+
+        let xcg_after_m = RocketMassProperties::calc_mass(&mass_engine, &self.params).xcg_total_m;
+
+        // Positions are measured from the nose: the shift in body axes is reversed
+        let shift_b_m = xcg_before_m - xcg_after_m;
+        let q_nb = self.state.quat_nb();
+        let w_b_rad_s = self.state.angvel_b_rad_s();
+        self.state
+            .set_pos_n_m(&(self.state.pos_n_m() + q_nb.transform_vector(&shift_b_m)));
+        self.state.set_vel_n_m_s(
+            &(self.state.vel_n_m_s() + q_nb.transform_vector(&w_b_rad_s.cross(&shift_b_m))),
+        );
+
+        Ok(())
+    }
+
+    /// Publishes the outputs of the latest step, and checks the static margin
+    fn update_output(&mut self, t: Timestamp) {
+        if let Some(stability) = self.output.update(t, self) {
+            self.margin_monitor.update(t, &stability);
+        }
+    }
 }
 
 pub(super) struct RocketOdeStep {
@@ -177,11 +271,17 @@ impl RocketOdeStep {
         let atmosphere_props = rocket.atmosphere.properties(altitude_m);
 
         let q_nb: UnitQuaternion<f64> = state.quat_nb();
-        let vel_b_m_s: Vector3<f64> =
-            q_nb.inverse_transform_vector(&state.vel_n_m_s().clone_owned());
+        let v_air_n_m_s: Vector3<f64> = state.vel_n_m_s() - rocket.wind.wind_n(altitude_m);
+        let vel_b_m_s: Vector3<f64> = q_nb.inverse_transform_vector(&v_air_n_m_s);
         let vel_norm_m_s = vel_b_m_s.norm();
 
         let w_b_rad_s: Vector3<f64> = state.angvel_b_rad_s();
+        // Attitude held by the rail
+        let w_att_b_rad_s = if rocket.on_rail() {
+            Vector3::zeros()
+        } else {
+            w_b_rad_s
+        };
         let mach = mach_number(vel_norm_m_s, atmosphere_props.speed_of_sound_m_s);
 
         let aero_state = AeroState::new(
@@ -191,18 +291,23 @@ impl RocketOdeStep {
             mach,
             atmosphere_props.air_density_kg_m3,
             rocket.step_state.servo_pos.clone(),
+            rocket.step_state.airbrake_extension,
         );
 
-        let aero_coeffs = rocket.aero_coeffs.coefficients(&aero_state);
+        let mut aero_coeffs = rocket.aero_coeffs.coefficients(&aero_state);
+        if !rocket.aero_coeffs.includes_airbrake() {
+            aero_coeffs.cA += rocket.airbrake_drag.delta_ca(rocket.step_state.airbrake_extension);
+        }
+        rocket.fin_cant.apply(&mut aero_coeffs);
 
         // TODO: Apply forces on correct point, not just COM
         let actions =
             Self::rocket_actions(rocket, t_s, &state, &aero_state, &aero_coeffs, &mass_rocket);
 
         let qw: Quaternion<f64> = Quaternion::from_vector(Vector4::new(
-            w_b_rad_s[0] / 2.0,
-            w_b_rad_s[1] / 2.0,
-            w_b_rad_s[2] / 2.0,
+            w_att_b_rad_s[0] / 2.0,
+            w_att_b_rad_s[1] / 2.0,
+            w_att_b_rad_s[2] / 2.0,
             0.0,
         ));
         let qdot: Quaternion<f64> = q_nb.into_inner() * qw;
@@ -210,8 +315,8 @@ impl RocketOdeStep {
         let acc_n_m_s2 = actions.tot_force_n_n / mass_rocket.mass_kg;
 
         let ang_acc_b_rad_s2: Vector3<f64> = mass_rocket.inertia_kgm2.try_inverse().unwrap()
-            * (actions.tot_moment_b_nm - mass_rocket.inertia_dot_kgm2_s * w_b_rad_s
-                + (mass_rocket.inertia_kgm2 * w_b_rad_s).cross(&w_b_rad_s));
+            * (actions.tot_moment_b_nm - mass_rocket.inertia_dot_kgm2_s * w_att_b_rad_s
+                + (mass_rocket.inertia_kgm2 * w_att_b_rad_s).cross(&w_att_b_rad_s));
 
         let accels = RocketAccelerations {
             acc_b_m_s2: q_nb.inverse_transform_vector(&acc_n_m_s2),
@@ -255,30 +360,40 @@ impl RocketOdeStep {
         let aero_force_b_n = aero_actions.forces_b_n;
         let aero_moment_b_nm = aero_actions.moments_b_nm;
 
-        let thrust_b_n = rocket.engine.thrust_b(t_ignition);
+        let thrust_b_n = rocket
+            .thrust_misalignment
+            .thrust_b(&rocket.engine.thrust_b(t_ignition));
+        let thrust_moment_b_nm = rocket
+            .thrust_misalignment
+            .moment_b(&thrust_b_n, &mass_props.xcg_total_m);
+
+        let parachute_force_n_n = rocket.recovery.drag_force_n(
+            rocket.fsm.t_from_drogue(t),
+            rocket.fsm.t_from_main(t),
+            &q_nb.transform_vector(&aero_state.v_air_b_m_s),
+            aero_state.air_density_kg_m3,
+            aero_state.mach,
+        );
 
         let force_n: Vector3<f64> = q_nb
             .transform_vector(&(thrust_b_n + aero_force_b_n + rocket.params.disturb_const_force_b))
             - mass_props.mass_dot_kg_s * &rocket_state.vel_n_m_s()
-            + rocket.params.g_n * mass_props.mass_kg;
+            + rocket.params.g_n * mass_props.mass_kg
+            + parachute_force_n_n;
 
         let (tot_force_n_n, tot_moment_b_nm) = match rocket.fsm.state() {
             State::OnPad {} => (Vector3::<f64>::zeros(), Vector3::<f64>::zeros()),
-            State::LiftingOff {} | State::FlyingRamp {} => {
-                if force_n[2].abs() > rocket.params.g_n[2].abs() * mass_props.mass_kg {
-                    (
-                        // Only keep component of acceleration parallel to the ramp
-                        rocket.params.ramp_versor.dot(&force_n) * rocket.params.ramp_versor,
-                        Vector3::<f64>::zeros(),
-                    )
-                } else {
-                    // Thurst not yet high enough to move
-                    (Vector3::<f64>::zeros(), Vector3::<f64>::zeros())
-                }
-            }
+            State::LiftingOff {} | State::FlyingRamp {} => (
+                rocket.rail.constrained_force_n(
+                    &force_n,
+                    &rocket_state.pos_n_m(),
+                    &rocket_state.vel_n_m_s(),
+                ),
+                Vector3::<f64>::zeros(),
+            ),
             _ => {
                 let torque_b: Vector3<f64> =
-                    aero_moment_b_nm + rocket.params.disturb_const_torque_b;
+                    aero_moment_b_nm + thrust_moment_b_nm + rocket.params.disturb_const_torque_b;
                 (force_n, torque_b)
             }
         };
@@ -288,6 +403,7 @@ impl RocketOdeStep {
         RocketActions {
             thrust_b_n,
             aero_actions: aero_actions,
+            parachute_force_n_n,
             tot_force_n_n,
             tot_force_b_n,
             tot_moment_b_nm,
@@ -303,13 +419,27 @@ impl OdeProblem<f64, 13> for Rocket {
     }
 }
 
+impl AttitudeOdeProblem<13> for Rocket {
+    fn quat_index(&self) -> usize {
+        6
+    }
+
+    fn angvel_b(&self, y: &SVector<f64, 13>) -> Vector3<f64> {
+        if self.on_rail() {
+            Vector3::zeros()
+        } else {
+            RocketState(*y).angvel_b_rad_s()
+        }
+    }
+}
+
 impl Node for Rocket {
     fn step(&mut self, i: usize, dt: TimeDelta, clock: &dyn Clock) -> Result<StepResult> {
         let t = Timestamp::now(clock);
 
         // First step, just propagate the initial conditions
         if i == 0 {
-            self.output.update(t, &self);
+            self.update_output(t);
             return Ok(StepResult::Continue);
         }
 
@@ -318,12 +448,28 @@ impl Node for Rocket {
             state: self.state.clone(),
         };
 
+        let t_s = t.monotonic.elapsed_seconds_f64();
+        let mut ejected = vec![];
+
         while let Ok(ev) = self.rx_sim_event.try_recv() {
+            ejected.extend(self.ejections.on_sim_event(&ev.1));
             self.fsm
                 .handle_with_context(&Event::Sim(ev.1), &mut fsm_ctx);
         }
+        while let Ok(ev) = self.rx_gnc_event.try_recv() {
+            ejected.extend(self.ejections.on_gnc_event(&ev.1.event));
+            self.fsm.handle_with_context(&ev.1.into(), &mut fsm_ctx);
+        }
+        while let Ok(Timestamped(_, pyro)) = self.rx_pyro.try_recv() {
+            self.fsm
+                .handle_with_context(&Event::Pyro(pyro.channel), &mut fsm_ctx);
+        }
         self.fsm.handle_with_context(&Event::Step, &mut fsm_ctx);
 
+        for ejection in &ejected {
+            self.eject(t_s, ejection)?;
+        }
+
         let servo_pos = if let Ok(Timestamped(_, servo_pos)) = self.rx_servo_pos.try_recv() {
             servo_pos
         } else {
@@ -332,19 +478,26 @@ impl Node for Rocket {
 
         self.step_state.servo_pos = servo_pos;
 
-        let next = RungeKutta4.solve(
-            self,
-            t.monotonic.elapsed_seconds_f64(),
-            TD(dt).seconds(),
-            self.state.0,
-        );
+        // The airbrakes hold their extension if no update is received
+        while let Ok(Timestamped(_, airbrake_pos)) = self.rx_airbrake_pos.try_recv() {
+            self.step_state.airbrake_extension = airbrake_pos.extension;
+        }
 
-        self.state.0 = next;
+        let dt_s = TD(dt).seconds();
 
-        // Normalize quaternion agains numerical errors
-        self.state.normalize_quat();
+        match self.params.attitude_integrator {
+            AttitudeIntegrator::Quaternion => {
+                self.state.0 = RungeKutta4.solve(self, t_s, dt_s, self.state.0);
 
-        self.output.update(t, &self);
+                // Normalize quaternion agains numerical errors
+                self.state.normalize_quat();
+            }
+            AttitudeIntegrator::Exponential => {
+                self.state.0 = MuntheKaas4.solve(self, t_s, dt_s, self.state.0);
+            }
+        }
+
+        self.update_output(t);
 
         // Stop conditions
         if (self.state.pos_n_m()[2] > 0.0 && t.monotonic.elapsed_seconds_f64() > 1.0)
@@ -357,10 +510,19 @@ impl Node for Rocket {
     }
 }
 
+/// Distance travelled along the rail for the liftoff to be detected
+const LIFTOFF_TRAVEL_M: f64 = 0.2;
+
 pub struct RocketFsm {
     tx_gnc_event: TelemetrySender<GncEventItem>,
     tx_sim_event: TelemetrySender<SimEvent>,
     ignition_time: Option<Timestamp>,
+    drogue_deploy_time: Option<Timestamp>,
+    main_deploy_time: Option<Timestamp>,
+
+    backup_deploy: bool,
+    main_deploy_alt_m: f64,
+    rail: LaunchRail,
 }
 
 pub struct RocketFsmContext {
@@ -373,11 +535,18 @@ impl RocketFsm {
     fn new(
         tx_gnc_event: TelemetrySender<GncEventItem>,
         tx_sim_event: TelemetrySender<SimEvent>,
+        recovery: &Recovery,
+        rail: LaunchRail,
     ) -> Self {
         RocketFsm {
             tx_gnc_event,
             tx_sim_event,
             ignition_time: None,
+            drogue_deploy_time: None,
+            main_deploy_time: None,
+            backup_deploy: recovery.backup_deploy,
+            main_deploy_alt_m: recovery.main_deploy_alt_m,
+            rail,
         }
     }
 
@@ -388,6 +557,16 @@ impl RocketFsm {
             0.0
         }
     }
+
+    pub fn t_from_drogue(&self, t: f64) -> Option<f64> {
+        self.drogue_deploy_time
+            .map(|t_deploy| t - t_deploy.monotonic.elapsed_seconds_f64())
+    }
+
+    pub fn t_from_main(&self, t: f64) -> Option<f64> {
+        self.main_deploy_time
+            .map(|t_deploy| t - t_deploy.monotonic.elapsed_seconds_f64())
+    }
 }
 
 #[state_machine(
@@ -411,10 +590,10 @@ impl RocketFsm {
     }
 
     #[state(entry_action = "enter_lifting_off")]
-    fn lifting_off(context: &mut RocketFsmContext, event: &Event) -> Response<State> {
+    fn lifting_off(&mut self, context: &mut RocketFsmContext, event: &Event) -> Response<State> {
         match event {
             Event::Step => {
-                if context.state.pos_n_m()[2] < -0.2 {
+                if self.rail.travel_m(&context.state.pos_n_m()) > LIFTOFF_TRAVEL_M {
                     Transition(State::flying_ramp())
                 } else {
                     Handled
@@ -436,11 +615,16 @@ impl RocketFsm {
     }
 
     #[state(entry_action = "enter_flying_ramp")]
-    fn flying_ramp(context: &mut RocketFsmContext, event: &Event) -> Response<State> {
+    fn flying_ramp(&mut self, context: &mut RocketFsmContext, event: &Event) -> Response<State> {
         match event {
             Event::Step => {
-                // TODO: Better ramp exit condition check
-                if context.state.pos_n_m()[2] < -2.0 {
+                if self.rail.cleared(&context.state.pos_n_m()) {
+                    self.tx_sim_event.send(
+                        context.time,
+                        SimEvent::RailExit {
+                            velocity_m_s: context.state.vel_n_m_s().norm(),
+                        },
+                    );
                     Transition(State::flying_free())
                 } else {
                     Handled
@@ -451,7 +635,43 @@ impl RocketFsm {
     }
 
     #[state]
-    fn flying_free(event: &Event) -> Response<State> {
+    fn flying_free(&mut self, context: &mut RocketFsmContext, event: &Event) -> Response<State> {
+        match event {
+            Event::Pyro(PyroChannel::Drogue) => Transition(State::descent_drogue()),
+            Event::Pyro(PyroChannel::Main) => Transition(State::descent_main()),
+            // Backup apogee detection: vertical velocity is positive down
+            Event::Step if self.backup_deploy && context.state.vel_n_m_s()[2] > 0.0 => {
+                Transition(State::descent_drogue())
+            }
+            _ => Super,
+        }
+    }
+
+    #[action]
+    fn enter_descent_drogue(&mut self, context: &mut RocketFsmContext) {
+        self.drogue_deploy_time = Some(context.time);
+    }
+
+    #[state(entry_action = "enter_descent_drogue")]
+    fn descent_drogue(&mut self, context: &mut RocketFsmContext, event: &Event) -> Response<State> {
+        match event {
+            Event::Pyro(PyroChannel::Main) => Transition(State::descent_main()),
+            Event::Step
+                if self.backup_deploy && -context.state.pos_n_m()[2] < self.main_deploy_alt_m =>
+            {
+                Transition(State::descent_main())
+            }
+            _ => Super,
+        }
+    }
+
+    #[action]
+    fn enter_descent_main(&mut self, context: &mut RocketFsmContext) {
+        self.main_deploy_time = Some(context.time);
+    }
+
+    #[state(entry_action = "enter_descent_main")]
+    fn descent_main(event: &Event) -> Response<State> {
         match event {
             _ => Super,
         }
@@ -1,7 +1,7 @@
 use core::f64;
 
-use anyhow::Result;
-use nalgebra::{Matrix3, Quaternion, SVector, UnitQuaternion, Vector3, Vector4, vector};
+use anyhow::{Result, anyhow};
+use nalgebra::{Matrix3, Quaternion, SVector, UnitQuaternion, Vector3, Vector4};
 
 use crate::{crater::aero::aerodynamics::AerodynamicActions, parameters::ParameterMap};
 
@@ -81,6 +81,8 @@ pub struct RocketActions {
 
     pub aero_actions: AerodynamicActions,
 
+    pub parachute_force_n_n: Vector3<f64>,
+
     pub tot_force_n_n: Vector3<f64>,
     pub tot_force_b_n: Vector3<f64>,
     pub tot_moment_b_nm: Vector3<f64>,
@@ -93,6 +95,16 @@ pub struct RocketAccelerations {
     pub ang_acc_b_rad_s2: Vector3<f64>, // Angular acceleration
 }
 
+/// Integration of the attitude quaternion
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttitudeIntegrator {
+    /// Integrated as a 4-vector with the rest of the state, then renormalized
+    Quaternion,
+    /// Propagated on SO(3) through the exponential map, without drifting off the unit norm.
+    /// Preferable for long coast & descent phases.
+    Exponential,
+}
+
 #[derive(Debug, Clone)]
 pub struct RocketParams {
     pub mass_body_kg: f64,
@@ -100,7 +112,6 @@ pub struct RocketParams {
     pub datcom_ref_pos_m: Vector3<f64>,
     pub xcg_body_m: Vector3<f64>,
     pub engine_ref_pos_m: Vector3<f64>,
-    pub origin_geo: Vector3<f64>,
     pub p0_n: Vector3<f64>,
     pub v0_b: Vector3<f64>,
     pub w0_b: Vector3<f64>,
@@ -110,7 +121,7 @@ pub struct RocketParams {
     pub max_t: f64,
     pub azimuth: f64,
     pub elevation: f64,
-    pub ramp_versor: Vector3<f64>,
+    pub attitude_integrator: AttitudeIntegrator,
 
     pub disturb_const_force_b: Vector3<f64>,
     pub disturb_const_torque_b: Vector3<f64>,
@@ -118,60 +129,21 @@ pub struct RocketParams {
 
 impl RocketParams {
     pub fn from_params(params: &ParameterMap) -> Result<Self> {
-        let inertia = params.get_param("inertia_empty")?.value_float_arr()?;
-
-        let inertia_empty = Matrix3::from_column_slice(&inertia);
-
-        let datcom_ref_pos = params.get_param("datcom_ref_pos")?.value_float_arr()?;
-        let datcom_ref_pos = Vector3::from_column_slice(&datcom_ref_pos);
-
-        let xcg_body = params.get_param("xcg_body")?.value_float_arr()?;
-        let xcg_body = Vector3::from_column_slice(&xcg_body);
-
-        let engine_ref_pos = params.get_param("engine_ref_pos")?.value_float_arr()?;
-        let engine_ref_pos = Vector3::from_column_slice(&engine_ref_pos);
+        let inertia_empty = params.get_matrix3("inertia_empty")?;
+        let datcom_ref_pos = params.get_vector3("datcom_ref_pos")?;
+        let xcg_body = params.get_vector3("xcg_body")?;
+        let engine_ref_pos = params.get_vector3("engine_ref_pos")?;
 
         let diameter = params.get_param("diameter")?.value_randfloat()?.sampled();
         let surface = f64::consts::PI * (diameter / 2.0).powf(2.0);
 
-        let orig_lat = params
-            .get_param("init.latitude")?
-            .value_float()?
-            .to_radians();
-        let orig_lon = params
-            .get_param("init.longitude")?
-            .value_float()?
-            .to_radians();
-        let orig_alt = params.get_param("init.altitude")?.value_float()?;
-
-        let origin_geo = Vector3::new(orig_lat, orig_lon, orig_alt);
-
-        let p0_n = params.get_param("init.p0_n")?.value_float_arr()?;
-        let p0_n = Vector3::from_column_slice(&p0_n);
-
-        let v0_b = params.get_param("init.v0_b")?.value_float_arr()?;
-        let v0_b = Vector3::from_column_slice(&v0_b);
-
-        let mut w0_b = params
-            .get_param("init.w0_b_deg")?
-            .value_float_arr()?
-            .to_owned();
-        w0_b.iter_mut().for_each(|w| *w = w.to_radians());
-
-        let w0_b = Vector3::from_column_slice(&w0_b);
-
-        let g_n = params.get_param("g_n")?.value_float_arr()?;
-        let g_n = Vector3::from_column_slice(&g_n);
-
-        let disturb_const_force_b = params
-            .get_param("disturbances.const_force_b")?
-            .value_float_arr()?;
-        let disturb_const_force_b = Vector3::from_column_slice(&disturb_const_force_b);
+        let p0_n = params.get_vector3("init.p0_n")?;
+        let v0_b = params.get_vector3("init.v0_b")?;
+        let w0_b = params.get_vector3("init.w0_b_deg")?.map(|w| w.to_radians());
+        let g_n = params.get_vector3("g_n")?;
 
-        let disturb_const_torque_b = params
-            .get_param("disturbances.const_torque_b")?
-            .value_float_arr()?;
-        let disturb_const_torque_b = Vector3::from_column_slice(&disturb_const_torque_b);
+        let disturb_const_force_b = params.get_vector3("disturbances.const_force_b")?;
+        let disturb_const_torque_b = params.get_vector3("disturbances.const_torque_b")?;
 
         let azimuth = params
             .get_param("init.azimuth")?
@@ -185,9 +157,15 @@ impl RocketParams {
             .sampled()
             .to_radians();
 
-        let q_nb = UnitQuaternion::from_euler_angles(0.0, elevation, azimuth);
-        let mut pad_versor_n = q_nb.transform_vector(&vector![1.0, 0.0, 0.0]);
-        pad_versor_n.normalize_mut();
+        let attitude_integrator = match params
+            .get_param("attitude_integrator")?
+            .value_string()?
+            .as_str()
+        {
+            "quaternion" => AttitudeIntegrator::Quaternion,
+            "exponential" => AttitudeIntegrator::Exponential,
+            unknown => return Err(anyhow!("Unknown attitude integrator: {unknown}")),
+        };
 
         Ok(RocketParams {
             mass_body_kg: params.get_param("mass")?.value_randfloat()?.sampled(),
@@ -195,7 +173,6 @@ impl RocketParams {
             datcom_ref_pos_m: datcom_ref_pos,
             xcg_body_m: xcg_body,
             engine_ref_pos_m: engine_ref_pos,
-            origin_geo,
             p0_n,
             v0_b,
             w0_b,
@@ -205,7 +182,7 @@ impl RocketParams {
             max_t: params.get_param("max_t")?.value_float()?,
             azimuth,
             elevation,
-            ramp_versor: pad_versor_n,
+            attitude_integrator,
             disturb_const_force_b,
             disturb_const_torque_b,
         })
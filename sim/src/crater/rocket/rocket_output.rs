@@ -1,7 +1,15 @@
 use crate::{
     core::time::Timestamp,
-    crater::{aero::aerodynamics::AeroState, channels, engine::engine::RocketEngineMassProperties},
+    crater::{
+        aero::{
+            aerodynamics::AeroState,
+            stability::{StabilitySample, center_of_pressure},
+        },
+        channels,
+        engine::engine::RocketEngineMassProperties,
+    },
     nodes::NodeTelemetry,
+    parameters::ParameterMap,
     telemetry::TelemetrySender,
 };
 
@@ -10,6 +18,7 @@ use crater_gnc::datatypes::gnc::NavigationOutput;
 
 use super::{
     mass::RocketMassProperties,
+    reference_point::ReferencePoint,
     rocket::{Rocket, RocketOdeStep},
     rocket_data::{RocketAccelerations, RocketActions, RocketState},
 };
@@ -23,10 +32,15 @@ pub struct RocketOutput {
     snd_rocket_mass: TelemetrySender<RocketMassProperties>,
     snd_engine_mass: TelemetrySender<RocketEngineMassProperties>,
     snd_ideal_nav: TelemetrySender<NavigationOutput>,
+    snd_stability: TelemetrySender<StabilitySample>,
+
+    snd_state_ref: TelemetrySender<RocketState>,
+    snd_accels_ref: TelemetrySender<RocketAccelerations>,
+    reference_point: ReferencePoint,
 }
 
 impl RocketOutput {
-    pub fn new(telemetry: &NodeTelemetry) -> Result<Self> {
+    pub fn new(telemetry: &NodeTelemetry, params: &ParameterMap) -> Result<Self> {
         Ok(Self {
             snd_state: telemetry.publish(channels::rocket::STATE)?,
             snd_actions: telemetry.publish(channels::rocket::ACTIONS)?,
@@ -35,29 +49,64 @@ impl RocketOutput {
             snd_rocket_mass: telemetry.publish(channels::rocket::MASS_ROCKET)?,
             snd_engine_mass: telemetry.publish(channels::rocket::MASS_ENGINE)?,
             snd_ideal_nav: telemetry.publish(channels::sensors::IDEAL_NAV_OUTPUT)?,
+            snd_stability: telemetry.publish(channels::rocket::STABILITY)?,
+            snd_state_ref: telemetry.publish(channels::rocket::STATE_REF)?,
+            snd_accels_ref: telemetry.publish(channels::rocket::ACCEL_REF)?,
+            reference_point: ReferencePoint::from_params(params)?,
         })
     }
 
-    /// Updates outputs from the results of the latest step
-    pub fn update(&self, t: Timestamp, rocket: &Rocket) {
+    /// Updates outputs from the results of the latest step.
+    /// The CG state is always published on the base channels, as sensor models need it to
+    /// compute lever arms. The ideal navigation output is expressed at the reference point.
+    /// Returns the stability of the rocket, if the aerodynamic model allows computing its CP.
+    pub fn update(&self, t: Timestamp, rocket: &Rocket) -> Option<StabilitySample> {
         self.snd_state.send(t, rocket.state.clone());
         let t_s = t.monotonic.elapsed_seconds_f64();
 
         let ode_output = RocketOdeStep::calc(rocket, t_s, rocket.state.clone());
 
+        let (state_ref, accels_ref) = self.reference_point.transform(
+            &rocket.state,
+            &ode_output.accels,
+            &ode_output.mass_rocket.xcg_total_m,
+        );
+
         let navout = NavigationOutput {
-            pos_n_m: rocket.state.pos_n_m().cast::<f32>(),
-            vel_n_m_s: rocket.state.vel_n_m_s().cast::<f32>(),
-            quat_nb: rocket.state.quat_nb().cast::<f32>(),
-            acc_unbias_b_m_s2: ode_output.accels.acc_b_m_s2.cast::<f32>(),
-            angvel_unbias_b_rad_s: rocket.state.angvel_b_rad_s().cast::<f32>(),
+            pos_n_m: state_ref.pos_n_m().cast::<f32>(),
+            vel_n_m_s: state_ref.vel_n_m_s().cast::<f32>(),
+            quat_nb: state_ref.quat_nb().cast::<f32>(),
+            acc_unbias_b_m_s2: accels_ref.acc_b_m_s2.cast::<f32>(),
+            angvel_unbias_b_rad_s: state_ref.angvel_b_rad_s().cast::<f32>(),
         };
 
+        self.snd_state_ref.send(t, state_ref);
+        self.snd_accels_ref.send(t, accels_ref);
+
+        let stability = center_of_pressure(
+            rocket.aero_coeffs.as_ref(),
+            &ode_output.aero_state,
+            rocket.params.datcom_ref_pos_m[0],
+            rocket.params.diameter,
+        )
+        .map(|x_cp_m| {
+            StabilitySample::new(
+                x_cp_m,
+                ode_output.mass_rocket.xcg_total_m[0],
+                rocket.params.diameter,
+            )
+        });
+        if let Some(stability) = &stability {
+            self.snd_stability.send(t, stability.clone());
+        }
+
         self.snd_ideal_nav.send(t, navout);
         self.snd_actions.send(t, ode_output.actions);
         self.snd_accels.send(t, ode_output.accels);
         self.snd_aerostate.send(t, ode_output.aero_state);
         self.snd_rocket_mass.send(t, ode_output.mass_rocket);
         self.snd_engine_mass.send(t, ode_output.mass_engine);
+
+        stability
     }
 }
@@ -0,0 +1,170 @@
+use std::collections::VecDeque;
+
+use anyhow::{Result, anyhow};
+use chrono::TimeDelta;
+use crater_gnc::datatypes::sensors::PressureSensorSample;
+use rand::Rng;
+use rand_distr::StandardNormal;
+use rand_xoshiro::Xoshiro256StarStar;
+
+use crate::{
+    core::time::{Clock, Timestamp},
+    crater::{
+        aero::{
+            aerodynamics::AeroState,
+            atmosphere::{Atmosphere, atmosphere_from_params},
+        },
+        channels,
+        sensors::{failures::SensorFailures, output::SensorOutputStage},
+    },
+    math::interp::{find_index, interpolate},
+    nodes::{Node, NodeContext, StepResult},
+    parameters::ParameterMap,
+    telemetry::{TelemetryReceiver, TelemetrySender, Timestamped},
+    utils::capacity::Capacity::Unbounded,
+};
+
+#[derive(Debug)]
+struct BarometerParams {
+    sample_period_s: f64,
+    delay_s: f64,
+    noise_std_pa: f64,
+    /// Bias random walk intensity [Pa/√s]
+    bias_drift_pa: f64,
+
+    /// Static port pressure coefficient as a function of the Mach number: the pressure at the
+    /// port is the freestream pressure plus `cp * q`
+    position_error_mach: Vec<f64>,
+    position_error_cp: Vec<f64>,
+}
+
+impl BarometerParams {
+    fn from_params(params: &ParameterMap) -> Result<Self> {
+        let position_error_mach = params
+            .get_param("position_error_mach")?
+            .value_float_arr()?
+            .to_vec();
+        let position_error_cp = params
+            .get_param("position_error_cp")?
+            .value_float_arr()?
+            .to_vec();
+
+        if position_error_mach.is_empty() || position_error_mach.len() != position_error_cp.len()
+        {
+            return Err(anyhow!(
+                "Barometer position error table must be non-empty and have as many values as Mach breakpoints"
+            ));
+        }
+
+        Ok(Self {
+            sample_period_s: 1.0 / params.get_param("rate")?.value_float()?,
+            delay_s: params.get_param("delay")?.value_float()?,
+            noise_std_pa: params.get_param("noise_std")?.value_float()?,
+            bias_drift_pa: params.get_param("bias_drift")?.value_float()?,
+            position_error_mach,
+            position_error_cp,
+        })
+    }
+}
+
+/// Static pressure sensor with noise, drifting bias, a finite sampling rate, transport delay and
+/// the quantization & full scale of its output stage. The pressure is measured at the static
+/// port, where it differs from the freestream pressure depending on the Mach number.
+#[derive(Debug)]
+pub struct StaticPressureSensor {
+    rx_aero: TelemetryReceiver<AeroState>,
+    tx_pressure: TelemetrySender<PressureSensorSample>,
+
+    params: BarometerParams,
+    failures: SensorFailures,
+    output: SensorOutputStage,
+    atmosphere: Box<dyn Atmosphere + Send>,
+    rng: Xoshiro256StarStar,
+
+    bias_pa: f64,
+    next_sample_s: f64,
+    /// Samples waiting for their delivery time
+    in_flight: VecDeque<(f64, PressureSensorSample)>,
+}
+
+impl StaticPressureSensor {
+    pub fn new(ctx: NodeContext) -> Result<Self> {
+        let rx_aero = ctx
+            .telemetry()
+            .subscribe(channels::rocket::AERO_STATE, Unbounded)?;
+        let tx_pressure = ctx
+            .telemetry()
+            .publish(channels::sensors::STATIC_PRESSURE)?;
+
+        let baro_params = ctx.parameters().get_map("sim.rocket.barometer")?;
+
+        Ok(Self {
+            rx_aero,
+            tx_pressure,
+            params: BarometerParams::from_params(baro_params)?,
+            failures: SensorFailures::from_params(baro_params, "pressure")?,
+            output: SensorOutputStage::from_params(&ctx, baro_params, "pressure")?,
+            atmosphere: atmosphere_from_params(ctx.parameters().get_map("sim.atmosphere")?)?,
+            rng: ctx.get_rng_256(),
+            bias_pa: baro_params.get_param("bias")?.value_randfloat()?.sampled(),
+            next_sample_s: 0.0,
+            in_flight: VecDeque::new(),
+        })
+    }
+
+    fn measure(&mut self, aero: &AeroState) -> f64 {
+        let p = &self.params;
+
+        let cp = interpolate(
+            &p.position_error_cp,
+            find_index(&p.position_error_mach, aero.mach),
+        )
+        .0;
+        let q_pa = 0.5 * aero.air_density_kg_m3 * aero.v_air_norm_m_s.powi(2);
+
+        let noise: f64 = self.rng.sample(StandardNormal);
+        self.atmosphere.pressure_pa(aero.altitude_m)
+            + cp * q_pa
+            + self.bias_pa
+            + p.noise_std_pa * noise
+    }
+}
+
+impl Node for StaticPressureSensor {
+    fn step(&mut self, _: usize, dt: TimeDelta, clock: &dyn Clock) -> Result<StepResult> {
+        let Timestamped(_, aero) = self
+            .rx_aero
+            .try_recv()
+            .expect("Barometer step executed, but no /rocket/aerostate input available");
+
+        let t = Timestamp::now(clock);
+        let t_s = t.monotonic.elapsed_seconds_f64();
+
+        let dt_s = dt.num_microseconds().unwrap() as f64 / 1e6;
+        let drift: f64 = self.rng.sample(StandardNormal);
+        self.bias_pa += self.params.bias_drift_pa * dt_s.sqrt() * drift;
+
+        if t_s >= self.next_sample_s {
+            self.next_sample_s += self.params.sample_period_s;
+
+            let mut pressure_pa = [self.measure(&aero)];
+            if self.failures.apply(t_s, &mut pressure_pa) {
+                self.output.apply(&mut pressure_pa);
+                let sample = PressureSensorSample {
+                    pressure_pa: pressure_pa[0] as f32,
+                    temperature_degc: Some(
+                        (self.atmosphere.temperature_k(aero.altitude_m) - 273.15) as f32,
+                    ),
+                };
+                self.in_flight.push_back((t_s + self.params.delay_s, sample));
+            }
+        }
+
+        while self.in_flight.front().is_some_and(|(t_out, _)| *t_out <= t_s) {
+            let (_, sample) = self.in_flight.pop_front().unwrap();
+            self.tx_pressure.send(t, sample);
+        }
+
+        Ok(StepResult::Continue)
+    }
+}
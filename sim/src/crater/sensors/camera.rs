@@ -0,0 +1,205 @@
+use anyhow::Result;
+use chrono::TimeDelta;
+use nalgebra::{UnitQuaternion, Vector3};
+
+use crate::{
+    core::time::{Clock, Timestamp},
+    crater::{channels, rocket::rocket_data::RocketState},
+    nodes::{Node, NodeContext, StepResult},
+    parameters::ParameterMap,
+    telemetry::{TelemetryReceiver, TelemetrySender, Timestamped},
+    utils::capacity::Capacity::Unbounded,
+};
+
+/// Where the ground target appears in the camera image
+#[derive(Debug, Clone, Default)]
+pub struct CameraPointing {
+    /// Target inside the field of view
+    pub in_view: bool,
+    /// Angle between the boresight and the line of sight to the target [rad]
+    pub off_axis_rad: f64,
+    /// Target position in the image, as angles from the boresight towards the image y (right)
+    /// and z (down) axes [rad]
+    pub image_h_rad: f64,
+    pub image_v_rad: f64,
+    pub range_m: f64,
+
+    /// Boresight direction, NED
+    pub boresight_n: Vector3<f64>,
+    /// Intersection of the boresight with the ground plane of the target, if the camera looks
+    /// below the horizon
+    pub ground_point_n: Option<Vector3<f64>>,
+
+    /// Total time with the target in view since the start of the simulation [s]
+    pub time_in_view_s: f64,
+}
+
+#[derive(Debug, Clone)]
+struct CameraParams {
+    quat_cam_b: UnitQuaternion<f64>,
+    half_fov_h_rad: f64,
+    half_fov_v_rad: f64,
+    target_n: Vector3<f64>,
+}
+
+impl CameraParams {
+    fn from_params(params: &ParameterMap) -> Result<Self> {
+        Ok(Self {
+            quat_cam_b: params.get_quaternion("quat_cam_b")?,
+            half_fov_h_rad: params.get_param("fov_h")?.value_float()?.to_radians() / 2.0,
+            half_fov_v_rad: params.get_param("fov_v")?.value_float()?.to_radians() / 2.0,
+            target_n: params.get_vector3("target_n")?,
+        })
+    }
+
+    /// Pointing of the camera towards the target, from the rocket position & attitude
+    fn pointing(&self, pos_n: &Vector3<f64>, quat_nb: &UnitQuaternion<f64>) -> CameraPointing {
+        let boresight_n =
+            quat_nb.transform_vector(&self.quat_cam_b.inverse_transform_vector(&Vector3::x()));
+
+        // Ground plane at the target altitude (NED, z down)
+        let height_m = self.target_n.z - pos_n.z;
+        let ground_point_n = (boresight_n.z > 0.0 && height_m > 0.0)
+            .then(|| pos_n + boresight_n * (height_m / boresight_n.z));
+
+        let los_n = self.target_n - pos_n;
+        let range_m = los_n.norm();
+        if range_m < f64::EPSILON {
+            // Camera on the target: no line of sight
+            return CameraPointing {
+                boresight_n,
+                ground_point_n,
+                ..Default::default()
+            };
+        }
+
+        let los_c = self
+            .quat_cam_b
+            .transform_vector(&quat_nb.inverse_transform_vector(&los_n));
+
+        let off_axis_rad = los_c.yz().norm().atan2(los_c.x);
+        let image_h_rad = los_c.y.atan2(los_c.x);
+        let image_v_rad = los_c.z.atan2(los_c.x);
+        let in_view = los_c.x > 0.0
+            && image_h_rad.abs() <= self.half_fov_h_rad
+            && image_v_rad.abs() <= self.half_fov_v_rad;
+
+        CameraPointing {
+            in_view,
+            off_axis_rad,
+            image_h_rad,
+            image_v_rad,
+            range_m,
+            boresight_n,
+            ground_point_n,
+            time_in_view_s: 0.0,
+        }
+    }
+}
+
+/// Body-mounted camera: computes whether the ground target (by default the launch pad) is in the
+/// field of view, to help placing the payload cameras
+#[derive(Debug)]
+pub struct Camera {
+    rx_state: TelemetryReceiver<RocketState>,
+    tx_pointing: TelemetrySender<CameraPointing>,
+
+    params: CameraParams,
+    time_in_view_s: f64,
+}
+
+impl Camera {
+    pub fn new(ctx: NodeContext) -> Result<Self> {
+        let rx_state = ctx
+            .telemetry()
+            .subscribe(channels::rocket::STATE, Unbounded)?;
+        let tx_pointing = ctx
+            .telemetry()
+            .publish(channels::sensors::CAMERA_POINTING)?;
+
+        Ok(Self {
+            rx_state,
+            tx_pointing,
+            params: CameraParams::from_params(ctx.parameters().get_map("sim.rocket.camera")?)?,
+            time_in_view_s: 0.0,
+        })
+    }
+}
+
+impl Node for Camera {
+    fn step(&mut self, _: usize, dt: TimeDelta, clock: &dyn Clock) -> Result<StepResult> {
+        let Timestamped(_, state) = self
+            .rx_state
+            .try_recv()
+            .expect("Camera step executed, but no /rocket/state input available");
+
+        let mut pointing = self.params.pointing(&state.pos_n_m(), &state.quat_nb());
+
+        if pointing.in_view {
+            self.time_in_view_s += dt.num_microseconds().unwrap() as f64 / 1e6;
+        }
+        pointing.time_in_view_s = self.time_in_view_s;
+
+        self.tx_pointing.send(Timestamp::now(clock), pointing);
+
+        Ok(StepResult::Continue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    fn params() -> CameraParams {
+        CameraParams {
+            // Looking aft
+            quat_cam_b: UnitQuaternion::from_axis_angle(&Vector3::z_axis(), std::f64::consts::PI),
+            half_fov_h_rad: 45f64.to_radians(),
+            half_fov_v_rad: 30f64.to_radians(),
+            target_n: Vector3::zeros(),
+        }
+    }
+
+    #[test]
+    fn test_pointing() {
+        let params = params();
+
+        // Nose up, 100 m above the pad: the pad is straight along the boresight
+        let nose_up = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), FRAC_PI_2);
+        let pos_n = Vector3::new(0.0, 0.0, -100.0);
+
+        let pointing = params.pointing(&pos_n, &nose_up);
+        assert!(pointing.in_view);
+        assert_relative_eq!(pointing.off_axis_rad, 0.0, epsilon = 1e-9);
+        assert_relative_eq!(pointing.range_m, 100.0, epsilon = 1e-9);
+        assert_relative_eq!(pointing.boresight_n, Vector3::z(), epsilon = 1e-9);
+        assert_relative_eq!(
+            pointing.ground_point_n.unwrap(),
+            Vector3::zeros(),
+            epsilon = 1e-9
+        );
+
+        // Drifted 100 m north: the pad is 45° off the boresight, along the image vertical axis,
+        // outside of the field of view
+        let pos_n = Vector3::new(100.0, 0.0, -100.0);
+        let pointing = params.pointing(&pos_n, &nose_up);
+        assert!(!pointing.in_view);
+        assert_relative_eq!(pointing.off_axis_rad, 45f64.to_radians(), epsilon = 1e-9);
+        assert_relative_eq!(pointing.image_h_rad, 0.0, epsilon = 1e-9);
+        assert_relative_eq!(
+            pointing.image_v_rad.abs(),
+            45f64.to_radians(),
+            epsilon = 1e-9
+        );
+
+        // Nose down: the camera looks at the sky
+        let nose_down = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), -FRAC_PI_2);
+        let pointing = params.pointing(&pos_n, &nose_down);
+        assert!(!pointing.in_view);
+        assert!(pointing.ground_point_n.is_none());
+    }
+}
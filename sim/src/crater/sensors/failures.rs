@@ -0,0 +1,253 @@
+use anyhow::{Result, anyhow};
+
+use crate::{
+    crater::{channels, events::SimEvent},
+    nodes::NodeContext,
+    parameters::{ParameterMap, ParameterTree},
+    telemetry::{TelemetryReceiver, Timestamped},
+    utils::capacity::Capacity::Unbounded,
+};
+
+/// Behavior of a failed sensor axis
+#[derive(Debug, Clone, PartialEq)]
+pub enum FailureKind {
+    /// Output stuck at a constant value
+    Constant { value: f64 },
+    /// Bias growing linearly from the failure onset
+    RampDrift { rate_per_s: f64 },
+    /// No sample is produced for `duration_s` at the beginning of every `period_s`. Affects the
+    /// whole sample, regardless of the configured axes.
+    PeriodicDropout { period_s: f64, duration_s: f64 },
+    /// Output rounded to a multiple of `step`
+    Quantization { step: f64 },
+    /// The axis outputs the true value of another axis
+    AxisSwap { source_axis: usize },
+}
+
+/// A failure affecting some axes of a sensor signal, starting at `t_start_s`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FailureMode {
+    pub kind: FailureKind,
+    pub axes: Vec<usize>,
+    pub t_start_s: f64,
+}
+
+impl FailureMode {
+    /// Reads a failure mode from its parameter map:
+    ///
+    /// ```toml
+    /// [sim.rocket.imu.failures.gyro.stuck_z]
+    /// mode = { val = "constant", type = "str" }
+    /// axes = { val = [2], type = "int[]" }
+    /// t_start = { val = 5.0, type = "float" }
+    /// value = { val = 0.0, type = "float" }
+    /// ```
+    pub fn from_params(params: &ParameterMap) -> Result<Self> {
+        let float = |name: &str| -> Result<f64> { Ok(params.get_param(name)?.value_float()?) };
+
+        let kind = match params.get_param("mode")?.value_string()?.as_str() {
+            "constant" => FailureKind::Constant {
+                value: float("value")?,
+            },
+            "ramp" => FailureKind::RampDrift {
+                rate_per_s: float("rate")?,
+            },
+            "dropout" => FailureKind::PeriodicDropout {
+                period_s: float("period")?,
+                duration_s: float("duration")?,
+            },
+            "quantization" => FailureKind::Quantization {
+                step: float("step")?,
+            },
+            "swap" => FailureKind::AxisSwap {
+                source_axis: usize::try_from(params.get_param("source_axis")?.value_int()?)?,
+            },
+            unknown => return Err(anyhow!("Unknown sensor failure mode '{unknown}'")),
+        };
+
+        let axes = params
+            .get_param("axes")?
+            .value_int_arr()?
+            .iter()
+            .map(|&a| usize::try_from(a))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            kind,
+            axes,
+            t_start_s: float("t_start")?,
+        })
+    }
+}
+
+/// Failure modes applied to a sensor signal, eg. the accelerometer or the gyroscope readings of
+/// an IMU. Any number of modes can be composed, each on its own subset of axes: they are applied
+/// in order to the ideal measurement.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SensorFailures {
+    modes: Vec<FailureMode>,
+}
+
+impl SensorFailures {
+    pub fn new(modes: Vec<FailureMode>) -> Self {
+        Self { modes }
+    }
+
+    /// Reads the failure modes of `signal` from the `failures` map of a sensor, if present.
+    /// Each entry of `failures.<signal>` is a failure mode, applied in alphabetical order.
+    pub fn from_params(sensor_params: &ParameterMap, signal: &str) -> Result<Self> {
+        if !sensor_params.contains_key("failures") {
+            return Ok(Self::default());
+        }
+
+        let failures = sensor_params.get_map("failures")?;
+        if !failures.contains_key(signal) {
+            return Ok(Self::default());
+        }
+
+        let mut modes = vec![];
+        for (_, tree) in failures.get_map(signal)?.iter() {
+            if let ParameterTree::Node(mode) = tree {
+                modes.push(FailureMode::from_params(mode)?);
+            }
+        }
+
+        Ok(Self { modes })
+    }
+
+    /// Applies the active failure modes to a measurement, at simulation time `t_s`.
+    /// Returns false if the sample is dropped.
+    pub fn apply(&self, t_s: f64, values: &mut [f64]) -> bool {
+        // Axis swaps read the true values, so that swapping two axes does not depend on the
+        // order in which the modes are applied
+        let ideal = values.to_vec();
+
+        for mode in self.modes.iter().filter(|m| t_s >= m.t_start_s) {
+            let dt = t_s - mode.t_start_s;
+
+            if let FailureKind::PeriodicDropout {
+                period_s,
+                duration_s,
+            } = mode.kind
+            {
+                if period_s <= 0.0 || dt % period_s < duration_s {
+                    return false;
+                }
+                continue;
+            }
+
+            for &axis in mode.axes.iter().filter(|&&a| a < values.len()) {
+                values[axis] = match mode.kind {
+                    FailureKind::Constant { value } => value,
+                    FailureKind::RampDrift { rate_per_s } => values[axis] + rate_per_s * dt,
+                    FailureKind::Quantization { step } if step > 0.0 => {
+                        (values[axis] / step).round() * step
+                    }
+                    FailureKind::AxisSwap { source_axis } if source_axis < ideal.len() => {
+                        ideal[source_axis]
+                    }
+                    _ => values[axis],
+                };
+            }
+        }
+
+        true
+    }
+}
+
+/// Sample dropouts of a sensor injected at runtime by `SensorDropout` sim events, eg. from a
+/// scenario file
+#[derive(Debug)]
+pub struct ScriptedDropout {
+    sensor: &'static str,
+    rx_sim_event: TelemetryReceiver<SimEvent>,
+    /// End of the latest dropout [s]
+    until_s: f64,
+}
+
+impl ScriptedDropout {
+    pub fn new(ctx: &NodeContext, sensor: &'static str) -> Result<Self> {
+        Ok(Self {
+            sensor,
+            rx_sim_event: ctx
+                .telemetry()
+                .subscribe_mp(channels::sim::SIM_EVENTS, Unbounded)?,
+            until_s: f64::NEG_INFINITY,
+        })
+    }
+
+    /// Whether the sensor produces no sample at simulation time `t_s`
+    pub fn active(&mut self, t_s: f64) -> bool {
+        while let Ok(Timestamped(t, event)) = self.rx_sim_event.try_recv() {
+            if let SimEvent::SensorDropout { sensor, duration_s } = event
+                && sensor == self.sensor
+            {
+                let until_s = t.monotonic.elapsed_seconds_f64() + duration_s;
+                self.until_s = self.until_s.max(until_s);
+            }
+        }
+
+        t_s < self.until_s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    fn mode(kind: FailureKind, axes: &[usize], t_start_s: f64) -> FailureMode {
+        FailureMode {
+            kind,
+            axes: axes.to_vec(),
+            t_start_s,
+        }
+    }
+
+    #[test]
+    fn test_compose_per_axis() {
+        let failures = SensorFailures::new(vec![
+            mode(FailureKind::AxisSwap { source_axis: 1 }, &[0], 0.0),
+            mode(FailureKind::AxisSwap { source_axis: 0 }, &[1], 0.0),
+            mode(FailureKind::RampDrift { rate_per_s: 0.5 }, &[2], 1.0),
+            mode(FailureKind::Quantization { step: 0.25 }, &[0, 1, 2], 0.0),
+        ]);
+
+        let mut values = [1.1, 2.0, 3.0];
+        assert!(failures.apply(3.0, &mut values));
+        assert_relative_eq!(values[0], 2.0);
+        assert_relative_eq!(values[1], 1.0);
+        assert_relative_eq!(values[2], 4.0);
+
+        // Not started yet
+        let failures = SensorFailures::new(vec![mode(
+            FailureKind::Constant { value: 0.0 },
+            &[0],
+            10.0,
+        )]);
+        let mut values = [1.0];
+        assert!(failures.apply(3.0, &mut values));
+        assert_relative_eq!(values[0], 1.0);
+        assert!(failures.apply(10.0, &mut values));
+        assert_relative_eq!(values[0], 0.0);
+    }
+
+    #[test]
+    fn test_periodic_dropout() {
+        let failures = SensorFailures::new(vec![mode(
+            FailureKind::PeriodicDropout {
+                period_s: 1.0,
+                duration_s: 0.2,
+            },
+            &[],
+            2.0,
+        )]);
+
+        let mut values = [0.0];
+        assert!(failures.apply(1.9, &mut values));
+        assert!(!failures.apply(2.1, &mut values));
+        assert!(failures.apply(2.5, &mut values));
+        assert!(!failures.apply(3.1, &mut values));
+    }
+}
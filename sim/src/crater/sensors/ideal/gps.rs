@@ -1,6 +1,6 @@
 use crate::{
     core::time::{Clock, Timestamp},
-    crater::{channels, rocket::rocket_data::RocketState},
+    crater::{channels, rocket::rocket_data::RocketState, sensors::failures::ScriptedDropout},
     nodes::{Node, NodeContext, StepResult},
     telemetry::{TelemetryReceiver, TelemetrySender, Timestamped},
     utils::capacity::Capacity::Unbounded,
@@ -14,6 +14,10 @@ pub struct IdealGPS {
     rx_state: TelemetryReceiver<RocketState>,
 
     tx_gps: TelemetrySender<GpsSensorSample>,
+
+    /// No fix is output from this time on [s], eg. to evaluate dead-reckoning
+    outage_start_s: Option<f64>,
+    dropout: ScriptedDropout,
 }
 
 impl IdealGPS {
@@ -24,7 +28,17 @@ impl IdealGPS {
 
         let tx_gps = ctx.telemetry().publish(channels::sensors::IDEAL_GPS)?;
 
-        Ok(Self { rx_state, tx_gps })
+        let outage_start_s = ctx
+            .parameters()
+            .get_param("sim.rocket.gps.outage_start")?
+            .value_float()?;
+
+        Ok(Self {
+            rx_state,
+            tx_gps,
+            outage_start_s: (outage_start_s >= 0.0).then_some(outage_start_s),
+            dropout: ScriptedDropout::new(&ctx, "gps")?,
+        })
     }
 }
 
@@ -35,6 +49,11 @@ impl Node for IdealGPS {
             .try_recv()
             .expect("GPS step executed, but no /rocket/state input available");
 
+        let t_s = clock.monotonic().elapsed_seconds_f64();
+        if self.outage_start_s.is_some_and(|t| t_s >= t) || self.dropout.active(t_s) {
+            return Ok(StepResult::Continue);
+        }
+
         let pos_n_m = state.pos_n_m();
         let vel_n_m_s = state.vel_n_m_s();
 
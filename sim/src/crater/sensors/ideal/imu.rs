@@ -6,6 +6,12 @@ use crate::{
             mass::RocketMassProperties,
             rocket_data::{RocketAccelerations, RocketState},
         },
+        sensors::{
+            failures::{ScriptedDropout, SensorFailures},
+            output::SensorOutputStage,
+            sampling::SampleClock,
+            thermal::ImuThermalModel,
+        },
     },
     nodes::{Node, NodeContext, StepResult},
     telemetry::{TelemetryReceiver, TelemetrySender, Timestamped},
@@ -13,25 +19,43 @@ use crate::{
 };
 use anyhow::Result;
 use chrono::TimeDelta;
-use crater_gnc::{DurationU64, datatypes::sensors::ImuSensorSample};
-use nalgebra::{Quaternion, UnitQuaternion, Vector3, Vector4};
+use crater_gnc::{
+    DurationU64, InstantU64,
+    common::imu_delta::ImuDeltaIntegrator,
+    datatypes::sensors::{ImuDeltaSample, ImuSensorSample},
+};
+use nalgebra::{UnitQuaternion, Vector3};
 
 #[derive(Debug)]
 pub struct ImuParams {
     pos_r: Vector3<f64>,
     quat_imu_b: UnitQuaternion<f64>,
     g_n: Vector3<f64>,
+    accel_failures: SensorFailures,
+    gyro_failures: SensorFailures,
+    /// Also output the delta-angles & delta-velocities, integrated between the output samples
+    delta_output: bool,
+    thermal: Option<ImuThermalModel>,
 }
 
-/// Implementation of an Ideal IMU, without noise or errors
+/// Implementation of an Ideal IMU, without noise or errors other than the configured thermal
+/// drift, failure modes & output stages. Sampled at every step, or on its own drifting ODR clock
+/// with interrupt latency & overruns if configured.
 #[derive(Debug)]
 pub struct IdealIMU {
     rx_state: TelemetryReceiver<RocketState>,
     rx_accels: TelemetryReceiver<RocketAccelerations>,
     rx_masses: TelemetryReceiver<RocketMassProperties>,
     params: ImuParams,
+    accel_output: SensorOutputStage,
+    gyro_output: SensorOutputStage,
+    sample_clock: Option<SampleClock>,
+    dropout: ScriptedDropout,
     tx_imu_translated: TelemetrySender<ImuSensorSample>,
     tx_imu_cg: TelemetrySender<ImuSensorSample>,
+
+    delta_translated: ImuDeltaIntegrator,
+    delta_cg: ImuDeltaIntegrator,
 }
 
 impl IdealIMU {
@@ -51,24 +75,18 @@ impl IdealIMU {
         let tx_imu_translated = ctx.telemetry().publish(channels::sensors::IDEAL_IMU)?;
         let tx_imu_cg = ctx.telemetry().publish(channels::sensors::IDEAL_IMU_CG)?;
 
-        let pos_r = imu_params.get_param("pos_r")?.value_float_arr()?;
-        let pos_r = Vector3::from_column_slice(&pos_r);
-
-        let quat_imu_b = imu_params.get_param("quat_imu_b")?.value_float_arr()?;
-        let quat_imu_b = UnitQuaternion::from_quaternion(Quaternion::from_vector(
-            Vector4::from_column_slice(&quat_imu_b),
-        ));
-
-        let g_n = ctx
-            .parameters()
-            .get_param("sim.rocket.g_n")?
-            .value_float_arr()?;
-        let g_n = Vector3::from_column_slice(&g_n);
+        let pos_r = imu_params.get_vector3("pos_r")?;
+        let quat_imu_b = imu_params.get_quaternion("quat_imu_b")?;
+        let g_n = ctx.parameters().get_vector3("sim.rocket.g_n")?;
 
         let imu_parameters = ImuParams {
             pos_r,
             quat_imu_b,
             g_n,
+            accel_failures: SensorFailures::from_params(imu_params, "accel")?,
+            gyro_failures: SensorFailures::from_params(imu_params, "gyro")?,
+            delta_output: imu_params.get_param("delta_output")?.value_bool()?,
+            thermal: ImuThermalModel::from_params(imu_params)?,
         };
 
         Ok(Self {
@@ -76,8 +94,14 @@ impl IdealIMU {
             rx_accels,
             rx_masses,
             params: imu_parameters,
+            accel_output: SensorOutputStage::from_params(&ctx, imu_params, "accel")?,
+            gyro_output: SensorOutputStage::from_params(&ctx, imu_params, "gyro")?,
+            sample_clock: SampleClock::from_params(&ctx, imu_params)?,
+            dropout: ScriptedDropout::new(&ctx, "imu")?,
             tx_imu_translated,
             tx_imu_cg,
+            delta_translated: ImuDeltaIntegrator::default(),
+            delta_cg: ImuDeltaIntegrator::default(),
         })
     }
 }
@@ -97,6 +121,20 @@ impl Node for IdealIMU {
             .try_recv()
             .expect("IMU step executed, but no /rocket/mass/rocket input available");
 
+        let t = Timestamp::now(clock);
+        let t_s = t.monotonic.elapsed_seconds_f64();
+
+        let (latency_us, overrun_count) = match self.sample_clock.as_mut() {
+            Some(sample_clock) => match sample_clock.poll(t_s) {
+                Some(timing) => ((timing.latency_s * 1e6) as u64, timing.overrun_count),
+                None => return Ok(StepResult::Continue),
+            },
+            None => (0, 0),
+        };
+        if self.dropout.active(t_s) {
+            return Ok(StepResult::Continue);
+        }
+
         let imu_to_cg = masses.xcg_total_m - self.params.pos_r;
         let angvel_b = state.angvel_b_rad_s();
 
@@ -109,32 +147,59 @@ impl Node for IdealIMU {
             + accel.ang_acc_b_rad_s2.cross(&imu_to_cg)
             + angvel_b.cross(&angvel_b.cross(&imu_to_cg));
 
-        let meas_acc_cg_imu = self.params.quat_imu_b.transform_vector(&meas_acc_cg_b);
-        let meas_acc_imu = self.params.quat_imu_b.transform_vector(&meas_acc_b);
-
-        let meas_angvel_imu: Vector3<f64> = self.params.quat_imu_b.transform_vector(&angvel_b);
+        let mut meas_acc_cg_imu = self.params.quat_imu_b.transform_vector(&meas_acc_cg_b);
+        let mut meas_acc_imu = self.params.quat_imu_b.transform_vector(&meas_acc_b);
+
+        let mut meas_angvel_imu: Vector3<f64> = self.params.quat_imu_b.transform_vector(&angvel_b);
+
+        let temperature_degc = self.params.thermal.as_ref().map(|thermal| {
+            let temperature_degc = thermal.profile.temperature_degc(t_s);
+            if let Some(accel) = &thermal.accel {
+                accel.apply(temperature_degc, meas_acc_cg_imu.as_mut_slice());
+                accel.apply(temperature_degc, meas_acc_imu.as_mut_slice());
+            }
+            if let Some(gyro) = &thermal.gyro {
+                gyro.apply(temperature_degc, meas_angvel_imu.as_mut_slice());
+            }
+            temperature_degc
+        });
+
+        let acc_failures = &self.params.accel_failures;
+        let available = acc_failures.apply(t_s, meas_acc_cg_imu.as_mut_slice())
+            & acc_failures.apply(t_s, meas_acc_imu.as_mut_slice())
+            & self
+                .params
+                .gyro_failures
+                .apply(t_s, meas_angvel_imu.as_mut_slice());
+
+        if !available {
+            return Ok(StepResult::Continue);
+        }
+
+        self.accel_output.apply(meas_acc_cg_imu.as_mut_slice());
+        self.accel_output.apply(meas_acc_imu.as_mut_slice());
+        self.gyro_output.apply(meas_angvel_imu.as_mut_slice());
+
+        let angvel = meas_angvel_imu.map(|v| v as f32);
+        let acc_cg = meas_acc_cg_imu.map(|v| v as f32);
+        let acc = meas_acc_imu.map(|v| v as f32);
+
+        let t_us = t.monotonic.elapsed().num_microseconds().unwrap() as u64;
+        let t_gnc = InstantU64::from_ticks(t_us).into();
+        let delta_cg = self.delta_cg.integrate(t_gnc, &angvel, &acc_cg);
+        let delta = self.delta_translated.integrate(t_gnc, &angvel, &acc);
+
+        let sample = |accel_m_s2: Vector3<f32>, delta: Option<ImuDeltaSample>| ImuSensorSample {
+            accel_m_s2,
+            angvel_rad_s: angvel,
+            int_latency: DurationU64::micros(latency_us).into(),
+            temperature_degc: temperature_degc.map(|t| t as f32),
+            overrun_count,
+            delta: delta.filter(|_| self.params.delta_output),
+        };
 
-        self.tx_imu_cg.send(
-            Timestamp::now(clock),
-            ImuSensorSample {
-                accel_m_s2: meas_acc_cg_imu.map(|v| v as f32),
-                angvel_rad_s: meas_angvel_imu.map(|v| v as f32),
-                int_latency: DurationU64::micros(0).into(),
-                temperature_degc: None,
-                overrun_count: 0,
-            },
-        );
-
-        self.tx_imu_translated.send(
-            Timestamp::now(clock),
-            ImuSensorSample {
-                accel_m_s2: meas_acc_imu.map(|v| v as f32),
-                angvel_rad_s: meas_angvel_imu.map(|v| v as f32),
-                int_latency: DurationU64::micros(0).into(),
-                temperature_degc: None,
-                overrun_count: 0,
-            },
-        );
+        self.tx_imu_cg.send(t, sample(acc_cg, delta_cg));
+        self.tx_imu_translated.send(t, sample(acc, delta));
 
         Ok(StepResult::Continue)
     }
@@ -1,36 +1,59 @@
 use crate::{
     core::time::{Clock, Timestamp},
-    crater::{channels, rocket::rocket_data::RocketState},
+    crater::{
+        channels,
+        environment::LaunchSite,
+        rocket::rocket_data::RocketState,
+        sensors::{
+            failures::{ScriptedDropout, SensorFailures},
+            output::SensorOutputStage,
+        },
+    },
     nodes::{Node, NodeContext, StepResult},
     telemetry::{TelemetryReceiver, TelemetrySender, Timestamped},
     utils::capacity::Capacity::Unbounded,
 };
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use chrono::TimeDelta;
 use crater_gnc::datatypes::sensors::MagnetometerSensorSample;
-use nalgebra::{Quaternion, UnitQuaternion, Vector3, Vector4};
-use num_traits::ToPrimitive;
+use nalgebra::{Matrix3, UnitQuaternion, Vector3};
+use rand::Rng;
+use rand_distr::StandardNormal;
+use rand_xoshiro::Xoshiro256StarStar;
 use world_magnetic_model::{GeomagneticField, uom::si::angle::radian};
 use world_magnetic_model::{
     time::{Date, macros::format_description},
     uom::si::{
         f32::{Angle, Length},
         length::meter,
-        magnetic_flux_density::nanotesla,
+        magnetic_flux_density::gauss,
     },
 };
 
 #[derive(Debug)]
 pub struct MagParams {
     quat_mag_b: UnitQuaternion<f64>,
+    /// Hard-iron offset, in the sensor frame [G]
+    hard_iron: Vector3<f64>,
+    /// Soft-iron distortion matrix, in the sensor frame
+    soft_iron: Matrix3<f64>,
+    noise_std_gauss: f64,
+    failures: SensorFailures,
 }
 
+/// Magnetometer measuring the geomagnetic field (World Magnetic Model) at the current position
+/// of the rocket, with hard-iron & soft-iron distortions and white noise
 #[derive(Debug)]
 pub struct IdealMagnetometer {
     rx_state: TelemetryReceiver<RocketState>,
     tx_magn: TelemetrySender<MagnetometerSensorSample>,
     mag_par: MagParams,
-    mag_ned: Vector3<f64>,
+    output: SensorOutputStage,
+    dropout: ScriptedDropout,
+    rng: Xoshiro256StarStar,
+
+    site: LaunchSite,
+    date: Date,
 }
 
 impl IdealMagnetometer {
@@ -44,27 +67,14 @@ impl IdealMagnetometer {
 
         let mag_params = ctx.parameters().get_map("sim.rocket.magnetomer")?;
 
-        let quat_mag_b = mag_params.get_param("quat_mag_b")?.value_float_arr()?;
-        let quat_mag_b = UnitQuaternion::from_quaternion(Quaternion::from_vector(
-            Vector4::from_column_slice(&quat_mag_b),
-        ));
-
-        let mag_par: MagParams = MagParams { quat_mag_b };
+        let mag_par: MagParams = MagParams {
+            quat_mag_b: mag_params.get_quaternion("quat_mag_b")?,
+            hard_iron: mag_params.get_vector3("hard_iron")?,
+            soft_iron: mag_params.get_matrix3("soft_iron")?,
+            noise_std_gauss: mag_params.get_param("noise_std")?.value_float()?,
+            failures: SensorFailures::from_params(mag_params, "field")?,
+        };
 
-        let latitude_rad = ctx
-            .parameters()
-            .get_param("sim.rocket.init.latitude")?
-            .value_float()?
-            .to_radians();
-        let longitude_rad = ctx
-            .parameters()
-            .get_param("sim.rocket.init.longitude")?
-            .value_float()?
-            .to_radians();
-        let altitude_m = ctx
-            .parameters()
-            .get_param("sim.rocket.init.altitude")?
-            .value_float()?;
         let date_str = ctx
             .parameters()
             .get_param("sim.rocket.date")?
@@ -73,46 +83,69 @@ impl IdealMagnetometer {
         let format = format_description!("[year]-[month]-[day]");
         let date = Date::parse(&date_str, format)?;
 
-        let mag_field = GeomagneticField::new(
-            Length::new::<meter>(altitude_m as f32),
-            Angle::new::<radian>(latitude_rad as f32),
-            Angle::new::<radian>(longitude_rad as f32),
-            date,
-        )
-        .unwrap();
-
-        let mag_n = mag_field.x().get::<nanotesla>().to_f64().unwrap();
-        let mag_e = mag_field.y().get::<nanotesla>().to_f64().unwrap();
-        let mag_d = mag_field.z().get::<nanotesla>().to_f64().unwrap();
-
-        let mag_ned: Vector3<f64> = Vector3::new(mag_n, mag_e, mag_d);
-
         Ok(Self {
             rx_state,
             tx_magn,
             mag_par,
-            mag_ned,
+            output: SensorOutputStage::from_params(&ctx, mag_params, "field")?,
+            dropout: ScriptedDropout::new(&ctx, "magnetometer")?,
+            rng: ctx.get_rng_256(),
+            site: LaunchSite::from_params(ctx.parameters())?,
+            date,
         })
     }
+
+    /// Geomagnetic field at the provided position, in the NED frame [G]
+    fn field_ned(&self, pos_n_m: &Vector3<f64>) -> Result<Vector3<f64>> {
+        let (lat, lon, alt) = self.site.geodetic(pos_n_m);
+
+        let field = GeomagneticField::new(
+            Length::new::<meter>(alt as f32),
+            Angle::new::<radian>(lat as f32),
+            Angle::new::<radian>(lon as f32),
+            self.date,
+        )
+        .map_err(|e| anyhow!("Error evaluating the geomagnetic field: {e:?}"))?;
+
+        Ok(Vector3::new(
+            field.x().get::<gauss>() as f64,
+            field.y().get::<gauss>() as f64,
+            field.z().get::<gauss>() as f64,
+        ))
+    }
 }
 
 impl Node for IdealMagnetometer {
-    #[allow(unused)]
     fn step(&mut self, _: usize, _: TimeDelta, clock: &dyn Clock) -> Result<StepResult> {
         let Timestamped(_, state) = self
             .rx_state
             .try_recv()
             .expect("Magnetometer step executed, but no /rocket/state input available");
 
+        let mag_ned = self.field_ned(&state.pos_n_m())?;
+        let mag_field_mag = self
+            .mag_par
+            .quat_mag_b
+            .transform_vector(&state.quat_nb().inverse_transform_vector(&mag_ned));
+
+        let noise = Vector3::from_fn(|_, _| self.rng.sample::<f64, _>(StandardNormal));
+        let mut mag_field_b = self.mag_par.soft_iron * mag_field_mag
+            + self.mag_par.hard_iron
+            + noise * self.mag_par.noise_std_gauss;
+
+        let t = Timestamp::now(clock);
+        let t_s = t.monotonic.elapsed_seconds_f64();
+        if self.dropout.active(t_s) || !self.mag_par.failures.apply(t_s, mag_field_b.as_mut_slice())
+        {
+            return Ok(StepResult::Continue);
+        }
+        self.output.apply(mag_field_b.as_mut_slice());
+
         let sample = MagnetometerSensorSample {
-            mag_field_b_gauss: self
-                .mag_par
-                .quat_mag_b
-                .transform_vector(&state.quat_nb().inverse_transform_vector(&self.mag_ned))
-                .map(|v| v as f32),
+            mag_field_b_gauss: mag_field_b.map(|v| v as f32),
         };
 
-        self.tx_magn.send(Timestamp::now(clock), sample);
+        self.tx_magn.send(t, sample);
 
         Ok(StepResult::Continue)
     }
@@ -1,9 +1,13 @@
 use crate::{
     core::time::{Clock, Timestamp},
     crater::{
-        aero::atmosphere::{Atmosphere, AtmosphereIsa},
+        aero::atmosphere::{Atmosphere, atmosphere_from_params},
         channels,
         rocket::rocket_data::RocketState,
+        sensors::{
+            failures::{ScriptedDropout, SensorFailures},
+            output::SensorOutputStage,
+        },
     },
     nodes::{Node, NodeContext, StepResult},
     telemetry::{TelemetryReceiver, TelemetrySender, Timestamped},
@@ -18,7 +22,10 @@ use crater_gnc::datatypes::sensors::PressureSensorSample;
 pub struct IdealStaticPressureSensor {
     rx_state: TelemetryReceiver<RocketState>,
     tx_pressure: TelemetrySender<PressureSensorSample>,
-    atmosphere: AtmosphereIsa,
+    atmosphere: Box<dyn Atmosphere + Send>,
+    failures: SensorFailures,
+    output: SensorOutputStage,
+    dropout: ScriptedDropout,
 }
 
 impl IdealStaticPressureSensor {
@@ -31,10 +38,17 @@ impl IdealStaticPressureSensor {
             .telemetry()
             .publish(channels::sensors::IDEAL_STATIC_PRESSURE)?;
 
+        let pressure_params = ctx.parameters().get_map("sim.rocket.pressure")?;
+        let failures = SensorFailures::from_params(pressure_params, "pressure")?;
+        let output = SensorOutputStage::from_params(&ctx, pressure_params, "pressure")?;
+
         Ok(Self {
             rx_state,
             tx_pressure,
-            atmosphere: AtmosphereIsa::default(),
+            atmosphere: atmosphere_from_params(ctx.parameters().get_map("sim.atmosphere")?)?,
+            failures,
+            output,
+            dropout: ScriptedDropout::new(&ctx, "pressure")?,
         })
     }
 }
@@ -46,10 +60,19 @@ impl Node for IdealStaticPressureSensor {
             .try_recv()
             .expect("IMU step executed, but no /rocket/state input available");
 
+        let mut pressure_pa = [self.atmosphere.pressure_pa(-state.pos_n_m()[2])];
+
+        let t = Timestamp::now(clock);
+        let t_s = t.monotonic.elapsed_seconds_f64();
+        if self.dropout.active(t_s) || !self.failures.apply(t_s, &mut pressure_pa) {
+            return Ok(StepResult::Continue);
+        }
+        self.output.apply(&mut pressure_pa);
+
         self.tx_pressure.send(
-            Timestamp::now(clock),
+            t,
             PressureSensorSample {
-                pressure_pa: self.atmosphere.pressure_pa(-state.pos_n_m()[2]) as f32,
+                pressure_pa: pressure_pa[0] as f32,
                 temperature_degc: None,
             },
         );
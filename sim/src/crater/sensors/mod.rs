@@ -1 +1,8 @@
+pub mod barometer;
+pub mod camera;
+pub mod failures;
 pub mod ideal;
+pub mod output;
+pub mod sampling;
+pub mod table;
+pub mod thermal;
@@ -0,0 +1,189 @@
+use anyhow::{Result, anyhow};
+use log::warn;
+use rand::Rng;
+use rand_xoshiro::Xoshiro256StarStar;
+
+use crate::{nodes::NodeContext, parameters::ParameterMap};
+
+/// Axes of a sample clipped to the full scale of the sensor, one bit per axis
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SaturationFlags(pub u32);
+
+impl SaturationFlags {
+    pub fn any(&self) -> bool {
+        self.0 != 0
+    }
+
+    pub fn is_saturated(&self, axis: usize) -> bool {
+        axis < 32 && self.0 & (1 << axis) != 0
+    }
+}
+
+/// Converter stage of a sensor: clips the measurement to the full scale of the sensor, then
+/// quantizes it to its resolution, optionally with dithering. Applied last by all the sensor
+/// models, after noise & failures.
+#[derive(Debug)]
+pub struct SensorOutputStage {
+    name: String,
+    /// Least significant bit. 0 disables the quantization.
+    resolution: f64,
+    min: f64,
+    max: f64,
+    /// Adds uniform noise of one LSB before the quantization, to decorrelate the quantization
+    /// error from the signal
+    dither: Option<Xoshiro256StarStar>,
+
+    saturated_samples: usize,
+}
+
+impl SensorOutputStage {
+    /// Output stage without clipping nor quantization
+    pub fn ideal(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            resolution: 0.0,
+            min: f64::NEG_INFINITY,
+            max: f64::INFINITY,
+            dither: None,
+            saturated_samples: 0,
+        }
+    }
+
+    /// Reads the output stage of `signal` from the `output` map of a sensor, if present. All the
+    /// entries are optional:
+    ///
+    /// ```toml
+    /// [sim.rocket.imu.output.accel]
+    /// resolution = { val = 0.0024, type = "float" }
+    /// min = { val = -156.9, type = "float" }
+    /// max = { val = 156.9, type = "float" }
+    /// dither = { val = false, type = "bool" }
+    /// ```
+    pub fn from_params(
+        ctx: &NodeContext,
+        sensor_params: &ParameterMap,
+        signal: &str,
+    ) -> Result<Self> {
+        let mut stage = Self::ideal(signal);
+
+        if !sensor_params.contains_key("output") {
+            return Ok(stage);
+        }
+        let outputs = sensor_params.get_map("output")?;
+        if !outputs.contains_key(signal) {
+            return Ok(stage);
+        }
+        let params = outputs.get_map(signal)?;
+
+        let float = |name: &str, default: f64| -> Result<f64> {
+            if params.contains_key(name) {
+                Ok(params.get_param(name)?.value_float()?)
+            } else {
+                Ok(default)
+            }
+        };
+
+        stage.resolution = float("resolution", 0.0)?;
+        stage.min = float("min", f64::NEG_INFINITY)?;
+        stage.max = float("max", f64::INFINITY)?;
+
+        if stage.resolution < 0.0 || stage.min > stage.max {
+            return Err(anyhow!(
+                "Invalid output stage of '{signal}': the resolution must be positive and min <= max"
+            ));
+        }
+
+        if params.contains_key("dither") && params.get_param("dither")?.value_bool()? {
+            stage.dither = Some(ctx.get_rng_256());
+        }
+
+        Ok(stage)
+    }
+
+    /// Number of samples with at least one saturated axis so far
+    pub fn saturated_samples(&self) -> usize {
+        self.saturated_samples
+    }
+
+    pub fn apply(&mut self, values: &mut [f64]) -> SaturationFlags {
+        let mut flags = SaturationFlags::default();
+
+        for (axis, value) in values.iter_mut().enumerate() {
+            if *value < self.min || *value > self.max {
+                flags.0 |= 1 << axis.min(31);
+            }
+            let mut v = value.clamp(self.min, self.max);
+
+            if self.resolution > 0.0 {
+                if let Some(rng) = self.dither.as_mut() {
+                    v += self.resolution * (rng.random::<f64>() - 0.5);
+                }
+                v = (v / self.resolution).round() * self.resolution;
+
+                // Quantization levels beyond the full scale are not representable
+                while v > self.max {
+                    v -= self.resolution;
+                }
+                while v < self.min {
+                    v += self.resolution;
+                }
+            }
+
+            *value = v;
+        }
+
+        if flags.any() {
+            if self.saturated_samples == 0 {
+                warn!("Sensor output '{}' saturated", self.name);
+            }
+            self.saturated_samples += 1;
+        }
+
+        flags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_clip_and_quantize() {
+        let mut stage = SensorOutputStage {
+            resolution: 0.25,
+            min: -2.0,
+            max: 1.9,
+            ..SensorOutputStage::ideal("test")
+        };
+
+        let mut values = [0.3, -5.0, 3.0];
+        let flags = stage.apply(&mut values);
+        assert_relative_eq!(values[0], 0.25);
+        assert_relative_eq!(values[1], -2.0);
+        // 1.9 rounds to 2.0, beyond the full scale
+        assert_relative_eq!(values[2], 1.75);
+        assert_eq!(flags, SaturationFlags(0b110));
+        assert!(!flags.is_saturated(0));
+        assert!(flags.is_saturated(1));
+        assert_eq!(stage.saturated_samples(), 1);
+
+        // Dithering: the mean of the quantized samples tracks the input
+        let mut stage = SensorOutputStage {
+            resolution: 1.0,
+            dither: Some(rand::SeedableRng::seed_from_u64(0)),
+            ..SensorOutputStage::ideal("test")
+        };
+        let mean = (0..10000)
+            .map(|_| {
+                let mut values = [0.3];
+                stage.apply(&mut values);
+                values[0]
+            })
+            .sum::<f64>()
+            / 10000.0;
+        assert_relative_eq!(mean, 0.3, epsilon = 0.02);
+        assert_eq!(stage.saturated_samples(), 0);
+    }
+}
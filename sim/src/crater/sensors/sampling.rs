@@ -0,0 +1,159 @@
+use anyhow::{Result, anyhow};
+use rand::Rng;
+use rand_distr::StandardNormal;
+use rand_xoshiro::Xoshiro256StarStar;
+
+use crate::{nodes::NodeContext, parameters::ParameterMap};
+
+/// Tolerance on the simulation time, so that an ODR equal to the simulation rate samples at every
+/// step despite rounding [s]
+const TIME_TOLERANCE_S: f64 = 1e-9;
+
+/// Timing of a sample read by the flight software
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SampleTiming {
+    /// Time from the data ready interrupt to it being serviced
+    pub latency_s: f64,
+    /// Samples overwritten in the sensor before being read, since the previous one
+    pub overrun_count: u8,
+}
+
+/// Sampling of a sensor on its own output data rate (ODR) clock, drifting with respect to the
+/// simulation time, read by an interrupt with a jittery latency. Samples ready faster than they
+/// are read, or read later than the next one is ready, are reported as overruns.
+#[derive(Debug)]
+pub struct SampleClock {
+    /// ODR period, including the clock drift
+    period_s: f64,
+    latency_s: f64,
+    latency_jitter_s: f64,
+    /// Probability of a spurious overrun on each sample (bus contention, missed interrupt...)
+    overrun_probability: f64,
+    rng: Xoshiro256StarStar,
+
+    /// Index of the next sample to be ready
+    next_sample: u64,
+}
+
+impl SampleClock {
+    pub fn new(
+        odr_hz: f64,
+        clock_drift_ppm: f64,
+        latency_s: f64,
+        latency_jitter_s: f64,
+        overrun_probability: f64,
+        rng: Xoshiro256StarStar,
+    ) -> Result<Self> {
+        if odr_hz <= 0.0 || !(0.0..=1.0).contains(&overrun_probability) {
+            return Err(anyhow!(
+                "Sampling ODR must be positive and the overrun probability within [0, 1]"
+            ));
+        }
+
+        Ok(Self {
+            period_s: (1.0 + clock_drift_ppm * 1e-6) / odr_hz,
+            latency_s,
+            latency_jitter_s,
+            overrun_probability,
+            rng,
+            next_sample: 0,
+        })
+    }
+
+    /// Reads the optional `sampling` map of a sensor. None if absent, in which case the sensor
+    /// is sampled at every step, without latency.
+    ///
+    /// ```toml
+    /// [sim.rocket.imu.sampling]
+    /// odr = { val = 1000.0, type = "float" }
+    /// clock_drift = { val = 50.0, type = "float" }
+    /// latency = { val = 20e-6, type = "float" }
+    /// latency_jitter = { val = 5e-6, type = "float" }
+    /// overrun_probability = { val = 1e-4, type = "float" }
+    /// ```
+    pub fn from_params(ctx: &NodeContext, sensor_params: &ParameterMap) -> Result<Option<Self>> {
+        if !sensor_params.contains_key("sampling") {
+            return Ok(None);
+        }
+        let params = sensor_params.get_map("sampling")?;
+        let float = |name: &str| -> Result<f64> { Ok(params.get_param(name)?.value_float()?) };
+
+        Ok(Some(Self::new(
+            float("odr")?,
+            float("clock_drift")?,
+            float("latency")?,
+            float("latency_jitter")?,
+            float("overrun_probability")?,
+            ctx.get_rng_256(),
+        )?))
+    }
+
+    /// Called at each step: the timing of the latest sample, if a new one is ready by `t_s`
+    pub fn poll(&mut self, t_s: f64) -> Option<SampleTiming> {
+        let t_s = t_s + TIME_TOLERANCE_S;
+        if t_s < self.next_sample as f64 * self.period_s {
+            return None;
+        }
+
+        // All the samples ready since the last read but the latest one are overwritten
+        let latest = (t_s / self.period_s).floor() as u64;
+        let mut overruns = latest - self.next_sample;
+        self.next_sample = latest + 1;
+
+        let jitter: f64 = self.rng.sample(StandardNormal);
+        let latency_s = (self.latency_s + self.latency_jitter_s * jitter).max(0.0);
+        if latency_s > self.period_s || self.rng.random::<f64>() < self.overrun_probability {
+            overruns += 1;
+        }
+
+        Some(SampleTiming {
+            latency_s,
+            overrun_count: overruns.min(u8::MAX as u64) as u8,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn test_sample_clock() -> Result<()> {
+        let rng = || Xoshiro256StarStar::seed_from_u64(0);
+
+        // 1 kHz ODR running 1% slow, sampled by 1 kHz steps: one step in ~100 without a sample
+        let mut clock = SampleClock::new(1000.0, 10000.0, 20e-6, 0.0, 0.0, rng())?;
+        let samples: Vec<_> = (0..1000)
+            .filter_map(|i| clock.poll(i as f64 * 1e-3))
+            .collect();
+        assert!((985..=995).contains(&samples.len()));
+        assert!(
+            samples
+                .iter()
+                .all(|s| s.latency_s == 20e-6 && s.overrun_count == 0)
+        );
+
+        // Steps slower than the ODR: the samples in between are overwritten
+        let mut clock = SampleClock::new(1000.0, 0.0, 0.0, 0.0, 0.0, rng())?;
+        assert_eq!(clock.poll(0.0).unwrap().overrun_count, 0);
+        assert_eq!(clock.poll(0.0025).unwrap().overrun_count, 1);
+
+        // Serviced after the next sample is ready
+        let mut clock = SampleClock::new(1000.0, 0.0, 2e-3, 0.0, 0.0, rng())?;
+        assert_eq!(clock.poll(0.0).unwrap().overrun_count, 1);
+
+        // Spurious overruns
+        let mut clock = SampleClock::new(1000.0, 0.0, 0.0, 0.0, 0.1, rng())?;
+        let overruns: u32 = (0..10000)
+            .filter_map(|i| clock.poll(i as f64 * 1e-3))
+            .map(|s| s.overrun_count as u32)
+            .sum();
+        assert!((800..1200).contains(&overruns));
+
+        assert!(SampleClock::new(0.0, 0.0, 0.0, 0.0, 0.0, rng()).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,383 @@
+use std::collections::VecDeque;
+
+use anyhow::{Result, anyhow};
+use chrono::TimeDelta;
+use rand::Rng;
+use rand_distr::StandardNormal;
+use rand_xoshiro::Xoshiro256StarStar;
+
+use crate::{
+    core::time::{Clock, Timestamp},
+    crater::{
+        aero::aerodynamics::AeroState,
+        channels,
+        rocket::{
+            mass::RocketMassProperties,
+            rocket_data::{RocketAccelerations, RocketState},
+        },
+        sensors::{failures::SensorFailures, output::SensorOutputStage},
+    },
+    math::interp::{find_index, interpolate},
+    nodes::{Node, NodeContext, NodeManager, StepResult},
+    parameters::{ParameterMap, ParameterTree},
+    telemetry::{TelemetryReceiver, TelemetrySender, Timestamped, interp::SampleHistory},
+    utils::capacity::Capacity::Unbounded,
+};
+
+/// Sample of one of the table sensors, all published on the same channel
+#[derive(Debug, Clone, Default)]
+pub struct TableSensorSample {
+    pub sensor: String,
+    pub value: f64,
+}
+
+/// Scalar quantities of a truth channel, selectable by name from the parameters
+pub trait TruthFields {
+    const FIELDS: &'static [&'static str];
+
+    fn field(&self, name: &str) -> Option<f64>;
+}
+
+impl TruthFields for RocketState {
+    const FIELDS: &'static [&'static str] = &[
+        "pos_n_m",
+        "pos_e_m",
+        "pos_d_m",
+        "altitude_m",
+        "vel_n_m_s",
+        "vel_e_m_s",
+        "vel_d_m_s",
+        "speed_m_s",
+        "angvel_x_rad_s",
+        "angvel_y_rad_s",
+        "angvel_z_rad_s",
+    ];
+
+    fn field(&self, name: &str) -> Option<f64> {
+        let (pos, vel, angvel) = (self.pos_n_m(), self.vel_n_m_s(), self.angvel_b_rad_s());
+
+        Some(match name {
+            "pos_n_m" => pos[0],
+            "pos_e_m" => pos[1],
+            "pos_d_m" => pos[2],
+            "altitude_m" => -pos[2],
+            "vel_n_m_s" => vel[0],
+            "vel_e_m_s" => vel[1],
+            "vel_d_m_s" => vel[2],
+            "speed_m_s" => vel.norm(),
+            "angvel_x_rad_s" => angvel[0],
+            "angvel_y_rad_s" => angvel[1],
+            "angvel_z_rad_s" => angvel[2],
+            _ => return None,
+        })
+    }
+}
+
+impl TruthFields for AeroState {
+    const FIELDS: &'static [&'static str] = &[
+        "mach",
+        "air_density_kg_m3",
+        "v_air_norm_m_s",
+        "dynamic_pressure_pa",
+        "altitude_m",
+        "alpha_rad",
+        "beta_rad",
+    ];
+
+    fn field(&self, name: &str) -> Option<f64> {
+        Some(match name {
+            "mach" => self.mach,
+            "air_density_kg_m3" => self.air_density_kg_m3,
+            "v_air_norm_m_s" => self.v_air_norm_m_s,
+            "dynamic_pressure_pa" => 0.5 * self.air_density_kg_m3 * self.v_air_norm_m_s.powi(2),
+            "altitude_m" => self.altitude_m,
+            "alpha_rad" => self.angles.alpha_rad,
+            "beta_rad" => self.angles.beta_rad,
+            _ => return None,
+        })
+    }
+}
+
+impl TruthFields for RocketAccelerations {
+    const FIELDS: &'static [&'static str] = &[
+        "acc_x_m_s2",
+        "acc_y_m_s2",
+        "acc_z_m_s2",
+        "ang_acc_x_rad_s2",
+        "ang_acc_y_rad_s2",
+        "ang_acc_z_rad_s2",
+    ];
+
+    fn field(&self, name: &str) -> Option<f64> {
+        Some(match name {
+            "acc_x_m_s2" => self.acc_b_m_s2[0],
+            "acc_y_m_s2" => self.acc_b_m_s2[1],
+            "acc_z_m_s2" => self.acc_b_m_s2[2],
+            "ang_acc_x_rad_s2" => self.ang_acc_b_rad_s2[0],
+            "ang_acc_y_rad_s2" => self.ang_acc_b_rad_s2[1],
+            "ang_acc_z_rad_s2" => self.ang_acc_b_rad_s2[2],
+            _ => return None,
+        })
+    }
+}
+
+impl TruthFields for RocketMassProperties {
+    const FIELDS: &'static [&'static str] = &["mass_kg", "mass_dot_kg_s", "xcg_m"];
+
+    fn field(&self, name: &str) -> Option<f64> {
+        Some(match name {
+            "mass_kg" => self.mass_kg,
+            "mass_dot_kg_s" => self.mass_dot_kg_s,
+            "xcg_m" => self.xcg_total_m[0],
+            _ => return None,
+        })
+    }
+}
+
+/// Extrapolation of a truth field past its latest sample, for the channels published after the
+/// sensors within a step [s]
+const MAX_TRUTH_EXTRAPOLATION_S: f64 = 0.01;
+
+/// Field of a truth channel, evaluated at the sampling times of the sensor
+trait TruthSource: std::fmt::Debug + Send {
+    /// Value at `t`, interpolated between the truth samples. The closest one is held if `t` is
+    /// out of their range.
+    fn at(&mut self, t: Timestamp) -> Option<f64>;
+}
+
+#[derive(Debug)]
+struct ChannelSource<T> {
+    rx: TelemetryReceiver<T>,
+    field: String,
+    history: SampleHistory<f64>,
+}
+
+impl<T: TruthFields + std::fmt::Debug + Send + 'static> TruthSource for ChannelSource<T> {
+    fn at(&mut self, t: Timestamp) -> Option<f64> {
+        while let Ok(Timestamped(ts, v)) = self.rx.try_recv() {
+            if let Some(value) = v.field(&self.field) {
+                self.history.push(Timestamped(ts, value));
+            }
+        }
+        self.history.at(t).map(|aligned| aligned.value)
+    }
+}
+
+fn subscribe_field<T: TruthFields + std::fmt::Debug + Send + 'static>(
+    ctx: &NodeContext,
+    channel: &str,
+    field: &str,
+) -> Result<Box<dyn TruthSource>> {
+    if !T::FIELDS.contains(&field) {
+        return Err(anyhow!(
+            "Unknown field '{field}' of channel '{channel}', available: {}",
+            T::FIELDS.join(", ")
+        ));
+    }
+
+    Ok(Box::new(ChannelSource::<T> {
+        rx: ctx.telemetry().subscribe(channel, Unbounded)?,
+        field: field.to_string(),
+        history: SampleHistory::new(2, MAX_TRUTH_EXTRAPOLATION_S),
+    }))
+}
+
+fn subscribe_source(ctx: &NodeContext, channel: &str, field: &str) -> Result<Box<dyn TruthSource>> {
+    match channel {
+        channels::rocket::STATE => subscribe_field::<RocketState>(ctx, channel, field),
+        channels::rocket::AERO_STATE => subscribe_field::<AeroState>(ctx, channel, field),
+        channels::rocket::ACCEL => subscribe_field::<RocketAccelerations>(ctx, channel, field),
+        channels::rocket::MASS_ROCKET => {
+            subscribe_field::<RocketMassProperties>(ctx, channel, field)
+        }
+        _ => Err(anyhow!(
+            "Channel '{channel}' cannot be read by table sensors"
+        )),
+    }
+}
+
+/// Calibration curve of a sensor: output as a function of the truth value, linearly interpolated
+/// and held constant outside of the table
+#[derive(Debug, Clone)]
+pub struct Calibration {
+    input: Vec<f64>,
+    output: Vec<f64>,
+}
+
+impl Calibration {
+    pub fn new(input: &[f64], output: &[f64]) -> Result<Self> {
+        if input.is_empty() || input.len() != output.len() {
+            return Err(anyhow!(
+                "Calibration table must be non-empty and have as many outputs as inputs"
+            ));
+        }
+        if input.windows(2).any(|w| w[1] <= w[0]) {
+            return Err(anyhow!(
+                "Calibration table inputs must be strictly increasing"
+            ));
+        }
+
+        Ok(Self {
+            input: input.to_vec(),
+            output: output.to_vec(),
+        })
+    }
+
+    pub fn apply(&self, x: f64) -> f64 {
+        interpolate(&self.output, find_index(&self.input, x)).0
+    }
+}
+
+#[derive(Debug)]
+struct TableSensorParams {
+    sample_period_s: f64,
+    delay_s: f64,
+    /// White noise, in output units
+    noise_std: f64,
+    calibration: Calibration,
+}
+
+impl TableSensorParams {
+    fn from_params(params: &ParameterMap) -> Result<Self> {
+        Ok(Self {
+            sample_period_s: 1.0 / params.get_param("rate")?.value_float()?,
+            delay_s: params.get_param("delay")?.value_float()?,
+            noise_std: params.get_param("noise_std")?.value_float()?,
+            calibration: Calibration::new(
+                params.get_param("calibration_input")?.value_float_arr()?,
+                params.get_param("calibration_output")?.value_float_arr()?,
+            )?,
+        })
+    }
+}
+
+/// Sensor defined in the parameters alone: a field of a truth channel, mapped through a
+/// calibration curve, with a bias, white noise, a finite sampling rate and a transport delay.
+/// The failures & output stage of the "value" signal apply as for the other sensors.
+#[derive(Debug)]
+pub struct TableSensor {
+    name: String,
+    source: Box<dyn TruthSource>,
+    tx_sample: TelemetrySender<TableSensorSample>,
+
+    params: TableSensorParams,
+    failures: SensorFailures,
+    output: SensorOutputStage,
+    rng: Xoshiro256StarStar,
+
+    bias: f64,
+    next_sample_s: f64,
+    /// Samples waiting for their delivery time
+    in_flight: VecDeque<(f64, f64)>,
+}
+
+impl TableSensor {
+    /// Sensor configured by `sim.rocket.table_sensors.<name>`
+    pub fn new(ctx: NodeContext, name: &str) -> Result<Self> {
+        let params = ctx
+            .parameters()
+            .get_map(&format!("sim.rocket.table_sensors.{name}"))?;
+
+        let source = subscribe_source(
+            &ctx,
+            &params.get_param("channel")?.value_string()?,
+            &params.get_param("field")?.value_string()?,
+        )?;
+
+        Ok(Self {
+            name: name.to_string(),
+            source,
+            tx_sample: ctx
+                .telemetry()
+                .publish_mp(channels::sensors::TABLE_SENSORS)?,
+            params: TableSensorParams::from_params(params)?,
+            failures: SensorFailures::from_params(params, "value")?,
+            output: SensorOutputStage::from_params(&ctx, params, "value")?,
+            rng: ctx.get_rng_256(),
+            bias: params.get_param("bias")?.value_randfloat()?.sampled(),
+            next_sample_s: 0.0,
+            in_flight: VecDeque::new(),
+        })
+    }
+}
+
+impl Node for TableSensor {
+    fn step(&mut self, _: usize, _: TimeDelta, clock: &dyn Clock) -> Result<StepResult> {
+        let t = Timestamp::now(clock);
+        let t_s = t.monotonic.elapsed_seconds_f64();
+
+        let truth = self.source.at(t);
+
+        if let Some(truth) = truth.filter(|_| t_s >= self.next_sample_s) {
+            self.next_sample_s += self.params.sample_period_s;
+
+            let noise: f64 = self.rng.sample(StandardNormal);
+            let mut value = [self.params.calibration.apply(truth)
+                + self.bias
+                + self.params.noise_std * noise];
+
+            if self.failures.apply(t_s, &mut value) {
+                self.output.apply(&mut value);
+                self.in_flight
+                    .push_back((t_s + self.params.delay_s, value[0]));
+            }
+        }
+
+        while self
+            .in_flight
+            .front()
+            .is_some_and(|(t_out, _)| *t_out <= t_s)
+        {
+            let (_, value) = self.in_flight.pop_front().unwrap();
+            self.tx_sample.send(
+                t,
+                TableSensorSample {
+                    sensor: self.name.clone(),
+                    value,
+                },
+            );
+        }
+
+        Ok(StepResult::Continue)
+    }
+}
+
+/// Adds a node for each of the sensors in `sim.rocket.table_sensors`, named "table_<sensor>"
+pub fn add_table_sensors(nm: &mut NodeManager) -> Result<()> {
+    let params = nm.parameters();
+    let rocket = params.get_map("sim.rocket")?;
+    if !rocket.contains_key("table_sensors") {
+        return Ok(());
+    }
+
+    for (name, tree) in rocket.get_map("table_sensors")?.iter() {
+        if matches!(tree, ParameterTree::Node(_)) {
+            let name = name.clone();
+            nm.add_node(&format!("table_{name}"), move |ctx| {
+                Ok(Box::new(TableSensor::new(ctx, &name)?))
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_calibration() {
+        // Thermocouple amplifier: 5 mV/°C over 0..1000 °C, with an offset of 1.25 V
+        let calibration = Calibration::new(&[0.0, 1000.0], &[1.25, 6.25]).unwrap();
+        assert_relative_eq!(calibration.apply(20.0), 1.35, epsilon = 1e-12);
+        // Held outside of the table
+        assert_relative_eq!(calibration.apply(-50.0), 1.25);
+        assert_relative_eq!(calibration.apply(2000.0), 6.25);
+
+        assert!(Calibration::new(&[0.0, 0.0], &[1.0, 2.0]).is_err());
+        assert!(Calibration::new(&[0.0, 1.0], &[1.0]).is_err());
+    }
+}
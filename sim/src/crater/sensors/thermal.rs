@@ -0,0 +1,162 @@
+use anyhow::{Result, anyhow};
+
+use crate::{
+    math::interp::{find_index, interpolate},
+    parameters::ParameterMap,
+};
+
+/// Temperature of a sensor over the simulation, tabulated against time, linearly interpolated &
+/// held at the ends
+#[derive(Debug, Clone)]
+pub struct TemperatureProfile {
+    t_s: Vec<f64>,
+    temperature_degc: Vec<f64>,
+}
+
+impl TemperatureProfile {
+    pub fn new(t_s: &[f64], temperature_degc: &[f64]) -> Result<Self> {
+        if t_s.is_empty() || t_s.len() != temperature_degc.len() {
+            return Err(anyhow!(
+                "Temperature profile must be non-empty, with as many temperatures as times"
+            ));
+        }
+
+        Ok(Self {
+            t_s: t_s.to_vec(),
+            temperature_degc: temperature_degc.to_vec(),
+        })
+    }
+
+    pub fn temperature_degc(&self, t_s: f64) -> f64 {
+        interpolate(&self.temperature_degc, find_index(&self.t_s, t_s)).0
+    }
+}
+
+/// Bias & scale factor error of a 3-axis signal, tabulated against the sensor temperature. The
+/// measurement is `(1 + scale) * value + bias`, per axis.
+#[derive(Debug, Clone)]
+pub struct ThermalDrift {
+    temperature_degc: Vec<f64>,
+    bias: [Vec<f64>; 3],
+    scale: [Vec<f64>; 3],
+}
+
+impl ThermalDrift {
+    /// `bias` & `scale` hold one `[x, y, z]` row per temperature
+    pub fn new(temperature_degc: &[f64], bias: &[f64], scale: &[f64]) -> Result<Self> {
+        let n = temperature_degc.len();
+        if n == 0 || bias.len() != 3 * n || scale.len() != 3 * n {
+            return Err(anyhow!(
+                "Thermal drift tables must have one [x, y, z] row of bias & scale per temperature"
+            ));
+        }
+
+        let columns = |table: &[f64]| {
+            [0, 1, 2].map(|axis| table.iter().skip(axis).step_by(3).copied().collect())
+        };
+
+        Ok(Self {
+            temperature_degc: temperature_degc.to_vec(),
+            bias: columns(bias),
+            scale: columns(scale),
+        })
+    }
+
+    pub fn from_params(params: &ParameterMap) -> Result<Self> {
+        Self::new(
+            params.get_param("temperature")?.value_float_arr()?,
+            params.get_param("bias")?.value_float_arr()?,
+            params.get_param("scale")?.value_float_arr()?,
+        )
+    }
+
+    pub fn apply(&self, temperature_degc: f64, values: &mut [f64]) {
+        let pos = find_index(&self.temperature_degc, temperature_degc);
+
+        for (axis, value) in values.iter_mut().enumerate().take(3) {
+            let bias = interpolate(&self.bias[axis], pos).0;
+            let scale = interpolate(&self.scale[axis], pos).0;
+
+            *value = (1.0 + scale) * *value + bias;
+        }
+    }
+}
+
+/// Temperature-dependent errors of an IMU, from the optional `thermal` map of its parameters:
+///
+/// ```toml
+/// [sim.rocket.imu.thermal]
+/// profile_t = { val = [0.0, 600.0], type = "float[]" }
+/// profile_temperature = { val = [25.0, 40.0], type = "float[]" }
+///
+/// [sim.rocket.imu.thermal.gyro]
+/// temperature = { val = [-20.0, 25.0, 85.0], type = "float[]" }
+/// bias = { val = [...], type = "float[]" }
+/// scale = { val = [...], type = "float[]" }
+/// ```
+///
+/// The `accel` & `gyro` drifts are both optional.
+#[derive(Debug, Clone)]
+pub struct ImuThermalModel {
+    pub profile: TemperatureProfile,
+    pub accel: Option<ThermalDrift>,
+    pub gyro: Option<ThermalDrift>,
+}
+
+impl ImuThermalModel {
+    /// None if the IMU has no `thermal` map
+    pub fn from_params(imu_params: &ParameterMap) -> Result<Option<Self>> {
+        if !imu_params.contains_key("thermal") {
+            return Ok(None);
+        }
+        let params = imu_params.get_map("thermal")?;
+
+        let drift = |signal: &str| -> Result<Option<ThermalDrift>> {
+            if params.contains_key(signal) {
+                Ok(Some(ThermalDrift::from_params(params.get_map(signal)?)?))
+            } else {
+                Ok(None)
+            }
+        };
+
+        Ok(Some(Self {
+            profile: TemperatureProfile::new(
+                params.get_param("profile_t")?.value_float_arr()?,
+                params.get_param("profile_temperature")?.value_float_arr()?,
+            )?,
+            accel: drift("accel")?,
+            gyro: drift("gyro")?,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_thermal_drift() -> Result<()> {
+        let profile = TemperatureProfile::new(&[0.0, 100.0], &[20.0, 40.0])?;
+        assert_relative_eq!(profile.temperature_degc(-1.0), 20.0);
+        assert_relative_eq!(profile.temperature_degc(25.0), 25.0);
+        assert_relative_eq!(profile.temperature_degc(500.0), 40.0);
+
+        // Rows at 20 & 40°C
+        let bias = [0.0, 0.1, -0.2, 0.2, 0.1, 0.2];
+        let scale = [0.0, 0.0, 0.0, 0.01, 0.0, -0.01];
+        let drift = ThermalDrift::new(&[20.0, 40.0], &bias, &scale)?;
+
+        // 30°C, halfway between the rows
+        let mut values = [1.0, 1.0, 1.0];
+        drift.apply(profile.temperature_degc(50.0), &mut values);
+        assert_relative_eq!(values[0], 1.005 + 0.1, epsilon = 1e-12);
+        assert_relative_eq!(values[1], 1.1, epsilon = 1e-12);
+        assert_relative_eq!(values[2], 0.995, epsilon = 1e-12);
+
+        assert!(ThermalDrift::new(&[20.0, 40.0], &[0.0; 3], &[0.0; 6]).is_err());
+
+        Ok(())
+    }
+}
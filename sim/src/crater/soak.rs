@@ -0,0 +1,284 @@
+use std::fs;
+
+use anyhow::Result;
+use chrono::TimeDelta;
+use crater_gnc::{
+    components::ada::{AdaAlgorithm, AdaConfig},
+    datatypes::sensors::PressureSensorSample,
+    hal::channel::Receiver,
+};
+use log::warn;
+use nalgebra::{UnitQuaternion, Vector3};
+use serde::Serialize;
+
+use crate::{
+    core::time::{Clock, Timestamp},
+    crater::{channels, events::SimEvent, rocket::rocket_data::RocketState},
+    model::ModelBuilder,
+    nodes::{FtlOrderedExecutor, Node, NodeContext, NodeManager, ParameterSampling, StepResult},
+    parameters::{ParameterMap, ParameterValue},
+    telemetry::{TelemetryReceiver, TelemetrySender, TelemetryService, Timestamped},
+    utils::capacity::Capacity::Unbounded,
+};
+
+#[derive(Debug, Clone)]
+pub struct SoakParams {
+    /// Period of the drift checks
+    pub check_interval_s: f64,
+    pub max_position_drift_m: f64,
+    pub max_attitude_drift_deg: f64,
+    pub max_velocity_m_s: f64,
+    /// Relative change of the ADA covariance trace since the first check
+    pub max_ada_cov_drift: f64,
+    pub max_memory_growth_mb: f64,
+}
+
+impl SoakParams {
+    pub fn from_params(params: &ParameterMap) -> Result<Self> {
+        Ok(Self {
+            check_interval_s: params.get_param("check_interval")?.value_float()?,
+            max_position_drift_m: params.get_param("max_position_drift")?.value_float()?,
+            max_attitude_drift_deg: params.get_param("max_attitude_drift")?.value_float()?,
+            max_velocity_m_s: params.get_param("max_velocity")?.value_float()?,
+            max_ada_cov_drift: params.get_param("max_ada_cov_drift")?.value_float()?,
+            max_memory_growth_mb: params.get_param("max_memory_growth")?.value_float()?,
+        })
+    }
+}
+
+/// Drift monitors, published at every check
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SoakStatus {
+    pub t_s: f64,
+    /// Drift of the rocket state since the start of the run, while it should sit still
+    pub position_drift_m: f64,
+    pub attitude_drift_deg: f64,
+    pub velocity_m_s: f64,
+    /// Covariance of the ADA filter, run on the barometer for the whole soak
+    pub ada_altitude_std_m: f64,
+    pub ada_cov_drift: f64,
+    /// Growth of the resident memory since the first check. Zero if not available.
+    pub memory_growth_mb: f64,
+}
+
+/// Resident memory of the process, on Linux
+fn resident_memory_mb() -> Option<f64> {
+    const PAGE_SIZE: f64 = 4096.0;
+
+    let statm = fs::read_to_string("/proc/self/statm").ok()?;
+    let pages: f64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+    Some(pages * PAGE_SIZE / 1e6)
+}
+
+/// Watches for slow divergences and leaks over hours of simulated time, which short runs never
+/// expose. Each monitor exceeding its limit is reported once with a `SoakDrift` event.
+pub struct SoakMonitor {
+    params: SoakParams,
+
+    rx_state: TelemetryReceiver<RocketState>,
+    rx_pressure: TelemetryReceiver<PressureSensorSample>,
+    tx_status: TelemetrySender<SoakStatus>,
+    tx_sim_event: TelemetrySender<SimEvent>,
+
+    ada: AdaAlgorithm,
+
+    initial: Option<(Vector3<f64>, UnitQuaternion<f64>)>,
+    last_state: Option<RocketState>,
+    ref_cov_trace: Option<f64>,
+    ref_memory_mb: Option<f64>,
+    last_check_s: f64,
+    tripped: Vec<&'static str>,
+}
+
+impl SoakMonitor {
+    pub fn new(ctx: NodeContext) -> Result<Self> {
+        let params = SoakParams::from_params(ctx.parameters().get_map("sim.soak")?)?;
+
+        if resident_memory_mb().is_none() {
+            warn!("Memory usage not available on this platform, it will not be monitored");
+        }
+
+        Ok(Self {
+            params,
+            rx_state: ctx
+                .telemetry()
+                .subscribe(channels::rocket::STATE, Unbounded)?,
+            rx_pressure: ctx
+                .telemetry()
+                .subscribe(channels::sensors::STATIC_PRESSURE, Unbounded)?,
+            tx_status: ctx.telemetry().publish(channels::sim::SOAK_STATUS)?,
+            tx_sim_event: ctx.telemetry().publish_mp(channels::sim::SIM_EVENTS)?,
+            ada: AdaAlgorithm::new(AdaConfig::default()),
+            initial: None,
+            last_state: None,
+            ref_cov_trace: None,
+            ref_memory_mb: None,
+            last_check_s: 0.0,
+            tripped: vec![],
+        })
+    }
+
+    fn status(&mut self, t_s: f64) -> SoakStatus {
+        let mut status = SoakStatus {
+            t_s,
+            ..Default::default()
+        };
+
+        if let (Some((pos0, quat0)), Some(state)) = (&self.initial, &self.last_state) {
+            status.position_drift_m = (state.pos_n_m() - pos0).norm();
+            status.attitude_drift_deg = quat0.angle_to(&state.quat_nb()).to_degrees();
+            status.velocity_m_s = state.vel_n_m_s().norm();
+        }
+
+        let cov = self.ada.covariance();
+        let valid = cov.iter().all(|v| v.is_finite())
+            && (0..3).all(|i| cov[(i, i)] > 0.0)
+            && (cov - cov.transpose()).amax() <= 1e-3 * cov.amax();
+        let trace = cov.trace() as f64;
+        let ref_trace = *self.ref_cov_trace.get_or_insert(trace);
+        status.ada_altitude_std_m = (cov[(0, 0)] as f64).sqrt();
+        status.ada_cov_drift = if valid {
+            (trace / ref_trace - 1.0).abs()
+        } else {
+            f64::INFINITY
+        };
+
+        if let Some(memory_mb) = resident_memory_mb() {
+            status.memory_growth_mb = memory_mb - *self.ref_memory_mb.get_or_insert(memory_mb);
+        }
+
+        status
+    }
+
+    fn check(&mut self, t: Timestamp, status: &SoakStatus) {
+        let p = &self.params;
+        let monitors = [
+            ("position", status.position_drift_m, p.max_position_drift_m),
+            (
+                "attitude",
+                status.attitude_drift_deg,
+                p.max_attitude_drift_deg,
+            ),
+            ("velocity", status.velocity_m_s, p.max_velocity_m_s),
+            ("ada_covariance", status.ada_cov_drift, p.max_ada_cov_drift),
+            ("memory", status.memory_growth_mb, p.max_memory_growth_mb),
+        ];
+
+        for (monitor, value, limit) in monitors {
+            if (value.is_nan() || value > limit) && !self.tripped.contains(&monitor) {
+                self.tripped.push(monitor);
+                self.tx_sim_event.send(
+                    t,
+                    SimEvent::SoakDrift {
+                        monitor: monitor.to_string(),
+                        value,
+                        limit,
+                    },
+                );
+            }
+        }
+    }
+}
+
+impl Node for SoakMonitor {
+    fn step(&mut self, _: usize, _: TimeDelta, clock: &dyn Clock) -> Result<StepResult> {
+        let t = Timestamp::now(clock);
+        let t_s = t.monotonic.elapsed_seconds_f64();
+
+        while let Ok(Timestamped(_, state)) = self.rx_state.try_recv() {
+            self.initial
+                .get_or_insert((state.pos_n_m(), state.quat_nb()));
+            self.last_state = Some(state);
+        }
+
+        while let Some(press) = Receiver::try_recv(&mut self.rx_pressure) {
+            self.ada.update(press);
+        }
+
+        if t_s - self.last_check_s >= self.params.check_interval_s {
+            self.last_check_s = t_s;
+
+            let status = self.status(t_s);
+            self.check(t, &status);
+            self.tx_status.send(t, status);
+        }
+
+        Ok(StepResult::Continue)
+    }
+}
+
+/// A monitor that exceeded its limit, with the value & time at which it did
+#[derive(Debug, Clone)]
+pub struct SoakDrift {
+    pub t_s: f64,
+    pub monitor: String,
+    pub value: f64,
+    pub limit: f64,
+}
+
+/// Outcome of a soak run
+pub struct SoakReport {
+    pub statuses: Vec<SoakStatus>,
+    pub drifts: Vec<SoakDrift>,
+}
+
+impl SoakReport {
+    pub fn passed(&self) -> bool {
+        self.drifts.is_empty()
+    }
+}
+
+/// Runs the model for `duration_s` of simulated time with the rocket held on the pad: the
+/// countdown is scrubbed before ignition, while the flight software keeps running.
+pub fn run_soak(
+    model: &dyn ModelBuilder,
+    base: &ParameterMap,
+    duration_s: f64,
+) -> Result<SoakReport> {
+    let mut params = base.clone();
+    params.set_param(
+        "sim.orchestrator.abort_before_ignition",
+        ParameterValue::Bool { val: true },
+    )?;
+    params.set_param(
+        "sim.rocket.max_t",
+        ParameterValue::Float { val: duration_s },
+    )?;
+
+    let dt_sec = params.get_param("sim.dt")?.value_float()?;
+    let dt = (dt_sec * 1000000.0) as i64;
+
+    let ts = TelemetryService::default();
+    channels::configure_access(&ts, &params)?;
+    let rx_status = ts.subscribe::<SoakStatus>(channels::sim::SOAK_STATUS, Unbounded)?;
+    let rx_sim_event = ts.subscribe_mp::<SimEvent>(channels::sim::SIM_EVENTS, Unbounded)?;
+
+    let mut nm = NodeManager::new(ts, params, ParameterSampling::Perfect, 0);
+    model.build(&mut nm)?;
+
+    FtlOrderedExecutor::run_blocking(nm, TimeDelta::microseconds(dt))?;
+
+    let mut statuses = vec![];
+    while let Ok(Timestamped(_, status)) = rx_status.try_recv() {
+        statuses.push(status);
+    }
+
+    let mut drifts = vec![];
+    while let Ok(Timestamped(t, event)) = rx_sim_event.try_recv() {
+        if let SimEvent::SoakDrift {
+            monitor,
+            value,
+            limit,
+        } = event
+        {
+            drifts.push(SoakDrift {
+                t_s: t.monotonic.elapsed_seconds_f64(),
+                monitor,
+                value,
+                limit,
+            });
+        }
+    }
+
+    Ok(SoakReport { statuses, drifts })
+}
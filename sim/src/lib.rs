@@ -7,4 +7,5 @@ pub mod telemetry;
 pub mod utils;
 pub mod model;
 pub mod runner;
-pub mod montecarlorunner;
\ No newline at end of file
+pub mod montecarlorunner;
+pub mod scenarios;
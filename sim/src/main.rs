@@ -1,10 +1,94 @@
-use anyhow::Result;
+use anyhow::{Result, anyhow};
+use clap::Parser;
 use crater::{
-    crater::logging::rerun::CraterUiLogConfig, model::OpenLoopCrater, runner::SingleThreadedRunner,
+    crater::logging::rerun::CraterUiLogConfig,
+    model::{
+        GsLinkedModel, HilCrater, ModelBuilder, OpenLoopCrater, RecordedModel, ReplayCrater,
+        UdpBridgedModel,
+    },
+    parameters,
+    runner::SingleThreadedRunner,
+    scenarios::{self, Scenario},
+    telemetry::ordering::{DeliveryOrdering, DeliveryTrace},
 };
 
 use log::info;
-use std::{env, path::Path};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// List the built-in scenarios and exit
+    #[arg(long)]
+    list_scenarios: bool,
+
+    /// Run a built-in scenario headless and check its assertions
+    #[arg(long)]
+    scenario: Option<String>,
+
+    /// Parameter file of the run or scenario
+    #[arg(short, long, default_value = "config/params.toml")]
+    params: PathBuf,
+
+    /// Seed used to sample the parameters. Random if not provided.
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Record the order of all the channel deliveries to this file
+    #[arg(long, conflicts_with = "replay_order")]
+    record_order: Option<PathBuf>,
+
+    /// Enforce the delivery order recorded in this file. Use with the seed of the recorded run.
+    #[arg(long)]
+    replay_order: Option<PathBuf>,
+
+    /// Record the inputs & outputs of the flight software to this file
+    #[arg(long, conflicts_with = "replay_telemetry")]
+    record_telemetry: Option<PathBuf>,
+
+    /// Run the flight software alone on the inputs recorded in this file
+    #[arg(long)]
+    replay_telemetry: Option<PathBuf>,
+
+    /// Stream the flight software telemetry over UDP, as configured in `sim.udp`
+    #[arg(long, conflicts_with_all = ["record_telemetry", "replay_telemetry"])]
+    udp: bool,
+
+    /// Expose the vehicle as a MAVLink endpoint to the ground station, as configured in
+    /// `sim.gs_link`
+    #[arg(long, conflicts_with_all = ["record_telemetry", "replay_telemetry", "udp"])]
+    gs_link: bool,
+
+    /// Fly the flight computer connected to the serial port of `sim.hil`, in place of the flight
+    /// software, in real time
+    #[arg(long, conflicts_with_all = ["record_telemetry", "replay_telemetry", "udp", "gs_link"])]
+    hil: bool,
+
+    /// Log the telemetry to CSV files in this directory, instead of streaming it to Rerun
+    #[arg(long)]
+    csv: Option<PathBuf>,
+
+    /// Record the Rerun log to a .rrd file, as configured in `sim.rerun`, instead of streaming
+    /// it to the viewer. Same as `--param /sim/rerun/output=file`.
+    #[arg(long, conflicts_with = "csv")]
+    rrd: bool,
+
+    /// Apply the changes to the parameter file while the simulation runs. Only some parameters,
+    /// like the servo dynamics, are applied live.
+    #[arg(long)]
+    reload_params: bool,
+
+    /// Override a parameter of the parameter file, eg.
+    /// `--param /sim/rocket/engine/simple/total_impulse=2500`. Can be repeated. The effective
+    /// parameters are saved with the run output.
+    #[arg(long = "param", value_name = "PATH=VALUE")]
+    overrides: Vec<String>,
+}
 
 fn main() -> Result<()> {
     // Default log level to "info"
@@ -13,23 +97,217 @@ fn main() -> Result<()> {
     }
 
     pretty_env_logger::init();
+
+    let args = Args::parse();
+
+    if args.list_scenarios {
+        for scenario in scenarios::catalog() {
+            println!("{:<20} {}", scenario.name, scenario.description);
+        }
+        return Ok(());
+    }
+
+    if let Some(name) = args.scenario {
+        let scenario = scenarios::find_scenario(&name).ok_or(anyhow!(
+            "Unknown scenario '{name}'. Use --list-scenarios to see the available ones."
+        ))?;
+        return run_scenario(&scenario, &args.params, &args.overrides);
+    }
+
     crater();
 
-    let runner = SingleThreadedRunner::new(
-        OpenLoopCrater {},
-        &Path::new("config/params.toml"),
-        Box::new(CraterUiLogConfig),
-        crater::nodes::ParameterSampling::Random,
-        None,
-    )?;
+    let mut overrides = args.overrides.clone();
+    if args.rrd {
+        overrides.push("/sim/rerun/output=file".to_string());
+    }
+    // A replay is not a flight configuration: the replayer injects the recorded GNC events, which
+    // the flight access policy reserves to the flight nodes
+    if args.replay_telemetry.is_some() {
+        overrides.push("/sim/access_control=false".to_string());
+    }
+
+    let ordering = if args.record_order.is_some() {
+        Some(Arc::new(DeliveryOrdering::record()))
+    } else if let Some(path) = &args.replay_order {
+        Some(Arc::new(DeliveryOrdering::replay(
+            DeliveryTrace::load(path)?,
+            Duration::from_secs(5),
+        )))
+    } else {
+        None
+    };
 
-    runner.run_blocking()?;
+    if let Some(path) = &args.record_telemetry {
+        run(
+            RecordedModel {
+                model: OpenLoopCrater {},
+                path: path.clone(),
+            },
+            &args.params,
+            args.seed,
+            ordering.clone(),
+            args.csv.as_deref(),
+            &overrides,
+            args.reload_params,
+        )?;
+        info!(
+            "Recorded the flight software telemetry to '{}'",
+            path.display()
+        );
+    } else if let Some(path) = &args.replay_telemetry {
+        run(
+            ReplayCrater { path: path.clone() },
+            &args.params,
+            args.seed,
+            ordering.clone(),
+            args.csv.as_deref(),
+            &overrides,
+            args.reload_params,
+        )?;
+    } else if args.udp {
+        run(
+            UdpBridgedModel {
+                model: OpenLoopCrater {},
+            },
+            &args.params,
+            args.seed,
+            ordering.clone(),
+            args.csv.as_deref(),
+            &overrides,
+            args.reload_params,
+        )?;
+    } else if args.gs_link {
+        run(
+            GsLinkedModel {
+                model: OpenLoopCrater {},
+            },
+            &args.params,
+            args.seed,
+            ordering.clone(),
+            args.csv.as_deref(),
+            &overrides,
+            args.reload_params,
+        )?;
+    } else if args.hil {
+        run(
+            HilCrater {},
+            &args.params,
+            args.seed,
+            ordering.clone(),
+            args.csv.as_deref(),
+            &overrides,
+            args.reload_params,
+        )?;
+    } else {
+        run(
+            OpenLoopCrater {},
+            &args.params,
+            args.seed,
+            ordering.clone(),
+            args.csv.as_deref(),
+            &overrides,
+            args.reload_params,
+        )?;
+    }
+
+    if let Some(ordering) = ordering {
+        if let Some(path) = &args.record_order {
+            let trace = ordering.trace();
+            trace.save(path)?;
+            info!(
+                "Recorded {} deliveries to '{}'",
+                trace.deliveries.len(),
+                path.display()
+            );
+        } else if let Some(divergence) = ordering.divergence() {
+            return Err(anyhow!(
+                "Run diverged from the replayed order at delivery {}: expected {:?}, got {:?}",
+                divergence.index,
+                divergence.expected,
+                divergence.found
+            ));
+        } else {
+            info!("Replayed delivery order matched");
+        }
+    }
 
     info!("Boom!");
 
     Ok(())
 }
 
+fn run(
+    model: impl ModelBuilder,
+    params: &Path,
+    seed: Option<u64>,
+    ordering: Option<Arc<DeliveryOrdering>>,
+    csv: Option<&Path>,
+    overrides: &[String],
+    reload_params: bool,
+) -> Result<()> {
+    let sampling = crater::nodes::ParameterSampling::Random;
+
+    let runner = match csv {
+        Some(out_dir) => {
+            info!("Logging telemetry to CSV files in '{}'", out_dir.display());
+            SingleThreadedRunner::with_csv(
+                model,
+                params,
+                overrides,
+                Box::new(CraterUiLogConfig),
+                out_dir,
+                sampling,
+                seed,
+                ordering,
+            )?
+        }
+        None => SingleThreadedRunner::new(
+            model,
+            params,
+            overrides,
+            Box::new(CraterUiLogConfig),
+            sampling,
+            seed,
+            ordering,
+        )?,
+    };
+
+    let runner = if reload_params {
+        runner.with_param_reload()
+    } else {
+        runner
+    };
+
+    runner.run_blocking()
+}
+
+fn run_scenario(scenario: &Scenario, params: &Path, overrides: &[String]) -> Result<()> {
+    let mut params = parameters::parse_string(fs::read_to_string(params)?)?;
+    for arg in overrides.iter() {
+        params.apply_override(arg)?;
+    }
+
+    info!("Running scenario '{}': {}", scenario.name, scenario.description);
+    let outcome = scenario.run(&params)?;
+
+    for result in outcome.results.iter() {
+        let status = if result.passed { "PASS" } else { "FAIL" };
+        println!("[{status}] {} ({})", result.description, result.actual);
+    }
+
+    for check in outcome.report.compliance.iter() {
+        let status = if check.passed { "PASS" } else { "FAIL" };
+        println!("[{status}] compliance: {} ({})", check.rule, check.actual);
+    }
+
+    if outcome.passed() {
+        info!("Scenario '{}' passed", scenario.name);
+        Ok(())
+    } else {
+        Err(anyhow!("Scenario '{}' failed", scenario.name))
+    }
+}
+
 fn crater() {
     println!("                             ____");
     println!("                     __,-~~/~    `---.");
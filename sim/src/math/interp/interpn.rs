@@ -71,10 +71,21 @@ impl<const D: usize> Lattice<D> {
     }
 }
 
+/// Behavior of the interpolation outside of the axes range
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Extrapolation {
+    /// The values on the edge of the table are held
+    #[default]
+    Clamp,
+    /// The first or last interval of the axis is extended linearly
+    Linear,
+}
+
 pub struct Interpolator<T, const D: usize> {
     axes: [Vec<T>; D],
     axes_steps: [Vec<T>; D],
     lattice: Lattice<D>,
+    extrapolation: Extrapolation,
 
     mut_alloc: RefCell<InterpolatorAlloc<T>>,
 }
@@ -107,10 +118,16 @@ impl<T: Float + TotalOrder + Sum, const D: usize> Interpolator<T, D> {
             axes,
             axes_steps,
             lattice: Lattice::new(size),
+            extrapolation: Extrapolation::Clamp,
             mut_alloc: RefCell::new(InterpolatorAlloc::new(1 << D)),
         })
     }
 
+    pub fn with_extrapolation(mut self, extrapolation: Extrapolation) -> Self {
+        self.extrapolation = extrapolation;
+        self
+    }
+
     fn find_edge_index(&self, state: &[T; D]) -> [usize; D] {
         // TODO: Memory
         let indices: [usize; D] = array::from_fn(|i| {
@@ -126,7 +143,10 @@ impl<T: Float + TotalOrder + Sum, const D: usize> Interpolator<T, D> {
         let x: [T; D] = array::from_fn(|i| {
             let is = indices[i];
             let v = (state[i] - self.axes[i][is]) / self.axes_steps[i][is];
-            v.min(T::one()).max(T::zero())
+            match self.extrapolation {
+                Extrapolation::Clamp => v.min(T::one()).max(T::zero()),
+                Extrapolation::Linear => v,
+            }
         });
 
         x
@@ -181,3 +201,42 @@ impl<T: Float + TotalOrder + Sum, const D: usize> Interpolator<T, D> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_extrapolation() {
+        // f(x, y) = 2x + 3y + 1 on a 3x2 grid
+        let x = [0.0, 1.0, 3.0];
+        let y = [-1.0, 1.0];
+        let f: Vec<f64> = x
+            .iter()
+            .flat_map(|x| y.iter().map(move |y| 2.0 * x + 3.0 * y + 1.0))
+            .collect();
+
+        let clamp = Interpolator::<f64, 2>::new([&x, &y]).unwrap();
+        let linear = Interpolator::<f64, 2>::new([&x, &y])
+            .unwrap()
+            .with_extrapolation(Extrapolation::Linear);
+
+        let mut out = [0.0];
+        for interp in [&clamp, &linear] {
+            interp.interpn(&[2.0, 0.5], &[&f], &mut out);
+            assert_relative_eq!(out[0], 6.5, epsilon = 1e-12);
+        }
+
+        clamp.interpn(&[4.0, -2.0], &[&f], &mut out);
+        assert_relative_eq!(out[0], 4.0, epsilon = 1e-12);
+        linear.interpn(&[4.0, -2.0], &[&f], &mut out);
+        assert_relative_eq!(out[0], 3.0, epsilon = 1e-12);
+
+        clamp.interpn(&[-1.0, 2.0], &[&f], &mut out);
+        assert_relative_eq!(out[0], 4.0, epsilon = 1e-12);
+        linear.interpn(&[-1.0, 2.0], &[&f], &mut out);
+        assert_relative_eq!(out[0], 5.0, epsilon = 1e-12);
+    }
+}
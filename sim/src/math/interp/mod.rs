@@ -2,4 +2,4 @@ mod interp1;
 mod interpn;
 
 pub use interp1::*;
-pub use interpn::Interpolator;
\ No newline at end of file
+pub use interpn::{Extrapolation, Interpolator};
\ No newline at end of file
@@ -1,3 +1,5 @@
+mod attitude;
 mod ode;
 
-pub use ode::*;
\ No newline at end of file
+pub use attitude::*;
+pub use ode::*;
@@ -1,25 +1,105 @@
 use crate::{
     crater::{
-        actuators::ideal::IdealServo,
-        gnc::{fsw::FlightSoftware, openloop::OpenloopControl, orchestrator::Orchestrator},
+        actuators::{airbrake::Airbrake, servo::ServoModel},
+        aero::envelope::FlightEnvelope,
+        environment::Environment,
+        gnc::{
+            cosim::CosimBridge,
+            dual_fc::{FcUnit, FcVoter},
+            fsw::{BurstRecorder, FlightSoftware},
+            gs_link::GsLink,
+            hil_link::HilLink,
+            openloop::OpenloopControl,
+            orchestrator::{Orchestrator, ScenarioPlayer},
+            radio_link::RadioLink,
+        },
+        recording::{bridge_gnc, record_gnc, replay_gnc_inputs},
         rocket::rocket::Rocket,
-        sensors::ideal::{IdealIMU, IdealMagnetometer, IdealStaticPressureSensor},
+        sensors::{
+            barometer::StaticPressureSensor,
+            camera::Camera,
+            ideal::{IdealGPS, IdealIMU, IdealMagnetometer, IdealStaticPressureSensor},
+            table::add_table_sensors,
+        },
+        soak::SoakMonitor,
     },
     nodes::NodeManager,
+    telemetry::{
+        recording::{TelemetryRecorder, TelemetryReplayer},
+        udp::UdpTelemetryBridge,
+    },
 };
-use anyhow::Result;
+use anyhow::{Result, anyhow};
+use std::path::PathBuf;
 
 pub trait ModelBuilder {
     fn build(&self, node_manager: &mut NodeManager) -> Result<()>;
 }
 
+impl<M: ModelBuilder + ?Sized> ModelBuilder for Box<M> {
+    fn build(&self, node_manager: &mut NodeManager) -> Result<()> {
+        (**self).build(node_manager)
+    }
+}
+
+/// Servos driven by the open loop sequence, unless the roll control of the flight software
+/// drives them (`sim.rocket.gnc.roll_control.closed_loop`)
+fn add_openloop_control(nm: &mut NodeManager) -> Result<()> {
+    let closed_loop = nm
+        .parameters()
+        .get_param("sim.rocket.gnc.roll_control.closed_loop")?
+        .value_bool()?;
+
+    if !closed_loop {
+        nm.add_node("openloop_control", |ctx| {
+            Ok(Box::new(OpenloopControl::new(ctx)?))
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Radio link between the flight software & the ground segment, if `sim.radio_link.enabled`
+fn add_radio_link(nm: &mut NodeManager) -> Result<()> {
+    let enabled = nm
+        .parameters()
+        .get_param("sim.radio_link.enabled")?
+        .value_bool()?;
+
+    if enabled {
+        nm.add_node("radio_link", |ctx| Ok(Box::new(RadioLink::new(ctx)?)))?;
+    }
+
+    Ok(())
+}
+
+/// Scenario player, if a scenario is configured in `sim.orchestrator.scenario`. Not part of the
+/// flight configuration: only the test access policy lets it inject events.
+fn add_scenario_player(nm: &mut NodeManager) -> Result<()> {
+    let scenario = nm
+        .parameters()
+        .get_param("sim.orchestrator.scenario")?
+        .value_string()?;
+
+    if !scenario.is_empty() {
+        nm.add_node("scenario_player", |ctx| {
+            Ok(Box::new(ScenarioPlayer::new(ctx)?))
+        })?;
+    }
+
+    Ok(())
+}
+
 #[derive(Debug, Clone)]
 pub struct OpenLoopCrater {}
 
 impl ModelBuilder for OpenLoopCrater {
     fn build(&self, nm: &mut NodeManager) -> Result<()> {
         nm.add_node("orchestrator", |ctx| Ok(Box::new(Orchestrator::new(ctx)?)))?;
+        add_scenario_player(nm)?;
+        nm.add_node("environment", |ctx| Ok(Box::new(Environment::new(ctx)?)))?;
         nm.add_node("rocket", |ctx| Ok(Box::new(Rocket::new("crater", ctx)?)))?;
+        nm.add_node("envelope", |ctx| Ok(Box::new(FlightEnvelope::new(ctx)?)))?;
         nm.add_node("ideal_imu", |ctx| Ok(Box::new(IdealIMU::new(ctx)?)))?;
         nm.add_node("ideal_mag", |ctx| {
             Ok(Box::new(IdealMagnetometer::new(ctx)?))
@@ -27,12 +107,310 @@ impl ModelBuilder for OpenLoopCrater {
         nm.add_node("ideal_press", |ctx| {
             Ok(Box::new(IdealStaticPressureSensor::new(ctx)?))
         })?;
+        nm.add_node("barometer", |ctx| Ok(Box::new(StaticPressureSensor::new(ctx)?)))?;
+        nm.add_node("ideal_gps", |ctx| Ok(Box::new(IdealGPS::new(ctx)?)))?;
+        add_table_sensors(nm)?;
+        nm.add_node("camera", |ctx| Ok(Box::new(Camera::new(ctx)?)))?;
         nm.add_node("fsw", |ctx| Ok(Box::new(FlightSoftware::new(ctx)?)))?;
+        nm.add_node("burst_recorder", |ctx| Ok(Box::new(BurstRecorder::new(ctx)?)))?;
+        add_radio_link(nm)?;
+        add_openloop_control(nm)?;
+        nm.add_node("servo", |ctx| Ok(Box::new(ServoModel::new(ctx)?)))?;
+        nm.add_node("airbrake", |ctx| Ok(Box::new(Airbrake::new(ctx)?)))?;
+
+        Ok(())
+    }
+}
+
+/// Crater dynamics & sensors, flown by an external autopilot through the co-simulation bridge
+#[derive(Debug, Clone)]
+pub struct CosimCrater {}
+
+impl ModelBuilder for CosimCrater {
+    fn build(&self, nm: &mut NodeManager) -> Result<()> {
+        nm.add_node("environment", |ctx| Ok(Box::new(Environment::new(ctx)?)))?;
+        nm.add_node("rocket", |ctx| Ok(Box::new(Rocket::new("crater", ctx)?)))?;
+        nm.add_node("envelope", |ctx| Ok(Box::new(FlightEnvelope::new(ctx)?)))?;
+        nm.add_node("ideal_imu", |ctx| Ok(Box::new(IdealIMU::new(ctx)?)))?;
+        nm.add_node("ideal_mag", |ctx| {
+            Ok(Box::new(IdealMagnetometer::new(ctx)?))
+        })?;
+        nm.add_node("ideal_press", |ctx| {
+            Ok(Box::new(IdealStaticPressureSensor::new(ctx)?))
+        })?;
+        nm.add_node("barometer", |ctx| Ok(Box::new(StaticPressureSensor::new(ctx)?)))?;
+        add_table_sensors(nm)?;
+        nm.add_node("camera", |ctx| Ok(Box::new(Camera::new(ctx)?)))?;
+        nm.add_node("cosim", |ctx| Ok(Box::new(CosimBridge::new(ctx)?)))?;
+        nm.add_node("servo", |ctx| Ok(Box::new(ServoModel::new(ctx)?)))?;
+        nm.add_node("airbrake", |ctx| Ok(Box::new(Airbrake::new(ctx)?)))?;
+
+        Ok(())
+    }
+}
+
+/// Crater dynamics & sensors, flown by the flight computer in the loop: the sensor samples are
+/// streamed to it over the HIL link. The engine is started by the scenario in
+/// `sim.orchestrator.scenario`.
+#[derive(Debug, Clone)]
+pub struct HilCrater {}
+
+impl ModelBuilder for HilCrater {
+    fn build(&self, nm: &mut NodeManager) -> Result<()> {
+        add_scenario_player(nm)?;
+        nm.add_node("environment", |ctx| Ok(Box::new(Environment::new(ctx)?)))?;
+        nm.add_node("rocket", |ctx| Ok(Box::new(Rocket::new("crater", ctx)?)))?;
+        nm.add_node("envelope", |ctx| Ok(Box::new(FlightEnvelope::new(ctx)?)))?;
+        nm.add_node("ideal_imu", |ctx| Ok(Box::new(IdealIMU::new(ctx)?)))?;
+        nm.add_node("ideal_mag", |ctx| {
+            Ok(Box::new(IdealMagnetometer::new(ctx)?))
+        })?;
+        nm.add_node("ideal_press", |ctx| {
+            Ok(Box::new(IdealStaticPressureSensor::new(ctx)?))
+        })?;
+        nm.add_node("barometer", |ctx| Ok(Box::new(StaticPressureSensor::new(ctx)?)))?;
+        nm.add_node("ideal_gps", |ctx| Ok(Box::new(IdealGPS::new(ctx)?)))?;
+        nm.add_node("hil_link", |ctx| Ok(Box::new(HilLink::new(ctx)?)))?;
+        nm.add_node("servo", |ctx| Ok(Box::new(ServoModel::new(ctx)?)))?;
+        nm.add_node("airbrake", |ctx| Ok(Box::new(Airbrake::new(ctx)?)))?;
+
+        Ok(())
+    }
+}
+
+/// Open loop Crater with some of the sensors failed from the start: the listed sensor nodes
+/// (eg. "ideal_press") are not instantiated, so their channels never produce data
+#[derive(Debug, Clone)]
+pub struct DegradedSensorsCrater {
+    pub failed: Vec<&'static str>,
+}
+
+impl ModelBuilder for DegradedSensorsCrater {
+    fn build(&self, nm: &mut NodeManager) -> Result<()> {
+        nm.add_node("orchestrator", |ctx| Ok(Box::new(Orchestrator::new(ctx)?)))?;
+        add_scenario_player(nm)?;
+        nm.add_node("environment", |ctx| Ok(Box::new(Environment::new(ctx)?)))?;
+        nm.add_node("rocket", |ctx| Ok(Box::new(Rocket::new("crater", ctx)?)))?;
+        nm.add_node("envelope", |ctx| Ok(Box::new(FlightEnvelope::new(ctx)?)))?;
+        if !self.failed.contains(&"ideal_imu") {
+            nm.add_node("ideal_imu", |ctx| Ok(Box::new(IdealIMU::new(ctx)?)))?;
+        }
+        if !self.failed.contains(&"ideal_mag") {
+            nm.add_node("ideal_mag", |ctx| {
+                Ok(Box::new(IdealMagnetometer::new(ctx)?))
+            })?;
+        }
+        if !self.failed.contains(&"ideal_press") {
+            nm.add_node("ideal_press", |ctx| {
+                Ok(Box::new(IdealStaticPressureSensor::new(ctx)?))
+            })?;
+        }
+        if !self.failed.contains(&"barometer") {
+            nm.add_node("barometer", |ctx| Ok(Box::new(StaticPressureSensor::new(ctx)?)))?;
+        }
+        nm.add_node("fsw", |ctx| Ok(Box::new(FlightSoftware::new(ctx)?)))?;
+        nm.add_node("burst_recorder", |ctx| Ok(Box::new(BurstRecorder::new(ctx)?)))?;
+        add_radio_link(nm)?;
+        add_openloop_control(nm)?;
+        nm.add_node("servo", |ctx| Ok(Box::new(ServoModel::new(ctx)?)))?;
+        nm.add_node("airbrake", |ctx| Ok(Box::new(Airbrake::new(ctx)?)))?;
+
+        Ok(())
+    }
+}
+
+/// Open loop Crater with two redundant flight computers, fed the same sensor streams, and a
+/// voter comparing their outputs and failing over between them
+#[derive(Debug, Clone)]
+pub struct DualFcCrater {}
+
+impl ModelBuilder for DualFcCrater {
+    fn build(&self, nm: &mut NodeManager) -> Result<()> {
+        nm.add_node("orchestrator", |ctx| Ok(Box::new(Orchestrator::new(ctx)?)))?;
+        add_scenario_player(nm)?;
+        nm.add_node("environment", |ctx| Ok(Box::new(Environment::new(ctx)?)))?;
+        nm.add_node("rocket", |ctx| Ok(Box::new(Rocket::new("crater", ctx)?)))?;
+        nm.add_node("envelope", |ctx| Ok(Box::new(FlightEnvelope::new(ctx)?)))?;
+        nm.add_node("ideal_imu", |ctx| Ok(Box::new(IdealIMU::new(ctx)?)))?;
+        nm.add_node("ideal_mag", |ctx| {
+            Ok(Box::new(IdealMagnetometer::new(ctx)?))
+        })?;
+        nm.add_node("ideal_press", |ctx| {
+            Ok(Box::new(IdealStaticPressureSensor::new(ctx)?))
+        })?;
+        nm.add_node("barometer", |ctx| Ok(Box::new(StaticPressureSensor::new(ctx)?)))?;
+        nm.add_node("camera", |ctx| Ok(Box::new(Camera::new(ctx)?)))?;
+        nm.add_node("fsw_a", |ctx| {
+            Ok(Box::new(FlightSoftware::new_redundant(ctx, FcUnit::A)?))
+        })?;
+        nm.add_node("fsw_b", |ctx| {
+            Ok(Box::new(FlightSoftware::new_redundant(ctx, FcUnit::B)?))
+        })?;
+        nm.add_node("fc_voter", |ctx| Ok(Box::new(FcVoter::new(ctx)?)))?;
         nm.add_node("openloop_control", |ctx| {
             Ok(Box::new(OpenloopControl::new(ctx)?))
         })?;
-        nm.add_node("ideal_servo", |ctx| Ok(Box::new(IdealServo::new(ctx)?)))?;
+        nm.add_node("servo", |ctx| Ok(Box::new(ServoModel::new(ctx)?)))?;
+        nm.add_node("airbrake", |ctx| Ok(Box::new(Airbrake::new(ctx)?)))?;
+
+        Ok(())
+    }
+}
+
+/// Open loop Crater with the drift monitors of the soak runs
+#[derive(Debug, Clone)]
+pub struct SoakCrater {}
+
+impl ModelBuilder for SoakCrater {
+    fn build(&self, nm: &mut NodeManager) -> Result<()> {
+        OpenLoopCrater {}.build(nm)?;
+        nm.add_node("soak_monitor", |ctx| Ok(Box::new(SoakMonitor::new(ctx)?)))?;
 
         Ok(())
     }
 }
+
+/// Model with the inputs & outputs of its flight software recorded to a file
+pub struct RecordedModel<M> {
+    pub model: M,
+    pub path: PathBuf,
+}
+
+impl<M: ModelBuilder> ModelBuilder for RecordedModel<M> {
+    fn build(&self, nm: &mut NodeManager) -> Result<()> {
+        self.model.build(nm)?;
+
+        let path = self.path.clone();
+        nm.add_node("recorder", move |ctx| {
+            let mut recorder = TelemetryRecorder::new(&path)?;
+            record_gnc(&mut recorder, ctx.telemetry())?;
+            Ok(Box::new(recorder))
+        })?;
+
+        Ok(())
+    }
+}
+
+/// Model streaming the flight software telemetry over UDP, configured by `sim.udp`. The ground
+/// commands of the remote end are only accepted outside of the flight & HIL configurations.
+pub struct UdpBridgedModel<M> {
+    pub model: M,
+}
+
+impl<M: ModelBuilder> ModelBuilder for UdpBridgedModel<M> {
+    fn build(&self, nm: &mut NodeManager) -> Result<()> {
+        self.model.build(nm)?;
+
+        nm.add_node("udp_bridge", |ctx| {
+            let params = ctx.parameters();
+            let local = params.get_param("sim.udp.local")?.value_string()?;
+            let remote = params.get_param("sim.udp.remote")?.value_string()?;
+            let commands = params.get_param("sim.udp.commands")?.value_bool()?;
+            // Any UDP peer could inject flight events
+            if commands && params.get_param("sim.access_control")?.value_bool()? {
+                return Err(anyhow!(
+                    "UDP commands (sim.udp.commands) are not accepted under the flight access \
+                     policy (sim.access_control)"
+                ));
+            }
+
+            let mut bridge = UdpTelemetryBridge::new(&local, &remote)?;
+            bridge_gnc(&mut bridge, ctx.telemetry(), commands)?;
+            Ok(Box::new(bridge))
+        })?;
+
+        Ok(())
+    }
+}
+
+/// Model exposed as a MAVLink endpoint to the ground station, configured by `sim.gs_link`
+pub struct GsLinkedModel<M> {
+    pub model: M,
+}
+
+impl<M: ModelBuilder> ModelBuilder for GsLinkedModel<M> {
+    fn build(&self, nm: &mut NodeManager) -> Result<()> {
+        self.model.build(nm)?;
+
+        nm.add_node("gs_link", |ctx| Ok(Box::new(GsLink::new(ctx)?)))?;
+
+        Ok(())
+    }
+}
+
+/// Flight software alone, fed the inputs of a recorded run. Stops at the end of the recording.
+#[derive(Debug, Clone)]
+pub struct ReplayCrater {
+    pub path: PathBuf,
+}
+
+impl ModelBuilder for ReplayCrater {
+    fn build(&self, nm: &mut NodeManager) -> Result<()> {
+        let path = self.path.clone();
+        nm.add_node("replayer", move |ctx| {
+            let mut replayer = TelemetryReplayer::new(&path)?;
+            replay_gnc_inputs(&mut replayer, ctx.telemetry())?;
+            Ok(Box::new(replayer))
+        })?;
+        nm.add_node("fsw", |ctx| Ok(Box::new(FlightSoftware::new(ctx)?)))?;
+        nm.add_node("burst_recorder", |ctx| Ok(Box::new(BurstRecorder::new(ctx)?)))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{
+        crater::{channels, recording},
+        nodes::ParameterSampling,
+        parameters::{ParameterMap, ParameterValue, parameters},
+        telemetry::TelemetryService,
+    };
+
+    fn default_params() -> Result<ParameterMap> {
+        Ok(parameters::parse_string(fs::read_to_string(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/config/params.toml"
+        ))?)?)
+    }
+
+    /// Builds a model with the default parameters, under the flight access policy they enable
+    fn build_with_default_params(model: impl ModelBuilder) -> Result<()> {
+        build_with_params(model, default_params()?)
+    }
+
+    fn build_with_params(model: impl ModelBuilder, params: ParameterMap) -> Result<()> {
+        assert!(params.get_param("sim.access_control")?.value_bool()?);
+
+        let ts = TelemetryService::default();
+        channels::register_units(&ts);
+        recording::register_recordables(&ts);
+        channels::configure_access(&ts, &params)?;
+
+        let mut nm = NodeManager::new(ts, params, ParameterSampling::Perfect, 0);
+        model.build(&mut nm)
+    }
+
+    #[test]
+    fn test_build_with_default_params() -> Result<()> {
+        build_with_default_params(OpenLoopCrater {})?;
+        build_with_default_params(DualFcCrater {})?;
+        build_with_default_params(DegradedSensorsCrater {
+            failed: vec!["barometer"],
+        })?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_with_radio_link() -> Result<()> {
+        let mut params = default_params()?;
+        params.set_param("sim.radio_link.enabled", ParameterValue::Bool { val: true })?;
+
+        build_with_params(OpenLoopCrater {}, params)
+    }
+}
@@ -9,15 +9,28 @@ use std::{
 use anyhow::Result;
 use chrono::TimeDelta;
 use log::info;
-use rand::{TryRngCore, rngs::OsRng};
+use rand::{RngCore, SeedableRng, TryRngCore, rngs::OsRng};
+use rand_xoshiro::SplitMix64;
 use serde::Serialize;
 
 use crate::{
-    crater::logging::rerun::{RerunLogConfig, RerunLoggerBuilder},
+    crater::{
+        aero::{aerodynamics::AeroState, forecast, wind::wind_from_params},
+        channels,
+        environment::LaunchSite,
+        logging::{
+            dispersion,
+            filter::ChannelLogFilter,
+            landing_scatter::{self, LandingSample, mean_wind},
+            rerun::{LogPipelineConfig, OverloadPolicy, RerunLogConfig, RerunLoggerBuilder},
+        },
+        rocket::rocket_data::RocketState,
+    },
     model::ModelBuilder,
     nodes::{FtlOrderedExecutor, NodeManager},
     parameters::{ParameterMap, parameters},
-    telemetry::TelemetryService,
+    telemetry::{TelemetryService, Timestamped},
+    utils::capacity::Capacity::Unbounded,
 };
 
 #[derive(Debug, Clone, Serialize)]
@@ -28,12 +41,30 @@ struct MonteCarloResult {
     sim_duration_us: i64,
     log_duration_us: i64,
     log_file: PathBuf,
+    landing_n_m: f64,
+    landing_e_m: f64,
+    apogee_m: f64,
+    /// Maximum dynamic pressure
+    max_q_pa: f64,
+    /// Mean wind from the ground to apogee. Empty if calm.
+    wind_from_deg: Option<f64>,
+    wind_speed_m_s: f64,
+
+    /// Sampled value of each random parameter, written to a separate file
+    #[serde(skip)]
+    sampled: Vec<(String, f64)>,
+}
+
+/// Seed of run `index` of the campaign
+fn run_seed(campaign_seed: u64, index: usize) -> u64 {
+    SplitMix64::seed_from_u64(campaign_seed.wrapping_add(index as u64)).next_u64()
 }
 
 fn worker(
     model: impl ModelBuilder,
     params: ParameterMap,
     log_config: impl RerunLogConfig,
+    campaign_seed: u64,
     thread_id: usize,
     run_index: Arc<AtomicUsize>,
     num_runs: usize,
@@ -47,13 +78,25 @@ fn worker(
             return Ok(());
         }
 
-        let seed = OsRng {}.try_next_u64().unwrap();
+        let seed = run_seed(campaign_seed, index);
 
         let ts = TelemetryService::default();
-
-        let mut log_builder = RerunLoggerBuilder::new(&ts);
+        channels::register_units(&ts);
+
+        // Logging to file after the run: nothing to gain by dropping data
+        let mut log_builder = RerunLoggerBuilder::with_config(
+            &ts,
+            LogPipelineConfig {
+                overload: OverloadPolicy::Block,
+                ..Default::default()
+            },
+        )
+        .with_filter(ChannelLogFilter::from_params(&params)?);
         log_config.subscribe_telem(&mut log_builder)?;
 
+        let rx_state = ts.subscribe::<RocketState>(channels::rocket::STATE, Unbounded)?;
+        let rx_aerostate = ts.subscribe::<AeroState>(channels::rocket::AERO_STATE, Unbounded)?;
+
         let mut nm = NodeManager::new(
             ts,
             params.clone(),
@@ -63,6 +106,9 @@ fn worker(
 
         model.build(&mut nm)?;
 
+        // Parameters as sampled for this run
+        let run_params = nm.parameters();
+
         let dt_sec = params.get_param("sim.dt")?.value_float()?;
         let dt = (dt_sec * 1000000.0) as i64;
 
@@ -70,6 +116,21 @@ fn worker(
         FtlOrderedExecutor::run_blocking(nm, TimeDelta::microseconds(dt))?;
         let sim_duration = Instant::now() - start_time;
 
+        let mut apogee_m: f64 = 0.0;
+        let mut landing = RocketState::default();
+        while let Ok(Timestamped(_, state)) = rx_state.try_recv() {
+            apogee_m = apogee_m.max(-state.pos_n_m()[2]);
+            landing = state;
+        }
+
+        let mut max_q_pa: f64 = 0.0;
+        while let Ok(Timestamped(_, aero)) = rx_aerostate.try_recv() {
+            max_q_pa = max_q_pa.max(0.5 * aero.air_density_kg_m3 * aero.v_air_norm_m_s.powi(2));
+        }
+
+        let wind = wind_from_params(run_params.get_map("sim.wind")?)?;
+        let (wind_from_deg, wind_speed_m_s) = mean_wind(wind.as_ref(), apogee_m);
+
         let start_time = Instant::now();
         let mut rec = rerun::RecordingStreamBuilder::new("crater")
             .save(out_dir.join(format!("mc_{index:04}.rrd")))?;
@@ -88,6 +149,13 @@ fn worker(
             sim_duration_us: sim_duration.as_micros() as i64,
             log_duration_us: log_duration.as_micros() as i64,
             log_file: PathBuf::new(),
+            landing_n_m: landing.pos_n_m()[0],
+            landing_e_m: landing.pos_n_m()[1],
+            apogee_m,
+            max_q_pa,
+            wind_from_deg,
+            wind_speed_m_s,
+            sampled: run_params.sampled_values(),
         };
 
         tx_result.send(result)?;
@@ -98,6 +166,8 @@ pub struct MonteCarloRunner<M, L> {
     num_workers: usize,
     num_runs: usize,
     params: ParameterMap,
+    /// Seed of the campaign, from which the seed of each run is derived
+    seed: u64,
     model_builder: M,
     log_config: L,
     out_dir: PathBuf,
@@ -114,21 +184,26 @@ where
         log_config: L,
         num_runs: usize,
         num_workers: Option<usize>,
+        seed: Option<u64>,
         out_dir: PathBuf,
     ) -> Result<Self> {
         info!("Reading parameters from '{}'", params.display());
 
         let params_toml = fs::read_to_string(params)?;
-        let params = parameters::parse_string(params_toml)?;
+        let mut params = parameters::parse_string(params_toml)?;
+        forecast::apply_forecast(&mut params)?;
 
         let num_workers = num_workers.unwrap_or_else(|| available_parallelism().unwrap().get());
 
-        info!("Montecarlo configuration: {num_workers} workers, {num_runs} runs");
+        let seed = seed.unwrap_or_else(|| OsRng {}.try_next_u64().unwrap());
+
+        info!("Montecarlo configuration: {num_workers} workers, {num_runs} runs, seed {seed}");
 
         Ok(MonteCarloRunner {
             num_workers,
             num_runs,
             params,
+            seed,
             model_builder,
             log_config,
             out_dir,
@@ -150,12 +225,14 @@ where
             let tx_result = tx_result.clone();
             let run_index = run_index.clone();
             let out_dir = self.out_dir.clone();
+            let campaign_seed = self.seed;
 
             let worker = std::thread::spawn(move || {
                 worker(
                     model,
                     params,
                     log_config,
+                    campaign_seed,
                     i,
                     run_index,
                     self.num_runs,
@@ -171,6 +248,9 @@ where
         // Write the results to csv
         let out_file = self.out_dir.join("montecarlo.csv");
         let mut writer = csv::Writer::from_path(out_file)?;
+        // Sampled parameters, one column per random parameter
+        let mut sampled_writer = csv::Writer::from_path(self.out_dir.join("mc_samples.csv"))?;
+        let mut samples = vec![];
 
         while let Ok(result) = rx_result.recv() {
             info!(
@@ -182,12 +262,78 @@ where
                 result.seed
             );
 
+            // All the runs sample the same parameters
+            if samples.is_empty() {
+                let mut header = vec!["index".to_string(), "seed".to_string()];
+                header.extend(result.sampled.iter().map(|(path, _)| path.clone()));
+                sampled_writer.write_record(&header)?;
+            }
+            let mut record = vec![result.index.to_string(), result.seed.to_string()];
+            record.extend(result.sampled.iter().map(|(_, v)| v.to_string()));
+            sampled_writer.write_record(&record)?;
+
+            samples.push(LandingSample {
+                index: result.index,
+                landing_n_m: result.landing_n_m,
+                landing_e_m: result.landing_e_m,
+                wind_from_deg: result.wind_from_deg,
+                wind_speed_m_s: result.wind_speed_m_s,
+            });
             writer.serialize(result)?;
         }
 
         for worker in workers {
             worker.join().unwrap()?;
         }
+        writer.flush()?;
+        sampled_writer.flush()?;
+
+        self.write_landing_scatter(&mut samples)?;
+
+        let site = LaunchSite::from_params(&self.params)?;
+        dispersion::write_campaign(&self.out_dir, &site)?.log_summary();
+
+        Ok(())
+    }
+
+    /// Landing points & dispersion ellipses of the runs, with a layer per wind direction, as
+    /// GeoJSON and as a Rerun recording
+    fn write_landing_scatter(&self, samples: &mut [LandingSample]) -> Result<()> {
+        samples.sort_by_key(|s| s.index);
+
+        let num_sectors = self
+            .params
+            .get_param("sim.montecarlo.wind_sectors")?
+            .value_int()? as usize;
+        let probability = self
+            .params
+            .get_param("sim.montecarlo.ellipse_probability")?
+            .value_float()?;
+        let site = LaunchSite::from_params(&self.params)?;
+
+        let sectors = landing_scatter::bucket_by_wind(samples, num_sectors.max(1), probability);
+        for sector in sectors.iter() {
+            match &sector.ellipse {
+                Some(e) => info!(
+                    "{}: {} runs, ellipse {:.0} x {:.0} m, major axis at {:.0} deg",
+                    sector.name,
+                    sector.samples.len(),
+                    2.0 * e.semi_major_m,
+                    2.0 * e.semi_minor_m,
+                    e.orientation_deg
+                ),
+                None => info!("{}: {} runs", sector.name, sector.samples.len()),
+            }
+        }
+
+        fs::write(
+            self.out_dir.join("landing_scatter.geojson"),
+            serde_json::to_string_pretty(&landing_scatter::to_geojson(&sectors, &site))?,
+        )?;
+
+        let rec = rerun::RecordingStreamBuilder::new("crater_landing_scatter")
+            .save(self.out_dir.join("landing_scatter.rrd"))?;
+        landing_scatter::log_rerun(&rec, &sectors, &site)?;
 
         Ok(())
     }
@@ -1,8 +1,17 @@
-use crate::core::time::SimulatedClock;
+use std::time::Instant;
 
-use super::{NodeManager, StepResult};
+use crate::{
+    core::time::{SimulatedClock, Timestamp},
+    crater::channels,
+};
+
+use super::{
+    NodeManager, StepResult,
+    profiling::{NodeProfiler, ProfilingReport, StepProfile},
+};
 use anyhow::{Context, Result};
 use chrono::{TimeDelta, Utc};
+use log::debug;
 
 // pub struct ThreadedExecutor {
 //     node_join_handles: HashMap<String, JoinHandle<Result<()>>>,
@@ -66,10 +75,42 @@ use chrono::{TimeDelta, Utc};
 //     }
 // }
 
+/// Steps the nodes as fast as possible, in dependency order: each node steps after the
+/// publishers of the channels it subscribes to, so it reads their samples of the same step.
+/// The wall-clock time of each node step is published on `/sim/profiling`.
 pub struct FtlOrderedExecutor;
 
 impl FtlOrderedExecutor {
-    pub fn run_blocking(mut node_mgr: NodeManager, simulated_step_period: TimeDelta) -> Result<()> {
+    pub fn run_blocking(node_mgr: NodeManager, simulated_step_period: TimeDelta) -> Result<()> {
+        let report = Self::run_profiled(node_mgr, simulated_step_period)?;
+        debug!("Node step times:\n{report}");
+
+        Ok(())
+    }
+
+    /// Runs the simulation, returning the step time statistics of the nodes
+    pub fn run_profiled(
+        mut node_mgr: NodeManager,
+        simulated_step_period: TimeDelta,
+    ) -> Result<ProfilingReport> {
+        let order = node_mgr.execution_order()?;
+        debug!(
+            "Execution order: {}",
+            order
+                .iter()
+                .map(|&i| node_mgr.nodes()[i].0.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        let tx_profile = node_mgr
+            .telemetry()
+            .publish_as::<StepProfile>("executor", channels::sim::PROFILING)?;
+        let mut profiler = NodeProfiler::new(
+            order.iter().map(|&i| node_mgr.nodes()[i].0.as_str()),
+            Some(tx_profile),
+        );
+
         let mut clock = SimulatedClock::new(Utc::now(), TimeDelta::zero());
 
         let mut outer_res = Ok(StepResult::Continue);
@@ -79,10 +120,14 @@ impl FtlOrderedExecutor {
         while !stop {
             clock.step(simulated_step_period);
 
-            for (name, node) in node_mgr.nodes_mut().iter_mut() {
+            for (k, &n) in order.iter().enumerate() {
+                let (name, node) = &mut node_mgr.nodes_mut()[n];
+
+                let start = Instant::now();
                 let res = node
                     .step(i, simulated_step_period, &clock)
                     .with_context(|| format!("Node {}: step() reported an error", name));
+                profiler.record(k, start.elapsed());
 
                 match res {
                     Ok(StepResult::Continue) => (),
@@ -94,10 +139,11 @@ impl FtlOrderedExecutor {
                 }
             }
 
+            profiler.end_step(Timestamp::now(&clock));
             i += 1;
         }
 
         outer_res?;
-        Ok(())
+        Ok(profiler.report())
     }
 }
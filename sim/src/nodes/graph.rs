@@ -0,0 +1,123 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeSet, BinaryHeap},
+};
+
+use super::Error;
+
+/// Channels a node publishes & subscribes to, recorded when it is created. Only the channels
+/// with a single producer order the nodes: multi-producer channels are event buses, whose
+/// events are handled in whichever step they are received.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NodeChannels {
+    pub published: BTreeSet<String>,
+    /// Read in the step they are published in: their publisher steps first
+    pub subscribed: BTreeSet<String>,
+    /// Read at the next step, which breaks the feedback loops (eg. actuators to dynamics)
+    pub feedback: BTreeSet<String>,
+}
+
+/// Order of execution of the nodes such that the publisher of each subscribed channel steps
+/// before its subscribers. Independent nodes keep the order they were added in.
+pub fn execution_order(nodes: &[(&str, &NodeChannels)]) -> Result<Vec<usize>, Error> {
+    let n = nodes.len();
+
+    // Adjacency from the publishers to their subscribers
+    let mut next: Vec<Vec<usize>> = vec![vec![]; n];
+    let mut num_deps = vec![0usize; n];
+    for (p, (_, publisher)) in nodes.iter().enumerate() {
+        for (s, (_, subscriber)) in nodes.iter().enumerate() {
+            if p != s && !publisher.published.is_disjoint(&subscriber.subscribed) {
+                next[p].push(s);
+                num_deps[s] += 1;
+            }
+        }
+    }
+
+    // Kahn's algorithm, taking the first added of the ready nodes
+    let mut ready: BinaryHeap<Reverse<usize>> =
+        (0..n).filter(|&i| num_deps[i] == 0).map(Reverse).collect();
+    let mut order = Vec::with_capacity(n);
+    while let Some(Reverse(i)) = ready.pop() {
+        order.push(i);
+        for &s in next[i].iter() {
+            num_deps[s] -= 1;
+            if num_deps[s] == 0 {
+                ready.push(Reverse(s));
+            }
+        }
+    }
+
+    if order.len() == n {
+        return Ok(order);
+    }
+
+    // Every node left depends on another one left: walk back through them until one repeats
+    let mut path = vec![(0..n).find(|&i| num_deps[i] > 0).unwrap()];
+    loop {
+        let last = *path.last().unwrap();
+        let prev = (0..n)
+            .find(|&p| num_deps[p] > 0 && next[p].contains(&last))
+            .unwrap();
+
+        if let Some(start) = path.iter().position(|&i| i == prev) {
+            let mut cycle = vec![nodes[prev].0];
+            cycle.extend(path[start + 1..].iter().rev().map(|&i| nodes[i].0));
+            cycle.push(nodes[prev].0);
+            return Err(Error::DependencyCycle(cycle.join(" -> ")));
+        }
+        path.push(prev);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channels(published: &[&str], subscribed: &[&str], feedback: &[&str]) -> NodeChannels {
+        let set = |c: &[&str]| c.iter().map(|c| c.to_string()).collect();
+        NodeChannels {
+            published: set(published),
+            subscribed: set(subscribed),
+            feedback: set(feedback),
+        }
+    }
+
+    #[test]
+    fn test_execution_order() {
+        let control = channels(&["/cmd"], &["/sensor"], &[]);
+        let logger = channels(&[], &["/sensor", "/cmd", "/state"], &[]);
+        let dynamics = channels(&["/state"], &[], &["/cmd"]);
+        let sensor = channels(&["/sensor"], &["/state"], &[]);
+        let pacer = channels(&[], &[], &[]);
+
+        let nodes = [
+            ("control", &control),
+            ("logger", &logger),
+            ("dynamics", &dynamics),
+            ("sensor", &sensor),
+            ("pacer", &pacer),
+        ];
+        let order: Vec<&str> = execution_order(&nodes)
+            .unwrap()
+            .into_iter()
+            .map(|i| nodes[i].0)
+            .collect();
+        assert_eq!(order, ["dynamics", "sensor", "control", "logger", "pacer"]);
+
+        // Without the feedback, dynamics -> sensor -> control -> dynamics
+        let dynamics = channels(&["/state"], &["/cmd"], &[]);
+        let nodes = [
+            ("logger", &logger),
+            ("control", &control),
+            ("dynamics", &dynamics),
+            ("sensor", &sensor),
+        ];
+        match execution_order(&nodes) {
+            Err(Error::DependencyCycle(cycle)) => {
+                assert_eq!(cycle, "control -> dynamics -> sensor -> control")
+            }
+            res => panic!("Cycle not detected: {res:?}"),
+        }
+    }
+}
@@ -1,5 +1,11 @@
 mod executor;
+mod graph;
 mod node;
+mod pacer;
+mod profiling;
 
 pub use executor::FtlOrderedExecutor;
+pub use graph::NodeChannels;
 pub use node::*;
+pub use pacer::RealtimePacer;
+pub use profiling::{NodeProfiler, NodeStats, NodeStepTime, ProfilingReport, StepProfile};
@@ -9,10 +9,17 @@ use std::{
 };
 use thiserror::Error;
 
+use super::graph::{self, NodeChannels};
 use crate::{
-    core::{path::Path, time::Clock},
-    parameters::ParameterMap,
-    telemetry::{TelemetryError, TelemetryReceiver, TelemetrySender, TelemetryService},
+    core::{
+        path::Path,
+        time::{Clock, Timestamp},
+    },
+    parameters::{ParameterMap, service::ParameterService},
+    telemetry::{
+        DynamicMessage, LatestReceiver, TelemetryError, TelemetryReceiver, TelemetrySender,
+        TelemetryService,
+    },
     utils::capacity::Capacity,
 };
 
@@ -23,6 +30,9 @@ pub enum Error {
 
     #[error(transparent)]
     NodeInstantiation(#[from] Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("Dependency cycle between the nodes: {0}")]
+    DependencyCycle(String),
 }
 
 pub enum StepResult {
@@ -42,7 +52,10 @@ pub enum ParameterSampling {
 pub struct NodeManager {
     telemetry: TelemetryService,
     parameters: Arc<ParameterMap>,
+    param_service: ParameterService,
     nodes: Vec<(String, Box<dyn Node + Send>)>,
+    /// Channels of each node, in the same order
+    channels: Vec<Arc<Mutex<NodeChannels>>>,
     rng: Arc<Mutex<SplitMix64>>,
     seed: u64,
 }
@@ -55,6 +68,7 @@ impl NodeManager {
         seed: u64,
     ) -> Self {
         let rng = Arc::new(Mutex::new(SplitMix64::seed_from_u64(seed)));
+        let source = parameters.clone();
 
         match parameter_sampling {
             ParameterSampling::Perfect => {
@@ -71,8 +85,10 @@ impl NodeManager {
 
         NodeManager {
             telemetry,
+            param_service: ParameterService::new(source, parameters.clone()),
             parameters: Arc::new(parameters),
             nodes: vec![],
+            channels: vec![],
             rng,
             seed,
         }
@@ -85,9 +101,14 @@ impl NodeManager {
         )
             -> Result<Box<dyn Node + Send>, Box<dyn std::error::Error + Send + Sync>>,
     {
+        let telemetry =
+            NodeTelemetry::new(self.telemetry.clone(), name, HashMap::new(), HashMap::new());
+        let channels = telemetry.channels.clone();
+
         let context = NodeContext::new(
-            NodeTelemetry::new(self.telemetry.clone(), HashMap::new(), HashMap::new()),
+            telemetry,
             self.parameters.clone(),
+            self.param_service.clone(),
             self.rng.clone(),
         );
 
@@ -95,6 +116,7 @@ impl NodeManager {
             name.to_string(),
             creator(context).expect(format!("Error creating node '{name}'").as_str()),
         ));
+        self.channels.push(channels);
 
         Ok(())
     }
@@ -107,10 +129,42 @@ impl NodeManager {
         &mut self.nodes
     }
 
+    /// Channels published & subscribed by each node, in the order of `nodes()`
+    pub fn channels(&self) -> Vec<NodeChannels> {
+        self.channels
+            .iter()
+            .map(|c| c.lock().unwrap().clone())
+            .collect()
+    }
+
+    /// Indices of the nodes in the order they must step, each after the publishers of the
+    /// channels it subscribes to. Fails if the nodes depend on each other in a cycle: one of
+    /// the subscriptions must then be made with `NodeTelemetry::subscribe_feedback`.
+    pub fn execution_order(&self) -> Result<Vec<usize>, Error> {
+        let channels = self.channels();
+        let nodes: Vec<(&str, &NodeChannels)> = self
+            .nodes
+            .iter()
+            .zip(channels.iter())
+            .map(|((name, _), c)| (name.as_str(), c))
+            .collect();
+
+        graph::execution_order(&nodes)
+    }
+
+    pub fn telemetry(&self) -> &TelemetryService {
+        &self.telemetry
+    }
+
+    /// Parameters the nodes were created with
     pub fn parameters(&self) -> Arc<ParameterMap> {
         self.parameters.clone()
     }
 
+    pub fn parameter_service(&self) -> &ParameterService {
+        &self.param_service
+    }
+
     pub fn seed(&self) -> u64 {
         self.seed
     }
@@ -126,6 +180,7 @@ pub struct NodeConfig {
 pub struct NodeContext {
     tm_dispatcher: NodeTelemetry,
     parameters: Arc<ParameterMap>,
+    param_service: ParameterService,
     rng: Arc<Mutex<SplitMix64>>,
 }
 
@@ -133,11 +188,13 @@ impl NodeContext {
     fn new(
         tm_dispatcher: NodeTelemetry,
         parameters: Arc<ParameterMap>,
+        param_service: ParameterService,
         rng: Arc<Mutex<SplitMix64>>,
     ) -> Self {
         Self {
             tm_dispatcher,
             parameters,
+            param_service,
             rng,
         }
     }
@@ -150,6 +207,11 @@ impl NodeContext {
         &self.parameters
     }
 
+    /// Parameters of the running simulation, for the nodes that apply changes live
+    pub fn parameter_service(&self) -> &ParameterService {
+        &self.param_service
+    }
+
     pub fn get_rng_256<R>(&self) -> R
     where
         R: SeedableRng<Seed = [u8; 32]>,
@@ -165,20 +227,27 @@ impl NodeContext {
 #[derive(Debug)]
 pub struct NodeTelemetry {
     telemetry: TelemetryService,
+    /// Name of the node, identifying its producers
+    node_name: String,
     input_map: HashMap<String, Path>,
     output_map: HashMap<String, Path>,
+    /// Channels published & subscribed through this node telemetry
+    channels: Arc<Mutex<NodeChannels>>,
 }
 
 impl NodeTelemetry {
     pub fn new(
         ts: TelemetryService,
+        node_name: &str,
         input_map: HashMap<String, Path>,
         output_map: HashMap<String, Path>,
     ) -> Self {
         NodeTelemetry {
             telemetry: ts,
+            node_name: node_name.to_string(),
             input_map,
             output_map,
+            channels: Arc::default(),
         }
     }
 
@@ -202,8 +271,40 @@ impl NodeTelemetry {
         &self,
         channel_name: &str,
     ) -> Result<TelemetrySender<T>, TelemetryError> {
-        self.telemetry
-            .publish::<T>(self.map_output(channel_name)?.as_str())
+        let path = self.map_output(channel_name)?;
+        let tx = self
+            .telemetry
+            .publish_as::<T>(&self.node_name, path.as_str())?;
+
+        self.channels
+            .lock()
+            .unwrap()
+            .published
+            .insert(path.as_str().to_string());
+        Ok(tx)
+    }
+
+    /// Publishes on a sticky channel, whose latest value is delivered to the late subscribers
+    pub fn publish_sticky<T: 'static + Send + Clone>(
+        &self,
+        channel_name: &str,
+        timestamp: Timestamp,
+        initial: T,
+    ) -> Result<TelemetrySender<T>, TelemetryError> {
+        let path = self.map_output(channel_name)?;
+        let tx = self.telemetry.publish_sticky_as::<T>(
+            &self.node_name,
+            path.as_str(),
+            timestamp,
+            initial,
+        )?;
+
+        self.channels
+            .lock()
+            .unwrap()
+            .published
+            .insert(path.as_str().to_string());
+        Ok(tx)
     }
 
     pub fn publish_mp<T: 'static + Send>(
@@ -211,7 +312,7 @@ impl NodeTelemetry {
         channel_name: &str,
     ) -> Result<TelemetrySender<T>, TelemetryError> {
         self.telemetry
-            .publish_mp::<T>(self.map_output(channel_name)?.as_str())
+            .publish_mp_as::<T>(&self.node_name, self.map_output(channel_name)?.as_str())
     }
 
     pub fn subscribe<T: 'static + Send>(
@@ -219,8 +320,59 @@ impl NodeTelemetry {
         channel_name: &str,
         capacity: Capacity,
     ) -> Result<TelemetryReceiver<T>, TelemetryError> {
-        self.telemetry
-            .subscribe::<T>(self.map_input(channel_name)?.as_str(), capacity)
+        let path = self.map_input(channel_name)?;
+        let rx = self.telemetry.subscribe::<T>(path.as_str(), capacity)?;
+
+        self.channels
+            .lock()
+            .unwrap()
+            .subscribed
+            .insert(path.as_str().to_string());
+        Ok(rx)
+    }
+
+    /// Subscribes to a channel read at the step after it is published, so that its publisher
+    /// does not need to step before this node. Closes the feedback loops of the model.
+    pub fn subscribe_feedback<T: 'static + Send>(
+        &self,
+        channel_name: &str,
+        capacity: Capacity,
+    ) -> Result<TelemetryReceiver<T>, TelemetryError> {
+        let path = self.map_input(channel_name)?;
+        let rx = self.telemetry.subscribe::<T>(path.as_str(), capacity)?;
+
+        self.channels
+            .lock()
+            .unwrap()
+            .feedback
+            .insert(path.as_str().to_string());
+        Ok(rx)
+    }
+
+    /// Reads the latest value of a channel, instead of queueing every sample
+    pub fn subscribe_latest<T: 'static + Send + Clone>(
+        &self,
+        channel_name: &str,
+    ) -> Result<LatestReceiver<T>, TelemetryError> {
+        let path = self.map_input(channel_name)?;
+        let rx = self.telemetry.subscribe_latest::<T>(path.as_str())?;
+
+        self.channels
+            .lock()
+            .unwrap()
+            .subscribed
+            .insert(path.as_str().to_string());
+        Ok(rx)
+    }
+
+    /// Subscribes to all the channels matching `pattern` (see
+    /// `TelemetryService::subscribe_prefix`). Does not order the node after their publishers.
+    pub fn subscribe_prefix(
+        &self,
+        pattern: &str,
+        capacity: Capacity,
+    ) -> Result<TelemetryReceiver<DynamicMessage>, TelemetryError> {
+        self.telemetry.subscribe_prefix(pattern, capacity)
     }
 
     pub fn subscribe_mp<T: 'static + Send>(
@@ -0,0 +1,39 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use chrono::TimeDelta;
+
+use crate::core::time::Clock;
+
+use super::{Node, StepResult};
+
+/// Slows the simulation down so that simulated time follows the wall clock, scaled by
+/// `speed`. Add it to a model to follow a run live.
+pub struct RealtimePacer {
+    speed: f64,
+    start: Option<Instant>,
+}
+
+impl RealtimePacer {
+    pub fn new(speed: f64) -> Self {
+        Self { speed, start: None }
+    }
+}
+
+impl Node for RealtimePacer {
+    fn step(&mut self, _i: usize, _dt: TimeDelta, clock: &dyn Clock) -> Result<StepResult> {
+        let start = *self.start.get_or_insert_with(Instant::now);
+
+        let target_s = clock.monotonic().elapsed_seconds_f64() / self.speed;
+        let wall_s = start.elapsed().as_secs_f64();
+
+        if target_s > wall_s {
+            thread::sleep(Duration::from_secs_f64(target_s - wall_s));
+        }
+
+        Ok(StepResult::Continue)
+    }
+}
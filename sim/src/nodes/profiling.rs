@@ -0,0 +1,176 @@
+use std::{fmt, time::Duration};
+
+use serde::Serialize;
+
+use crate::{core::time::Timestamp, telemetry::TelemetrySender};
+
+/// Wall-clock time taken by a node in a step
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeStepTime {
+    pub node: String,
+    pub step_us: f64,
+}
+
+/// Wall-clock time taken by each node in a step, in the order of execution
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StepProfile {
+    pub nodes: Vec<NodeStepTime>,
+    pub total_us: f64,
+}
+
+/// Step time statistics of a node over a run
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeStats {
+    pub node: String,
+    pub steps: usize,
+    pub total: Duration,
+    pub max: Duration,
+}
+
+impl NodeStats {
+    pub fn mean(&self) -> Duration {
+        self.total / (self.steps.max(1) as u32)
+    }
+}
+
+/// Step time statistics of the nodes of a run, slowest first
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfilingReport {
+    pub nodes: Vec<NodeStats>,
+    /// Time taken by all the steps
+    pub total: Duration,
+}
+
+impl fmt::Display for ProfilingReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<24} {:>8} {:>12} {:>12} {:>12} {:>7}",
+            "node", "steps", "mean [us]", "max [us]", "total [ms]", "share"
+        )?;
+
+        let total_s = self.total.as_secs_f64().max(f64::MIN_POSITIVE);
+        for n in self.nodes.iter() {
+            writeln!(
+                f,
+                "{:<24} {:>8} {:>12.1} {:>12.1} {:>12.1} {:>6.1}%",
+                n.node,
+                n.steps,
+                n.mean().as_secs_f64() * 1e6,
+                n.max.as_secs_f64() * 1e6,
+                n.total.as_secs_f64() * 1e3,
+                100.0 * n.total.as_secs_f64() / total_s
+            )?;
+        }
+
+        write!(f, "Total: {:.3} s", self.total.as_secs_f64())
+    }
+}
+
+/// Records the step time of the nodes, publishing it at each step
+pub struct NodeProfiler {
+    nodes: Vec<NodeStats>,
+    step: StepProfile,
+    tx_profile: Option<TelemetrySender<StepProfile>>,
+}
+
+impl NodeProfiler {
+    /// `nodes` in the order of execution
+    pub fn new<'a>(
+        nodes: impl IntoIterator<Item = &'a str>,
+        tx_profile: Option<TelemetrySender<StepProfile>>,
+    ) -> Self {
+        let nodes: Vec<NodeStats> = nodes
+            .into_iter()
+            .map(|node| NodeStats {
+                node: node.to_string(),
+                steps: 0,
+                total: Duration::ZERO,
+                max: Duration::ZERO,
+            })
+            .collect();
+
+        let step = StepProfile {
+            nodes: nodes
+                .iter()
+                .map(|n| NodeStepTime {
+                    node: n.node.clone(),
+                    step_us: 0.0,
+                })
+                .collect(),
+            total_us: 0.0,
+        };
+
+        Self {
+            nodes,
+            step,
+            tx_profile,
+        }
+    }
+
+    /// Records the step of the `k`-th node in the order of execution
+    pub fn record(&mut self, k: usize, duration: Duration) {
+        let stats = &mut self.nodes[k];
+        stats.steps += 1;
+        stats.total += duration;
+        stats.max = stats.max.max(duration);
+
+        self.step.nodes[k].step_us = duration.as_secs_f64() * 1e6;
+    }
+
+    /// Publishes the times of the step, at simulated time `t`
+    pub fn end_step(&mut self, t: Timestamp) {
+        self.step.total_us = self.step.nodes.iter().map(|n| n.step_us).sum();
+
+        if let Some(tx) = self.tx_profile.as_ref() {
+            tx.send(t, self.step.clone());
+        }
+        for n in self.step.nodes.iter_mut() {
+            n.step_us = 0.0;
+        }
+    }
+
+    pub fn report(&self) -> ProfilingReport {
+        let mut nodes = self.nodes.clone();
+        nodes.sort_by(|a, b| b.total.cmp(&a.total));
+
+        ProfilingReport {
+            total: nodes.iter().map(|n| n.total).sum(),
+            nodes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiler() {
+        let mut profiler = NodeProfiler::new(["rocket", "aero", "fsw"], None);
+
+        let us = Duration::from_micros;
+        for (rocket, aero, fsw) in [(10, 200, 30), (20, 400, 10)] {
+            profiler.record(0, us(rocket));
+            profiler.record(1, us(aero));
+            profiler.record(2, us(fsw));
+            profiler.end_step(Timestamp::from_micros(0));
+        }
+        // Stopped before the last node stepped
+        profiler.record(0, us(30));
+
+        let report = profiler.report();
+        let names: Vec<&str> = report.nodes.iter().map(|n| n.node.as_str()).collect();
+        assert_eq!(names, ["aero", "rocket", "fsw"]);
+        assert_eq!(report.total, us(700));
+
+        assert_eq!(report.nodes[0].mean(), us(300));
+        assert_eq!(report.nodes[0].max, us(400));
+        assert_eq!(report.nodes[1].steps, 3);
+        assert_eq!(report.nodes[1].mean(), us(20));
+
+        let table = report.to_string();
+        assert!(table.lines().nth(1).unwrap().starts_with("aero"));
+        assert!(table.ends_with("Total: 0.001 s"));
+    }
+}
@@ -0,0 +1,346 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use ratatui::{
+    Frame,
+    crossterm::event::{self, Event as TermEvent, KeyCode},
+    layout::{Constraint, Layout},
+    style::{Style, Stylize},
+    widgets::{Block, Paragraph, Row, Table, TableState},
+};
+
+use super::{ParameterMap, schema::ParameterSchema};
+
+/// Interactive terminal editor of a parameter file. Values are checked against their type and
+/// the schema ranges as they are entered, and the file is written back normalized (see
+/// [ParameterMap::to_toml_string]).
+pub struct ParameterEditor {
+    params: ParameterMap,
+    schema: ParameterSchema,
+    out_path: PathBuf,
+
+    /// Paths of the parameters, without the leading '.', in display order
+    paths: Vec<String>,
+    /// Paths of the parameters out of range
+    invalid: Vec<String>,
+    table_state: TableState,
+
+    /// Value being edited, as toml
+    input: Option<String>,
+    status: String,
+    status_error: bool,
+    modified: bool,
+    quit_requested: bool,
+}
+
+impl ParameterEditor {
+    pub fn new(params: ParameterMap, schema: ParameterSchema, out_path: &Path) -> Self {
+        let paths = params
+            .params()
+            .iter()
+            .map(|p| p.path().trim_start_matches('.').to_string())
+            .collect();
+
+        let mut editor = Self {
+            params,
+            schema,
+            out_path: out_path.to_path_buf(),
+            paths,
+            invalid: vec![],
+            table_state: TableState::default().with_selected(0),
+            input: None,
+            status: String::new(),
+            status_error: false,
+            modified: false,
+            quit_requested: false,
+        };
+
+        editor.validate();
+        if editor.invalid.is_empty() {
+            editor.set_status("Enter: edit, s: save, q: quit", false);
+        }
+
+        editor
+    }
+
+    pub fn params(&self) -> &ParameterMap {
+        &self.params
+    }
+
+    /// Runs the editor until the user quits
+    pub fn run_blocking(mut self) -> Result<()> {
+        let mut terminal = ratatui::init();
+
+        let res = loop {
+            if let Err(e) = terminal.draw(|frame| self.draw(frame)) {
+                break Err(e.into());
+            }
+
+            match event::read() {
+                Ok(TermEvent::Key(key)) => {
+                    if self.handle_key(key.code) {
+                        break Ok(());
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => break Err(e.into()),
+            }
+        };
+
+        ratatui::restore();
+        res
+    }
+
+    /// Returns true when the editor must be closed
+    fn handle_key(&mut self, key: KeyCode) -> bool {
+        if let Some(input) = self.input.as_mut() {
+            match key {
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Enter => self.commit(),
+                KeyCode::Esc => {
+                    self.input = None;
+                    self.set_status("Edit cancelled", false);
+                }
+                _ => {}
+            }
+
+            return false;
+        }
+
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => {
+                if !self.modified || self.quit_requested {
+                    return true;
+                }
+                self.quit_requested = true;
+                self.set_status(
+                    "Unsaved changes: press q again to quit without saving",
+                    true,
+                );
+                return false;
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.table_state.select_previous(),
+            KeyCode::Down | KeyCode::Char('j') => self.table_state.select_next(),
+            KeyCode::PageUp => self.table_state.scroll_up_by(20),
+            KeyCode::PageDown => self.table_state.scroll_down_by(20),
+            KeyCode::Home => self.table_state.select_first(),
+            KeyCode::End => self.table_state.select_last(),
+            KeyCode::Enter => {
+                if let Some(path) = self.selected() {
+                    let value = self.params.get_param(path).unwrap().value().val_toml();
+                    self.input = Some(value);
+                }
+            }
+            KeyCode::Char('s') => self.save(),
+            _ => {}
+        }
+
+        self.quit_requested = false;
+        false
+    }
+
+    fn selected(&self) -> Option<&str> {
+        self.table_state
+            .selected()
+            .and_then(|i| self.paths.get(i.min(self.paths.len().saturating_sub(1))))
+            .map(|p| p.as_str())
+    }
+
+    fn commit(&mut self) {
+        let (Some(path), Some(input)) = (self.selected().map(str::to_string), self.input.clone())
+        else {
+            return;
+        };
+
+        let current = self.params.get_param(&path).unwrap().value();
+        let value = match current.with_val_toml(&input) {
+            Ok(value) => value,
+            Err(_) => {
+                self.set_status(
+                    &format!("'{input}' is not a valid {} value", current.type_name()),
+                    true,
+                );
+                return;
+            }
+        };
+
+        if let Err(e) = self.schema.check(&path, &value) {
+            self.set_status(&e.to_string(), true);
+            return;
+        }
+
+        self.params.set_param(&path, value).unwrap();
+        self.input = None;
+        self.modified = true;
+        self.validate();
+        self.set_status(&format!("{path} updated"), false);
+    }
+
+    fn validate(&mut self) {
+        let errors = self.schema.validate(&self.params);
+
+        self.invalid = self
+            .paths
+            .iter()
+            .filter(|p| {
+                self.schema
+                    .check(p, self.params.get_param(p).unwrap().value())
+                    .is_err()
+            })
+            .cloned()
+            .collect();
+
+        if let Some(first) = errors.first() {
+            self.set_status(&format!("{} schema errors: {first}", errors.len()), true);
+        }
+    }
+
+    fn save(&mut self) {
+        if !self.invalid.is_empty() {
+            self.set_status(
+                &format!(
+                    "Not saved: {} parameters out of range ({})",
+                    self.invalid.len(),
+                    self.invalid[0]
+                ),
+                true,
+            );
+            return;
+        }
+
+        match fs::write(&self.out_path, self.params.to_toml_string()) {
+            Ok(()) => {
+                self.modified = false;
+                self.set_status(&format!("Saved to {}", self.out_path.display()), false);
+            }
+            Err(e) => self.set_status(&format!("Error saving: {e}"), true),
+        }
+    }
+
+    fn set_status(&mut self, status: &str, error: bool) {
+        self.status = status.to_string();
+        self.status_error = error;
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [body, info, footer] = Layout::vertical([
+            Constraint::Min(0),
+            Constraint::Length(3),
+            Constraint::Length(3),
+        ])
+        .areas(frame.area());
+
+        let rows = self.paths.iter().map(|path| {
+            let value = self.params.get_param(path).unwrap().value();
+            let spec = self.schema.spec(path);
+
+            let row = Row::new(vec![
+                path.clone(),
+                value.type_name().to_string(),
+                value.val_toml(),
+                spec.and_then(|s| s.unit.clone()).unwrap_or_default(),
+                spec.and_then(|s| s.range_str()).unwrap_or_default(),
+            ]);
+
+            if self.invalid.contains(path) {
+                row.red()
+            } else {
+                row
+            }
+        });
+
+        let modified = if self.modified { " [modified]" } else { "" };
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(45),
+                Constraint::Length(11),
+                Constraint::Min(20),
+                Constraint::Length(8),
+                Constraint::Length(16),
+            ],
+        )
+        .header(Row::new(vec!["parameter", "type", "value", "unit", "range"]).bold())
+        .row_highlight_style(Style::new().reversed())
+        .block(Block::bordered().title(format!(" {}{modified} ", self.out_path.display())));
+
+        frame.render_stateful_widget(table, body, &mut self.table_state);
+
+        let description = self
+            .selected()
+            .and_then(|p| self.schema.spec(p))
+            .and_then(|s| s.description.clone())
+            .unwrap_or_default();
+        frame.render_widget(Paragraph::new(description).block(Block::bordered()), info);
+
+        let footer_text = match &self.input {
+            Some(input) => Paragraph::new(format!("> {input}"))
+                .block(Block::bordered().title(" Enter: confirm, Esc: cancel ")),
+            None if self.status_error => {
+                Paragraph::new(self.status.clone().red()).block(Block::bordered())
+            }
+            None => Paragraph::new(self.status.clone()).block(Block::bordered()),
+        };
+        frame.render_widget(footer_text, footer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parameters::parse_string;
+
+    use super::*;
+
+    #[test]
+    fn test_edit() {
+        let params = parse_string(
+            "[sim]
+            dt = { val = 0.003, type = \"float\" }
+            name = { val = \"a\", type = \"str\" }"
+                .to_string(),
+        )
+        .unwrap();
+        let schema =
+            ParameterSchema::parse_string("[sim]\ndt = { unit = \"s\", min = 0.0, max = 0.1 }")
+                .unwrap();
+
+        let mut editor = ParameterEditor::new(params, schema, Path::new("params.toml"));
+
+        fn edit(editor: &mut ParameterEditor, text: &str) {
+            editor.handle_key(KeyCode::Enter);
+            editor.input.as_mut().unwrap().clear();
+            for c in text.chars() {
+                editor.handle_key(KeyCode::Char(c));
+            }
+            editor.handle_key(KeyCode::Enter);
+        }
+
+        // Out of range: the value is kept, and the edit stays open
+        edit(&mut editor, "0.5");
+        assert!(editor.input.is_some());
+        assert!(editor.status_error);
+        editor.handle_key(KeyCode::Esc);
+
+        // Wrong type
+        edit(&mut editor, "\"x\"");
+        assert!(editor.status_error);
+        editor.handle_key(KeyCode::Esc);
+
+        edit(&mut editor, "0.002");
+        assert!(editor.input.is_none());
+        assert_eq!(
+            editor.params().get_param("sim.dt").unwrap().value_float(),
+            Ok(0.002)
+        );
+
+        // Quitting with unsaved changes requires a confirmation
+        assert!(!editor.handle_key(KeyCode::Char('q')));
+        assert!(editor.handle_key(KeyCode::Char('q')));
+    }
+}
@@ -1,2 +1,5 @@
+pub mod editor;
 pub mod parameters;
-pub use parameters::*;
\ No newline at end of file
+pub mod schema;
+pub mod service;
+pub use parameters::*;
@@ -1,7 +1,12 @@
-use std::collections::{BTreeMap, btree_map};
-
-use rand::Rng;
-use rand_distr::{Distribution, Normal, Uniform};
+use std::{
+    collections::{BTreeMap, btree_map},
+    fmt::Write,
+};
+
+use nalgebra::{Matrix3, Quaternion, UnitQuaternion, Vector3, Vector4};
+use rand::{Rng, SeedableRng};
+use rand_distr::{Distribution, Normal, StandardNormal, Uniform};
+use rand_xoshiro::Xoshiro256StarStar;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use toml::{Table, Value};
@@ -25,6 +30,16 @@ pub enum Error {
 
     #[error("Element '{path}' is not a map")]
     NotAMap { path: String },
+
+    #[error("Bad parameter override '{0}', expected 'path=value'")]
+    BadOverride(String),
+
+    #[error("Parameter '{path}' has {len} elements, expected {expected}")]
+    BadLength {
+        path: String,
+        expected: usize,
+        len: usize,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -52,6 +67,22 @@ impl FloatDistribution {
             }
         }
     }
+
+    /// Value for the draws of a correlation group
+    fn value_at(&self, draw: GroupDraw) -> f64 {
+        match self {
+            FloatDistribution::Normal { mean, std_dev } => mean + std_dev * draw.normal,
+            FloatDistribution::Uniform { min, max } => min + (max - min) * draw.uniform,
+        }
+    }
+}
+
+/// Draws shared by all the parameters of a correlation group: a standard normal one for the
+/// normal distributions, a uniform one in [0, 1) for the uniform distributions
+#[derive(Debug, Clone, Copy)]
+struct GroupDraw {
+    normal: f64,
+    uniform: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -59,9 +90,33 @@ pub struct RandFloat {
     val: f64,
     sampled: Option<f64>,
     dist: FloatDistribution,
+
+    /// Named random stream the value is drawn from. Values of a stream do not change when
+    /// random parameters are added to or removed from other streams.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stream: Option<String>,
+    /// Seed of the stream, which then draws the same values in every run. Ignored without a
+    /// stream.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+    /// Correlation group within the stream: the values of a group are drawn together, at the
+    /// same quantile of their distributions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    group: Option<String>,
 }
 
 impl RandFloat {
+    pub fn new(val: f64, dist: FloatDistribution) -> Self {
+        RandFloat {
+            val,
+            sampled: None,
+            dist,
+            stream: None,
+            seed: None,
+            group: None,
+        }
+    }
+
     pub fn value(&self) -> f64 {
         self.val
     }
@@ -73,6 +128,14 @@ impl RandFloat {
     pub fn distribution(&self) -> FloatDistribution {
         self.dist.clone()
     }
+
+    pub fn stream(&self) -> Option<&str> {
+        self.stream.as_deref()
+    }
+
+    pub fn group(&self) -> Option<&str> {
+        self.group.as_deref()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -101,6 +164,77 @@ pub enum ParameterValue {
     RandFloatArray { val: Vec<RandFloat> },
 }
 
+impl ParameterValue {
+    /// Type name, as in the parameter files
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            ParameterValue::Bool { .. } => "bool",
+            ParameterValue::Int { .. } => "int",
+            ParameterValue::Float { .. } => "float",
+            ParameterValue::String { .. } => "str",
+            ParameterValue::RandFloat(_) => "randfloat",
+            ParameterValue::BoolArray { .. } => "bool[]",
+            ParameterValue::IntArray { .. } => "int[]",
+            ParameterValue::FloatArray { .. } => "float[]",
+            ParameterValue::StringArray { .. } => "str[]",
+            ParameterValue::RandFloatArray { .. } => "randfloat[]",
+        }
+    }
+
+    /// Inline table representation, as in the parameter files. Sampled values are not included.
+    pub fn to_toml(&self) -> String {
+        let mut table = self.to_table();
+
+        let mut fields = vec![];
+        for key in ["val", "type"] {
+            if let Some(v) = table.remove(key) {
+                fields.push(format!("{key} = {v}"));
+            }
+        }
+        fields.extend(table.iter().map(|(k, v)| format!("{k} = {v}")));
+
+        format!("{{ {} }}", fields.join(", "))
+    }
+
+    /// Representation of the `val` field alone
+    pub fn val_toml(&self) -> String {
+        self.to_table()["val"].to_string()
+    }
+
+    /// Parses a new value of the same type from its toml representation. Distributions of random
+    /// values are kept.
+    pub fn with_val_toml(&self, val: &str) -> Result<ParameterValue, Error> {
+        let mut parsed = toml::from_str::<Table>(&format!("val = {val}"))?;
+        let val = parsed
+            .remove("val")
+            .filter(|_| parsed.is_empty())
+            .ok_or_else(|| Error::BadToml("val".to_string()))?;
+
+        let mut table = self.to_table();
+        table.insert("val".to_string(), val);
+
+        Ok(Value::Table(table).try_into()?)
+    }
+
+    fn to_table(&self) -> Table {
+        let mut table = match Value::try_from(self) {
+            Ok(Value::Table(table)) => table,
+            _ => unreachable!("Parameter values are always serialized to a table"),
+        };
+
+        table.remove("sampled");
+        if let Some(Value::Array(vals)) = table.get_mut("val") {
+            for v in vals.iter_mut() {
+                if let Value::Table(t) = v {
+                    t.remove("sampled");
+                }
+            }
+        }
+
+        table
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Parameter {
     path: String,
@@ -112,6 +246,10 @@ impl Parameter {
         &self.path
     }
 
+    pub fn value(&self) -> &ParameterValue {
+        &self.value
+    }
+
     pub fn value_bool(&self) -> Result<bool, Error> {
         if let ParameterValue::Bool { val } = self.value {
             Ok(val)
@@ -200,6 +338,19 @@ impl Parameter {
         }
     }
 
+    /// Float array of exactly `len` elements
+    pub fn value_float_arr_len(&self, len: usize) -> Result<&[f64], Error> {
+        let val = self.value_float_arr()?;
+        if val.len() != len {
+            return Err(Error::BadLength {
+                path: self.path.clone(),
+                expected: len,
+                len: val.len(),
+            });
+        }
+        Ok(val)
+    }
+
     pub fn value_string_arr(&self) -> Result<&[String], Error> {
         if let ParameterValue::StringArray { val } = &self.value {
             Ok(val)
@@ -276,51 +427,258 @@ impl ParameterMap {
         Ok(self.get(rel_path)?.as_map()?)
     }
 
+    /// Vector from a float array of 3 elements
+    pub fn get_vector3(&self, rel_path: &str) -> Result<Vector3<f64>, Error> {
+        Ok(Vector3::from_column_slice(
+            self.get_param(rel_path)?.value_float_arr_len(3)?,
+        ))
+    }
+
+    /// Rotation from a float array of 4 elements, with the w component last. The quaternion is
+    /// normalized.
+    pub fn get_quaternion(&self, rel_path: &str) -> Result<UnitQuaternion<f64>, Error> {
+        Ok(UnitQuaternion::from_quaternion(Quaternion::from_vector(
+            Vector4::from_column_slice(self.get_param(rel_path)?.value_float_arr_len(4)?),
+        )))
+    }
+
+    /// Matrix from a float array of 9 elements, row by row
+    pub fn get_matrix3(&self, rel_path: &str) -> Result<Matrix3<f64>, Error> {
+        Ok(Matrix3::from_row_slice(
+            self.get_param(rel_path)?.value_float_arr_len(9)?,
+        ))
+    }
+
+    /// Replaces the value of an existing parameter
+    pub fn set_param(&mut self, rel_path: &str, value: ParameterValue) -> Result<(), Error> {
+        let not_found = || Error::NotFound {
+            path: append_path(&self.path, rel_path),
+        };
+
+        let mut parts = rel_path.split(".");
+
+        let mut elem = self
+            .map
+            .get_mut(parts.next().expect("Split cannot return an empty iterator"))
+            .ok_or_else(not_found)?;
+
+        for part in parts {
+            match elem {
+                ParameterTree::Node(n) => {
+                    elem = n.map.get_mut(part).ok_or_else(not_found)?;
+                }
+                ParameterTree::Leaf(_) => {
+                    return Err(not_found());
+                }
+            }
+        }
+
+        match elem {
+            ParameterTree::Leaf(param) => {
+                param.value = value;
+                Ok(())
+            }
+            ParameterTree::Node(m) => Err(Error::NotAParameter {
+                path: m.path.clone(),
+            }),
+        }
+    }
+
+    /// Applies an override given as `path=value`, eg. `/sim/rocket/engine/simple/total_impulse=2500`.
+    /// The path is separated by '/' or '.', the value is in toml syntax and keeps the type of the
+    /// parameter. Strings may be unquoted. Random parameters are fixed to the value.
+    pub fn apply_override(&mut self, arg: &str) -> Result<(), Error> {
+        let (path, val) = arg
+            .split_once('=')
+            .ok_or_else(|| Error::BadOverride(arg.to_string()))?;
+        let path = path.trim().trim_start_matches(['/', '.']).replace('/', ".");
+        let val = val.trim();
+
+        let param = self.get_param(&path)?;
+        let bad_cast = || Error::BadCast {
+            path: param.path.clone(),
+            dtype: param.value.type_name().to_string(),
+        };
+
+        let value = match &param.value {
+            ParameterValue::RandFloat(_) => {
+                let val = val.parse::<f64>().map_err(|_| bad_cast())?;
+                ParameterValue::RandFloat(RandFloat::new(
+                    val,
+                    FloatDistribution::Normal {
+                        mean: val,
+                        std_dev: 0.0,
+                    },
+                ))
+            }
+            ParameterValue::String { .. } => {
+                param
+                    .value
+                    .with_val_toml(val)
+                    .unwrap_or_else(|_| ParameterValue::String {
+                        val: val.to_string(),
+                    })
+            }
+            value => value.with_val_toml(val).map_err(|_| bad_cast())?,
+        };
+
+        self.set_param(&path, value)
+    }
+
+    /// All the parameters of the map & its submaps, sorted by path
+    pub fn params(&self) -> Vec<&Parameter> {
+        let mut params = vec![];
+        for (_, elem) in self.map.iter() {
+            match elem {
+                ParameterTree::Node(map) => params.extend(map.params()),
+                ParameterTree::Leaf(param) => params.push(param),
+            }
+        }
+        params
+    }
+
+    /// Normalized toml representation: one table per map, sorted keys and one line per
+    /// parameter. Comments of the source file are not preserved.
+    pub fn to_toml_string(&self) -> String {
+        let mut out = String::new();
+        self.write_toml(&mut out);
+        out
+    }
+
+    fn write_toml(&self, out: &mut String) {
+        let has_params = self
+            .map
+            .values()
+            .any(|e| matches!(e, ParameterTree::Leaf(_)));
+
+        // Empty maps still get a header, so that they are not lost
+        if !self.path.is_empty() && (has_params || self.map.is_empty()) {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            writeln!(out, "[{}]", self.path.trim_start_matches('.')).unwrap();
+        }
+
+        for (key, elem) in self.map.iter() {
+            if let ParameterTree::Leaf(param) = elem {
+                writeln!(out, "{key} = {}", param.value.to_toml()).unwrap();
+            }
+        }
+
+        for elem in self.map.values() {
+            if let ParameterTree::Node(map) = elem {
+                map.write_toml(out);
+            }
+        }
+    }
+
     pub fn iter(&self) -> ParameterMapIter<'_> {
         ParameterMapIter {
             iter: self.map.iter(),
         }
     }
 
+    /// Samples the random parameters. Each named stream is drawn from its own generator, seeded
+    /// with the seed of the stream if set, else from `rng` and the stream name. The parameters
+    /// without a stream are then drawn from `rng`, in path order.
     pub fn resample(&mut self, mut rng: impl Rng) {
-        self.resample_inner(&mut rng);
+        let mut streams: BTreeMap<Option<String>, Vec<&mut RandFloat>> = BTreeMap::new();
+        for rnd_float in self.rand_floats_mut() {
+            streams
+                .entry(rnd_float.stream.clone())
+                .or_default()
+                .push(rnd_float);
+        }
+        let default = streams.remove(&None).unwrap_or_default();
+
+        // Only drawn if needed, so that files without streams sample as they always did
+        if !streams.is_empty() {
+            let base_seed = rng.next_u64();
+
+            for (name, rnd_floats) in streams {
+                let name = name.unwrap_or_default();
+                let seed = rnd_floats
+                    .iter()
+                    .find_map(|r| r.seed)
+                    .unwrap_or_else(|| base_seed ^ stream_hash(&name));
+
+                sample_stream(rnd_floats, &mut Xoshiro256StarStar::seed_from_u64(seed));
+            }
+        }
+
+        sample_stream(default, &mut rng);
     }
 
-    fn resample_inner<R>(&mut self, rng: &mut R)
-    where
-        R: Rng,
-    {
-        for (_, param) in self.map.iter_mut() {
-            match param {
-                ParameterTree::Node(map) => {
-                    map.resample_inner(rng);
+    pub fn resample_perfect(&mut self) {
+        for rnd_float in self.rand_floats_mut() {
+            rnd_float.sampled = Some(rnd_float.val);
+        }
+    }
+
+    /// Sampled value of each random parameter, by path. Elements of arrays are named
+    /// `path[i]`. Parameters not sampled yet are skipped.
+    pub fn sampled_values(&self) -> Vec<(String, f64)> {
+        let mut values = vec![];
+        for param in self.params() {
+            let path = param.path.trim_start_matches('.');
+            match &param.value {
+                ParameterValue::RandFloat(rnd_float) => {
+                    values.extend(rnd_float.sampled.map(|v| (path.to_string(), v)));
                 }
-                ParameterTree::Leaf(param) => match &mut param.value {
-                    ParameterValue::RandFloat(rnd_float) => {
-                        rnd_float.sampled = Some(rnd_float.dist.sample(rng));
+                ParameterValue::RandFloatArray { val } => {
+                    for (i, rnd_float) in val.iter().enumerate() {
+                        values.extend(rnd_float.sampled.map(|v| (format!("{path}[{i}]"), v)));
                     }
-                    _ => {}
-                },
+                }
+                _ => {}
             }
         }
+        values
     }
-    pub fn resample_perfect(&mut self) {
-        for (_, param) in self.map.iter_mut() {
-            match param {
-                ParameterTree::Node(map) => {
-                    map.resample_perfect();
-                }
+
+    /// All the random values of the map & its submaps, in path order
+    fn rand_floats_mut(&mut self) -> Vec<&mut RandFloat> {
+        let mut rnd_floats = vec![];
+        for (_, elem) in self.map.iter_mut() {
+            match elem {
+                ParameterTree::Node(map) => rnd_floats.extend(map.rand_floats_mut()),
                 ParameterTree::Leaf(param) => match &mut param.value {
-                    ParameterValue::RandFloat(rnd_float) => {
-                        rnd_float.sampled = Some(rnd_float.val);
-                    }
+                    ParameterValue::RandFloat(rnd_float) => rnd_floats.push(rnd_float),
+                    ParameterValue::RandFloatArray { val } => rnd_floats.extend(val.iter_mut()),
                     _ => {}
                 },
             }
         }
+        rnd_floats
     }
 }
 
+/// Draws the values of a stream in order. The draws of a group are made at its first value.
+fn sample_stream<R: Rng>(rnd_floats: Vec<&mut RandFloat>, rng: &mut R) {
+    let mut groups: BTreeMap<String, GroupDraw> = BTreeMap::new();
+
+    for rnd_float in rnd_floats {
+        let sampled = match &rnd_float.group {
+            None => rnd_float.dist.sample(rng),
+            Some(group) => {
+                let draw = *groups.entry(group.clone()).or_insert_with(|| GroupDraw {
+                    normal: rng.sample(StandardNormal),
+                    uniform: rng.random(),
+                });
+                rnd_float.dist.value_at(draw)
+            }
+        };
+        rnd_float.sampled = Some(sampled);
+    }
+}
+
+/// FNV-1a hash of a stream name, stable across builds & platforms
+fn stream_hash(name: &str) -> u64 {
+    name.bytes().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
 #[derive(Default)]
 pub struct ParameterMapIter<'a> {
     iter: btree_map::Iter<'a, String, ParameterTree>,
@@ -410,6 +768,7 @@ fn append_path(root: &str, key: &str) -> String {
 mod tests {
     use core::f64;
 
+    use approx::assert_relative_eq;
     use toml::Value;
 
     use super::*;
@@ -650,6 +1009,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_vector_accessors() {
+        let str = r#"
+            v = { val = [1.0, 2.0, 3.0], type = "float[]" }
+            q = { val = [0.0, 0.0, 2.0, 0.0], type = "float[]" }
+            m = { val = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0], type = "float[]" }
+            f = { val = 1.0, type = "float" }
+        "#;
+        let map = parse_string(str.to_string()).unwrap();
+
+        assert_eq!(map.get_vector3("v"), Ok(Vector3::new(1.0, 2.0, 3.0)));
+        assert_eq!(
+            map.get_quaternion("q").map(|q| *q.quaternion()),
+            Ok(Quaternion::new(0.0, 0.0, 0.0, 1.0))
+        );
+        assert_eq!(map.get_matrix3("m").map(|m| m[(0, 1)]), Ok(2.0));
+
+        assert_eq!(
+            map.get_vector3("m"),
+            Err(Error::BadLength {
+                path: ".m".to_string(),
+                expected: 3,
+                len: 9
+            })
+        );
+        assert!(matches!(map.get_matrix3("v"), Err(Error::BadLength { .. })));
+        assert!(matches!(map.get_vector3("f"), Err(Error::BadCast { .. })));
+    }
+
     #[test]
     fn test_rand_float() {
         let str = "rand_float = { val = 1.0, type = \"randfloat\", dist = { type=\"normal\", mean = 1.0, std_dev = 1.0 } }";
@@ -666,6 +1054,9 @@ mod tests {
                             mean: 1.0,
                             std_dev: 1.0,
                         },
+                        stream: None,
+                        seed: None,
+                        group: None,
                     }),
                 }),
             )]),
@@ -684,6 +1075,9 @@ mod tests {
                         val: 1.0,
                         sampled: None,
                         dist: FloatDistribution::Uniform { min: 1.0, max: 1.0 },
+                        stream: None,
+                        seed: None,
+                        group: None,
                     }),
                 }),
             )]),
@@ -691,4 +1085,202 @@ mod tests {
 
         assert_eq!(parse_string(str.to_string()), Ok(expected));
     }
+
+    #[test]
+    fn test_resample_streams() {
+        let params = r#"
+            a = { val = 0.0, type = "randfloat", dist = { type = "normal", mean = 0.0, std_dev = 1.0 } }
+            [fins]
+            cant = { type = "randfloat[]", val = [
+                { val = 0.0, dist = { type = "normal", mean = 0.0, std_dev = 0.1 }, stream = "fins", group = "cant" },
+                { val = 1.0, dist = { type = "normal", mean = 1.0, std_dev = 0.2 }, stream = "fins", group = "cant" },
+            ] }
+            sweep = { val = 0.0, type = "randfloat", dist = { type = "uniform", min = 10.0, max = 20.0 }, stream = "fins", group = "cant" }
+            [wind]
+            offset = { val = 0.0, type = "randfloat", dist = { type = "normal", mean = 0.0, std_dev = 30.0 }, stream = "wind", seed = 42 }
+        "#;
+        let source = parse_string(params.to_string()).unwrap();
+
+        let sample = |source: &ParameterMap, seed: u64| {
+            let mut sampled = source.clone();
+            sampled.resample(Xoshiro256StarStar::seed_from_u64(seed));
+            sampled.sampled_values().into_iter().collect::<BTreeMap<_, _>>()
+        };
+
+        let values = sample(&source, 1);
+        assert_eq!(
+            values.keys().collect::<Vec<_>>(),
+            ["a", "fins.cant[0]", "fins.cant[1]", "fins.sweep", "wind.offset"]
+        );
+
+        // Drawn together: same quantile of the distributions
+        let z = values["fins.cant[0]"] / 0.1;
+        assert_relative_eq!((values["fins.cant[1]"] - 1.0) / 0.2, z, epsilon = 1e-12);
+        assert!((10.0..20.0).contains(&values["fins.sweep"]));
+
+        // Seeded stream: the same in every run
+        let other = sample(&source, 2);
+        assert_eq!(values["wind.offset"], other["wind.offset"]);
+        assert_ne!(values["fins.cant[0]"], other["fins.cant[0]"]);
+        assert_eq!(values, sample(&source, 1));
+
+        // Streams do not depend on the parameters of the others
+        let mut more = params.to_string();
+        more.push_str(
+            r#"b = { val = 0.0, type = "randfloat", dist = { type = "normal", mean = 0.0, std_dev = 1.0 } }"#,
+        );
+        let more = sample(&parse_string(more).unwrap(), 1);
+        assert_eq!(values["fins.cant[0]"], more["fins.cant[0]"]);
+        assert_eq!(values["fins.sweep"], more["fins.sweep"]);
+    }
+
+    #[test]
+    fn test_set_param() {
+        let str = "[nested]
+        hello_int = { val = 1, type = \"int\" }
+        ";
+
+        let mut params = parse_string(str.to_string()).unwrap();
+
+        params
+            .set_param("nested.hello_int", ParameterValue::Int { val: 2 })
+            .unwrap();
+        assert_eq!(
+            params.get_param("nested.hello_int").unwrap().value_int(),
+            Ok(2)
+        );
+
+        assert_eq!(
+            params.set_param("nested.missing", ParameterValue::Int { val: 2 }),
+            Err(Error::NotFound {
+                path: ".nested.missing".to_string()
+            })
+        );
+        assert_eq!(
+            params.set_param("nested", ParameterValue::Int { val: 2 }),
+            Err(Error::NotAParameter {
+                path: ".nested".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_apply_override() {
+        let str = "[sim.engine]
+        total_impulse = { val = 320, type = \"float\" }
+        model = { val = \"simple\", type = \"str\" }
+        mass = { val = 2, type = \"randfloat\", dist = { type = \"normal\", mean = 2, std_dev = 0.1 } }
+        ";
+
+        let mut params = parse_string(str.to_string()).unwrap();
+
+        params
+            .apply_override("/sim/engine/total_impulse=2500")
+            .unwrap();
+        params
+            .apply_override("sim.engine.model = tabulated")
+            .unwrap();
+        params.apply_override("sim.engine.mass=2.5").unwrap();
+
+        let engine = params.get_map("sim.engine").unwrap();
+        assert_eq!(
+            engine.get_param("total_impulse").unwrap().value_float(),
+            Ok(2500.0)
+        );
+        assert_eq!(
+            engine.get_param("model").unwrap().value_string(),
+            Ok("tabulated".to_string())
+        );
+        let mut sampled = params.clone();
+        sampled.resample(rand::rng());
+        assert_eq!(
+            sampled
+                .get_param("sim.engine.mass")
+                .unwrap()
+                .value_randfloat()
+                .unwrap()
+                .sampled(),
+            2.5
+        );
+
+        assert_eq!(
+            params.apply_override("sim.engine.total_impulse"),
+            Err(Error::BadOverride("sim.engine.total_impulse".to_string()))
+        );
+        assert_eq!(
+            params.apply_override("sim.engine.total_impulse=high"),
+            Err(Error::BadCast {
+                path: ".sim.engine.total_impulse".to_string(),
+                dtype: "float".to_string()
+            })
+        );
+        assert!(matches!(
+            params.apply_override("sim.engine.missing=1"),
+            Err(Error::NotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_to_toml_string() {
+        let str = "top = { val = 1, type = \"int\" }
+
+        [nested]
+        mass = { val = 2, type = \"randfloat\", dist = { type = \"normal\", mean = 2, std_dev = 0.1 } }
+        names = { val = [\"a\", \"b\"], type = \"str[]\" }
+
+        [nested.empty]
+
+        [nested.double]
+        quat = { val = [0.0, 0.0, 0.0, 1.0], type = \"float[]\" }
+        ";
+
+        let mut params = parse_string(str.to_string()).unwrap();
+        // Sampled values are not written
+        params.resample_perfect();
+
+        let normalized = params.to_toml_string();
+        assert_eq!(
+            normalized,
+            "top = { val = 1, type = \"int\" }
+
+[nested]
+mass = { val = 2.0, type = \"randfloat\", dist = { mean = 2.0, std_dev = 0.1, type = \"normal\" } }
+names = { val = [\"a\", \"b\"], type = \"str[]\" }
+
+[nested.double]
+quat = { val = [0.0, 0.0, 0.0, 1.0], type = \"float[]\" }
+
+[nested.empty]
+"
+        );
+
+        let mut reparsed = parse_string(normalized).unwrap();
+        reparsed.resample_perfect();
+        assert_eq!(reparsed, params);
+    }
+
+    #[test]
+    fn test_with_val_toml() {
+        let value = ParameterValue::FloatArray { val: vec![1.0] };
+        assert_eq!(
+            value.with_val_toml("[1, 2.5]"),
+            Ok(ParameterValue::FloatArray {
+                val: vec![1.0, 2.5]
+            })
+        );
+        assert!(value.with_val_toml("\"a\"").is_err());
+        assert!(value.with_val_toml("[1.0], x = 2").is_err());
+
+        let value = ParameterValue::RandFloat(RandFloat::new(
+            1.0,
+            FloatDistribution::Uniform { min: 0.0, max: 2.0 },
+        ));
+        assert_eq!(
+            value.with_val_toml("1.5"),
+            Ok(ParameterValue::RandFloat(RandFloat::new(
+                1.5,
+                FloatDistribution::Uniform { min: 0.0, max: 2.0 }
+            )))
+        );
+    }
 }
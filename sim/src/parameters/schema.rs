@@ -0,0 +1,226 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+use thiserror::Error;
+use toml::{Table, Value};
+
+use super::{ParameterMap, ParameterValue};
+
+#[derive(Debug, Clone, Error, PartialEq)]
+pub enum SchemaError {
+    #[error("Error deserializing the parameter schema")]
+    Deserialize(#[from] toml::de::Error),
+
+    #[error("Parameter schema does not have the right structure (error in '{0}')")]
+    BadToml(String),
+
+    #[error("'{path}' = {value} is below the minimum of {min}")]
+    BelowMin { path: String, value: f64, min: f64 },
+
+    #[error("'{path}' = {value} is above the maximum of {max}")]
+    AboveMax { path: String, value: f64, max: f64 },
+
+    #[error("Schema entry '{path}' does not match any parameter")]
+    Unused { path: String },
+}
+
+/// Unit & valid range of a parameter. Ranges apply to every element of arrays, and to the
+/// nominal value of random parameters.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ParameterSpec {
+    pub unit: Option<String>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub description: Option<String>,
+}
+
+impl ParameterSpec {
+    /// Range as text, e.g. "[0, 100]" or "[0, ∞)"
+    pub fn range_str(&self) -> Option<String> {
+        match (self.min, self.max) {
+            (None, None) => None,
+            (min, max) => Some(format!(
+                "{}{}, {}{}",
+                if min.is_some() { "[" } else { "(" },
+                min.map_or("-∞".to_string(), |v| v.to_string()),
+                max.map_or("∞".to_string(), |v| v.to_string()),
+                if max.is_some() { "]" } else { ")" },
+            )),
+        }
+    }
+}
+
+/// Units & ranges of the parameters, loaded from a toml file with the same structure as the
+/// parameter file, e.g.
+/// ```toml
+/// [sim.rocket.barometer]
+/// rate = { unit = "Hz", min = 1.0 }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParameterSchema {
+    specs: BTreeMap<String, ParameterSpec>,
+}
+
+impl ParameterSchema {
+    pub fn parse_string(toml_str: &str) -> Result<Self, SchemaError> {
+        let table = toml::from_str::<Table>(toml_str)?;
+
+        let mut specs = BTreeMap::new();
+        Self::parse_recursive(table, "", &mut specs)?;
+
+        Ok(Self { specs })
+    }
+
+    fn parse_recursive(
+        table: Table,
+        root: &str,
+        specs: &mut BTreeMap<String, ParameterSpec>,
+    ) -> Result<(), SchemaError> {
+        for (key, val) in table.into_iter() {
+            let path = if root.is_empty() {
+                key
+            } else {
+                format!("{root}.{key}")
+            };
+
+            match val {
+                Value::Table(val) => {
+                    if let Ok(spec) = val.clone().try_into::<ParameterSpec>() {
+                        specs.insert(path, spec);
+                    } else {
+                        Self::parse_recursive(val, &path, specs)?;
+                    }
+                }
+                _ => return Err(SchemaError::BadToml(path)),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Spec of the parameter at `path` (e.g. "sim.rocket.mass"), if any
+    pub fn spec(&self, path: &str) -> Option<&ParameterSpec> {
+        self.specs.get(path.trim_start_matches('.'))
+    }
+
+    /// Checks a value of the parameter at `path` against its range
+    pub fn check(&self, path: &str, value: &ParameterValue) -> Result<(), SchemaError> {
+        let Some(spec) = self.spec(path) else {
+            return Ok(());
+        };
+
+        let values: Vec<f64> = match value {
+            ParameterValue::Int { val } => vec![*val as f64],
+            ParameterValue::Float { val } => vec![*val],
+            ParameterValue::RandFloat(val) => vec![val.value()],
+            ParameterValue::IntArray { val } => val.iter().map(|v| *v as f64).collect(),
+            ParameterValue::FloatArray { val } => val.clone(),
+            ParameterValue::RandFloatArray { val } => val.iter().map(|v| v.value()).collect(),
+            _ => vec![],
+        };
+
+        let path = path.trim_start_matches('.').to_string();
+        for value in values {
+            if let Some(min) = spec.min.filter(|min| value < *min) {
+                return Err(SchemaError::BelowMin { path, value, min });
+            }
+            if let Some(max) = spec.max.filter(|max| value > *max) {
+                return Err(SchemaError::AboveMax { path, value, max });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks all the parameters, and reports the schema entries matching no parameter, which
+    /// are usually renamed or misspelled parameters
+    pub fn validate(&self, params: &ParameterMap) -> Vec<SchemaError> {
+        let all_params = params.params();
+
+        let mut errors: Vec<SchemaError> = all_params
+            .iter()
+            .filter_map(|p| self.check(p.path(), p.value()).err())
+            .collect();
+
+        for path in self.specs.keys() {
+            if !all_params
+                .iter()
+                .any(|p| p.path().trim_start_matches('.') == path)
+            {
+                errors.push(SchemaError::Unused { path: path.clone() });
+            }
+        }
+
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parameters::parse_string;
+
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let schema = ParameterSchema::parse_string(
+            "
+            [sim]
+            dt = { unit = \"s\", min = 0.0, max = 0.1 }
+
+            [sim.rocket]
+            xcg = { unit = \"m\", min = 0.0 }
+            missing = { unit = \"m\" }
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(schema.spec("sim.dt").unwrap().unit.as_deref(), Some("s"));
+        assert_eq!(
+            schema
+                .spec(".sim.rocket.xcg")
+                .unwrap()
+                .range_str()
+                .as_deref(),
+            Some("[0, ∞)")
+        );
+
+        let params = parse_string(
+            "
+            [sim]
+            dt = { val = 0.5, type = \"float\" }
+
+            [sim.rocket]
+            xcg = { val = [0.5, -1.0], type = \"float[]\" }
+            "
+            .to_string(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            schema.validate(&params),
+            vec![
+                SchemaError::AboveMax {
+                    path: "sim.dt".to_string(),
+                    value: 0.5,
+                    max: 0.1
+                },
+                SchemaError::BelowMin {
+                    path: "sim.rocket.xcg".to_string(),
+                    value: -1.0,
+                    min: 0.0
+                },
+                SchemaError::Unused {
+                    path: "sim.rocket.missing".to_string()
+                },
+            ]
+        );
+
+        assert!(
+            schema
+                .check("sim.dt", &ParameterValue::Float { val: 0.01 })
+                .is_ok()
+        );
+    }
+}
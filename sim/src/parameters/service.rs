@@ -0,0 +1,235 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, RwLock, Weak},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use anyhow::Result;
+use crossbeam_channel::{Receiver, Sender, unbounded};
+use log::{info, warn};
+
+use super::{Parameter, ParameterMap, parameters};
+
+#[derive(Debug)]
+struct Watcher {
+    /// Path of a parameter or of a map, without the leading '.'
+    path: String,
+    tx: Sender<Parameter>,
+}
+
+impl Watcher {
+    fn matches(&self, param_path: &str) -> bool {
+        let param_path = param_path.trim_start_matches('.');
+        param_path
+            .strip_prefix(self.path.as_str())
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    /// Parameters as read from the file, before sampling
+    source: Mutex<ParameterMap>,
+    /// Parameters of the running simulation
+    current: RwLock<Arc<ParameterMap>>,
+    watchers: Mutex<Vec<Watcher>>,
+}
+
+/// Parameters of a running simulation, which can be reloaded while it runs.
+///
+/// Only the parameters changed in the file since the previous load are updated, so values set
+/// after loading (forecast, scenarios) are kept until edited. Reloaded random parameters take
+/// their nominal value. Nodes that support live changes watch their parameters and apply the new
+/// values at their next step; the others keep the values they were created with.
+#[derive(Debug, Clone)]
+pub struct ParameterService {
+    inner: Arc<Inner>,
+}
+
+impl ParameterService {
+    /// `source` is the parameter file as read, `sampled` the parameters used by the simulation
+    pub fn new(source: ParameterMap, sampled: ParameterMap) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                source: Mutex::new(source),
+                current: RwLock::new(Arc::new(sampled)),
+                watchers: Mutex::new(vec![]),
+            }),
+        }
+    }
+
+    /// Current parameters of the simulation
+    pub fn parameters(&self) -> Arc<ParameterMap> {
+        self.inner.current.read().unwrap().clone()
+    }
+
+    /// Receives the new value of the parameter at `path` each time it changes. If `path` is a
+    /// map, receives all the changed parameters within it.
+    pub fn watch(&self, path: &str) -> Receiver<Parameter> {
+        let (tx, rx) = unbounded();
+        self.inner.watchers.lock().unwrap().push(Watcher {
+            path: path.trim_start_matches('.').to_string(),
+            tx,
+        });
+        rx
+    }
+
+    /// Applies the parameters of `source` that changed since the previous load. Parameters that
+    /// are not in the running simulation, or whose type changed, are ignored. Returns the
+    /// updated parameters.
+    pub fn reload(&self, source: ParameterMap) -> Vec<Parameter> {
+        let mut nominal = source.clone();
+        nominal.resample_perfect();
+
+        let mut prev_source = self.inner.source.lock().unwrap();
+        let mut current = self.inner.current.write().unwrap();
+        let mut updated = vec![];
+
+        for param in nominal.params() {
+            let path = param.path().trim_start_matches('.');
+            let Ok(prev) = prev_source.get_param(path) else {
+                warn!("Parameter '{path}' is not in the running simulation, ignored");
+                continue;
+            };
+            if source.get_param(path).ok() == Some(prev) {
+                continue;
+            }
+            if prev.value().type_name() != param.value().type_name() {
+                warn!(
+                    "Parameter '{path}' changed type from {} to {}, ignored",
+                    prev.value().type_name(),
+                    param.value().type_name()
+                );
+                continue;
+            }
+
+            if Arc::make_mut(&mut *current)
+                .set_param(path, param.value().clone())
+                .is_ok()
+            {
+                updated.push(param.clone());
+            }
+        }
+
+        *prev_source = source;
+        drop(current);
+        drop(prev_source);
+
+        let mut watchers = self.inner.watchers.lock().unwrap();
+        for param in updated.iter() {
+            info!(
+                "Parameter '{}' reloaded",
+                param.path().trim_start_matches('.')
+            );
+
+            // Watchers whose receiver is gone are dropped
+            watchers.retain(|w| !w.matches(param.path()) || w.tx.send(param.clone()).is_ok());
+        }
+
+        updated
+    }
+
+    /// Reloads the parameters from the file at `path`
+    pub fn reload_file(&self, path: &Path) -> Result<Vec<Parameter>> {
+        let source = parameters::parse_string(fs::read_to_string(path)?)?;
+        Ok(self.reload(source))
+    }
+
+    /// Reloads the parameter file at `path` whenever it is modified, checking every `period`.
+    /// Stops once all the handles to the service are dropped.
+    pub fn watch_file(&self, path: PathBuf, period: Duration) -> JoinHandle<()> {
+        let service = Arc::downgrade(&self.inner);
+
+        thread::spawn(move || {
+            let modified = || fs::metadata(&path).and_then(|m| m.modified()).ok();
+            let mut last_modified = modified();
+
+            loop {
+                thread::sleep(period);
+
+                let Some(inner) = Weak::upgrade(&service) else {
+                    return;
+                };
+
+                let t = modified();
+                if t == last_modified {
+                    continue;
+                }
+                last_modified = t;
+
+                if let Err(e) = (ParameterService { inner }).reload_file(&path) {
+                    warn!(
+                        "Error reloading the parameters from '{}': {e}",
+                        path.display()
+                    );
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parameters::parse_string;
+
+    const PARAMS: &str = r#"
+        [servo]
+        natural_freq = { val = 25.0, type = "float" }
+        damping = { val = 0.8, type = "float" }
+
+        [engine]
+        thrust_scale = { val = 1.0, type = "randfloat", dist = { type = "normal", mean = 1.0, std_dev = 0.1 } }
+    "#;
+
+    #[test]
+    fn test_reload() {
+        let source = parse_string(PARAMS.to_string()).unwrap();
+        let mut sampled = source.clone();
+        sampled.resample_perfect();
+
+        let service = ParameterService::new(source, sampled);
+        let rx_servo = service.watch("servo");
+        let rx_damping = service.watch("servo.damping");
+        let rx_engine = service.watch("engine");
+
+        let edited = PARAMS.replace("val = 0.8", "val = 0.7").replace(
+            "val = 1.0, type = \"randfloat\"",
+            "val = 1.1, type = \"randfloat\"",
+        );
+        let updated = service.reload(parse_string(edited.clone()).unwrap());
+        assert_eq!(updated.len(), 2);
+
+        let params = service.parameters();
+        assert_eq!(
+            params.get_param("servo.damping").unwrap().value_float(),
+            Ok(0.7)
+        );
+        assert_eq!(
+            params
+                .get_param("servo.natural_freq")
+                .unwrap()
+                .value_float(),
+            Ok(25.0)
+        );
+        assert_eq!(
+            params
+                .get_param("engine.thrust_scale")
+                .unwrap()
+                .value_randfloat()
+                .unwrap()
+                .sampled(),
+            1.1
+        );
+
+        assert_eq!(rx_servo.try_iter().count(), 1);
+        assert_eq!(rx_damping.try_recv().unwrap().value_float(), Ok(0.7));
+        assert_eq!(rx_engine.try_iter().count(), 1);
+
+        // Unchanged file: nothing to apply
+        assert!(service.reload(parse_string(edited).unwrap()).is_empty());
+        assert!(rx_servo.try_recv().is_err());
+    }
+}
@@ -1,72 +1,227 @@
 use std::{
+    fmt::Write,
     fs,
     path::{Path, PathBuf},
+    sync::Arc,
     thread,
     time::{Duration, Instant},
 };
 
 pub use anyhow::Result;
-use chrono::TimeDelta;
+use anyhow::anyhow;
+use chrono::{Local, TimeDelta};
 use log::info;
 use rand::{TryRngCore, rngs::OsRng};
 use rerun::log::ChunkBatcherConfig;
 
 use crate::{
-    crater::logging::rerun::{RerunLogConfig, RerunLoggerBuilder},
+    crater::{
+        aero::forecast,
+        channels,
+        logging::{
+            csv::{CsvLogConfig, CsvLoggerBuilder},
+            filter::ChannelLogFilter,
+            rerun::{RerunLogConfig, RerunLoggerBuilder},
+        },
+        recording,
+    },
     model::ModelBuilder,
     nodes::{FtlOrderedExecutor, NodeManager, ParameterSampling},
-    parameters::parameters,
-    telemetry::TelemetryService,
+    parameters::{ParameterMap, parameters},
+    telemetry::{TelemetryService, diagnostics::ChannelMonitor, ordering::DeliveryOrdering},
 };
 
+/// Where the Rerun log goes
+#[derive(Debug, Clone, PartialEq)]
 pub enum LogOutput {
+    /// Streamed to a running viewer
     Ui,
+    /// Recorded to a .rrd file, to be opened in the viewer later
     File(PathBuf),
 }
 
+impl LogOutput {
+    /// Reads the output from `sim.rerun`. Recordings are named after the run and its start time,
+    /// eg. `runs/crater_20250914_103000.rrd`.
+    pub fn from_params(params: &ParameterMap) -> Result<Self> {
+        let rerun = params.get_map("sim.rerun")?;
+
+        match rerun.get_param("output")?.value_string()?.as_str() {
+            "viewer" => Ok(LogOutput::Ui),
+            "file" => {
+                let dir = PathBuf::from(rerun.get_param("dir")?.value_string()?);
+                let run_name = rerun.get_param("run_name")?.value_string()?;
+                let start = Local::now().format("%Y%m%d_%H%M%S");
+
+                Ok(LogOutput::File(dir.join(format!("{run_name}_{start}.rrd"))))
+            }
+            unknown => Err(anyhow!("Unknown Rerun output: {unknown}")),
+        }
+    }
+}
+
+enum RunLogger {
+    Rerun {
+        config: Box<dyn RerunLogConfig>,
+        builder: RerunLoggerBuilder,
+        output: LogOutput,
+    },
+    Csv(CsvLoggerBuilder),
+}
+
 pub struct SingleThreadedRunner {
     nm: NodeManager,
-    log_config: Box<dyn RerunLogConfig>,
-    log_builder: RerunLoggerBuilder,
+    logger: RunLogger,
+    /// Parameters of the run after the overrides, as toml
+    effective_params: String,
+    params_path: PathBuf,
+    reload_params: bool,
 }
 
 impl SingleThreadedRunner {
+    /// Runner logging the telemetry to Rerun, streamed to the viewer or recorded to a file as
+    /// configured in `sim.rerun`
     pub fn new(
         model: impl ModelBuilder,
         params: &Path,
+        overrides: &[String],
         log_config: Box<dyn RerunLogConfig>,
         param_sampling: ParameterSampling,
         seed: Option<u64>,
+        ordering: Option<Arc<DeliveryOrdering>>,
     ) -> Result<Self> {
+        let (nm, ts, effective_params) =
+            Self::build_model(model, params, overrides, param_sampling, seed, ordering)?;
+
+        let mut builder = RerunLoggerBuilder::new(&ts)
+            .with_filter(ChannelLogFilter::from_params(&nm.parameters())?);
+        log_config.subscribe_telem(&mut builder)?;
+        let output = LogOutput::from_params(&nm.parameters())?;
+
+        Ok(Self {
+            nm,
+            logger: RunLogger::Rerun {
+                config: log_config,
+                builder,
+                output,
+            },
+            effective_params,
+            params_path: params.to_path_buf(),
+            reload_params: false,
+        })
+    }
+
+    /// Runner logging the telemetry to one CSV file per channel in `out_dir`
+    pub fn with_csv(
+        model: impl ModelBuilder,
+        params: &Path,
+        overrides: &[String],
+        log_config: Box<dyn CsvLogConfig>,
+        out_dir: &Path,
+        param_sampling: ParameterSampling,
+        seed: Option<u64>,
+        ordering: Option<Arc<DeliveryOrdering>>,
+    ) -> Result<Self> {
+        let (nm, ts, effective_params) =
+            Self::build_model(model, params, overrides, param_sampling, seed, ordering)?;
+
+        let mut builder = CsvLoggerBuilder::new(&ts, out_dir)?
+            .with_filter(ChannelLogFilter::from_params(&nm.parameters())?);
+        log_config.subscribe_csv(&mut builder)?;
+
+        fs::write(out_dir.join("params.toml"), &effective_params)?;
+
+        Ok(Self {
+            nm,
+            logger: RunLogger::Csv(builder),
+            effective_params,
+            params_path: params.to_path_buf(),
+            reload_params: false,
+        })
+    }
+
+    /// Builds the model, with the parameters read from `params` and then overridden. Returns
+    /// the effective parameters as toml, with the seed, the overrides and the sampled values in
+    /// the header.
+    fn build_model(
+        model: impl ModelBuilder,
+        params: &Path,
+        overrides: &[String],
+        param_sampling: ParameterSampling,
+        seed: Option<u64>,
+        ordering: Option<Arc<DeliveryOrdering>>,
+    ) -> Result<(NodeManager, TelemetryService, String)> {
         info!("Reading parameters from '{}'", params.display());
 
         let params_toml = fs::read_to_string(params)?;
-        let params = parameters::parse_string(params_toml)?;
+        let mut params = parameters::parse_string(params_toml)?;
+        forecast::apply_forecast(&mut params)?;
+
+        for arg in overrides.iter() {
+            params.apply_override(arg)?;
+            info!("Parameter override: {arg}");
+        }
 
         let ts = TelemetryService::default();
+        channels::register_units(&ts);
+        recording::register_recordables(&ts);
+        channels::configure_access(&ts, &params)?;
+        if let Some(ordering) = ordering {
+            ts.set_ordering(ordering);
+        }
 
         info!("Initalizing node manager");
 
         let seed = seed.unwrap_or(OsRng {}.try_next_u64().unwrap());
+        info!("Simulation seed is {seed}");
+
         let mut nm = NodeManager::new(ts.clone(), params.clone(), param_sampling, seed);
 
+        let mut effective_params = format!("# Seed: {seed}\n");
+        for arg in overrides.iter() {
+            writeln!(effective_params, "# Override: {arg}")?;
+        }
+        for (path, value) in nm.parameters().sampled_values() {
+            writeln!(effective_params, "# Sampled: {path} = {value}")?;
+        }
+        effective_params.push('\n');
+        effective_params.push_str(&params.to_toml_string());
+
         model.build(&mut nm)?;
 
-        let mut log_builder = RerunLoggerBuilder::new(&ts);
-        log_config.subscribe_telem(&mut log_builder)?;
+        let diagnostics = params.get_map("sim.diagnostics")?;
+        if diagnostics.get_param("enabled")?.value_bool()? {
+            let period = diagnostics.get_param("period")?.value_float()?;
+            let ts = ts.clone();
+            nm.add_node("channel_monitor", move |ctx| {
+                Ok(Box::new(ChannelMonitor::new(
+                    ts,
+                    ctx.telemetry().publish(channels::sim::CHANNELS)?,
+                    TimeDelta::microseconds((period * 1e6) as i64),
+                )))
+            })?;
+        }
 
-        Ok(Self {
-            nm,
-            log_builder,
-            log_config,
-        })
+        Ok((nm, ts, effective_params))
+    }
+
+    /// Applies the changes to the parameter file while the simulation runs, to the nodes that
+    /// support it
+    pub fn with_param_reload(mut self) -> Self {
+        self.reload_params = true;
+        self
     }
 
     pub fn run_blocking(self) -> Result<()> {
         let params = self.nm.parameters();
+
+        if self.reload_params {
+            info!("Watching '{}' for changes", self.params_path.display());
+            self.nm
+                .parameter_service()
+                .watch_file(self.params_path.clone(), Duration::from_millis(500));
+        }
         let nm = self.nm;
-        let log_builder = self.log_builder;
-        let log_config = self.log_config;
 
         let simulation = thread::spawn(move || -> Result<()> {
             let dt_sec = params.get_param("sim.dt")?.value_float()?;
@@ -78,36 +233,105 @@ impl SingleThreadedRunner {
             info!("Running simulation!");
 
             let start_time = Instant::now();
-            FtlOrderedExecutor::run_blocking(nm, TimeDelta::microseconds(dt))?;
+            let report = FtlOrderedExecutor::run_profiled(nm, TimeDelta::microseconds(dt))?;
 
             let duration = (Instant::now() - start_time).as_secs_f64();
 
             info!("Simulation ended! Duration: {duration:.6} s");
+            info!("Node step times:\n{report}");
 
             Ok(())
         });
 
-        info!("Connecting to Rerun interface...");
+        match self.logger {
+            RunLogger::Rerun {
+                config,
+                builder,
+                output,
+            } => Self::log_rerun(config, builder, output, &self.effective_params)?,
+            RunLogger::Csv(builder) => {
+                builder.build().log_blocking()?;
+                info!("CSV log completed");
+            }
+        }
+
+        simulation.join().unwrap()?;
+
+        Ok(())
+    }
 
+    fn log_rerun(
+        log_config: Box<dyn RerunLogConfig>,
+        log_builder: RerunLoggerBuilder,
+        output: LogOutput,
+        effective_params: &str,
+    ) -> Result<()> {
         let mut batcher_cfg = ChunkBatcherConfig::default();
         batcher_cfg.flush_tick = Duration::from_millis(50);
         batcher_cfg.apply_env()?; // Values specified in env take precedence
 
-        let mut rec = rerun::RecordingStreamBuilder::new("crater")
-            .batcher_config(batcher_cfg)
-            .connect_grpc_opts(
-                "rerun+http://127.0.0.1:9876/proxy",
-                Some(Duration::from_secs(60)),
-            )?;
+        let rec_builder = rerun::RecordingStreamBuilder::new("crater").batcher_config(batcher_cfg);
+
+        let mut rec = match &output {
+            LogOutput::Ui => {
+                info!("Connecting to Rerun interface...");
+                let rec = rec_builder.connect_grpc_opts(
+                    "rerun+http://127.0.0.1:9876/proxy",
+                    Some(Duration::from_secs(60)),
+                )?;
+                info!("Rerun connected!");
+                rec
+            }
+            LogOutput::File(path) => {
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir)?;
+                }
+                info!("Recording Rerun log to '{}'", path.display());
+                rec_builder.save(path)?
+            }
+        };
 
-        info!("Rerun connected!");
         log_config.init_rec(&mut rec)?;
+        rec.log_static("params", &rerun::TextDocument::new(effective_params))?;
 
         let logger = log_builder.build(rec)?;
         logger.log_blocking()?;
 
-        info!("Rerun log completed");
-        simulation.join().unwrap()?;
+        match output {
+            LogOutput::Ui => info!("Rerun log completed"),
+            LogOutput::File(path) => info!("Rerun log saved to '{}'", path.display()),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_output() -> Result<()> {
+        let params = |output: &str| {
+            parameters::parse_string(format!(
+                "[sim.rerun]\n\
+                 output = {{ val = \"{output}\", type = \"str\" }}\n\
+                 dir = {{ val = \"runs\", type = \"str\" }}\n\
+                 run_name = {{ val = \"flight\", type = \"str\" }}"
+            ))
+        };
+
+        assert_eq!(LogOutput::from_params(&params("viewer")?)?, LogOutput::Ui);
+
+        let LogOutput::File(path) = LogOutput::from_params(&params("file")?)? else {
+            panic!("Expected a file output");
+        };
+        assert_eq!(path.parent(), Some(Path::new("runs")));
+        let name = path.file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("flight_") && name.ends_with(".rrd"));
+        assert_eq!(name.len(), "flight_20250914_103000.rrd".len());
+
+        assert!(LogOutput::from_params(&params("tcp")?).is_err());
 
         Ok(())
     }
@@ -0,0 +1,182 @@
+use serde::Serialize;
+
+use crate::crater::logging::report::FlightReport;
+
+/// Time given to the roll rate to settle, in `Assertion::RollRateBelow` [s]
+pub const ROLL_SETTLING_S: f64 = 3.0;
+
+/// Check on the outcome of a scenario run
+#[derive(Debug, Clone)]
+pub enum Assertion {
+    ApogeeBetween { min_m: f64, max_m: f64 },
+    MaxAccelBelow { max_g: f64 },
+    /// Horizontal distance of the landing point from the launch site
+    LandsWithin { radius_m: f64 },
+    /// An event whose description contains the pattern is emitted during the run
+    EventOccurs(&'static str),
+    /// No event whose description contains the pattern is emitted during the run
+    EventAbsent(&'static str),
+    /// Events matching each of the patterns are emitted in this order
+    EventSequence(Vec<&'static str>),
+    /// The roll rate stays below the limit from `ROLL_SETTLING_S` after the event matching the
+    /// pattern until apogee
+    RollRateBelow {
+        after: &'static str,
+        max_deg_s: f64,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AssertionResult {
+    pub description: String,
+    pub passed: bool,
+    pub actual: String,
+}
+
+impl Assertion {
+    pub fn description(&self) -> String {
+        match self {
+            Assertion::ApogeeBetween { min_m, max_m } => {
+                format!("apogee in [{min_m:.0}, {max_m:.0}] m")
+            }
+            Assertion::MaxAccelBelow { max_g } => format!("max acceleration < {max_g:.1} g"),
+            Assertion::LandsWithin { radius_m } => {
+                format!("lands within {radius_m:.0} m of the launch site")
+            }
+            Assertion::EventOccurs(pattern) => format!("event '{pattern}' occurs"),
+            Assertion::EventAbsent(pattern) => format!("event '{pattern}' does not occur"),
+            Assertion::EventSequence(patterns) => {
+                format!("events in order: {}", patterns.join(", "))
+            }
+            Assertion::RollRateBelow { after, max_deg_s } => format!(
+                "roll rate < {max_deg_s:.1} deg/s from {ROLL_SETTLING_S:.1} s after '{after}'"
+            ),
+        }
+    }
+
+    pub fn check(&self, report: &FlightReport) -> AssertionResult {
+        let m = &report.metrics;
+        let find_event = |pattern: &str| {
+            report
+                .events
+                .iter()
+                .find(|(_, ev)| ev.contains(pattern))
+                .map(|(t, _)| *t)
+        };
+
+        let (passed, actual) = match self {
+            Assertion::ApogeeBetween { min_m, max_m } => (
+                m.apogee_m >= *min_m && m.apogee_m <= *max_m,
+                format!("{:.1} m", m.apogee_m),
+            ),
+            Assertion::MaxAccelBelow { max_g } => {
+                (m.max_accel_g < *max_g, format!("{:.2} g", m.max_accel_g))
+            }
+            Assertion::LandsWithin { radius_m } => {
+                let dist = m.landing_n_m.xy().norm();
+                (dist <= *radius_m, format!("{dist:.1} m"))
+            }
+            Assertion::EventOccurs(pattern) => match find_event(pattern) {
+                Some(t) => (true, format!("at T+{t:.3} s")),
+                None => (false, "not found".to_string()),
+            },
+            Assertion::EventAbsent(pattern) => match find_event(pattern) {
+                Some(t) => (false, format!("at T+{t:.3} s")),
+                None => (true, "not found".to_string()),
+            },
+            Assertion::EventSequence(patterns) => {
+                // Each pattern is searched after the event matching the previous one
+                let mut events = report.events.iter();
+                let mut times = vec![];
+                let mut missing = None;
+
+                for pattern in patterns.iter() {
+                    match events.find(|(_, ev)| ev.contains(pattern)) {
+                        Some((t, _)) => times.push(format!("T+{t:.3} s")),
+                        None => {
+                            missing = Some(pattern);
+                            break;
+                        }
+                    }
+                }
+
+                match missing {
+                    None => (true, times.join(", ")),
+                    Some(pattern) => (false, format!("'{pattern}' not found in order")),
+                }
+            }
+            Assertion::RollRateBelow { after, max_deg_s } => match find_event(after) {
+                Some(t_event) => {
+                    let max_roll_rate = report
+                        .roll_rate_deg_s
+                        .iter()
+                        .filter(|(t, _)| *t >= t_event + ROLL_SETTLING_S && *t <= m.t_apogee_s)
+                        .map(|(_, p)| p.abs())
+                        .fold(0.0, f64::max);
+
+                    (
+                        max_roll_rate < *max_deg_s,
+                        format!("{max_roll_rate:.1} deg/s"),
+                    )
+                }
+                None => (false, format!("'{after}' not found")),
+            },
+        };
+
+        AssertionResult {
+            description: self.description(),
+            passed,
+            actual,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roll_rate_below() {
+        // Roll rate decaying from 100 deg/s at burnout (T+4 s), apogee at T+15 s
+        let mut report = FlightReport {
+            title: String::new(),
+            pos_n_m: vec![],
+            speed_m_s: vec![],
+            mach: vec![],
+            roll_rate_deg_s: (0..200)
+                .map(|i| {
+                    let t = i as f64 * 0.1;
+                    (t, 100.0 * (-(t - 4.0).max(0.0)).exp())
+                })
+                .collect(),
+            events: vec![(4.0, "[gnc] FlightBurnout from Fmm".to_string())],
+            metrics: Default::default(),
+            nav_errors: None,
+            descent: Default::default(),
+            compliance: vec![],
+            parameters: vec![],
+        };
+        report.metrics.t_apogee_s = 15.0;
+
+        let check = |max_deg_s| {
+            Assertion::RollRateBelow {
+                after: "FlightBurnout",
+                max_deg_s,
+            }
+            .check(&report)
+            .passed
+        };
+
+        // ~5 deg/s when the roll rate is checked from, 3 s after burnout
+        assert!(check(10.0));
+        assert!(!check(1.0));
+        assert!(
+            !Assertion::RollRateBelow {
+                after: "Liftoff",
+                max_deg_s: 1000.0,
+            }
+            .check(&report)
+            .passed
+        );
+    }
+}
@@ -0,0 +1,283 @@
+use crate::{
+    model::{DegradedSensorsCrater, DualFcCrater, OpenLoopCrater},
+    parameters::{FloatDistribution, ParameterValue, RandFloat},
+};
+
+use super::{Assertion, Scenario};
+
+fn float_arr(val: &[f64]) -> ParameterValue {
+    ParameterValue::FloatArray { val: val.to_vec() }
+}
+
+/// Nominal flight checks, shared by the scenarios where the rocket is expected to fly
+pub(super) fn nominal_flight() -> Vec<Assertion> {
+    vec![
+        Assertion::EventOccurs("StartEngine"),
+        Assertion::EventOccurs("rocket: FlyingRamp -> FlyingFree"),
+        Assertion::ApogeeBetween {
+            min_m: 100.0,
+            max_m: 5000.0,
+        },
+        Assertion::MaxAccelBelow { max_g: 30.0 },
+    ]
+}
+
+/// Built-in scenarios
+pub fn catalog() -> Vec<Scenario> {
+    vec![
+        Scenario {
+            name: "nominal",
+            description: "Nominal open loop flight, no wind",
+            model: Box::new(OpenLoopCrater {}),
+            overrides: vec![(
+                "sim.wind.model",
+                ParameterValue::String {
+                    val: "none".to_string(),
+                },
+            )],
+            script: Some("config/openloop_seq.toml"),
+            assertions: [
+                nominal_flight(),
+                vec![Assertion::LandsWithin { radius_m: 3000.0 }],
+            ]
+            .concat(),
+        },
+        Scenario {
+            name: "gusty",
+            description: "Open loop flight through strong, rapidly shifting wind layers",
+            model: Box::new(OpenLoopCrater {}),
+            overrides: vec![
+                (
+                    "sim.wind.model",
+                    ParameterValue::String {
+                        val: "tabulated".to_string(),
+                    },
+                ),
+                (
+                    "sim.wind.tabulated.altitude_m",
+                    float_arr(&[0.0, 50.0, 150.0, 300.0, 500.0, 800.0, 1200.0, 3000.0]),
+                ),
+                (
+                    "sim.wind.tabulated.speed_m_s",
+                    float_arr(&[6.0, 12.0, 4.0, 15.0, 8.0, 18.0, 10.0, 20.0]),
+                ),
+                (
+                    "sim.wind.tabulated.direction_deg",
+                    float_arr(&[270.0, 300.0, 240.0, 290.0, 330.0, 260.0, 300.0, 280.0]),
+                ),
+            ],
+            script: Some("config/openloop_seq.toml"),
+            assertions: [
+                nominal_flight(),
+                vec![Assertion::LandsWithin { radius_m: 8000.0 }],
+            ]
+            .concat(),
+        },
+        Scenario {
+            name: "baro_failure",
+            description: "Barometer dead on the pad: ADA cannot calibrate, launch must be inhibited",
+            model: Box::new(DegradedSensorsCrater {
+                failed: vec!["ideal_press"],
+            }),
+            overrides: vec![("sim.rocket.max_t", ParameterValue::Float { val: 10.0 })],
+            script: None,
+            assertions: vec![
+                Assertion::EventAbsent("AdaCalibrationDone"),
+                Assertion::EventAbsent("StartEngine"),
+                Assertion::ApogeeBetween {
+                    min_m: -1.0,
+                    max_m: 1.0,
+                },
+            ],
+        },
+        Scenario {
+            name: "imu_mag_failure",
+            description: "IMU and magnetometer dead: navigation blind, open loop flight unaffected",
+            model: Box::new(DegradedSensorsCrater {
+                failed: vec!["ideal_imu", "ideal_mag"],
+            }),
+            overrides: vec![],
+            script: Some("config/openloop_seq.toml"),
+            assertions: nominal_flight(),
+        },
+        Scenario {
+            name: "abort",
+            description: "Launch scrubbed by the ground at the end of the arming delay",
+            model: Box::new(OpenLoopCrater {}),
+            overrides: vec![
+                (
+                    "sim.orchestrator.abort_before_ignition",
+                    ParameterValue::Bool { val: true },
+                ),
+                ("sim.rocket.max_t", ParameterValue::Float { val: 10.0 }),
+            ],
+            script: None,
+            assertions: vec![
+                Assertion::EventOccurs("orchestrator: Arm -> Aborted"),
+                Assertion::EventAbsent("StartEngine"),
+                Assertion::ApogeeBetween {
+                    min_m: -1.0,
+                    max_m: 1.0,
+                },
+            ],
+        },
+        Scenario {
+            name: "roll_control",
+            description: "Canted fins spin the rocket up, the roll control of the flight software \
+                          damps the roll from burnout",
+            model: Box::new(OpenLoopCrater {}),
+            overrides: vec![
+                (
+                    "sim.rocket.gnc.roll_control.closed_loop",
+                    ParameterValue::Bool { val: true },
+                ),
+                (
+                    "sim.rocket.aero.fin_cant.cant",
+                    ParameterValue::RandFloat(RandFloat::new(
+                        1.0,
+                        FloatDistribution::Normal {
+                            mean: 1.0,
+                            std_dev: 0.0,
+                        },
+                    )),
+                ),
+                (
+                    "sim.wind.model",
+                    ParameterValue::String {
+                        val: "none".to_string(),
+                    },
+                ),
+            ],
+            script: None,
+            assertions: [
+                nominal_flight(),
+                vec![Assertion::RollRateBelow {
+                    after: "FlightBurnout",
+                    max_deg_s: 10.0,
+                }],
+            ]
+            .concat(),
+        },
+        Scenario {
+            name: "dual_fc",
+            description: "Two redundant flight computers in agreement, unit A in command",
+            model: Box::new(DualFcCrater {}),
+            overrides: vec![],
+            script: Some("config/openloop_seq.toml"),
+            assertions: [
+                nominal_flight(),
+                vec![
+                    Assertion::EventAbsent("FcDivergence"),
+                    Assertion::EventAbsent("FcFailover"),
+                ],
+            ]
+            .concat(),
+        },
+        Scenario {
+            name: "dual_fc_failover",
+            description: "Flight computer A dies during the flight, B takes over",
+            model: Box::new(DualFcCrater {}),
+            overrides: vec![(
+                "sim.rocket.gnc.dual_fc.fail_time_a",
+                ParameterValue::Float { val: 20.0 },
+            )],
+            script: Some("config/openloop_seq.toml"),
+            assertions: [
+                nominal_flight(),
+                vec![
+                    Assertion::EventOccurs("FcFailover { from: A, to: B }"),
+                    Assertion::EventAbsent("FcDivergence"),
+                ],
+            ]
+            .concat(),
+        },
+    ]
+}
+
+pub fn find_scenario(name: &str) -> Option<Scenario> {
+    catalog().into_iter().find(|s| s.name == name)
+}
+
+/// Stages of the pre-deployment rehearsal, flown with the flight parameters: the full countdown
+/// scripted by the ground, followed by the flight, and a scrubbed countdown. The countdown
+/// commands go over the radio link model, and the ground follows the flight software through it.
+pub fn rehearsal() -> Vec<Scenario> {
+    let radio_link = || ("sim.radio_link.enabled", ParameterValue::Bool { val: true });
+
+    vec![
+        Scenario {
+            name: "countdown_and_flight",
+            description: "Calibration, arming and ignition commanded by the ground, then flight",
+            model: Box::new(OpenLoopCrater {}),
+            overrides: vec![radio_link()],
+            script: None,
+            assertions: [
+                vec![Assertion::EventSequence(vec![
+                    "CmdFmmCalibrate",
+                    "AdaCalibrationDone",
+                    "FlightStateReady",
+                    "CmdFmmArm",
+                    "FlightStateArmed",
+                    "StartEngine",
+                    "FlightLiftoff",
+                    "rocket: FlyingRamp -> FlyingFree",
+                    "FlightBurnout",
+                    "AdaApogeeDetected",
+                    "CmdDeployDrogue",
+                    "RecoveryDrogueFired",
+                    "RecoveryMainFired",
+                ])],
+                nominal_flight(),
+            ]
+            .concat(),
+        },
+        Scenario {
+            name: "scrub",
+            description: "Countdown scrubbed by the ground at the end of the arming delay",
+            model: Box::new(OpenLoopCrater {}),
+            overrides: vec![
+                radio_link(),
+                (
+                    "sim.orchestrator.abort_before_ignition",
+                    ParameterValue::Bool { val: true },
+                ),
+                ("sim.rocket.max_t", ParameterValue::Float { val: 10.0 }),
+            ],
+            script: None,
+            assertions: vec![
+                Assertion::EventSequence(vec![
+                    "CmdFmmCalibrate",
+                    "FlightStateReady",
+                    "CmdFmmArm",
+                    "FlightStateArmed",
+                    "orchestrator: Arm -> Aborted",
+                ]),
+                Assertion::EventAbsent("StartEngine"),
+            ],
+        },
+        Scenario {
+            name: "unapproved_config",
+            description: "Launch scrubbed: the configuration frozen at arming is not the approved one",
+            model: Box::new(OpenLoopCrater {}),
+            overrides: vec![
+                radio_link(),
+                (
+                    "sim.orchestrator.approved_config_hash",
+                    ParameterValue::String {
+                        val: "0000000000000000".to_string(),
+                    },
+                ),
+                ("sim.rocket.max_t", ParameterValue::Float { val: 10.0 }),
+            ],
+            script: None,
+            assertions: vec![
+                Assertion::EventSequence(vec![
+                    "CmdFmmArm",
+                    "FlightStateArmed",
+                    "orchestrator: Arm -> Aborted",
+                ]),
+                Assertion::EventAbsent("StartEngine"),
+            ],
+        },
+    ]
+}
@@ -0,0 +1,435 @@
+use anyhow::Result;
+use chrono::TimeDelta;
+use log::{info, warn};
+use serde::Serialize;
+
+use crate::{
+    crater::{
+        channels,
+        gnc::{ServoPosition, cosim::CosimLinkStatus},
+        logging::report::{FlightReport, FlightReportBuilder},
+    },
+    model::CosimCrater,
+    nodes::{FtlOrderedExecutor, NodeManager, ParameterSampling},
+    parameters::{ParameterMap, ParameterValue},
+    telemetry::{TelemetryReceiver, TelemetryService, Timestamped},
+    utils::capacity::Capacity::Unbounded,
+};
+
+use super::{Assertion, AssertionResult, catalog::nominal_flight};
+
+/// Bench measurements of a HIL step, from the commands & timing of the hardware in the loop
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HilMetrics {
+    /// Lockstep steps acknowledged by the hardware
+    pub steps: usize,
+    /// Simulation time of the last acknowledged step
+    pub duration_s: f64,
+    pub max_round_trip_ms: f64,
+    pub mean_round_trip_ms: f64,
+    pub servo_min_deg: [f64; 4],
+    pub servo_max_deg: [f64; 4],
+    pub max_servo_rate_deg_s: f64,
+}
+
+/// Pass/fail criterion of a HIL step
+#[derive(Debug, Clone)]
+pub enum HilCriterion {
+    /// The hardware keeps up with the lockstep for the whole step
+    RunsFor {
+        duration_s: f64,
+    },
+    MaxRoundTrip {
+        max_ms: f64,
+    },
+    /// All the servo commands stay within this deflection from neutral
+    ServoNeutral {
+        tol_deg: f64,
+    },
+    /// Each servo is commanded to at least these deflections
+    ServoSweep {
+        min_deg: f64,
+        max_deg: f64,
+    },
+    MaxServoRate {
+        max_deg_s: f64,
+    },
+    /// Check on the simulated flight
+    Flight(Assertion),
+}
+
+impl HilCriterion {
+    pub fn check(&self, metrics: &HilMetrics, report: &FlightReport) -> AssertionResult {
+        let m = metrics;
+        let (description, passed, actual) = match self {
+            HilCriterion::RunsFor { duration_s } => (
+                format!("runs in lockstep for {duration_s:.1} s"),
+                m.duration_s >= *duration_s,
+                format!("{:.3} s, {} steps", m.duration_s, m.steps),
+            ),
+            HilCriterion::MaxRoundTrip { max_ms } => (
+                format!("step round trip < {max_ms:.2} ms"),
+                m.steps > 0 && m.max_round_trip_ms < *max_ms,
+                format!(
+                    "max {:.3} ms, mean {:.3} ms",
+                    m.max_round_trip_ms, m.mean_round_trip_ms
+                ),
+            ),
+            HilCriterion::ServoNeutral { tol_deg } => {
+                let max_abs = m
+                    .servo_min_deg
+                    .iter()
+                    .chain(m.servo_max_deg.iter())
+                    .fold(0.0f64, |acc, v| acc.max(v.abs()));
+                (
+                    format!("servo commands within ±{tol_deg:.1} deg"),
+                    max_abs <= *tol_deg,
+                    format!("max {max_abs:.2} deg"),
+                )
+            }
+            HilCriterion::ServoSweep { min_deg, max_deg } => (
+                format!("each servo swept over [{min_deg:.1}, {max_deg:.1}] deg"),
+                m.servo_min_deg.iter().all(|v| v <= min_deg)
+                    && m.servo_max_deg.iter().all(|v| v >= max_deg),
+                format!(
+                    "min {:.1?} deg, max {:.1?} deg",
+                    m.servo_min_deg, m.servo_max_deg
+                ),
+            ),
+            HilCriterion::MaxServoRate { max_deg_s } => (
+                format!("servo command rate < {max_deg_s:.0} deg/s"),
+                m.max_servo_rate_deg_s < *max_deg_s,
+                format!("{:.1} deg/s", m.max_servo_rate_deg_s),
+            ),
+            HilCriterion::Flight(assertion) => return assertion.check(report),
+        };
+
+        AssertionResult {
+            description,
+            passed,
+            actual,
+        }
+    }
+}
+
+/// Collects the bench measurements. Must be created before running the step.
+struct HilMonitor {
+    rx_link: TelemetryReceiver<CosimLinkStatus>,
+    rx_servo_cmd: TelemetryReceiver<ServoPosition>,
+}
+
+impl HilMonitor {
+    fn new(ts: &TelemetryService) -> Result<Self> {
+        Ok(Self {
+            rx_link: ts.subscribe(channels::sim::COSIM_LINK, Unbounded)?,
+            rx_servo_cmd: ts.subscribe(channels::gnc::SERVO_COMMAND, Unbounded)?,
+        })
+    }
+
+    fn build(self) -> HilMetrics {
+        let mut m = HilMetrics {
+            servo_min_deg: [f64::INFINITY; 4],
+            servo_max_deg: [f64::NEG_INFINITY; 4],
+            ..Default::default()
+        };
+
+        let mut tot_round_trip_ms = 0.0;
+        while let Ok(Timestamped(t, link)) = self.rx_link.try_recv() {
+            m.steps += 1;
+            m.duration_s = t.monotonic.elapsed_seconds_f64();
+            m.max_round_trip_ms = m.max_round_trip_ms.max(link.round_trip_ms);
+            tot_round_trip_ms += link.round_trip_ms;
+        }
+        if m.steps > 0 {
+            m.mean_round_trip_ms = tot_round_trip_ms / m.steps as f64;
+        }
+
+        let mut last: Option<(f64, ServoPosition)> = None;
+        while let Ok(Timestamped(t, cmd)) = self.rx_servo_cmd.try_recv() {
+            let t_s = t.monotonic.elapsed_seconds_f64();
+            let deg = cmd.pos_rad.map(f64::to_degrees);
+
+            for i in 0..4 {
+                m.servo_min_deg[i] = m.servo_min_deg[i].min(deg[i]);
+                m.servo_max_deg[i] = m.servo_max_deg[i].max(deg[i]);
+            }
+
+            if let Some((t_last, last_cmd)) = last.as_ref().filter(|(t_last, _)| t_s > *t_last) {
+                let rate =
+                    (cmd.pos_rad - last_cmd.pos_rad).abs().max().to_degrees() / (t_s - t_last);
+                m.max_servo_rate_deg_s = m.max_servo_rate_deg_s.max(rate);
+            }
+            last = Some((t_s, cmd));
+        }
+
+        // No commands received
+        if last.is_none() {
+            m.servo_min_deg = [0.0; 4];
+            m.servo_max_deg = [0.0; 4];
+        }
+
+        m
+    }
+}
+
+/// Bench test run against the hardware in the loop, connected through the co-simulation bridge.
+/// The hardware must (re)connect to the bridge at the start of every step.
+pub struct HilStep {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Bench test the hardware is asked to run (see [crate::crater::gnc::cosim::protocol]).
+    /// None for a flight.
+    pub test: Option<&'static str>,
+    pub overrides: Vec<(&'static str, ParameterValue)>,
+    pub criteria: Vec<HilCriterion>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HilStepStatus {
+    Pass,
+    Fail,
+    /// The step could not be run, eg. the hardware disconnected
+    Error,
+    /// Not run, as a previous step did not pass
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HilStepOutcome {
+    pub name: String,
+    pub description: String,
+    pub status: HilStepStatus,
+    pub error: Option<String>,
+    pub metrics: Option<HilMetrics>,
+    pub results: Vec<AssertionResult>,
+}
+
+/// Machine-readable outcome of a HIL sequence, for the acceptance records
+#[derive(Debug, Clone, Serialize)]
+pub struct HilReport {
+    /// UTC, RFC 3339
+    pub date: String,
+    pub params: String,
+    pub passed: bool,
+    pub steps: Vec<HilStepOutcome>,
+}
+
+impl HilStep {
+    fn parameters(&self, base: &ParameterMap) -> Result<ParameterMap> {
+        let mut params = base.clone();
+
+        for (path, value) in self.overrides.iter() {
+            params.set_param(path, value.clone())?;
+        }
+        params.set_param(
+            "sim.cosim.test",
+            ParameterValue::String {
+                val: self.test.unwrap_or_default().to_string(),
+            },
+        )?;
+
+        Ok(params)
+    }
+
+    fn run_inner(&self, base: &ParameterMap) -> Result<(HilMetrics, FlightReport)> {
+        let params = self.parameters(base)?;
+
+        let dt_sec = params.get_param("sim.dt")?.value_float()?;
+        let dt = (dt_sec * 1000000.0) as i64;
+
+        let ts = TelemetryService::default();
+        channels::configure_access(&ts, &params)?;
+        let report_builder = FlightReportBuilder::new(&ts)?;
+        let monitor = HilMonitor::new(&ts)?;
+
+        let mut nm = NodeManager::new(ts, params, ParameterSampling::Perfect, 0);
+        CosimCrater {}.build(&mut nm)?;
+
+        let run_params = nm.parameters();
+
+        FtlOrderedExecutor::run_blocking(nm, TimeDelta::microseconds(dt))?;
+
+        Ok((
+            monitor.build(),
+            report_builder.build(self.name, &run_params),
+        ))
+    }
+
+    /// Runs the step against the hardware and checks its criteria
+    pub fn run(&self, base: &ParameterMap) -> HilStepOutcome {
+        let mut outcome = HilStepOutcome {
+            name: self.name.to_string(),
+            description: self.description.to_string(),
+            status: HilStepStatus::Error,
+            error: None,
+            metrics: None,
+            results: vec![],
+        };
+
+        match self.run_inner(base) {
+            Ok((metrics, report)) => {
+                outcome.results = self
+                    .criteria
+                    .iter()
+                    .map(|c| c.check(&metrics, &report))
+                    .collect();
+                outcome.status = if outcome.results.iter().all(|r| r.passed) {
+                    HilStepStatus::Pass
+                } else {
+                    HilStepStatus::Fail
+                };
+                outcome.metrics = Some(metrics);
+            }
+            Err(e) => outcome.error = Some(format!("{e:#}")),
+        }
+
+        outcome
+    }
+
+    fn skipped(&self) -> HilStepOutcome {
+        HilStepOutcome {
+            name: self.name.to_string(),
+            description: self.description.to_string(),
+            status: HilStepStatus::Skipped,
+            error: None,
+            metrics: None,
+            results: vec![],
+        }
+    }
+}
+
+/// Runs the steps in order. Unless `keep_going`, the steps after the first one not passing are
+/// skipped, so that a faulty bench is not flown.
+pub fn run_sequence(
+    steps: &[HilStep],
+    base: &ParameterMap,
+    keep_going: bool,
+) -> Vec<HilStepOutcome> {
+    let mut outcomes: Vec<HilStepOutcome> = vec![];
+
+    for step in steps.iter() {
+        if !keep_going && outcomes.iter().any(|o| o.status != HilStepStatus::Pass) {
+            outcomes.push(step.skipped());
+            continue;
+        }
+
+        info!("HIL step '{}': {}", step.name, step.description);
+        let outcome = step.run(base);
+
+        match outcome.status {
+            HilStepStatus::Error => warn!(
+                "HIL step '{}' could not run: {}",
+                step.name,
+                outcome.error.as_deref().unwrap_or_default()
+            ),
+            status => info!("HIL step '{}': {status:?}", step.name),
+        }
+
+        outcomes.push(outcome);
+    }
+
+    outcomes
+}
+
+fn max_t(t: f64) -> (&'static str, ParameterValue) {
+    ("sim.rocket.max_t", ParameterValue::Float { val: t })
+}
+
+/// Standard bench acceptance sequence
+pub fn bench_sequence() -> Vec<HilStep> {
+    // The hardware must answer within a simulation step to run in real time
+    let round_trip = HilCriterion::MaxRoundTrip { max_ms: 3.0 };
+
+    vec![
+        HilStep {
+            name: "sensor_sanity",
+            description: "Rocket on the pad: the hardware receives the sensor samples and keeps \
+                the control surfaces neutral",
+            test: Some("sensor_sanity"),
+            overrides: vec![max_t(10.0)],
+            criteria: vec![
+                HilCriterion::RunsFor { duration_s: 9.9 },
+                round_trip.clone(),
+                HilCriterion::ServoNeutral { tol_deg: 0.5 },
+                HilCriterion::Flight(Assertion::EventAbsent("StartEngine")),
+            ],
+        },
+        HilStep {
+            name: "actuator_sweep",
+            description: "Rocket on the pad: the hardware sweeps each servo over its full travel",
+            test: Some("actuator_sweep"),
+            overrides: vec![max_t(20.0)],
+            criteria: vec![
+                HilCriterion::RunsFor { duration_s: 19.9 },
+                round_trip.clone(),
+                HilCriterion::ServoSweep {
+                    min_deg: -10.0,
+                    max_deg: 10.0,
+                },
+                HilCriterion::MaxServoRate { max_deg_s: 600.0 },
+                HilCriterion::Flight(Assertion::EventAbsent("StartEngine")),
+            ],
+        },
+        HilStep {
+            name: "flight",
+            description: "Full simulated flight flown by the hardware, from ignition to landing",
+            test: None,
+            overrides: vec![],
+            criteria: [
+                vec![round_trip],
+                nominal_flight()
+                    .into_iter()
+                    .map(HilCriterion::Flight)
+                    .collect(),
+            ]
+            .concat(),
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_servo_criteria() {
+        let metrics = HilMetrics {
+            steps: 100,
+            duration_s: 10.0,
+            max_round_trip_ms: 1.0,
+            mean_round_trip_ms: 0.5,
+            servo_min_deg: [-12.0, -12.0, -12.0, -5.0],
+            servo_max_deg: [12.0, 12.0, 12.0, 12.0],
+            max_servo_rate_deg_s: 200.0,
+        };
+        let report = FlightReport {
+            title: String::new(),
+            pos_n_m: vec![],
+            speed_m_s: vec![],
+            mach: vec![],
+            roll_rate_deg_s: vec![],
+            events: vec![],
+            metrics: Default::default(),
+            nav_errors: None,
+            descent: Default::default(),
+            compliance: vec![],
+            parameters: vec![],
+        };
+
+        let check = |c: HilCriterion| c.check(&metrics, &report).passed;
+
+        assert!(check(HilCriterion::RunsFor { duration_s: 9.9 }));
+        assert!(check(HilCriterion::MaxRoundTrip { max_ms: 3.0 }));
+        assert!(check(HilCriterion::MaxServoRate { max_deg_s: 600.0 }));
+        assert!(!check(HilCriterion::ServoNeutral { tol_deg: 0.5 }));
+        // Servo 4 did not reach -10 deg
+        assert!(!check(HilCriterion::ServoSweep {
+            min_deg: -10.0,
+            max_deg: 10.0
+        }));
+        assert!(check(HilCriterion::Flight(Assertion::EventAbsent(
+            "StartEngine"
+        ))));
+    }
+}
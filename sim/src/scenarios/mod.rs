@@ -0,0 +1,89 @@
+mod assertions;
+mod catalog;
+pub mod hil;
+
+pub use assertions::{Assertion, AssertionResult};
+pub use catalog::{catalog, find_scenario, rehearsal};
+
+use anyhow::Result;
+use chrono::TimeDelta;
+
+use crate::{
+    crater::{
+        channels,
+        logging::report::{FlightReport, FlightReportBuilder},
+    },
+    model::ModelBuilder,
+    nodes::{FtlOrderedExecutor, NodeManager, ParameterSampling},
+    parameters::{ParameterMap, ParameterValue},
+    telemetry::TelemetryService,
+};
+
+/// A ready to run simulation case: the model to simulate, the changes to apply on top of the
+/// base parameters, the scenario script and the checks the run must pass
+pub struct Scenario {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub model: Box<dyn ModelBuilder>,
+    pub overrides: Vec<(&'static str, ParameterValue)>,
+    /// Open loop servo sequence flown in the scenario. Uses the one in the base parameters if None.
+    pub script: Option<&'static str>,
+    pub assertions: Vec<Assertion>,
+}
+
+pub struct ScenarioOutcome {
+    pub report: FlightReport,
+    pub results: Vec<AssertionResult>,
+}
+
+impl ScenarioOutcome {
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(|r| r.passed)
+    }
+}
+
+impl Scenario {
+    /// Base parameters with the scenario overrides applied
+    pub fn parameters(&self, base: &ParameterMap) -> Result<ParameterMap> {
+        let mut params = base.clone();
+
+        for (path, value) in self.overrides.iter() {
+            params.set_param(path, value.clone())?;
+        }
+
+        if let Some(script) = self.script {
+            params.set_param(
+                "sim.rocket.gnc.openloop.sequence",
+                ParameterValue::String {
+                    val: script.to_string(),
+                },
+            )?;
+        }
+
+        Ok(params)
+    }
+
+    /// Runs the scenario with nominal parameters and checks its assertions
+    pub fn run(&self, base: &ParameterMap) -> Result<ScenarioOutcome> {
+        let params = self.parameters(base)?;
+
+        let dt_sec = params.get_param("sim.dt")?.value_float()?;
+        let dt = (dt_sec * 1000000.0) as i64;
+
+        let ts = TelemetryService::default();
+        channels::configure_access(&ts, &params)?;
+        let report_builder = FlightReportBuilder::new(&ts)?;
+
+        let mut nm = NodeManager::new(ts, params, ParameterSampling::Perfect, 0);
+        self.model.build(&mut nm)?;
+
+        let run_params = nm.parameters();
+
+        FtlOrderedExecutor::run_blocking(nm, TimeDelta::microseconds(dt))?;
+
+        let report = report_builder.build(self.name, &run_params);
+        let results = self.assertions.iter().map(|a| a.check(&report)).collect();
+
+        Ok(ScenarioOutcome { report, results })
+    }
+}
@@ -0,0 +1,35 @@
+use std::collections::HashMap;
+
+/// Restricts which producers may publish on safety relevant channels, so that a test or debug
+/// node cannot command the flight by mistake.
+///
+/// Producers are identified by the name of the node creating them. Channels not listed in the
+/// policy can be published by any producer.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChannelAccessPolicy {
+    allowed: HashMap<String, Vec<String>>,
+}
+
+impl ChannelAccessPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only allows the listed producers to publish on the channel
+    pub fn allow(mut self, channel_name: &str, producers: &[&str]) -> Self {
+        self.allowed
+            .entry(channel_name.to_string())
+            .or_default()
+            .extend(producers.iter().map(|p| p.to_string()));
+        self
+    }
+
+    /// Whether the producer may publish on the channel. Anonymous producers, not created by a
+    /// node, may only publish unrestricted channels.
+    pub fn is_allowed(&self, channel_name: &str, producer: Option<&str>) -> bool {
+        match self.allowed.get(channel_name) {
+            Some(producers) => producer.is_some_and(|p| producers.iter().any(|a| a == p)),
+            None => true,
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+use chrono::TimeDelta;
+use log::warn;
+use serde::Serialize;
+
+use super::{ChannelInfo, TelemetrySender, TelemetryService};
+use crate::{
+    core::time::{Clock, Timestamp},
+    nodes::{Node, StepResult},
+};
+
+/// Traffic of a channel over the last diagnostics period
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChannelStats {
+    #[serde(flatten)]
+    pub info: ChannelInfo,
+    /// Messages per second of simulated time
+    pub rate_hz: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ChannelDiagnostics {
+    pub channels: Vec<ChannelStats>,
+}
+
+/// Publishes the channels of the model with their message rates every `period`. Warns once for
+/// each channel that is subscribed to but has no producer, usually a misspelled channel name.
+pub struct ChannelMonitor {
+    ts: TelemetryService,
+    tx_diagnostics: TelemetrySender<ChannelDiagnostics>,
+    period: TimeDelta,
+
+    /// Time & message counts of the last publication
+    last: Option<(Timestamp, HashMap<String, u64>)>,
+    unpublished: HashSet<String>,
+}
+
+impl ChannelMonitor {
+    pub fn new(
+        ts: TelemetryService,
+        tx_diagnostics: TelemetrySender<ChannelDiagnostics>,
+        period: TimeDelta,
+    ) -> Self {
+        Self {
+            ts,
+            tx_diagnostics,
+            period,
+            last: None,
+            unpublished: HashSet::new(),
+        }
+    }
+
+    fn check_wiring(&mut self, channels: &[ChannelInfo]) {
+        for ch in channels.iter() {
+            if !ch.multi_producer
+                && ch.num_producers == 0
+                && ch.num_subscribers > 0
+                && self.unpublished.insert(ch.name.clone())
+            {
+                warn!(
+                    "Channel '{}' ({}) has {} subscriber(s), but no producer",
+                    ch.name, ch.typename, ch.num_subscribers
+                );
+            }
+        }
+    }
+}
+
+impl Node for ChannelMonitor {
+    fn step(&mut self, _: usize, _: TimeDelta, clock: &dyn Clock) -> Result<StepResult> {
+        let t = Timestamp::now(clock);
+        let elapsed = |t_last: &Timestamp| t.monotonic - t_last.monotonic;
+        if self
+            .last
+            .as_ref()
+            .is_some_and(|(t_last, _)| elapsed(t_last) < self.period)
+        {
+            return Ok(StepResult::Continue);
+        }
+
+        let channels = self.ts.channels();
+        self.check_wiring(&channels);
+
+        let diagnostics = ChannelDiagnostics {
+            channels: channels
+                .iter()
+                .map(|ch| {
+                    let rate_hz = match &self.last {
+                        Some((t_last, counts)) => {
+                            let dt_s = elapsed(t_last).as_seconds_f64();
+                            let last = counts.get(&ch.name).copied().unwrap_or(0);
+
+                            (ch.num_messages - last) as f64 / dt_s
+                        }
+                        None => 0.0,
+                    };
+
+                    ChannelStats {
+                        info: ch.clone(),
+                        rate_hz,
+                    }
+                })
+                .collect(),
+        };
+
+        self.last = Some((
+            t,
+            channels
+                .into_iter()
+                .map(|ch| (ch.name, ch.num_messages))
+                .collect(),
+        ));
+
+        // Published after counting, so that this channel shows the previous publications
+        self.tx_diagnostics.send(t, diagnostics);
+
+        Ok(StepResult::Continue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+    use crate::{core::time::SimulatedClock, utils::capacity::Capacity::Unbounded};
+
+    #[test]
+    fn test_channel_monitor() -> Result<()> {
+        let ts = TelemetryService::default();
+        let tx_state = ts.publish::<f64>("/rocket/state")?;
+        let _rx_state = ts.subscribe::<f64>("/rocket/state", Unbounded)?;
+        let _rx_typo = ts.subscribe::<f64>("/rocket/stat", Unbounded)?;
+
+        let rx = ts.subscribe::<ChannelDiagnostics>("/sim/channels", Unbounded)?;
+        let mut monitor = ChannelMonitor::new(
+            ts.clone(),
+            ts.publish("/sim/channels")?,
+            TimeDelta::milliseconds(500),
+        );
+
+        let dt = TimeDelta::milliseconds(10);
+        let mut clock = SimulatedClock::new(Utc::now(), TimeDelta::zero());
+        for i in 0..101 {
+            tx_state.send(Timestamp::now(&clock), 0.0);
+            monitor.step(i, dt, &clock)?;
+            clock.step(dt);
+        }
+
+        // At 0, 0.5 & 1 s
+        let diagnostics: Vec<ChannelDiagnostics> = rx.inner().try_iter().map(|d| d.1).collect();
+        assert_eq!(diagnostics.len(), 3);
+
+        let last = &diagnostics[2].channels;
+        let names: Vec<&str> = last.iter().map(|ch| ch.info.name.as_str()).collect();
+        assert_eq!(names, ["/rocket/stat", "/rocket/state", "/sim/channels"]);
+
+        assert_eq!(last[1].info.num_messages, 101);
+        assert!((last[1].rate_hz - 100.0).abs() < 1e-9);
+        assert!((last[2].rate_hz - 2.0).abs() < 1e-9);
+        assert_eq!(diagnostics[0].channels[1].rate_hz, 0.0);
+
+        assert_eq!(
+            monitor.unpublished,
+            HashSet::from(["/rocket/stat".to_string()])
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::VecDeque;
+
+use nalgebra::{RealField, UnitQuaternion, Vector3, convert};
+
+use super::Timestamped;
+use crate::core::time::Timestamp;
+
+/// Values that can be linearly interpolated between two samples
+pub trait Interpolate: Clone {
+    /// Value at fraction `t` of the way from `self` to `other`. Values of `t` outside [0, 1]
+    /// extrapolate.
+    fn interp(&self, other: &Self, t: f64) -> Self;
+}
+
+impl Interpolate for f64 {
+    fn interp(&self, other: &Self, t: f64) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Interpolate for f32 {
+    fn interp(&self, other: &Self, t: f64) -> Self {
+        self + (other - self) * t as f32
+    }
+}
+
+impl<T: RealField + Copy> Interpolate for Vector3<T> {
+    fn interp(&self, other: &Self, t: f64) -> Self {
+        self + (other - self) * convert::<f64, T>(t)
+    }
+}
+
+impl<T: RealField + Copy> Interpolate for UnitQuaternion<T> {
+    /// Constant angular rate rotation from `self` to `other`
+    fn interp(&self, other: &Self, t: f64) -> Self {
+        let delta = other * self.inverse();
+        UnitQuaternion::from_scaled_axis(delta.scaled_axis() * convert::<f64, T>(t)) * self
+    }
+}
+
+/// How an aligned value was obtained from the producer samples
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alignment {
+    /// A sample at the requested time
+    Exact,
+    /// Between the two samples around the requested time
+    Interpolated,
+    /// Past the latest sample, from the last two
+    Extrapolated,
+    /// Closest sample held: there was a single sample, the requested time is before the
+    /// history or too far past the latest sample to extrapolate
+    Held,
+}
+
+/// Value of a channel at a requested time, with its validity
+#[derive(Debug, Clone, PartialEq)]
+pub struct Aligned<T> {
+    pub value: T,
+    pub alignment: Alignment,
+    /// Distance from the requested time to the closest sample used [s]
+    pub age_s: f64,
+}
+
+impl<T> Aligned<T> {
+    /// The value is representative of the requested time
+    pub fn is_valid(&self) -> bool {
+        self.alignment != Alignment::Held
+    }
+}
+
+/// Latest samples of a channel, to evaluate it at times in between producer updates
+#[derive(Debug, Clone)]
+pub struct SampleHistory<T> {
+    samples: VecDeque<Timestamped<T>>,
+    capacity: usize,
+    max_extrapolation_s: f64,
+}
+
+impl<T: Interpolate> SampleHistory<T> {
+    /// Keeps the latest `capacity` samples (at least 2), and extrapolates up to
+    /// `max_extrapolation_s` past the latest one
+    pub fn new(capacity: usize, max_extrapolation_s: f64) -> Self {
+        let capacity = capacity.max(2);
+
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+            max_extrapolation_s,
+        }
+    }
+
+    /// Adds a sample. Samples older than the latest one are discarded.
+    pub fn push(&mut self, sample: Timestamped<T>) {
+        if self.samples.back().is_some_and(|last| sample.0 < last.0) {
+            return;
+        }
+        if self.samples.back().is_some_and(|last| sample.0 == last.0) {
+            self.samples.pop_back();
+        }
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn latest(&self) -> Option<&Timestamped<T>> {
+        self.samples.back()
+    }
+
+    /// Value at time `t`. None if no sample was received yet.
+    pub fn at(&self, t: Timestamp) -> Option<Aligned<T>> {
+        let t_s = t.monotonic.elapsed_seconds_f64();
+        let secs = |s: &Timestamped<T>| s.0.monotonic.elapsed_seconds_f64();
+
+        let first = self.samples.front()?;
+        let last = self.samples.back()?;
+
+        let held = |s: &Timestamped<T>| Aligned {
+            value: s.1.clone(),
+            alignment: Alignment::Held,
+            age_s: (t_s - secs(s)).abs(),
+        };
+
+        if t_s < secs(first) {
+            return Some(held(first));
+        }
+
+        if t_s >= secs(last) {
+            let age_s = t_s - secs(last);
+            if age_s == 0.0 {
+                return Some(Aligned {
+                    value: last.1.clone(),
+                    alignment: Alignment::Exact,
+                    age_s,
+                });
+            }
+
+            let n = self.samples.len();
+            if n < 2 || age_s > self.max_extrapolation_s {
+                return Some(held(last));
+            }
+
+            let prev = &self.samples[n - 2];
+            let frac = (t_s - secs(prev)) / (secs(last) - secs(prev));
+            return Some(Aligned {
+                value: prev.1.interp(&last.1, frac),
+                alignment: Alignment::Extrapolated,
+                age_s,
+            });
+        }
+
+        // first <= t < last: there are at least two samples around t
+        let i1 = self.samples.partition_point(|s| secs(s) <= t_s);
+        let (s0, s1) = (&self.samples[i1 - 1], &self.samples[i1]);
+        let (t0, t1) = (secs(s0), secs(s1));
+
+        if t_s == t0 {
+            return Some(Aligned {
+                value: s0.1.clone(),
+                alignment: Alignment::Exact,
+                age_s: 0.0,
+            });
+        }
+
+        Some(Aligned {
+            value: s0.1.interp(&s1.1, (t_s - t0) / (t1 - t0)),
+            alignment: Alignment::Interpolated,
+            age_s: (t_s - t0).min(t1 - t_s),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    fn ts(t_s: f64) -> Timestamp {
+        Timestamp::from_micros((t_s * 1e6) as i64)
+    }
+
+    #[test]
+    fn test_interpolate_between_samples() {
+        let mut history = SampleHistory::new(4, 0.02);
+        assert!(history.at(ts(0.0)).is_none());
+
+        // 50 Hz producer
+        for k in 0..5 {
+            let t_s = k as f64 * 0.02;
+            history.push(Timestamped(ts(t_s), 10.0 * t_s));
+        }
+
+        // Oldest sample dropped
+        let a = history.at(ts(0.01)).unwrap();
+        assert_eq!(a.alignment, Alignment::Held);
+        assert!(!a.is_valid());
+
+        let a = history.at(ts(0.05)).unwrap();
+        assert_eq!(a.alignment, Alignment::Interpolated);
+        assert_relative_eq!(a.value, 0.5, epsilon = 1e-9);
+        assert_relative_eq!(a.age_s, 0.01, epsilon = 1e-9);
+
+        let a = history.at(ts(0.06)).unwrap();
+        assert_eq!(a.alignment, Alignment::Exact);
+        assert_relative_eq!(a.value, 0.6, epsilon = 1e-9);
+
+        let a = history.at(ts(0.09)).unwrap();
+        assert_eq!(a.alignment, Alignment::Extrapolated);
+        assert_relative_eq!(a.value, 0.9, epsilon = 1e-9);
+
+        // Too far past the latest sample
+        let a = history.at(ts(0.2)).unwrap();
+        assert_eq!(a.alignment, Alignment::Held);
+        assert_relative_eq!(a.value, 0.8, epsilon = 1e-9);
+        assert_relative_eq!(a.age_s, 0.12, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_interpolate_quaternion() {
+        let q0 = UnitQuaternion::from_euler_angles(0.0f64, 0.0, 0.0);
+        let q1 = UnitQuaternion::from_euler_angles(0.0, 0.0, 0.2);
+
+        let q = q0.interp(&q1, 0.5);
+        assert_relative_eq!(q.euler_angles().2, 0.1, epsilon = 1e-9);
+
+        let q = q0.interp(&q1, 1.5);
+        assert_relative_eq!(q.euler_angles().2, 0.3, epsilon = 1e-9);
+    }
+}
@@ -1,4 +1,14 @@
+mod access;
 mod service;
+pub mod diagnostics;
+pub mod interp;
 pub mod selector;
+pub mod units;
+pub mod ordering;
+pub mod recording;
+pub mod udp;
+mod udp_proto;
 
-pub use service::*;
\ No newline at end of file
+pub use access::ChannelAccessPolicy;
+pub use service::*;
+pub use units::ChannelUnits;
@@ -0,0 +1,222 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    sync::{Condvar, Mutex},
+    time::Duration,
+};
+
+use anyhow::Result;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::core::time::Timestamp;
+
+/// A message delivered on a telemetry channel
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Delivery {
+    pub channel: String,
+    pub t_us: i64,
+}
+
+/// Ordered list of the deliveries on all the channels of a run. Events are delivered through
+/// channels too, so this also captures the order in which events are handled.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeliveryTrace {
+    pub deliveries: Vec<Delivery>,
+}
+
+impl DeliveryTrace {
+    /// Saves the trace as newline-delimited JSON
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        for delivery in self.deliveries.iter() {
+            serde_json::to_writer(&mut writer, delivery)?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut deliveries = vec![];
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                deliveries.push(serde_json::from_str(&line)?);
+            }
+        }
+
+        Ok(Self { deliveries })
+    }
+}
+
+/// First delivery that did not match the replayed trace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderingDivergence {
+    pub index: usize,
+    pub expected: Option<Delivery>,
+    pub found: Delivery,
+}
+
+#[derive(Debug)]
+enum OrderingMode {
+    Record,
+    Replay { trace: DeliveryTrace, timeout: Duration },
+}
+
+#[derive(Debug, Default)]
+struct OrderingState {
+    deliveries: Vec<Delivery>,
+    divergence: Option<OrderingDivergence>,
+}
+
+/// Records the interleaving of channel deliveries during a run, or forces a run to follow a
+/// recorded interleaving.
+///
+/// In replay mode, a sender blocks until its delivery is the next one in the trace, so the
+/// recorded order is reproduced even when nodes run on different threads. If the run diverges
+/// from the trace (the expected delivery does not show up within the timeout), the divergence is
+/// reported and ordering is no longer enforced.
+#[derive(Debug)]
+pub struct DeliveryOrdering {
+    mode: OrderingMode,
+    state: Mutex<OrderingState>,
+    turn: Condvar,
+}
+
+impl DeliveryOrdering {
+    pub fn record() -> Self {
+        Self {
+            mode: OrderingMode::Record,
+            state: Mutex::new(OrderingState::default()),
+            turn: Condvar::new(),
+        }
+    }
+
+    pub fn replay(trace: DeliveryTrace, timeout: Duration) -> Self {
+        Self {
+            mode: OrderingMode::Replay { trace, timeout },
+            state: Mutex::new(OrderingState::default()),
+            turn: Condvar::new(),
+        }
+    }
+
+    /// Performs a delivery on a channel, in the order imposed by the trace if replaying
+    pub(super) fn deliver<R>(&self, channel: &str, t: Timestamp, deliver: impl FnOnce() -> R) -> R {
+        let delivery = Delivery {
+            channel: channel.to_string(),
+            t_us: t.monotonic.elapsed().num_microseconds().unwrap_or(i64::MAX),
+        };
+
+        self.take_turn(delivery);
+
+        // Sent without the lock: a send blocked on a full bounded subscriber must not hold up the
+        // deliveries on the other channels, one of which may be the one its consumer waits for
+        deliver()
+    }
+
+    /// Waits for the turn of `delivery` if replaying, and records it
+    fn take_turn(&self, delivery: Delivery) {
+        let mut state = self.state.lock().unwrap();
+
+        if let OrderingMode::Replay { trace, timeout } = &self.mode {
+            if state.divergence.is_none() {
+                let is_turn = |s: &mut OrderingState| {
+                    trace.deliveries.get(s.deliveries.len()) == Some(&delivery)
+                        || s.divergence.is_some()
+                };
+
+                let (next_state, res) = self
+                    .turn
+                    .wait_timeout_while(state, *timeout, |s| !is_turn(s))
+                    .unwrap();
+                state = next_state;
+
+                if res.timed_out() && state.divergence.is_none() {
+                    let divergence = OrderingDivergence {
+                        index: state.deliveries.len(),
+                        expected: trace.deliveries.get(state.deliveries.len()).cloned(),
+                        found: delivery.clone(),
+                    };
+                    warn!("Delivery order diverged from the replayed trace: {divergence:?}");
+
+                    state.divergence = Some(divergence);
+                    self.turn.notify_all();
+                }
+            }
+        }
+
+        state.deliveries.push(delivery);
+        self.turn.notify_all();
+    }
+
+    /// Deliveries performed so far
+    pub fn trace(&self) -> DeliveryTrace {
+        DeliveryTrace {
+            deliveries: self.state.lock().unwrap().deliveries.clone(),
+        }
+    }
+
+    pub fn divergence(&self) -> Option<OrderingDivergence> {
+        self.state.lock().unwrap().divergence.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, mpsc},
+        thread,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_replay_enforces_order() {
+        let t = Timestamp::from_micros(0);
+        let delivery = |channel: &str| Delivery {
+            channel: channel.to_string(),
+            t_us: 0,
+        };
+
+        let trace = DeliveryTrace {
+            deliveries: vec![delivery("/b"), delivery("/a")],
+        };
+        let ordering = Arc::new(DeliveryOrdering::replay(trace.clone(), Duration::from_secs(5)));
+
+        // "/a" is sent first, but must wait for "/b" to be delivered
+        let ordering_a = ordering.clone();
+        let handle = thread::spawn(move || ordering_a.deliver("/a", t, || ()));
+
+        thread::sleep(Duration::from_millis(50));
+        ordering.deliver("/b", t, || ());
+        handle.join().unwrap();
+
+        assert_eq!(ordering.trace(), trace);
+        assert_eq!(ordering.divergence(), None);
+    }
+
+    #[test]
+    fn test_blocked_delivery_does_not_block_others() {
+        let t = Timestamp::from_micros(0);
+        let ordering = Arc::new(DeliveryOrdering::record());
+
+        // Bounded subscriber, already full: the delivery on "/a" blocks until it is drained
+        let (tx, rx) = mpsc::sync_channel(1);
+        tx.send(0).unwrap();
+
+        let ordering_a = ordering.clone();
+        let handle = thread::spawn(move || ordering_a.deliver("/a", t, || tx.send(1).unwrap()));
+
+        // The consumer delivers on another channel before draining "/a"
+        thread::sleep(Duration::from_millis(50));
+        ordering.deliver("/b", t, || ());
+        assert_eq!(rx.recv().unwrap(), 0);
+        assert_eq!(rx.recv().unwrap(), 1);
+        handle.join().unwrap();
+
+        assert_eq!(ordering.trace().deliveries.len(), 2);
+    }
+}
@@ -0,0 +1,462 @@
+//! Telemetry recording & replay.
+//!
+//! Recordings are newline-delimited JSON, one [`RecordLine`] per sample, in the order the
+//! samples were produced. Each recorded type is converted to & from a plain serializable record,
+//! so that the recordings do not depend on the layout of the telemetry types.
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+use chrono::TimeDelta;
+use log::warn;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use super::{DynamicMessage, TelemetryReceiver, TelemetrySender, TelemetryService, Timestamped};
+use crate::{
+    core::time::{Clock, Timestamp},
+    nodes::{Node, NodeTelemetry, StepResult},
+    utils::capacity::Capacity::Unbounded,
+};
+
+/// Telemetry types that can be recorded and replayed
+pub trait Recordable: Sized + Clone + Send + 'static {
+    type Record: Serialize + DeserializeOwned;
+
+    fn to_record(&self) -> Self::Record;
+
+    fn from_record(record: Self::Record) -> Result<Self>;
+}
+
+/// Encodes a value as its record, to make the channels of type `T` available to the prefix
+/// subscriptions (see `TelemetryService::register_dynamic`)
+pub fn encode_record<T: Recordable>(value: &T) -> serde_json::Value {
+    serde_json::to_value(value.to_record()).unwrap_or_default()
+}
+
+/// Registers the record encoder of `T` in the telemetry service
+pub fn register_recordable<T: Recordable>(ts: &TelemetryService) {
+    ts.register_dynamic::<T>(encode_record::<T>);
+}
+
+/// Number of producers of a channel, which determines how it is subscribed & published
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Producers {
+    Single,
+    Multiple,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordLine {
+    pub t_us: i64,
+    pub channel: String,
+    pub value: serde_json::Value,
+}
+
+/// Reads all the samples of a recording, in the order they were produced
+pub fn read_recording(path: &Path) -> Result<Vec<RecordLine>> {
+    let file =
+        File::open(path).with_context(|| format!("Cannot open recording '{}'", path.display()))?;
+
+    let mut lines = vec![];
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        lines.push(
+            serde_json::from_str::<RecordLine>(&line)
+                .with_context(|| format!("Invalid record at line {}", i + 1))?,
+        );
+    }
+
+    Ok(lines)
+}
+
+/// Samples of `channel` in a recording, decoded
+pub fn decode_channel<T: Recordable>(
+    lines: &[RecordLine],
+    channel: &str,
+) -> Result<Vec<Timestamped<T>>> {
+    lines
+        .iter()
+        .filter(|line| line.channel == channel)
+        .map(|line| {
+            let value = serde_json::from_value(line.value.clone())
+                .map_err(anyhow::Error::from)
+                .and_then(T::from_record)
+                .with_context(|| format!("Invalid record on '{channel}'"))?;
+            Ok(Timestamped(Timestamp::from_micros(line.t_us), value))
+        })
+        .collect()
+}
+
+/// Node recording or replaying a set of channels
+pub trait RecordingEndpoint {
+    fn add_channel<T: Recordable>(
+        &mut self,
+        telem: &NodeTelemetry,
+        channel: &str,
+        producers: Producers,
+    ) -> Result<()>;
+}
+
+trait RecordedChannel {
+    fn drain(&mut self, lines: &mut Vec<RecordLine>) -> Result<()>;
+}
+
+struct RecordedChannelImpl<T> {
+    channel: String,
+    rx: TelemetryReceiver<T>,
+}
+
+impl<T: Recordable> RecordedChannel for RecordedChannelImpl<T> {
+    fn drain(&mut self, lines: &mut Vec<RecordLine>) -> Result<()> {
+        while let Ok(Timestamped(t, value)) = self.rx.try_recv() {
+            lines.push(RecordLine {
+                t_us: t.monotonic.elapsed().num_microseconds().unwrap(),
+                channel: self.channel.clone(),
+                value: serde_json::to_value(value.to_record())?,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Channels matching a prefix, received as their records
+struct RecordedPrefix {
+    rx: TelemetryReceiver<DynamicMessage>,
+}
+
+impl RecordedChannel for RecordedPrefix {
+    fn drain(&mut self, lines: &mut Vec<RecordLine>) -> Result<()> {
+        while let Ok(Timestamped(t, message)) = self.rx.try_recv() {
+            lines.push(RecordLine {
+                t_us: t.monotonic.elapsed().num_microseconds().unwrap(),
+                channel: message.channel,
+                value: message.value,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes all the samples of the recorded channels to a file, with their timestamps
+pub struct TelemetryRecorder {
+    writer: BufWriter<File>,
+    channels: Vec<Box<dyn RecordedChannel + Send>>,
+    lines: Vec<RecordLine>,
+}
+
+impl TelemetryRecorder {
+    pub fn new(path: &Path) -> Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("Cannot create recording '{}'", path.display()))?;
+
+        Ok(Self {
+            writer: BufWriter::new(file),
+            channels: vec![],
+            lines: vec![],
+        })
+    }
+}
+
+impl RecordingEndpoint for TelemetryRecorder {
+    fn add_channel<T: Recordable>(
+        &mut self,
+        telem: &NodeTelemetry,
+        channel: &str,
+        producers: Producers,
+    ) -> Result<()> {
+        let rx = match producers {
+            Producers::Single => telem.subscribe::<T>(channel, Unbounded)?,
+            Producers::Multiple => telem.subscribe_mp::<T>(channel, Unbounded)?,
+        };
+
+        self.channels.push(Box::new(RecordedChannelImpl {
+            channel: channel.to_string(),
+            rx,
+        }));
+
+        Ok(())
+    }
+}
+
+impl TelemetryRecorder {
+    /// Records all the channels matching `pattern` (eg. `/sensors/*`), of the types registered
+    /// with `register_recordable`
+    pub fn add_prefix(&mut self, telem: &NodeTelemetry, pattern: &str) -> Result<()> {
+        let rx = telem.subscribe_prefix(pattern, Unbounded)?;
+        self.channels.push(Box::new(RecordedPrefix { rx }));
+
+        Ok(())
+    }
+}
+
+impl Node for TelemetryRecorder {
+    fn step(&mut self, _: usize, _: TimeDelta, _: &dyn Clock) -> Result<StepResult> {
+        for channel in self.channels.iter_mut() {
+            channel.drain(&mut self.lines)?;
+        }
+
+        // Samples of different channels in production order. The sort is stable: samples of a
+        // channel keep their order.
+        self.lines.sort_by_key(|line| line.t_us);
+
+        for line in self.lines.drain(..) {
+            serde_json::to_writer(&mut self.writer, &line)?;
+            self.writer.write_all(b"\n")?;
+        }
+        self.writer.flush()?;
+
+        Ok(StepResult::Continue)
+    }
+}
+
+trait ReplayedChannel {
+    fn publish(&self, t: Timestamp, value: serde_json::Value) -> Result<()>;
+}
+
+struct ReplayedChannelImpl<T> {
+    tx: TelemetrySender<T>,
+    filter: fn(&T) -> bool,
+}
+
+impl<T: Recordable> ReplayedChannel for ReplayedChannelImpl<T> {
+    fn publish(&self, t: Timestamp, value: serde_json::Value) -> Result<()> {
+        let value = T::from_record(serde_json::from_value(value)?)?;
+        if (self.filter)(&value) {
+            self.tx.send(t, value);
+        }
+
+        Ok(())
+    }
+}
+
+/// Publishes the samples of a recording on their channels, at their recorded times. Samples of
+/// channels that are not replayed are skipped. The run stops at the end of the recording.
+pub struct TelemetryReplayer {
+    lines: Vec<RecordLine>,
+    next: usize,
+    channels: HashMap<String, Box<dyn ReplayedChannel + Send>>,
+}
+
+impl TelemetryReplayer {
+    pub fn new(path: &Path) -> Result<Self> {
+        Ok(Self {
+            lines: read_recording(path)?,
+            next: 0,
+            channels: HashMap::new(),
+        })
+    }
+
+    /// Replays only the samples of `channel` accepted by `filter`, eg. to re-inject the commands
+    /// on a channel also carrying the outputs of the replayed nodes
+    pub fn add_channel_filtered<T: Recordable>(
+        &mut self,
+        telem: &NodeTelemetry,
+        channel: &str,
+        producers: Producers,
+        filter: fn(&T) -> bool,
+    ) -> Result<()> {
+        if !self.lines.iter().any(|line| line.channel == channel) {
+            warn!("Channel '{channel}' is not in the recording, it will not produce data");
+        }
+
+        let tx = match producers {
+            Producers::Single => telem.publish::<T>(channel)?,
+            Producers::Multiple => telem.publish_mp::<T>(channel)?,
+        };
+
+        self.channels.insert(
+            channel.to_string(),
+            Box::new(ReplayedChannelImpl { tx, filter }),
+        );
+
+        Ok(())
+    }
+}
+
+impl RecordingEndpoint for TelemetryReplayer {
+    fn add_channel<T: Recordable>(
+        &mut self,
+        telem: &NodeTelemetry,
+        channel: &str,
+        producers: Producers,
+    ) -> Result<()> {
+        self.add_channel_filtered::<T>(telem, channel, producers, |_| true)
+    }
+}
+
+impl Node for TelemetryReplayer {
+    fn step(&mut self, _: usize, _: TimeDelta, clock: &dyn Clock) -> Result<StepResult> {
+        let now_us = clock.monotonic().elapsed().num_microseconds().unwrap();
+
+        while let Some(line) = self.lines.get(self.next) {
+            if line.t_us > now_us {
+                break;
+            }
+
+            if let Some(channel) = self.channels.get(&line.channel) {
+                channel
+                    .publish(Timestamp::from_micros(line.t_us), line.value.clone())
+                    .with_context(|| format!("Invalid record on '{}'", line.channel))?;
+            }
+            self.next += 1;
+        }
+
+        if self.next < self.lines.len() {
+            Ok(StepResult::Continue)
+        } else {
+            Ok(StepResult::Stop)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, env, fs, iter, process};
+
+    use chrono::Utc;
+
+    use super::*;
+    use crate::{core::time::SimulatedClock, telemetry::TelemetryService};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Sample(f64);
+
+    impl Recordable for Sample {
+        type Record = f64;
+
+        fn to_record(&self) -> f64 {
+            self.0
+        }
+
+        fn from_record(record: f64) -> Result<Self> {
+            Ok(Sample(record))
+        }
+    }
+
+    fn node_telemetry(ts: &TelemetryService, name: &str) -> NodeTelemetry {
+        NodeTelemetry::new(ts.clone(), name, HashMap::new(), HashMap::new())
+    }
+
+    fn drain<T>(rx: &TelemetryReceiver<T>) -> Vec<Timestamped<T>> {
+        iter::from_fn(|| rx.try_recv().ok()).collect()
+    }
+
+    #[test]
+    fn test_record_and_replay() -> Result<()> {
+        let path = env::temp_dir().join(format!("crater_recording_{}.jsonl", process::id()));
+        let mut clock = SimulatedClock::new(Utc::now(), TimeDelta::zero());
+        let dt = TimeDelta::milliseconds(10);
+
+        // Record
+        let ts = TelemetryService::default();
+        let tx = ts.publish::<Sample>("/test/a")?;
+        let tx_events = ts.publish_mp::<Sample>("/test/events")?;
+        let mut recorder = TelemetryRecorder::new(&path)?;
+        let telem = node_telemetry(&ts, "recorder");
+        recorder.add_channel::<Sample>(&telem, "/test/a", Producers::Single)?;
+        recorder.add_channel::<Sample>(&telem, "/test/events", Producers::Multiple)?;
+
+        for i in 0..5 {
+            clock.step(dt);
+            let t = Timestamp::now(&clock);
+            tx.send(t, Sample(i as f64));
+            if i % 2 == 0 {
+                tx_events.send(t, Sample(-(i as f64)));
+            }
+            recorder.step(i, dt, &clock)?;
+        }
+        drop(recorder);
+
+        // Replay the first channel, and the events of the second one
+        let ts = TelemetryService::default();
+        let mut replayer = TelemetryReplayer::new(&path)?;
+        let telem = node_telemetry(&ts, "replayer");
+        replayer.add_channel::<Sample>(&telem, "/test/a", Producers::Single)?;
+        replayer.add_channel_filtered::<Sample>(
+            &telem,
+            "/test/events",
+            Producers::Multiple,
+            |s| s.0 < -1.0,
+        )?;
+
+        let rx = ts.subscribe::<Sample>("/test/a", Unbounded)?;
+        let rx_events = ts.subscribe_mp::<Sample>("/test/events", Unbounded)?;
+
+        let mut clock = SimulatedClock::new(Utc::now(), TimeDelta::zero());
+        clock.step(dt);
+        clock.step(dt);
+        assert!(matches!(
+            replayer.step(0, dt, &clock)?,
+            StepResult::Continue
+        ));
+        let replayed = drain(&rx);
+        assert_eq!(
+            replayed,
+            vec![
+                Timestamped(Timestamp::from_micros(10_000), Sample(0.0)),
+                Timestamped(Timestamp::from_micros(20_000), Sample(1.0)),
+            ]
+        );
+
+        for _ in 0..3 {
+            clock.step(dt);
+        }
+        assert!(matches!(replayer.step(1, dt, &clock)?, StepResult::Stop));
+        assert_eq!(drain(&rx).len(), 3);
+
+        let events: Vec<_> = drain(&rx_events).into_iter().map(|s| s.1.0).collect();
+        assert_eq!(events, vec![-2.0, -4.0]);
+
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_record_prefix() -> Result<()> {
+        let path = env::temp_dir().join(format!("crater_prefix_{}.jsonl", process::id()));
+        let mut clock = SimulatedClock::new(Utc::now(), TimeDelta::zero());
+        let dt = TimeDelta::milliseconds(10);
+
+        let ts = TelemetryService::default();
+        register_recordable::<Sample>(&ts);
+        let mut recorder = TelemetryRecorder::new(&path)?;
+        recorder.add_prefix(&node_telemetry(&ts, "recorder"), "/test/*")?;
+
+        // Created after the recorder
+        let tx_a = ts.publish::<Sample>("/test/a")?;
+        let tx_b = ts.publish::<Sample>("/test/b")?;
+        let tx_other = ts.publish::<Sample>("/other")?;
+
+        clock.step(dt);
+        let t = Timestamp::now(&clock);
+        tx_a.send(t, Sample(1.0));
+        tx_b.send(t, Sample(2.0));
+        tx_other.send(t, Sample(3.0));
+        recorder.step(0, dt, &clock)?;
+        drop(recorder);
+
+        let lines: Vec<RecordLine> = fs::read_to_string(&path)?
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?;
+        let channels: Vec<(&str, f64)> = lines
+            .iter()
+            .map(|l| (l.channel.as_str(), l.value.as_f64().unwrap()))
+            .collect();
+        assert_eq!(channels, [("/test/a", 1.0), ("/test/b", 2.0)]);
+
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+}
@@ -1,12 +1,25 @@
 use std::{
-    any::{Any, type_name},
+    any::{Any, TypeId, type_name},
     collections::HashMap,
-    sync::{Arc, Mutex},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+#[cfg(feature = "async")]
+use std::{
+    future,
+    task::{Poll, Waker},
 };
 
-use crossbeam_channel::{Receiver, Sender, TryRecvError, bounded, unbounded};
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender, TryRecvError, bounded, unbounded};
+use log::error;
+use serde::Serialize;
+use serde_json::Value;
 use thiserror::Error;
 
+use super::{access::ChannelAccessPolicy, ordering::DeliveryOrdering, units::ChannelUnits};
 use crate::{core::time::Timestamp, utils::capacity::Capacity};
 
 #[derive(PartialEq, Eq, Error, Debug)]
@@ -23,34 +36,121 @@ pub enum TelemetryError {
     #[error("Trying to read from a closed channel")]
     Disconnected,
 
+    #[error("Timed out waiting for a value on the channel")]
+    Timeout,
+
     #[error("Cannot create more than one producer for a channel")]
     AlreadyHasProducer,
 
     #[error("Provided channel name is not valid")]
     InvalidChannelName,
+
+    #[error("Producer '{producer}' is not allowed to publish on '{channel}'")]
+    PublishNotAllowed { channel: String, producer: String },
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Timestamped<T>(pub Timestamp, pub T);
 
+/// Value of a channel of any type, received through a prefix subscription
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynamicMessage {
+    pub channel: String,
+    pub value: Value,
+}
+
+impl DynamicMessage {
+    /// Numeric leaves of the value, by path (eg. `acc/x`, `samples/0`), with the booleans as 0
+    /// & 1. A value that is a number itself has an empty path.
+    pub fn numeric_fields(&self) -> Vec<(String, f64)> {
+        let mut fields = vec![];
+        add_numeric_fields(&self.value, String::new(), &mut fields);
+        fields
+    }
+}
+
+fn add_numeric_fields(value: &Value, path: String, fields: &mut Vec<(String, f64)>) {
+    let child = |key: &str| match path.as_str() {
+        "" => key.to_string(),
+        _ => format!("{path}/{key}"),
+    };
+
+    match value {
+        Value::Number(n) => fields.extend(n.as_f64().map(|v| (path, v))),
+        Value::Bool(b) => fields.push((path, if *b { 1.0 } else { 0.0 })),
+        Value::Array(values) => {
+            for (i, v) in values.iter().enumerate() {
+                add_numeric_fields(v, child(&i.to_string()), fields);
+            }
+        }
+        Value::Object(map) => {
+            for (key, v) in map.iter() {
+                add_numeric_fields(v, child(key), fields);
+            }
+        }
+        Value::Null | Value::String(_) => {}
+    }
+}
+
+/// Converts the values of a channel type to dynamic messages
+pub type DynamicEncoder<T> = fn(&T) -> Value;
+
+#[derive(Debug, Clone)]
+struct DynamicSender {
+    tx: Sender<Timestamped<DynamicMessage>>,
+    #[cfg(feature = "async")]
+    wakers: Wakers,
+}
+
 #[derive(Debug)]
 pub struct TelemetrySender<T> {
     transport: Arc<TelemetryChannelTransportInner<T>>,
+    channel_name: String,
+    ordering: Option<Arc<DeliveryOrdering>>,
+    num_messages: Arc<AtomicU64>,
 }
 
 impl<T: 'static + Clone> TelemetrySender<T> {
     pub fn send(&self, timestamp: Timestamp, value: T) {
+        match &self.ordering {
+            Some(ordering) => {
+                ordering.deliver(&self.channel_name, timestamp, || self.deliver(timestamp, value))
+            }
+            None => self.deliver(timestamp, value),
+        }
+    }
+
+    fn deliver(&self, timestamp: Timestamp, value: T) {
         let senders = self.transport.senders.lock().unwrap();
 
         for tx in senders.iter() {
             tx.0.send(Timestamped(timestamp, value.clone())).unwrap();
         }
+
+        for (tx, encode) in self.transport.dynamic.lock().unwrap().iter() {
+            let message = DynamicMessage {
+                channel: self.channel_name.clone(),
+                value: encode(&value),
+            };
+            tx.tx.send(Timestamped(timestamp, message)).unwrap();
+
+            #[cfg(feature = "async")]
+            wake_all(&tx.wakers);
+        }
+
+        *self.transport.latest.lock().unwrap() = Some(Timestamped(timestamp, value));
+        self.num_messages.fetch_add(1, Ordering::Relaxed);
+
+        #[cfg(feature = "async")]
+        wake_all(&self.transport.wakers);
     }
 }
 
 #[derive(Debug)]
 pub struct TelemetryReceiver<T> {
     receiver: Receiver<Timestamped<T>>,
+    #[cfg(feature = "async")]
+    wakers: Wakers,
 }
 
 impl<T> TelemetryReceiver<T> {
@@ -67,11 +167,70 @@ impl<T> TelemetryReceiver<T> {
         })
     }
 
+    /// Waits for a value for at most `timeout`
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<Timestamped<T>, TelemetryError> {
+        self.receiver
+            .recv_timeout(timeout)
+            .map_err(Self::timeout_error)
+    }
+
+    /// Waits for a value until `deadline`
+    pub fn recv_deadline(&self, deadline: Instant) -> Result<Timestamped<T>, TelemetryError> {
+        self.receiver
+            .recv_deadline(deadline)
+            .map_err(Self::timeout_error)
+    }
+
+    fn timeout_error(e: RecvTimeoutError) -> TelemetryError {
+        match e {
+            RecvTimeoutError::Timeout => TelemetryError::Timeout,
+            RecvTimeoutError::Disconnected => TelemetryError::Disconnected,
+        }
+    }
+
     pub fn inner(&self) -> &Receiver<Timestamped<T>> {
         &self.receiver
     }
 }
 
+#[cfg(feature = "async")]
+impl<T> TelemetryReceiver<T> {
+    /// Waits for a value without blocking the thread, for the async consumers
+    pub async fn recv_async(&self) -> Result<Timestamped<T>, TelemetryError> {
+        future::poll_fn(|cx| match self.try_recv() {
+            Err(TelemetryError::Empty) => {
+                {
+                    let mut wakers = self.wakers.lock().unwrap();
+                    if !wakers.iter().any(|w| w.will_wake(cx.waker())) {
+                        wakers.push(cx.waker().clone());
+                    }
+                }
+
+                // A value may have been delivered before the waker was registered
+                match self.try_recv() {
+                    Err(TelemetryError::Empty) => Poll::Pending,
+                    res => Poll::Ready(res),
+                }
+            }
+            res => Poll::Ready(res),
+        })
+        .await
+    }
+}
+
+/// Latest value of a channel, without queueing the samples
+#[derive(Debug)]
+pub struct LatestReceiver<T> {
+    transport: Arc<TelemetryChannelTransportInner<T>>,
+}
+
+impl<T: Clone> LatestReceiver<T> {
+    /// Latest value published, if any
+    pub fn get(&self) -> Option<Timestamped<T>> {
+        self.transport.latest()
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum ChannelType {
     SpMc,
@@ -80,7 +239,6 @@ enum ChannelType {
 
 #[derive(Debug)]
 struct TelemetryChannel {
-    #[allow(dead_code)]
     name: String,
 
     typename: String,
@@ -90,6 +248,24 @@ struct TelemetryChannel {
     ch_type: ChannelType,
     num_producers: usize,
     num_subscribers: usize,
+    /// Shared with the producers, which count the messages they publish
+    num_messages: Arc<AtomicU64>,
+
+    type_id: TypeId,
+    /// Forwards the values to a prefix subscriber, with a `DynamicEncoder` of the channel type
+    add_dynamic: fn(&mut Self, &(dyn Any + Send), DynamicSender),
+}
+
+/// Description of a channel of a running model, to debug its wiring
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChannelInfo {
+    pub name: String,
+    pub typename: String,
+    pub multi_producer: bool,
+    pub num_producers: usize,
+    pub num_subscribers: usize,
+    /// Messages published since the channel was created
+    pub num_messages: u64,
 }
 
 #[derive(Debug)]
@@ -100,16 +276,57 @@ struct TelemetryChannelTransport<T> {
 #[derive(Debug)]
 struct TelemetryChannelTransportInner<T> {
     senders: Mutex<Vec<(Sender<Timestamped<T>>, usize)>>,
+    /// Last value published
+    latest: Mutex<Option<Timestamped<T>>>,
+    /// Set on sticky channels, whose latest value is delivered to the late subscribers. Copies
+    /// the latest value, as `T` is only known to be `Clone` where the channel is made sticky.
+    sticky: Mutex<Option<CopyLatest<T>>>,
+    /// Prefix subscribers, with the encoder of `T`
+    dynamic: Mutex<Vec<(DynamicSender, DynamicEncoder<T>)>>,
+    /// Wakers of the async receivers waiting for a value
+    #[cfg(feature = "async")]
+    wakers: Wakers,
 }
 
+type CopyLatest<T> = fn(&TelemetryChannelTransportInner<T>) -> Option<Timestamped<T>>;
+
+#[cfg(feature = "async")]
+type Wakers = Arc<Mutex<Vec<Waker>>>;
+
 impl<T> Default for TelemetryChannelTransportInner<T> {
     fn default() -> Self {
         TelemetryChannelTransportInner {
             senders: Mutex::new(Vec::new()),
+            latest: Mutex::new(None),
+            sticky: Mutex::new(None),
+            dynamic: Mutex::new(Vec::new()),
+            #[cfg(feature = "async")]
+            wakers: Arc::default(),
         }
     }
 }
 
+#[cfg(feature = "async")]
+fn wake_all(wakers: &Wakers) {
+    for waker in wakers.lock().unwrap().drain(..) {
+        waker.wake();
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T> Drop for TelemetryChannelTransportInner<T> {
+    fn drop(&mut self) {
+        // The channel is closed: the waiting receivers return Disconnected
+        wake_all(&self.wakers);
+    }
+}
+
+impl<T: Clone> TelemetryChannelTransportInner<T> {
+    fn latest(&self) -> Option<Timestamped<T>> {
+        self.latest.lock().unwrap().clone()
+    }
+}
+
 impl TelemetryChannel {
     fn new<T: 'static + Send>(name: &str, ch_type: ChannelType) -> Self {
         let transport = TelemetryChannelTransport::<T> {
@@ -123,15 +340,26 @@ impl TelemetryChannel {
             ch_type,
             num_producers: 0,
             num_subscribers: 0,
+            num_messages: Arc::default(),
+            type_id: TypeId::of::<T>(),
+            add_dynamic: Self::add_dynamic::<T>,
         }
     }
 
-    fn add_producer<T: 'static>(&mut self) -> Result<TelemetrySender<T>, TelemetryError> {
+    fn add_producer<T: 'static>(
+        &mut self,
+        ordering: Option<Arc<DeliveryOrdering>>,
+    ) -> Result<TelemetrySender<T>, TelemetryError> {
         self.num_producers += 1;
+        let channel_name = self.name.clone();
+        let num_messages = self.num_messages.clone();
         let transport = self.transport_mut::<T>()?;
 
         Ok(TelemetrySender {
             transport: transport.inner.clone(),
+            channel_name,
+            ordering,
+            num_messages,
         })
     }
 
@@ -148,9 +376,37 @@ impl TelemetryChannel {
             Capacity::Unbounded => unbounded(),
         };
 
+        let sticky = *transport.inner.sticky.lock().unwrap();
+        if let Some(sample) = sticky.and_then(|latest| latest(&transport.inner)) {
+            // Cannot fail: the receiver is held & empty
+            tx.try_send(sample).unwrap();
+        }
+
         transport.inner.senders.lock().unwrap().push((tx, num_subs));
 
-        Ok(TelemetryReceiver { receiver: rx })
+        Ok(TelemetryReceiver {
+            receiver: rx,
+            #[cfg(feature = "async")]
+            wakers: transport.inner.wakers.clone(),
+        })
+    }
+
+    fn add_dynamic<T: 'static>(&mut self, encoder: &(dyn Any + Send), tx: DynamicSender) {
+        let encoder = *encoder.downcast_ref::<DynamicEncoder<T>>().unwrap();
+        let transport = self.transport_mut::<T>().unwrap();
+
+        transport.inner.dynamic.lock().unwrap().push((tx, encoder));
+    }
+
+    fn info(&self) -> ChannelInfo {
+        ChannelInfo {
+            name: self.name.clone(),
+            typename: self.typename.clone(),
+            multi_producer: self.ch_type == ChannelType::MpMc,
+            num_producers: self.num_producers,
+            num_subscribers: self.num_subscribers,
+            num_messages: self.num_messages.load(Ordering::Relaxed),
+        }
     }
 
     #[allow(dead_code)]
@@ -184,6 +440,13 @@ pub struct TelemetryService {
 pub struct TelemetryServiceInner {
     remap: HashMap<String, String>,
     channels: HashMap<String, TelemetryChannel>,
+    units: HashMap<String, ChannelUnits>,
+    ordering: Option<Arc<DeliveryOrdering>>,
+    access_policy: Option<ChannelAccessPolicy>,
+    /// `DynamicEncoder` of each type, by type id
+    encoders: HashMap<TypeId, Box<dyn Any + Send>>,
+    /// Patterns of the prefix subscriptions
+    prefix_subscribers: Vec<(String, DynamicSender)>,
 }
 
 impl TelemetryService {
@@ -192,6 +455,11 @@ impl TelemetryService {
             inner: Arc::new(Mutex::new(TelemetryServiceInner {
                 remap,
                 channels: HashMap::new(),
+                units: HashMap::new(),
+                ordering: None,
+                access_policy: None,
+                encoders: HashMap::new(),
+                prefix_subscribers: Vec::new(),
             })),
         }
     }
@@ -200,20 +468,39 @@ impl TelemetryService {
         &self,
         channel_name: &str,
     ) -> Result<TelemetrySender<T>, TelemetryError> {
-        self.publish_impl(channel_name, ChannelType::SpMc)
+        self.publish_impl(channel_name, ChannelType::SpMc, None)
     }
 
     pub fn publish_mp<T: 'static + Send>(
         &self,
         channel_name: &str,
     ) -> Result<TelemetrySender<T>, TelemetryError> {
-        self.publish_impl(channel_name, ChannelType::MpMc)
+        self.publish_impl(channel_name, ChannelType::MpMc, None)
+    }
+
+    /// Publishes on behalf of a named producer, checked against the access policy
+    pub fn publish_as<T: 'static + Send>(
+        &self,
+        producer: &str,
+        channel_name: &str,
+    ) -> Result<TelemetrySender<T>, TelemetryError> {
+        self.publish_impl(channel_name, ChannelType::SpMc, Some(producer))
+    }
+
+    /// Publishes on behalf of a named producer, checked against the access policy
+    pub fn publish_mp_as<T: 'static + Send>(
+        &self,
+        producer: &str,
+        channel_name: &str,
+    ) -> Result<TelemetrySender<T>, TelemetryError> {
+        self.publish_impl(channel_name, ChannelType::MpMc, Some(producer))
     }
 
     fn publish_impl<T: 'static + Send>(
         &self,
         channel_name: &str,
         ch_type: ChannelType,
+        producer: Option<&str>,
     ) -> Result<TelemetrySender<T>, TelemetryError> {
         // Remap the channel if needed
         let mut inner = self.inner.lock().unwrap();
@@ -224,12 +511,27 @@ impl TelemetryService {
             .or(Some(channel_name.to_string()))
             .unwrap();
 
+        let allowed = inner
+            .access_policy
+            .as_ref()
+            .is_none_or(|policy| policy.is_allowed(&channel_name, producer));
+        if !allowed {
+            let producer = producer.unwrap_or("<anonymous>").to_string();
+            error!("Rejected producer '{producer}' on protected channel '{channel_name}'");
+
+            return Err(TelemetryError::PublishNotAllowed {
+                channel: channel_name,
+                producer,
+            });
+        }
+
+        let ordering = inner.ordering.clone();
         let channel = inner.get_channel::<T>(channel_name.as_str(), ch_type);
 
         match channel {
             Some(channel) => {
                 if channel.ch_type == ChannelType::MpMc || channel.num_producers == 0 {
-                    channel.add_producer()
+                    channel.add_producer(ordering)
                 } else {
                     Err(TelemetryError::AlreadyHasProducer)
                 }
@@ -254,6 +556,170 @@ impl TelemetryService {
         self.subscribe_impl(channel_name, capacity, ChannelType::MpMc)
     }
 
+    /// Records or enforces the order of the deliveries on all the channels. Only applies to
+    /// the producers created after this call.
+    pub fn set_ordering(&self, ordering: Arc<DeliveryOrdering>) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.ordering = Some(ordering);
+    }
+
+    /// Restricts the producers allowed on the protected channels. Only applies to the producers
+    /// created after this call.
+    pub fn set_access_policy(&self, policy: ChannelAccessPolicy) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.access_policy = Some(policy);
+    }
+
+    /// Attaches unit metadata to a channel. The channel does not need to exist yet.
+    pub fn set_units(&self, channel_name: &str, units: ChannelUnits) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.units.insert(channel_name.to_string(), units);
+    }
+
+    pub fn units(&self, channel_name: &str) -> Option<ChannelUnits> {
+        let inner = self.inner.lock().unwrap();
+        inner.units.get(channel_name).cloned()
+    }
+
+    /// Publishes on a sticky channel, starting from the `initial` value: the subscribers created
+    /// after a value is published receive the latest one right away
+    pub fn publish_sticky<T: 'static + Send + Clone>(
+        &self,
+        channel_name: &str,
+        timestamp: Timestamp,
+        initial: T,
+    ) -> Result<TelemetrySender<T>, TelemetryError> {
+        self.publish_sticky_impl(channel_name, None, timestamp, initial)
+    }
+
+    /// Publishes on a sticky channel on behalf of a named producer, checked against the access
+    /// policy
+    pub fn publish_sticky_as<T: 'static + Send + Clone>(
+        &self,
+        producer: &str,
+        channel_name: &str,
+        timestamp: Timestamp,
+        initial: T,
+    ) -> Result<TelemetrySender<T>, TelemetryError> {
+        self.publish_sticky_impl(channel_name, Some(producer), timestamp, initial)
+    }
+
+    fn publish_sticky_impl<T: 'static + Send + Clone>(
+        &self,
+        channel_name: &str,
+        producer: Option<&str>,
+        timestamp: Timestamp,
+        initial: T,
+    ) -> Result<TelemetrySender<T>, TelemetryError> {
+        let tx = self.publish_impl::<T>(channel_name, ChannelType::SpMc, producer)?;
+        *tx.transport.sticky.lock().unwrap() = Some(TelemetryChannelTransportInner::latest);
+
+        tx.send(timestamp, initial);
+        Ok(tx)
+    }
+
+    /// Latest value published on the channel, if any. Does not need a subscription: the last
+    /// value of every channel is kept.
+    pub fn latest<T: 'static + Send + Clone>(
+        &self,
+        channel_name: &str,
+    ) -> Result<Option<Timestamped<T>>, TelemetryError> {
+        let mut inner = self.inner.lock().unwrap();
+
+        match inner.channels.get_mut(channel_name) {
+            Some(channel) => Ok(channel.transport_mut::<T>()?.inner.latest()),
+            None => Ok(None),
+        }
+    }
+
+    /// Reader of the latest value published on the channel, for the nodes that only need the
+    /// current value instead of every sample
+    pub fn subscribe_latest<T: 'static + Send + Clone>(
+        &self,
+        channel_name: &str,
+    ) -> Result<LatestReceiver<T>, TelemetryError> {
+        let mut inner = self.inner.lock().unwrap();
+        let channel = inner
+            .get_channel::<T>(channel_name, ChannelType::SpMc)
+            .ok_or(TelemetryError::WrongChannelType)?;
+
+        Ok(LatestReceiver {
+            transport: channel.transport_mut::<T>()?.inner.clone(),
+        })
+    }
+
+    /// All the channels created so far, by name
+    pub fn channels(&self) -> Vec<ChannelInfo> {
+        let inner = self.inner.lock().unwrap();
+
+        let mut channels: Vec<ChannelInfo> = inner
+            .channels
+            .values()
+            .map(TelemetryChannel::info)
+            .collect();
+        channels.sort_by(|a, b| a.name.cmp(&b.name));
+        channels
+    }
+
+    /// Makes the channels of type `T` available to the prefix subscriptions
+    pub fn register_dynamic<T: 'static + Send>(&self, encode: DynamicEncoder<T>) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.encoders.insert(TypeId::of::<T>(), Box::new(encode));
+
+        let names: Vec<String> = inner
+            .channels
+            .values()
+            .filter(|ch| ch.type_id == TypeId::of::<T>())
+            .map(|ch| ch.name.clone())
+            .collect();
+        for name in names.iter() {
+            inner.add_prefix_subscribers(name, None);
+        }
+    }
+
+    /// Subscribes to all the channels matching `pattern`, created before or after the
+    /// subscription: a channel name, or a prefix ending with `*`, which matches any path below
+    /// it (eg. `/sensors/*`). Only the channels of the types registered with
+    /// `register_dynamic` are received.
+    pub fn subscribe_prefix(
+        &self,
+        pattern: &str,
+        capacity: Capacity,
+    ) -> Result<TelemetryReceiver<DynamicMessage>, TelemetryError> {
+        if !pattern.starts_with('/') || pattern.trim_end_matches('*').contains('*') {
+            return Err(TelemetryError::InvalidChannelName);
+        }
+
+        let (tx, rx) = match capacity {
+            Capacity::Bounded(cap) => bounded(cap.get()),
+            Capacity::Unbounded => unbounded(),
+        };
+        let tx = DynamicSender {
+            tx,
+            #[cfg(feature = "async")]
+            wakers: Wakers::default(),
+        };
+
+        let mut inner = self.inner.lock().unwrap();
+        let names: Vec<String> = inner
+            .channels
+            .keys()
+            .filter(|name| matches_pattern(pattern, name))
+            .cloned()
+            .collect();
+        for name in names.iter() {
+            inner.add_prefix_subscribers(name, Some((pattern, &tx)));
+        }
+        let receiver = TelemetryReceiver {
+            receiver: rx,
+            #[cfg(feature = "async")]
+            wakers: tx.wakers.clone(),
+        };
+        inner.prefix_subscribers.push((pattern.to_string(), tx));
+
+        Ok(receiver)
+    }
+
     fn subscribe_impl<T: 'static + Send>(
         &self,
         channel_name: &str,
@@ -280,6 +746,7 @@ impl TelemetryServiceInner {
                 channel_name.to_string(),
                 TelemetryChannel::new::<T>(channel_name, ch_type),
             );
+            self.add_prefix_subscribers(channel_name, None);
         }
 
         let ch = self.channels.get_mut(channel_name).unwrap();
@@ -290,6 +757,40 @@ impl TelemetryServiceInner {
             None
         }
     }
+
+    /// Forwards a channel to the matching prefix subscribers, if its type has an encoder. To all
+    /// of them, or only to `subscriber`.
+    fn add_prefix_subscribers(
+        &mut self,
+        channel_name: &str,
+        subscriber: Option<(&str, &DynamicSender)>,
+    ) {
+        let channel = self.channels.get_mut(channel_name).unwrap();
+        let Some(encoder) = self.encoders.get(&channel.type_id) else {
+            return;
+        };
+
+        let subscribers: Vec<(&str, &DynamicSender)> = match subscriber {
+            Some(subscriber) => vec![subscriber],
+            None => self
+                .prefix_subscribers
+                .iter()
+                .map(|(pattern, tx)| (pattern.as_str(), tx))
+                .collect(),
+        };
+        for (pattern, tx) in subscribers {
+            if matches_pattern(pattern, channel_name) {
+                (channel.add_dynamic)(channel, encoder.as_ref(), tx.clone());
+            }
+        }
+    }
+}
+
+fn matches_pattern(pattern: &str, channel_name: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => channel_name.starts_with(prefix),
+        None => pattern == channel_name,
+    }
 }
 
 #[cfg(test)]
@@ -342,6 +843,246 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_recv_timeout() -> Result<(), TelemetryError> {
+        let telem_service = TelemetryService::default();
+        let sub = telem_service.subscribe::<f64>("/test/channel/1", Capacity::Unbounded)?;
+        let ts = Timestamp::now(&SystemClock::default());
+
+        let start = Instant::now();
+        assert_eq!(
+            sub.recv_timeout(Duration::from_millis(20)),
+            Err(TelemetryError::Timeout)
+        );
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        let prod = telem_service.publish::<f64>("/test/channel/1")?;
+        let sender = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(10));
+            prod.send(ts, 1.0);
+        });
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        assert_eq!(sub.recv_deadline(deadline), Ok(Timestamped(ts, 1.0)));
+
+        // Closed when the producer & the service are gone
+        sender.join().unwrap();
+        drop(telem_service);
+        assert_eq!(
+            sub.recv_deadline(deadline),
+            Err(TelemetryError::Disconnected)
+        );
+
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_recv_async() -> Result<(), TelemetryError> {
+        use std::{
+            pin::pin,
+            task::{Context, Wake},
+            thread::{self, Thread},
+        };
+
+        struct ThreadWaker(Thread);
+
+        impl Wake for ThreadWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        fn block_on<F: Future>(fut: F) -> F::Output {
+            let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+            let mut cx = Context::from_waker(&waker);
+            let mut fut = pin!(fut);
+
+            loop {
+                match fut.as_mut().poll(&mut cx) {
+                    Poll::Ready(output) => return output,
+                    Poll::Pending => thread::park(),
+                }
+            }
+        }
+
+        let telem_service = TelemetryService::default();
+        let sub = telem_service.subscribe::<f64>("/test/channel/1", Capacity::Unbounded)?;
+        let prod = telem_service.publish::<f64>("/test/channel/1")?;
+        let ts = Timestamp::now(&SystemClock::default());
+
+        let sender = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            prod.send(ts, 1.0);
+            thread::sleep(Duration::from_millis(10));
+            prod.send(ts, 2.0);
+        });
+
+        assert_eq!(block_on(sub.recv_async()), Ok(Timestamped(ts, 1.0)));
+        assert_eq!(block_on(sub.recv_async()), Ok(Timestamped(ts, 2.0)));
+
+        sender.join().unwrap();
+        drop(telem_service);
+        assert_eq!(
+            block_on(sub.recv_async()),
+            Err(TelemetryError::Disconnected)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_latest() -> Result<(), TelemetryError> {
+        let telem_service = TelemetryService::default();
+        let ts = Timestamp::now(&SystemClock::default());
+
+        assert_eq!(telem_service.latest::<f64>("/test/channel/1")?, None);
+
+        let prod = telem_service.publish::<f64>("/test/channel/1")?;
+        let latest = telem_service.subscribe_latest::<f64>("/test/channel/1")?;
+        assert_eq!(latest.get(), None);
+
+        prod.send(ts, 1.0);
+        prod.send(ts, 2.0);
+        assert_eq!(latest.get(), Some(Timestamped(ts, 2.0)));
+        assert_eq!(
+            telem_service.latest::<f64>("/test/channel/1")?,
+            Some(Timestamped(ts, 2.0))
+        );
+        assert!(telem_service.latest::<f32>("/test/channel/1").is_err());
+
+        // Late subscribers only receive the following values, unless the channel is sticky
+        let late = telem_service.subscribe::<f64>("/test/channel/1", Capacity::Unbounded)?;
+        assert_eq!(late.try_recv(), Err(TelemetryError::Empty));
+
+        let early = telem_service.subscribe::<u32>("/test/sticky", Capacity::Unbounded)?;
+        let sticky = telem_service.publish_sticky::<u32>("/test/sticky", ts, 7)?;
+        assert_eq!(early.try_recv(), Ok(Timestamped(ts, 7)));
+
+        let late = telem_service.subscribe::<u32>("/test/sticky", 1usize.into())?;
+        assert_eq!(late.try_recv(), Ok(Timestamped(ts, 7)));
+
+        sticky.send(ts, 8);
+        let late = telem_service.subscribe::<u32>("/test/sticky", 1usize.into())?;
+        assert_eq!(late.try_recv(), Ok(Timestamped(ts, 8)));
+        assert_eq!(late.try_recv(), Err(TelemetryError::Empty));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_channels() -> Result<(), TelemetryError> {
+        let telem_service = TelemetryService::default();
+        let ts = Timestamp::now(&SystemClock::default());
+
+        let prod = telem_service.publish::<f64>("/test/channel/1")?;
+        let _sub1 = telem_service.subscribe::<f64>("/test/channel/1", Capacity::Unbounded)?;
+        let _sub2 = telem_service.subscribe::<f64>("/test/channel/1", Capacity::Unbounded)?;
+        let _events = telem_service.subscribe_mp::<u32>("/test/events", Capacity::Unbounded)?;
+
+        prod.send(ts, 1.0);
+        prod.send(ts, 2.0);
+
+        assert_eq!(
+            telem_service.channels(),
+            [
+                ChannelInfo {
+                    name: "/test/channel/1".to_string(),
+                    typename: "f64".to_string(),
+                    multi_producer: false,
+                    num_producers: 1,
+                    num_subscribers: 2,
+                    num_messages: 2,
+                },
+                ChannelInfo {
+                    name: "/test/events".to_string(),
+                    typename: "u32".to_string(),
+                    multi_producer: true,
+                    num_producers: 0,
+                    num_subscribers: 1,
+                    num_messages: 0,
+                },
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_subscribe_prefix() -> Result<(), TelemetryError> {
+        let telem_service = TelemetryService::default();
+        let ts = Timestamp::now(&SystemClock::default());
+
+        let imu = telem_service.publish::<f64>("/sensors/imu")?;
+        let sensors = telem_service.subscribe_prefix("/sensors/*", Capacity::Unbounded)?;
+        let baro = telem_service.subscribe_prefix("/sensors/baro", Capacity::Unbounded)?;
+        assert!(
+            telem_service
+                .subscribe_prefix("/sensors/*/x", Capacity::Unbounded)
+                .is_err()
+        );
+
+        // Types without an encoder are not received
+        let gps = telem_service.publish::<u32>("/sensors/gps")?;
+        telem_service.register_dynamic::<f64>(|v| Value::from(*v));
+
+        let baro_tx = telem_service.publish::<f64>("/sensors/baro")?;
+        let state = telem_service.publish::<f64>("/rocket/state")?;
+
+        imu.send(ts, 1.0);
+        gps.send(ts, 2);
+        baro_tx.send(ts, 3.0);
+        state.send(ts, 4.0);
+
+        let message = |channel: &str, value: f64| {
+            Ok(Timestamped(
+                ts,
+                DynamicMessage {
+                    channel: channel.to_string(),
+                    value: Value::from(value),
+                },
+            ))
+        };
+        assert_eq!(sensors.try_recv(), message("/sensors/imu", 1.0));
+        assert_eq!(sensors.try_recv(), message("/sensors/baro", 3.0));
+        assert_eq!(sensors.try_recv(), Err(TelemetryError::Empty));
+        assert_eq!(baro.try_recv(), message("/sensors/baro", 3.0));
+        assert_eq!(baro.try_recv(), Err(TelemetryError::Empty));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_numeric_fields() {
+        let message = DynamicMessage {
+            channel: "/sensors/imu".to_string(),
+            value: serde_json::json!({
+                "acc": { "x": 1.0, "y": -2 },
+                "valid": true,
+                "name": "imu",
+                "samples": [3.5, null],
+            }),
+        };
+
+        let mut fields = message.numeric_fields();
+        fields.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            fields,
+            [
+                ("acc/x".to_string(), 1.0),
+                ("acc/y".to_string(), -2.0),
+                ("samples/0".to_string(), 3.5),
+                ("valid".to_string(), 1.0),
+            ]
+        );
+
+        let scalar = DynamicMessage {
+            channel: "/sensors/baro".to_string(),
+            value: Value::from(3.0),
+        };
+        assert_eq!(scalar.numeric_fields(), [(String::new(), 3.0)]);
+    }
+
     #[test]
     fn test_remap() -> Result<(), TelemetryError> {
         let remap = HashMap::from([
@@ -406,4 +1147,46 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_access_policy() -> Result<(), TelemetryError> {
+        let telem_service = TelemetryService::default();
+        telem_service.set_access_policy(
+            ChannelAccessPolicy::new().allow("/test/protected", &["fsw", "orchestrator"]),
+        );
+
+        telem_service.publish_mp_as::<f64>("fsw", "/test/protected")?;
+        telem_service.publish_mp_as::<f64>("orchestrator", "/test/protected")?;
+        telem_service.publish_as::<f64>("debug", "/test/open")?;
+
+        assert_eq!(
+            telem_service
+                .publish_mp_as::<f64>("debug", "/test/protected")
+                .err(),
+            Some(TelemetryError::PublishNotAllowed {
+                channel: "/test/protected".to_string(),
+                producer: "debug".to_string()
+            })
+        );
+        assert!(telem_service.publish_mp::<f64>("/test/protected").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_units() {
+        let telem_service = TelemetryService::default();
+
+        assert_eq!(telem_service.units("/test/channel/1"), None);
+
+        telem_service.set_units(
+            "/test/channel/1",
+            ChannelUnits::new().field("speed", "m/s").vector3("pos", "m"),
+        );
+
+        let units = telem_service.units("/test/channel/1").unwrap();
+        assert_eq!(units.get("speed"), Some("m/s"));
+        assert_eq!(units.get("pos/y"), Some("m"));
+        assert_eq!(units.get("pos"), None);
+    }
 }
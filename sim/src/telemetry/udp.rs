@@ -0,0 +1,470 @@
+//! Live telemetry over UDP, for external tools, dashboards or another process.
+//!
+//! Each step, the samples of the forwarded channels are sent as protobuf [`TelemetryDatagram`]s,
+//! generated from interfaces/udp_telemetry.proto. Samples are encoded from their [`Recordable`]
+//! record, flattened into named fields: `accel_m_s2[0]`, `delta.dt_us`, ... Datagrams received
+//! from the remote end carry commands, in the same format, which are published on the command
+//! channels. The command channels are not enabled in the flight & HIL configurations.
+
+use std::{
+    collections::HashMap,
+    io::ErrorKind,
+    net::{SocketAddr, UdpSocket},
+};
+
+use anyhow::{Context, Result, anyhow};
+use chrono::TimeDelta;
+use log::warn;
+use prost::Message;
+use serde_json::{Map, Value};
+
+pub use super::udp_proto::{
+    TelemetryDatagram, TelemetryField, TelemetrySample, telemetry_field::Value as FieldValue,
+};
+use super::{
+    TelemetryReceiver, TelemetrySender, Timestamped,
+    recording::{Producers, Recordable, RecordingEndpoint},
+};
+use crate::{
+    core::time::{Clock, Timestamp},
+    nodes::{Node, NodeTelemetry, StepResult},
+    utils::capacity::Capacity::Unbounded,
+};
+
+/// Datagrams are split to stay below the typical MTU, to avoid IP fragmentation
+const MAX_DATAGRAM_BYTES: usize = 1400;
+
+/// Flattens a record into named fields. Null values (eg. empty options) are omitted.
+pub fn flatten(prefix: &str, value: &Value, fields: &mut Vec<TelemetryField>) {
+    let mut push = |value| {
+        fields.push(TelemetryField {
+            name: prefix.to_string(),
+            value: Some(value),
+        })
+    };
+
+    match value {
+        Value::Null => {}
+        Value::Bool(v) => push(FieldValue::Flag(*v)),
+        Value::Number(v) => push(FieldValue::Number(v.as_f64().unwrap_or(f64::NAN))),
+        Value::String(v) => push(FieldValue::Text(v.clone())),
+        Value::Array(values) => {
+            for (i, v) in values.iter().enumerate() {
+                flatten(&format!("{prefix}[{i}]"), v, fields);
+            }
+        }
+        Value::Object(values) => {
+            for (k, v) in values.iter() {
+                if prefix.is_empty() {
+                    flatten(k, v, fields);
+                } else {
+                    flatten(&format!("{prefix}.{k}"), v, fields);
+                }
+            }
+        }
+    }
+}
+
+enum PathSegment<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+fn parse_field_name(name: &str) -> Result<Vec<PathSegment<'_>>> {
+    let mut segments = vec![];
+
+    for part in name.split('.').filter(|p| !p.is_empty()) {
+        let mut pieces = part.split('[');
+
+        let key = pieces.next().unwrap_or_default();
+        if !key.is_empty() {
+            segments.push(PathSegment::Key(key));
+        }
+        for index in pieces {
+            let index = index
+                .strip_suffix(']')
+                .and_then(|i| i.parse().ok())
+                .ok_or(anyhow!("Invalid field name '{name}'"))?;
+            segments.push(PathSegment::Index(index));
+        }
+    }
+
+    Ok(segments)
+}
+
+/// Rebuilds a record from its flattened fields. Integral numbers are restored as integers, so
+/// that they can be deserialized into integer fields.
+pub fn unflatten(fields: &[TelemetryField]) -> Result<Value> {
+    let mut root = Value::Null;
+
+    for field in fields.iter() {
+        let value = match &field.value {
+            Some(FieldValue::Number(v)) if v.fract() == 0.0 && v.abs() < i64::MAX as f64 => {
+                Value::from(*v as i64)
+            }
+            Some(FieldValue::Number(v)) => Value::from(*v),
+            Some(FieldValue::Text(v)) => Value::from(v.clone()),
+            Some(FieldValue::Flag(v)) => Value::from(*v),
+            None => continue,
+        };
+
+        let mut node = &mut root;
+        for segment in parse_field_name(&field.name)? {
+            let mismatch = || anyhow!("Field '{}' does not match the others", field.name);
+
+            node = match segment {
+                PathSegment::Key(key) => {
+                    if node.is_null() {
+                        *node = Value::Object(Map::new());
+                    }
+                    node.as_object_mut()
+                        .ok_or_else(mismatch)?
+                        .entry(key)
+                        .or_insert(Value::Null)
+                }
+                PathSegment::Index(i) => {
+                    if node.is_null() {
+                        *node = Value::Array(vec![]);
+                    }
+                    let values = node.as_array_mut().ok_or_else(mismatch)?;
+                    if values.len() <= i {
+                        values.resize(i + 1, Value::Null);
+                    }
+                    &mut values[i]
+                }
+            };
+        }
+        *node = value;
+    }
+
+    Ok(root)
+}
+
+trait ForwardedChannel {
+    fn drain(&mut self, samples: &mut Vec<TelemetrySample>) -> Result<()>;
+}
+
+struct ForwardedChannelImpl<T> {
+    channel: String,
+    rx: TelemetryReceiver<T>,
+}
+
+impl<T: Recordable> ForwardedChannel for ForwardedChannelImpl<T> {
+    fn drain(&mut self, samples: &mut Vec<TelemetrySample>) -> Result<()> {
+        while let Ok(Timestamped(t, value)) = self.rx.try_recv() {
+            let mut fields = vec![];
+            flatten("", &serde_json::to_value(value.to_record())?, &mut fields);
+
+            samples.push(TelemetrySample {
+                channel: self.channel.clone(),
+                t_us: t.monotonic.elapsed().num_microseconds().unwrap(),
+                fields,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+trait CommandChannel {
+    fn publish(&self, t: Timestamp, fields: &[TelemetryField]) -> Result<()>;
+}
+
+struct CommandChannelImpl<T> {
+    tx: TelemetrySender<T>,
+    filter: fn(&T) -> bool,
+}
+
+impl<T: Recordable> CommandChannel for CommandChannelImpl<T> {
+    fn publish(&self, t: Timestamp, fields: &[TelemetryField]) -> Result<()> {
+        let value = T::from_record(serde_json::from_value(unflatten(fields)?)?)?;
+        if (self.filter)(&value) {
+            self.tx.send(t, value);
+        }
+
+        Ok(())
+    }
+}
+
+/// Sends the samples of the forwarded channels to a remote UDP endpoint, and publishes the
+/// commands received from it. Never blocks: datagrams that cannot be sent are dropped.
+pub struct UdpTelemetryBridge {
+    socket: UdpSocket,
+    remote: SocketAddr,
+
+    forwarded: Vec<Box<dyn ForwardedChannel + Send>>,
+    commands: HashMap<String, Box<dyn CommandChannel + Send>>,
+
+    samples: Vec<TelemetrySample>,
+    send_errors: usize,
+}
+
+impl UdpTelemetryBridge {
+    /// Sends to `remote`, from a socket bound to `local`. Commands are received on `local`.
+    pub fn new(local: &str, remote: &str) -> Result<Self> {
+        let socket =
+            UdpSocket::bind(local).with_context(|| format!("Cannot bind UDP socket to {local}"))?;
+        socket.set_nonblocking(true)?;
+
+        let remote = remote
+            .parse()
+            .with_context(|| format!("Invalid UDP telemetry address '{remote}'"))?;
+
+        Ok(Self {
+            socket,
+            remote,
+            forwarded: vec![],
+            commands: HashMap::new(),
+            samples: vec![],
+            send_errors: 0,
+        })
+    }
+
+    /// Address commands are received on
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// Publishes the commands received for `channel` accepted by `filter`, eg. only the ground
+    /// commands on a channel also carrying the events of the flight software
+    pub fn add_command_channel<T: Recordable>(
+        &mut self,
+        telem: &NodeTelemetry,
+        channel: &str,
+        producers: Producers,
+        filter: fn(&T) -> bool,
+    ) -> Result<()> {
+        let tx = match producers {
+            Producers::Single => telem.publish::<T>(channel)?,
+            Producers::Multiple => telem.publish_mp::<T>(channel)?,
+        };
+
+        self.commands.insert(
+            channel.to_string(),
+            Box::new(CommandChannelImpl { tx, filter }),
+        );
+
+        Ok(())
+    }
+
+    fn send(&mut self, datagram: &TelemetryDatagram) {
+        if let Err(e) = self.socket.send_to(&datagram.encode_to_vec(), self.remote) {
+            if self.send_errors == 0 {
+                warn!("Cannot send UDP telemetry to {}: {e}", self.remote);
+            }
+            self.send_errors += 1;
+        }
+    }
+
+    fn receive_commands(&mut self, t: Timestamp) -> Result<()> {
+        let mut buf = [0u8; 65536];
+
+        loop {
+            let len = match self.socket.recv(&mut buf) {
+                Ok(len) => len,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(()),
+                // Reported by some platforms when the remote end is not listening
+                Err(e) if e.kind() == ErrorKind::ConnectionReset => continue,
+                Err(e) => return Err(e.into()),
+            };
+
+            let datagram = match TelemetryDatagram::decode(&buf[..len]) {
+                Ok(datagram) => datagram,
+                Err(e) => {
+                    warn!("Invalid UDP command datagram: {e}");
+                    continue;
+                }
+            };
+
+            for sample in datagram.samples.iter() {
+                match self.commands.get(&sample.channel) {
+                    Some(channel) => {
+                        if let Err(e) = channel.publish(t, &sample.fields) {
+                            warn!("Invalid UDP command on '{}': {e:#}", sample.channel);
+                        }
+                    }
+                    None => warn!("UDP command on unknown channel '{}'", sample.channel),
+                }
+            }
+        }
+    }
+}
+
+impl RecordingEndpoint for UdpTelemetryBridge {
+    fn add_channel<T: Recordable>(
+        &mut self,
+        telem: &NodeTelemetry,
+        channel: &str,
+        producers: Producers,
+    ) -> Result<()> {
+        let rx = match producers {
+            Producers::Single => telem.subscribe::<T>(channel, Unbounded)?,
+            Producers::Multiple => telem.subscribe_mp::<T>(channel, Unbounded)?,
+        };
+
+        self.forwarded.push(Box::new(ForwardedChannelImpl {
+            channel: channel.to_string(),
+            rx,
+        }));
+
+        Ok(())
+    }
+}
+
+impl Node for UdpTelemetryBridge {
+    fn step(&mut self, i: usize, _: TimeDelta, clock: &dyn Clock) -> Result<StepResult> {
+        if !self.commands.is_empty() {
+            self.receive_commands(Timestamp::now(clock))?;
+        }
+
+        for channel in self.forwarded.iter_mut() {
+            channel.drain(&mut self.samples)?;
+        }
+        self.samples.sort_by_key(|s| s.t_us);
+
+        let mut datagram = TelemetryDatagram {
+            step: i as u64,
+            samples: vec![],
+        };
+        for sample in std::mem::take(&mut self.samples) {
+            let len = sample.encoded_len();
+            if !datagram.samples.is_empty()
+                && datagram.encoded_len() + len + prost::length_delimiter_len(len) + 1
+                    > MAX_DATAGRAM_BYTES
+            {
+                self.send(&datagram);
+                datagram.samples.clear();
+            }
+            datagram.samples.push(sample);
+        }
+        if !datagram.samples.is_empty() {
+            self.send(&datagram);
+        }
+
+        Ok(StepResult::Continue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::Duration};
+
+    use chrono::Utc;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{core::time::SimulatedClock, telemetry::TelemetryService};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Sample(f64);
+
+    impl Recordable for Sample {
+        type Record = f64;
+
+        fn to_record(&self) -> f64 {
+            self.0
+        }
+
+        fn from_record(record: f64) -> Result<Self> {
+            Ok(Sample(record))
+        }
+    }
+
+    #[test]
+    fn test_flatten() -> Result<()> {
+        let value = json!({
+            "accel": [1.5, -2.25, 3],
+            "delta": { "dt_us": 1000, "name": "imu" },
+            "temperature": null,
+            "valid": true,
+        });
+
+        let mut fields = vec![];
+        flatten("", &value, &mut fields);
+
+        let names: Vec<&str> = fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "accel[0]",
+                "accel[1]",
+                "accel[2]",
+                "delta.dt_us",
+                "delta.name",
+                "valid"
+            ]
+        );
+
+        let mut expected = value.clone();
+        expected.as_object_mut().unwrap().remove("temperature");
+        assert_eq!(unflatten(&fields)?, expected);
+
+        // Single values
+        let mut fields = vec![];
+        flatten("", &json!("Drogue"), &mut fields);
+        assert_eq!(unflatten(&fields)?, json!("Drogue"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_forward_and_command() -> Result<()> {
+        let mut clock = SimulatedClock::new(Utc::now(), TimeDelta::zero());
+        let dt = TimeDelta::milliseconds(10);
+
+        let remote = UdpSocket::bind("127.0.0.1:0")?;
+        remote.set_read_timeout(Some(Duration::from_secs(1)))?;
+
+        let ts = TelemetryService::default();
+        let tx = ts.publish::<Sample>("/test/a")?;
+        let rx_cmd = ts.subscribe::<Sample>("/test/cmd", Unbounded)?;
+
+        let telem = NodeTelemetry::new(ts.clone(), "udp_bridge", HashMap::new(), HashMap::new());
+        let mut bridge = UdpTelemetryBridge::new("127.0.0.1:0", &remote.local_addr()?.to_string())?;
+        bridge.add_channel::<Sample>(&telem, "/test/a", Producers::Single)?;
+        bridge
+            .add_command_channel::<Sample>(&telem, "/test/cmd", Producers::Single, |s| s.0 > 0.0)?;
+
+        clock.step(dt);
+        tx.send(Timestamp::now(&clock), Sample(1.5));
+        tx.send(Timestamp::now(&clock), Sample(2.5));
+        bridge.step(0, dt, &clock)?;
+
+        let mut buf = [0u8; 2048];
+        let len = remote.recv(&mut buf)?;
+        let datagram = TelemetryDatagram::decode(&buf[..len])?;
+        assert_eq!(datagram.samples.len(), 2);
+        assert_eq!(datagram.samples[0].channel, "/test/a");
+        assert_eq!(datagram.samples[0].t_us, 10_000);
+        assert_eq!(
+            datagram.samples[1].fields[0].value,
+            Some(FieldValue::Number(2.5))
+        );
+
+        // Commands, of which the filtered one is not published
+        let command = |v: f64| TelemetrySample {
+            channel: "/test/cmd".to_string(),
+            t_us: 0,
+            fields: vec![TelemetryField {
+                name: String::new(),
+                value: Some(FieldValue::Number(v)),
+            }],
+        };
+        let datagram = TelemetryDatagram {
+            step: 0,
+            samples: vec![command(3.0), command(-1.0)],
+        };
+        remote.send_to(&datagram.encode_to_vec(), bridge.local_addr()?)?;
+        thread::sleep(Duration::from_millis(50));
+
+        clock.step(dt);
+        bridge.step(1, dt, &clock)?;
+
+        let Timestamped(t, cmd) = rx_cmd.try_recv().unwrap();
+        assert_eq!(t, Timestamp::from_micros(20_000));
+        assert_eq!(cmd, Sample(3.0));
+        assert!(rx_cmd.try_recv().is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+// Generated by `cargo xtask codegen` from interfaces/udp_telemetry.proto: do not edit.
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TelemetryDatagram {
+    /// Simulation step the samples were sent at. Unused in commands.
+    #[prost(uint64, tag = "1")]
+    pub step: u64,
+    #[prost(message, repeated, tag = "2")]
+    pub samples: Vec<TelemetrySample>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TelemetrySample {
+    #[prost(string, tag = "1")]
+    pub channel: String,
+    /// Simulation time of the sample. Ignored in commands.
+    #[prost(int64, tag = "2")]
+    pub t_us: i64,
+    #[prost(message, repeated, tag = "3")]
+    pub fields: Vec<TelemetryField>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TelemetryField {
+    /// Path of the field in the record, empty if the record is a single value
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(oneof = "telemetry_field::Value", tags = "2, 3, 4")]
+    pub value: Option<telemetry_field::Value>,
+}
+
+/// Nested types of `TelemetryField`
+pub mod telemetry_field {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Value {
+        #[prost(double, tag = "2")]
+        Number(f64),
+        #[prost(string, tag = "3")]
+        Text(String),
+        #[prost(bool, tag = "4")]
+        Flag(bool),
+    }
+}
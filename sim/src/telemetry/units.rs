@@ -0,0 +1,51 @@
+use std::collections::BTreeMap;
+
+/// Physical units of the fields of a telemetry channel.
+///
+/// Fields are identified by their path relative to the channel (eg. `pos_n/x`), matching the
+/// entity paths used when the channel is logged.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChannelUnits {
+    fields: BTreeMap<String, String>,
+}
+
+impl ChannelUnits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn field(mut self, path: &str, unit: &str) -> Self {
+        self.fields.insert(path.to_string(), unit.to_string());
+        self
+    }
+
+    /// Adds the named components of a field, all with the same unit. An empty path refers
+    /// to the channel itself.
+    pub fn components(self, path: &str, names: &[&str], unit: &str) -> Self {
+        names.iter().fold(self, |u, c| {
+            if path.is_empty() {
+                u.field(c, unit)
+            } else {
+                u.field(&format!("{path}/{c}"), unit)
+            }
+        })
+    }
+
+    /// Adds the x, y, z components of a vector field
+    pub fn vector3(self, path: &str, unit: &str) -> Self {
+        self.components(path, &["x", "y", "z"], unit)
+    }
+
+    /// Adds the yaw, pitch, roll components of an euler angles field
+    pub fn euler(self, path: &str, unit: &str) -> Self {
+        self.components(path, &["yaw", "pitch", "roll"], unit)
+    }
+
+    pub fn get(&self, path: &str) -> Option<&str> {
+        self.fields.get(path).map(|u| u.as_str())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.fields.iter().map(|(p, u)| (p.as_str(), u.as_str()))
+    }
+}
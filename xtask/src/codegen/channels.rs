@@ -0,0 +1,74 @@
+use std::fmt::Write;
+
+use anyhow::Result;
+
+use super::{DEFINITIONS, Definitions, UnitDef};
+
+pub const OUTPUT: &str = "sim/src/crater/channel_defs.rs";
+
+/// Channel name constants, grouped in modules, and the registration of their units
+pub fn generate(defs: &Definitions) -> Result<String> {
+    let mut out = String::new();
+
+    writeln!(
+        out,
+        "// Generated by `cargo xtask codegen` from {DEFINITIONS}: do not edit.\n"
+    )?;
+    writeln!(
+        out,
+        "use crate::telemetry::{{ChannelUnits, TelemetryService}};\n"
+    )?;
+
+    let mut groups: Vec<&str> = vec![];
+    for ch in defs.channels.iter() {
+        if !groups.contains(&ch.group.as_str()) {
+            groups.push(&ch.group);
+        }
+    }
+
+    for group in groups {
+        writeln!(out, "pub mod {group} {{")?;
+        for ch in defs.channels.iter().filter(|c| c.group == group) {
+            if let Some(doc) = &ch.doc {
+                writeln!(out, "/// {doc}")?;
+            }
+            writeln!(out, "pub const {}: &str = {:?};", ch.name, ch.path)?;
+        }
+        writeln!(out, "}}\n")?;
+    }
+
+    writeln!(
+        out,
+        "/// Attaches unit metadata to the channels, used to label the logged series.\n\
+         /// Field paths match the entity paths used by the loggers, so units refer to logged values."
+    )?;
+    writeln!(out, "pub fn register_units(ts: &TelemetryService) {{")?;
+    for ch in defs.channels.iter() {
+        let units = defs.units(ch)?;
+        if units.is_empty() {
+            continue;
+        }
+
+        write!(
+            out,
+            "ts.set_units({}, ChannelUnits::new()",
+            ch.qualified_name()
+        )?;
+        for unit in units {
+            match unit {
+                UnitDef::Field { field, unit } => write!(out, ".field({field:?}, {unit:?})")?,
+                UnitDef::Vector3 { field, unit } => write!(out, ".vector3({field:?}, {unit:?})")?,
+                UnitDef::Euler { field, unit } => write!(out, ".euler({field:?}, {unit:?})")?,
+                UnitDef::Components {
+                    field,
+                    components,
+                    unit,
+                } => write!(out, ".components({field:?}, &{components:?}, {unit:?})")?,
+            }
+        }
+        writeln!(out, ");")?;
+    }
+    writeln!(out, "}}")?;
+
+    Ok(out)
+}
@@ -0,0 +1,124 @@
+use std::fmt::Write;
+
+use anyhow::{Context, Result};
+
+use super::{DEFINITIONS, Definitions};
+
+pub const OUTPUT: &str = "gnc/proto/mav_crater.xml";
+pub const VERSION_OUTPUT: &str = "gnc/src/datatypes/dialect.rs";
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Interface version of the dialect, for the messages stored outside of a MAVLink stream, such as
+/// the records of the flash log
+pub fn generate_version(defs: &Definitions) -> Result<String> {
+    let major = u16::try_from(defs.mavlink.version).context("MAVLink dialect version over u16")?;
+    let mut out = String::new();
+
+    writeln!(
+        out,
+        "// Generated by `cargo xtask codegen` from {DEFINITIONS}: do not edit.\n"
+    )?;
+    writeln!(out, "use super::version::InterfaceVersion;\n")?;
+    writeln!(out, "/// Version of the mav_crater dialect")?;
+    writeln!(
+        out,
+        "pub const MAV_CRATER_VERSION: InterfaceVersion = InterfaceVersion::new({major}, 0);"
+    )?;
+
+    Ok(out)
+}
+
+/// MAVLink dialect of the crater messages, in the XML format read by mavlink-bindgen
+pub fn generate(defs: &Definitions) -> Result<String> {
+    let mav = &defs.mavlink;
+    let mut out = String::new();
+
+    writeln!(
+        out,
+        "<mavlink xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\"\n    \
+         xsi:noNamespaceSchemaLocation=\"https://raw.githubusercontent.com/ArduPilot/pymavlink/refs/heads/master/generator/mavschema.xsd\">"
+    )?;
+    writeln!(
+        out,
+        "    <!-- Generated by `cargo xtask codegen` from {DEFINITIONS}: do not edit. -->"
+    )?;
+    writeln!(out, "    <version>{}</version>", mav.version)?;
+
+    writeln!(out, "    <enums>")?;
+    for e in mav.enums.iter() {
+        writeln!(out, "        <enum name=\"{}\">", escape(&e.name))?;
+        writeln!(
+            out,
+            "            <description>{}</description>",
+            escape(&e.description)
+        )?;
+        for entry in e.entries.iter() {
+            writeln!(
+                out,
+                "            <entry name=\"{}\" value=\"{}\">",
+                escape(&entry.name),
+                entry.value
+            )?;
+            writeln!(
+                out,
+                "                <description>{}</description>",
+                escape(&entry.description)
+            )?;
+            for (i, param) in entry.params.iter().enumerate() {
+                writeln!(
+                    out,
+                    "                <param index=\"{}\">{}</param>",
+                    i + 1,
+                    escape(param)
+                )?;
+            }
+            writeln!(out, "            </entry>")?;
+        }
+        writeln!(out, "        </enum>")?;
+    }
+    writeln!(out, "    </enums>")?;
+
+    writeln!(out, "    <messages>")?;
+    for msg in mav.messages.iter() {
+        writeln!(
+            out,
+            "        <message id=\"{}\" name=\"{}\">",
+            msg.id,
+            escape(&msg.name)
+        )?;
+        writeln!(
+            out,
+            "            <description>{}</description>",
+            escape(&msg.description)
+        )?;
+        for field in msg.fields.iter() {
+            write!(
+                out,
+                "            <field type=\"{}\" name=\"{}\"",
+                escape(&field.ty),
+                escape(&field.name)
+            )?;
+            if let Some(e) = &field.enum_name {
+                write!(out, " enum=\"{}\"", escape(e))?;
+            }
+            if let Some(units) = &field.units {
+                write!(out, " units=\"{}\"", escape(units))?;
+            }
+            if let Some(invalid) = &field.invalid {
+                write!(out, " invalid=\"{}\"", escape(invalid))?;
+            }
+            writeln!(out, ">{}</field>", escape(&field.description))?;
+        }
+        writeln!(out, "        </message>")?;
+    }
+    writeln!(out, "    </messages>")?;
+    writeln!(out, "</mavlink>")?;
+
+    Ok(out)
+}
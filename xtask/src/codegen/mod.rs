@@ -0,0 +1,285 @@
+//! Code generation from the interface definitions in `interfaces/telemetry.toml`, and from the
+//! protobuf messages of the UDP telemetry in `interfaces/udp_telemetry.proto`.
+//!
+//! Adding a channel or a MAVLink message only requires editing the definitions and running
+//! `cargo xtask codegen`. The MAVLink bindings are then generated from the dialect by the build
+//! script of the gnc crate.
+
+mod channels;
+mod mavlink;
+mod proto;
+
+use std::{
+    collections::HashSet,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde::Deserialize;
+
+pub const DEFINITIONS: &str = "interfaces/telemetry.toml";
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Definitions {
+    pub channels: Vec<ChannelDef>,
+    pub mavlink: MavlinkDef,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChannelDef {
+    /// Module the constant is declared in
+    pub group: String,
+    pub name: String,
+    pub path: String,
+    pub doc: Option<String>,
+    #[serde(default)]
+    pub units: Vec<UnitDef>,
+    /// Reuse the units of another channel, as "group::NAME"
+    pub units_from: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum UnitDef {
+    Field {
+        field: String,
+        unit: String,
+    },
+    Vector3 {
+        field: String,
+        unit: String,
+    },
+    Euler {
+        field: String,
+        unit: String,
+    },
+    Components {
+        field: String,
+        components: Vec<String>,
+        unit: String,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MavlinkDef {
+    pub version: u32,
+    pub enums: Vec<MavEnumDef>,
+    pub messages: Vec<MavMessageDef>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MavEnumDef {
+    pub name: String,
+    pub description: String,
+    pub entries: Vec<MavEnumEntryDef>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MavEnumEntryDef {
+    pub name: String,
+    pub value: u32,
+    pub description: String,
+    #[serde(default)]
+    pub params: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MavMessageDef {
+    pub id: u32,
+    pub name: String,
+    pub description: String,
+    pub fields: Vec<MavFieldDef>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MavFieldDef {
+    #[serde(rename = "type")]
+    pub ty: String,
+    pub name: String,
+    pub description: String,
+    pub units: Option<String>,
+    #[serde(rename = "enum")]
+    pub enum_name: Option<String>,
+    pub invalid: Option<String>,
+}
+
+impl Definitions {
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = root.join(DEFINITIONS);
+        let defs: Definitions = toml::from_str(
+            &fs::read_to_string(&path).with_context(|| format!("Reading {}", path.display()))?,
+        )
+        .with_context(|| format!("Parsing {}", path.display()))?;
+
+        defs.validate()?;
+        Ok(defs)
+    }
+
+    fn validate(&self) -> Result<()> {
+        let mut names = HashSet::new();
+        let mut paths = HashSet::new();
+        for ch in self.channels.iter() {
+            if !names.insert(ch.qualified_name()) {
+                bail!("Duplicate channel '{}'", ch.qualified_name());
+            }
+            if !paths.insert(&ch.path) {
+                bail!("Duplicate channel path '{}'", ch.path);
+            }
+            if ch.units_from.is_some() && !ch.units.is_empty() {
+                bail!(
+                    "Channel '{}' has both units & units_from",
+                    ch.qualified_name()
+                );
+            }
+        }
+        for ch in self.channels.iter() {
+            self.units(ch)?;
+        }
+
+        let mut ids = HashSet::new();
+        for msg in self.mavlink.messages.iter() {
+            if !ids.insert(msg.id) {
+                bail!("Duplicate MAVLink message id {} ({})", msg.id, msg.name);
+            }
+            for field in msg.fields.iter() {
+                if let Some(e) = &field.enum_name
+                    && !self.mavlink.enums.iter().any(|d| &d.name == e)
+                {
+                    bail!("Unknown enum '{e}' in {}.{}", msg.name, field.name);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Units of the channel, following `units_from`
+    pub fn units<'a>(&'a self, ch: &'a ChannelDef) -> Result<&'a [UnitDef]> {
+        match &ch.units_from {
+            Some(from) => {
+                let source = self
+                    .channels
+                    .iter()
+                    .find(|c| &c.qualified_name() == from)
+                    .ok_or(anyhow!(
+                        "Unknown channel '{from}' in {}",
+                        ch.qualified_name()
+                    ))?;
+                if source.units_from.is_some() {
+                    bail!("Channel '{from}' used in units_from must define its own units");
+                }
+                Ok(&source.units)
+            }
+            None => Ok(&ch.units),
+        }
+    }
+}
+
+impl ChannelDef {
+    pub fn qualified_name(&self) -> String {
+        format!("{}::{}", self.group, self.name)
+    }
+}
+
+/// A file derived from the definitions
+#[derive(Debug)]
+pub struct GeneratedFile {
+    pub path: PathBuf,
+    pub content: String,
+}
+
+impl GeneratedFile {
+    fn is_up_to_date(&self) -> bool {
+        fs::read_to_string(&self.path).is_ok_and(|c| c == self.content)
+    }
+
+    /// Writes the file if its content changed. Returns whether it was written.
+    pub fn write(&self) -> Result<bool> {
+        if self.is_up_to_date() {
+            return Ok(false);
+        }
+
+        fs::write(&self.path, &self.content)
+            .with_context(|| format!("Writing {}", self.path.display()))?;
+        Ok(true)
+    }
+}
+
+pub fn generate(root: &Path) -> Result<Vec<GeneratedFile>> {
+    let defs = Definitions::load(root)?;
+
+    Ok(vec![
+        GeneratedFile {
+            path: root.join(channels::OUTPUT),
+            content: rustfmt(&channels::generate(&defs)?)?,
+        },
+        GeneratedFile {
+            path: root.join(mavlink::OUTPUT),
+            content: mavlink::generate(&defs)?,
+        },
+        GeneratedFile {
+            path: root.join(mavlink::VERSION_OUTPUT),
+            content: rustfmt(&mavlink::generate_version(&defs)?)?,
+        },
+        GeneratedFile {
+            path: root.join(proto::OUTPUT),
+            content: rustfmt(&proto::generate(&read_input(root, proto::INPUT)?)?)?,
+        },
+    ])
+}
+
+fn read_input(root: &Path, path: &str) -> Result<String> {
+    let path = root.join(path);
+    fs::read_to_string(&path).with_context(|| format!("Reading {}", path.display()))
+}
+
+/// Generated files whose content differs from the definitions
+pub fn stale_files(root: &Path) -> Result<Vec<PathBuf>> {
+    Ok(generate(root)?
+        .into_iter()
+        .filter(|f| !f.is_up_to_date())
+        .map(|f| f.path)
+        .collect())
+}
+
+/// Formats the generated code, so that it is stable when formatting the whole crate
+fn rustfmt(code: &str) -> Result<String> {
+    let mut child = Command::new("rustfmt")
+        .args(["--edition", "2024", "--emit", "stdout"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("Running rustfmt")?;
+
+    child.stdin.take().unwrap().write_all(code.as_bytes())?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!("rustfmt failed on the generated code");
+    }
+
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_files_in_sync() {
+        let stale = stale_files(&crate::repo_root()).unwrap();
+        assert!(
+            stale.is_empty(),
+            "Generated files out of date, run `cargo xtask codegen`: {stale:?}"
+        );
+    }
+}
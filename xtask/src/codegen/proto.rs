@@ -0,0 +1,317 @@
+//! Rust types of the protobuf messages, laid out as prost-build would generate them. Only the
+//! subset of proto3 used by the interfaces is supported: scalar, message & repeated fields, and
+//! oneofs of scalars.
+
+use std::fmt::Write;
+
+use anyhow::{Context, Result, anyhow, bail};
+
+pub const INPUT: &str = "interfaces/udp_telemetry.proto";
+pub const OUTPUT: &str = "sim/src/telemetry/udp_proto.rs";
+
+#[derive(Debug)]
+struct ProtoField {
+    doc: Vec<String>,
+    ty: String,
+    name: String,
+    tag: u32,
+    repeated: bool,
+}
+
+#[derive(Debug)]
+struct ProtoOneof {
+    doc: Vec<String>,
+    name: String,
+    fields: Vec<ProtoField>,
+}
+
+#[derive(Debug)]
+enum ProtoItem {
+    Field(ProtoField),
+    Oneof(ProtoOneof),
+}
+
+#[derive(Debug)]
+struct ProtoMessage {
+    doc: Vec<String>,
+    name: String,
+    items: Vec<ProtoItem>,
+}
+
+fn parse_field(line: &str, doc: Vec<String>) -> Result<ProtoField> {
+    let (decl, tag) = line
+        .strip_suffix(';')
+        .and_then(|l| l.split_once('='))
+        .ok_or(anyhow!("Invalid field '{line}'"))?;
+    let tag = tag
+        .trim()
+        .parse()
+        .with_context(|| format!("Invalid tag in '{line}'"))?;
+
+    let (repeated, ty, name) = match decl.split_whitespace().collect::<Vec<_>>()[..] {
+        ["repeated", ty, name] => (true, ty, name),
+        [ty, name] => (false, ty, name),
+        _ => bail!("Invalid field '{line}'"),
+    };
+
+    Ok(ProtoField {
+        doc,
+        ty: ty.to_string(),
+        name: name.to_string(),
+        tag,
+        repeated,
+    })
+}
+
+fn parse(src: &str) -> Result<Vec<ProtoMessage>> {
+    let mut messages: Vec<ProtoMessage> = vec![];
+    let mut message: Option<ProtoMessage> = None;
+    let mut oneof: Option<ProtoOneof> = None;
+    let mut doc = vec![];
+
+    for line in src.lines().map(str::trim) {
+        if let Some(comment) = line.strip_prefix("//") {
+            doc.push(comment.trim().to_string());
+            continue;
+        }
+        // Comments separated by a blank line document the file, not the next item
+        let doc = std::mem::take(&mut doc);
+
+        if line.is_empty() || line.starts_with("package ") {
+            continue;
+        } else if line.starts_with("syntax ") {
+            if line != "syntax = \"proto3\";" {
+                bail!("Only proto3 is supported: '{line}'");
+            }
+        } else if line == "}" {
+            if let Some(o) = oneof.take() {
+                let Some(m) = message.as_mut() else {
+                    bail!("Unbalanced '}}'");
+                };
+                m.items.push(ProtoItem::Oneof(o));
+            } else {
+                messages.push(message.take().ok_or(anyhow!("Unbalanced '}}'"))?);
+            }
+        } else if let Some(name) = line
+            .strip_prefix("message ")
+            .and_then(|l| l.strip_suffix('{'))
+        {
+            if message.is_some() {
+                bail!("Nested messages are not supported: '{line}'");
+            }
+            message = Some(ProtoMessage {
+                doc,
+                name: name.trim().to_string(),
+                items: vec![],
+            });
+        } else if let Some(name) = line
+            .strip_prefix("oneof ")
+            .and_then(|l| l.strip_suffix('{'))
+        {
+            if message.is_none() || oneof.is_some() {
+                bail!("Oneof outside of a message: '{line}'");
+            }
+            oneof = Some(ProtoOneof {
+                doc,
+                name: name.trim().to_string(),
+                fields: vec![],
+            });
+        } else {
+            let field = parse_field(line, doc)?;
+            if let Some(o) = oneof.as_mut() {
+                if field.repeated {
+                    bail!("Repeated field in a oneof: '{line}'");
+                }
+                o.fields.push(field);
+            } else if let Some(m) = message.as_mut() {
+                m.items.push(ProtoItem::Field(field));
+            } else {
+                bail!("Field outside of a message: '{line}'");
+            }
+        }
+    }
+
+    if message.is_some() {
+        bail!("Unterminated message");
+    }
+    Ok(messages)
+}
+
+/// Rust type & prost type of a scalar
+fn scalar(ty: &str) -> Option<(&'static str, &'static str)> {
+    Some(match ty {
+        "double" => ("f64", "double"),
+        "float" => ("f32", "float"),
+        "int32" => ("i32", "int32"),
+        "int64" => ("i64", "int64"),
+        "uint32" => ("u32", "uint32"),
+        "uint64" => ("u64", "uint64"),
+        "bool" => ("bool", "bool"),
+        "string" => ("String", "string"),
+        "bytes" => ("Vec<u8>", "bytes = \"vec\""),
+        _ => return None,
+    })
+}
+
+fn snake_case(name: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            out.push('_');
+        }
+        out.push(c.to_ascii_lowercase());
+    }
+    out
+}
+
+fn camel_case(name: &str) -> String {
+    name.split('_')
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|c| c.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+fn write_doc(out: &mut String, doc: &[String]) -> Result<()> {
+    for line in doc {
+        writeln!(out, "/// {line}")?;
+    }
+    Ok(())
+}
+
+fn write_field(out: &mut String, field: &ProtoField, messages: &[ProtoMessage]) -> Result<()> {
+    write_doc(out, &field.doc)?;
+
+    let (rust_ty, attr) = match scalar(&field.ty) {
+        Some((rust_ty, prost_ty)) if field.repeated => {
+            (format!("Vec<{rust_ty}>"), format!("{prost_ty}, repeated"))
+        }
+        Some((rust_ty, prost_ty)) => (rust_ty.to_string(), prost_ty.to_string()),
+        None => {
+            if !messages.iter().any(|m| m.name == field.ty) {
+                bail!("Unknown type '{}' of field '{}'", field.ty, field.name);
+            }
+            if field.repeated {
+                (
+                    format!("Vec<{}>", field.ty),
+                    "message, repeated".to_string(),
+                )
+            } else {
+                (
+                    format!("Option<{}>", field.ty),
+                    "message, optional".to_string(),
+                )
+            }
+        }
+    };
+
+    writeln!(out, "#[prost({attr}, tag = \"{}\")]", field.tag)?;
+    writeln!(out, "pub {}: {rust_ty},", field.name)?;
+    Ok(())
+}
+
+/// Protobuf messages of the UDP telemetry, with the prost derives
+pub fn generate(src: &str) -> Result<String> {
+    let messages = parse(src)?;
+    let mut out = String::new();
+
+    writeln!(
+        out,
+        "// Generated by `cargo xtask codegen` from {INPUT}: do not edit.\n"
+    )?;
+
+    for msg in messages.iter() {
+        let module = snake_case(&msg.name);
+
+        write_doc(&mut out, &msg.doc)?;
+        writeln!(out, "#[derive(Clone, PartialEq, prost::Message)]")?;
+        writeln!(out, "pub struct {} {{", msg.name)?;
+        for item in msg.items.iter() {
+            match item {
+                ProtoItem::Field(field) => write_field(&mut out, field, &messages)?,
+                ProtoItem::Oneof(oneof) => {
+                    let tags: Vec<String> =
+                        oneof.fields.iter().map(|f| f.tag.to_string()).collect();
+                    let ty = format!("{module}::{}", camel_case(&oneof.name));
+
+                    write_doc(&mut out, &oneof.doc)?;
+                    writeln!(
+                        out,
+                        "#[prost(oneof = \"{ty}\", tags = \"{}\")]",
+                        tags.join(", ")
+                    )?;
+                    writeln!(out, "pub {}: Option<{ty}>,", oneof.name)?;
+                }
+            }
+        }
+        writeln!(out, "}}\n")?;
+
+        let oneofs: Vec<&ProtoOneof> = msg
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                ProtoItem::Oneof(oneof) => Some(oneof),
+                ProtoItem::Field(_) => None,
+            })
+            .collect();
+        if oneofs.is_empty() {
+            continue;
+        }
+
+        writeln!(out, "/// Nested types of `{}`", msg.name)?;
+        writeln!(out, "pub mod {module} {{")?;
+        for oneof in oneofs {
+            writeln!(out, "#[derive(Clone, PartialEq, prost::Oneof)]")?;
+            writeln!(out, "pub enum {} {{", camel_case(&oneof.name))?;
+            for field in oneof.fields.iter() {
+                let (rust_ty, prost_ty) = scalar(&field.ty).ok_or(anyhow!(
+                    "Only scalars are supported in a oneof: '{}'",
+                    field.name
+                ))?;
+                write_doc(&mut out, &field.doc)?;
+                writeln!(out, "#[prost({prost_ty}, tag = \"{}\")]", field.tag)?;
+                writeln!(out, "{}({rust_ty}),", camel_case(&field.name))?;
+            }
+            writeln!(out, "}}")?;
+        }
+        writeln!(out, "}}\n")?;
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let messages = parse(
+            "// File comment\n\nsyntax = \"proto3\";\n\nmessage A {\n  // Doc\n  \
+             repeated B b = 1;\n  oneof value {\n    double number = 2;\n  }\n}\n\n\
+             message B {\n  uint64 step = 1;\n}\n",
+        )
+        .unwrap();
+
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].doc.is_empty());
+        let ProtoItem::Field(b) = &messages[0].items[0] else {
+            panic!("Expected a field");
+        };
+        assert_eq!(b.doc, ["Doc"]);
+        assert!(b.repeated);
+        assert_eq!((b.ty.as_str(), b.tag), ("B", 1));
+        let ProtoItem::Oneof(value) = &messages[0].items[1] else {
+            panic!("Expected a oneof");
+        };
+        assert_eq!(value.fields[0].name, "number");
+
+        assert!(parse("syntax = \"proto2\";").is_err());
+        assert!(parse("message A {\n  repeated double x;\n}").is_err());
+        assert!(parse("message A {\n  double x = 1;\n").is_err());
+    }
+}
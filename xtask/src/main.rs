@@ -0,0 +1,64 @@
+//! Repository tasks, run with `cargo xtask <task>` from anywhere in the repository.
+//!
+//! Tasks:
+//!  - `codegen [--check]`: regenerates the files derived from the interface definitions in
+//!    `interfaces/`. With `--check`, only verifies that they are up to date.
+//!  - `no-std`: builds the gnc crate in every feature combination & the flight software for each
+//!    board for the flight computer target, checking their dependencies & the firmware size.
+
+mod codegen;
+mod no_std;
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use anyhow::{Result, bail};
+
+fn repo_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .to_path_buf()
+}
+
+fn run() -> Result<bool> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    match args.as_slice() {
+        ["codegen"] => {
+            for file in codegen::generate(&repo_root())? {
+                if file.write()? {
+                    println!("Updated {}", file.path.display());
+                }
+            }
+            Ok(true)
+        }
+        ["codegen", "--check"] => {
+            let stale = codegen::stale_files(&repo_root())?;
+            for path in stale.iter() {
+                eprintln!(
+                    "{} is out of date, run `cargo xtask codegen`",
+                    path.display()
+                );
+            }
+            Ok(stale.is_empty())
+        }
+        ["no-std"] => no_std::check(&repo_root()),
+        _ => bail!("Usage: cargo xtask codegen [--check] | no-std"),
+    }
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("Error: {e:#}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,380 @@
+//! Checks of the embedded builds, for the flight computer target:
+//!  - the gnc crate in every combination of its optional features, except `std`
+//!  - the flight software firmware for each board, with and without its optional features
+//!
+//! Each build must not enable the `std` feature of a dependency nor pull in one of the heavy
+//! allocating crates that must not end up in the flight software. Violations are reported with
+//! the dependency path leading to them. The firmware must also fit in its flash budget.
+//!
+//! Requires the target to be installed: `rustup target add thumbv7em-none-eabihf`.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{Context, Result, bail};
+
+const TARGET: &str = "thumbv7em-none-eabihf";
+
+/// Features only meant for host builds
+const HOST_FEATURES: [&str; 1] = ["std"];
+
+/// Crates that pull in heavy allocation or std machinery
+const FORBIDDEN_CRATES: [&str; 5] = ["serde_json", "regex", "anyhow", "hashbrown", "tokio"];
+
+/// Boards the flight software is built for, one per build
+const FSW_BOARDS: [&str; 2] = ["nucleo_stm32f756", "crater_stm32f767"];
+
+/// Largest flash taken by the firmware [KiB]: three quarters of the 1 MiB of both boards, so that
+/// its growth is noticed well before it no longer fits
+const FSW_FLASH_BUDGET_KIB: u64 = 768;
+
+/// Directory of the builds, apart from the regular ones of the crate
+const TARGET_DIR: &str = "target/no_std";
+
+/// Optional features declared in the manifest, except the default ones & `excluded`
+fn features(manifest: &str, excluded: &[&str]) -> Vec<String> {
+    manifest
+        .lines()
+        .skip_while(|l| l.trim() != "[features]")
+        .skip(1)
+        .take_while(|l| !l.trim_start().starts_with('['))
+        .filter_map(|l| l.split_once('=').map(|(name, _)| name.trim().to_string()))
+        .filter(|f| {
+            !f.is_empty()
+                && !f.starts_with('#')
+                && f != "default"
+                && !excluded.contains(&f.as_str())
+        })
+        .collect()
+}
+
+/// Every combination of the features, comma separated
+fn combinations(features: &[String]) -> Vec<String> {
+    (0..(1u32 << features.len()))
+        .map(|mask| {
+            features
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| mask & (1 << i) != 0)
+                .map(|(_, f)| f.as_str())
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+        .collect()
+}
+
+/// Runs cargo in the crate directory, so that its cargo configuration applies. Returns whether
+/// it succeeded & its output.
+fn cargo(dir: &Path, args: &[&str]) -> Result<(bool, String)> {
+    let output = Command::new(env::var("CARGO").unwrap_or("cargo".to_string()))
+        .current_dir(dir)
+        .args(args)
+        .arg("--target-dir")
+        .arg(dir.join(TARGET_DIR))
+        .output()
+        .context("Could not run cargo")?;
+
+    let mut out = String::from_utf8_lossy(&output.stdout).to_string();
+    out.push_str(&String::from_utf8_lossy(&output.stderr));
+
+    Ok((output.status.success(), out))
+}
+
+/// Dependencies with `std` enabled or forbidden, with the path leading to them
+fn dependency_violations(dir: &Path, common: &[&str]) -> Result<Vec<String>> {
+    // One line per package & enabled feature, eg. "serde feature \"std\""
+    let tree_args = [
+        "tree",
+        "-e",
+        "normal,features",
+        "--prefix",
+        "none",
+        "--format",
+        "{p}",
+    ];
+    let (ok, tree) = cargo(dir, &[&tree_args[..], common].concat())?;
+    if !ok {
+        bail!("cargo tree failed:\n{tree}");
+    }
+
+    let mut offending: Vec<String> = tree
+        .lines()
+        .filter_map(|l| {
+            let name = l.split_whitespace().next()?;
+            let std_enabled = l.contains("feature \"std\"");
+
+            (std_enabled || FORBIDDEN_CRATES.contains(&name)).then(|| name.to_string())
+        })
+        .collect();
+    offending.sort();
+    offending.dedup();
+
+    let mut violations = vec![];
+    for name in offending {
+        // Inverted tree: path from the offending crate back to the checked one
+        let (_, path) = cargo(
+            dir,
+            &[
+                &["tree", "-e", "normal,features", "--invert", name.as_str()],
+                common,
+            ]
+            .concat(),
+        )?;
+        violations.push(format!("'{name}' pulled in through:\n{path}"));
+    }
+
+    Ok(violations)
+}
+
+const SHT_NOBITS: u32 = 8;
+const SHF_ALLOC: u32 = 0x2;
+
+/// Flash taken by a 32-bit little endian ELF image [bytes]: its loaded sections with contents,
+/// the initialized data included
+fn flash_size(elf: &[u8]) -> Result<u64> {
+    let u16_at = |at: usize| -> Result<u16> {
+        Ok(u16::from_le_bytes(
+            elf.get(at..at + 2).context("Truncated ELF")?.try_into()?,
+        ))
+    };
+    let u32_at = |at: usize| -> Result<u32> {
+        Ok(u32::from_le_bytes(
+            elf.get(at..at + 4).context("Truncated ELF")?.try_into()?,
+        ))
+    };
+
+    // ELF32, little endian
+    if elf.get(..6) != Some(&[0x7F, b'E', b'L', b'F', 1, 1]) {
+        bail!("Not a 32-bit little endian ELF");
+    }
+
+    let sh_offset = u32_at(0x20)? as usize;
+    let sh_entry_size = u16_at(0x2E)? as usize;
+    let sh_count = u16_at(0x30)? as usize;
+
+    let mut size = 0;
+    for i in 0..sh_count {
+        let header = sh_offset + i * sh_entry_size;
+        let sh_type = u32_at(header + 4)?;
+        let sh_flags = u32_at(header + 8)?;
+        let sh_size = u32_at(header + 20)?;
+
+        if sh_flags & SHF_ALLOC != 0 && sh_type != SHT_NOBITS {
+            size += sh_size as u64;
+        }
+    }
+
+    Ok(size)
+}
+
+/// Builds the gnc crate in every combination of its optional features. Returns the number of
+/// failed combinations.
+fn check_gnc(root: &Path, report: &mut String) -> Result<usize> {
+    let dir = root.join("gnc");
+    let features = features(&fs::read_to_string(dir.join("Cargo.toml"))?, &HOST_FEATURES);
+    if features.is_empty() {
+        bail!("No optional features in the gnc manifest");
+    }
+
+    let mut failed = 0;
+    for combination in combinations(&features) {
+        let common = [
+            "--target",
+            TARGET,
+            "--no-default-features",
+            "--features",
+            &combination,
+        ];
+
+        let (ok, out) = cargo(
+            &dir,
+            &[&["build", "--lib", "--release"], &common[..]].concat(),
+        )?;
+        let violations = if ok {
+            dependency_violations(&dir, &common)?
+        } else {
+            vec![format!("build failed:\n{out}")]
+        };
+
+        if violations.is_empty() {
+            println!("[PASS] gnc [{combination}]");
+        } else {
+            failed += 1;
+            println!("[FAIL] gnc [{combination}]");
+            report.push_str(&format!("\n=== gnc [{combination}] ===\n"));
+            for v in violations {
+                report.push_str(&v);
+                report.push('\n');
+            }
+        }
+    }
+
+    Ok(failed)
+}
+
+/// Builds the firmware for each board, with every combination of the other optional features,
+/// and checks that it fits in the flash budget. Returns the number of failed builds.
+fn check_fsw(root: &Path, report: &mut String) -> Result<usize> {
+    let dir = root.join("fsw");
+    let features = features(&fs::read_to_string(dir.join("Cargo.toml"))?, &FSW_BOARDS);
+    let elf = dir
+        .join(TARGET_DIR)
+        .join(TARGET)
+        .join("release")
+        .join("crater");
+
+    let mut failed = 0;
+    for board in FSW_BOARDS {
+        for others in combinations(&features) {
+            let combination = if others.is_empty() {
+                board.to_string()
+            } else {
+                format!("{board},{others}")
+            };
+            let common = [
+                "--target",
+                TARGET,
+                "--no-default-features",
+                "--features",
+                &combination,
+            ];
+
+            let (ok, out) = cargo(
+                &dir,
+                &[&["build", "--bin", "crater", "--release"], &common[..]].concat(),
+            )?;
+            let mut violations = vec![];
+            let mut size_kib = 0;
+            if ok {
+                violations = dependency_violations(&dir, &common)?;
+
+                let image = fs::read(&elf).with_context(|| format!("Reading {}", elf.display()))?;
+                size_kib = flash_size(&image)?.div_ceil(1024);
+                if size_kib > FSW_FLASH_BUDGET_KIB {
+                    violations.push(format!(
+                        "firmware takes {size_kib} KiB of flash, over the {FSW_FLASH_BUDGET_KIB} KiB budget"
+                    ));
+                }
+            } else {
+                violations.push(format!("build failed:\n{out}"));
+            }
+
+            if violations.is_empty() {
+                println!("[PASS] fsw [{combination}] ({size_kib} KiB of flash)");
+            } else {
+                failed += 1;
+                println!("[FAIL] fsw [{combination}]");
+                report.push_str(&format!("\n=== fsw [{combination}] ===\n"));
+                for v in violations {
+                    report.push_str(&v);
+                    report.push('\n');
+                }
+            }
+        }
+    }
+
+    Ok(failed)
+}
+
+fn target_installed() -> Result<bool> {
+    let output = Command::new(env::var("RUSTC").unwrap_or("rustc".to_string()))
+        .args(["--print", "sysroot"])
+        .output()
+        .context("Could not run rustc")?;
+    let sysroot = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+
+    Ok(sysroot.join("lib/rustlib").join(TARGET).exists())
+}
+
+/// Runs all the checks. Returns whether they passed.
+pub fn check(root: &Path) -> Result<bool> {
+    if !target_installed()? {
+        bail!("Target {TARGET} not installed, run `rustup target add {TARGET}`");
+    }
+
+    let mut report = String::new();
+    let failed = check_gnc(root, &mut report)? + check_fsw(root, &mut report)?;
+
+    if failed > 0 {
+        eprintln!("{failed} embedded builds failed:\n{report}");
+    }
+    Ok(failed == 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_features() {
+        let manifest = r#"
+[dependencies]
+heapless = "0.8"
+
+[features]
+default = ["nucleo_stm32f756"]
+nucleo_stm32f756 = ["embassy-stm32/stm32f756zg"]
+crater_stm32f767 = ["embassy-stm32/stm32f767zi"]
+# Sensor samples received from the simulation
+hil = []
+
+[lib]
+"#;
+        assert_eq!(features(manifest, &FSW_BOARDS), vec!["hil"]);
+        assert_eq!(
+            combinations(&features(manifest, &["hil"])),
+            vec![
+                "",
+                "nucleo_stm32f756",
+                "crater_stm32f767",
+                "nucleo_stm32f756,crater_stm32f767"
+            ]
+        );
+    }
+
+    /// ELF32 little endian image with the given sections, as (type, flags, size)
+    fn elf(sections: &[(u32, u32, u32)]) -> Vec<u8> {
+        const HEADER_LEN: usize = 0x34;
+        const SH_ENTRY_LEN: usize = 40;
+
+        let mut image = vec![0u8; HEADER_LEN];
+        image[..6].copy_from_slice(&[0x7F, b'E', b'L', b'F', 1, 1]);
+        image[0x20..0x24].copy_from_slice(&(HEADER_LEN as u32).to_le_bytes());
+        image[0x2E..0x30].copy_from_slice(&(SH_ENTRY_LEN as u16).to_le_bytes());
+        image[0x30..0x32].copy_from_slice(&(sections.len() as u16).to_le_bytes());
+
+        for (sh_type, sh_flags, sh_size) in sections {
+            let mut header = [0u8; SH_ENTRY_LEN];
+            header[4..8].copy_from_slice(&sh_type.to_le_bytes());
+            header[8..12].copy_from_slice(&sh_flags.to_le_bytes());
+            header[20..24].copy_from_slice(&sh_size.to_le_bytes());
+            image.extend(header);
+        }
+        image
+    }
+
+    #[test]
+    fn test_flash_size() {
+        const SHT_PROGBITS: u32 = 1;
+        const SHF_EXECINSTR: u32 = 0x4;
+
+        let image = elf(&[
+            (0, 0, 0),
+            // .vector_table, .text & .data
+            (SHT_PROGBITS, SHF_ALLOC, 0x400),
+            (SHT_PROGBITS, SHF_ALLOC | SHF_EXECINSTR, 0x2000),
+            (SHT_PROGBITS, SHF_ALLOC | 0x1, 0x100),
+            // .bss: RAM only
+            (SHT_NOBITS, SHF_ALLOC | 0x1, 0x800),
+            // .defmt & debug info: not loaded
+            (SHT_PROGBITS, 0, 0x1000),
+        ]);
+        assert_eq!(flash_size(&image).unwrap(), 0x400 + 0x2000 + 0x100);
+
+        assert!(flash_size(b"not an elf").is_err());
+        assert!(flash_size(&image[..0x40]).is_err());
+    }
+}